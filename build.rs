@@ -0,0 +1,46 @@
+//! Records the version of the rust-analyzer crates we were built against
+//!
+//! The `ra_ap_*` dependencies are declared as `0.0`, so the version actually
+//! used is only known from Cargo.lock. It is part of the project fingerprint
+//! stamped on persisted caches: symbols indexed by one rust-analyzer release
+//! aren't necessarily what another one would produce. Every `ra_ap_*` crate
+//! in the lockfile is recorded too, for the `capabilities` tool.
+
+use std::path::Path;
+
+fn main() {
+    let lockfile = Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock");
+    println!("cargo:rerun-if-changed={}", lockfile.display());
+
+    let lock = std::fs::read_to_string(&lockfile).unwrap_or_default();
+    let version = locked_version(&lock, "ra_ap_ide").unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=CRATOGRAPHER_RA_AP_VERSION={}", version);
+
+    let crates: Vec<String> =
+        locked_packages(&lock, "ra_ap_").into_iter().map(|(name, version)| format!("{}@{}", name, version)).collect();
+    println!("cargo:rustc-env=CRATOGRAPHER_RA_AP_CRATES={}", crates.join(","));
+}
+
+/// The version of `package` in a Cargo.lock
+fn locked_version(lock: &str, package: &str) -> Option<String> {
+    let mut lines = lock.lines();
+    lines.find(|line| line.trim() == format!("name = \"{}\"", package))?;
+    let version = lines.next()?.trim().strip_prefix("version = \"")?;
+    Some(version.trim_end_matches('"').to_string())
+}
+
+/// Name and version of each package in a Cargo.lock whose name starts with `prefix`
+fn locked_packages(lock: &str, prefix: &str) -> Vec<(String, String)> {
+    let mut packages = Vec::new();
+    let mut lines = lock.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        let Some(name) = line.strip_prefix("name = \"").map(|name| name.trim_end_matches('"')) else { continue };
+        if !name.starts_with(prefix) {
+            continue;
+        }
+        if let Some(version) = lines.next().and_then(|line| line.strip_prefix("version = \"")) {
+            packages.push((name.to_string(), version.trim_end_matches('"').to_string()));
+        }
+    }
+    packages
+}
@@ -4,10 +4,125 @@
 //! of Rust code. It handles project loading, symbol lookups, and other code intelligence
 //! features needed by Cratographer.
 
-use ra_ap_ide::{AnalysisHost, SymbolKind as RaSymbolKind};
+mod annotations;
+mod api_exposure;
+mod budget;
+mod build_env;
+mod build_scripts;
+mod call_hierarchy;
+mod capabilities;
+mod cfg_duplicates;
+mod closures;
+mod coherence;
+mod constants;
+mod conversions;
+mod covering_tests;
+mod crate_cfg;
+mod crate_graph;
+mod crate_items;
+mod debug_views;
+mod delegation;
+mod derive_usage;
+mod diagnostics;
+mod disambiguation;
+mod drop_types;
+mod embedded_files;
+mod entry_points;
+mod error_handling;
+mod events;
+mod exporters;
+mod feature_api;
+mod features;
+mod file_dependencies;
+mod fingerprint;
+mod fmt_coverage;
+mod glob_conflicts;
+mod hidden_api;
+mod hierarchy;
+mod highlight_related;
+mod hover;
+mod impl_trait;
+mod implementations;
+mod inlay_hints;
+mod iterator_chain;
+mod lifetimes;
+mod library_cache;
+mod logging;
+mod macros;
+mod match_sites;
+mod module_summary;
+mod module_tree;
+mod monikers;
+mod monomorphization;
+mod move_item;
+mod msrv;
+mod navigation;
+mod object_safety;
+mod operators;
+mod ownership;
+mod package_dependencies;
+mod path_resolution;
+mod proc_macro_server;
+mod provider;
+mod quick_index;
+mod reference_index;
+mod references;
+mod rename;
+mod reverse_dependencies;
+mod rewrite_paths;
+mod runnables;
+mod rustdoc;
+mod scratch;
+mod semantic_tokens;
+mod signature_filter;
+mod signature_help;
+mod snippet;
+mod source;
+mod split_module;
+mod structural_search;
+mod suggestions;
+mod trait_overrides;
+mod use_graph;
+mod workspace_members;
+
+pub use budget::{Budget, Coverage};
+pub use build_env::BUILD_ENV_MACROS;
+pub use capabilities::ra_ap_crates;
+pub use cfg_duplicates::DuplicateStatus;
+pub use closures::ClosureShapeQuery;
+pub use constants::ConstantQuery;
+pub use disambiguation::Candidate;
+pub use embedded_files::EMBEDDING_MACROS;
+pub use entry_points::ENTRY_POINT_KINDS;
+pub use error_handling::ERROR_HANDLING_KINDS;
+pub use events::IndexEventKind;
+pub use fingerprint::RA_AP_VERSION;
+pub use glob_conflicts::GLOB_CONFLICT_KINDS;
+pub use hierarchy::Related;
+pub use implementations::ImplBlock;
+pub use logging::LOG_LEVELS;
+pub use module_summary::ModuleItem;
+pub use module_tree::ModuleNode;
+pub use provider::SymbolProvider;
+pub use quick_index::QuickIndex;
+pub use references::Reference;
+pub use runnables::RUNNABLE_KINDS;
+pub use rustdoc::RustdocLink;
+pub use scratch::ScratchFile;
+pub use signature_filter::SignatureFilter;
+pub use trait_overrides::OverrideStatus;
+
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{AnalysisHost, FileId, FilePosition, RootDatabase, SourceRootId, SymbolKind as RaSymbolKind};
+use ra_ap_ide_db::defs::{Definition, NameClass};
 use ra_ap_paths::{AbsPathBuf, Utf8PathBuf};
-use ra_ap_project_model::CargoConfig;
-use std::path::PathBuf;
+use ra_ap_project_model::{CargoConfig, ProjectWorkspace};
+use ra_ap_syntax::{algo::find_node_at_offset, ast::{self, HasName}, AstNode, TextRange, TextSize};
+use reference_index::{ReferenceIndex, ReferenceIndexBuild};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
 /// Search mode for symbol lookup
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -35,6 +150,24 @@ pub enum SymbolFilter {
     All,
 }
 
+impl SymbolFilter {
+    /// Whether a symbol of the given kind passes the filter
+    pub fn accepts(self, kind: SymbolKind) -> bool {
+        match self {
+            SymbolFilter::Types => matches!(kind, SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Trait | SymbolKind::TypeAlias),
+            SymbolFilter::Implementations => kind == SymbolKind::Impl,
+            SymbolFilter::Functions => matches!(kind, SymbolKind::Function | SymbolKind::Method),
+            SymbolFilter::All => true,
+        }
+    }
+}
+
+/// Maximum number of results returned by a symbol search
+const SYMBOL_SEARCH_LIMIT: usize = 32;
+
+/// Maximum number of same-named symbols considered when resolving a name to definitions
+const DEFINITION_SEARCH_LIMIT: usize = 1024;
+
 /// Options for symbol search
 #[derive(Debug, Clone, Default)]
 pub struct SearchOptions {
@@ -44,6 +177,64 @@ pub struct SearchOptions {
     pub include_library: bool,
     /// Filter by symbol kind
     pub filter: SymbolFilter,
+    /// Only return symbols from crates with this version, either exact
+    /// ("2.0.39") or a prefix of whole components ("2" or "2.0")
+    ///
+    /// Disambiguates results when several versions of a crate are in the graph.
+    pub crate_version: Option<String>,
+    /// Only return functions whose signature has this shape
+    pub signature: SignatureFilter,
+    /// Only return symbols from these crates (empty: any crate)
+    ///
+    /// Library symbols are searched whenever this is set, so listing a few
+    /// dependencies narrows the search to them without `include_library`.
+    pub crates_include: Vec<String>,
+    /// Never return symbols from these crates
+    pub crates_exclude: Vec<String>,
+    /// List exact name matches first, the workspace's own before
+    /// dependencies', so library hits can't crowd the project's symbols out
+    /// of the result limit
+    pub prefer_workspace: bool,
+}
+
+impl SearchOptions {
+    /// Whether a symbol passes the kind filter, version pin and crate lists
+    fn accepts(&self, symbol: &SymbolInfo) -> bool {
+        let in_list = |list: &[String]| {
+            symbol.crate_name.as_deref().is_some_and(|name| list.iter().any(|c| crate_names_match(c, name)))
+        };
+
+        self.filter.accepts(symbol.kind)
+            && self.crate_version.as_deref().is_none_or(|pin| {
+                symbol.crate_version.as_deref().is_some_and(|version| version_matches(version, pin))
+            })
+            && (self.crates_include.is_empty() || in_list(&self.crates_include))
+            && !in_list(&self.crates_exclude)
+    }
+
+    /// Whether library symbols are part of the search
+    fn searches_libraries(&self) -> bool {
+        self.include_library || !self.crates_include.is_empty()
+    }
+
+    /// Whether filters beyond the symbol search may drop results, so more
+    /// candidates should be fetched
+    fn narrows_results(&self) -> bool {
+        self.crate_version.is_some()
+            || !self.signature.is_empty()
+            || !self.crates_include.is_empty()
+            || !self.crates_exclude.is_empty()
+    }
+}
+
+/// Whether two crate names are the same, treating `-` and `_` alike as Cargo does
+fn crate_names_match(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.chars().zip(b.chars()).all(|(x, y)| x == y || (matches!(x, '-' | '_') && matches!(y, '-' | '_')))
+}
+
+/// Whether a crate version matches a pin, exactly or by leading components
+fn version_matches(version: &str, pin: &str) -> bool {
+    version == pin || version.strip_prefix(pin).is_some_and(|rest| rest.starts_with('.'))
 }
 
 /// Error types for analyzer operations
@@ -57,6 +248,8 @@ pub enum AnalyzerError {
     IoError(std::io::Error),
     /// Canceled operation
     Canceled,
+    /// A queried name matched several symbols
+    Ambiguous { name: String, candidates: Vec<Candidate> },
     /// Unknown error
     Other(String),
 }
@@ -68,6 +261,10 @@ impl std::fmt::Display for AnalyzerError {
             AnalyzerError::ManifestNotFound(msg) => write!(f, "Manifest not found: {}", msg),
             AnalyzerError::IoError(err) => write!(f, "IO error: {}", err),
             AnalyzerError::Canceled => write!(f, "Operation was canceled"),
+            AnalyzerError::Ambiguous { name, candidates } => {
+                let ids: Vec<_> = candidates.iter().map(|c| c.symbol_id.as_str()).collect();
+                write!(f, "'{}' matches {} symbols, pass one's symbol_id: {}", name, candidates.len(), ids.join(", "))
+            }
             AnalyzerError::Other(msg) => write!(f, "Error: {}", msg),
         }
     }
@@ -89,6 +286,34 @@ pub struct Analyzer {
     host: AnalysisHost,
     vfs: ra_ap_vfs::Vfs,
     loader: Option<ra_ap_vfs_notify::NotifyHandle>,
+    /// Root directory of the loaded workspace
+    project_root: Option<PathBuf>,
+    /// Cargo metadata of the loaded workspace: packages, targets and dependencies
+    workspace: Option<ProjectWorkspace>,
+    /// What persisted state built for the loaded project depends on
+    fingerprint: Option<fingerprint::ProjectFingerprint>,
+    /// How VFS files are partitioned into source roots, kept to place files added later
+    source_root_config: Option<ra_ap_load_cargo::SourceRootConfig>,
+    /// Ownership rules from the project's CODEOWNERS
+    code_owners: ownership::CodeOwners,
+    /// Scratch files mounted into workspace crates
+    scratch: scratch::ScratchMounts,
+    /// Cross-reference index, built on demand by `build_reference_index`
+    references: Option<ReferenceIndex>,
+    /// Full rescan of the reference index queued by a definition change,
+    /// run a batch at a time by `continue_reference_rescan`
+    reference_rescan: Option<ReferenceIndexBuild>,
+    /// Incremented whenever a reference index is put in place, which can
+    /// happen without the revision moving on
+    reference_generation: u64,
+    /// Incremented whenever the indexed contents change
+    revision: u64,
+    /// Library symbols served from the shared cache, once loaded
+    library_symbols: Option<Vec<SymbolInfo>>,
+    /// Log of index changes for clients polling `index_events`
+    events: events::EventLog,
+    /// The server expanding proc macros, or why there is none
+    proc_macro_server: Result<ra_ap_proc_macro_api::ProcMacroClient, String>,
 }
 
 impl Analyzer {
@@ -98,6 +323,19 @@ impl Analyzer {
             host: AnalysisHost::new(None), // No LRU capacity limit
             vfs: ra_ap_vfs::Vfs::default(),
             loader: None,
+            project_root: None,
+            workspace: None,
+            fingerprint: None,
+            source_root_config: None,
+            code_owners: ownership::CodeOwners::default(),
+            scratch: scratch::ScratchMounts::default(),
+            references: None,
+            reference_rescan: None,
+            reference_generation: 0,
+            revision: 0,
+            library_symbols: None,
+            events: events::EventLog::default(),
+            proc_macro_server: Err("no project loaded".to_string()),
         }
     }
 
@@ -112,9 +350,10 @@ impl Analyzer {
     /// Returns a receiver channel for file change notifications
     pub fn load_project(&mut self, project_path: impl Into<PathBuf>) -> Result<crossbeam_channel::Receiver<ra_ap_vfs::loader::Message>, AnalyzerError> {
         use crossbeam_channel::unbounded;
-        use ra_ap_ide_db::{ChangeWithProcMacros, RootDatabase};
-        use ra_ap_load_cargo::ProjectFolders;
-        use ra_ap_project_model::{ProjectManifest, ProjectWorkspace};
+        use ra_ap_ide_db::base_db::ProcMacroLoadingError;
+        use ra_ap_ide_db::{ChangeWithProcMacros, FxHashMap, RootDatabase};
+        use ra_ap_load_cargo::{load_proc_macro, ProjectFolders};
+        use ra_ap_project_model::ProjectManifest;
         use ra_ap_vfs::loader::{Handle, LoadingProgress};
 
         let project_path: PathBuf = project_path.into();
@@ -143,6 +382,9 @@ impl Analyzer {
         let mut workspace = ProjectWorkspace::load(manifest, &cargo_config, &progress)
             .map_err(|e| AnalyzerError::ProjectLoadError(format!("{:?}", e)))?;
 
+        self.project_root = Some(PathBuf::from(workspace.workspace_root().as_str()));
+        self.code_owners = ownership::CodeOwners::load(Path::new(workspace.workspace_root().as_str()));
+
         // Load build scripts if needed
         let build_scripts = workspace.run_build_scripts(&cargo_config, &progress)
             .map_err(|e| AnalyzerError::ProjectLoadError(format!("{:?}", e)))?;
@@ -152,8 +394,16 @@ impl Analyzer {
         let lru_cap = std::env::var("RA_LRU_CAP").ok().and_then(|it| it.parse::<u16>().ok());
         let mut db = RootDatabase::new(lru_cap);
 
+        // Start the proc-macro server before the crate graph is built, so the
+        // workspace's proc-macro crates can be loaded into it
+        let extra_env = FxHashMap::default();
+        self.proc_macro_server = proc_macro_server::spawn(&workspace, &extra_env);
+        if let Err(e) = &self.proc_macro_server {
+            eprintln!("Proc macros won't be expanded: {}", e);
+        }
+
         // Build crate graph and load files into VFS
-        let (crate_graph, _proc_macros) = workspace.to_crate_graph(
+        let (crate_graph, proc_macro_paths) = workspace.to_crate_graph(
             &mut |path: &ra_ap_paths::AbsPath| {
                 let contents = loader.load_sync(path);
                 let vfs_path = ra_ap_vfs::VfsPath::from(path.to_path_buf());
@@ -162,8 +412,18 @@ impl Analyzer {
                     (excluded == ra_ap_vfs::FileExcluded::No).then_some(file_id)
                 })
             },
-            &Default::default(),
+            &extra_env,
         );
+        let proc_macros = proc_macro_paths
+            .into_iter()
+            .map(|(krate, path)| {
+                let loaded = path.and_then(|(_, path)| match &self.proc_macro_server {
+                    Ok(server) => load_proc_macro(server, &path, &[]),
+                    Err(e) => Err(ProcMacroLoadingError::ProcMacroSrvError(e.clone().into_boxed_str())),
+                });
+                (krate, loaded)
+            })
+            .collect();
 
         // Build project folders with watch indices
         let project_folders = ProjectFolders::new(std::slice::from_ref(&workspace), &[], None);
@@ -207,14 +467,21 @@ impl Analyzer {
         let source_roots = project_folders.source_root_config.partition(&self.vfs);
         analysis_change.set_roots(source_roots);
         analysis_change.set_crate_graph(crate_graph);
+        analysis_change.set_proc_macros(proc_macros);
+        self.source_root_config = Some(project_folders.source_root_config);
 
         db.apply_change(analysis_change);
 
         // Create AnalysisHost from the loaded database
         self.host = AnalysisHost::with_database(db);
 
-        // Store loader for future file watching
+        // Store loader for future file watching, and the metadata for
+        // workspace queries
         self.loader = Some(loader);
+        self.fingerprint = self.project_root.as_deref().map(|root| fingerprint::ProjectFingerprint::of_workspace(&workspace, root));
+        self.workspace = Some(workspace);
+        self.revision += 1;
+        self.events.push(self.revision, events::IndexEventKind::CrateGraphReloaded);
 
         Ok(receiver)
     }
@@ -223,6 +490,11 @@ impl Analyzer {
     ///
     /// This searches across the entire workspace for symbols matching the given name.
     pub fn find_symbol(&self, name: &str, options: &SearchOptions) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        // Without a name, a signature filter audits every workspace function
+        if name.is_empty() && !options.signature.is_empty() {
+            return self.find_workspace_functions(options);
+        }
+
         let analysis = self.host.analysis();
 
         // Build the query with the specified options
@@ -235,8 +507,10 @@ impl Analyzer {
             SearchMode::Prefix => { query.prefix(); },
         }
 
-        // Apply library inclusion
-        if options.include_library {
+        // Apply library inclusion; libraries covered by the shared library
+        // cache are searched there instead of in rust-analyzer's index
+        let use_library_cache = options.searches_libraries() && self.library_symbols.is_some();
+        if options.searches_libraries() && !use_library_cache {
             query.libs();
         }
 
@@ -246,87 +520,162 @@ impl Analyzer {
         }
 
         // Use symbol_search to find all symbols matching the name
-        // Limit to 32 results
-        let symbols = analysis.symbol_search(query, 32)
+        // Limit to 32 results, over-fetching when a version pin, signature
+        // filter or crate list will drop some, or when ranking may bring
+        // later workspace symbols forward
+        let ranked = options.prefer_workspace && options.searches_libraries();
+        let search_limit = if options.narrows_results() || ranked {
+            SYMBOL_SEARCH_LIMIT * 8
+        } else {
+            SYMBOL_SEARCH_LIMIT
+        };
+        let symbols = analysis.symbol_search(query, search_limit)
             .map_err(|_| AnalyzerError::Canceled)?;
 
-        // Convert to our SymbolInfo type, filtering by symbol kind
-        let results = symbols
+        // Convert to our SymbolInfo type, filtering by symbol kind, version, crate and signature;
+        // cached library symbols come without a navigation target, already complete
+        let crates = self.crates_by_source_root()?;
+        let mut results: Vec<(Option<&ra_ap_ide::NavigationTarget>, SymbolInfo)> = symbols
+            .iter()
+            .filter_map(|nav| Some((Some(nav), self.nav_to_bare_symbol(&analysis, &crates, nav)?)))
+            .filter(|(_, sym)| options.accepts(sym))
+            .filter(|(_, sym)| self.signature_matches(sym, &options.signature))
+            .take(if ranked { search_limit } else { SYMBOL_SEARCH_LIMIT })
+            .collect();
+
+        if use_library_cache {
+            let remaining = if ranked { SYMBOL_SEARCH_LIMIT } else { SYMBOL_SEARCH_LIMIT.saturating_sub(results.len()) };
+            results.extend(self.search_library_cache(name, options, remaining).into_iter().map(|sym| (None, sym)));
+        }
+
+        if ranked {
+            // Stable, so the symbol search's own order holds within each group
+            let wanted = name.rsplit("::").next().unwrap_or(name);
+            results.sort_by_cached_key(|(_, sym)| (sym.name != wanted, !self.is_workspace_symbol(sym)));
+            results.truncate(SYMBOL_SEARCH_LIMIT);
+        }
+
+        // Monikers, rustdoc links and attributes need semantic analysis, so
+        // they're only resolved for the symbols returned
+        let sema = Semantics::new(self.host.raw_database());
+        Ok(results
             .into_iter()
-            .filter_map(|nav| {
-                // Filter to only include symbol kinds we care about
-                let kind = convert_symbol_kind(nav.kind.unwrap_or(RaSymbolKind::Module))?;
-
-                // Apply post-search filtering based on SymbolFilter
-                match options.filter {
-                    SymbolFilter::Types => {
-                        // Types filter is handled by query.only_types() above
-                        // This should already be filtered, but we can double-check
-                    }
-                    SymbolFilter::Implementations => {
-                        // Only keep Impl blocks
-                        if kind != SymbolKind::Impl {
-                            return None;
-                        }
-                    }
-                    SymbolFilter::Functions => {
-                        // Only keep Function and Method
-                        if !matches!(kind, SymbolKind::Function | SymbolKind::Method) {
-                            return None;
-                        }
-                    }
-                    SymbolFilter::All => {
-                        // No filtering
-                    }
+            .map(|(nav, mut sym)| {
+                if let Some(nav) = nav {
+                    self.resolve_symbol_details(&sema, nav, &mut sym);
                 }
+                sym
+            })
+            .collect())
+    }
 
-                let file_id = nav.file_id;
-                let range = nav.full_range;
-
-                // Try to get the file path from VFS
-                let file_path = self.vfs.file_path(file_id);
-                let path_str = file_path.as_path()
-                    .map(|p| p.to_string())
-                    .unwrap_or_else(|| format!("{:?}", file_id));
-
-                // Get file text to compute line numbers
-                let (start_line, end_line) = if let Some(text) = analysis.file_text(file_id).ok() {
-                    let line_index = ra_ap_ide::LineIndex::new(&text);
-                    let start = line_index.line_col(range.start());
-                    let end = line_index.line_col(range.end());
-                    (start.line, end.line)
-                } else {
-                    (0, 0)
-                };
+    /// Whether a symbol is defined in a workspace (rather than library) file
+    fn is_workspace_symbol(&self, symbol: &SymbolInfo) -> bool {
+        let analysis = self.host.analysis();
+        self.file_id(&symbol.file_path)
+            .ok()
+            .and_then(|file_id| analysis.source_root_id(file_id).ok())
+            .and_then(|root| analysis.is_local_source_root(root).ok())
+            .unwrap_or(false)
+    }
 
-                // Extract documentation
-                let documentation = nav.docs.as_ref().map(|d| d.as_str().to_string());
+    /// Convert a navigation target to our SymbolInfo type
+    ///
+    /// Returns `None` for symbol kinds we don't report.
+    fn nav_to_symbol(
+        &self,
+        analysis: &ra_ap_ide::Analysis,
+        crates: &CratesBySourceRoot,
+        nav: &ra_ap_ide::NavigationTarget,
+    ) -> Option<SymbolInfo> {
+        let mut symbol = self.nav_to_bare_symbol(analysis, crates, nav)?;
+        self.resolve_symbol_details(&Semantics::new(self.host.raw_database()), nav, &mut symbol);
+        Some(symbol)
+    }
 
-                Some(SymbolInfo {
-                    name: nav.name.to_string(),
-                    kind,
-                    file_path: path_str,
-                    start_line,
-                    end_line,
-                    documentation,
-                })
-            })
-            .collect();
+    /// Convert a navigation target to our SymbolInfo type without its
+    /// moniker, rustdoc link and attributes, which are cheap to filter on
+    /// but costly to resolve
+    ///
+    /// Returns `None` for symbol kinds we don't report.
+    fn nav_to_bare_symbol(
+        &self,
+        analysis: &ra_ap_ide::Analysis,
+        crates: &CratesBySourceRoot,
+        nav: &ra_ap_ide::NavigationTarget,
+    ) -> Option<SymbolInfo> {
+        // Filter to only include symbol kinds we care about
+        let kind = convert_symbol_kind(nav.kind.unwrap_or(RaSymbolKind::Module))?;
+
+        let file_id = nav.file_id;
+        let range = nav.full_range;
+
+        // Get file text to compute line numbers
+        let (start_line, end_line, size) = if let Ok(text) = analysis.file_text(file_id) {
+            let line_index = ra_ap_ide::LineIndex::new(&text);
+            let start = line_index.line_col(range.start());
+            let end = line_index.line_col(range.end());
+            (start.line, end.line, Some(SymbolSize::of(&text[range])))
+        } else {
+            (0, 0, None)
+        };
 
-        Ok(results)
+        // Extract documentation
+        let documentation = nav.docs.as_ref().map(|d| d.as_str().to_string());
+
+        let (crate_name, crate_version) = analysis.source_root_id(file_id).ok()
+            .and_then(|root| crates.get(&root).cloned())
+            .unwrap_or_default();
+
+        let file_path = self.file_path(file_id);
+        Some(SymbolInfo {
+            name: nav.name.to_string(),
+            kind,
+            owners: self.owners_of(&file_path),
+            file_path,
+            start_line,
+            end_line,
+            documentation,
+            crate_name,
+            crate_version,
+            moniker: None,
+            rustdoc: None,
+            approximate: false,
+            attributes: Vec::new(),
+            size,
+        })
+    }
+
+    /// Fill in the moniker, rustdoc link and attributes of a symbol from its navigation target
+    fn resolve_symbol_details(&self, sema: &Semantics<'_, RootDatabase>, nav: &ra_ap_ide::NavigationTarget, symbol: &mut SymbolInfo) {
+        let file_id = nav.file_id;
+        symbol.moniker = nav.focus_range.and_then(|focus| self.moniker_at(sema, file_id, focus.start()));
+        symbol.rustdoc = nav.focus_range.and_then(|focus| self.rustdoc_at(sema, file_id, focus.start()));
+        symbol.attributes = match symbol.kind {
+            SymbolKind::Function | SymbolKind::Method => self.fn_attributes_at(file_id, nav.full_range),
+            _ => Vec::new(),
+        };
     }
 
     /// List all symbols defined in a file
     ///
     /// Given a file path, this returns all symbols defined in that file.
     pub fn enumerate_file(&self, file_path: &str) -> Result<Vec<SymbolInfo>, AnalyzerError> {
-        // Convert file path to FileId
-        let abs_path = AbsPathBuf::assert(Utf8PathBuf::from(file_path));
-        let vfs_path = ra_ap_vfs::VfsPath::from(abs_path);
+        let file_id = self.file_id(file_path)?;
+        self.file_symbols(file_id, file_path)
+    }
 
-        let (file_id, _) = self.vfs.file_id(&vfs_path)
-            .ok_or_else(|| AnalyzerError::Other(format!("File not found in VFS: {}", file_path)))?;
+    /// List the symbols of several files at once
+    ///
+    /// Relative paths (as printed by `git diff --name-only`) are resolved
+    /// against the project root. Each file succeeds or fails on its own, so
+    /// one bad path does not spoil the rest of the batch.
+    pub fn enumerate_files(&self, file_paths: &[String]) -> Vec<(String, Result<Vec<SymbolInfo>, AnalyzerError>)> {
+        SymbolProvider::enumerate_files(self, file_paths)
+    }
 
+    /// List all symbols defined in a file, by `FileId`
+    fn file_symbols(&self, file_id: FileId, file_path: &str) -> Result<Vec<SymbolInfo>, AnalyzerError> {
         let analysis = self.host.analysis();
 
         // Use file_structure to get all symbols in the file
@@ -339,6 +688,11 @@ impl Analyzer {
         let text = analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
         let line_index = ra_ap_ide::LineIndex::new(&text);
 
+        let root = analysis.source_root_id(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let (crate_name, crate_version) = self.crates_by_source_root()?.remove(&root).unwrap_or_default();
+        let sema = Semantics::new(self.host.raw_database());
+        let owners = self.owners_of(file_path);
+
         // Convert to our SymbolInfo type, filtering based on SymbolKind
         let results = structure
             .into_iter()
@@ -358,6 +712,17 @@ impl Analyzer {
                             start_line: start.line,
                             end_line: end.line,
                             documentation: node.detail.clone(),
+                            crate_name: crate_name.clone(),
+                            crate_version: crate_version.clone(),
+                            moniker: self.moniker_at(&sema, file_id, node.navigation_range.start()),
+                            rustdoc: self.rustdoc_at(&sema, file_id, node.navigation_range.start()),
+                            approximate: false,
+                            attributes: match kind {
+                                SymbolKind::Function | SymbolKind::Method => self.fn_attributes_at(file_id, node.node_range),
+                                _ => Vec::new(),
+                            },
+                            owners: owners.clone(),
+                            size: Some(SymbolSize::of(&text[node.node_range])),
                         }
                     })
                 } else {
@@ -369,6 +734,218 @@ impl Analyzer {
         Ok(results)
     }
 
+    /// Revision of the index contents
+    ///
+    /// Changes every time a project is loaded or file changes are applied, so
+    /// results computed at one revision can be reused until it moves on.
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Name and version of the crate owning each source root
+    ///
+    /// A package's library and binary targets share a source root; the first
+    /// crate seen wins, which is fine since they share the version too.
+    fn crates_by_source_root(&self) -> Result<CratesBySourceRoot, AnalyzerError> {
+        let db = self.host.raw_database();
+        let analysis = self.host.analysis();
+        let mut crates = HashMap::new();
+
+        for krate in ra_ap_hir::Crate::all(db) {
+            let root = analysis.source_root_id(krate.root_file(db)).map_err(|_| AnalyzerError::Canceled)?;
+            crates.entry(root).or_insert_with(|| {
+                (krate.display_name(db).map(|n| n.to_string()), krate.version(db))
+            });
+        }
+
+        Ok(crates)
+    }
+
+    /// Look up the VFS `FileId` for an absolute file path
+    fn file_id(&self, file_path: &str) -> Result<FileId, AnalyzerError> {
+        // Scratch files live in the VFS under the path they're mounted at
+        let mounted = self.scratch.virtual_path(file_path).unwrap_or(file_path);
+        let abs_path = AbsPathBuf::assert(Utf8PathBuf::from(mounted));
+        let vfs_path = ra_ap_vfs::VfsPath::from(abs_path);
+
+        let (file_id, _) = self.vfs.file_id(&vfs_path)
+            .ok_or_else(|| AnalyzerError::Other(format!("File not found in VFS: {}", file_path)))?;
+
+        Ok(file_id)
+    }
+
+    /// Convert a 0-based line and column in a file to a `FilePosition`
+    fn file_position(&self, file_path: &str, line: u32, column: u32) -> Result<FilePosition, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let analysis = self.host.analysis();
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let text = analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let outside = || AnalyzerError::Other(format!("Position {}:{} is outside the file", line, column));
+
+        // `LineIndex::offset` only checks the line; an offset past the end of
+        // its line, or inside a character, would panic in the syntax tree
+        let offset = line_index.offset(ra_ap_ide::LineCol { line, col: column }).ok_or_else(outside)?;
+        let line_end = match line_index.offset(ra_ap_ide::LineCol { line: line + 1, col: 0 }) {
+            Some(next_line) => u32::from(next_line) as usize - 1,
+            None => text.len(),
+        };
+        let offset_usize = u32::from(offset) as usize;
+        if offset_usize > line_end || !text.is_char_boundary(offset_usize) {
+            return Err(outside());
+        }
+        Ok(FilePosition { file_id, offset })
+    }
+
+    /// The text range spanning whole lines of a file (0-based, inclusive)
+    fn lines_text_range(&self, file_id: FileId, start_line: u32, end_line: u32) -> Result<TextRange, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let offset = |line: u32| line_index.offset(ra_ap_ide::LineCol { line, col: 0 });
+        let start = offset(start_line)
+            .ok_or_else(|| AnalyzerError::Other(format!("Line {} is outside the file", start_line)))?;
+        // A last line past the end of the file, up to `u32::MAX`, means the rest of it
+        let end = match end_line.checked_add(1).and_then(offset) {
+            Some(end) => end,
+            None => TextSize::of(analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?.as_ref()),
+        };
+        if end < start {
+            return Err(AnalyzerError::Other(format!("Empty line range {}..={}", start_line, end_line)));
+        }
+        Ok(TextRange::new(start, end))
+    }
+
+    /// Render the path of a VFS file, falling back to its id for virtual files
+    fn file_path(&self, file_id: FileId) -> String {
+        let path = self.vfs.file_path(file_id)
+            .as_path()
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| format!("{:?}", file_id));
+        match self.scratch.scratch_path(&path) {
+            Some(scratch_path) => scratch_path.to_string(),
+            None => path,
+        }
+    }
+
+    /// Convert a text range within a file to zero-based start and end lines
+    fn line_range(&self, file_id: FileId, range: TextRange) -> Result<(u32, u32), AnalyzerError> {
+        let line_index = self.host.analysis().file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let start = line_index.line_col(range.start());
+        let end = line_index.line_col(range.end());
+        Ok((start.line, end.line))
+    }
+
+    /// All Rust source files that belong to the workspace (library files excluded)
+    fn workspace_files(&self) -> Result<Vec<FileId>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let mut files = Vec::new();
+
+        for (file_id, path) in self.vfs.iter() {
+            if path.name_and_extension().map(|(_, ext)| ext) != Some(Some("rs")) {
+                continue;
+            }
+            let root = analysis.source_root_id(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            if analysis.is_local_source_root(root).map_err(|_| AnalyzerError::Canceled)? {
+                files.push(file_id);
+            }
+        }
+
+        Ok(files)
+    }
+
+    /// Definitions a bare name or qualified path refers to
+    ///
+    /// Searches the workspace and libraries. A qualified path such as
+    /// `fmt::Display` matches definitions whose full path ends with it, and a
+    /// moniker or positional id (a symbol_id from an earlier result) matches
+    /// exactly one.
+    fn resolve_definitions(&self, sema: &Semantics<'_, RootDatabase>, path: &str) -> Result<Vec<Definition>, AnalyzerError> {
+        Ok(self.definition_positions(sema, path)?.into_iter().map(|(def, _)| def).collect())
+    }
+
+    /// Like `resolve_definitions`, with the position of each definition's name
+    fn definition_positions(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        path: &str,
+    ) -> Result<Vec<(Definition, FilePosition)>, AnalyzerError> {
+        if let Some((moniker, file_path, line)) = disambiguation::split_positional_id(path) {
+            let analysis = self.host.analysis();
+            let mut definitions = self.definition_positions(sema, moniker)?;
+            definitions.retain(|(_, position)| {
+                self.file_path(position.file_id) == file_path
+                    && analysis.file_line_index(position.file_id).is_ok_and(|index| index.line_col(position.offset).line == line)
+            });
+            return Ok(definitions);
+        }
+
+        let moniker_name = monikers::moniker_item_name(path);
+        let name = moniker_name.as_deref().unwrap_or_else(|| path.rsplit("::").next().unwrap_or(path));
+        let suffix = format!("::{}", path);
+        let analysis = self.host.analysis();
+        let mut definitions: Vec<(Definition, FilePosition)> = Vec::new();
+
+        for libs in [false, true] {
+            let mut query = ra_ap_ide::Query::new(name.to_string());
+            query.exact();
+            if libs {
+                query.libs();
+            }
+            let navs = analysis.symbol_search(query, DEFINITION_SEARCH_LIMIT).map_err(|_| AnalyzerError::Canceled)?;
+            for nav in navs {
+                let Some(focus) = nav.focus_range else { continue };
+                let Some(def) = definition_at(sema, nav.file_id, focus.start()) else { continue };
+                let matches = if moniker_name.is_some() {
+                    monikers::definition_moniker(sema.db, def).as_deref() == Some(path)
+                } else {
+                    !path.contains("::")
+                        || reference_index::definition_key(sema.db, def).is_some_and(|key| key == path || key.ends_with(&suffix))
+                };
+                if matches && !definitions.iter().any(|(d, _)| *d == def) {
+                    definitions.push((def, FilePosition { file_id: nav.file_id, offset: focus.start() }));
+                }
+            }
+        }
+
+        Ok(definitions)
+    }
+
+    /// Find function definitions by name
+    ///
+    /// When `file_path` is given only that file is searched, otherwise every
+    /// workspace file is scanned. Methods inside impl and trait blocks match too.
+    /// The returned syntax nodes belong to `sema`, so they can be fed straight
+    /// back into it for semantic queries.
+    fn find_functions(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        name: &str,
+        file_path: Option<&str>,
+    ) -> Result<Vec<(FileId, ast::Fn)>, AnalyzerError> {
+        let files = match file_path {
+            Some(path) => vec![self.file_id(path)?],
+            None => self.workspace_files()?,
+        };
+
+        let mut functions = Vec::new();
+        for file_id in files {
+            let source_file = sema.parse_guess_edition(file_id);
+            functions.extend(
+                source_file
+                    .syntax()
+                    .descendants()
+                    .filter_map(ast::Fn::cast)
+                    .filter(|func| func.name().is_some_and(|n| n.text() == name))
+                    .map(|func| (file_id, func)),
+            );
+        }
+
+        if functions.is_empty() {
+            return Err(AnalyzerError::Other(format!("Function not found: {}", name)));
+        }
+
+        Ok(functions)
+    }
+
     /// Apply incremental file changes to the analysis host
     ///
     /// This is called by the background watcher task when files change on disk.
@@ -379,8 +956,14 @@ impl Analyzer {
     ) -> Result<(), AnalyzerError> {
         use ra_ap_ide_db::ChangeWithProcMacros;
 
-        // Update VFS with new file contents
-        for (path, contents) in files {
+        // Update VFS with new file contents, keeping the declarations of
+        // scratch files mounted into a crate root
+        for (path, mut contents) in files {
+            if let Some(text) = contents.as_deref().and_then(|bytes| std::str::from_utf8(bytes).ok()) {
+                if let Some(text) = self.scratch.with_declarations(path.as_str(), text) {
+                    contents = Some(text.into_bytes());
+                }
+            }
             let vfs_path = ra_ap_vfs::VfsPath::from(path);
             self.vfs.set_file_contents(vfs_path, contents);
         }
@@ -393,21 +976,64 @@ impl Analyzer {
 
         // Build ChangeWithProcMacros from VFS changes
         let mut analysis_change = ChangeWithProcMacros::default();
+        let mut changed = Vec::new();
+        let mut deleted = Vec::new();
+        // Files that were indexed before this change, whose symbols get diffed
+        let mut existing = Vec::new();
+        let mut files_added = false;
         for (_, file) in changes {
             match file.change {
-                ra_ap_vfs::Change::Create(v, _) | ra_ap_vfs::Change::Modify(v, _) => {
+                ra_ap_vfs::Change::Create(v, _) => {
                     if let Ok(text) = String::from_utf8(v) {
                         analysis_change.change_file(file.file_id, Some(text));
+                        changed.push(file.file_id);
+                        files_added = true;
+                    }
+                }
+                ra_ap_vfs::Change::Modify(v, _) => {
+                    if let Ok(text) = String::from_utf8(v) {
+                        analysis_change.change_file(file.file_id, Some(text));
+                        changed.push(file.file_id);
+                        existing.push(file.file_id);
                     }
                 }
                 ra_ap_vfs::Change::Delete => {
                     analysis_change.change_file(file.file_id, None);
+                    deleted.push(file.file_id);
+                    existing.push(file.file_id);
                 }
             }
         }
+        let before = self.snapshot_symbols(&existing);
+
+        // New files only resolve as modules once they're part of a source root
+        if files_added || !deleted.is_empty() {
+            if let Some(config) = &self.source_root_config {
+                analysis_change.set_roots(config.partition(&self.vfs));
+            }
+        }
 
         // Apply changes to analysis host
         self.host.apply_change(analysis_change);
+        self.revision += 1;
+
+        // Cached library symbols of changed dependencies are stale
+        let library_changed = {
+            let analysis = self.host.analysis();
+            changed.iter().chain(&deleted).any(|&file_id| {
+                analysis
+                    .source_root_id(file_id)
+                    .and_then(|root| analysis.is_local_source_root(root))
+                    .is_ok_and(|local| !local)
+            })
+        };
+        if library_changed {
+            self.refresh_library_cache();
+        }
+
+        // Keep the reference index in sync with the new file contents
+        self.update_reference_index(&changed, &deleted)?;
+        self.record_file_events(before, &changed, &deleted);
 
         Ok(())
     }
@@ -419,6 +1045,63 @@ impl Default for Analyzer {
     }
 }
 
+/// Name and kind label for an item, as shown in tool output
+///
+/// Returns `None` for items that don't define a named symbol (use
+/// declarations, extern blocks, macro invocations).
+fn item_label(item: &ast::Item) -> Option<(String, &'static str)> {
+    fn named(node: &impl HasName) -> Option<String> {
+        node.name().map(|n| n.text().to_string())
+    }
+
+    match item {
+        ast::Item::Const(it) => Some((named(it)?, "Const")),
+        ast::Item::Enum(it) => Some((named(it)?, "Enum")),
+        ast::Item::Fn(it) => Some((named(it)?, "Function")),
+        ast::Item::Module(it) => Some((named(it)?, "Module")),
+        ast::Item::Static(it) => Some((named(it)?, "Static")),
+        ast::Item::Struct(it) => Some((named(it)?, "Struct")),
+        ast::Item::Trait(it) => Some((named(it)?, "Trait")),
+        ast::Item::TypeAlias(it) => Some((named(it)?, "TypeAlias")),
+        ast::Item::Union(it) => Some((named(it)?, "Union")),
+        ast::Item::MacroRules(it) => Some((named(it)?, "Macro")),
+        ast::Item::Impl(it) => {
+            let self_ty = it.self_ty()?.syntax().text().to_string();
+            let label = match it.trait_() {
+                Some(trait_) => format!("impl {} for {}", trait_.syntax().text(), self_ty),
+                None => format!("impl {}", self_ty),
+            };
+            Some((label, "Impl"))
+        }
+        _ => None,
+    }
+}
+
+/// The definition whose name is at `offset` in a file
+fn definition_at(sema: &Semantics<'_, RootDatabase>, file_id: FileId, offset: TextSize) -> Option<Definition> {
+    let source = sema.parse_guess_edition(file_id);
+    let name = find_node_at_offset::<ast::Name>(source.syntax(), offset)?;
+    NameClass::classify(sema, &name)?.defined()
+}
+
+/// Collapse runs of whitespace, so multi-line source reads as one line
+fn normalize_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Hash text for persisted caches
+///
+/// FNV-1a, chosen because it is stable across Rust releases, unlike `DefaultHasher`.
+fn stable_hash(text: &str) -> u64 {
+    text.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(0x100000001b3))
+}
+
+/// Render a type the way rust-analyzer shows it in hovers
+fn display_type(db: &RootDatabase, ty: &ra_ap_hir::Type, krate: ra_ap_hir::Crate) -> String {
+    use ra_ap_hir::HirDisplay;
+    ty.display(db, krate.to_display_target(db)).to_string()
+}
+
 /// Convert rust-analyzer's SymbolKind to our SymbolKind
 /// Returns None for symbol kinds we don't care about
 fn convert_symbol_kind(kind: RaSymbolKind) -> Option<SymbolKind> {
@@ -438,7 +1121,7 @@ fn convert_symbol_kind(kind: RaSymbolKind) -> Option<SymbolKind> {
 }
 
 /// Information about a symbol in the codebase
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SymbolInfo {
     pub name: String,
     pub kind: SymbolKind,
@@ -446,10 +1129,56 @@ pub struct SymbolInfo {
     pub start_line: u32,
     pub end_line: u32,
     pub documentation: Option<String>,
+    /// Name of the crate the symbol belongs to
+    #[serde(default)]
+    pub crate_name: Option<String>,
+    /// Version of that crate, telling apart duplicate versions in the graph
+    #[serde(default)]
+    pub crate_version: Option<String>,
+    /// Stable name of the symbol that can be resolved again later, see `resolve_moniker`
+    #[serde(default)]
+    pub moniker: Option<String>,
+    /// Where `cargo doc` documents the symbol
+    #[serde(default)]
+    pub rustdoc: Option<RustdocLink>,
+    /// Whether the symbol comes from a syntax-only backend, see `QuickIndex`
+    #[serde(default)]
+    pub approximate: bool,
+    /// A function's attributes as written, e.g. `inline(always)` or
+    /// `tracing::instrument(skip(self))`; empty for other symbols
+    #[serde(default)]
+    pub attributes: Vec<String>,
+    /// Owners of the symbol's file according to CODEOWNERS, see `owners_of`
+    #[serde(default)]
+    pub owners: Vec<String>,
+    /// Size of the symbol's source, definition and body included
+    #[serde(default)]
+    pub size: Option<SymbolSize>,
+}
+
+/// How much source a symbol spans, to judge whether reading all of it is
+/// worth the context or its signature will do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SymbolSize {
+    pub lines: u32,
+    pub bytes: u32,
+    /// Approximate number of LLM tokens, counting four bytes per token
+    pub tokens: u32,
 }
 
+impl SymbolSize {
+    /// Size of a symbol's source text
+    pub fn of(text: &str) -> Self {
+        let bytes = text.len() as u32;
+        SymbolSize { lines: text.lines().count().max(1) as u32, bytes, tokens: bytes.div_ceil(4) }
+    }
+}
+
+/// Crate name and version per source root
+type CratesBySourceRoot = HashMap<SourceRootId, (Option<String>, Option<String>)>;
+
 /// Kind of symbol - only includes symbol kinds we care about
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, JsonSchema)]
 pub enum SymbolKind {
     Const,
     Enum,
@@ -468,12 +1197,106 @@ pub enum SymbolKind {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(stable_hash(""), 0xcbf29ce484222325);
+        assert_ne!(stable_hash("fn a() {}"), stable_hash("fn b() {}"));
+    }
+
+    #[test]
+    fn test_file_position_checks_column() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let first_line = text.lines().next().unwrap().len() as u32;
+
+        // The end of a line is a position, one past it is not
+        assert!(analyzer.file_position(path, 0, first_line).is_ok());
+        assert!(analyzer.file_position(path, 0, first_line + 1).is_err());
+        assert!(analyzer.file_position(path, 0, 10_000).is_err());
+
+        // Nor is a column running past the end of the file on its last line
+        let last_line = text.lines().count() as u32 - 1;
+        assert!(analyzer.file_position(path, last_line, text.len() as u32).is_err());
+        assert!(analyzer.file_position(path, last_line + 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_version_pin_matching() {
+        assert!(version_matches("2.0.39", "2.0.39"));
+        assert!(version_matches("2.0.39", "2"));
+        assert!(version_matches("2.0.39", "2.0"));
+        assert!(!version_matches("20.1.0", "2"));
+        assert!(!version_matches("1.0.109", "2"));
+    }
+
+    #[test]
+    fn test_crate_lists() {
+        let symbol = |crate_name: &str| SymbolInfo {
+            name: "Runtime".to_string(),
+            kind: SymbolKind::Struct,
+            file_path: "lib.rs".to_string(),
+            start_line: 0,
+            end_line: 0,
+            documentation: None,
+            crate_name: Some(crate_name.to_string()),
+            crate_version: None,
+            moniker: None,
+            rustdoc: None,
+            approximate: false,
+            attributes: Vec::new(),
+            owners: Vec::new(),
+            size: None,
+        };
+
+        let include = SearchOptions { crates_include: vec!["tokio".to_string(), "async-std".to_string()], ..Default::default() };
+        assert!(include.accepts(&symbol("tokio")));
+        assert!(include.accepts(&symbol("async_std")));
+        assert!(!include.accepts(&symbol("hyper")));
+        assert!(include.searches_libraries());
+
+        let exclude = SearchOptions { crates_exclude: vec!["hyper".to_string()], ..Default::default() };
+        assert!(exclude.accepts(&symbol("tokio")));
+        assert!(!exclude.accepts(&symbol("hyper")));
+        assert!(!exclude.searches_libraries());
+    }
+
     #[test]
     fn test_analyzer_creation() {
         let _analyzer = Analyzer::new();
         // Just verify we can create an analyzer
     }
 
+    #[test]
+    fn test_enumerate_files_isolates_errors() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let results = analyzer.enumerate_files(&["src/main.rs".to_string(), "src/missing.rs".to_string()]);
+        assert_eq!(results.len(), 2);
+        assert!(results[0].0.ends_with("src/main.rs"), "Relative paths should resolve against the project root");
+        assert!(results[0].1.as_ref().is_ok_and(|symbols| !symbols.is_empty()));
+        assert!(results[1].1.is_err(), "A missing file should fail on its own");
+    }
+
+    #[test]
+    fn test_symbols_are_tagged_with_crate_version() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let options = SearchOptions { mode: SearchMode::Exact, ..Default::default() };
+        let symbols = analyzer.find_symbol("Analyzer", &options).unwrap();
+        let analyzer_struct = symbols.iter().find(|s| s.kind == SymbolKind::Struct).expect("Analyzer struct not found");
+        assert_eq!(analyzer_struct.crate_name.as_deref(), Some("cratographer"));
+        assert_eq!(analyzer_struct.crate_version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+
+        // Pinning to a version no crate has filters everything out
+        let pinned = SearchOptions { crate_version: Some("999".to_string()), ..options };
+        assert!(analyzer.find_symbol("Analyzer", &pinned).unwrap().is_empty());
+    }
+
     #[test]
     fn test_load_and_find_symbol() {
         let mut analyzer = Analyzer::new();
@@ -551,6 +1374,11 @@ mod tests {
             mode: SearchMode::Exact,
             include_library: false,
             filter: SymbolFilter::All,
+            crate_version: None,
+            signature: SignatureFilter::default(),
+            crates_include: Vec::new(),
+            crates_exclude: Vec::new(),
+            prefer_workspace: false,
         };
         let exact_results = analyzer.find_symbol("Analyzer", &exact_options);
         assert!(exact_results.is_ok(), "Exact search failed: {:?}", exact_results.err());
@@ -583,6 +1411,11 @@ mod tests {
             mode: SearchMode::Prefix,
             include_library: false,
             filter: SymbolFilter::All,
+            crate_version: None,
+            signature: SignatureFilter::default(),
+            crates_include: Vec::new(),
+            crates_exclude: Vec::new(),
+            prefer_workspace: false,
         };
         let prefix_results = analyzer.find_symbol("Analyzer", &prefix_options);
         assert!(prefix_results.is_ok(), "Prefix search failed: {:?}", prefix_results.err());
@@ -626,6 +1459,11 @@ mod tests {
             mode: SearchMode::Exact,
             include_library: false,
             filter: SymbolFilter::All,
+            crate_version: None,
+            signature: SignatureFilter::default(),
+            crates_include: Vec::new(),
+            crates_exclude: Vec::new(),
+            prefer_workspace: false,
         };
         let no_lib_results = analyzer.find_symbol("HashMap", &no_lib_options);
         assert!(no_lib_results.is_ok(), "Search without library failed: {:?}", no_lib_results.err());
@@ -638,6 +1476,11 @@ mod tests {
             mode: SearchMode::Exact,
             include_library: true,
             filter: SymbolFilter::All,
+            crate_version: None,
+            signature: SignatureFilter::default(),
+            crates_include: Vec::new(),
+            crates_exclude: Vec::new(),
+            prefer_workspace: false,
         };
         let with_lib_results = analyzer.find_symbol("HashMap", &with_lib_options);
         assert!(with_lib_results.is_ok(), "Search with library failed: {:?}", with_lib_results.err());
@@ -672,6 +1515,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_prefer_workspace_ranks_own_symbols_first() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Dependencies have plenty of symbols fuzzily matching "Analyzer"
+        let options = SearchOptions { include_library: true, prefer_workspace: true, ..Default::default() };
+        let results = analyzer.find_symbol("Analyzer", &options).unwrap();
+        assert!(results.len() <= SYMBOL_SEARCH_LIMIT);
+        assert_eq!(results[0].name, "Analyzer");
+        assert!(analyzer.is_workspace_symbol(&results[0]));
+    }
+
     #[test]
     fn test_enumerate_analyzer_file() {
         let mut analyzer = Analyzer::new();
@@ -0,0 +1,145 @@
+//! Per-item annotations of a file
+//!
+//! What an editor shows as code lenses above each item: how many references
+//! it has, how many impls a type or trait has, and which tests, benchmarks
+//! or binaries it is. On top of rust-analyzer's lenses, each item also gets
+//! the number of its references made from test code, so an item no test
+//! touches stands out. Together they tell which items are load-bearing
+//! before one is changed or removed.
+
+use super::entry_points::attribute_entry;
+use super::features::{node_cfgs, CfgExpr};
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{AnnotationConfig, AnnotationKind, AnnotationLocation, FileRange, RootDatabase};
+use ra_ap_syntax::{ast, AstNode, NodeOrToken};
+use std::collections::BTreeMap;
+
+/// The annotations of one item
+#[derive(Debug, Clone)]
+pub struct ItemAnnotation {
+    pub name: String,
+    /// Item kind, e.g. `Function` or `Struct`
+    pub kind: &'static str,
+    /// 0-based line of the item's name
+    pub line: u32,
+    /// Number of references, for items rust-analyzer counts them for
+    pub references: Option<usize>,
+    /// How many of the references are in `#[test]` functions or `#[cfg(test)]` modules
+    pub test_references: Option<usize>,
+    /// Number of impls, for types and traits
+    pub implementations: Option<usize>,
+    /// Runnable kinds the item is, e.g. `test` or `test_module`
+    pub runnables: Vec<&'static str>,
+}
+
+impl Analyzer {
+    /// The annotations of each item of a file that has any, in file order
+    pub fn annotations(&self, file_path: &str) -> Result<Vec<ItemAnnotation>, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let config = AnnotationConfig {
+            binary_target: false,
+            annotate_runnables: false,
+            annotate_impls: true,
+            annotate_references: true,
+            annotate_method_references: true,
+            annotate_enum_variant_references: false,
+            location: AnnotationLocation::AboveName,
+            filter_adjacent_derive_implementations: false,
+        };
+        let source = sema.parse_guess_edition(file_id);
+
+        // Keyed by the item's name range, which also orders them by position
+        let mut items: BTreeMap<(u32, u32), ItemAnnotation> = BTreeMap::new();
+        for annotation in analysis.annotations(&config, file_id).map_err(|_| AnalyzerError::Canceled)? {
+            let Some(item) = source
+                .syntax()
+                .covering_element(annotation.range)
+                .ancestors()
+                .find_map(ast::Item::cast)
+            else {
+                continue;
+            };
+            let Some((name, kind)) = item_label(&item) else { continue };
+            let key = (u32::from(annotation.range.start()), u32::from(annotation.range.end()));
+            let line = self.line_range(file_id, annotation.range)?.0;
+
+            let resolved = analysis.resolve_annotation(&config, annotation).map_err(|_| AnalyzerError::Canceled)?;
+            let entry = items.entry(key).or_insert_with(|| ItemAnnotation {
+                name,
+                kind,
+                line,
+                references: None,
+                test_references: None,
+                implementations: None,
+                runnables: Vec::new(),
+            });
+            match resolved.kind {
+                AnnotationKind::HasReferences { data, .. } => {
+                    let references = data.unwrap_or_default();
+                    entry.test_references = Some(references.iter().filter(|r| in_test_code(&sema, r)).count());
+                    entry.references = Some(references.len());
+                }
+                AnnotationKind::HasImpls { data, .. } => entry.implementations = Some(data.map_or(0, |impls| impls.len())),
+                AnnotationKind::Runnable(_) => {}
+            }
+        }
+
+        let mut annotations: Vec<ItemAnnotation> = items.into_values().collect();
+        for runnable in self.runnables_in(file_id)? {
+            // A runnable's range starts with its attributes; it belongs to the item named within it
+            if let Some(item) = annotations
+                .iter_mut()
+                .filter(|a| a.line >= runnable.start_line && a.line <= runnable.end_line)
+                .min_by_key(|a| a.line)
+            {
+                item.runnables.push(runnable.kind);
+            }
+        }
+        Ok(annotations)
+    }
+}
+
+/// Whether a reference is in a `#[test]` function or under `#[cfg(test)]`
+fn in_test_code(sema: &Semantics<'_, RootDatabase>, range: &FileRange) -> bool {
+    let source = sema.parse_guess_edition(range.file_id);
+    let test_cfg = CfgExpr::Atom("test".to_string());
+    let node = match source.syntax().covering_element(range.range) {
+        NodeOrToken::Node(node) => node,
+        NodeOrToken::Token(token) => match token.parent() {
+            Some(node) => node,
+            None => return false,
+        },
+    };
+    node_cfgs(&node).contains(&test_cfg)
+        || node.ancestors().filter_map(ast::Item::cast).any(|item| attribute_entry(&item) == Some("test"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotations_of_runnables_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/runnables.rs").unwrap();
+        let path = path.to_str().unwrap();
+
+        let annotations = analyzer.annotations(path).unwrap();
+        assert!(annotations.windows(2).all(|w| w[0].line <= w[1].line));
+
+        // The Runnable struct is used outside this file
+        let runnable = annotations.iter().find(|a| a.name == "Runnable" && a.kind == "Struct").unwrap();
+        assert!(runnable.references.is_some_and(|n| n > 0));
+        assert!(runnable.implementations.is_some());
+
+        // Test functions are runnables, and referenced from nowhere
+        let test = annotations.iter().find(|a| a.kind == "Function" && a.runnables.contains(&"test")).unwrap();
+        assert!(test.name.starts_with("test_"));
+        assert_eq!(test.references, Some(0));
+    }
+}
@@ -0,0 +1,362 @@
+//! Public API exposure of a type
+//!
+//! Lists the public signatures a type appears in: function parameters and
+//! return types, public fields, enum variants, trait definitions, type
+//! aliases, constants and impl headers. Unlike a plain reference search this
+//! ignores function bodies and private items, so the result is exactly what
+//! changing or removing the type breaks for downstream users.
+
+use super::reference_index::definition_key;
+use super::{item_label, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{HasVisibility as _, PathResolution, Semantics, Visibility};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasGenericParams, HasName, HasTypeBounds, HasVisibility},
+    AstNode, SyntaxNode,
+};
+
+/// A public signature mentioning the type
+#[derive(Debug, Clone)]
+pub struct SignatureUse {
+    /// Name of the exposing item, e.g. `parse`, `Config.path` or `Shape::Circle`
+    pub item: String,
+    /// One of "fn", "field", "variant", "trait", "type_alias", "const", "static" or "impl"
+    pub kind: &'static str,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The signature as written, without bodies
+    pub signature: String,
+    /// Whether every enclosing module is public too, so the item is nameable
+    /// from outside the crate without a re-export
+    pub reachable: bool,
+}
+
+impl Analyzer {
+    /// Find the public signatures in the workspace that mention a type
+    ///
+    /// `type_name` is a bare name (matching every type of that name) or a
+    /// qualified path such as `analyzer::SymbolInfo`.
+    pub fn public_signature_uses(&self, type_name: &str) -> Result<Vec<SignatureUse>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut uses = Vec::new();
+
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            let file_reachable = file_module_public(&sema, file_id);
+
+            let public_impl = |impl_: &ast::Impl| impl_public(&sema, impl_);
+            for item in source.syntax().descendants().filter_map(ast::Item::cast) {
+                for (item_name, kind, node, signature) in exposed_signatures(&item, &public_impl) {
+                    if !signature.iter().any(|node| mentions_type(&sema, db, node, type_name)) {
+                        continue;
+                    }
+
+                    let (start_line, end_line) = self.line_range(file_id, node.text_range())?;
+                    let text = signature.iter().map(|n| n.text().to_string()).collect::<Vec<_>>().join(" ");
+                    uses.push(SignatureUse {
+                        item: item_name,
+                        kind,
+                        file_path: self.file_path(file_id),
+                        start_line,
+                        end_line,
+                        signature: normalize_whitespace(&text),
+                        reachable: file_reachable && inline_modules_public(&node),
+                    });
+                }
+            }
+        }
+
+        Ok(uses)
+    }
+}
+
+/// The public signatures an item exposes, with the nodes making up each one
+///
+/// Returns (name, kind, item node, signature nodes) for every exposed part.
+/// `impl_public` tells whether an impl block's self type and trait are public;
+/// neither its header nor its members are exposed otherwise.
+fn exposed_signatures(item: &ast::Item, impl_public: &dyn Fn(&ast::Impl) -> bool) -> Vec<(String, &'static str, SyntaxNode, Vec<SyntaxNode>)> {
+    fn generics(node: &impl HasGenericParams) -> Vec<SyntaxNode> {
+        let params = node.generic_param_list().map(|l| l.syntax().clone());
+        let wheres = node.where_clause().map(|w| w.syntax().clone());
+        params.into_iter().chain(wheres).collect()
+    }
+    fn name(node: &impl HasName) -> String {
+        node.name().map(|n| n.text().to_string()).unwrap_or_default()
+    }
+
+    // Items in function bodies can't be named from outside
+    let in_body = item.syntax().ancestors().skip(1).any(|n| ast::Fn::can_cast(n.kind()) || ast::BlockExpr::can_cast(n.kind()));
+    if in_body || enclosing_impl(item).is_some_and(|impl_| !impl_public(&impl_)) {
+        return Vec::new();
+    }
+
+    let mut exposed = Vec::new();
+    match item {
+        ast::Item::Fn(func) if is_public_member(item) => {
+            let mut signature = generics(func);
+            signature.extend(func.param_list().map(|p| p.syntax().clone()));
+            signature.extend(func.ret_type().map(|r| r.syntax().clone()));
+            exposed.push((name(func), "fn", func.syntax().clone(), signature));
+        }
+        ast::Item::Struct(strukt) if is_pub(strukt) => {
+            for (field_name, field) in field_types(strukt.field_list(), true) {
+                exposed.push((format!("{}.{}", name(strukt), field_name), "field", field.clone(), vec![field]));
+            }
+        }
+        ast::Item::Union(union) if is_pub(union) => {
+            let fields = union.record_field_list().map(ast::FieldList::RecordFieldList);
+            for (field_name, field) in field_types(fields, true) {
+                exposed.push((format!("{}.{}", name(union), field_name), "field", field.clone(), vec![field]));
+            }
+        }
+        ast::Item::Enum(enum_) if is_pub(enum_) => {
+            for variant in enum_.variant_list().into_iter().flat_map(|l| l.variants()) {
+                // Variant fields are as public as the enum
+                let signature: Vec<_> = field_types(variant.field_list(), false).into_iter().map(|(_, ty)| ty).collect();
+                if !signature.is_empty() {
+                    exposed.push((format!("{}::{}", name(enum_), name(&variant)), "variant", variant.syntax().clone(), signature));
+                }
+            }
+        }
+        ast::Item::Trait(trait_) if is_pub(trait_) => {
+            let mut signature = generics(trait_);
+            signature.extend(trait_.type_bound_list().map(|b| b.syntax().clone()));
+            exposed.push((name(trait_), "trait", trait_.syntax().clone(), signature));
+        }
+        ast::Item::TypeAlias(alias) if is_public_member(item) => {
+            let mut signature = generics(alias);
+            signature.extend(alias.type_bound_list().map(|b| b.syntax().clone()));
+            signature.extend(alias.ty().map(|t| t.syntax().clone()));
+            exposed.push((name(alias), "type_alias", alias.syntax().clone(), signature));
+        }
+        ast::Item::Const(konst) if is_public_member(item) => {
+            let signature = konst.ty().map(|t| t.syntax().clone()).into_iter().collect();
+            exposed.push((name(konst), "const", konst.syntax().clone(), signature));
+        }
+        ast::Item::Static(statik) if is_pub(statik) => {
+            let signature = statik.ty().map(|t| t.syntax().clone()).into_iter().collect();
+            exposed.push((name(statik), "static", statik.syntax().clone(), signature));
+        }
+        ast::Item::Impl(impl_) if impl_public(impl_) => {
+            if let Some((label, _)) = item_label(item) {
+                let mut signature = generics(impl_);
+                signature.extend(impl_.trait_().map(|t| t.syntax().clone()));
+                signature.extend(impl_.self_ty().map(|t| t.syntax().clone()));
+                exposed.push((label, "impl", impl_.syntax().clone(), signature));
+            }
+        }
+        _ => {}
+    }
+    exposed
+}
+
+/// The impl block an associated item belongs to
+fn enclosing_impl(item: &ast::Item) -> Option<ast::Impl> {
+    let list = item.syntax().parent().and_then(ast::AssocItemList::cast)?;
+    list.syntax().parent().and_then(ast::Impl::cast)
+}
+
+/// Whether an impl's self type and trait are declared `pub`, or belong to
+/// another crate
+///
+/// Self types that aren't a single ADT, such as references or tuples, count
+/// as public; what they mention is checked like any other signature.
+fn impl_public(sema: &Semantics<'_, RootDatabase>, impl_: &ast::Impl) -> bool {
+    let db = sema.db;
+    let Some(def) = sema.to_def(impl_) else { return false };
+    let krate = def.module(db).krate();
+    let self_public = def.self_ty(db).as_adt().is_none_or(|adt| adt.module(db).krate() != krate || adt.visibility(db) == Visibility::Public);
+    let trait_public = def.trait_(db).is_none_or(|trait_| trait_.module(db).krate() != krate || trait_.visibility(db) == Visibility::Public);
+    self_public && trait_public
+}
+
+/// Field names and type nodes, keeping only `pub` fields if `pub_only`
+fn field_types(fields: Option<ast::FieldList>, pub_only: bool) -> Vec<(String, SyntaxNode)> {
+    match fields {
+        Some(ast::FieldList::RecordFieldList(list)) => list
+            .fields()
+            .filter(|f| !pub_only || is_pub(f))
+            .filter_map(|f| Some((f.name()?.text().to_string(), f.ty()?.syntax().clone())))
+            .collect(),
+        Some(ast::FieldList::TupleFieldList(list)) => list
+            .fields()
+            .enumerate()
+            .filter(|(_, f)| !pub_only || is_pub(f))
+            .filter_map(|(i, f)| Some((i.to_string(), f.ty()?.syntax().clone())))
+            .collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Whether a node is declared plain `pub` (not `pub(crate)` and the like)
+fn is_pub(node: &impl HasVisibility) -> bool {
+    node.visibility().is_some_and(|v| v.syntax().text() == "pub")
+}
+
+/// Whether a fn, const or type alias is public API
+///
+/// Trait items share the trait's visibility; items of trait impls are
+/// exposed through the impl header rather than on their own.
+fn is_public_member(item: &ast::Item) -> bool {
+    let container = item.syntax().parent().and_then(ast::AssocItemList::cast).and_then(|list| list.syntax().parent());
+    match container.as_ref().and_then(|node| ast::Item::cast(node.clone())) {
+        Some(ast::Item::Trait(trait_)) => is_pub(&trait_),
+        Some(ast::Item::Impl(impl_)) => impl_.trait_().is_none() && has_pub_visibility(item),
+        _ => has_pub_visibility(item),
+    }
+}
+
+/// Whether an item is public API of its module: declared `pub`, or a
+/// member of a public trait
+pub(super) fn is_public_item(item: &ast::Item) -> bool {
+    match item {
+        ast::Item::Fn(_) | ast::Item::Const(_) | ast::Item::TypeAlias(_) => is_public_member(item),
+        ast::Item::Struct(it) => is_pub(it),
+        ast::Item::Enum(it) => is_pub(it),
+        ast::Item::Union(it) => is_pub(it),
+        ast::Item::Trait(it) => is_pub(it),
+        ast::Item::Static(it) => is_pub(it),
+        ast::Item::Module(it) => is_pub(it),
+        _ => false,
+    }
+}
+
+fn has_pub_visibility(item: &ast::Item) -> bool {
+    match item {
+        ast::Item::Fn(it) => is_pub(it),
+        ast::Item::Const(it) => is_pub(it),
+        ast::Item::TypeAlias(it) => is_pub(it),
+        _ => false,
+    }
+}
+
+/// Whether every inline `mod` block around a node is public
+pub(super) fn inline_modules_public(node: &SyntaxNode) -> bool {
+    node.ancestors().filter_map(ast::Module::cast).all(|module| is_pub(&module))
+}
+
+/// Whether the module a file defines, and each of its parents, is public
+pub(super) fn file_module_public(sema: &Semantics<'_, RootDatabase>, file_id: FileId) -> bool {
+    let db = sema.db;
+    let Some(module) = sema.file_to_module_def(file_id) else { return false };
+    module
+        .path_to_root(db)
+        .into_iter()
+        .filter(|m| m.parent(db).is_some())
+        .all(|m| m.visibility(db) == Visibility::Public)
+}
+
+/// Whether any type path inside `node` resolves to the named type
+fn mentions_type(sema: &Semantics<'_, RootDatabase>, db: &RootDatabase, node: &SyntaxNode, type_name: &str) -> bool {
+    let suffix = format!("::{}", type_name);
+    node.descendants().filter_map(ast::Path::cast).any(|path| {
+        let def = match sema.resolve_path(&path) {
+            Some(PathResolution::Def(def)) => Definition::from(def),
+            _ => return false,
+        };
+        definition_key(db, def).is_some_and(|key| key == type_name || key.ends_with(&suffix))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scratch::ScratchProject;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn exposed(src: &str) -> Vec<(String, &'static str)> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        file.syntax()
+            .descendants()
+            .filter_map(ast::Item::cast)
+            .flat_map(|item| exposed_signatures(&item, &|_| true))
+            .map(|(name, kind, _, _)| (name, kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_only_public_signatures_are_exposed() {
+        let items = exposed(
+            "pub struct Config { pub path: Path, cache: Cache }
+             struct Private { pub x: X }
+             pub enum Shape { Circle(f64), Empty }
+             pub fn load(p: Path) -> Config { todo!() }
+             pub(crate) fn internal(p: Path) {}
+             impl Config { pub fn new() -> Self { todo!() } fn helper(&self) {} }
+             impl Display for Config { fn fmt(&self, f: &mut Formatter) -> Result { todo!() } }
+             pub trait Source { fn read(&self) -> Data; }",
+        );
+
+        assert_eq!(
+            items,
+            vec![
+                ("Config.path".to_string(), "field"),
+                ("Shape::Circle".to_string(), "variant"),
+                ("load".to_string(), "fn"),
+                ("impl Config".to_string(), "impl"),
+                ("new".to_string(), "fn"),
+                ("impl Display for Config".to_string(), "impl"),
+                ("Source".to_string(), "trait"),
+                ("read".to_string(), "fn"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_public_signature_uses_in_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let uses = analyzer.public_signature_uses("SymbolKind").unwrap();
+        assert!(
+            uses.iter().any(|u| u.kind == "field" && u.item == "SymbolInfo.kind"),
+            "SymbolInfo.kind should expose SymbolKind: {:?}",
+            uses
+        );
+        assert!(uses.iter().any(|u| u.kind == "fn" && u.item == "accepts"));
+    }
+
+    #[test]
+    fn test_items_in_bodies_are_not_exposed() {
+        let items = exposed(
+            "pub fn outer() { pub struct Local { pub x: X } pub fn inner(x: X) {} impl Local { pub fn new() -> Self { todo!() } } }
+             pub const C: u8 = { pub fn in_const(x: X) {} 0 };",
+        );
+
+        assert_eq!(items, vec![("outer".to_string(), "fn"), ("C".to_string(), "const")]);
+    }
+
+    #[test]
+    fn test_impls_of_private_types_and_traits_are_not_exposed() {
+        let analyzer = ScratchProject::new(
+            "api-exposure",
+            &[(
+                "exposure.rs",
+                "pub struct ExposureProbe;\n\
+                 struct PrivateHolder;\n\
+                 trait PrivateTrait { fn probe(&self) -> ExposureProbe; }\n\
+                 pub trait PublicTrait { fn probe(&self) -> ExposureProbe; }\n\
+                 impl PrivateHolder { pub fn private_probe() -> ExposureProbe { ExposureProbe } }\n\
+                 impl From<ExposureProbe> for PrivateHolder { fn from(_: ExposureProbe) -> Self { PrivateHolder } }\n\
+                 impl PrivateTrait for ExposureProbe { fn probe(&self) -> ExposureProbe { ExposureProbe } }\n\
+                 impl PublicTrait for ExposureProbe { fn probe(&self) -> ExposureProbe { ExposureProbe } }\n\
+                 impl ExposureProbe { pub fn public_probe() -> ExposureProbe { ExposureProbe } }\n",
+            )],
+        );
+
+        let uses = analyzer.public_signature_uses("ExposureProbe").unwrap();
+        let items: Vec<&str> = uses.iter().map(|u| u.item.as_str()).collect();
+
+        // A pub fn on a private type isn't public API
+        assert!(!items.contains(&"private_probe"), "{:?}", items);
+        // Nor are impl headers for a private self type or trait
+        assert!(!items.contains(&"impl From<ExposureProbe> for PrivateHolder"), "{:?}", items);
+        assert!(!items.contains(&"impl PrivateTrait for ExposureProbe"), "{:?}", items);
+        assert!(items.contains(&"impl PublicTrait for ExposureProbe"), "{:?}", items);
+        assert!(items.contains(&"public_probe"), "{:?}", items);
+    }
+}
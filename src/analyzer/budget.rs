@@ -0,0 +1,65 @@
+//! Time budgets for expensive analyses
+//!
+//! Workspace-wide scans that type-check every file can take long enough on a
+//! large project for the client to time out, at which point the work is
+//! lost. Given a budget, such a scan stops when the budget runs out and
+//! returns what it has found, with how many files it covered, instead of an
+//! answer that never arrives.
+
+use std::time::{Duration, Instant};
+
+/// How long an analysis may run
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Budget {
+    /// When the analysis should stop; `None` for no limit
+    deadline: Option<Instant>,
+}
+
+impl Budget {
+    /// A budget that never runs out
+    pub fn unlimited() -> Self {
+        Budget::default()
+    }
+
+    /// A budget of `millis` milliseconds from now, or no limit; one too
+    /// large to represent never runs out either
+    pub fn from_millis(millis: Option<u64>) -> Self {
+        Budget { deadline: millis.and_then(|millis| Instant::now().checked_add(Duration::from_millis(millis))) }
+    }
+
+    /// Whether the budget has run out
+    pub fn exhausted(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+    }
+}
+
+/// How much of a budgeted scan was done
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub files_scanned: usize,
+    pub files_total: usize,
+}
+
+impl Coverage {
+    /// Whether every file was scanned, so the result is the full answer
+    pub fn complete(&self) -> bool {
+        self.files_scanned == self.files_total
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_runs_out() {
+        assert!(!Budget::unlimited().exhausted());
+        assert!(!Budget::from_millis(None).exhausted());
+        assert!(Budget::from_millis(Some(0)).exhausted());
+        assert!(!Budget::from_millis(Some(60_000)).exhausted());
+        assert!(!Budget::from_millis(Some(u64::MAX)).exhausted());
+
+        assert!(Coverage { files_scanned: 3, files_total: 3 }.complete());
+        assert!(!Coverage { files_scanned: 2, files_total: 3 }.complete());
+    }
+}
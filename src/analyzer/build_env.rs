@@ -0,0 +1,174 @@
+//! Compile-time environment coupling
+//!
+//! `env!` and `option_env!` read environment variables when the crate is
+//! compiled, and `include_str!`, `include_bytes!` and `include!` read files;
+//! either way the binary depends on the build machine, not just the source.
+//! Deployment engineers auditing that coupling need every such use, with its
+//! arguments and where it is, including uses nested in other macros'
+//! arguments such as `include_str!(concat!(env!("OUT_DIR"), "/gen.rs"))`.
+//!
+//! This works on the syntax tree, matching the macros by name.
+
+use super::{item_label, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{ast, AstNode, SyntaxKind, SyntaxNode, SyntaxToken, T};
+
+/// The macros reported, in report order
+pub const BUILD_ENV_MACROS: &[&str] = &["env", "option_env", "include_str", "include_bytes", "include"];
+
+/// One use of a compile-time environment or file macro
+#[derive(Debug, Clone)]
+pub struct BuildEnvUsage {
+    /// One of `BUILD_ENV_MACROS`
+    pub macro_name: &'static str,
+    /// The variable name or file path when given as a string literal
+    pub argument: Option<String>,
+    /// The arguments as written
+    pub arguments: String,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing item, e.g. `main` or `impl Config`
+    pub item: Option<String>,
+    /// Item kind as in `enumerate_file`, e.g. "Function"
+    pub item_kind: Option<&'static str>,
+}
+
+impl Analyzer {
+    /// Every use of `BUILD_ENV_MACROS` in the workspace, by file and then in
+    /// source order
+    pub fn build_env_usages(&self) -> Result<Vec<BuildEnvUsage>, AnalyzerError> {
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut usages = Vec::new();
+        for (file_path, file_id) in files {
+            usages.extend(self.file_build_env_usages(file_id, &file_path)?);
+        }
+        Ok(usages)
+    }
+
+    fn file_build_env_usages(&self, file_id: FileId, file_path: &str) -> Result<Vec<BuildEnvUsage>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let source = analysis.parse(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let mut usages = Vec::new();
+        for (macro_name, name_token, args) in macro_uses(source.syntax(), BUILD_ENV_MACROS) {
+            let (item, item_kind) = name_token
+                .parent_ancestors()
+                .filter_map(ast::Item::cast)
+                .find_map(|item| item_label(&item))
+                .map_or((None, None), |(name, kind)| (Some(name), Some(kind)));
+            usages.push(BuildEnvUsage {
+                macro_name,
+                argument: first_string_literal(&args),
+                arguments: inner_text(&args),
+                file_path: file_path.to_string(),
+                line: line_index.line_col(name_token.text_range().start()).line,
+                item,
+                item_kind,
+            });
+        }
+        Ok(usages)
+    }
+}
+
+/// The uses of the named macros in a syntax tree, with the token naming
+/// the macro and the token tree of its arguments, in source order
+///
+/// Macro calls in code are `MACRO_CALL` nodes; those inside another macro's
+/// arguments are only tokens: a name, `!` and a token tree.
+pub(super) fn macro_uses(root: &SyntaxNode, names: &[&'static str]) -> Vec<(&'static str, SyntaxToken, SyntaxNode)> {
+    let reported = |name: &str| names.iter().copied().find(|m| *m == name);
+    let mut uses = Vec::new();
+    for node in root.descendants() {
+        if let Some(call) = ast::MacroCall::cast(node.clone()) {
+            let Some(name) = call.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref()) else {
+                continue;
+            };
+            let (Some(macro_name), Some(token), Some(args)) =
+                (reported(name.text().as_str()), name.syntax().first_token(), call.token_tree())
+            else {
+                continue;
+            };
+            uses.push((macro_name, token, args.syntax().clone()));
+        } else if node.kind() == SyntaxKind::TOKEN_TREE {
+            let children: Vec<_> = node.children_with_tokens().filter(|child| !child.kind().is_trivia()).collect();
+            for window in children.windows(3) {
+                let [name, bang, args] = window else { continue };
+                let (Some(name), Some(args)) = (name.as_token(), args.as_node()) else { continue };
+                if name.kind() != SyntaxKind::IDENT || bang.kind() != T![!] || args.kind() != SyntaxKind::TOKEN_TREE {
+                    continue;
+                }
+                if let Some(macro_name) = reported(name.text()) {
+                    uses.push((macro_name, name.clone(), args.clone()));
+                }
+            }
+        }
+    }
+    uses.sort_by_key(|(_, token, _)| token.text_range().start());
+    uses
+}
+
+/// The value of the first top-level argument, if it is a string literal
+fn first_string_literal(args: &SyntaxNode) -> Option<String> {
+    let first = args
+        .children_with_tokens()
+        .filter(|child| !child.kind().is_trivia())
+        .nth(1)?
+        .into_token()?;
+    ast::String::cast(first)?.value().ok().map(|value| value.to_string())
+}
+
+/// The arguments of a macro call without the surrounding delimiters
+fn inner_text(args: &SyntaxNode) -> String {
+    let text = args.text().to_string();
+    let inner = text.get(1..text.len().saturating_sub(1)).unwrap_or_default();
+    normalize_whitespace(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_macro_uses_including_nested() {
+        let file = SourceFile::parse(
+            r#"
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+fn token() -> Option<&'static str> { option_env!("API_TOKEN") }
+include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+fn not_reported() { println!("{}", std::env::var("HOME").unwrap()); }
+"#,
+            Edition::CURRENT,
+        )
+        .tree();
+        let uses: Vec<_> = macro_uses(file.syntax(), BUILD_ENV_MACROS)
+            .into_iter()
+            .map(|(name, _, args)| (name, first_string_literal(&args), inner_text(&args)))
+            .collect();
+
+        assert_eq!(
+            uses,
+            vec![
+                ("env", Some("CARGO_PKG_VERSION".to_string()), "\"CARGO_PKG_VERSION\"".to_string()),
+                ("option_env", Some("API_TOKEN".to_string()), "\"API_TOKEN\"".to_string()),
+                ("include", None, "concat!(env!(\"OUT_DIR\"), \"/generated.rs\")".to_string()),
+                ("env", Some("OUT_DIR".to_string()), "\"OUT_DIR\"".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_env_usages_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // build.rs sets CRATOGRAPHER_RA_AP_VERSION, which the analyzer reads with env!
+        let usages = analyzer.build_env_usages().unwrap();
+        let version = usages.iter().find(|u| u.argument.as_deref() == Some("CRATOGRAPHER_RA_AP_VERSION")).unwrap();
+        assert_eq!(version.macro_name, "env");
+        assert!(version.item.is_some());
+    }
+}
@@ -0,0 +1,211 @@
+//! Build script analysis
+//!
+//! A package's `build.rs` is compiled as a crate of its own, and the cfgs and
+//! environment variables it emits with `cargo:` directives steer how the rest
+//! of the package builds. Each build script is listed with its symbols and
+//! the directives found in its string literals, e.g.
+//! `println!("cargo:rustc-cfg=has_simd")`. Every emitted cfg is linked to the
+//! package items gated on it; `feature_matrix` can't decide those (it reports
+//! them as unknown), since only running the build script would.
+//!
+//! Directives are read from the source, not from a build: ones assembled at
+//! runtime show up with their format placeholders and are marked dynamic.
+
+use super::features::{module_cfgs, node_cfgs, CfgExpr};
+use super::{item_label, Analyzer, AnalyzerError, SymbolInfo};
+use ra_ap_hir::Semantics;
+use ra_ap_syntax::{ast, AstNode, AstToken};
+
+/// A `cargo:` directive a build script prints
+#[derive(Debug, Clone, PartialEq)]
+pub struct BuildDirective {
+    /// The instruction, e.g. `rustc-cfg`, `rustc-env` or `rerun-if-changed`
+    pub key: String,
+    /// Everything after the `=`, e.g. `has_simd` or `GIT_HASH={}`
+    pub value: String,
+    pub line: u32,
+    /// Whether the value has format placeholders, so the emitted text is
+    /// only known at build time
+    pub dynamic: bool,
+}
+
+/// A package item gated on a cfg its build script emits
+#[derive(Debug, Clone)]
+pub struct BuildCfgItem {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The item's combined cfg predicate, including those of enclosing items and modules
+    pub cfg: String,
+}
+
+/// A cfg emitted by a build script and the items it gates
+#[derive(Debug, Clone)]
+pub struct BuildCfg {
+    /// The cfg as it appears in `#[cfg]`, e.g. `has_simd` or `backend = "gl"`
+    pub cfg: String,
+    pub items: Vec<BuildCfgItem>,
+}
+
+/// A package's build script
+#[derive(Debug, Clone)]
+pub struct BuildScript {
+    pub crate_name: String,
+    pub file_path: String,
+    pub symbols: Vec<SymbolInfo>,
+    pub directives: Vec<BuildDirective>,
+    /// The static `rustc-cfg` directives, with the items they gate
+    pub cfgs: Vec<BuildCfg>,
+    /// Names of the environment variables set with `rustc-env`
+    pub env_vars: Vec<String>,
+}
+
+impl Analyzer {
+    /// List the workspace build scripts with their symbols, the `cargo:`
+    /// directives they print and the package items gated on the cfgs they emit
+    ///
+    /// A build script is a crate root file named `build.rs`.
+    pub fn build_scripts(&self) -> Result<Vec<BuildScript>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let files = self.workspace_files()?;
+
+        let mut scripts = Vec::new();
+        for &file_id in &files {
+            let file_path = self.file_path(file_id);
+            if !file_path.ends_with("/build.rs") && file_path != "build.rs" {
+                continue;
+            }
+            let Some(module) = sema.file_to_module_def(file_id) else { continue };
+            if module.parent(db).is_some() {
+                continue;
+            }
+
+            let source_file = sema.parse_guess_edition(file_id);
+            let mut directives = Vec::new();
+            for token in source_file.syntax().descendants_with_tokens().filter_map(|e| e.into_token()) {
+                let Some(string) = ast::String::cast(token) else { continue };
+                let Ok(value) = string.value() else { continue };
+                let line = self.line_range(file_id, string.syntax().text_range())?.0;
+                directives.extend(parse_directives(&value, line));
+            }
+
+            // The other targets of the package share the build script's source root
+            let root = analysis.source_root_id(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let mut cfgs: Vec<BuildCfg> = Vec::new();
+            for directive in directives.iter().filter(|d| d.key == "rustc-cfg" && !d.dynamic) {
+                let Some(cfg) = CfgExpr::parse(&directive.value) else { continue };
+                if cfgs.iter().any(|c| c.cfg == cfg.to_string()) {
+                    continue;
+                }
+                let mut items = Vec::new();
+                for &other in &files {
+                    if other == file_id || analysis.source_root_id(other).map_err(|_| AnalyzerError::Canceled)? != root {
+                        continue;
+                    }
+                    let file_cfgs = module_cfgs(&sema, db, other);
+                    for item in sema.parse_guess_edition(other).syntax().descendants().filter_map(ast::Item::cast) {
+                        let Some((name, kind)) = item_label(&item) else { continue };
+                        let mut item_cfgs = file_cfgs.clone();
+                        item_cfgs.extend(node_cfgs(item.syntax()));
+                        if !item_cfgs.iter().any(|c| c.mentions(&cfg)) {
+                            continue;
+                        }
+                        let item_cfg = match item_cfgs.len() {
+                            1 => item_cfgs.remove(0),
+                            _ => CfgExpr::All(item_cfgs),
+                        };
+                        let (start_line, end_line) = self.line_range(other, item.syntax().text_range())?;
+                        items.push(BuildCfgItem {
+                            name,
+                            kind: kind.to_string(),
+                            file_path: self.file_path(other),
+                            start_line,
+                            end_line,
+                            cfg: item_cfg.to_string(),
+                        });
+                    }
+                }
+                items.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+                cfgs.push(BuildCfg { cfg: cfg.to_string(), items });
+            }
+
+            let mut env_vars: Vec<String> = directives
+                .iter()
+                .filter(|d| d.key == "rustc-env")
+                .filter_map(|d| d.value.split_once('=').map(|(name, _)| name.to_string()))
+                .collect();
+            env_vars.sort();
+            env_vars.dedup();
+
+            scripts.push(BuildScript {
+                crate_name: module.krate().display_name(db).map(|n| n.to_string()).unwrap_or_default(),
+                symbols: self.file_symbols(file_id, &file_path)?,
+                file_path,
+                directives,
+                cfgs,
+                env_vars,
+            });
+        }
+
+        scripts.sort_by(|a, b| a.file_path.cmp(&b.file_path));
+        Ok(scripts)
+    }
+}
+
+/// The `cargo:` (or `cargo::`) directives in a string literal's value, one
+/// per line
+fn parse_directives(text: &str, line: u32) -> Vec<BuildDirective> {
+    text.lines()
+        .filter_map(|l| {
+            let rest = l.trim().strip_prefix("cargo:")?;
+            let rest = rest.strip_prefix(':').unwrap_or(rest);
+            let (key, value) = rest.split_once('=')?;
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+                return None;
+            }
+            Some(BuildDirective {
+                key: key.to_string(),
+                value: value.to_string(),
+                line,
+                dynamic: value.contains('{'),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_directives() {
+        let directives = parse_directives("cargo:rustc-cfg=has_simd", 3);
+        assert_eq!(directives, vec![BuildDirective {
+            key: "rustc-cfg".to_string(),
+            value: "has_simd".to_string(),
+            line: 3,
+            dynamic: false,
+        }]);
+
+        // The newer `cargo::` form, key-value cfgs and format placeholders
+        let directives = parse_directives("cargo::rustc-cfg=backend=\"gl\"\ncargo:rustc-env=GIT_HASH={}", 0);
+        assert_eq!(directives.len(), 2);
+        assert_eq!(CfgExpr::parse(&directives[0].value), Some(CfgExpr::KeyValue("backend".into(), "gl".into())));
+        assert!(directives[1].dynamic);
+
+        assert!(parse_directives("not a directive", 0).is_empty());
+    }
+
+    #[test]
+    fn test_build_scripts_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // This package has no build script
+        assert!(analyzer.build_scripts().unwrap().is_empty());
+    }
+}
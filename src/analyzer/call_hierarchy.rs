@@ -0,0 +1,113 @@
+//! Incoming call hierarchy
+//!
+//! Lists the functions calling a given function, with every call site, using
+//! rust-analyzer's call hierarchy. The first thing to check before changing a
+//! function's signature or behavior.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::CallHierarchyConfig;
+use ra_ap_ide_db::defs::Definition;
+
+/// A function calling the queried one
+#[derive(Debug, Clone)]
+pub struct Caller {
+    pub name: String,
+    /// Name of the enclosing item, e.g. the impl's type for a method
+    pub container: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Positions of the calls within the caller (0-based line and column)
+    pub call_sites: Vec<(u32, u32)>,
+    /// Stable moniker of the caller, as for `resolve_moniker`
+    pub moniker: Option<String>,
+}
+
+/// The callers of one function
+#[derive(Debug, Clone)]
+pub struct IncomingCalls {
+    /// Qualified path of the called function
+    pub function: String,
+    pub callers: Vec<Caller>,
+}
+
+impl Analyzer {
+    /// Find the callers of the function matching a name, qualified path or
+    /// symbol_id
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several functions match. With `exclude_tests`, calls from test functions are left out.
+    pub fn callers_of(&self, name: &str, exclude_tests: bool) -> Result<Vec<IncomingCalls>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let config = CallHierarchyConfig { exclude_tests };
+
+        let functions: Vec<_> = self
+            .definition_positions(&sema, name)?
+            .into_iter()
+            .filter(|(def, _)| matches!(def, Definition::Function(_)))
+            .collect();
+        if functions.is_empty() {
+            return Err(AnalyzerError::Other(format!("No function named '{}' found", name)));
+        }
+        self.require_unique(&sema, name, &functions.iter().map(|(def, _)| *def).collect::<Vec<_>>())?;
+
+        let mut results = Vec::new();
+        for (def, position) in functions {
+            let calls = analysis.incoming_calls(&config, position).map_err(|_| AnalyzerError::Canceled)?.unwrap_or_default();
+
+            let mut callers = Vec::new();
+            for call in calls {
+                let nav = call.target;
+                let (start_line, end_line) = self.line_range(nav.file_id, nav.full_range)?;
+                let line_index = analysis.file_line_index(nav.file_id).map_err(|_| AnalyzerError::Canceled)?;
+                let mut call_sites: Vec<_> = call
+                    .ranges
+                    .iter()
+                    .map(|range| {
+                        let position = line_index.line_col(range.range.start());
+                        (position.line, position.col)
+                    })
+                    .collect();
+                call_sites.sort();
+
+                callers.push(Caller {
+                    name: nav.name.to_string(),
+                    container: nav.container_name.as_ref().map(|c| c.to_string()),
+                    file_path: self.file_path(nav.file_id),
+                    start_line,
+                    end_line,
+                    call_sites,
+                    moniker: self.moniker_at(&sema, nav.file_id, nav.focus_or_full_range().start()),
+                });
+            }
+            callers.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+
+            results.push(IncomingCalls {
+                function: definition_key(db, def).unwrap_or_else(|| name.to_string()),
+                callers,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_callers_of_file_position() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let results = analyzer.callers_of("Analyzer::file_position", false).unwrap();
+        assert_eq!(results.len(), 1);
+        let callers = &results[0].callers;
+        assert!(callers.iter().any(|c| c.name == "goto_definition" && c.file_path.ends_with("navigation.rs")));
+        assert!(callers.iter().all(|c| !c.call_sites.is_empty()));
+    }
+}
@@ -0,0 +1,86 @@
+//! What this build and the loaded project support
+//!
+//! Clients adapt to the server rather than finding out at call time: which
+//! rust-analyzer release answers their queries, and whether the optional
+//! subsystems are on. Proc macros need the toolchain's proc-macro server,
+//! live updates need the file watcher, and persisted state needs a writable
+//! cache directory, so each can be missing on a given machine.
+
+use super::fingerprint::PersistedStatus;
+use super::library_cache::library_cache_dir;
+use super::Analyzer;
+
+/// Each `ra_ap_*` crate compiled in, as `name@version`, from Cargo.lock at build time
+const RA_AP_CRATES: &str = env!("CRATOGRAPHER_RA_AP_CRATES");
+
+/// State of the optional subsystems for the loaded project
+#[derive(Debug, Clone)]
+pub struct Subsystems {
+    /// Why proc macros aren't expanded, or `None` if they are
+    pub proc_macros_unavailable: Option<String>,
+    /// Whether source files are watched for changes
+    pub watching: bool,
+    /// Whether library symbols are served from the shared cache
+    pub library_cache: bool,
+    /// Directory of the shared library cache, if one can be determined
+    pub library_cache_dir: Option<String>,
+    /// Whether the persisted reference index matches the project
+    pub reference_index: PersistedStatus,
+}
+
+/// Name and version of each `ra_ap_*` crate compiled in; a crate can be
+/// listed with several versions
+pub fn ra_ap_crates() -> Vec<(&'static str, &'static str)> {
+    parse_crates(RA_AP_CRATES)
+}
+
+fn parse_crates(crates: &str) -> Vec<(&str, &str)> {
+    crates.split(',').filter_map(|entry| entry.split_once('@')).collect()
+}
+
+impl Analyzer {
+    /// State of the optional subsystems
+    pub fn subsystems(&self) -> Subsystems {
+        Subsystems {
+            proc_macros_unavailable: self.proc_macro_server.as_ref().err().cloned(),
+            watching: self.loader.is_some(),
+            library_cache: self.library_symbols.is_some(),
+            library_cache_dir: library_cache_dir().map(|dir| dir.to_string_lossy().into_owned()),
+            reference_index: self.reference_index_status(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::RA_AP_VERSION;
+
+    #[test]
+    fn test_parse_crates() {
+        assert_eq!(
+            parse_crates("ra_ap_hir@0.0.352,ra_ap_paths@0.0.352,ra_ap_paths@0.0.357"),
+            vec![("ra_ap_hir", "0.0.352"), ("ra_ap_paths", "0.0.352"), ("ra_ap_paths", "0.0.357")]
+        );
+        assert!(parse_crates("").is_empty());
+    }
+
+    #[test]
+    fn test_ra_ap_ide_is_compiled_in() {
+        if RA_AP_VERSION != "unknown" {
+            assert!(ra_ap_crates().contains(&("ra_ap_ide", RA_AP_VERSION)));
+        }
+    }
+
+    #[test]
+    fn test_subsystems_before_and_after_loading() {
+        let mut analyzer = Analyzer::new();
+        let before = analyzer.subsystems();
+        assert!(!before.watching);
+        assert!(before.proc_macros_unavailable.is_some());
+        assert!(!before.library_cache);
+
+        assert!(analyzer.load_project(".").is_ok());
+        assert!(analyzer.subsystems().watching);
+    }
+}
@@ -0,0 +1,353 @@
+//! Duplicate definitions across cfg branches
+//!
+//! Platform and feature code often defines the same item several times under
+//! different `#[cfg]`s. That is fine as long as the predicates are mutually
+//! exclusive; when some configuration enables two of them at once, the crate
+//! fails to build there. This analysis finds items sharing a name and
+//! namespace in the same module or impl block and decides, by enumerating the
+//! cfg options involved, whether any configuration enables more than one.
+//! An item is only enabled where the modules and impl blocks enclosing it are,
+//! so their cfgs count towards its own: the same function in a `#[cfg(unix)]`
+//! and a `#[cfg(windows)]` copy of `mod imp` is fine.
+
+use super::features::{own_cfgs, CfgExpr};
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode, SyntaxNode,
+};
+use std::collections::HashMap;
+
+/// Keys that take exactly one value for a given target
+const SINGLE_VALUED_KEYS: &[&str] = &[
+    "target_os", "target_arch", "target_env", "target_vendor", "target_endian", "target_pointer_width", "panic",
+];
+
+/// Bare cfg options of which at most one is set
+const EXCLUSIVE_ATOMS: &[&str] = &["unix", "windows"];
+
+/// Maximum number of distinct cfg options enumerated for one pair of items
+const MAX_CFG_OPTIONS: usize = 16;
+
+/// Whether duplicate definitions can coexist in some configuration
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateStatus {
+    /// No configuration enables more than one definition
+    Exclusive,
+    /// Some configuration enables two definitions at once
+    Conflict,
+    /// Too many cfg options to decide
+    Unknown,
+}
+
+/// One definition among a set of duplicates
+#[derive(Debug, Clone)]
+pub struct DuplicateSite {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The predicate enabling the item, including those of its enclosing
+    /// modules and impl blocks, if there is one
+    pub cfg: Option<String>,
+}
+
+/// An item defined more than once in the same scope
+#[derive(Debug, Clone)]
+pub struct DuplicateDefinition {
+    pub name: String,
+    /// "type" or "value"
+    pub namespace: &'static str,
+    /// The module or impl block holding the definitions
+    pub scope: String,
+    pub definitions: Vec<DuplicateSite>,
+    pub status: DuplicateStatus,
+    /// For conflicts, cfg options under which two definitions are both enabled
+    pub witness: Option<String>,
+}
+
+impl Analyzer {
+    /// Find items defined more than once in the same scope across cfg branches
+    pub fn cfg_duplicates(&self) -> Result<Vec<DuplicateDefinition>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut duplicates = Vec::new();
+
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            let file_path = self.file_path(file_id);
+
+            // Group definitions by scope, name and namespace
+            let mut groups: HashMap<(String, String, &'static str), Vec<(SyntaxNode, Vec<CfgExpr>)>> = HashMap::new();
+            for item in source.syntax().descendants().filter_map(ast::Item::cast) {
+                let Some((name, namespace)) = item_namespace(&item) else { continue };
+                let Some(scope) = item_scope(item.syntax()) else { continue };
+                groups.entry((scope, name, namespace)).or_default().push((item.syntax().clone(), effective_cfgs(&item)));
+            }
+
+            for ((scope, name, namespace), items) in groups {
+                if items.len() < 2 {
+                    continue;
+                }
+
+                let cfgs: Vec<Option<CfgExpr>> = items.iter().map(|(_, cfgs)| combine(cfgs)).collect();
+                let (status, witness) = classify(&cfgs);
+
+                let mut definitions = Vec::new();
+                for ((node, _), cfg) in items.iter().zip(&cfgs) {
+                    let (start_line, end_line) = self.line_range(file_id, node.text_range())?;
+                    definitions.push(DuplicateSite {
+                        file_path: file_path.clone(),
+                        start_line,
+                        end_line,
+                        cfg: cfg.as_ref().map(|c| c.to_string()),
+                    });
+                }
+                definitions.sort_by_key(|d| d.start_line);
+
+                duplicates.push(DuplicateDefinition {
+                    name,
+                    namespace,
+                    scope: format!("{} ({})", scope, file_path),
+                    definitions,
+                    status,
+                    witness,
+                });
+            }
+        }
+
+        duplicates.sort_by(|a, b| (&a.scope, a.definitions[0].start_line).cmp(&(&b.scope, b.definitions[0].start_line)));
+        Ok(duplicates)
+    }
+}
+
+/// Name and namespace of an item that can clash with a same-named sibling
+fn item_namespace(item: &ast::Item) -> Option<(String, &'static str)> {
+    fn named(node: &impl HasName) -> Option<String> {
+        node.name().map(|n| n.text().to_string())
+    }
+
+    match item {
+        ast::Item::Struct(it) => Some((named(it)?, "type")),
+        ast::Item::Enum(it) => Some((named(it)?, "type")),
+        ast::Item::Union(it) => Some((named(it)?, "type")),
+        ast::Item::Trait(it) => Some((named(it)?, "type")),
+        ast::Item::TypeAlias(it) => Some((named(it)?, "type")),
+        ast::Item::Module(it) => Some((named(it)?, "type")),
+        ast::Item::Fn(it) => Some((named(it)?, "value")),
+        ast::Item::Const(it) => Some((named(it)?, "value")),
+        ast::Item::Static(it) => Some((named(it)?, "value")),
+        _ => None,
+    }
+}
+
+/// Where an item is declared: the file itself, an inline module or an impl
+/// block, given as a path of labels; `None` for items in function bodies
+fn item_scope(node: &SyntaxNode) -> Option<String> {
+    let mut labels = Vec::new();
+    for ancestor in node.ancestors().skip(1) {
+        if let Some(module) = ast::Module::cast(ancestor.clone()) {
+            labels.push(format!("mod {}", module.name()?.text()));
+        } else if let Some(impl_) = ast::Impl::cast(ancestor.clone()) {
+            labels.push(item_label(&ast::Item::Impl(impl_))?.0);
+        } else if let Some(trait_) = ast::Trait::cast(ancestor.clone()) {
+            labels.push(format!("trait {}", trait_.name()?.text()));
+        } else if ast::BlockExpr::can_cast(ancestor.kind()) || ast::Fn::can_cast(ancestor.kind()) {
+            return None;
+        }
+    }
+    labels.reverse();
+    if labels.is_empty() {
+        return Some("file".to_string());
+    }
+    Some(labels.join(" > "))
+}
+
+/// The cfgs of an item and of the modules, impl blocks and traits enclosing
+/// it within the file, all of which must hold for the item to be enabled
+fn effective_cfgs(item: &ast::Item) -> Vec<CfgExpr> {
+    let mut cfgs = own_cfgs(item);
+    for ancestor in item.syntax().ancestors().skip(1) {
+        if let Some(module) = ast::Module::cast(ancestor.clone()) {
+            cfgs.extend(own_cfgs(&module));
+        } else if let Some(impl_) = ast::Impl::cast(ancestor.clone()) {
+            cfgs.extend(own_cfgs(&impl_));
+        } else if let Some(trait_) = ast::Trait::cast(ancestor) {
+            cfgs.extend(own_cfgs(&trait_));
+        }
+    }
+    cfgs
+}
+
+fn combine(cfgs: &[CfgExpr]) -> Option<CfgExpr> {
+    match cfgs.len() {
+        0 => None,
+        1 => Some(cfgs[0].clone()),
+        _ => Some(CfgExpr::All(cfgs.to_vec())),
+    }
+}
+
+/// Decide whether any configuration enables two of the definitions
+fn classify(cfgs: &[Option<CfgExpr>]) -> (DuplicateStatus, Option<String>) {
+    let mut status = DuplicateStatus::Exclusive;
+    for (i, a) in cfgs.iter().enumerate() {
+        for b in &cfgs[i + 1..] {
+            match (a, b) {
+                // An unconditional definition clashes wherever the other exists
+                (None, None) => return (DuplicateStatus::Conflict, Some("any configuration".to_string())),
+                (Some(cfg), None) | (None, Some(cfg)) => match satisfying(&[cfg]) {
+                    Some(Some(witness)) => return (DuplicateStatus::Conflict, Some(witness)),
+                    Some(None) => {}
+                    None => status = DuplicateStatus::Unknown,
+                },
+                (Some(a), Some(b)) => match satisfying(&[a, b]) {
+                    Some(Some(witness)) => return (DuplicateStatus::Conflict, Some(witness)),
+                    Some(None) => {}
+                    None => status = DuplicateStatus::Unknown,
+                },
+            }
+        }
+    }
+    (status, None)
+}
+
+/// Find cfg options under which all predicates hold
+///
+/// Returns `Some(Some(options))` with a satisfying assignment rendered as the
+/// enabled options, `Some(None)` if there is none, and `None` if there are too
+/// many options to enumerate.
+fn satisfying(exprs: &[&CfgExpr]) -> Option<Option<String>> {
+    let mut options = Vec::new();
+    exprs.iter().for_each(|expr| collect_options(expr, &mut options));
+    if options.len() > MAX_CFG_OPTIONS {
+        return None;
+    }
+
+    for mask in 0u32..(1 << options.len()) {
+        let enabled: Vec<&CfgExpr> = options.iter().enumerate().filter(|(i, _)| mask & (1 << i) != 0).map(|(_, o)| o).collect();
+        if !consistent(&enabled) {
+            continue;
+        }
+        if exprs.iter().all(|expr| holds(expr, &enabled)) {
+            let rendered = if enabled.is_empty() {
+                "no cfg options set".to_string()
+            } else {
+                enabled.iter().map(|o| o.to_string()).collect::<Vec<_>>().join(", ")
+            };
+            return Some(Some(rendered));
+        }
+    }
+    Some(None)
+}
+
+/// Collect the atomic options (`unix`, `feature = "x"`) of a predicate
+fn collect_options(expr: &CfgExpr, out: &mut Vec<CfgExpr>) {
+    match expr {
+        CfgExpr::Atom(_) | CfgExpr::KeyValue(..) => {
+            if !out.contains(expr) {
+                out.push(expr.clone());
+            }
+        }
+        CfgExpr::Not(inner) => collect_options(inner, out),
+        CfgExpr::All(exprs) | CfgExpr::Any(exprs) => exprs.iter().for_each(|e| collect_options(e, out)),
+    }
+}
+
+/// Whether a set of enabled options describes a possible target
+fn consistent(enabled: &[&CfgExpr]) -> bool {
+    let exclusive_atoms = enabled.iter().filter(|o| matches!(o, CfgExpr::Atom(name) if EXCLUSIVE_ATOMS.contains(&name.as_str()))).count();
+    if exclusive_atoms > 1 {
+        return false;
+    }
+    SINGLE_VALUED_KEYS.iter().all(|key| {
+        enabled.iter().filter(|o| matches!(o, CfgExpr::KeyValue(k, _) if k == key)).count() <= 1
+    })
+}
+
+fn holds(expr: &CfgExpr, enabled: &[&CfgExpr]) -> bool {
+    match expr {
+        CfgExpr::Atom(_) | CfgExpr::KeyValue(..) => enabled.contains(&expr),
+        CfgExpr::Not(inner) => !holds(inner, enabled),
+        CfgExpr::All(exprs) => exprs.iter().all(|e| holds(e, enabled)),
+        CfgExpr::Any(exprs) => exprs.iter().any(|e| holds(e, enabled)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn cfg(text: &str) -> Option<CfgExpr> {
+        Some(CfgExpr::parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_exclusive_and_conflicting_cfgs() {
+        assert_eq!(classify(&[cfg("unix"), cfg("windows")]).0, DuplicateStatus::Exclusive);
+        assert_eq!(classify(&[cfg("feature = \"a\""), cfg("not(feature = \"a\")")]).0, DuplicateStatus::Exclusive);
+        assert_eq!(classify(&[cfg("target_os = \"linux\""), cfg("target_os = \"macos\"")]).0, DuplicateStatus::Exclusive);
+
+        let (status, witness) = classify(&[cfg("feature = \"a\""), cfg("feature = \"b\"")]);
+        assert_eq!(status, DuplicateStatus::Conflict);
+        assert_eq!(witness.as_deref(), Some("feature = \"a\", feature = \"b\""));
+
+        assert_eq!(classify(&[cfg("unix"), None]).0, DuplicateStatus::Conflict);
+        assert_eq!(classify(&[cfg("unix"), cfg("not(windows)")]).0, DuplicateStatus::Conflict);
+    }
+
+    #[test]
+    fn test_scopes_separate_modules_and_skip_bodies() {
+        let file = SourceFile::parse(
+            "fn top() {}
+             mod a { fn inner() {} }
+             impl Foo { fn method() {} }
+             fn body() { fn local() {} }",
+            Edition::CURRENT,
+        )
+        .tree();
+        let scopes: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::Fn::cast)
+            .map(|f| (f.name().unwrap().text().to_string(), item_scope(f.syntax())))
+            .collect();
+
+        assert_eq!(
+            scopes,
+            vec![
+                ("top".to_string(), Some("file".to_string())),
+                ("inner".to_string(), Some("mod a".to_string())),
+                ("method".to_string(), Some("impl Foo".to_string())),
+                ("body".to_string(), Some("file".to_string())),
+                ("local".to_string(), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_enclosing_cfgs_apply_to_items() {
+        let file = SourceFile::parse(
+            "#[cfg(unix)] mod imp { pub fn f() {} }
+             #[cfg(windows)] mod imp { pub fn f() {} }
+             #[cfg(feature = \"a\")] impl Foo { fn g() {} }
+             #[cfg(not(feature = \"a\"))] impl Foo { fn g() {} }
+             mod plain { fn h() {} }
+             mod plain { fn h() {} }",
+            Edition::CURRENT,
+        )
+        .tree();
+        let mut groups: HashMap<(String, String), Vec<Option<CfgExpr>>> = HashMap::new();
+        for item in file.syntax().descendants().filter_map(ast::Fn::cast) {
+            let name = item.name().unwrap().text().to_string();
+            let scope = item_scope(item.syntax()).unwrap();
+            groups.entry((scope, name)).or_default().push(combine(&effective_cfgs(&ast::Item::Fn(item))));
+        }
+
+        // Per-platform and per-feature copies of a scope don't clash
+        assert_eq!(classify(&groups[&("mod imp".to_string(), "f".to_string())]).0, DuplicateStatus::Exclusive);
+        assert_eq!(classify(&groups[&("impl Foo".to_string(), "g".to_string())]).0, DuplicateStatus::Exclusive);
+        assert_eq!(classify(&groups[&("mod plain".to_string(), "h".to_string())]).0, DuplicateStatus::Conflict);
+        assert_eq!(groups[&("mod imp".to_string(), "f".to_string())][0], cfg("unix"));
+    }
+}
@@ -0,0 +1,399 @@
+//! Closure and Fn-trait usage analysis
+//!
+//! Lists the closures inside a function together with what they capture and
+//! which Fn trait rust-analyzer inferred for them, and finds workspace APIs
+//! that accept closures of a given shape.
+
+use super::{normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_syntax::{
+    ast::{self, HasGenericParams, HasName},
+    AstNode,
+};
+
+/// A closure found inside a function body
+#[derive(Debug, Clone)]
+pub struct ClosureInfo {
+    /// Name of the enclosing function
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Parameter list as written, e.g. `|x: &str|`
+    pub params: String,
+    /// Whether the closure is declared with `move`
+    pub is_move: bool,
+    /// Inferred closure kind: `Fn`, `FnMut` or `FnOnce`
+    pub fn_trait: String,
+    pub captures: Vec<CaptureInfo>,
+}
+
+/// A single variable (or place) captured by a closure
+#[derive(Debug, Clone)]
+pub struct CaptureInfo {
+    /// The captured place, e.g. `self.items` or `count`
+    pub place: String,
+    /// How the place is captured: `SharedRef`, `UniqueSharedRef`, `MutableRef` or `Move`
+    pub kind: String,
+}
+
+/// Shape of closure parameter to search for
+#[derive(Debug, Clone, Default)]
+pub struct ClosureShapeQuery {
+    /// Only match this Fn trait (`Fn`, `FnMut` or `FnOnce`)
+    pub fn_trait: Option<String>,
+    /// Only match closures taking exactly this many arguments
+    pub arity: Option<usize>,
+}
+
+/// A function parameter that accepts a closure
+#[derive(Debug, Clone)]
+pub struct ClosureApiInfo {
+    /// Name of the function accepting the closure
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The parameter pattern, e.g. `f`
+    pub parameter: String,
+    /// The closure bound as written, e.g. `FnMut(&str) -> bool`
+    pub shape: String,
+    pub fn_trait: String,
+    pub arity: usize,
+}
+
+impl Analyzer {
+    /// List all closures defined inside the named function
+    ///
+    /// Every function with a matching name is inspected (all files in the
+    /// workspace unless `file_path` narrows the search).
+    pub fn closures_in_function(&self, function: &str, file_path: Option<&str>) -> Result<Vec<ClosureInfo>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut results = Vec::new();
+
+        for (file_id, func) in self.find_functions(&sema, function, file_path)? {
+            let path = self.file_path(file_id);
+
+            for closure in func.syntax().descendants().filter_map(ast::ClosureExpr::cast) {
+                let (start_line, end_line) = self.line_range(file_id, closure.syntax().text_range())?;
+                let params = closure
+                    .param_list()
+                    .map(|p| p.syntax().text().to_string())
+                    .unwrap_or_default();
+
+                // Capture analysis needs type inference; closures in code that
+                // doesn't type check are still listed, just without captures
+                let hir_closure = sema
+                    .type_of_expr(&ast::Expr::ClosureExpr(closure.clone()))
+                    .and_then(|ty| ty.original.as_closure());
+
+                let (fn_trait, captures) = match hir_closure {
+                    Some(c) => (
+                        format!("{:?}", c.fn_trait(db)),
+                        c.captured_items(db)
+                            .into_iter()
+                            .map(|capture| CaptureInfo {
+                                place: capture.display_place(db),
+                                kind: format!("{:?}", capture.kind()),
+                            })
+                            .collect(),
+                    ),
+                    None => ("Unknown".to_string(), Vec::new()),
+                };
+
+                results.push(ClosureInfo {
+                    function: function.to_string(),
+                    file_path: path.clone(),
+                    start_line,
+                    end_line,
+                    params,
+                    is_move: closure.move_token().is_some(),
+                    fn_trait,
+                    captures,
+                });
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Find workspace functions with a parameter that accepts a closure
+    ///
+    /// Closure parameters are recognised through `impl Fn..`, `dyn Fn..` and
+    /// generic parameters bounded by an Fn trait, either inline or in a where
+    /// clause, of the function or of its impl or trait.
+    pub fn find_closure_apis(&self, query: &ClosureShapeQuery) -> Result<Vec<ClosureApiInfo>, AnalyzerError> {
+        let sema = Semantics::new(self.host.raw_database());
+        let mut results = Vec::new();
+
+        for file_id in self.workspace_files()? {
+            let source_file = sema.parse_guess_edition(file_id);
+
+            for func in source_file.syntax().descendants().filter_map(ast::Fn::cast) {
+                let Some(name) = func.name() else { continue };
+                let Some(param_list) = func.param_list() else { continue };
+
+                for param in param_list.params() {
+                    let (Some(pat), Some(ty)) = (param.pat(), param.ty()) else { continue };
+
+                    for bound in closure_bounds(&func, &ty) {
+                        let Some((fn_trait, arity)) = closure_shape(&bound) else { continue };
+                        let shape = normalize_whitespace(&bound.syntax().text().to_string());
+                        if query.fn_trait.as_deref().is_some_and(|t| t != fn_trait) {
+                            continue;
+                        }
+                        if query.arity.is_some_and(|a| a != arity) {
+                            continue;
+                        }
+
+                        let (start_line, end_line) = self.line_range(file_id, func.syntax().text_range())?;
+                        results.push(ClosureApiInfo {
+                            function: name.text().to_string(),
+                            file_path: self.file_path(file_id),
+                            start_line,
+                            end_line,
+                            parameter: pat.syntax().text().to_string(),
+                            shape,
+                            fn_trait: fn_trait.to_string(),
+                            arity,
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(results)
+    }
+}
+
+/// Collect the trait bounds that apply to a parameter type
+///
+/// This covers bounds written directly in the type (`impl Fn()`, `&dyn Fn()`,
+/// `Box<dyn Fn()>`) plus bounds on a generic parameter the type names, also
+/// behind a reference or pointer (`&F`, `&mut F`, `*const F`), whether the
+/// function declares it or the impl or trait it belongs to. Only the
+/// top-level bounds count: a `dyn Fn` in the arguments or return type of
+/// another bound is not a closure the parameter accepts.
+fn closure_bounds(func: &ast::Fn, ty: &ast::Type) -> Vec<ast::TypeBound> {
+    let mut bounds = Vec::new();
+    written_bounds(ty, &mut bounds);
+
+    let mut ty = ty.clone();
+    loop {
+        ty = match ty {
+            ast::Type::RefType(ref_type) => match ref_type.ty() {
+                Some(inner) => inner,
+                None => return bounds,
+            },
+            ast::Type::PtrType(ptr_type) => match ptr_type.ty() {
+                Some(inner) => inner,
+                None => return bounds,
+            },
+            _ => break,
+        };
+    }
+    let ast::Type::PathType(path_type) = ty else { return bounds };
+    let generic_name = path_type.syntax().text().to_string();
+
+    // The parameter may name a generic of the function or of the impl or
+    // trait around it; the innermost declaration shadows the others
+    if generic_bounds(func, &generic_name, &mut bounds) {
+        return bounds;
+    }
+    for ancestor in func.syntax().ancestors().skip(1) {
+        let declared = if let Some(impl_) = ast::Impl::cast(ancestor.clone()) {
+            generic_bounds(&impl_, &generic_name, &mut bounds)
+        } else if let Some(trait_) = ast::Trait::cast(ancestor.clone()) {
+            generic_bounds(&trait_, &generic_name, &mut bounds)
+        } else {
+            // A function nested in another body can't name the outer generics
+            ast::Fn::can_cast(ancestor.kind())
+        };
+        if declared {
+            break;
+        }
+    }
+
+    bounds
+}
+
+/// Add the bounds on the generic parameter `name` declared by an item, from
+/// its generic parameter list and its where clause
+///
+/// Returns whether the item declares the parameter.
+fn generic_bounds(item: &impl HasGenericParams, name: &str, out: &mut Vec<ast::TypeBound>) -> bool {
+    let mut declared = false;
+    if let Some(generics) = item.generic_param_list() {
+        for type_param in generics.syntax().children().filter_map(ast::TypeParam::cast) {
+            if type_param.name().is_some_and(|n| n.text() == name) {
+                declared = true;
+                if let Some(list) = type_param.type_bound_list() {
+                    out.extend(list.bounds());
+                }
+            }
+        }
+    }
+
+    if let Some(where_clause) = item.where_clause() {
+        for pred in where_clause.predicates() {
+            if pred.ty().is_some_and(|t| t.syntax().text() == name) {
+                if let Some(list) = pred.type_bound_list() {
+                    out.extend(list.bounds());
+                }
+            }
+        }
+    }
+    declared
+}
+
+/// Collect the bounds of `impl` and `dyn` types making up a type, looking
+/// through references, pointers, parentheses and generic arguments such as
+/// `Box<..>`, but not into the bounds themselves
+fn written_bounds(ty: &ast::Type, out: &mut Vec<ast::TypeBound>) {
+    match ty {
+        ast::Type::ImplTraitType(it) => out.extend(it.type_bound_list().into_iter().flat_map(|list| list.bounds())),
+        ast::Type::DynTraitType(it) => out.extend(it.type_bound_list().into_iter().flat_map(|list| list.bounds())),
+        ast::Type::RefType(it) => {
+            if let Some(inner) = it.ty() {
+                written_bounds(&inner, out);
+            }
+        }
+        ast::Type::PtrType(it) => {
+            if let Some(inner) = it.ty() {
+                written_bounds(&inner, out);
+            }
+        }
+        ast::Type::ParenType(it) => {
+            if let Some(inner) = it.ty() {
+                written_bounds(&inner, out);
+            }
+        }
+        ast::Type::PathType(it) => {
+            let args = it.path().and_then(|path| path.segment()).and_then(|segment| segment.generic_arg_list());
+            for arg in args.into_iter().flat_map(|list| list.generic_args()) {
+                if let ast::GenericArg::TypeArg(arg) = arg {
+                    if let Some(inner) = arg.ty() {
+                        written_bounds(&inner, out);
+                    }
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Classify a trait bound as a closure bound
+///
+/// Returns the Fn trait name and the number of arguments for bounds like
+/// `FnMut(&str, usize) -> bool` or `for<'a> std::ops::Fn(&'a T)`, and `None`
+/// for anything else.
+fn closure_shape(bound: &ast::TypeBound) -> Option<(&'static str, usize)> {
+    let mut ty = bound.ty()?;
+    // `for<'a> Fn(&'a T)` may parse as a higher-ranked type around the path
+    if let ast::Type::ForType(for_type) = ty {
+        ty = for_type.ty()?;
+    }
+    let ast::Type::PathType(path_type) = ty else { return None };
+    let segment = path_type.path()?.segment()?;
+
+    let fn_trait = match segment.name_ref()?.text().as_str() {
+        "Fn" => "Fn",
+        "FnMut" => "FnMut",
+        "FnOnce" => "FnOnce",
+        _ => return None,
+    };
+    let arity = segment.parenthesized_arg_list()?.type_args().count();
+
+    Some((fn_trait, arity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn param_bounds(src: &str) -> Vec<Vec<String>> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        let func = file.syntax().descendants().find_map(ast::Fn::cast).unwrap();
+        func.param_list()
+            .unwrap()
+            .params()
+            .filter_map(|param| param.ty())
+            .map(|ty| closure_bounds(&func, &ty).iter().map(|b| b.syntax().text().to_string()).collect())
+            .collect()
+    }
+
+    fn shape(bound: &str) -> Option<(&'static str, usize)> {
+        let src = format!("fn f(_: impl {}) {{}}", bound);
+        let file = SourceFile::parse(&src, Edition::CURRENT).tree();
+        closure_shape(&file.syntax().descendants().find_map(ast::TypeBound::cast).unwrap())
+    }
+
+    #[test]
+    fn test_closure_bounds_behind_references() {
+        let bounds = param_bounds("fn f<F: Fn(u8), G>(a: &F, b: &mut G, c: *const F) where G: FnMut() {}");
+        assert_eq!(bounds, vec![vec!["Fn(u8)"], vec!["FnMut()"], vec!["Fn(u8)"]]);
+    }
+
+    #[test]
+    fn test_closure_bounds_of_enclosing_impl_and_trait() {
+        let method_bounds = |src: &str, method: &str| -> Vec<String> {
+            let file = SourceFile::parse(src, Edition::CURRENT).tree();
+            let func = file.syntax().descendants().filter_map(ast::Fn::cast).find(|f| f.name().unwrap().text() == method).unwrap();
+            let ty = func.param_list().unwrap().params().last().unwrap().ty().unwrap();
+            closure_bounds(&func, &ty).iter().map(|b| b.syntax().text().to_string()).collect()
+        };
+
+        let src = "impl<F: Fn(u8)> Handler<F> { fn new(f: F) -> Self { fn nested<F>(f: F) {} todo!() } }";
+        assert_eq!(method_bounds(src, "new"), vec!["Fn(u8)"]);
+        // A nested function's own generic shadows the impl's
+        assert!(method_bounds(src, "nested").is_empty());
+
+        let src = "trait Source<F> where F: FnMut() { fn run(&self, f: &mut F); fn other<F: Fn()>(f: F); }";
+        assert_eq!(method_bounds(src, "run"), vec!["FnMut()"]);
+        assert_eq!(method_bounds(src, "other"), vec!["Fn()"]);
+    }
+
+    #[test]
+    fn test_nested_dyn_fn_is_not_a_bound_of_the_parameter() {
+        let bounds = param_bounds(
+            "fn f<F: Fn(&dyn Fn(u8))>(a: F, b: impl Fn(&dyn Fn(u8)) -> Box<dyn Fn()>, c: Box<dyn FnMut(&dyn Fn())>) {}",
+        );
+        assert_eq!(
+            bounds,
+            vec![vec!["Fn(&dyn Fn(u8))"], vec!["Fn(&dyn Fn(u8)) -> Box<dyn Fn()>"], vec!["FnMut(&dyn Fn())"]]
+        );
+    }
+
+    #[test]
+    fn test_closure_shape_classification() {
+        assert_eq!(shape("Fn()"), Some(("Fn", 0)));
+        assert_eq!(shape("FnMut(&str) -> bool"), Some(("FnMut", 1)));
+        assert_eq!(shape("FnOnce(Vec<(u8, u8)>, usize)"), Some(("FnOnce", 2)));
+        assert_eq!(shape("for<'a> std::ops::Fn(&'a T) -> &'a U"), Some(("Fn", 1)));
+        assert_eq!(shape("Fn(Box<dyn Fn() -> u8>, u8) -> u8"), Some(("Fn", 2)));
+        assert_eq!(shape("Fn(fn(u8) -> u8)"), Some(("Fn", 1)));
+        assert_eq!(shape("Clone"), None);
+        assert_eq!(shape("Into<String>"), None);
+    }
+
+    #[test]
+    fn test_closures_in_function() {
+        let mut analyzer = Analyzer::new();
+        let result = analyzer.load_project(".");
+        assert!(result.is_ok(), "Failed to load project: {:?}", result.err());
+
+        // find_symbol converts navigation targets through a filter_map closure
+        let closures = analyzer.closures_in_function("find_symbol", None);
+        assert!(closures.is_ok(), "Failed to list closures: {:?}", closures.err());
+
+        let closures = closures.unwrap();
+        assert!(!closures.is_empty(), "Should find closures in find_symbol");
+        assert!(closures.iter().any(|c| c.params == "|nav|"), "Should find the |nav| closure: {:?}", closures);
+        for closure in &closures {
+            assert!(["Fn", "FnMut", "FnOnce"].contains(&closure.fn_trait.as_str()), "Unexpected Fn trait: {:?}", closure);
+            assert!(closure.start_line <= closure.end_line);
+        }
+    }
+}
@@ -0,0 +1,415 @@
+//! Orphan rule and overlap pre-check for a proposed trait impl
+//!
+//! Given an impl header such as `impl Display for Config`, reports whether the
+//! orphan rule allows it in the target crate and which existing impls of the
+//! trait it would overlap with. The overlap check unifies impl headers
+//! syntactically: a generic parameter on either side matches anything, so
+//! blanket impls are reported as possible conflicts since their bounds are not
+//! evaluated.
+
+use super::implementations::impl_header;
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, Semantics};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasGenericParams, HasName},
+    AstNode, Edition, SourceFile,
+};
+
+/// Types that are local if their (first) type argument is
+const FUNDAMENTAL_TYPES: &[&str] = &["Box", "Pin"];
+
+/// Built-in types, which are never local
+const PRIMITIVE_TYPES: &[&str] = &[
+    "bool", "char", "str", "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize", "f32", "f64",
+];
+
+/// Outcome of checking a proposed impl
+#[derive(Debug, Clone)]
+pub struct CoherenceCheck {
+    /// Full path of the trait, if it could be resolved
+    pub trait_path: Option<String>,
+    pub self_type: String,
+    /// Whether the orphan rule allows the impl; `None` if a type could not be resolved
+    pub orphan_allowed: Option<bool>,
+    /// Why the orphan rule allows or rejects the impl
+    pub orphan_reason: String,
+    /// Existing impls of the trait the proposed one overlaps with
+    pub conflicts: Vec<ConflictingImpl>,
+}
+
+/// An existing impl overlapping the proposed one
+#[derive(Debug, Clone)]
+pub struct ConflictingImpl {
+    pub header: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Whether the impls overlap for certain, rather than only for some
+    /// instantiations of a generic parameter
+    pub certain: bool,
+}
+
+/// Where a type stands with respect to the orphan rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Locality {
+    Local,
+    Foreign,
+    /// An uncovered type parameter of the impl
+    Param,
+    Unknown,
+}
+
+/// How far two impl headers overlap
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Overlap {
+    None,
+    Possible,
+    Certain,
+}
+
+impl Analyzer {
+    /// Check whether a proposed trait impl would be accepted
+    ///
+    /// `header` is an impl header such as `impl<T> From<T> for Wrapper<T>`; the
+    /// leading `impl` may be left out. The impl is assumed to live in the
+    /// crate containing `file_path`, or in the first workspace crate.
+    pub fn check_coherence(&self, header: &str, file_path: Option<&str>) -> Result<CoherenceCheck, AnalyzerError> {
+        let proposed = parse_impl_header(header)
+            .ok_or_else(|| AnalyzerError::Other(format!("Expected an impl header like `impl Trait for Type`, got: {}", header)))?;
+        let (trait_ty, self_ty) = match (proposed.trait_(), proposed.self_ty()) {
+            (Some(trait_ty), Some(self_ty)) => (trait_ty, self_ty),
+            _ => return Err(AnalyzerError::Other(format!("Not a trait impl: {}", header))),
+        };
+        let trait_path = match &trait_ty {
+            ast::Type::PathType(path) => path.path().map(|p| path_without_generics(&p)),
+            _ => None,
+        }
+        .ok_or_else(|| AnalyzerError::Other(format!("Unsupported trait in impl header: {}", trait_ty.syntax().text())))?;
+
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let krate = self.target_crate(&sema, file_path)?;
+        let is_local = |path: &str| -> Option<bool> {
+            let defs = self.resolve_definitions(&sema, path).ok()?;
+            if defs.is_empty() {
+                return None;
+            }
+            Some(defs.iter().any(|def| def.module(db).is_some_and(|m| m.krate() == krate)))
+        };
+
+        // Orphan rule: the trait is local, or the first of Self and the
+        // trait's type arguments that isn't foreign is a local type
+        let params = type_param_names(&proposed);
+        let mut types = vec![self_ty.clone()];
+        types.extend(type_args(&trait_ty));
+        let localities: Vec<(String, Locality)> = types
+            .iter()
+            .map(|ty| (ty.syntax().text().to_string(), locality(ty, &params, &is_local)))
+            .collect();
+        let (orphan_allowed, orphan_reason) = orphan_verdict(is_local(&trait_path), &localities);
+
+        // Overlap: existing impls of the trait whose headers unify with ours
+        let traits: Vec<_> = self.resolve_definitions(&sema, &trait_path)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Trait(trait_) => Some(trait_),
+                _ => None,
+            })
+            .collect();
+        let resolved_path = traits.first().and_then(|t| definition_key(db, Definition::Trait(*t)));
+
+        let mut conflicts = Vec::new();
+        for trait_ in traits {
+            for existing in ra_ap_hir::Impl::all_for_trait(db, trait_) {
+                let Some(source) = sema.source(existing) else { continue };
+                let overlap = header_overlap(&proposed, &source.value);
+                if overlap == Overlap::None {
+                    continue;
+                }
+
+                let range = sema.original_range(source.value.syntax());
+                let file_id = range.file_id.file_id(db);
+                let (start_line, end_line) = self.line_range(file_id, range.range)?;
+                conflicts.push(ConflictingImpl {
+                    header: impl_header(&source.value),
+                    file_path: self.file_path(file_id),
+                    start_line,
+                    end_line,
+                    certain: overlap == Overlap::Certain,
+                });
+            }
+        }
+
+        Ok(CoherenceCheck {
+            trait_path: resolved_path,
+            self_type: self_ty.syntax().text().to_string(),
+            orphan_allowed,
+            orphan_reason,
+            conflicts,
+        })
+    }
+
+    /// The crate an impl would be added to
+    fn target_crate(&self, sema: &Semantics<'_, RootDatabase>, file_path: Option<&str>) -> Result<Crate, AnalyzerError> {
+        let db = sema.db;
+        let krate = match file_path {
+            Some(path) => sema.file_to_module_def(self.file_id(path)?).map(|m| m.krate()),
+            None => Crate::all(db).into_iter().find(|krate| krate.origin(db).is_local()),
+        };
+        krate.ok_or_else(|| AnalyzerError::Other("No workspace crate to check the impl against".to_string()))
+    }
+}
+
+fn parse_impl_header(header: &str) -> Option<ast::Impl> {
+    let header = header.trim().trim_end_matches(';');
+    let header = header.strip_suffix("{}").unwrap_or(header).trim();
+    let source = if header.starts_with("impl ") || header.starts_with("impl<") {
+        format!("{} {{}}", header)
+    } else {
+        format!("impl {} {{}}", header)
+    };
+    SourceFile::parse(&source, Edition::CURRENT).tree().syntax().descendants().find_map(ast::Impl::cast)
+}
+
+/// `std::fmt::Display` for `std::fmt::Display`, `From` for `From<T>`
+fn path_without_generics(path: &ast::Path) -> String {
+    path.segments()
+        .filter_map(|segment| segment.name_ref())
+        .map(|name| name.text().to_string())
+        .collect::<Vec<_>>()
+        .join("::")
+}
+
+fn type_param_names(impl_: &ast::Impl) -> Vec<String> {
+    impl_
+        .generic_param_list()
+        .into_iter()
+        .flat_map(|list| list.generic_params())
+        .filter_map(|param| match param {
+            ast::GenericParam::TypeParam(it) => it.name().map(|n| n.text().to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Type arguments of the last segment of a path type
+fn type_args(ty: &ast::Type) -> Vec<ast::Type> {
+    let ast::Type::PathType(path) = ty else { return Vec::new() };
+    path.path()
+        .and_then(|p| p.segment())
+        .and_then(|segment| segment.generic_arg_list())
+        .into_iter()
+        .flat_map(|list| list.generic_args())
+        .filter_map(|arg| match arg {
+            ast::GenericArg::TypeArg(it) => it.ty(),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Name of a single-segment path type, e.g. `T`
+fn simple_name(ty: &ast::Type) -> Option<String> {
+    let ast::Type::PathType(path) = ty else { return None };
+    let path = path.path()?;
+    if path.qualifier().is_some() {
+        return None;
+    }
+    Some(path.segment()?.name_ref()?.text().to_string())
+}
+
+fn locality(ty: &ast::Type, params: &[String], is_local: &impl Fn(&str) -> Option<bool>) -> Locality {
+    match ty {
+        // References are fundamental: `&Local` counts as local
+        ast::Type::RefType(it) => it.ty().map_or(Locality::Unknown, |inner| locality(&inner, params, is_local)),
+        ast::Type::PathType(it) => {
+            let Some(path) = it.path() else { return Locality::Unknown };
+            let name = path.segment().and_then(|s| s.name_ref()).map(|n| n.text().to_string()).unwrap_or_default();
+
+            if simple_name(ty).is_some_and(|n| params.contains(&n)) {
+                return Locality::Param;
+            }
+            if PRIMITIVE_TYPES.contains(&name.as_str()) && path.qualifier().is_none() {
+                return Locality::Foreign;
+            }
+            if FUNDAMENTAL_TYPES.contains(&name.as_str()) {
+                if let Some(inner) = type_args(ty).first() {
+                    return locality(inner, params, is_local);
+                }
+            }
+            match is_local(&path_without_generics(&path)) {
+                Some(true) => Locality::Local,
+                Some(false) => Locality::Foreign,
+                None => Locality::Unknown,
+            }
+        }
+        ast::Type::DynTraitType(it) => {
+            let trait_path = it
+                .type_bound_list()
+                .and_then(|bounds| bounds.bounds().find_map(|b| b.ty()))
+                .and_then(|ty| match ty {
+                    ast::Type::PathType(p) => p.path(),
+                    _ => None,
+                });
+            match trait_path.and_then(|p| is_local(&path_without_generics(&p))) {
+                Some(true) => Locality::Local,
+                Some(false) => Locality::Foreign,
+                None => Locality::Unknown,
+            }
+        }
+        // Tuples, slices, arrays, pointers and fn pointers are never local
+        _ => Locality::Foreign,
+    }
+}
+
+/// Apply the orphan rule to the trait and the types in order (Self first)
+fn orphan_verdict(trait_local: Option<bool>, types: &[(String, Locality)]) -> (Option<bool>, String) {
+    if trait_local == Some(true) {
+        return (Some(true), "The trait is defined in this crate".to_string());
+    }
+
+    for (ty, locality) in types {
+        match locality {
+            Locality::Local => return (Some(true), format!("`{}` is a local type", ty)),
+            Locality::Param => {
+                return (
+                    Some(false),
+                    format!(
+                        "E0210: type parameter `{}` must be covered by a local type when implementing a foreign trait",
+                        ty
+                    ),
+                )
+            }
+            Locality::Unknown => return (None, format!("Could not resolve `{}`", ty)),
+            Locality::Foreign => {}
+        }
+    }
+
+    match trait_local {
+        Some(_) => (
+            Some(false),
+            "E0117: only traits defined in the current crate can be implemented for types defined outside of it".to_string(),
+        ),
+        None => (None, "Could not resolve the trait".to_string()),
+    }
+}
+
+/// How far two impls of the same trait overlap
+fn header_overlap(a: &ast::Impl, b: &ast::Impl) -> Overlap {
+    let (a_params, b_params) = (type_param_names(a), type_param_names(b));
+
+    let (Some(a_self), Some(b_self)) = (a.self_ty(), b.self_ty()) else { return Overlap::None };
+    let mut overlap = unify(&a_self, &a_params, &b_self, &b_params);
+
+    let a_args = a.trait_().map(|t| type_args(&t)).unwrap_or_default();
+    let b_args = b.trait_().map(|t| type_args(&t)).unwrap_or_default();
+    if a_args.len() != b_args.len() {
+        // Defaulted type arguments (e.g. `PartialEq` vs `PartialEq<Rhs>`)
+        overlap = overlap.min(Overlap::Possible);
+    }
+    for (a_arg, b_arg) in a_args.iter().zip(&b_args) {
+        overlap = overlap.min(unify(a_arg, &a_params, b_arg, &b_params));
+    }
+    overlap
+}
+
+/// Whether two types, each with its own impl's type parameters, can be equal
+fn unify(a: &ast::Type, a_params: &[String], b: &ast::Type, b_params: &[String]) -> Overlap {
+    let is_param = |ty: &ast::Type, params: &[String]| simple_name(ty).is_some_and(|n| params.contains(&n));
+    if is_param(a, a_params) || is_param(b, b_params) {
+        return Overlap::Possible;
+    }
+
+    match (a, b) {
+        (ast::Type::InferType(_), _) | (_, ast::Type::InferType(_)) => Overlap::Possible,
+        (ast::Type::RefType(x), ast::Type::RefType(y)) => {
+            if x.mut_token().is_some() != y.mut_token().is_some() {
+                return Overlap::None;
+            }
+            match (x.ty(), y.ty()) {
+                (Some(x), Some(y)) => unify(&x, a_params, &y, b_params),
+                _ => Overlap::Possible,
+            }
+        }
+        (ast::Type::PathType(x), ast::Type::PathType(y)) => {
+            let last = |p: &ast::PathType| p.path().and_then(|p| p.segment()).and_then(|s| s.name_ref()).map(|n| n.text().to_string());
+            if last(x) != last(y) {
+                return Overlap::None;
+            }
+            let (x_args, y_args) = (type_args(a), type_args(b));
+            if x_args.len() != y_args.len() {
+                return Overlap::Possible;
+            }
+            x_args
+                .iter()
+                .zip(&y_args)
+                .map(|(x, y)| unify(x, a_params, y, b_params))
+                .min()
+                .unwrap_or(Overlap::Certain)
+        }
+        (ast::Type::TupleType(x), ast::Type::TupleType(y)) => {
+            let (x, y): (Vec<_>, Vec<_>) = (x.fields().collect(), y.fields().collect());
+            if x.len() != y.len() {
+                return Overlap::None;
+            }
+            x.iter().zip(&y).map(|(x, y)| unify(x, a_params, y, b_params)).min().unwrap_or(Overlap::Certain)
+        }
+        (ast::Type::SliceType(x), ast::Type::SliceType(y)) => match (x.ty(), y.ty()) {
+            (Some(x), Some(y)) => unify(&x, a_params, &y, b_params),
+            _ => Overlap::Possible,
+        },
+        _ => {
+            let text = |ty: &ast::Type| ty.syntax().text().to_string().split_whitespace().collect::<String>();
+            match text(a) == text(b) {
+                true => Overlap::Certain,
+                false => Overlap::None,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overlap(a: &str, b: &str) -> Overlap {
+        header_overlap(&parse_impl_header(a).unwrap(), &parse_impl_header(b).unwrap())
+    }
+
+    #[test]
+    fn test_header_overlap() {
+        assert_eq!(overlap("Display for Config", "impl Display for Config"), Overlap::Certain);
+        assert_eq!(overlap("Display for Config", "impl Display for Other"), Overlap::None);
+        assert_eq!(overlap("ToString for Config", "impl<T: Display> ToString for T"), Overlap::Possible);
+        assert_eq!(overlap("From<u32> for Id", "impl From<u64> for Id"), Overlap::None);
+        assert_eq!(overlap("From<Vec<u8>> for Id", "impl<T> From<Vec<T>> for Id"), Overlap::Possible);
+        assert_eq!(overlap("Trait for &mut Config", "impl Trait for &Config"), Overlap::None);
+    }
+
+    #[test]
+    fn test_orphan_verdict() {
+        let local = |path: &str| match path {
+            "Config" => Some(true),
+            "Display" | "Vec" | "From" => Some(false),
+            _ => None,
+        };
+        let verdict = |header: &str, trait_local: Option<bool>| {
+            let impl_ = parse_impl_header(header).unwrap();
+            let params = type_param_names(&impl_);
+            let mut types = vec![impl_.self_ty().unwrap()];
+            types.extend(type_args(&impl_.trait_().unwrap()));
+            let localities: Vec<_> = types.iter().map(|ty| (ty.syntax().text().to_string(), locality(ty, &params, &local))).collect();
+            orphan_verdict(trait_local, &localities).0
+        };
+
+        assert_eq!(verdict("Display for Config", Some(false)), Some(true));
+        assert_eq!(verdict("Display for Box<Config>", Some(false)), Some(true));
+        assert_eq!(verdict("Display for Vec<Config>", Some(false)), Some(false));
+        assert_eq!(verdict("From<Config> for Vec<u8>", Some(false)), Some(true));
+        assert_eq!(verdict("impl<T> From<Config> for T", Some(false)), Some(false));
+        assert_eq!(verdict("Display for (Config, u8)", Some(false)), Some(false));
+        assert_eq!(verdict("Display for u32", Some(true)), Some(true));
+    }
+}
@@ -0,0 +1,181 @@
+//! Search for constants and statics by value
+//!
+//! Finds the named constant behind a magic number: every `const` and `static`
+//! in the workspace is evaluated with rust-analyzer's const evaluator and
+//! matched against an exact value, a numeric range, or both, optionally
+//! narrowed by name (e.g. all `*TIMEOUT*` constants between 1000 and 60000).
+
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode,
+};
+
+/// What to look for; unset fields match anything
+#[derive(Debug, Clone, Default)]
+pub struct ConstantQuery {
+    /// Exact value, compared numerically for integers ("4096", "0x1000")
+    /// and textually otherwise ("\"utf-8\"", "true")
+    pub value: Option<String>,
+    /// Smallest accepted integer value
+    pub min: Option<i128>,
+    /// Largest accepted integer value
+    pub max: Option<i128>,
+    /// Case-insensitive substring of the name, e.g. "timeout"
+    pub name_contains: Option<String>,
+}
+
+/// A constant or static with its evaluated value
+#[derive(Debug, Clone)]
+pub struct ConstantInfo {
+    pub name: String,
+    /// "const" or "static"
+    pub kind: &'static str,
+    /// The type as written in the declaration
+    pub ty: String,
+    /// The evaluated value, or the initializer as written if evaluation failed
+    pub value: String,
+    /// The value as an integer, when it is one
+    pub integer: Option<i128>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl Analyzer {
+    /// Find workspace constants and statics whose value matches the query
+    pub fn find_constants(&self, query: &ConstantQuery) -> Result<Vec<ConstantInfo>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let wanted_integer = query.value.as_deref().and_then(parse_integer);
+        let name_contains = query.name_contains.as_deref().map(str::to_lowercase);
+        let mut constants = Vec::new();
+
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            for node in source.syntax().descendants() {
+                let (name, kind, ty, evaluated, initializer) = if let Some(konst) = ast::Const::cast(node.clone()) {
+                    let Some(name) = konst.name() else { continue };
+                    let def = sema.to_def(&konst);
+                    let evaluated = def.and_then(|c| {
+                        let target = c.module(db).krate().to_display_target(db);
+                        c.eval(db).ok().map(|value| value.render(db, target))
+                    });
+                    let ty = def.map(|c| display_type(db, &c.ty(db), c.module(db).krate()));
+                    (name, "const", ty, evaluated, konst.body())
+                } else if let Some(statik) = ast::Static::cast(node.clone()) {
+                    let Some(name) = statik.name() else { continue };
+                    let def = sema.to_def(&statik);
+                    let evaluated = def.and_then(|s| {
+                        let target = s.module(db).krate().to_display_target(db);
+                        s.eval(db).ok().map(|value| value.render(db, target))
+                    });
+                    let ty = def.map(|s| display_type(db, &s.ty(db), s.module(db).krate()));
+                    (name, "static", ty, evaluated, statik.body())
+                } else {
+                    continue;
+                };
+
+                let name = name.text().to_string();
+                if name_contains.as_ref().is_some_and(|needle| !name.to_lowercase().contains(needle)) {
+                    continue;
+                }
+                let Some(value) = evaluated.or_else(|| initializer.map(|e| e.syntax().text().to_string())) else { continue };
+                let integer = parse_integer(&value);
+                if !value_matches(query, wanted_integer, &value, integer) {
+                    continue;
+                }
+
+                let (start_line, end_line) = self.line_range(file_id, node.text_range())?;
+                constants.push(ConstantInfo {
+                    name,
+                    kind,
+                    ty: ty.unwrap_or_default(),
+                    value,
+                    integer,
+                    file_path: self.file_path(file_id),
+                    start_line,
+                    end_line,
+                });
+            }
+        }
+
+        Ok(constants)
+    }
+}
+
+fn value_matches(query: &ConstantQuery, wanted_integer: Option<i128>, value: &str, integer: Option<i128>) -> bool {
+    let exact = match (&query.value, wanted_integer) {
+        (None, _) => true,
+        (Some(_), Some(wanted)) => integer == Some(wanted),
+        (Some(wanted), None) => value.trim() == wanted.trim(),
+    };
+    let in_range = (query.min.is_none() && query.max.is_none())
+        || integer.is_some_and(|n| query.min.is_none_or(|min| n >= min) && query.max.is_none_or(|max| n <= max));
+    exact && in_range
+}
+
+/// Parse an integer as rendered by the evaluator ("4096 (0x1000)") or
+/// written in source ("4_096", "0x1000usize", "-1")
+fn parse_integer(text: &str) -> Option<i128> {
+    let token = text.split_whitespace().next()?;
+    let (negative, digits) = match token.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, token),
+    };
+    let digits = digits.replace('_', "");
+    let (radix, digits) = match digits.get(..2) {
+        Some("0x") => (16, &digits[2..]),
+        Some("0o") => (8, &digits[2..]),
+        Some("0b") => (2, &digits[2..]),
+        _ => (10, digits.as_str()),
+    };
+    // Drop a type suffix such as `u64` (hex digits may contain letters, so
+    // only strip from the first `u` or `i`)
+    let digits = digits.split(['u', 'i']).next()?;
+    let value = i128::from_str_radix(digits, radix).ok()?;
+    Some(if negative { -value } else { value })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_integer() {
+        assert_eq!(parse_integer("4096"), Some(4096));
+        assert_eq!(parse_integer("4096 (0x1000)"), Some(4096));
+        assert_eq!(parse_integer("0x1000usize"), Some(4096));
+        assert_eq!(parse_integer("4_096u32"), Some(4096));
+        assert_eq!(parse_integer("-1"), Some(-1));
+        assert_eq!(parse_integer("0b1010"), Some(10));
+        assert_eq!(parse_integer("\"text\""), None);
+        assert_eq!(parse_integer("1.5"), None);
+    }
+
+    #[test]
+    fn test_value_and_range_matching() {
+        let by_value = ConstantQuery { value: Some("0x20".to_string()), ..Default::default() };
+        assert!(value_matches(&by_value, parse_integer("0x20"), "32", Some(32)));
+        assert!(!value_matches(&by_value, parse_integer("0x20"), "33", Some(33)));
+
+        let by_range = ConstantQuery { min: Some(1000), max: Some(60_000), ..Default::default() };
+        assert!(value_matches(&by_range, None, "5000", Some(5000)));
+        assert!(!value_matches(&by_range, None, "\"5000\"", None));
+    }
+
+    #[test]
+    fn test_find_constants_by_value() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let query = ConstantQuery { value: Some("1024".to_string()), ..Default::default() };
+        let found = analyzer.find_constants(&query).unwrap();
+        assert!(
+            found.iter().any(|c| c.name == "DEFINITION_SEARCH_LIMIT"),
+            "DEFINITION_SEARCH_LIMIT is 1024: {:?}",
+            found
+        );
+    }
+}
@@ -0,0 +1,136 @@
+//! Conversions to and from a type
+//!
+//! Answers "how do I get a `Foo` from a `Bar`" from impl data: every
+//! `From`, `Into`, `TryFrom`, `TryInto` and `AsRef` impl with the type on
+//! either side, plus `Deref` impls whose target or implementor it is. Impls in
+//! dependencies and the standard library are included, since conversions
+//! such as `From<Foo> for String` are as useful as the crate's own.
+
+use super::reference_index::definition_key;
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, AssocItem, Impl, Semantics, Type};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::AstNode;
+
+/// Conversion traits, as paths `resolve_definitions` can find
+const CONVERSION_TRAITS: &[&str] =
+    &["convert::From", "convert::Into", "convert::TryFrom", "convert::TryInto", "convert::AsRef", "ops::Deref"];
+
+/// One way to convert between two types
+#[derive(Debug, Clone)]
+pub struct Conversion {
+    /// The trait providing it, e.g. "From" or "Deref"
+    pub trait_name: String,
+    /// Source type of the conversion
+    pub from: String,
+    /// Target type; a reference for `AsRef` and `Deref`
+    pub to: String,
+    /// Whether the conversion can fail (`TryFrom`/`TryInto`)
+    pub fallible: bool,
+    /// Whether the queried type is the source ("into") or the target ("from")
+    pub direction: &'static str,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl Analyzer {
+    /// List the conversion impls involving a type on either side
+    pub fn find_conversions(&self, type_name: &str) -> Result<Vec<Conversion>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let adts: Vec<Adt> = self.resolve_definitions(&sema, type_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Adt(adt) => Some(adt),
+                _ => None,
+            })
+            .collect();
+        if adts.is_empty() {
+            return Err(AnalyzerError::Other(format!("No type named '{}' found", type_name)));
+        }
+        self.require_unique(&sema, type_name, &adts.iter().map(|&a| Definition::Adt(a)).collect::<Vec<_>>())?;
+        let is_queried = |ty: &Type| ty.strip_references().as_adt().is_some_and(|adt| adts.contains(&adt));
+
+        let mut conversions = Vec::new();
+        for path in CONVERSION_TRAITS {
+            let trait_name = path.rsplit("::").next().unwrap_or(path);
+            let traits = self.resolve_definitions(&sema, path)?.into_iter().filter_map(|def| match def {
+                // Only the standard library's traits, not same-named local ones
+                Definition::Trait(trait_) if definition_key(db, def).is_some_and(|key| key.starts_with("core::")) => Some(trait_),
+                _ => None,
+            });
+
+            for trait_ in traits {
+                for impl_ in Impl::all_for_trait(db, trait_) {
+                    let krate = impl_.module(db).krate();
+                    let self_ty = impl_.self_ty(db);
+                    let other = match trait_name {
+                        "Deref" => assoc_type(db, impl_, "Target"),
+                        _ => impl_.trait_ref(db).and_then(|trait_ref| trait_ref.get_type_argument(1)),
+                    };
+                    let Some(other) = other else { continue };
+                    if !is_queried(&self_ty) && !is_queried(&other) {
+                        continue;
+                    }
+
+                    // `From<A> for B` and `TryFrom` go from A to B; the others from Self
+                    let (from, to) = match trait_name {
+                        "From" | "TryFrom" => (&other, &self_ty),
+                        _ => (&self_ty, &other),
+                    };
+                    let borrowed = matches!(trait_name, "AsRef" | "Deref");
+                    let to_text = display_type(db, to, krate);
+
+                    let Some(source) = sema.source(impl_) else { continue };
+                    let range = sema.original_range(source.value.syntax());
+                    let file_id = range.file_id.file_id(db);
+                    let (start_line, end_line) = self.line_range(file_id, range.range)?;
+
+                    conversions.push(Conversion {
+                        trait_name: trait_name.to_string(),
+                        from: display_type(db, from, krate),
+                        to: if borrowed { format!("&{}", to_text) } else { to_text },
+                        fallible: trait_name.starts_with("Try"),
+                        direction: if is_queried(from) { "into" } else { "from" },
+                        file_path: self.file_path(file_id),
+                        start_line,
+                        end_line,
+                    });
+                }
+            }
+        }
+
+        Ok(conversions)
+    }
+}
+
+/// An associated type of an impl, such as the `Target` of a `Deref` impl
+pub(super) fn assoc_type(db: &RootDatabase, impl_: Impl, name: &str) -> Option<Type> {
+    impl_.items(db).into_iter().find_map(|item| match item {
+        AssocItem::TypeAlias(alias) if alias.name(db).as_str() == name => Some(alias.ty(db)),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions_of_analyzer_error() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let conversions = analyzer.find_conversions("AnalyzerError").unwrap();
+        let from_io = conversions
+            .iter()
+            .find(|c| c.trait_name == "From" && c.to == "AnalyzerError")
+            .expect("AnalyzerError converts from io::Error");
+        assert!(from_io.from.ends_with("Error"), "Converts from {}", from_io.from);
+        assert_eq!(from_io.direction, "from");
+        assert!(!from_io.fallible);
+    }
+}
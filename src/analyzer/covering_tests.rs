@@ -0,0 +1,171 @@
+//! Tests exercising a function
+//!
+//! After changing a function, the tests worth running first are those that
+//! call it, directly or through helpers. This walks the call hierarchy
+//! upwards from the function, breadth first and to a configurable depth,
+//! and keeps the callers that are test functions (`#[test]`, or harness
+//! attributes such as `#[tokio::test]`), each with the chain of calls that
+//! reaches the function and the cargo command running it.
+//!
+//! Calls the call hierarchy can't see, such as through trait objects or
+//! function pointers, aren't followed.
+
+use super::entry_points::attribute_entry;
+use super::reference_index::definition_key;
+use super::runnables::Runnable;
+use super::{Analyzer, AnalyzerError, Budget};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{CallHierarchyConfig, FilePosition};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{ast, AstNode};
+use std::collections::HashSet;
+
+/// Functions visited at most, so a widely used function can't make the walk unbounded
+const MAX_VISITED: usize = 2000;
+
+/// A test calling the function
+#[derive(Debug, Clone)]
+pub struct CoveringTest {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Number of calls between the test and the function; 1 for a direct call
+    pub depth: usize,
+    /// Names of the functions along the chain, from the test down to the function
+    pub chain: Vec<String>,
+    /// The test as listed by `runnables`, with the command running just it
+    pub runnable: Option<Runnable>,
+}
+
+/// The tests reaching a function
+#[derive(Debug, Clone)]
+pub struct CoveringTests {
+    /// Qualified path of the function
+    pub function: String,
+    /// Tests by depth, then by file and line
+    pub tests: Vec<CoveringTest>,
+    /// Whether the walk stopped at `MAX_VISITED` functions or when the budget
+    /// ran out, so tests may be missing
+    pub truncated: bool,
+}
+
+impl Analyzer {
+    /// Find the tests calling the function matching a name, qualified path or
+    /// symbol_id, through at most `max_depth` calls, stopping early when the
+    /// budget runs out
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several functions match.
+    pub fn covering_tests(&self, name: &str, max_depth: usize, budget: Budget) -> Result<CoveringTests, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let config = CallHierarchyConfig { exclude_tests: false };
+
+        let functions: Vec<_> = self
+            .definition_positions(&sema, name)?
+            .into_iter()
+            .filter(|(def, _)| matches!(def, Definition::Function(_)))
+            .collect();
+        let Some(&(def, position)) = functions.first() else {
+            return Err(AnalyzerError::Other(format!("No function named '{}' found", name)));
+        };
+        self.require_unique(&sema, name, &functions.iter().map(|(def, _)| *def).collect::<Vec<_>>())?;
+        let function_name = name.rsplit("::").next().unwrap_or(name).to_string();
+
+        let mut visited: HashSet<FilePosition> = HashSet::from([position]);
+        // Each function of the frontier with the chain from it down to the function
+        let mut frontier: Vec<(FilePosition, Vec<String>)> = vec![(position, vec![function_name])];
+        let mut found = Vec::new();
+        let mut truncated = false;
+
+        'walk: for depth in 1..=max_depth {
+            let mut next = Vec::new();
+            for (position, chain) in &frontier {
+                if budget.exhausted() {
+                    truncated = true;
+                    break 'walk;
+                }
+                let calls = analysis.incoming_calls(&config, *position).map_err(|_| AnalyzerError::Canceled)?.unwrap_or_default();
+                for call in calls {
+                    let nav = call.target;
+                    let caller = FilePosition { file_id: nav.file_id, offset: nav.focus_or_full_range().start() };
+                    if visited.len() >= MAX_VISITED {
+                        truncated = true;
+                        break 'walk;
+                    }
+                    if !visited.insert(caller) {
+                        continue;
+                    }
+                    let chain: Vec<String> = std::iter::once(nav.name.to_string()).chain(chain.iter().cloned()).collect();
+
+                    let source = sema.parse_guess_edition(nav.file_id);
+                    let is_test = source
+                        .syntax()
+                        .token_at_offset(caller.offset)
+                        .right_biased()
+                        .and_then(|token| token.parent_ancestors().find_map(ast::Fn::cast))
+                        .is_some_and(|func| attribute_entry(&ast::Item::Fn(func)) == Some("test"));
+                    if is_test {
+                        let (start_line, end_line) = self.line_range(nav.file_id, nav.full_range)?;
+                        found.push((nav.file_id, CoveringTest {
+                            name: nav.name.to_string(),
+                            file_path: self.file_path(nav.file_id),
+                            start_line,
+                            end_line,
+                            depth,
+                            chain,
+                            runnable: None,
+                        }));
+                    } else {
+                        next.push((caller, chain));
+                    }
+                }
+            }
+            frontier = next;
+            if frontier.is_empty() {
+                break;
+            }
+        }
+
+        let mut tests = Vec::new();
+        for (file_id, mut test) in found {
+            test.runnable = self
+                .runnables_in(file_id)?
+                .into_iter()
+                .find(|r| r.kind == "test" && r.start_line <= test.start_line && test.end_line <= r.end_line);
+            tests.push(test);
+        }
+        tests.sort_by(|a, b| (a.depth, &a.file_path, a.start_line).cmp(&(b.depth, &b.file_path, b.start_line)));
+
+        Ok(CoveringTests {
+            function: definition_key(db, def).unwrap_or_else(|| name.to_string()),
+            tests,
+            truncated,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_covering_tests_direct_and_transitive() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Tests call enum_match_sites directly
+        let direct = analyzer.covering_tests("Analyzer::enum_match_sites", 1, Budget::unlimited()).unwrap();
+        assert!(direct.tests.iter().all(|t| t.depth == 1 && t.chain.len() == 2));
+        assert!(!direct.tests.is_empty());
+        assert!(direct.tests.iter().all(|t| t.runnable.as_ref().and_then(|r| r.command_line()).is_some_and(|c| c.ends_with("--exact"))));
+
+        // Reached through load_project's callees at greater depth
+        let transitive = analyzer.covering_tests("Analyzer::workspace_files", 3, Budget::unlimited()).unwrap();
+        assert!(transitive.tests.iter().any(|t| t.depth > 1));
+        assert!(transitive.tests.windows(2).all(|w| w[0].depth <= w[1].depth));
+
+        assert!(analyzer.covering_tests("no_such_function", 3, Budget::unlimited()).is_err());
+    }
+}
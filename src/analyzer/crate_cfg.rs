@@ -0,0 +1,110 @@
+//! The cfg each crate was loaded with
+//!
+//! Which items exist depends on the cfg options a crate is analyzed with:
+//! its enabled Cargo features, `test`, `debug_assertions`, the target's
+//! `unix` or `target_os = "linux"`. A symbol missing from results because
+//! it sits behind a disabled `#[cfg(feature = "...")]` is confusing unless
+//! the options are known. These come from the crate graph, so they are what
+//! rust-analyzer used, not what a particular `cargo build` would.
+
+use super::feature_api::feature_names;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Crate;
+
+/// The cfg options of one crate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrateCfg {
+    pub crate_name: String,
+    pub version: Option<String>,
+    /// Whether the crate is a workspace member
+    pub workspace: bool,
+    /// Enabled Cargo features, implied ones included
+    pub enabled_features: Vec<String>,
+    /// Declared features that are not enabled
+    pub disabled_features: Vec<String>,
+    /// Enabled cfg names, e.g. `test`, `unix` or `debug_assertions`
+    pub flags: Vec<String>,
+    /// Enabled `key = "value"` options other than features, e.g. `target_os = "linux"`
+    pub key_values: Vec<String>,
+}
+
+impl Analyzer {
+    /// The cfg options of each workspace crate, or of `crate_name`; with
+    /// `include_library`, of library crates too
+    ///
+    /// Workspace crates come first, then by name and version. Targets of one
+    /// package loaded with the same options are listed once.
+    pub fn crate_cfg(&self, crate_name: Option<&str>, include_library: bool) -> Result<Vec<CrateCfg>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let mut crates: Vec<CrateCfg> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| include_library || crate_name.is_some() || krate.origin(db).is_local())
+            .filter_map(|krate| {
+                let name = krate.display_name(db)?.to_string();
+                if crate_name.is_some_and(|wanted| wanted.replace('-', "_") != name.replace('-', "_")) {
+                    return None;
+                }
+                let cfg = krate.cfg(db);
+                let enabled_features = feature_names(cfg.get_cfg_values("feature"));
+                let disabled_features = feature_names(krate.potential_cfg(db).get_cfg_values("feature"))
+                    .into_iter()
+                    .filter(|feature| !enabled_features.contains(feature))
+                    .collect();
+
+                let mut keys: Vec<String> = cfg.get_cfg_keys().map(|key| key.to_string()).collect();
+                keys.sort();
+                keys.dedup();
+                let (mut flags, mut key_values) = (Vec::new(), Vec::new());
+                for key in keys.into_iter().filter(|key| key != "feature") {
+                    let values = feature_names(cfg.get_cfg_values(&key));
+                    if values.is_empty() {
+                        flags.push(key);
+                    } else {
+                        key_values.extend(values.into_iter().map(|value| format!("{} = \"{}\"", key, value)));
+                    }
+                }
+
+                Some(CrateCfg {
+                    crate_name: name,
+                    version: krate.version(db),
+                    workspace: krate.origin(db).is_local(),
+                    enabled_features,
+                    disabled_features,
+                    flags,
+                    key_values,
+                })
+            })
+            .collect();
+        if crates.is_empty() {
+            if let Some(crate_name) = crate_name {
+                return Err(AnalyzerError::Other(format!("No crate named '{}'", crate_name)));
+            }
+        }
+
+        crates.sort_by(|a, b| (!a.workspace, &a.crate_name, &a.version).cmp(&(!b.workspace, &b.crate_name, &b.version)));
+        crates.dedup();
+        Ok(crates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crate_cfg_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let crates = analyzer.crate_cfg(None, false).unwrap();
+        assert!(crates.iter().all(|c| c.workspace));
+        let this = crates.iter().find(|c| c.crate_name == "cratographer").unwrap();
+        assert!(!this.flags.iter().any(|flag| flag == "feature"));
+        assert!(this.key_values.iter().all(|kv| !kv.starts_with("feature")));
+
+        // Libraries are listed when asked for, or by name
+        let core = analyzer.crate_cfg(Some("core"), false).unwrap();
+        assert!(core.iter().all(|c| c.crate_name == "core" && !c.workspace));
+        assert!(analyzer.crate_cfg(Some("no_such_crate"), false).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! The loaded crate graph
+//!
+//! Every crate rust-analyzer loaded for the workspace, with its edition,
+//! where it comes from and the crates it depends on under which names. The
+//! workspace crates alone give the architectural overview; with libraries,
+//! the whole graph down to `core` is listed.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Crate;
+
+/// A dependency edge, as the depending crate sees it
+#[derive(Debug, Clone)]
+pub struct CrateDependency {
+    /// The name the dependency is imported under, e.g. `serde_json`
+    pub name: String,
+    /// The dependency's own crate name
+    pub crate_name: String,
+    pub version: Option<String>,
+}
+
+/// A crate of the graph
+#[derive(Debug, Clone)]
+pub struct CrateNode {
+    pub name: String,
+    pub version: Option<String>,
+    pub edition: String,
+    /// "workspace", "library", "lang" for the standard library crates, or "rustc"
+    pub origin: &'static str,
+    pub root_file: String,
+    pub dependencies: Vec<CrateDependency>,
+}
+
+impl Analyzer {
+    /// The workspace crates and their dependency edges; with
+    /// `include_library`, the library crates too
+    ///
+    /// Workspace crates come first, then by name and version.
+    pub fn crate_graph(&self, include_library: bool) -> Result<Vec<CrateNode>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let name_of = |krate: Crate| krate.display_name(db).map(|name| name.to_string()).unwrap_or_else(|| "unnamed".to_string());
+
+        let mut nodes: Vec<CrateNode> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| include_library || krate.origin(db).is_local())
+            .map(|krate| {
+                let origin = krate.origin(db);
+                let origin = if origin.is_local() {
+                    "workspace"
+                } else if origin.is_lang() {
+                    "lang"
+                } else if origin.is_lib() {
+                    "library"
+                } else {
+                    "rustc"
+                };
+                let mut dependencies: Vec<CrateDependency> = krate
+                    .dependencies(db)
+                    .into_iter()
+                    .map(|dep| CrateDependency {
+                        name: dep.name.as_str().to_string(),
+                        crate_name: name_of(dep.krate),
+                        version: dep.krate.version(db),
+                    })
+                    .collect();
+                dependencies.sort_by(|a, b| a.name.cmp(&b.name));
+
+                CrateNode {
+                    name: name_of(krate),
+                    version: krate.version(db),
+                    edition: krate.edition(db).to_string(),
+                    origin,
+                    root_file: self.file_path(krate.root_file(db)),
+                    dependencies,
+                }
+            })
+            .collect();
+
+        nodes.sort_by(|a, b| {
+            (a.origin != "workspace", &a.name, &a.version, &a.root_file).cmp(&(b.origin != "workspace", &b.name, &b.version, &b.root_file))
+        });
+        Ok(nodes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crate_graph_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let workspace = analyzer.crate_graph(false).unwrap();
+        assert!(workspace.iter().all(|node| node.origin == "workspace"));
+        let this = workspace.iter().find(|node| node.name == "cratographer").unwrap();
+        assert_eq!(this.edition, "2021");
+        assert!(this.root_file.ends_with("src/main.rs"));
+        assert!(this.dependencies.iter().any(|dep| dep.name == "serde_json"));
+        assert!(this.dependencies.iter().any(|dep| dep.name == "std"));
+
+        let all = analyzer.crate_graph(true).unwrap();
+        assert!(all.len() > workspace.len());
+        assert_eq!(all[0].origin, "workspace");
+        assert!(all.iter().any(|node| node.name == "core" && node.origin == "lang"));
+    }
+}
@@ -0,0 +1,139 @@
+//! The public items of a whole crate
+//!
+//! `enumerate_file` answers for one file at a time, which is too fine for
+//! getting oriented in an unfamiliar crate. This walks a crate's public
+//! modules from its root and lists the public items each declares, for
+//! workspace crates and dependencies alike. Items re-exported from
+//! elsewhere are listed where they are declared, not where they are
+//! re-exported, and private modules are skipped along with everything in
+//! them.
+
+use super::move_item::module_path;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Crate, HasVisibility, Module, ModuleDef, Visibility};
+use ra_ap_ide::RootDatabase;
+
+/// A public item of a crate
+#[derive(Debug, Clone)]
+pub struct CrateItem {
+    pub name: String,
+    /// Item kind as in `enumerate_file`, e.g. "Struct" or "Function"
+    pub kind: &'static str,
+    /// Path of the declaring module, starting with the crate name, e.g. `serde::de`
+    pub module: String,
+}
+
+/// A page of a crate's public items
+#[derive(Debug, Clone)]
+pub struct CrateItems {
+    pub crate_name: String,
+    pub version: Option<String>,
+    /// Number of public items in the whole crate
+    pub total: usize,
+    /// The items of this page, by module and then by name
+    pub items: Vec<CrateItem>,
+}
+
+impl Analyzer {
+    /// Public items of the crate `crate_name`, skipping the first `offset`
+    /// and returning at most `limit`
+    ///
+    /// When several versions of a crate are loaded, `version` picks one;
+    /// otherwise workspace crates are preferred, then the newest version.
+    pub fn enumerate_crate(
+        &self,
+        crate_name: &str,
+        version: Option<&str>,
+        offset: usize,
+        limit: usize,
+    ) -> Result<CrateItems, AnalyzerError> {
+        let db = self.host.raw_database();
+        let wanted = crate_name.replace('-', "_");
+        let mut candidates: Vec<Crate> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| krate.display_name(db).is_some_and(|name| name.to_string().replace('-', "_") == wanted))
+            .filter(|krate| version.is_none_or(|version| krate.version(db).as_deref() == Some(version)))
+            .collect();
+        candidates.sort_by_key(|krate| (!krate.origin(db).is_local(), std::cmp::Reverse(krate.version(db))));
+        let krate = *candidates.first().ok_or_else(|| match version {
+            Some(version) => AnalyzerError::Other(format!("No crate named '{}' at version {}", crate_name, version)),
+            None => AnalyzerError::Other(format!("No crate named '{}' in the crate graph", crate_name)),
+        })?;
+
+        let mut items = Vec::new();
+        collect_public_items(db, krate.root_module(), &wanted, &mut items);
+
+        Ok(CrateItems {
+            crate_name: wanted,
+            version: krate.version(db),
+            total: items.len(),
+            items: items.into_iter().skip(offset).take(limit).collect(),
+        })
+    }
+}
+
+/// Add the public items of `module` and of its public submodules, depth first
+fn collect_public_items(db: &RootDatabase, module: Module, crate_name: &str, items: &mut Vec<CrateItem>) {
+    let path = module_path(db, module).replacen("crate", crate_name, 1);
+    let mut declared: Vec<CrateItem> = module
+        .declarations(db)
+        .into_iter()
+        .filter(|def| def.visibility(db) == Visibility::Public)
+        .filter_map(|def| {
+            Some(CrateItem {
+                name: def.name(db)?.as_str().to_string(),
+                kind: def_kind(def)?,
+                module: path.clone(),
+            })
+        })
+        .collect();
+    declared.sort_by(|a, b| a.name.cmp(&b.name));
+    items.extend(declared);
+
+    let mut children: Vec<Module> = module.children(db).filter(|child| child.visibility(db) == Visibility::Public).collect();
+    children.sort_by_key(|child| child.name(db).map(|name| name.as_str().to_string()));
+    for child in children {
+        collect_public_items(db, child, crate_name, items);
+    }
+}
+
+fn def_kind(def: ModuleDef) -> Option<&'static str> {
+    Some(match def {
+        ModuleDef::Module(_) => "Module",
+        ModuleDef::Function(_) => "Function",
+        ModuleDef::Adt(Adt::Struct(_)) => "Struct",
+        ModuleDef::Adt(Adt::Enum(_)) => "Enum",
+        ModuleDef::Adt(Adt::Union(_)) => "Union",
+        ModuleDef::Const(_) => "Const",
+        ModuleDef::Static(_) => "Static",
+        ModuleDef::Trait(_) => "Trait",
+        ModuleDef::TypeAlias(_) => "TypeAlias",
+        ModuleDef::Macro(_) => "Macro",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enumerate_library_crate() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let all = analyzer.enumerate_crate("serde_json", None, 0, usize::MAX).unwrap();
+        assert_eq!(all.crate_name, "serde_json");
+        assert!(all.version.is_some());
+        assert_eq!(all.items.len(), all.total);
+        assert!(all.items.iter().any(|item| item.name == "from_str" && item.kind == "Function" && item.module == "serde_json"));
+        assert!(all.items.iter().any(|item| item.module == "serde_json::value"));
+
+        // Pages follow on from each other
+        let page = analyzer.enumerate_crate("serde_json", None, 5, 10).unwrap();
+        assert_eq!(page.items.len(), 10);
+        assert_eq!(page.items[0].name, all.items[5].name);
+
+        assert!(analyzer.enumerate_crate("no_such_crate", None, 0, 10).is_err());
+    }
+}
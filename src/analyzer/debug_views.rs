@@ -0,0 +1,135 @@
+//! Debugging views of rust-analyzer's internal representations
+//!
+//! The HIR of a function body: rust-analyzer's lowered form of the body, with
+//! macros expanded and sugar such as `for` loops and `?` desugared, which
+//! type inference then runs on. Useful for diagnosing surprising inference
+//! results. And the MIR built from it, for borrow and move questions. The
+//! text formats are rust-analyzer's and not stable.
+//!
+//! The syntax tree of a file or line range: every node and token with its
+//! kind and extent, for people writing proc macros or lints who need to know
+//! the node structure of some code.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide::LineIndex;
+use ra_ap_syntax::{AstNode, NodeOrToken, SyntaxElement, TextSize};
+
+impl Analyzer {
+    /// The HIR of the function body containing a position (0-based)
+    ///
+    /// Outside a function body this is rust-analyzer's explanation of why
+    /// there is nothing to show.
+    pub fn view_hir(&self, file_path: &str, line: u32, column: u32) -> Result<String, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        self.host.analysis().view_hir(position).map_err(|_| AnalyzerError::Canceled)
+    }
+
+    /// The MIR of the function containing a position (0-based)
+    ///
+    /// Bodies rust-analyzer can't lower to MIR yield its error message instead.
+    pub fn view_mir(&self, file_path: &str, line: u32, column: u32) -> Result<String, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        self.host.analysis().view_mir(position).map_err(|_| AnalyzerError::Canceled)
+    }
+
+    /// Dump the syntax tree of a file, or of the smallest node covering the
+    /// given lines (0-based, inclusive)
+    ///
+    /// One element per line, indented by depth: its kind, its extent as
+    /// 0-based `line:column` pairs and, for tokens, its text.
+    pub fn view_syntax_tree(&self, file_path: &str, lines: Option<(u32, u32)>) -> Result<String, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let analysis = self.host.analysis();
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let root = analysis.parse(file_id).map_err(|_| AnalyzerError::Canceled)?.syntax().clone();
+
+        let element = match lines {
+            None => NodeOrToken::Node(root),
+            Some((start_line, end_line)) => root.covering_element(self.lines_text_range(file_id, start_line, end_line)?),
+        };
+
+        let mut dump = String::new();
+        dump_element(&element, &line_index, 0, &mut dump);
+        Ok(dump)
+    }
+}
+
+/// Append an element and, for nodes, its children
+fn dump_element(element: &SyntaxElement, line_index: &LineIndex, depth: usize, out: &mut String) {
+    let position = |offset: TextSize| {
+        let lc = line_index.line_col(offset);
+        format!("{}:{}", lc.line, lc.col)
+    };
+    let range = element.text_range();
+    out.push_str(&format!(
+        "{:indent$}{:?} {}..{}",
+        "",
+        element.kind(),
+        position(range.start()),
+        position(range.end()),
+        indent = depth * 2
+    ));
+
+    match element {
+        NodeOrToken::Token(token) => out.push_str(&format!(" {:?}\n", token.text())),
+        NodeOrToken::Node(node) => {
+            out.push('\n');
+            for child in node.children_with_tokens() {
+                dump_element(&child, line_index, depth + 1, out);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_hir_of_function() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Inside `view_hir`'s body in this file
+        let path = std::fs::canonicalize("src/analyzer/debug_views.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("let position = self.file_position")).unwrap() as u32;
+
+        let hir = analyzer.view_hir(path, line, 12).unwrap();
+        assert!(hir.contains("file_position"));
+    }
+
+    #[test]
+    fn test_view_mir_of_function() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Inside `view_mir`'s body in this file
+        let path = std::fs::canonicalize("src/analyzer/debug_views.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains(".view_mir(position)")).unwrap() as u32;
+
+        let mir = analyzer.view_mir(path, line, 12).unwrap();
+        assert!(mir.contains("bb0"));
+    }
+
+    #[test]
+    fn test_view_syntax_tree() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer/debug_views.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let tree = analyzer.view_syntax_tree(path, None).unwrap();
+        assert!(tree.starts_with("SOURCE_FILE 0:0.."));
+
+        // The lines of `dump_element`'s signature are covered by its FN node
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.starts_with("fn dump_element(")).unwrap() as u32;
+        let tree = analyzer.view_syntax_tree(path, Some((line, line))).unwrap();
+        assert!(tree.lines().next().unwrap().starts_with("FN "));
+        assert!(tree.contains("  IDENT") && tree.contains("\"dump_element\""));
+    }
+}
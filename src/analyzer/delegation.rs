@@ -0,0 +1,266 @@
+//! Impls that only forward to an inner field
+//!
+//! Newtype wrappers often implement a trait, or a slice of the inner type's
+//! API, by calling the same method on the wrapped field:
+//!
+//! ```ignore
+//! impl Display for UserId {
+//!     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+//! }
+//! ```
+//!
+//! Knowing which impls are pure forwarding, and to which field, tells
+//! whether a `Deref` impl, a derive or a delegation macro would replace
+//! them, or whether the boilerplate is the lesser evil.
+//!
+//! A method forwards when its body is a single call on a field of `self`
+//! (`self.inner.len()`, or `Trait::method(&self.inner, ..)`) passing the
+//! method's own parameters in order, optionally followed by `?` or
+//! `.into()`. This works on the syntax of each impl.
+
+use super::budget::{Budget, Coverage};
+use super::implementations::impl_header;
+use super::reference_index::definition_key;
+use super::{display_type, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::FileId;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasArgList, HasName},
+    AstNode,
+};
+
+/// A method of an impl that forwards to a field
+#[derive(Debug, Clone)]
+pub struct ForwardingMethod {
+    pub name: String,
+    /// The field forwarded to, e.g. `inner` or `0`
+    pub field: String,
+    /// The forwarding call as written, e.g. `self.0.fmt(f)`
+    pub call: String,
+}
+
+/// An impl block whose methods forward to fields of `self`
+#[derive(Debug, Clone)]
+pub struct DelegatingImpl {
+    /// The impl header, e.g. `impl Display for UserId`
+    pub header: String,
+    /// Qualified path of the implemented trait; `None` for an inherent impl
+    pub trait_path: Option<String>,
+    pub self_type: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Number of methods in the impl
+    pub methods: usize,
+    pub forwarding: Vec<ForwardingMethod>,
+    /// The one field every forwarding method targets, if they agree
+    pub target_field: Option<String>,
+    /// Type of `target_field`, e.g. `u64`
+    pub target_type: Option<String>,
+}
+
+impl DelegatingImpl {
+    /// Whether every method of the impl forwards
+    pub fn fully_delegating(&self) -> bool {
+        self.forwarding.len() == self.methods
+    }
+}
+
+/// Delegating impls of the workspace files scanned within the budget
+#[derive(Debug, Clone)]
+pub struct DelegatingImpls {
+    pub impls: Vec<DelegatingImpl>,
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// Find the workspace impls whose methods forward to a field of `self`:
+    /// all of their methods, or with `include_partial` at least one
+    pub fn delegating_impls(&self, include_partial: bool, budget: Budget) -> Result<DelegatingImpls, AnalyzerError> {
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        let mut impls = Vec::new();
+        for (_, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            impls.extend(self.file_delegating_impls(file_id)?.into_iter().filter(|i| include_partial || i.fully_delegating()));
+            coverage.files_scanned += 1;
+        }
+        Ok(DelegatingImpls { impls, coverage })
+    }
+
+    fn file_delegating_impls(&self, file_id: FileId) -> Result<Vec<DelegatingImpl>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source = sema.parse_guess_edition(file_id);
+        let krate = sema.file_to_module_def(file_id).map(|module| module.krate());
+
+        let mut impls = Vec::new();
+        for impl_ in source.syntax().descendants().filter_map(ast::Impl::cast) {
+            let functions: Vec<ast::Fn> = impl_
+                .assoc_item_list()
+                .into_iter()
+                .flat_map(|list| list.assoc_items())
+                .filter_map(|item| match item {
+                    ast::AssocItem::Fn(func) => Some(func),
+                    _ => None,
+                })
+                .collect();
+            let forwards: Vec<(ForwardingMethod, ast::FieldExpr)> =
+                functions.iter().filter_map(|func| Some((func.name()?, forwarded_field(func)?))).map(|(name, (field, call))| {
+                    let method = ForwardingMethod {
+                        name: name.text().to_string(),
+                        field: field.name_ref().map(|name| name.text().to_string()).unwrap_or_default(),
+                        call: normalize_whitespace(&call.syntax().text().to_string()),
+                    };
+                    (method, field)
+                }).collect();
+            if forwards.is_empty() {
+                continue;
+            }
+
+            let first = &forwards[0];
+            let target = forwards.iter().all(|(method, _)| method.field == first.0.field).then(|| first.clone());
+            let target_type = target.as_ref().and_then(|(_, field)| {
+                let ty = sema.type_of_expr(&ast::Expr::FieldExpr(field.clone()))?.original;
+                Some(display_type(db, &ty, krate?))
+            });
+            let trait_path = sema
+                .to_def(&impl_)
+                .and_then(|def| def.trait_(db))
+                .and_then(|trait_| definition_key(db, Definition::Trait(trait_)));
+            let (start_line, end_line) = self.line_range(file_id, impl_.syntax().text_range())?;
+
+            impls.push(DelegatingImpl {
+                header: impl_header(&impl_),
+                trait_path,
+                self_type: impl_.self_ty().map(|ty| normalize_whitespace(&ty.syntax().text().to_string())).unwrap_or_default(),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                methods: functions.len(),
+                target_field: target.map(|(method, _)| method.field),
+                target_type,
+                forwarding: forwards.into_iter().map(|(method, _)| method).collect(),
+            });
+        }
+        Ok(impls)
+    }
+}
+
+/// The field of `self` a method forwards to, with the forwarding call
+fn forwarded_field(func: &ast::Fn) -> Option<(ast::FieldExpr, ast::Expr)> {
+    func.param_list()?.self_param()?;
+    let body = func.body()?.stmt_list()?;
+    let expr = match (body.statements().next(), body.tail_expr()) {
+        (None, Some(tail)) => tail,
+        // A unit method ending in `;`
+        (Some(ast::Stmt::ExprStmt(stmt)), None) if body.statements().count() == 1 => stmt.expr()?,
+        _ => return None,
+    };
+    let call = strip_wrappers(expr);
+
+    let params: Vec<String> = func
+        .param_list()?
+        .params()
+        .map(|param| match param.pat()? {
+            ast::Pat::IdentPat(pat) => pat.name().map(|name| name.text().to_string()),
+            _ => None,
+        })
+        .collect::<Option<_>>()?;
+    let (field, args): (ast::Expr, Vec<ast::Expr>) = match &call {
+        ast::Expr::MethodCallExpr(method) => (method.receiver()?, method.arg_list()?.args().collect()),
+        // `Trait::method(&self.field, ..)`
+        ast::Expr::CallExpr(path_call) if matches!(path_call.expr()?, ast::Expr::PathExpr(_)) => {
+            let mut args = path_call.arg_list()?.args();
+            (args.next()?, args.collect())
+        }
+        _ => return None,
+    };
+
+    let ast::Expr::FieldExpr(field) = strip_refs(field) else { return None };
+    let receiver_is_self = match field.expr()? {
+        ast::Expr::PathExpr(path) => path.path().is_some_and(|path| path.syntax().text() == "self"),
+        _ => false,
+    };
+    let passes_params = args.len() == params.len()
+        && args.into_iter().zip(&params).all(|(arg, param)| match strip_refs(arg) {
+            ast::Expr::PathExpr(path) => path.path().is_some_and(|path| path.syntax().text() == param.as_str()),
+            _ => false,
+        });
+    (receiver_is_self && passes_params).then_some((field, call))
+}
+
+/// An expression without a trailing `?` or `.into()` and without parentheses
+fn strip_wrappers(expr: ast::Expr) -> ast::Expr {
+    match expr {
+        ast::Expr::TryExpr(ref inner) => inner.expr().map_or(expr, strip_wrappers),
+        ast::Expr::ParenExpr(ref inner) => inner.expr().map_or(expr, strip_wrappers),
+        ast::Expr::MethodCallExpr(ref call)
+            if call.name_ref().is_some_and(|name| name.text() == "into") && call.arg_list().is_some_and(|list| list.args().next().is_none()) =>
+        {
+            call.receiver().map_or(expr, strip_wrappers)
+        }
+        _ => expr,
+    }
+}
+
+/// An expression without leading `&`, `&mut` or `*`
+fn strip_refs(expr: ast::Expr) -> ast::Expr {
+    match expr {
+        ast::Expr::RefExpr(ref inner) => inner.expr().map_or(expr, strip_refs),
+        ast::Expr::PrefixExpr(ref inner) if inner.op_kind() == Some(ast::UnaryOp::Deref) => inner.expr().map_or(expr, strip_refs),
+        ast::Expr::ParenExpr(ref inner) => inner.expr().map_or(expr, strip_refs),
+        _ => expr,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn forwards(src: &str) -> Vec<Option<String>> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        file.syntax()
+            .descendants()
+            .filter_map(ast::Fn::cast)
+            .map(|func| forwarded_field(&func).and_then(|(field, _)| field.name_ref()).map(|name| name.text().to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_forwarded_fields() {
+        let src = r#"
+impl Display for Id {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result { self.0.fmt(f) }
+}
+impl Wrapper {
+    fn len(&self) -> usize { self.inner.len() }
+    fn push(&mut self, item: Item) { self.inner.push(item); }
+    fn get(&self, i: usize) -> Result<u8, Error> { Ok(self.inner.get(i)?.into()) }
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> { io::Write::write(&mut self.writer, buf) }
+    fn swapped(&self, a: u8, b: u8) -> u8 { self.inner.op(b, a) }
+    fn counted(&self) -> usize { self.calls += 1; self.inner.len() }
+    fn new() -> Self { Self::default() }
+}
+"#;
+        assert_eq!(
+            forwards(src),
+            vec![
+                Some("0".to_string()),
+                Some("inner".to_string()),
+                Some("inner".to_string()),
+                None,
+                Some("writer".to_string()),
+                None,
+                None,
+                None,
+            ]
+        );
+    }
+}
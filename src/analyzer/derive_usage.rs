@@ -0,0 +1,253 @@
+//! Derive usage statistics
+//!
+//! Counts, per derive macro, the workspace types deriving it and where they
+//! are, e.g. every `Serialize` type before switching serde strategies.
+//! Derives are grouped by the macro they resolve to, so `Serialize` and
+//! `serde::Serialize` count together; derives under `cfg_attr` are included
+//! with their condition. Each type also carries its size from
+//! rust-analyzer's layout computation, to single out large types deriving
+//! `Clone` or `Copy`; generic types have no size.
+
+use super::features::CfgExpr;
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Semantics};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasAttrs, HasName},
+    AstNode,
+};
+use std::collections::HashMap;
+
+/// A workspace type deriving a macro
+#[derive(Debug, Clone)]
+pub struct DerivingType {
+    /// Qualified path of the type
+    pub path: String,
+    pub file_path: String,
+    pub line: u32,
+    /// Size in bytes, when the layout is known
+    pub size: Option<u64>,
+    /// The `cfg_attr` condition the derive is under, if any
+    pub cfg: Option<String>,
+}
+
+/// A derive macro and the types using it
+#[derive(Debug, Clone)]
+pub struct DeriveUsage {
+    /// The macro's qualified path when it resolves, e.g. `serde_derive::Serialize`,
+    /// or as written
+    pub derive: String,
+    /// Its last path segment, e.g. `Serialize`
+    pub name: String,
+    pub types: Vec<DerivingType>,
+}
+
+impl Analyzer {
+    /// Count the workspace types deriving each macro, most used first
+    ///
+    /// With `derive`, only macros whose name or path matches it are counted;
+    /// with `min_size`, only types of at least that many bytes.
+    pub fn derive_usage(&self, derive: Option<&str>, min_size: Option<u64>) -> Result<Vec<DeriveUsage>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut usages: HashMap<String, DeriveUsage> = HashMap::new();
+        let mut unresolved = Vec::new();
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            for adt_node in source.syntax().descendants().filter_map(ast::Adt::cast) {
+                let Some(adt) = sema.to_def(&adt_node) else { continue };
+                let size = adt.layout(db).ok().map(|layout| layout.size());
+                if min_size.is_some_and(|min| size.is_none_or(|size| size < min)) {
+                    continue;
+                }
+
+                for attr in adt_node.attrs() {
+                    for (written, resolved, cfg) in attr_derives(&sema, &attr) {
+                        if resolved.is_none() && !unresolved.contains(&written) {
+                            unresolved.push(written.clone());
+                        }
+                        let path = resolved.unwrap_or_else(|| written.clone());
+                        let name = written.rsplit("::").next().unwrap_or(&written).to_string();
+                        if derive.is_some_and(|wanted| wanted != name && wanted != path && wanted != written) {
+                            continue;
+                        }
+
+                        let line = self.line_range(file_id, adt_node.syntax().text_range())?.0;
+                        let usage = usages.entry(path.clone()).or_insert_with(|| DeriveUsage {
+                            derive: path,
+                            name,
+                            types: Vec::new(),
+                        });
+                        usage.types.push(DerivingType {
+                            path: adt_path(db, adt, &adt_node),
+                            file_path: self.file_path(file_id),
+                            line,
+                            size,
+                            cfg,
+                        });
+                    }
+                }
+            }
+        }
+
+        // Derives under `cfg_attr` aren't resolved; count them with the macro
+        // of that name if there is exactly one
+        for written in unresolved {
+            let Some(usage) = usages.get(&written) else { continue };
+            let matching: Vec<String> = usages
+                .iter()
+                .filter(|(path, other)| **path != written && other.name == usage.name)
+                .map(|(path, _)| path.clone())
+                .collect();
+            if let [target] = &matching[..] {
+                let types = usages.remove(&written).map(|usage| usage.types).unwrap_or_default();
+                usages.entry(target.clone()).and_modify(|usage| usage.types.extend(types));
+            }
+        }
+
+        let mut usages: Vec<DeriveUsage> = usages.into_values().collect();
+        usages.sort_by(|a, b| b.types.len().cmp(&a.types.len()).then(a.derive.cmp(&b.derive)));
+        for usage in &mut usages {
+            usage.types.sort_by(|a, b| (&a.file_path, a.line).cmp(&(&b.file_path, b.line)));
+        }
+        Ok(usages)
+    }
+}
+
+/// Qualified path of a type, or its bare name
+fn adt_path(db: &RootDatabase, adt: Adt, node: &ast::Adt) -> String {
+    definition_key(db, Definition::Adt(adt))
+        .or_else(|| node.name().map(|name| name.text().to_string()))
+        .unwrap_or_default()
+}
+
+/// The derives in an attribute: each as written, with its resolved path and
+/// `cfg_attr` condition
+fn attr_derives(sema: &Semantics<'_, RootDatabase>, attr: &ast::Attr) -> Vec<(String, Option<String>, Option<String>)> {
+    let Some(path) = attr.path() else { return Vec::new() };
+    let Some(tt) = attr.token_tree() else { return Vec::new() };
+    let text = tt.syntax().text().to_string();
+    let Some(inner) = text.strip_prefix('(').and_then(|t| t.strip_suffix(')')) else { return Vec::new() };
+
+    match path.syntax().text().to_string().as_str() {
+        "derive" => {
+            let resolved = sema.resolve_derive_macro(attr).unwrap_or_default();
+            derive_list(inner)
+                .into_iter()
+                .enumerate()
+                .map(|(i, written)| {
+                    let macro_ = resolved.get(i).copied().flatten();
+                    let path = macro_.and_then(|m| definition_key(sema.db, Definition::Macro(m)));
+                    (written, path, None)
+                })
+                .collect()
+        }
+        "cfg_attr" => cfg_attr_derives(inner)
+            .map(|(cfg, derives)| derives.into_iter().map(|written| (written, None, Some(cfg.clone()))).collect())
+            .unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// The comma-separated paths of a `derive(...)` list
+fn derive_list(inner: &str) -> Vec<String> {
+    inner
+        .split(',')
+        .map(|path| path.split_whitespace().collect::<String>())
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+/// The condition and derives of `cfg_attr(condition, derive(...), ...)`
+///
+/// Every `derive(...)` among the attributes counts; `None` if there is none.
+fn cfg_attr_derives(inner: &str) -> Option<(String, Vec<String>)> {
+    let mut parts = split_top_level(inner).into_iter();
+    let condition = parts.next()?.trim();
+    let derives: Vec<String> = parts
+        .filter_map(|attr| {
+            let list = attr.trim().strip_prefix("derive")?.trim_start().strip_prefix('(')?.trim_end().strip_suffix(')')?;
+            Some(derive_list(list))
+        })
+        .flatten()
+        .collect();
+    if derives.is_empty() {
+        return None;
+    }
+    let cfg = CfgExpr::parse(condition).map(|cfg| cfg.to_string()).unwrap_or_else(|| condition.to_string());
+    Some((cfg, derives))
+}
+
+/// Split at the commas outside of parentheses, brackets and string literals
+fn split_top_level(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let (mut depth, mut in_string, mut escaped, mut start) = (0usize, false, false, 0);
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derive_lists() {
+        assert_eq!(derive_list("Debug, Clone,serde :: Serialize"), vec!["Debug", "Clone", "serde::Serialize"]);
+        assert_eq!(
+            cfg_attr_derives("feature = \"serde\", derive(Serialize, Deserialize)"),
+            Some(("feature = \"serde\"".to_string(), vec!["Serialize".to_string(), "Deserialize".to_string()]))
+        );
+        assert_eq!(cfg_attr_derives("test, allow(dead_code)"), None);
+
+        // The condition may itself mention "derive", and one cfg_attr may hold several derives
+        assert_eq!(
+            cfg_attr_derives("feature = \"derive\", derive(Serialize), allow(dead_code), derive(Deserialize,)"),
+            Some(("feature = \"derive\"".to_string(), vec!["Serialize".to_string(), "Deserialize".to_string()]))
+        );
+        assert_eq!(
+            cfg_attr_derives("all(feature = \"a, b\", unix), derive(Debug)"),
+            Some(("all(feature = \"a, b\", unix)".to_string(), vec!["Debug".to_string()]))
+        );
+    }
+
+    #[test]
+    fn test_derive_usage_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let usages = analyzer.derive_usage(Some("Clone"), None).unwrap();
+        assert_eq!(usages.len(), 1);
+        let clone = &usages[0];
+        assert!(clone.types.iter().any(|t| t.path == "cratographer::analyzer::SymbolInfo"));
+        // `SymbolKind` is a fieldless enum, so its layout is known
+        let kind = clone.types.iter().find(|t| t.path.ends_with("::SymbolKind")).unwrap();
+        assert_eq!(kind.size, Some(1));
+
+        let large = analyzer.derive_usage(Some("Clone"), Some(16)).unwrap();
+        assert!(large[0].types.iter().all(|t| t.size.is_some_and(|size| size >= 16)));
+    }
+}
@@ -0,0 +1,145 @@
+//! Per-file diagnostics
+//!
+//! Runs rust-analyzer's native diagnostics on a file: syntax errors,
+//! unresolved names and imports, type mismatches, missing match arms,
+//! unused variables and the like, without a `cargo check`. These are
+//! rust-analyzer's own checks, so they are quick but not the compiler's full
+//! set: borrow checking, for one, is missing.
+//!
+//! The whole workspace can be checked at once too, within a time budget.
+
+use super::budget::{Budget, Coverage};
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide::{AssistResolveStrategy, DiagnosticsConfig, FileId, Severity};
+
+/// A diagnostic rust-analyzer reports on a file
+#[derive(Debug, Clone)]
+pub struct FileDiagnostic {
+    /// The lint or error code, e.g. `E0308` or `unused_variables`
+    pub code: String,
+    pub message: String,
+    /// `error`, `warning` or `hint`
+    pub severity: &'static str,
+    /// Extent of the offending code (0-based)
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Labels of the quick fixes rust-analyzer offers, e.g. `Add missing match arms`
+    pub fixes: Vec<String>,
+}
+
+/// Diagnostics of the workspace files checked within the budget
+#[derive(Debug, Clone)]
+pub struct WorkspaceDiagnostics {
+    /// Files with diagnostics, by path, each with its diagnostics in source order
+    pub files: Vec<(String, Vec<FileDiagnostic>)>,
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// Run rust-analyzer's diagnostics on every workspace file, by path,
+    /// until the budget runs out
+    pub fn workspace_diagnostics(&self, experimental: bool, budget: Budget) -> Result<WorkspaceDiagnostics, AnalyzerError> {
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        let mut found = Vec::new();
+        for (path, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            let diagnostics = self.file_diagnostics(file_id, experimental)?;
+            coverage.files_scanned += 1;
+            if !diagnostics.is_empty() {
+                found.push((path, diagnostics));
+            }
+        }
+        Ok(WorkspaceDiagnostics { files: found, coverage })
+    }
+
+    /// Run rust-analyzer's diagnostics on a file, in source order
+    ///
+    /// Experimental diagnostics, which are prone to false positives, are only
+    /// included on request.
+    pub fn diagnostics(&self, file_path: &str, experimental: bool) -> Result<Vec<FileDiagnostic>, AnalyzerError> {
+        self.file_diagnostics(self.file_id(file_path)?, experimental)
+    }
+
+    /// Run rust-analyzer's diagnostics on a file, by `FileId`
+    pub(super) fn file_diagnostics(&self, file_id: FileId, experimental: bool) -> Result<Vec<FileDiagnostic>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let mut config = DiagnosticsConfig::test_sample();
+        config.experimental = experimental;
+
+        let found = analysis
+            .full_diagnostics(&config, AssistResolveStrategy::None, file_id)
+            .map_err(|_| AnalyzerError::Canceled)?;
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let mut diagnostics: Vec<FileDiagnostic> = found
+            .into_iter()
+            .filter(|d| d.range.file_id == file_id)
+            .filter_map(|d| {
+                let severity = match d.severity {
+                    Severity::Error => "error",
+                    Severity::Warning => "warning",
+                    Severity::WeakWarning => "hint",
+                    Severity::Allow => return None,
+                };
+                let start = line_index.line_col(d.range.range.start());
+                let end = line_index.line_col(d.range.range.end());
+                Some(FileDiagnostic {
+                    code: d.code.as_str().to_string(),
+                    message: d.message,
+                    severity,
+                    start_line: start.line,
+                    start_column: start.col,
+                    end_line: end.line,
+                    end_column: end.col,
+                    fixes: d.fixes.unwrap_or_default().into_iter().map(|fix| fix.label.to_string()).collect(),
+                })
+            })
+            .collect();
+        diagnostics.sort_by_key(|d| (d.start_line, d.start_column));
+        Ok(diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics_of_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer/diagnostics.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let lines = std::fs::read_to_string(path).unwrap().lines().count() as u32;
+        for diagnostic in analyzer.diagnostics(path, false).unwrap() {
+            assert!(diagnostic.start_line <= diagnostic.end_line && diagnostic.end_line <= lines);
+            assert!(["error", "warning", "hint"].contains(&diagnostic.severity));
+        }
+
+        assert!(analyzer.diagnostics("/nonexistent/file.rs", false).is_err());
+    }
+
+    #[test]
+    fn test_workspace_diagnostics_within_budget() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let all = analyzer.workspace_diagnostics(false, Budget::unlimited()).unwrap();
+        assert!(all.coverage.complete());
+        assert!(all.coverage.files_total > 0);
+
+        // An exhausted budget returns at once with what little was done
+        let none = analyzer.workspace_diagnostics(false, Budget::from_millis(Some(0))).unwrap();
+        assert_eq!(none.coverage.files_scanned, 0);
+        assert!(!none.coverage.complete());
+        assert!(none.files.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Disambiguating name-based queries
+//!
+//! Tools taking a name act on one symbol. When a name such as `Error` or
+//! `new` matches several, they don't pick one or merge the results; the
+//! query fails with the list of candidates, each with a symbol_id (its
+//! moniker) and enough context to choose. Repeating the query with that
+//! symbol_id in place of the name resolves to exactly that symbol.
+//!
+//! Monikers are unique in all but rare cases, such as two impls of one
+//! generic trait whose arguments display alike (`From<io::Error>` and
+//! `From<fmt::Error>`). Candidates sharing a moniker get a positional
+//! symbol_id instead, `moniker@file_path:line`, which picks the definition
+//! declared on that (1-based) line.
+
+use super::monikers::{definition_kind, definition_moniker};
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FilePosition, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+
+/// One of the symbols an ambiguous name matched
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    /// The symbol's moniker, to pass back in place of the name; a
+    /// positional id when candidates share a moniker, and the qualified path
+    /// for symbols without one
+    pub symbol_id: String,
+    /// Qualified path, e.g. `std::io::error::Error`
+    pub path: String,
+    /// One of the moniker kinds, e.g. "struct", "trait" or "fn"
+    pub kind: &'static str,
+    pub crate_name: Option<String>,
+    /// Whether the defining crate is part of the workspace
+    pub local: bool,
+    pub file_path: String,
+    pub line: u32,
+    /// The source line declaring the symbol, trimmed
+    pub context: String,
+}
+
+impl Analyzer {
+    /// Fail with `AnalyzerError::Ambiguous` when `name` resolved to more than
+    /// one of `definitions`, listing them as candidates
+    pub(super) fn require_unique(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        name: &str,
+        definitions: &[Definition],
+    ) -> Result<(), AnalyzerError> {
+        if definitions.len() < 2 {
+            return Ok(());
+        }
+
+        let mut candidates = Vec::new();
+        for (def, position) in self.definition_positions(sema, name)? {
+            if definitions.contains(&def) {
+                candidates.push(self.candidate(sema.db, def, position)?);
+            }
+        }
+
+        // Ids have to pick exactly one candidate; pin shared ones to a position
+        let ids: Vec<String> = candidates.iter().map(|c| c.symbol_id.clone()).collect();
+        for candidate in &mut candidates {
+            if candidate.symbol_id.contains('#') && ids.iter().filter(|id| **id == candidate.symbol_id).count() > 1 {
+                candidate.symbol_id = format!("{}@{}:{}", candidate.symbol_id, candidate.file_path, candidate.line + 1);
+            }
+        }
+
+        // The workspace's own symbols before dependencies'
+        candidates.sort_by(|a, b| (!a.local, &a.path).cmp(&(!b.local, &b.path)));
+        Err(AnalyzerError::Ambiguous { name: name.to_string(), candidates })
+    }
+
+    fn candidate(&self, db: &RootDatabase, def: Definition, position: FilePosition) -> Result<Candidate, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let line_index = analysis.file_line_index(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let text = analysis.file_text(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let line = line_index.line_col(position.offset).line;
+        let path = definition_key(db, def).unwrap_or_default();
+        let krate = def.krate(db);
+
+        Ok(Candidate {
+            symbol_id: definition_moniker(db, def).unwrap_or_else(|| path.clone()),
+            path,
+            kind: definition_kind(def).unwrap_or("item"),
+            crate_name: krate.and_then(|k| k.display_name(db)).map(|n| n.to_string()),
+            local: krate.is_some_and(|k| k.origin(db).is_local()),
+            file_path: self.file_path(position.file_id),
+            line,
+            context: text.lines().nth(line as usize).unwrap_or_default().trim().to_string(),
+        })
+    }
+}
+
+/// Split a positional symbol_id into its moniker, file path and 0-based line
+pub(super) fn split_positional_id(id: &str) -> Option<(&str, &str, u32)> {
+    let kind_start = id.find('#')?;
+    let at = kind_start + id[kind_start..].find('@')?;
+    let (file_path, line) = id[at + 1..].rsplit_once(':')?;
+    let line = line.parse::<u32>().ok()?.checked_sub(1)?;
+    Some((&id[..at], file_path, line))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scratch::ScratchProject;
+
+    #[test]
+    fn test_ambiguous_name_lists_candidates() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Both the analyzer's and rust-analyzer's SymbolKind
+        let Err(AnalyzerError::Ambiguous { candidates, .. }) = analyzer.impls_of_type("SymbolKind", true) else {
+            panic!("SymbolKind should be ambiguous");
+        };
+        assert!(candidates.len() > 1);
+        assert!(candidates[0].local);
+        assert!(candidates.iter().all(|c| c.kind == "enum" && c.context.contains("enum SymbolKind")));
+
+        // Its symbol_id picks exactly one
+        let blocks = analyzer.impls_of_type(&candidates[0].symbol_id, true).unwrap();
+        assert!(blocks.iter().all(|b| b.self_type == "SymbolKind"));
+    }
+
+    #[test]
+    fn test_positional_ids() {
+        assert_eq!(
+            split_positional_id("a@1.0.0::Pair::from#fn:From<Error>@/p/src/lib.rs:12"),
+            Some(("a@1.0.0::Pair::from#fn:From<Error>", "/p/src/lib.rs", 11))
+        );
+        assert_eq!(split_positional_id("a@1.0.0::Pair::from#fn:From<Error>"), None);
+        assert_eq!(split_positional_id("a::Pair::from"), None);
+    }
+
+    #[test]
+    fn test_impls_of_one_generic_trait_get_distinct_ids() {
+        let analyzer = ScratchProject::new(
+            "disambiguation",
+            &[(
+                "pairs.rs",
+                "pub trait Convert<T> { fn convert(&self) -> T; }\n\
+                 pub struct Pair;\n\
+                 impl Convert<u8> for Pair { fn convert(&self) -> u8 { 0 } }\n\
+                 impl Convert<u16> for Pair { fn convert(&self) -> u16 { 0 } }\n\
+                 impl Convert<Option<std::io::Error>> for Pair { fn convert(&self) -> Option<std::io::Error> { None } }\n\
+                 impl Convert<Option<std::fmt::Error>> for Pair { fn convert(&self) -> Option<std::fmt::Error> { None } }\n",
+            )],
+        );
+
+        let Err(AnalyzerError::Ambiguous { candidates, .. }) = analyzer.find_references("convert") else {
+            panic!("convert should be ambiguous");
+        };
+        let impls: Vec<_> = candidates.iter().filter(|c| c.file_path.ends_with("pairs.rs") && c.context.starts_with("impl")).collect();
+        assert_eq!(impls.len(), 4);
+        assert!(impls.iter().any(|c| c.symbol_id.ends_with("#fn:Convert<u8>")));
+
+        // Every id, whether a moniker or positional, picks exactly one definition
+        for candidate in &impls {
+            let found = analyzer.find_references(&candidate.symbol_id);
+            assert!(found.is_ok(), "{} should be unique: {:?}", candidate.symbol_id, found.err());
+        }
+    }
+
+    #[test]
+    fn test_unique_name_is_not_ambiguous() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        assert!(analyzer.find_implementations("SymbolProvider", false).is_ok());
+    }
+}
@@ -0,0 +1,279 @@
+//! Drop impls and the types that contain them
+//!
+//! Lists the workspace types that run custom code when destroyed: those
+//! implementing `Drop` themselves, and those holding such a type in a field,
+//! directly or through other types, `Box`es, tuples and arrays. This is what
+//! to look at when reasoning about resource cleanup, lock guards and drop
+//! order. A value runs its own `drop` first, then drops its fields in
+//! declaration order (locals drop in reverse order of declaration), so
+//! fields are listed in declaration order.
+//!
+//! By default only the workspace's own `Drop` impls count; with
+//! `include_library`, dependencies' and the standard library's do too, such
+//! as `MutexGuard` or `File` (and `Vec`, so that most types qualify).
+
+use super::reference_index::definition_key;
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Field, Impl, Semantics, Type};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{ast, AstNode};
+use std::collections::HashMap;
+
+/// A field whose type has destruction code
+#[derive(Debug, Clone)]
+pub struct DropField {
+    /// The field name, `Variant.field` for enum variants; tuple fields by index
+    pub name: String,
+    pub ty: String,
+    /// The types with a `Drop` impl the field reaches, e.g. `MutexGuard`
+    pub drops: Vec<String>,
+}
+
+/// A workspace type with destruction code
+#[derive(Debug, Clone)]
+pub struct DropType {
+    /// Qualified path of the type
+    pub path: String,
+    pub kind: &'static str,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Location of its own `impl Drop`, as `(file_path, start_line)`
+    pub drop_impl: Option<(String, u32)>,
+    /// Fields with destruction code, in declaration (and so drop) order
+    pub fields: Vec<DropField>,
+}
+
+impl Analyzer {
+    /// List the workspace types that implement `Drop` or contain a type that does
+    pub fn drop_types(&self, include_library: bool) -> Result<Vec<DropType>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut drop_impls: HashMap<Adt, Impl> = HashMap::new();
+        for def in self.resolve_definitions(&sema, "ops::Drop")? {
+            let Definition::Trait(trait_) = def else { continue };
+            if !definition_key(db, def).is_some_and(|key| key.starts_with("core::")) {
+                continue;
+            }
+            for impl_ in Impl::all_for_trait(db, trait_) {
+                let Some(adt) = impl_.self_ty(db).as_adt() else { continue };
+                if include_library || adt.module(db).krate().origin(db).is_local() {
+                    drop_impls.insert(adt, impl_);
+                }
+            }
+        }
+
+        let mut adts = Vec::new();
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            adts.extend(source.syntax().descendants().filter_map(ast::Adt::cast).filter_map(|adt| sema.to_def(&adt)));
+        }
+
+        let mut drops = DropSearch { db, drop_impls: &drop_impls, memo: HashMap::new(), cut_at: usize::MAX };
+        let mut types = Vec::new();
+        for adt in adts {
+            let fields: Vec<DropField> = adt_fields(db, adt)
+                .into_iter()
+                .filter_map(|(name, field)| {
+                    let ty = field.ty(db);
+                    let mut reached = Vec::new();
+                    drops.type_drops(&ty, &mut reached);
+                    (!reached.is_empty()).then(|| DropField {
+                        name,
+                        ty: display_type(db, &ty, adt.module(db).krate()),
+                        drops: reached.iter().map(|adt| adt.name(db).as_str().to_string()).collect(),
+                    })
+                })
+                .collect();
+            let drop_impl = drop_impls.get(&adt).copied();
+            if drop_impl.is_none() && fields.is_empty() {
+                continue;
+            }
+
+            let Some(source) = sema.source(adt) else { continue };
+            let range = sema.original_range(source.value.syntax());
+            let file_id = range.file_id.file_id(db);
+            let (start_line, end_line) = self.line_range(file_id, range.range)?;
+            let drop_impl = match drop_impl.and_then(|impl_| sema.source(impl_)) {
+                Some(source) => {
+                    let range = sema.original_range(source.value.syntax());
+                    let impl_file = range.file_id.file_id(db);
+                    Some((self.file_path(impl_file), self.line_range(impl_file, range.range)?.0))
+                }
+                None => None,
+            };
+            types.push(DropType {
+                path: definition_key(db, Definition::Adt(adt)).unwrap_or_else(|| adt.name(db).as_str().to_string()),
+                kind: match adt {
+                    Adt::Struct(_) => "struct",
+                    Adt::Enum(_) => "enum",
+                    Adt::Union(_) => "union",
+                },
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                drop_impl,
+                fields,
+            });
+        }
+
+        types.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(types)
+    }
+}
+
+/// The fields of a struct, union or of every enum variant, named for display
+fn adt_fields(db: &RootDatabase, adt: Adt) -> Vec<(String, Field)> {
+    let named = |prefix: &str, fields: Vec<Field>| {
+        fields
+            .into_iter()
+            .map(|field| (format!("{}{}", prefix, field.name(db).as_str()), field))
+            .collect::<Vec<_>>()
+    };
+    match adt {
+        Adt::Struct(strukt) => named("", strukt.fields(db)),
+        Adt::Union(union) => named("", union.fields(db)),
+        Adt::Enum(enum_) => enum_
+            .variants(db)
+            .into_iter()
+            .flat_map(|variant| named(&format!("{}.", variant.name(db).as_str()), variant.fields(db)))
+            .collect(),
+    }
+}
+
+/// Which types reach a `Drop` impl, memoized across the search
+struct DropSearch<'a> {
+    db: &'a RootDatabase,
+    drop_impls: &'a HashMap<Adt, Impl>,
+    /// The `Drop` types reachable from each ADT's fields (and itself)
+    memo: HashMap<Adt, Vec<Adt>>,
+    /// Depth in the walk of the outermost type a recursive type was cut off
+    /// at; results below it miss what that type reaches, so aren't memoized
+    cut_at: usize,
+}
+
+impl DropSearch<'_> {
+    /// Add the types with a `Drop` impl that dropping a value of `ty` runs
+    fn type_drops(&mut self, ty: &Type, out: &mut Vec<Adt>) {
+        let mut stack = Vec::new();
+        self.walk(ty, out, &mut stack);
+    }
+
+    /// `stack` holds the ADTs whose fields are being walked, outermost first
+    fn walk(&mut self, ty: &Type, out: &mut Vec<Adt>, stack: &mut Vec<Adt>) {
+        // References and raw pointers don't own what they point to
+        if ty.is_reference() || ty.is_raw_ptr() {
+            return;
+        }
+        if let Some((element, _)) = ty.as_array(self.db) {
+            self.walk(&element, out, stack);
+            return;
+        }
+        for field in ty.tuple_fields(self.db) {
+            self.walk(&field, out, stack);
+        }
+
+        let Some(adt) = ty.as_adt() else { return };
+        // Type arguments are usually owned, as in `Box<T>` or `Option<T>`
+        for arg in ty.type_arguments() {
+            self.walk(&arg, out, stack);
+        }
+        for reached in self.adt_drops(adt, stack) {
+            if !out.contains(&reached) {
+                out.push(reached);
+            }
+        }
+    }
+
+    /// The `Drop` types an ADT reaches: itself if it implements `Drop`, and
+    /// those of its fields for workspace types
+    fn adt_drops(&mut self, adt: Adt, stack: &mut Vec<Adt>) -> Vec<Adt> {
+        if let Some(reached) = self.memo.get(&adt) {
+            return reached.clone();
+        }
+        // A recursive type reaches nothing more through itself; what it
+        // reaches is added once the walk is back at it
+        if let Some(depth) = stack.iter().position(|&outer| outer == adt) {
+            self.cut_at = self.cut_at.min(depth);
+            return Vec::new();
+        }
+        let depth = stack.len();
+        stack.push(adt);
+        let outer_cut = std::mem::replace(&mut self.cut_at, usize::MAX);
+
+        let mut reached = Vec::new();
+        if self.drop_impls.contains_key(&adt) {
+            reached.push(adt);
+        }
+        // Library types only count through their own impl, not their private fields
+        if adt.module(self.db).krate().origin(self.db).is_local() {
+            for (_, field) in adt_fields(self.db, adt) {
+                self.walk(&field.ty(self.db), &mut reached, stack);
+            }
+        }
+        stack.pop();
+
+        // Cut-offs at this type are made up for by its own result; those
+        // further out leave it incomplete until the walk gets back there
+        if self.cut_at >= depth {
+            self.memo.insert(adt, reached.clone());
+            self.cut_at = outer_cut;
+        } else {
+            self.cut_at = self.cut_at.min(outer_cut);
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scratch::ScratchProject;
+
+    #[test]
+    fn test_drop_types_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Without library impls, only types reaching a workspace `Drop` impl
+        let own = analyzer.drop_types(false).unwrap();
+        for ty in &own {
+            assert!(ty.drop_impl.is_some() || !ty.fields.is_empty());
+        }
+
+        // `Analyzer` holds an `Option<Vec<SymbolInfo>>`, and `Vec` implements `Drop`
+        let all = analyzer.drop_types(true).unwrap();
+        assert!(all.len() >= own.len());
+        let analyzer_type = all.iter().find(|t| t.path == "cratographer::analyzer::Analyzer").unwrap();
+        let field = analyzer_type.fields.iter().find(|f| f.name == "library_symbols").unwrap();
+        assert!(field.drops.contains(&"Vec".to_string()));
+    }
+
+    #[test]
+    fn test_mutually_recursive_types_keep_their_drops() {
+        // Walking `DropProbeD` reaches `DropProbeB` while `DropProbeA` is being walked
+        let analyzer = ScratchProject::new(
+            "drop-types",
+            &[(
+                "recursive.rs",
+                "pub struct DropProbeD { pub a: DropProbeA }\n\
+                 pub struct DropProbeA { pub g: DropProbeGuard, pub b: Box<DropProbeB> }\n\
+                 pub struct DropProbeB { pub a: Option<Box<DropProbeA>> }\n\
+                 pub struct DropProbeC { pub b: DropProbeB }\n\
+                 pub struct DropProbeGuard;\n\
+                 impl Drop for DropProbeGuard { fn drop(&mut self) {} }\n",
+            )],
+        );
+
+        let types = analyzer.drop_types(false).unwrap();
+        let field_drops = |ty: &str, field: &str| {
+            let ty = types.iter().find(|t| t.path.ends_with(ty)).unwrap_or_else(|| panic!("{} should be listed", ty));
+            ty.fields.iter().find(|f| f.name == field).map(|f| f.drops.clone()).unwrap_or_default()
+        };
+        for (ty, field) in [("DropProbeD", "a"), ("DropProbeA", "b"), ("DropProbeB", "a"), ("DropProbeC", "b")] {
+            assert!(field_drops(ty, field).contains(&"DropProbeGuard".to_string()), "{}.{} should reach DropProbeGuard", ty, field);
+        }
+    }
+}
@@ -0,0 +1,193 @@
+//! Non-Rust files embedded by macros
+//!
+//! `include_str!`, `include_bytes!` and `include!` pull files into a crate
+//! at compile time, as do sqlx's `query_file!` family and `migrate!`. A
+//! change to such a `.sql`, `.html` or `.json` file changes the Rust items
+//! embedding it, which no reference search shows. This lists every
+//! embedding with the file it resolves to and the item it is in, so a change
+//! on either side can be traced to the other.
+//!
+//! `include*!` paths are relative to the file containing the call, sqlx's to
+//! the package's manifest directory. Paths built with `concat!` or `env!`,
+//! such as generated files in `OUT_DIR`, are only known after a build and
+//! are reported unresolved. Like `build_env_usages`, this works on the
+//! syntax tree, matching the macros by name.
+
+use super::build_env::macro_uses;
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{ast, AstNode, SyntaxNode};
+use std::path::{Component, Path, PathBuf};
+
+/// The macros reported: std's, then sqlx's
+pub const EMBEDDING_MACROS: &[&str] = &[
+    "include_str",
+    "include_bytes",
+    "include",
+    "query_file",
+    "query_file_as",
+    "query_file_scalar",
+    "query_file_unchecked",
+    "query_file_as_unchecked",
+    "query_file_scalar_unchecked",
+    "migrate",
+];
+
+/// A file or directory embedded by a macro
+#[derive(Debug, Clone)]
+pub struct EmbeddedFile {
+    /// One of `EMBEDDING_MACROS`
+    pub macro_name: &'static str,
+    /// The path as written, when given as a string literal
+    pub argument: Option<String>,
+    /// The embedded file, or directory for `migrate!`, as an absolute path
+    pub resolved_path: Option<String>,
+    /// Whether `resolved_path` exists on disk
+    pub exists: bool,
+    /// The Rust file embedding it
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing item, e.g. `load_user` or `impl UserStore`
+    pub item: Option<String>,
+    /// Item kind as in `enumerate_file`, e.g. "Function"
+    pub item_kind: Option<&'static str>,
+}
+
+impl Analyzer {
+    /// Every file embedded by `EMBEDDING_MACROS` in the workspace, by file and
+    /// then in source order
+    ///
+    /// With `embedded`, only the embeddings of that file, or of the files
+    /// below it for a directory; a relative path is taken from the project root.
+    pub fn embedded_files(&self, embedded: Option<&str>) -> Result<Vec<EmbeddedFile>, AnalyzerError> {
+        let wanted = embedded.map(|path| match (&self.project_root, Path::new(path).is_relative()) {
+            (Some(root), true) => normalize(&root.join(path)),
+            _ => normalize(Path::new(path)),
+        });
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut embeddings = Vec::new();
+        for (file_path, file_id) in files {
+            embeddings.extend(self.file_embeddings(file_id, &file_path)?.into_iter().filter(|embedding| {
+                wanted.as_ref().is_none_or(|wanted| {
+                    embedding.resolved_path.as_ref().is_some_and(|resolved| Path::new(resolved).starts_with(wanted))
+                })
+            }));
+        }
+        Ok(embeddings)
+    }
+
+    fn file_embeddings(&self, file_id: FileId, file_path: &str) -> Result<Vec<EmbeddedFile>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let source = analysis.parse(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let mut embeddings = Vec::new();
+        for (macro_name, name_token, args) in macro_uses(source.syntax(), EMBEDDING_MACROS) {
+            let argument = first_string_argument(&args);
+            let base = if macro_name.starts_with("include") {
+                Path::new(file_path).parent().map(Path::to_path_buf)
+            } else {
+                manifest_dir(Path::new(file_path))
+            };
+            let resolved = match (&argument, base) {
+                (Some(argument), Some(base)) => Some(normalize(&base.join(argument))),
+                // `migrate!()` reads `./migrations`
+                (None, Some(base)) if macro_name == "migrate" && args.text().to_string().trim() == "()" => {
+                    Some(base.join("migrations"))
+                }
+                _ => None,
+            };
+            let (item, item_kind) = name_token
+                .parent_ancestors()
+                .filter_map(ast::Item::cast)
+                .find_map(|item| item_label(&item))
+                .map_or((None, None), |(name, kind)| (Some(name), Some(kind)));
+            embeddings.push(EmbeddedFile {
+                macro_name,
+                argument,
+                exists: resolved.as_ref().is_some_and(|path| path.exists()),
+                resolved_path: resolved.map(|path| path.to_string_lossy().into_owned()),
+                file_path: file_path.to_string(),
+                line: line_index.line_col(name_token.text_range().start()).line,
+                item,
+                item_kind,
+            });
+        }
+        Ok(embeddings)
+    }
+}
+
+/// The first top-level argument that is a string literal; sqlx's `_as`
+/// macros take the record type before the path
+fn first_string_argument(args: &SyntaxNode) -> Option<String> {
+    args.children_with_tokens()
+        .filter_map(|child| child.into_token())
+        .find_map(ast::String::cast)
+        .and_then(|literal| literal.value().ok().map(|value| value.to_string()))
+}
+
+/// The directory of the closest `Cargo.toml` above a source file
+fn manifest_dir(file: &Path) -> Option<PathBuf> {
+    file.ancestors().skip(1).find(|dir| dir.join("Cargo.toml").is_file()).map(Path::to_path_buf)
+}
+
+/// A path with `.` and `..` components folded away, without touching the disk
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_path_arguments() {
+        let file = SourceFile::parse(
+            r#"
+const SCHEMA: &str = include_str!("../sql/schema.sql");
+fn user(id: i64) { sqlx::query_file_as!(User, "queries/user.sql", id); }
+static MIGRATOR: Migrator = sqlx::migrate!();
+include!(concat!(env!("OUT_DIR"), "/gen.rs"));
+"#,
+            Edition::CURRENT,
+        )
+        .tree();
+        let uses: Vec<_> =
+            macro_uses(file.syntax(), EMBEDDING_MACROS).into_iter().map(|(name, _, args)| (name, first_string_argument(&args))).collect();
+
+        assert_eq!(
+            uses,
+            vec![
+                ("include_str", Some("../sql/schema.sql".to_string())),
+                ("query_file_as", Some("queries/user.sql".to_string())),
+                ("migrate", None),
+                ("include", None),
+            ]
+        );
+        assert_eq!(normalize(Path::new("/p/src/../sql/./schema.sql")), PathBuf::from("/p/sql/schema.sql"));
+    }
+
+    #[test]
+    fn test_embedded_files_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Every embedding found resolves next to the file or package embedding it
+        let embeddings = analyzer.embedded_files(None).unwrap();
+        assert!(embeddings.iter().all(|e| e.argument.is_none() || e.resolved_path.is_some()));
+        assert!(analyzer.embedded_files(Some("no/such/file.sql")).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+//! Workspace entry points
+//!
+//! Lists everything reachable from outside the code itself: `main` functions
+//! of binaries, build scripts, the public API of library targets, items
+//! exported to foreign code with `#[no_mangle]` or `#[export_name]`, and test
+//! functions. Threat modeling and onboarding both start from this list.
+
+use super::api_exposure::{file_module_public, inline_modules_public};
+use super::module_summary::enclosing_modules;
+use super::move_item::module_path;
+use super::{item_label, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, Semantics};
+use ra_ap_syntax::{
+    ast::{self, HasAttrs, HasName},
+    AstNode, SourceFile,
+};
+use std::collections::HashMap;
+
+/// Kinds of entry point, in report order
+pub const ENTRY_POINT_KINDS: &[&str] = &["main", "build_script", "public_api", "no_mangle", "test"];
+
+/// What a crate of the workspace is built as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TargetKind {
+    Library,
+    Binary,
+    BuildScript,
+    /// Integration tests and benchmarks
+    Harness,
+}
+
+/// One externally reachable item
+#[derive(Debug, Clone)]
+pub struct EntryPoint {
+    /// One of `ENTRY_POINT_KINDS`
+    pub kind: &'static str,
+    /// Path of the item, starting with its crate name; the re-exported tree
+    /// for `pub use`
+    pub name: String,
+    /// Item kind as in `enumerate_file`, e.g. "Function", or "Use" for re-exports
+    pub item_kind: &'static str,
+    pub crate_name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl Analyzer {
+    /// List the entry points of every workspace crate
+    pub fn entry_points(&self) -> Result<Vec<EntryPoint>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut targets: HashMap<Crate, TargetKind> = HashMap::new();
+        let mut entries = Vec::new();
+
+        for file_id in self.workspace_files()? {
+            let Some(module) = sema.file_to_module_def(file_id) else { continue };
+            let krate = module.krate();
+            let target = *targets.entry(krate).or_insert_with(|| {
+                let root_file = krate.root_file(db);
+                target_kind(&self.file_path(root_file), &sema.parse_guess_edition(root_file))
+            });
+            let crate_name = krate.display_name(db).map(|n| n.to_string()).unwrap_or_else(|| "unnamed".to_string());
+            let is_root = module.parent(db).is_none();
+            let public_file = target == TargetKind::Library && file_module_public(&sema, file_id);
+            let source = sema.parse_guess_edition(file_id);
+
+            for item in source.syntax().descendants().filter_map(ast::Item::cast) {
+                let inline_modules = enclosing_modules(&item);
+                let path = |name: &str| {
+                    let mut segments = vec![module_path(db, module).replacen("crate", &crate_name, 1)];
+                    segments.extend(inline_modules.iter().flatten().cloned());
+                    segments.push(name.to_string());
+                    segments.join("::")
+                };
+
+                let (kind, name, item_kind) = if let Some(kind) = attribute_entry(&item) {
+                    let Some((name, item_kind)) = item_label(&item) else { continue };
+                    (kind, path(&name), item_kind)
+                } else if is_root && inline_modules.as_ref().is_some_and(|m| m.is_empty()) && is_main(&item) {
+                    match target {
+                        TargetKind::Binary => ("main", path("main"), "Function"),
+                        TargetKind::BuildScript => ("build_script", path("main"), "Function"),
+                        _ => continue,
+                    }
+                } else if public_file && inline_modules.is_some() && inline_modules_public(item.syntax()) && is_public_item(&item) {
+                    match &item {
+                        ast::Item::Use(use_) => {
+                            let Some(tree) = use_.use_tree() else { continue };
+                            ("public_api", path(&normalize_whitespace(&tree.syntax().text().to_string())), "Use")
+                        }
+                        _ => {
+                            let Some((name, item_kind)) = item_label(&item) else { continue };
+                            ("public_api", path(&name), item_kind)
+                        }
+                    }
+                } else {
+                    continue;
+                };
+
+                let (start_line, end_line) = self.line_range(file_id, item.syntax().text_range())?;
+                entries.push(EntryPoint {
+                    kind,
+                    name,
+                    item_kind,
+                    crate_name: crate_name.clone(),
+                    file_path: self.file_path(file_id),
+                    start_line,
+                    end_line,
+                });
+            }
+        }
+
+        entries.sort_by(|a, b| {
+            let rank = |kind| ENTRY_POINT_KINDS.iter().position(|k| *k == kind);
+            (rank(a.kind), &a.crate_name, &a.file_path, a.start_line).cmp(&(rank(b.kind), &b.crate_name, &b.file_path, b.start_line))
+        });
+
+        Ok(entries)
+    }
+}
+
+/// Classify a crate by its root file
+///
+/// Build scripts are `build.rs`; roots declaring `fn main` are binaries;
+/// roots under `tests/` or `benches/` run under the test harness. Everything
+/// else is a library.
+fn target_kind(root_path: &str, root: &SourceFile) -> TargetKind {
+    if root_path.ends_with("/build.rs") {
+        TargetKind::BuildScript
+    } else if root.items().any(|item| is_main(&item)) {
+        TargetKind::Binary
+    } else if root_path.contains("/tests/") || root_path.contains("/benches/") {
+        TargetKind::Harness
+    } else {
+        TargetKind::Library
+    }
+}
+
+fn is_main(item: &ast::Item) -> bool {
+    matches!(item, ast::Item::Fn(f) if f.name().is_some_and(|n| n.text() == "main"))
+}
+
+/// Entry point kind given by an item's attributes: exported symbols and tests
+pub(super) fn attribute_entry(item: &ast::Item) -> Option<&'static str> {
+    item.attrs().find_map(|attr| {
+        let name = attr.path()?.segment()?.name_ref()?.text().to_string();
+        match name.as_str() {
+            "no_mangle" | "export_name" => Some("no_mangle"),
+            // `#[test]`, and harness attributes such as `#[tokio::test]`
+            "test" if matches!(item, ast::Item::Fn(_)) => Some("test"),
+            _ => None,
+        }
+    })
+}
+
+/// Whether an item is `pub`, or a `#[macro_export]` macro
+fn is_public_item(item: &ast::Item) -> bool {
+    if let ast::Item::MacroRules(macro_) = item {
+        return macro_.attrs().any(|attr| attr.path().is_some_and(|p| p.syntax().text() == "macro_export"));
+    }
+    if matches!(item, ast::Item::Impl(_) | ast::Item::Module(_)) {
+        return false;
+    }
+    item.syntax()
+        .children()
+        .find_map(ast::Visibility::cast)
+        .is_some_and(|v| v.syntax().text() == "pub")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::Edition;
+
+    fn items(src: &str) -> Vec<ast::Item> {
+        SourceFile::parse(src, Edition::CURRENT).tree().syntax().descendants().filter_map(ast::Item::cast).collect()
+    }
+
+    #[test]
+    fn test_target_kinds() {
+        let parse = |src| SourceFile::parse(src, Edition::CURRENT).tree();
+        assert_eq!(target_kind("/p/build.rs", &parse("fn main() {}")), TargetKind::BuildScript);
+        assert_eq!(target_kind("/p/src/main.rs", &parse("fn main() {}")), TargetKind::Binary);
+        assert_eq!(target_kind("/p/tests/api.rs", &parse("#[test] fn t() {}")), TargetKind::Harness);
+        assert_eq!(target_kind("/p/src/lib.rs", &parse("pub fn f() {}")), TargetKind::Library);
+    }
+
+    #[test]
+    fn test_attribute_entries() {
+        let kinds: Vec<_> = items(
+            "#[no_mangle] pub extern \"C\" fn a() {} #[unsafe(no_mangle)] fn b() {} #[export_name = \"c\"] static C: u8 = 0;
+             #[test] fn d() {} #[tokio::test] async fn e() {} #[cfg(test)] mod tests {} fn f() {}",
+        )
+        .iter()
+        .map(attribute_entry)
+        .collect();
+
+        assert_eq!(
+            kinds,
+            vec![Some("no_mangle"), Some("no_mangle"), Some("no_mangle"), Some("test"), Some("test"), None, None]
+        );
+    }
+
+    #[test]
+    fn test_public_items() {
+        let public: Vec<_> = items(
+            "pub fn a() {} pub(crate) fn b() {} fn c() {} pub use x::Y; #[macro_export] macro_rules! m { () => {} } impl S {}",
+        )
+        .iter()
+        .map(is_public_item)
+        .collect();
+
+        assert_eq!(public, vec![true, false, false, true, true, false]);
+    }
+
+    #[test]
+    fn test_entry_points_of_cratographer() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let entries = analyzer.entry_points().unwrap();
+        assert!(entries.iter().any(|e| e.kind == "main" && e.file_path.ends_with("src/main.rs")));
+        assert!(entries.iter().any(|e| e.kind == "test" && e.name.ends_with("test_entry_points_of_cratographer")));
+    }
+}
@@ -0,0 +1,287 @@
+//! Error-handling style inventory
+//!
+//! Before a team standardizes on an error strategy, it needs to know what
+//! the code does today. Per workspace crate this counts, with locations:
+//!
+//! - `unwrap` and `expect`: `.unwrap()` and `.expect(..)` calls, which
+//!   panic instead of propagating;
+//! - `try_operator`: uses of `?`;
+//! - `anyhow_macro`: `anyhow!`, `bail!`, `ensure!` and `format_err!`
+//!   from the anyhow crate;
+//! - `error_type`: types implementing `std::error::Error`, the crate's own
+//!   error types;
+//! - `boxed_error`: `Box<dyn Error>` in the signatures of public functions,
+//!   which callers can't match on.
+//!
+//! Test code (`#[test]` functions and `#[cfg(test)]` modules) is left out
+//! unless asked for, since unwrapping is the norm there.
+
+use super::budget::{Budget, Coverage};
+use super::entry_points::attribute_entry;
+use super::features::{module_cfgs, node_cfgs, CfgExpr};
+use super::reference_index::definition_key;
+use super::{normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Impl, Semantics};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasArgList, HasName, HasVisibility},
+    AstNode, SyntaxNode,
+};
+use std::collections::BTreeMap;
+
+/// Kinds of error-handling site, in report order
+pub const ERROR_HANDLING_KINDS: &[&str] = &["unwrap", "expect", "try_operator", "anyhow_macro", "error_type", "boxed_error"];
+
+/// Macros of the anyhow crate that build or return errors
+const ANYHOW_MACROS: &[&str] = &["anyhow", "bail", "ensure", "format_err"];
+
+/// One error-handling site
+#[derive(Debug, Clone)]
+pub struct ErrorSite {
+    /// One of `ERROR_HANDLING_KINDS`
+    pub kind: &'static str,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing function or type, if any
+    pub item: Option<String>,
+    /// The site as written, shortened to one line, e.g. `config.get("port").unwrap()`
+    pub text: String,
+}
+
+/// The error-handling sites of one crate
+#[derive(Debug, Clone)]
+pub struct CrateErrorHandling {
+    pub crate_name: String,
+    /// Count per kind, for every kind of `ERROR_HANDLING_KINDS`
+    pub counts: Vec<(&'static str, usize)>,
+    /// By file and line
+    pub sites: Vec<ErrorSite>,
+}
+
+/// The error-handling inventory of the workspace files scanned within the budget
+#[derive(Debug, Clone)]
+pub struct ErrorHandlingInventory {
+    /// By crate name
+    pub crates: Vec<CrateErrorHandling>,
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// Inventory the error handling of every workspace crate
+    pub fn error_handling_inventory(&self, include_tests: bool, budget: Budget) -> Result<ErrorHandlingInventory, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut by_crate: BTreeMap<String, Vec<ErrorSite>> = BTreeMap::new();
+        let test_cfg = CfgExpr::Atom("test".to_string());
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        for (file_path, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            coverage.files_scanned += 1;
+            if !include_tests && module_cfgs(&sema, db, file_id).contains(&test_cfg) {
+                continue;
+            }
+            let Some(krate) = sema.file_to_module_def(file_id).map(|module| module.krate()) else { continue };
+            let crate_name = krate.display_name(db).map(|name| name.to_string()).unwrap_or_default();
+
+            let sites = self.file_error_sites(&sema, file_id, &file_path, include_tests)?;
+            by_crate.entry(crate_name).or_default().extend(sites);
+        }
+
+        let crates = by_crate
+            .into_iter()
+            .map(|(crate_name, sites)| CrateErrorHandling {
+                crate_name,
+                counts: ERROR_HANDLING_KINDS.iter().map(|kind| (*kind, sites.iter().filter(|s| s.kind == *kind).count())).collect(),
+                sites,
+            })
+            .collect();
+        Ok(ErrorHandlingInventory { crates, coverage })
+    }
+
+    fn file_error_sites(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        file_id: FileId,
+        file_path: &str,
+        include_tests: bool,
+    ) -> Result<Vec<ErrorSite>, AnalyzerError> {
+        let db = sema.db;
+        let source = sema.parse_guess_edition(file_id);
+
+        let mut sites = Vec::new();
+        for node in source.syntax().descendants() {
+            let kind = if let Some(call) = ast::MethodCallExpr::cast(node.clone()) {
+                let args = call.arg_list().map_or(0, |list| list.args().count());
+                match call.name_ref().map(|name| name.text().to_string()).as_deref() {
+                    Some("unwrap") if args == 0 => "unwrap",
+                    Some("expect") if args == 1 => "expect",
+                    _ => continue,
+                }
+            } else if ast::TryExpr::can_cast(node.kind()) {
+                "try_operator"
+            } else if let Some(call) = ast::MacroCall::cast(node.clone()) {
+                if !is_anyhow_macro(sema, &call) {
+                    continue;
+                }
+                "anyhow_macro"
+            } else if let Some(adt) = ast::Adt::cast(node.clone()) {
+                if !sema.to_def(&adt).is_some_and(|adt| implements_error(db, adt)) {
+                    continue;
+                }
+                "error_type"
+            } else if let Some(func) = ast::Fn::cast(node.clone()) {
+                if !func.visibility().is_some_and(|vis| vis.syntax().text() == "pub") || !boxed_error_in_signature(&func) {
+                    continue;
+                }
+                "boxed_error"
+            } else {
+                continue;
+            };
+            if !include_tests && in_test_code(&node) {
+                continue;
+            }
+
+            let (line, _) = self.line_range(file_id, node.text_range())?;
+            sites.push(ErrorSite {
+                kind,
+                file_path: file_path.to_string(),
+                line,
+                item: enclosing_item(&node, kind),
+                text: site_text(&node),
+            });
+        }
+        Ok(sites)
+    }
+}
+
+/// Whether a macro call is one of anyhow's; by name when it doesn't resolve
+fn is_anyhow_macro(sema: &Semantics<'_, RootDatabase>, call: &ast::MacroCall) -> bool {
+    let Some(name) = call.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref()) else { return false };
+    if !ANYHOW_MACROS.contains(&name.text().as_str()) {
+        return false;
+    }
+    match sema.resolve_macro_call(call) {
+        Some(macro_) => macro_.module(sema.db).krate().display_name(sema.db).is_some_and(|krate| krate.to_string() == "anyhow"),
+        None => true,
+    }
+}
+
+/// Whether a type implements `std::error::Error` itself
+fn implements_error(db: &RootDatabase, adt: Adt) -> bool {
+    Impl::all_for_type(db, adt.ty(db))
+        .into_iter()
+        .filter_map(|impl_| impl_.trait_(db))
+        .filter_map(|trait_| definition_key(db, Definition::Trait(trait_)))
+        .any(|path| path == "core::error::Error" || path == "std::error::Error")
+}
+
+/// Whether a function's parameters or return type contain `Box<dyn ..Error..>`
+fn boxed_error_in_signature(func: &ast::Fn) -> bool {
+    let types = func
+        .param_list()
+        .into_iter()
+        .flat_map(|list| list.params())
+        .filter_map(|param| param.ty())
+        .chain(func.ret_type().and_then(|ret| ret.ty()));
+    types.flat_map(|ty| ty.syntax().descendants().filter_map(ast::PathType::cast).collect::<Vec<_>>()).any(|path_ty| {
+        let Some(segment) = path_ty.path().and_then(|path| path.segment()) else { return false };
+        segment.name_ref().is_some_and(|name| name.text() == "Box")
+            && segment.generic_arg_list().is_some_and(|args| {
+                args.syntax()
+                    .descendants()
+                    .filter_map(ast::DynTraitType::cast)
+                    .any(|dyn_ty| dyn_ty.syntax().descendants().filter_map(ast::NameRef::cast).any(|name| name.text() == "Error"))
+            })
+    })
+}
+
+/// Whether a node is inside a `#[test]` function or under `#[cfg(test)]`
+fn in_test_code(node: &SyntaxNode) -> bool {
+    let test_cfg = CfgExpr::Atom("test".to_string());
+    node_cfgs(node).contains(&test_cfg)
+        || node.ancestors().filter_map(ast::Item::cast).any(|item| attribute_entry(&item) == Some("test"))
+}
+
+/// Name of the function or type a site belongs to: the type itself for
+/// `error_type`, the function itself for `boxed_error`
+fn enclosing_item(node: &SyntaxNode, kind: &str) -> Option<String> {
+    let mut ancestors = node.ancestors();
+    if !matches!(kind, "error_type" | "boxed_error") {
+        ancestors.next();
+    }
+    ancestors.find_map(|ancestor| {
+        if let Some(func) = ast::Fn::cast(ancestor.clone()) {
+            return func.name().map(|name| name.text().to_string());
+        }
+        ast::Adt::cast(ancestor).and_then(|adt| adt.name()).map(|name| name.text().to_string())
+    })
+}
+
+/// The first line of a site's text, shortened
+fn site_text(node: &SyntaxNode) -> String {
+    let text = node.text().to_string();
+    let first = normalize_whitespace(text.lines().next().unwrap_or_default());
+    if first.chars().count() > 100 {
+        format!("{}...", first.chars().take(100).collect::<String>())
+    } else {
+        first
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_boxed_error_and_test_code() {
+        let file = SourceFile::parse(
+            r#"
+pub fn load(path: &str) -> Result<String, Box<dyn std::error::Error + Send>> { todo!() }
+pub fn parse(input: Box<dyn Display>) -> Result<u8, ParseError> { todo!() }
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn loads() { load("x").unwrap(); }
+}
+fn lib() { Some(1).unwrap(); }
+"#,
+            Edition::CURRENT,
+        )
+        .tree();
+        let fns: Vec<ast::Fn> = file.syntax().descendants().filter_map(ast::Fn::cast).collect();
+        assert!(boxed_error_in_signature(&fns[0]));
+        assert!(!boxed_error_in_signature(&fns[1]));
+
+        let unwraps: Vec<SyntaxNode> = file
+            .syntax()
+            .descendants()
+            .filter(|node| ast::MethodCallExpr::cast(node.clone()).is_some_and(|call| call.name_ref().is_some_and(|n| n.text() == "unwrap")))
+            .collect();
+        assert_eq!(unwraps.iter().map(in_test_code).collect::<Vec<_>>(), vec![true, false]);
+        assert_eq!(enclosing_item(&unwraps[1], "unwrap").as_deref(), Some("lib"));
+        assert_eq!(site_text(&unwraps[1]), "Some(1).unwrap()");
+    }
+
+    #[test]
+    fn test_error_handling_inventory_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let inventory = analyzer.error_handling_inventory(false, Budget::unlimited()).unwrap();
+        assert!(inventory.coverage.complete());
+        let krate = inventory.crates.iter().find(|c| c.crate_name == "cratographer").unwrap();
+        assert_eq!(krate.counts.len(), ERROR_HANDLING_KINDS.len());
+        // The analyzer propagates with `?` and defines AnalyzerError
+        assert!(krate.sites.iter().any(|s| s.kind == "try_operator"));
+        assert!(krate.sites.iter().any(|s| s.kind == "error_type" && s.item.as_deref() == Some("AnalyzerError")));
+        // Test functions, which unwrap freely, are left out
+        assert!(krate.sites.iter().all(|s| !s.item.as_ref().is_some_and(|item| item.starts_with("test_"))));
+    }
+}
@@ -0,0 +1,206 @@
+//! Index change events
+//!
+//! A bounded log of what changed in the index: files re-indexed, symbols that
+//! appeared or disappeared, and crate graph reloads. Clients poll it with the
+//! last sequence number they saw to learn whether their cached knowledge of
+//! the codebase is stale.
+
+use super::{convert_symbol_kind, Analyzer, SymbolKind};
+use ra_ap_ide::{FileId, StructureNodeKind};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// A symbol as far as change events tell symbols apart
+pub(super) type SymbolKey = (String, SymbolKind);
+
+/// Number of events kept before the oldest are dropped
+const EVENT_LOG_CAPACITY: usize = 1000;
+
+/// What happened to the index
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndexEventKind {
+    /// A file's contents changed and it was re-indexed
+    FileReindexed { file_path: String },
+    /// A file was removed from the index
+    FileRemoved { file_path: String },
+    /// A symbol now exists that didn't before
+    SymbolAdded { file_path: String, name: String, kind: SymbolKind },
+    /// A symbol that existed before is gone
+    SymbolRemoved { file_path: String, name: String, kind: SymbolKind },
+    /// The project was (re)loaded with a new crate graph
+    CrateGraphReloaded,
+}
+
+/// A single entry in the event log
+#[derive(Debug, Clone)]
+pub struct IndexEvent {
+    /// Monotonically increasing sequence number, starting at 1
+    pub sequence: u64,
+    /// Index revision after the change
+    pub revision: u64,
+    pub kind: IndexEventKind,
+}
+
+/// Events newer than a given sequence number
+#[derive(Debug, Clone)]
+pub struct IndexEvents {
+    pub events: Vec<IndexEvent>,
+    /// Sequence number of the newest event, to pass as `since` next time
+    pub latest_sequence: u64,
+    /// Whether older events the caller hasn't seen were already dropped, in
+    /// which case all cached knowledge should be treated as stale
+    pub truncated: bool,
+}
+
+/// Bounded log of index events
+#[derive(Debug)]
+pub struct EventLog {
+    events: VecDeque<IndexEvent>,
+    next_sequence: u64,
+    capacity: usize,
+}
+
+impl EventLog {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self { events: VecDeque::new(), next_sequence: 1, capacity }
+    }
+
+    pub fn push(&mut self, revision: u64, kind: IndexEventKind) {
+        self.events.push_back(IndexEvent { sequence: self.next_sequence, revision, kind });
+        self.next_sequence += 1;
+        while self.events.len() > self.capacity {
+            self.events.pop_front();
+        }
+    }
+
+    /// Events with a sequence number greater than `since`
+    pub fn since(&self, since: u64) -> IndexEvents {
+        let oldest = self.events.front().map_or(self.next_sequence, |e| e.sequence);
+        IndexEvents {
+            events: self.events.iter().filter(|e| e.sequence > since).cloned().collect(),
+            latest_sequence: self.next_sequence - 1,
+            truncated: since.saturating_add(1) < oldest,
+        }
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::with_capacity(EVENT_LOG_CAPACITY)
+    }
+}
+
+impl Analyzer {
+    /// Index events newer than the given sequence number
+    pub fn index_events(&self, since: u64) -> IndexEvents {
+        self.events.since(since)
+    }
+
+    /// Symbols of the given files as currently indexed, for diffing after a change
+    pub(super) fn snapshot_symbols(&self, files: &[FileId]) -> HashMap<FileId, Vec<SymbolKey>> {
+        files
+            .iter()
+            .filter_map(|&file_id| self.symbol_keys(file_id).map(|symbols| (file_id, symbols)))
+            .collect()
+    }
+
+    /// Names and kinds of a file's symbols
+    ///
+    /// Taken straight from the file structure: unlike `file_symbols` this
+    /// needs no semantic analysis, which matters as it runs on every change.
+    fn symbol_keys(&self, file_id: FileId) -> Option<Vec<SymbolKey>> {
+        let config = ra_ap_ide::FileStructureConfig { exclude_locals: true };
+        let structure = self.host.analysis().file_structure(&config, file_id).ok()?;
+        Some(
+            structure
+                .into_iter()
+                .filter_map(|node| match node.kind {
+                    StructureNodeKind::SymbolKind(kind) => convert_symbol_kind(kind).map(|kind| (node.label, kind)),
+                    _ => None,
+                })
+                .collect(),
+        )
+    }
+
+    /// Record events for applied file changes, diffing symbols against `before`
+    pub(super) fn record_file_events(&mut self, before: HashMap<FileId, Vec<SymbolKey>>, changed: &[FileId], deleted: &[FileId]) {
+        for &file_id in changed {
+            let file_path = self.file_path(file_id);
+            let after = self.symbol_keys(file_id).unwrap_or_default();
+            let old = before.get(&file_id).map(Vec::as_slice).unwrap_or_default();
+
+            self.events.push(self.revision, IndexEventKind::FileReindexed { file_path: file_path.clone() });
+            for kind in diff_symbols(&file_path, old, &after) {
+                self.events.push(self.revision, kind);
+            }
+        }
+
+        for &file_id in deleted {
+            let file_path = self.file_path(file_id);
+            let old = before.get(&file_id).map(Vec::as_slice).unwrap_or_default();
+
+            self.events.push(self.revision, IndexEventKind::FileRemoved { file_path: file_path.clone() });
+            for kind in diff_symbols(&file_path, old, &[]) {
+                self.events.push(self.revision, kind);
+            }
+        }
+    }
+}
+
+/// Symbols added and removed between two versions of a file, compared by name and kind
+fn diff_symbols(file_path: &str, before: &[SymbolKey], after: &[SymbolKey]) -> Vec<IndexEventKind> {
+    let old: HashSet<&SymbolKey> = before.iter().collect();
+    let new: HashSet<&SymbolKey> = after.iter().collect();
+
+    let removed = before.iter().filter(|k| !new.contains(k)).map(|(name, kind)| IndexEventKind::SymbolRemoved {
+        file_path: file_path.to_string(),
+        name: name.clone(),
+        kind: *kind,
+    });
+    let added = after.iter().filter(|k| !old.contains(k)).map(|(name, kind)| IndexEventKind::SymbolAdded {
+        file_path: file_path.to_string(),
+        name: name.clone(),
+        kind: *kind,
+    });
+
+    removed.chain(added).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn symbol(name: &str, kind: SymbolKind) -> SymbolKey {
+        (name.to_string(), kind)
+    }
+
+    #[test]
+    fn test_diff_symbols() {
+        let before = vec![symbol("Foo", SymbolKind::Struct), symbol("bar", SymbolKind::Function)];
+        let after = vec![symbol("Foo", SymbolKind::Enum), symbol("bar", SymbolKind::Function)];
+
+        let events = diff_symbols("lib.rs", &before, &after);
+        assert_eq!(events, vec![
+            IndexEventKind::SymbolRemoved { file_path: "lib.rs".to_string(), name: "Foo".to_string(), kind: SymbolKind::Struct },
+            IndexEventKind::SymbolAdded { file_path: "lib.rs".to_string(), name: "Foo".to_string(), kind: SymbolKind::Enum },
+        ]);
+    }
+
+    #[test]
+    fn test_event_log_polling_and_truncation() {
+        let mut log = EventLog::with_capacity(2);
+        log.push(1, IndexEventKind::CrateGraphReloaded);
+        log.push(2, IndexEventKind::FileReindexed { file_path: "a.rs".to_string() });
+
+        let all = log.since(0);
+        assert_eq!(all.events.len(), 2);
+        assert_eq!(all.latest_sequence, 2);
+        assert!(!all.truncated);
+        assert!(log.since(2).events.is_empty());
+
+        log.push(3, IndexEventKind::FileReindexed { file_path: "b.rs".to_string() });
+        let after_drop = log.since(0);
+        assert!(after_drop.truncated, "Event 1 was dropped, so a caller at 0 missed it");
+        assert!(!log.since(1).truncated);
+        assert!(log.since(u64::MAX).events.is_empty());
+    }
+}
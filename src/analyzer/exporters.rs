@@ -0,0 +1,126 @@
+//! Who exports a name
+//!
+//! For a bare identifier such as `Error`, lists every importable item of that
+//! name in the crate graph: the crate and module defining it, its kind, and
+//! the `use` path that would bring it into scope from a given module. Settles
+//! "there are three `Error` types in scope, which one is this" situations.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, FindPathConfig, ItemInNs, Module, ModuleDef, Semantics};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+
+/// An item exporting the queried name
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    /// Full path of the definition, e.g. `std::io::error::Error`
+    pub path: String,
+    /// One of "mod", "struct", "enum", "union", "variant", "trait", "type",
+    /// "fn", "const", "static" or "macro"
+    pub kind: &'static str,
+    pub crate_name: Option<String>,
+    /// Whether the defining crate is part of the workspace
+    pub local: bool,
+    /// The `use` path to import it from the context module, preferring
+    /// public re-exports such as `std::io::Error`; `None` if it can't be
+    /// imported from there (e.g. its crate isn't a dependency)
+    pub import: Option<String>,
+    pub file_path: String,
+    pub line: u32,
+}
+
+impl Analyzer {
+    /// List the importable items named `name`, with import paths as seen from
+    /// the module of `file_path` (default: the first workspace crate's root)
+    pub fn who_exports(&self, name: &str, file_path: Option<&str>) -> Result<Vec<Exporter>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let context = match file_path {
+            Some(path) => sema.file_to_module_def(self.file_id(path)?),
+            None => Crate::all(db)
+                .into_iter()
+                .find(|krate| krate.origin(db).is_local())
+                .and_then(|krate| sema.file_to_module_def(krate.root_file(db))),
+        }
+        .ok_or_else(|| AnalyzerError::Other("No module to import into".to_string()))?;
+        let analysis = self.host.analysis();
+
+        let mut exporters = Vec::new();
+        for (def, position) in self.definition_positions(&sema, name)? {
+            let Some((module_def, kind)) = importable(db, def) else { continue };
+            let krate = module_def.module(db).map(|m| m.krate());
+            let line_index = analysis.file_line_index(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+            exporters.push(Exporter {
+                path: definition_key(db, def).unwrap_or_else(|| name.to_string()),
+                kind,
+                crate_name: krate.and_then(|k| k.display_name(db)).map(|n| n.to_string()),
+                local: krate.is_some_and(|k| k.origin(db).is_local()),
+                import: import_path(db, context, module_def),
+                file_path: self.file_path(position.file_id),
+                line: line_index.line_col(position.offset).line,
+            });
+        }
+
+        // Importable items first, then the workspace's own before dependencies'
+        exporters.sort_by(|a, b| {
+            (a.import.is_none(), !a.local, &a.path).cmp(&(b.import.is_none(), !b.local, &b.path))
+        });
+        Ok(exporters)
+    }
+}
+
+/// The module-level item a definition is, with its kind; `None` for
+/// associated items, fields, locals and other things `use` can't name
+fn importable(db: &RootDatabase, def: Definition) -> Option<(ModuleDef, &'static str)> {
+    Some(match def {
+        Definition::Module(it) => (ModuleDef::Module(it), "mod"),
+        Definition::Adt(it) => {
+            let kind = match it {
+                ra_ap_hir::Adt::Struct(_) => "struct",
+                ra_ap_hir::Adt::Enum(_) => "enum",
+                ra_ap_hir::Adt::Union(_) => "union",
+            };
+            (ModuleDef::Adt(it), kind)
+        }
+        Definition::Variant(it) => (ModuleDef::Variant(it), "variant"),
+        Definition::Trait(it) => (ModuleDef::Trait(it), "trait"),
+        Definition::TypeAlias(it) if it.as_assoc_item(db).is_none() => (ModuleDef::TypeAlias(it), "type"),
+        Definition::Function(it) if it.as_assoc_item(db).is_none() => (ModuleDef::Function(it), "fn"),
+        Definition::Const(it) if it.as_assoc_item(db).is_none() => (ModuleDef::Const(it), "const"),
+        Definition::Static(it) => (ModuleDef::Static(it), "static"),
+        Definition::Macro(it) => (ModuleDef::Macro(it), "macro"),
+        _ => return None,
+    })
+}
+
+/// Shortest `use` path to an item from a module, following re-exports
+fn import_path(db: &RootDatabase, context: Module, item: ModuleDef) -> Option<String> {
+    let config = FindPathConfig { prefer_no_std: false, prefer_prelude: true, prefer_absolute: false, allow_unstable: false };
+    let path = context.find_path(db, ItemInNs::from(item), config)?;
+    Some(path.display(db, context.krate().edition(db)).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_who_exports_error() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let exporters = analyzer.who_exports("Error", None).unwrap();
+        let io = exporters
+            .iter()
+            .find(|e| e.import.as_deref() == Some("std::io::Error"))
+            .expect("std::io::Error is importable through its re-export");
+        assert_eq!(io.kind, "struct");
+        assert!(!io.local);
+
+        // Importable items come first
+        let first_unimportable = exporters.iter().position(|e| e.import.is_none()).unwrap_or(exporters.len());
+        assert!(exporters[first_unimportable..].iter().all(|e| e.import.is_none()));
+    }
+}
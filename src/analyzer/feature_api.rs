@@ -0,0 +1,153 @@
+//! Feature-gated public API
+//!
+//! Compares each workspace crate's public items with its default features
+//! against `--all-features`, and lists the items only available (or only
+//! missing) behind features. The two sets come from the crate graph: the
+//! features the project was loaded with, which are the defaults with their
+//! implications resolved by cargo, and every feature the crate declares.
+//! Presence is decided by evaluating `#[cfg]` attributes, as `feature_matrix`
+//! does, so no extra build is needed.
+
+use super::api_exposure::{file_module_public, inline_modules_public, is_public_item};
+use super::features::{module_cfgs, node_cfgs, CfgExpr};
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, Semantics};
+use ra_ap_syntax::{ast, AstNode};
+use std::collections::HashMap;
+
+/// A public item whose presence differs between default and all features
+#[derive(Debug, Clone)]
+pub struct GatedApiItem {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The combined cfg predicate, including those of enclosing items and modules
+    pub cfg: String,
+    /// Presence with the default features; `None` when it depends on
+    /// non-feature cfgs such as `unix`
+    pub with_default: Option<bool>,
+    /// Presence with all features
+    pub with_all: Option<bool>,
+    /// Whether every enclosing module is public too, so the item is nameable
+    /// from outside the crate without a re-export
+    pub reachable: bool,
+}
+
+/// The feature-gated public API of one crate
+#[derive(Debug, Clone)]
+pub struct FeatureApi {
+    pub crate_name: String,
+    /// Features enabled by default, implied ones included
+    pub default_features: Vec<String>,
+    /// Every feature the crate declares
+    pub all_features: Vec<String>,
+    pub items: Vec<GatedApiItem>,
+}
+
+impl Analyzer {
+    /// List the public items of each workspace crate that differ between
+    /// default features and all features
+    ///
+    /// Crates without any such items are left out.
+    pub fn feature_gated_api(&self) -> Result<Vec<FeatureApi>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut crates: HashMap<Crate, FeatureApi> = HashMap::new();
+        for file_id in self.workspace_files()? {
+            let Some(module) = sema.file_to_module_def(file_id) else { continue };
+            let krate = module.krate();
+            let api = crates.entry(krate).or_insert_with(|| FeatureApi {
+                crate_name: krate.display_name(db).map(|n| n.to_string()).unwrap_or_default(),
+                default_features: feature_names(krate.cfg(db).get_cfg_values("feature")),
+                all_features: feature_names(krate.potential_cfg(db).get_cfg_values("feature")),
+                items: Vec::new(),
+            });
+
+            let file_cfgs = module_cfgs(&sema, db, file_id);
+            let file_reachable = file_module_public(&sema, file_id);
+            let source_file = sema.parse_guess_edition(file_id);
+            for item in source_file.syntax().descendants().filter_map(ast::Item::cast) {
+                if !is_public_item(&item) {
+                    continue;
+                }
+                let Some((name, kind)) = item_label(&item) else { continue };
+                let mut cfgs = file_cfgs.clone();
+                cfgs.extend(node_cfgs(item.syntax()));
+                let cfg = match cfgs.len() {
+                    0 => continue,
+                    1 => cfgs.remove(0),
+                    _ => CfgExpr::All(cfgs),
+                };
+                let Some((with_default, with_all)) = presence_change(&cfg, &api.default_features, &api.all_features) else {
+                    continue;
+                };
+
+                let (start_line, end_line) = self.line_range(file_id, item.syntax().text_range())?;
+                api.items.push(GatedApiItem {
+                    name,
+                    kind: kind.to_string(),
+                    file_path: self.file_path(file_id),
+                    start_line,
+                    end_line,
+                    cfg: cfg.to_string(),
+                    with_default,
+                    with_all,
+                    reachable: file_reachable && inline_modules_public(item.syntax()),
+                });
+            }
+        }
+
+        let mut apis: Vec<FeatureApi> = crates.into_values().filter(|api| !api.items.is_empty()).collect();
+        apis.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+        for api in &mut apis {
+            api.items.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        }
+        Ok(apis)
+    }
+}
+
+/// Names from a crate's cfg values, such as its `feature` values, sorted
+pub(super) fn feature_names<'a, T: ToString + 'a>(values: impl Iterator<Item = &'a T>) -> Vec<String> {
+    let mut features: Vec<String> = values.map(|f| f.to_string()).collect();
+    features.sort();
+    features
+}
+
+/// An item's presence with the default and with all features, if the two differ
+fn presence_change(cfg: &CfgExpr, default: &[String], all: &[String]) -> Option<(Option<bool>, Option<bool>)> {
+    let (with_default, with_all) = (cfg.eval(default), cfg.eval(all));
+    (with_default != with_all).then_some((with_default, with_all))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_presence_change() {
+        let default = features(&["std"]);
+        let all = features(&["serde", "std"]);
+
+        let gated = CfgExpr::parse("feature = \"serde\"").unwrap();
+        assert_eq!(presence_change(&gated, &default, &all), Some((Some(false), Some(true))));
+
+        // Enabled by default: same either way
+        let default_on = CfgExpr::parse("feature = \"std\"").unwrap();
+        assert_eq!(presence_change(&default_on, &default, &all), None);
+
+        // Removed by a feature
+        let negated = CfgExpr::parse("not(feature = \"serde\")").unwrap();
+        assert_eq!(presence_change(&negated, &default, &all), Some((Some(true), Some(false))));
+
+        // With all features, the non-feature cfg decides, so it is unknown
+        let platform = CfgExpr::parse("all(unix, feature = \"serde\")").unwrap();
+        assert_eq!(presence_change(&platform, &default, &all), Some((Some(false), None)));
+    }
+}
@@ -0,0 +1,382 @@
+//! Crate feature matrix audit
+//!
+//! Evaluates `#[cfg(...)]` attributes against several feature combinations
+//! and reports the items whose existence depends on which features are
+//! enabled. This works purely on the syntax tree, so checking N combinations
+//! doesn't need N cargo builds.
+
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_syntax::{
+    ast::{self, HasAttrs},
+    AstNode, SyntaxNode,
+};
+
+/// A parsed `cfg` predicate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgExpr {
+    /// A bare name such as `test` or `unix`
+    Atom(String),
+    /// A `key = "value"` pair such as `feature = "serde"`
+    KeyValue(String, String),
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Not(Box<CfgExpr>),
+}
+
+impl CfgExpr {
+    /// Parse the contents of a `cfg(...)` attribute, without the outer parentheses
+    pub fn parse(input: &str) -> Option<CfgExpr> {
+        let tokens = tokenize(input);
+        let mut pos = 0;
+        let expr = parse_expr(&tokens, &mut pos)?;
+        (pos == tokens.len()).then_some(expr)
+    }
+
+    /// Evaluate the predicate with the given features enabled
+    ///
+    /// Only features vary between combinations; any other predicate (`test`,
+    /// `unix`, `target_os = ...`) is unknown, which makes the result `None`
+    /// unless the rest of the expression decides it.
+    pub fn eval(&self, features: &[String]) -> Option<bool> {
+        match self {
+            CfgExpr::KeyValue(key, value) if key == "feature" => Some(features.contains(value)),
+            CfgExpr::Atom(_) | CfgExpr::KeyValue(..) => None,
+            CfgExpr::Not(inner) => inner.eval(features).map(|b| !b),
+            CfgExpr::All(exprs) => {
+                let results: Vec<_> = exprs.iter().map(|e| e.eval(features)).collect();
+                if results.contains(&Some(false)) {
+                    Some(false)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(true)
+                }
+            }
+            CfgExpr::Any(exprs) => {
+                let results: Vec<_> = exprs.iter().map(|e| e.eval(features)).collect();
+                if results.contains(&Some(true)) {
+                    Some(true)
+                } else if results.contains(&None) {
+                    None
+                } else {
+                    Some(false)
+                }
+            }
+        }
+    }
+
+    /// Whether `cfg` occurs anywhere in the predicate, negated or not
+    pub fn mentions(&self, cfg: &CfgExpr) -> bool {
+        self == cfg
+            || match self {
+                CfgExpr::Atom(_) | CfgExpr::KeyValue(..) => false,
+                CfgExpr::Not(inner) => inner.mentions(cfg),
+                CfgExpr::All(exprs) | CfgExpr::Any(exprs) => exprs.iter().any(|e| e.mentions(cfg)),
+            }
+    }
+
+    /// Collect every feature name mentioned in the predicate
+    pub fn features(&self, out: &mut Vec<String>) {
+        match self {
+            CfgExpr::KeyValue(key, value) if key == "feature" => {
+                if !out.contains(value) {
+                    out.push(value.clone());
+                }
+            }
+            CfgExpr::Atom(_) | CfgExpr::KeyValue(..) => {}
+            CfgExpr::Not(inner) => inner.features(out),
+            CfgExpr::All(exprs) | CfgExpr::Any(exprs) => exprs.iter().for_each(|e| e.features(out)),
+        }
+    }
+}
+
+impl std::fmt::Display for CfgExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let join = |exprs: &[CfgExpr]| exprs.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(", ");
+        match self {
+            CfgExpr::Atom(name) => write!(f, "{}", name),
+            CfgExpr::KeyValue(key, value) => write!(f, "{} = \"{}\"", key, value),
+            CfgExpr::All(exprs) => write!(f, "all({})", join(exprs)),
+            CfgExpr::Any(exprs) => write!(f, "any({})", join(exprs)),
+            CfgExpr::Not(inner) => write!(f, "not({})", inner),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Eq,
+    Comma,
+    Open,
+    Close,
+}
+
+fn tokenize(input: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '(' | ')' | ',' | '=' => {
+                chars.next();
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    ',' => Token::Comma,
+                    _ => Token::Eq,
+                });
+            }
+            '"' => {
+                chars.next();
+                let value: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(Token::Str(value));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    ident.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            _ => {
+                chars.next();
+            }
+        }
+    }
+    tokens
+}
+
+fn parse_expr(tokens: &[Token], pos: &mut usize) -> Option<CfgExpr> {
+    let Token::Ident(name) = tokens.get(*pos)? else { return None };
+    *pos += 1;
+
+    match tokens.get(*pos) {
+        Some(Token::Eq) => {
+            *pos += 1;
+            let Token::Str(value) = tokens.get(*pos)? else { return None };
+            *pos += 1;
+            Some(CfgExpr::KeyValue(name.clone(), value.clone()))
+        }
+        Some(Token::Open) => {
+            *pos += 1;
+            let mut args = Vec::new();
+            while tokens.get(*pos) != Some(&Token::Close) {
+                args.push(parse_expr(tokens, pos)?);
+                if tokens.get(*pos) == Some(&Token::Comma) {
+                    *pos += 1;
+                }
+            }
+            *pos += 1;
+            match name.as_str() {
+                "all" => Some(CfgExpr::All(args)),
+                "any" => Some(CfgExpr::Any(args)),
+                "not" if args.len() == 1 => Some(CfgExpr::Not(Box::new(args.remove(0)))),
+                _ => None,
+            }
+        }
+        _ => Some(CfgExpr::Atom(name.clone())),
+    }
+}
+
+/// An item whose presence differs between feature combinations
+#[derive(Debug, Clone)]
+pub struct FeatureGatedItem {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The combined cfg predicate that applies to the item, including
+    /// those inherited from enclosing modules
+    pub cfg: String,
+    /// Presence in each combination, in the order of `FeatureMatrix::combinations`;
+    /// `None` when it depends on non-feature cfgs such as `test` or `unix`
+    pub presence: Vec<Option<bool>>,
+}
+
+/// Result of a feature matrix audit
+#[derive(Debug, Clone)]
+pub struct FeatureMatrix {
+    /// The feature combinations that were evaluated
+    pub combinations: Vec<Vec<String>>,
+    pub items: Vec<FeatureGatedItem>,
+}
+
+impl Analyzer {
+    /// Report workspace items that only exist under some feature combinations
+    ///
+    /// When `combinations` is empty, the matrix is built from the features the
+    /// cfg attributes mention: no features, each feature on its own, and all of
+    /// them together. Feature names are taken literally; implications declared
+    /// in Cargo.toml (including `default`) are not expanded.
+    pub fn feature_matrix(&self, combinations: Vec<Vec<String>>) -> Result<FeatureMatrix, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        // Gather every gated item first so default combinations can be derived
+        let mut gated = Vec::new();
+        for file_id in self.workspace_files()? {
+            let file_cfgs = module_cfgs(&sema, db, file_id);
+            let source_file = sema.parse_guess_edition(file_id);
+
+            for item in source_file.syntax().descendants().filter_map(ast::Item::cast) {
+                let Some((name, kind)) = item_label(&item) else { continue };
+                let mut cfgs = file_cfgs.clone();
+                cfgs.extend(node_cfgs(item.syntax()));
+                if cfgs.is_empty() {
+                    continue;
+                }
+                gated.push((file_id, item, name, kind, cfgs));
+            }
+        }
+
+        let combinations = if combinations.is_empty() {
+            let mut features = Vec::new();
+            for (.., cfgs) in &gated {
+                cfgs.iter().for_each(|cfg| cfg.features(&mut features));
+            }
+            features.sort();
+            default_combinations(&features)
+        } else {
+            combinations
+        };
+
+        let mut items = Vec::new();
+        for (file_id, item, name, kind, cfgs) in gated {
+            let cfg = match cfgs.len() {
+                1 => cfgs[0].clone(),
+                _ => CfgExpr::All(cfgs),
+            };
+            let presence: Vec<Option<bool>> = combinations.iter().map(|features| cfg.eval(features)).collect();
+            if presence.iter().all(|p| *p == presence[0]) {
+                continue;
+            }
+
+            let (start_line, end_line) = self.line_range(file_id, item.syntax().text_range())?;
+            items.push(FeatureGatedItem {
+                name,
+                kind: kind.to_string(),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                cfg: cfg.to_string(),
+                presence,
+            });
+        }
+
+        Ok(FeatureMatrix { combinations, items })
+    }
+}
+
+/// No features, each feature alone, and all features together
+fn default_combinations(features: &[String]) -> Vec<Vec<String>> {
+    let mut combinations = vec![Vec::new()];
+    combinations.extend(features.iter().map(|f| vec![f.clone()]));
+    if features.len() > 1 {
+        combinations.push(features.to_vec());
+    }
+    combinations
+}
+
+/// The `cfg` predicates attached to a node and to the items enclosing it
+pub(super) fn node_cfgs(node: &SyntaxNode) -> Vec<CfgExpr> {
+    node.ancestors()
+        .filter_map(ast::Item::cast)
+        .flat_map(|item| item.attrs().collect::<Vec<_>>())
+        .filter_map(|attr| attr_cfg(&attr))
+        .collect()
+}
+
+/// The `cfg` predicates that decide whether a file's module exists at all:
+/// inner `#![cfg]` attributes plus the attributes on each `mod` declaration
+/// leading to it from the crate root
+pub(super) fn module_cfgs(sema: &Semantics<'_, RootDatabase>, db: &RootDatabase, file_id: FileId) -> Vec<CfgExpr> {
+    let mut cfgs: Vec<CfgExpr> = sema
+        .parse_guess_edition(file_id)
+        .attrs()
+        .filter_map(|attr| attr_cfg(&attr))
+        .collect();
+
+    let mut module = sema.file_to_module_def(file_id);
+    while let Some(current) = module {
+        if let Some(decl) = current.declaration_source(db) {
+            cfgs.extend(node_cfgs(decl.value.syntax()));
+        }
+        module = current.parent(db);
+    }
+
+    cfgs
+}
+
+/// The `cfg` predicates attached directly to a node, ignoring its ancestors
+pub(super) fn own_cfgs(node: &impl HasAttrs) -> Vec<CfgExpr> {
+    node.attrs().filter_map(|attr| attr_cfg(&attr)).collect()
+}
+
+/// Parse a `#[cfg(...)]` attribute; other attributes yield `None`
+fn attr_cfg(attr: &ast::Attr) -> Option<CfgExpr> {
+    if attr.path()?.syntax().text() != "cfg" {
+        return None;
+    }
+    let tt = attr.token_tree()?.syntax().text().to_string();
+    let inner = tt.strip_prefix('(')?.strip_suffix(')')?;
+    CfgExpr::parse(inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn features(names: &[&str]) -> Vec<String> {
+        names.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_cfg_expressions() {
+        assert_eq!(CfgExpr::parse("test"), Some(CfgExpr::Atom("test".to_string())));
+        assert_eq!(
+            CfgExpr::parse("feature = \"serde\""),
+            Some(CfgExpr::KeyValue("feature".to_string(), "serde".to_string()))
+        );
+
+        let expr = CfgExpr::parse("all(feature = \"a\", not(any(feature = \"b\", windows)))").unwrap();
+        assert_eq!(expr.to_string(), "all(feature = \"a\", not(any(feature = \"b\", windows)))");
+        assert_eq!(CfgExpr::parse("all(feature = \"a\""), None);
+    }
+
+    #[test]
+    fn test_eval_with_unknown_atoms() {
+        let expr = CfgExpr::parse("all(feature = \"a\", not(feature = \"b\"))").unwrap();
+        assert_eq!(expr.eval(&features(&["a"])), Some(true));
+        assert_eq!(expr.eval(&features(&["a", "b"])), Some(false));
+        assert_eq!(expr.eval(&features(&[])), Some(false));
+
+        // Non-feature predicates stay unknown unless the rest decides
+        let expr = CfgExpr::parse("all(test, feature = \"a\")").unwrap();
+        assert_eq!(expr.eval(&features(&[])), Some(false));
+        assert_eq!(expr.eval(&features(&["a"])), None);
+        let expr = CfgExpr::parse("any(unix, feature = \"a\")").unwrap();
+        assert_eq!(expr.eval(&features(&["a"])), Some(true));
+    }
+
+    #[test]
+    fn test_mentions() {
+        let expr = CfgExpr::parse("all(feature = \"a\", not(any(has_simd, windows)))").unwrap();
+        assert!(expr.mentions(&CfgExpr::Atom("has_simd".into())));
+        assert!(expr.mentions(&CfgExpr::KeyValue("feature".into(), "a".into())));
+        assert!(!expr.mentions(&CfgExpr::Atom("unix".into())));
+    }
+
+    #[test]
+    fn test_default_combinations() {
+        let combos = default_combinations(&features(&["a", "b"]));
+        assert_eq!(combos, vec![features(&[]), features(&["a"]), features(&["b"]), features(&["a", "b"])]);
+    }
+}
@@ -0,0 +1,218 @@
+//! Per-file dependency summary
+//!
+//! Answers "how entangled is this file" before moving or splitting it: what
+//! it pulls in through `use` declarations, resolved to the crates and modules
+//! they come from, and which of its own definitions other files refer to.
+//! Incoming references come from the reference index.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{PathResolution, Semantics};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::{Definition, NameClass};
+use ra_ap_syntax::{ast, AstNode};
+
+/// One name brought into scope by a `use` declaration
+#[derive(Debug, Clone)]
+pub struct ImportedItem {
+    /// The imported path as written, e.g. `std::collections::HashMap`
+    pub path: String,
+    /// Qualified path of what it resolves to, if it resolves
+    pub resolved: Option<String>,
+    /// The crate it comes from for external imports, otherwise the module
+    pub origin: Option<String>,
+    /// Whether it comes from another crate
+    pub external: bool,
+}
+
+/// A definition in the file and who refers to it from elsewhere
+#[derive(Debug, Clone)]
+pub struct ExportedSymbol {
+    pub name: String,
+    /// Qualified path of the definition
+    pub definition: String,
+    /// Files outside this one that reference the definition
+    pub referencing_files: Vec<String>,
+    /// Number of references from outside this file
+    pub reference_count: usize,
+}
+
+/// What a file depends on and what depends on it
+#[derive(Debug, Clone)]
+pub struct FileDependencies {
+    pub file_path: String,
+    pub imports: Vec<ImportedItem>,
+    /// Distinct crates imported from
+    pub external_crates: Vec<String>,
+    /// Distinct modules of the file's own crate imported from
+    pub internal_modules: Vec<String>,
+    pub exported: Vec<ExportedSymbol>,
+}
+
+impl Analyzer {
+    /// Summarize a file's imports and the use of its definitions elsewhere
+    ///
+    /// Requires the reference index (see `build_reference_index`).
+    pub fn file_dependencies(&self, file_path: &str) -> Result<FileDependencies, AnalyzerError> {
+        let index = self.references.as_ref()
+            .ok_or_else(|| AnalyzerError::Other("Reference index has not been built yet".to_string()))?;
+        let file_id = self.file_id(file_path)?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source = sema.parse_guess_edition(file_id);
+
+        let imports = file_imports(&sema, file_id);
+        let mut external_crates = Vec::new();
+        let mut internal_modules = Vec::new();
+        for import in &imports {
+            let Some(origin) = &import.origin else { continue };
+            let seen = if import.external { &mut external_crates } else { &mut internal_modules };
+            if !seen.contains(origin) {
+                seen.push(origin.clone());
+            }
+        }
+
+        let mut exported = Vec::new();
+        for name in source.syntax().descendants().filter_map(ast::Name::cast) {
+            let def = match NameClass::classify(&sema, &name) {
+                Some(NameClass::Definition(def)) if is_item(def) => def,
+                _ => continue,
+            };
+            let Some(key) = definition_key(db, def) else { continue };
+
+            let mut referencing_files = Vec::new();
+            let mut reference_count = 0;
+            for site in index.references_to(&key).iter().filter(|site| site.file_path != file_path) {
+                reference_count += 1;
+                if !referencing_files.contains(&site.file_path) {
+                    referencing_files.push(site.file_path.clone());
+                }
+            }
+            exported.push(ExportedSymbol {
+                name: name.text().to_string(),
+                definition: key,
+                referencing_files,
+                reference_count,
+            });
+        }
+
+        Ok(FileDependencies {
+            file_path: file_path.to_string(),
+            imports,
+            external_crates,
+            internal_modules,
+            exported,
+        })
+    }
+}
+
+/// Resolve every name a file's `use` declarations bring into scope
+pub(super) fn file_imports(sema: &Semantics<'_, RootDatabase>, file_id: FileId) -> Vec<ImportedItem> {
+    let db = sema.db;
+    let source = sema.parse_guess_edition(file_id);
+    let krate = sema.file_to_module_def(file_id).map(|m| m.krate());
+
+    let mut imports = Vec::new();
+    for tree in source.syntax().descendants().filter_map(ast::UseTree::cast) {
+        // Only leaves of nested use trees name something
+        if tree.use_tree_list().is_some() {
+            continue;
+        }
+        let Some(path) = tree.path() else { continue };
+
+        let def = match sema.resolve_path(&path) {
+            Some(PathResolution::Def(def)) => Some(Definition::from(def)),
+            _ => None,
+        };
+        let owner = def.and_then(|def| def.module(db));
+        let external = owner.is_some_and(|m| Some(m.krate()) != krate);
+        let origin = owner.and_then(|module| {
+            if external {
+                module.krate().display_name(db).map(|n| n.to_string())
+            } else {
+                definition_key(db, Definition::Module(module))
+            }
+        });
+
+        imports.push(ImportedItem {
+            path: written_path(&tree),
+            resolved: def.and_then(|def| definition_key(db, def)),
+            origin,
+            external,
+        });
+    }
+    imports
+}
+
+/// The full path of a use tree leaf, including the prefixes of enclosing trees
+pub(super) fn written_path(tree: &ast::UseTree) -> String {
+    let mut segments: Vec<String> = tree
+        .syntax()
+        .ancestors()
+        .filter_map(ast::UseTree::cast)
+        .filter_map(|t| t.path().map(|p| p.syntax().text().to_string()))
+        .collect();
+    segments.reverse();
+    // `use std::fmt::{self}` imports `std::fmt` itself
+    if segments.len() > 1 && segments.last().is_some_and(|s| s == "self") {
+        segments.pop();
+    }
+    let mut path = segments.join("::");
+    if tree.star_token().is_some() {
+        path.push_str("::*");
+    }
+    path
+}
+
+/// Whether a definition is an item other files can refer to
+pub(super) fn is_item(def: Definition) -> bool {
+    matches!(
+        def,
+        Definition::Module(_)
+            | Definition::Function(_)
+            | Definition::Adt(_)
+            | Definition::Variant(_)
+            | Definition::Const(_)
+            | Definition::Static(_)
+            | Definition::Trait(_)
+            | Definition::TypeAlias(_)
+            | Definition::Macro(_)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_written_paths_of_nested_use_trees() {
+        let file = SourceFile::parse("use std::{collections::HashMap, fmt::{self, Display}, io::*};", Edition::CURRENT).tree();
+        let paths: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::UseTree::cast)
+            .filter(|t| t.use_tree_list().is_none())
+            .map(|t| written_path(&t))
+            .collect();
+
+        assert_eq!(paths, vec!["std::collections::HashMap", "std::fmt", "std::fmt::Display", "std::io::*"]);
+    }
+
+    #[test]
+    fn test_file_dependencies_of_query_cache() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        analyzer.build_reference_index().expect("Failed to build reference index");
+
+        let path = std::fs::canonicalize("src/query_cache.rs").unwrap();
+        let deps = analyzer.file_dependencies(path.to_str().unwrap()).unwrap();
+
+        assert!(deps.external_crates.contains(&"rmcp".to_string()), "Imports: {:?}", deps.imports);
+        let cache = deps.exported.iter().find(|s| s.name == "QueryCache").expect("QueryCache should be listed");
+        assert!(
+            cache.referencing_files.iter().any(|f| f.ends_with("main.rs")),
+            "QueryCache is used from main.rs"
+        );
+    }
+}
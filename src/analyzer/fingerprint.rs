@@ -0,0 +1,154 @@
+//! Project fingerprint for persisted state
+//!
+//! Persisted caches are only valid for the setup they were built under. A
+//! toolchain upgrade changes the standard library, a rust-analyzer upgrade
+//! changes how code is resolved, an updated Cargo.lock pulls in different
+//! dependency code, and enabling a feature changes which items exist. The
+//! fingerprint captures all of these when the project is loaded; persisted
+//! state stamped with a different fingerprint is discarded and rebuilt
+//! rather than served stale.
+
+use super::{stable_hash, Analyzer, AnalyzerError};
+use ra_ap_project_model::{ProjectWorkspace, ProjectWorkspaceKind};
+use std::path::Path;
+
+/// Version of the `ra_ap_*` crates, from Cargo.lock at build time
+pub const RA_AP_VERSION: &str = env!("CRATOGRAPHER_RA_AP_VERSION");
+
+/// What persisted state depends on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectFingerprint {
+    /// Version of the project's Rust toolchain, if known
+    pub toolchain: Option<String>,
+    /// Version of the rust-analyzer crates cratographer was built with
+    pub analyzer_version: String,
+    /// Hash of the workspace's Cargo.lock, if it has one
+    pub lockfile_hash: Option<String>,
+    /// Enabled features of the workspace packages, as `package/feature`
+    pub features: Vec<String>,
+}
+
+/// Whether persisted state matches the loaded project
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PersistedStatus {
+    /// Stamped with the current fingerprint, and reused
+    Current,
+    /// Stamped with another fingerprint, and rebuilt on next use
+    Stale,
+    /// Nothing persisted yet
+    Missing,
+}
+
+impl PersistedStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PersistedStatus::Current => "current",
+            PersistedStatus::Stale => "stale",
+            PersistedStatus::Missing => "missing",
+        }
+    }
+
+    /// Whether anything is persisted, current or not
+    pub fn exists(self) -> bool {
+        self != PersistedStatus::Missing
+    }
+}
+
+impl ProjectFingerprint {
+    /// Fingerprint of a loaded workspace
+    pub(super) fn of_workspace(workspace: &ProjectWorkspace, project_root: &Path) -> Self {
+        let lockfile_hash = std::fs::read_to_string(project_root.join("Cargo.lock"))
+            .ok()
+            .map(|lock| format!("{:016x}", stable_hash(&lock)));
+
+        let mut features = Vec::new();
+        if let ProjectWorkspaceKind::Cargo { cargo, .. } = &workspace.kind {
+            for package in cargo.packages().filter(|&package| cargo[package].is_member) {
+                let data = &cargo[package];
+                features.extend(data.active_features.iter().map(|feature| format!("{}/{}", data.name, feature)));
+            }
+        }
+        features.sort();
+
+        ProjectFingerprint {
+            toolchain: workspace.toolchain.as_ref().map(|version| version.to_string()),
+            analyzer_version: RA_AP_VERSION.to_string(),
+            lockfile_hash,
+            features,
+        }
+    }
+
+    /// Hash of every component, stamped on project-specific persisted state
+    pub fn digest(&self) -> u64 {
+        stable_hash(&format!(
+            "{}|{}|{}|{}",
+            self.toolchain_digest(),
+            self.lockfile_hash.as_deref().unwrap_or_default(),
+            self.features.join(","),
+            env!("CARGO_PKG_VERSION"),
+        ))
+    }
+
+    /// Hash of the toolchain and rust-analyzer versions only
+    ///
+    /// The shared library cache is keyed by crate version and cfg set
+    /// already, so a project's lockfile and features don't concern it.
+    pub(super) fn toolchain_digest(&self) -> u64 {
+        stable_hash(&format!("{}|{}", self.toolchain.as_deref().unwrap_or_default(), self.analyzer_version))
+    }
+}
+
+impl Analyzer {
+    /// The fingerprint of the loaded project
+    pub fn project_fingerprint(&self) -> Result<&ProjectFingerprint, AnalyzerError> {
+        self.fingerprint.as_ref().ok_or_else(|| AnalyzerError::Other("No project loaded".to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint() -> ProjectFingerprint {
+        ProjectFingerprint {
+            toolchain: Some("1.85.0".to_string()),
+            analyzer_version: "0.0.270".to_string(),
+            lockfile_hash: Some("00000000deadbeef".to_string()),
+            features: vec!["app/default".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_digest_covers_every_component() {
+        let base = fingerprint();
+        let changes: [fn(&mut ProjectFingerprint); 4] = [
+            |f| f.toolchain = Some("1.86.0".to_string()),
+            |f| f.analyzer_version = "0.0.271".to_string(),
+            |f| f.lockfile_hash = None,
+            |f| f.features.push("app/extra".to_string()),
+        ];
+        for change in changes {
+            let mut changed = base.clone();
+            change(&mut changed);
+            assert_ne!(changed.digest(), base.digest());
+        }
+
+        // Only toolchain and analyzer changes concern the shared library cache
+        let mut changed = base.clone();
+        changed.lockfile_hash = None;
+        changed.features.clear();
+        assert_eq!(changed.toolchain_digest(), base.toolchain_digest());
+    }
+
+    #[test]
+    fn test_fingerprint_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.project_fingerprint().is_err());
+        assert!(analyzer.load_project(".").is_ok());
+
+        let fingerprint = analyzer.project_fingerprint().unwrap();
+        assert!(fingerprint.toolchain.is_some());
+        assert!(fingerprint.lockfile_hash.is_some());
+        assert_ne!(fingerprint.analyzer_version, "unknown");
+    }
+}
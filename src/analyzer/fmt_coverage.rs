@@ -0,0 +1,155 @@
+//! Debug and Display coverage of public types
+//!
+//! A public type without `Debug` can't be logged with `{:?}`, nor be part of
+//! another type deriving it; one without `Display` can't be shown to users
+//! or serve as an error message. Before improving logging or error
+//! reporting, this lists, per workspace crate, the public types lacking
+//! either impl, whether derived or written by hand.
+//!
+//! Types are those declared `pub` in any module of the crate, whether or
+//! not the module itself is public; impls are matched against the type
+//! itself, so blanket impls aren't counted.
+
+use super::move_item::module_path;
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Crate, HasVisibility, Impl, ModuleDef, Semantics, Visibility};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::AstNode;
+
+const DEBUG: &str = "core::fmt::Debug";
+const DISPLAY: &str = "core::fmt::Display";
+
+/// A public type lacking a Debug or Display impl
+#[derive(Debug, Clone)]
+pub struct FmtGap {
+    /// Qualified path of the type
+    pub path: String,
+    /// `struct`, `enum` or `union`
+    pub kind: &'static str,
+    pub file_path: String,
+    pub line: u32,
+    pub has_debug: bool,
+    pub has_display: bool,
+}
+
+/// The Debug and Display coverage of one crate's public types
+#[derive(Debug, Clone)]
+pub struct CrateFmtCoverage {
+    pub crate_name: String,
+    /// Public types considered
+    pub types: usize,
+    /// How many of them implement each trait
+    pub with_debug: usize,
+    pub with_display: usize,
+    /// The types lacking either impl, by path
+    pub gaps: Vec<FmtGap>,
+}
+
+impl Analyzer {
+    /// Debug and Display coverage of the public types of each workspace
+    /// crate, or of `crate_name`
+    ///
+    /// With `module`, only types declared in that module or below it count,
+    /// e.g. `analyzer` or `crate::analyzer::budget`.
+    pub fn fmt_coverage(&self, crate_name: Option<&str>, module: Option<&str>) -> Result<Vec<CrateFmtCoverage>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let module = module.map(|m| {
+            let m = m.trim_start_matches("crate::");
+            if m.is_empty() || m == "crate" { "crate".to_string() } else { format!("crate::{}", m) }
+        });
+        let in_module = |path: &str| {
+            module.as_deref().is_none_or(|wanted| path == wanted || path.starts_with(&format!("{}::", wanted)))
+        };
+
+        let mut crates: Vec<(String, Crate)> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| krate.origin(db).is_local())
+            .filter_map(|krate| Some((krate.display_name(db)?.to_string(), krate)))
+            .filter(|(name, _)| crate_name.is_none_or(|wanted| wanted.replace('-', "_") == name.replace('-', "_")))
+            .collect();
+        if crates.is_empty() {
+            if let Some(crate_name) = crate_name {
+                return Err(AnalyzerError::Other(format!("No workspace crate named '{}'", crate_name)));
+            }
+        }
+        crates.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut results = Vec::new();
+        for (name, krate) in crates {
+            let mut coverage = CrateFmtCoverage { crate_name: name, types: 0, with_debug: 0, with_display: 0, gaps: Vec::new() };
+            for module in krate.modules(db) {
+                if !in_module(&module_path(db, module)) {
+                    continue;
+                }
+                for def in module.declarations(db) {
+                    let ModuleDef::Adt(adt) = def else { continue };
+                    if adt.visibility(db) != Visibility::Public {
+                        continue;
+                    }
+                    let (has_debug, has_display) = fmt_impls(db, adt);
+                    coverage.types += 1;
+                    coverage.with_debug += usize::from(has_debug);
+                    coverage.with_display += usize::from(has_display);
+                    if has_debug && has_display {
+                        continue;
+                    }
+
+                    let Some(source) = sema.source(adt) else { continue };
+                    let range = sema.original_range(source.value.syntax());
+                    let file_id = range.file_id.file_id(db);
+                    let (line, _) = self.line_range(file_id, range.range)?;
+                    coverage.gaps.push(FmtGap {
+                        path: definition_key(db, Definition::Adt(adt)).unwrap_or_else(|| adt.name(db).as_str().to_string()),
+                        kind: match adt {
+                            Adt::Struct(_) => "struct",
+                            Adt::Enum(_) => "enum",
+                            Adt::Union(_) => "union",
+                        },
+                        file_path: self.file_path(file_id),
+                        line,
+                        has_debug,
+                        has_display,
+                    });
+                }
+            }
+            coverage.gaps.sort_by(|a, b| a.path.cmp(&b.path));
+            results.push(coverage);
+        }
+        Ok(results)
+    }
+}
+
+/// Whether a type has a Debug impl and a Display impl of its own, derived or not
+fn fmt_impls(db: &RootDatabase, adt: Adt) -> (bool, bool) {
+    let traits: Vec<String> = Impl::all_for_type(db, adt.ty(db))
+        .into_iter()
+        .filter_map(|impl_| impl_.trait_(db))
+        .filter_map(|trait_| definition_key(db, Definition::Trait(trait_)))
+        .collect();
+    (traits.iter().any(|t| t == DEBUG), traits.iter().any(|t| t == DISPLAY))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fmt_coverage_of_budget_module() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let coverage = analyzer.fmt_coverage(Some("cratographer"), Some("analyzer::budget")).unwrap();
+        assert_eq!(coverage.len(), 1);
+        // Budget and Coverage both derive Debug and neither implements Display
+        assert_eq!(coverage[0].types, 2);
+        assert_eq!(coverage[0].with_debug, 2);
+        assert_eq!(coverage[0].with_display, 0);
+        assert!(coverage[0].gaps.iter().all(|gap| gap.has_debug && !gap.has_display));
+        assert!(coverage[0].gaps.iter().any(|gap| gap.path.ends_with("budget::Budget")));
+
+        assert!(analyzer.fmt_coverage(Some("no_such_crate"), None).is_err());
+    }
+}
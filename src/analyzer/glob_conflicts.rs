@@ -0,0 +1,292 @@
+//! Names that glob imports make surprising
+//!
+//! A glob import brings in every public name of a module, so a name used
+//! in its scope can come from somewhere a reader doesn't expect:
+//!
+//! - an item declared or explicitly imported in the scope silently wins over
+//!   a glob-imported item of the same name (`shadowed_glob`), so the code
+//!   doesn't use the item the glob's module provides;
+//! - two globs provide different items of the same name (`ambiguous_glob`),
+//!   which the compiler rejects as soon as the name is used, and which
+//!   adding an item to either module can bring about.
+//!
+//! Automated refactors that add items or imports are prone to introducing
+//! both. Only names the code in the scope actually uses are reported.
+
+use super::budget::{Budget, Coverage};
+use super::reference_index::definition_key;
+use super::{normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, ModuleDef, PathResolution, ScopeDef, Semantics, StructKind};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode, SyntaxKind, SyntaxNode,
+};
+
+/// Kinds of glob conflict
+pub const GLOB_CONFLICT_KINDS: &[&str] = &["shadowed_glob", "ambiguous_glob"];
+
+/// Namespaces an item occupies, as bits, so items in different namespaces
+/// (a function and a module named alike) don't conflict
+const TYPES: u8 = 1;
+const VALUES: u8 = 2;
+const MACROS: u8 = 4;
+
+/// Where a conflicting name comes from
+#[derive(Debug, Clone)]
+pub struct NameSource {
+    /// The `use` declaration, or `declared` for an item of the scope itself
+    pub via: String,
+    /// Qualified path of the item the name refers to through it
+    pub item: String,
+}
+
+/// A name used in a scope where glob imports make its meaning unclear
+#[derive(Debug, Clone)]
+pub struct GlobConflict {
+    /// One of `GLOB_CONFLICT_KINDS`
+    pub kind: &'static str,
+    pub name: String,
+    pub file_path: String,
+    /// First use of the name in the scope (0-based)
+    pub line: u32,
+    /// Qualified path of what the name resolves to there, if it resolves
+    pub resolved: Option<String>,
+    /// The competing sources: the winning one first for `shadowed_glob`
+    pub sources: Vec<NameSource>,
+}
+
+/// Glob conflicts of the workspace files checked within the budget
+#[derive(Debug, Clone)]
+pub struct WorkspaceGlobConflicts {
+    pub conflicts: Vec<GlobConflict>,
+    pub coverage: Coverage,
+}
+
+/// An item a name in scope may refer to
+struct Candidate {
+    def: ModuleDef,
+    namespaces: u8,
+    source: NameSource,
+}
+
+impl Analyzer {
+    /// Find the glob conflicts of one file
+    pub fn glob_conflicts(&self, file_path: &str) -> Result<Vec<GlobConflict>, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        self.file_glob_conflicts(file_id)
+    }
+
+    /// Find the glob conflicts of every workspace file, by path, until the budget runs out
+    pub fn workspace_glob_conflicts(&self, budget: Budget) -> Result<WorkspaceGlobConflicts, AnalyzerError> {
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        let mut conflicts = Vec::new();
+        for (_, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            conflicts.extend(self.file_glob_conflicts(file_id)?);
+            coverage.files_scanned += 1;
+        }
+        Ok(WorkspaceGlobConflicts { conflicts, coverage })
+    }
+
+    fn file_glob_conflicts(&self, file_id: FileId) -> Result<Vec<GlobConflict>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source = sema.parse_guess_edition(file_id);
+        let file_path = self.file_path(file_id);
+
+        // The file's own scope and those of its inline modules
+        let scopes = std::iter::once(source.syntax().clone())
+            .chain(source.syntax().descendants().filter_map(ast::ItemList::cast).map(|list| list.syntax().clone()));
+
+        let mut conflicts = Vec::new();
+        for scope in scopes {
+            let Some(module) = sema.scope(&scope).map(|scope| scope.module()) else { continue };
+            let (globbed, explicit) = scope_candidates(&sema, &scope, module);
+            if globbed.is_empty() {
+                continue;
+            }
+
+            let mut reported: Vec<String> = Vec::new();
+            for path in own_nodes(&scope).filter_map(ast::Path::cast).filter(|path| path.qualifier().is_none()) {
+                let Some(name) = path.segment().and_then(|segment| segment.name_ref()) else { continue };
+                let name = name.text().to_string();
+                if reported.contains(&name) {
+                    continue;
+                }
+                let from_globs: Vec<&Candidate> = globbed.iter().filter(|(n, _)| *n == name).map(|(_, c)| c).collect();
+                if from_globs.is_empty() {
+                    continue;
+                }
+                let resolution = match sema.resolve_path(&path) {
+                    Some(PathResolution::Def(def)) => Some(def),
+                    _ => None,
+                };
+                // Only the namespace the use resolves in matters
+                let wanted = resolution.map(|def| namespaces(db, def)).unwrap_or(TYPES | VALUES | MACROS);
+                let from_globs: Vec<&Candidate> = from_globs.into_iter().filter(|c| c.namespaces & wanted != 0).collect();
+
+                let winner = explicit.iter().filter(|(n, _)| *n == name).map(|(_, c)| c).find(|c| c.namespaces & wanted != 0);
+                let (kind, sources) = if let Some(winner) = winner {
+                    let shadowed: Vec<&Candidate> = from_globs.iter().copied().filter(|c| c.def != winner.def).collect();
+                    if shadowed.is_empty() {
+                        continue;
+                    }
+                    ("shadowed_glob", std::iter::once(winner).chain(shadowed).map(|c| c.source.clone()).collect())
+                } else {
+                    let mut distinct: Vec<&Candidate> = Vec::new();
+                    for candidate in from_globs {
+                        if !distinct.iter().any(|c| c.def == candidate.def) {
+                            distinct.push(candidate);
+                        }
+                    }
+                    if distinct.len() < 2 {
+                        continue;
+                    }
+                    ("ambiguous_glob", distinct.into_iter().map(|c| c.source.clone()).collect())
+                };
+
+                let (line, _) = self.line_range(file_id, path.syntax().text_range())?;
+                conflicts.push(GlobConflict {
+                    kind,
+                    name: name.clone(),
+                    file_path: file_path.clone(),
+                    line,
+                    resolved: resolution.and_then(|def| definition_key(db, Definition::from(def))),
+                    sources,
+                });
+                reported.push(name);
+            }
+        }
+        Ok(conflicts)
+    }
+}
+
+/// The names glob imports bring into a scope, and those its own items and
+/// explicit imports bring in
+fn scope_candidates(
+    sema: &Semantics<'_, RootDatabase>,
+    scope: &SyntaxNode,
+    module: ra_ap_hir::Module,
+) -> (Vec<(String, Candidate)>, Vec<(String, Candidate)>) {
+    let db = sema.db;
+    let candidate = |def: ModuleDef, via: &str| Candidate {
+        def,
+        namespaces: namespaces(db, def),
+        source: NameSource {
+            via: via.to_string(),
+            item: definition_key(db, Definition::from(def)).unwrap_or_else(|| "?".to_string()),
+        },
+    };
+
+    let mut explicit: Vec<(String, Candidate)> = module
+        .declarations(db)
+        .into_iter()
+        .filter_map(|def| Some((def.name(db)?.as_str().to_string(), candidate(def, "declared"))))
+        .collect();
+    let mut globbed = Vec::new();
+
+    for item in scope.children().filter_map(ast::Use::cast) {
+        let via = normalize_whitespace(&item.syntax().text().to_string());
+        for tree in item.syntax().descendants().filter_map(ast::UseTree::cast) {
+            if tree.use_tree_list().is_some() {
+                continue;
+            }
+            let Some(path) = tree.path() else { continue };
+            let Some(PathResolution::Def(def)) = sema.resolve_path(&path) else { continue };
+
+            if tree.star_token().is_some() {
+                let items: Vec<(String, ModuleDef)> = match def {
+                    ModuleDef::Module(target) => target
+                        .scope(db, Some(module))
+                        .into_iter()
+                        .filter_map(|(name, def)| match def {
+                            ScopeDef::ModuleDef(def) => Some((name.as_str().to_string(), def)),
+                            _ => None,
+                        })
+                        .collect(),
+                    ModuleDef::Adt(Adt::Enum(enum_)) => enum_
+                        .variants(db)
+                        .into_iter()
+                        .map(|variant| (variant.name(db).as_str().to_string(), ModuleDef::Variant(variant)))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                globbed.extend(items.into_iter().map(|(name, def)| (name, candidate(def, &via))));
+            } else {
+                let name = match tree.rename() {
+                    Some(rename) => rename.name().map(|name| name.text().to_string()),
+                    None => path.segment().and_then(|segment| segment.name_ref()).map(|name| name.text().to_string()),
+                };
+                // `self` imports the module named by the path's qualifier
+                let name = match name.as_deref() {
+                    Some("self") => path.qualifier().and_then(|q| q.segment()?.name_ref()).map(|name| name.text().to_string()),
+                    _ => name,
+                };
+                if let Some(name) = name.filter(|name| name != "_") {
+                    explicit.push((name, candidate(def, &via)));
+                }
+            }
+        }
+    }
+    (globbed, explicit)
+}
+
+/// The nodes of a scope, leaving out `use` declarations and the bodies of
+/// inline modules, which are scopes of their own
+fn own_nodes(scope: &SyntaxNode) -> impl Iterator<Item = SyntaxNode> + '_ {
+    let nested = |node: &SyntaxNode| {
+        node.kind() == SyntaxKind::USE
+            || (node.kind() == SyntaxKind::ITEM_LIST && node.parent().is_some_and(|parent| ast::Module::can_cast(parent.kind())))
+    };
+    scope
+        .descendants()
+        .filter(move |node| !node.ancestors().take_while(|ancestor| ancestor != scope).any(|ancestor| nested(&ancestor)))
+}
+
+/// The namespaces an item occupies
+fn namespaces(db: &RootDatabase, def: ModuleDef) -> u8 {
+    match def {
+        ModuleDef::Function(_) | ModuleDef::Const(_) | ModuleDef::Static(_) => VALUES,
+        ModuleDef::Adt(Adt::Struct(s)) if s.kind(db) != StructKind::Record => TYPES | VALUES,
+        ModuleDef::Variant(_) => TYPES | VALUES,
+        ModuleDef::Macro(_) => MACROS,
+        _ => TYPES,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_own_nodes_skip_uses_and_inline_modules() {
+        let file = ra_ap_syntax::SourceFile::parse(
+            "use a::*;\nfn f() { g(); }\nmod inner { fn h() { k(); } }",
+            ra_ap_syntax::Edition::CURRENT,
+        )
+        .tree();
+        let names: Vec<String> = own_nodes(file.syntax())
+            .filter_map(ast::Path::cast)
+            .filter(|path| path.qualifier().is_none())
+            .map(|path| path.syntax().text().to_string())
+            .collect();
+        assert_eq!(names, vec!["g"]);
+    }
+
+    #[test]
+    fn test_workspace_glob_conflicts_complete() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let report = analyzer.workspace_glob_conflicts(Budget::unlimited()).unwrap();
+        assert!(report.coverage.complete());
+        assert!(report.conflicts.iter().all(|c| GLOB_CONFLICT_KINDS.contains(&c.kind) && c.sources.len() >= 2));
+    }
+}
@@ -0,0 +1,92 @@
+//! Uses of dependencies' hidden API
+//!
+//! Items marked `#[doc(hidden)]` are public only for technical reasons, such
+//! as macro support code, and are exempt from semver: a patch release may
+//! change or remove them. The reference index records which referenced
+//! dependency items are hidden, so this lists the workspace code that can
+//! break on a dependency update without a major version bump.
+//!
+//! An item counts as hidden when it, its parent (the enum of a variant, the
+//! trait or impl of an associated item) or any enclosing module is.
+
+use super::reference_index::IndexedReferences;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{AssocItemContainer, HasAttrs, VariantDef};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+
+impl Analyzer {
+    /// List the `#[doc(hidden)]` dependency items the workspace references,
+    /// with the reference sites, from the reference index
+    pub fn hidden_api_uses(&self) -> Result<Vec<IndexedReferences>, AnalyzerError> {
+        let index = self.references.as_ref()
+            .ok_or_else(|| AnalyzerError::Other("Reference index has not been built yet".to_string()))?;
+        Ok(index.hidden_uses())
+    }
+}
+
+/// Whether a definition is `#[doc(hidden)]`, itself or through its parent
+/// or an enclosing module
+pub(super) fn is_doc_hidden(db: &RootDatabase, def: Definition) -> bool {
+    let container = match def {
+        Definition::Function(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::Const(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::TypeAlias(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        _ => None,
+    };
+    let hidden = match def {
+        Definition::Module(it) => it.attrs(db).is_doc_hidden(),
+        Definition::Adt(it) => it.attrs(db).is_doc_hidden(),
+        Definition::Variant(it) => it.attrs(db).is_doc_hidden() || it.parent_enum(db).attrs(db).is_doc_hidden(),
+        Definition::Field(it) => {
+            it.attrs(db).is_doc_hidden()
+                || match it.parent_def(db) {
+                    VariantDef::Struct(s) => s.attrs(db).is_doc_hidden(),
+                    VariantDef::Union(u) => u.attrs(db).is_doc_hidden(),
+                    VariantDef::Variant(v) => v.attrs(db).is_doc_hidden() || v.parent_enum(db).attrs(db).is_doc_hidden(),
+                }
+        }
+        Definition::Trait(it) => it.attrs(db).is_doc_hidden(),
+        Definition::TypeAlias(it) => it.attrs(db).is_doc_hidden() || container_hidden(db, container),
+        Definition::Function(it) => it.attrs(db).is_doc_hidden() || container_hidden(db, container),
+        Definition::Const(it) => it.attrs(db).is_doc_hidden() || container_hidden(db, container),
+        Definition::Static(it) => it.attrs(db).is_doc_hidden(),
+        Definition::Macro(it) => it.attrs(db).is_doc_hidden(),
+        _ => false,
+    };
+
+    hidden
+        || def
+            .module(db)
+            .is_some_and(|module| module.path_to_root(db).into_iter().any(|m| m.attrs(db).is_doc_hidden()))
+}
+
+/// Whether the trait or impl holding an associated item is hidden; for an
+/// impl, also whether its self type is
+fn container_hidden(db: &RootDatabase, container: Option<AssocItemContainer>) -> bool {
+    match container {
+        Some(AssocItemContainer::Trait(trait_)) => trait_.attrs(db).is_doc_hidden(),
+        Some(AssocItemContainer::Impl(impl_)) => {
+            impl_.attrs(db).is_doc_hidden()
+                || impl_.self_ty(db).as_adt().is_some_and(|adt| adt.attrs(db).is_doc_hidden())
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hidden_api_uses_need_the_index() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        assert!(analyzer.hidden_api_uses().is_err());
+
+        analyzer.build_reference_index().unwrap();
+        let uses = analyzer.hidden_api_uses().unwrap();
+        // Only dependencies' items, never the workspace's own
+        assert!(uses.iter().all(|u| !u.definition.starts_with("cratographer::")));
+    }
+}
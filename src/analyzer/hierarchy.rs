@@ -0,0 +1,183 @@
+//! Trait and type hierarchy
+//!
+//! For a trait: its supertraits, the traits declaring it as a supertrait and
+//! the types implementing it. For a type: the traits it implements. Built on
+//! the same impl queries as the override matrix; blanket impls such as
+//! `impl<T: Display> ToString for T` are listed for traits but not matched
+//! against a queried type.
+
+use super::reference_index::definition_key;
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, Impl, ModuleDef, Semantics, Trait};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::AstNode;
+
+/// A trait or type related to the queried one
+#[derive(Debug, Clone)]
+pub struct Related {
+    /// Qualified path of a trait, or the self type of an impl
+    pub name: String,
+    /// Location of the trait, or of the impl for implementors and
+    /// implemented traits
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// The hierarchy around one trait or type
+#[derive(Debug, Clone)]
+pub struct Hierarchy {
+    /// Qualified path of the queried trait or type
+    pub path: String,
+    /// "trait" or "type"
+    pub kind: &'static str,
+    /// Direct supertraits (traits only)
+    pub supertraits: Vec<Related>,
+    /// Traits with the queried one as a direct supertrait (traits only)
+    pub subtraits: Vec<Related>,
+    /// Impls of the trait, by self type (traits only)
+    pub implementors: Vec<Related>,
+    /// Traits the type implements, by impl (types only)
+    pub traits: Vec<Related>,
+}
+
+impl Analyzer {
+    /// Build the hierarchy of the trait or type matching a name, qualified
+    /// path or symbol_id
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several match. Subtraits, implementors and implemented traits outside the workspace
+    /// are only included with `include_library`.
+    pub fn type_hierarchy(&self, name: &str, include_library: bool) -> Result<Vec<Hierarchy>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let included = |krate: Crate| include_library || krate.origin(db).is_local();
+
+        let definitions: Vec<Definition> = self.resolve_definitions(&sema, name)?
+            .into_iter()
+            .filter(|def| matches!(def, Definition::Trait(_) | Definition::Adt(_)))
+            .collect();
+        self.require_unique(&sema, name, &definitions)?;
+
+        let mut hierarchies = Vec::new();
+        for def in definitions {
+            let path = definition_key(db, def).unwrap_or_else(|| name.to_string());
+            let hierarchy = match def {
+                Definition::Trait(trait_) => {
+                    let mut supertraits = Vec::new();
+                    for supertrait in trait_.direct_supertraits(db) {
+                        supertraits.extend(self.trait_location(&sema, supertrait)?);
+                    }
+
+                    let mut subtraits = Vec::new();
+                    for krate in Crate::all(db).into_iter().filter(|krate| included(*krate)) {
+                        for module in krate.modules(db) {
+                            for decl in module.declarations(db) {
+                                let ModuleDef::Trait(other) = decl else { continue };
+                                if other.direct_supertraits(db).contains(&trait_) {
+                                    subtraits.extend(self.trait_location(&sema, other)?);
+                                }
+                            }
+                        }
+                    }
+
+                    let mut implementors = Vec::new();
+                    for impl_ in Impl::all_for_trait(db, trait_) {
+                        let krate = impl_.module(db).krate();
+                        if !included(krate) {
+                            continue;
+                        }
+                        let name = display_type(db, &impl_.self_ty(db), krate);
+                        implementors.extend(self.impl_location(&sema, impl_, name)?);
+                    }
+
+                    Hierarchy { path, kind: "trait", supertraits, subtraits, implementors, traits: Vec::new() }
+                }
+                Definition::Adt(adt) => {
+                    let mut traits = Vec::new();
+                    for impl_ in Impl::all_for_type(db, adt.ty(db)) {
+                        let Some(trait_) = impl_.trait_(db) else { continue };
+                        if !included(impl_.module(db).krate()) {
+                            continue;
+                        }
+                        let name = definition_key(db, Definition::Trait(trait_)).unwrap_or_default();
+                        traits.extend(self.impl_location(&sema, impl_, name)?);
+                    }
+
+                    Hierarchy {
+                        path,
+                        kind: "type",
+                        supertraits: Vec::new(),
+                        subtraits: Vec::new(),
+                        implementors: Vec::new(),
+                        traits,
+                    }
+                }
+                _ => continue,
+            };
+            hierarchies.push(hierarchy);
+        }
+
+        if hierarchies.is_empty() {
+            return Err(AnalyzerError::Other(format!("No trait or type named '{}' found", name)));
+        }
+        for hierarchy in &mut hierarchies {
+            for list in [&mut hierarchy.subtraits, &mut hierarchy.implementors, &mut hierarchy.traits] {
+                list.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+            }
+        }
+        Ok(hierarchies)
+    }
+
+    /// A trait with the location of its definition
+    fn trait_location(&self, sema: &Semantics<'_, RootDatabase>, trait_: Trait) -> Result<Option<Related>, AnalyzerError> {
+        let db = sema.db;
+        let Some(source) = sema.source(trait_) else { return Ok(None) };
+        let range = sema.original_range(source.value.syntax());
+        let file_id = range.file_id.file_id(db);
+        let (start_line, end_line) = self.line_range(file_id, range.range)?;
+        Ok(Some(Related {
+            name: definition_key(db, Definition::Trait(trait_)).unwrap_or_else(|| trait_.name(db).as_str().to_string()),
+            file_path: self.file_path(file_id),
+            start_line,
+            end_line,
+        }))
+    }
+
+    /// An impl, under the given name, with the location of its block
+    fn impl_location(&self, sema: &Semantics<'_, RootDatabase>, impl_: Impl, name: String) -> Result<Option<Related>, AnalyzerError> {
+        let Some(source) = sema.source(impl_) else { return Ok(None) };
+        let range = sema.original_range(source.value.syntax());
+        let file_id = range.file_id.file_id(sema.db);
+        let (start_line, end_line) = self.line_range(file_id, range.range)?;
+        Ok(Some(Related { name, file_path: self.file_path(file_id), start_line, end_line }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchy_of_symbol_provider() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let hierarchies = analyzer.type_hierarchy("SymbolProvider", false).unwrap();
+        assert_eq!(hierarchies.len(), 1);
+        let provider = &hierarchies[0];
+        assert_eq!(provider.kind, "trait");
+        assert!(provider.implementors.iter().any(|r| r.name == "Analyzer"));
+        assert!(provider.implementors.iter().any(|r| r.name == "QuickIndex"));
+    }
+
+    #[test]
+    fn test_hierarchy_of_type() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let hierarchies = analyzer.type_hierarchy("QuickIndex", false).unwrap();
+        assert_eq!(hierarchies[0].kind, "type");
+        assert!(hierarchies[0].traits.iter().any(|r| r.name.ends_with("SymbolProvider")));
+    }
+}
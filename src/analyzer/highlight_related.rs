@@ -0,0 +1,111 @@
+//! Related highlights within a file
+//!
+//! What an editor highlights when the cursor rests on a token: every usage
+//! of the identifier under it in the same file, read or written, and for
+//! control-flow keywords the places they relate to: the `break`s and
+//! `continue`s of a loop, the exit points (`return`, `?` and the tail
+//! expression) of a function or closure, the `.await`s of an async block.
+//! Cheaper than a workspace-wide reference search when only the current
+//! file matters.
+
+use super::references::reference_kind;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide::{HighlightRelatedConfig, ReferenceCategory};
+
+/// A highlighted range
+#[derive(Debug, Clone)]
+pub struct RelatedHighlight {
+    /// 0-based start and end of the range
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// `read`, `write` or `import` for usages of a symbol, `related` for
+    /// control-flow highlights such as exit points
+    pub kind: &'static str,
+    /// The highlighted text, e.g. `count` or `return`
+    pub text: String,
+}
+
+impl Analyzer {
+    /// The ranges related to the token at a position (0-based) in the same
+    /// file, in source order
+    pub fn highlight_related(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<RelatedHighlight>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+        let config = HighlightRelatedConfig {
+            references: true,
+            exit_points: true,
+            break_points: true,
+            closure_captures: true,
+            yield_points: true,
+            branch_exit_points: true,
+        };
+        let ranges = analysis
+            .highlight_related(config, position)
+            .map_err(|_| AnalyzerError::Canceled)?
+            .unwrap_or_default();
+        let line_index = analysis.file_line_index(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let text = analysis.file_text(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let mut highlights: Vec<RelatedHighlight> = ranges
+            .into_iter()
+            .map(|highlight| {
+                let start = line_index.line_col(highlight.range.start());
+                let end = line_index.line_col(highlight.range.end());
+                RelatedHighlight {
+                    line: start.line,
+                    column: start.col,
+                    end_line: end.line,
+                    end_column: end.col,
+                    kind: highlight_kind(highlight.category),
+                    text: text[highlight.range].to_string(),
+                }
+            })
+            .collect();
+        highlights.sort_by_key(|h| (h.line, h.column));
+        highlights.dedup_by_key(|h| (h.line, h.column, h.end_line, h.end_column));
+        Ok(highlights)
+    }
+}
+
+/// The kind of a highlight: how it uses the symbol, or `related` for one
+/// that isn't a usage
+fn highlight_kind(category: ReferenceCategory) -> &'static str {
+    if category.is_empty() {
+        "related"
+    } else {
+        reference_kind(category)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_highlight_kind() {
+        assert_eq!(highlight_kind(ReferenceCategory::empty()), "related");
+        assert_eq!(highlight_kind(ReferenceCategory::WRITE), "write");
+        assert_eq!(highlight_kind(ReferenceCategory::READ), "read");
+    }
+
+    #[test]
+    fn test_highlight_related_local() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let file_path = std::fs::canonicalize("src/analyzer/highlight_related.rs").unwrap();
+        let file_path = file_path.to_str().unwrap();
+
+        // `line_index` where it is bound in highlight_related
+        let source = std::fs::read_to_string(file_path).unwrap();
+        let (line, text) = source.lines().enumerate().find(|(_, l)| l.trim_start().starts_with("let line_index")).unwrap();
+        let column = text.find("line_index").unwrap() as u32;
+
+        let highlights = analyzer.highlight_related(file_path, line as u32, column).unwrap();
+        assert!(highlights.len() >= 3);
+        assert!(highlights.iter().all(|h| h.text == "line_index"));
+        assert_eq!(highlights[0].line, line as u32);
+        assert!(highlights[1..].iter().all(|h| h.kind == "read"));
+    }
+}
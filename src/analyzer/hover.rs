@@ -0,0 +1,85 @@
+//! Hover information at a position
+//!
+//! The same popup an editor shows: the resolved signature or type of the
+//! symbol under the cursor, its trait bounds and generic substitutions, and
+//! its rendered documentation, as Markdown.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide::{FileRange, HoverConfig, HoverDocFormat, SubstTyLen};
+use ra_ap_syntax::TextRange;
+
+/// Hover text for a symbol or expression
+#[derive(Debug, Clone)]
+pub struct Hover {
+    /// Markdown with the signature or type in a code block, then the docs
+    pub markdown: String,
+    /// Extent of the hovered token or expression (0-based)
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+/// Hover settings: full documentation, no links (which would point at an
+/// editor's URL handlers), and no truncation of types or item lists
+fn hover_config() -> HoverConfig {
+    HoverConfig {
+        links_in_hover: false,
+        memory_layout: None,
+        documentation: true,
+        keywords: true,
+        format: HoverDocFormat::Markdown,
+        max_trait_assoc_items_count: None,
+        max_fields_count: None,
+        max_enum_variants_count: None,
+        max_subst_ty_len: SubstTyLen::Unlimited,
+        show_drop_glue: false,
+    }
+}
+
+impl Analyzer {
+    /// Hover information at a position (0-based), or `None` where an editor
+    /// would show nothing, e.g. on whitespace or punctuation
+    pub fn hover(&self, file_path: &str, line: u32, column: u32) -> Result<Option<Hover>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+        let range = FileRange { file_id: position.file_id, range: TextRange::empty(position.offset) };
+        let Some(result) = analysis.hover(&hover_config(), range).map_err(|_| AnalyzerError::Canceled)? else {
+            return Ok(None);
+        };
+
+        let line_index = analysis.file_line_index(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let start = line_index.line_col(result.range.start());
+        let end = line_index.line_col(result.range.end());
+        Ok(Some(Hover {
+            markdown: result.info.markup.as_str().to_string(),
+            start_line: start.line,
+            start_column: start.col,
+            end_line: end.line,
+            end_column: end.col,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hover_shows_signature_and_docs() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `pub fn hover(&self, ...)` in this file
+        let path = std::fs::canonicalize("src/analyzer/hover.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.trim_start().starts_with("pub fn hover(")).unwrap() as u32;
+        let column = text.lines().nth(line as usize).unwrap().find("hover").unwrap() as u32;
+
+        let hover = analyzer.hover(path, line, column).unwrap().expect("Function names have hover text");
+        assert!(hover.markdown.contains("pub fn hover(&self"), "Hover was {}", hover.markdown);
+        assert!(hover.markdown.contains("Hover information at a position"));
+        assert_eq!(hover.start_line, line);
+    }
+}
@@ -0,0 +1,181 @@
+//! `impl Trait` return type inspection
+//!
+//! For functions returning an opaque `impl Trait`, reports what callers can rely
+//! on (the declared bounds and everything they imply) and, where inference can
+//! see through the body, the concrete type hiding behind it.
+
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode, SyntaxKind,
+};
+
+/// What is known about a function's `impl Trait` return type
+#[derive(Debug, Clone)]
+pub struct ImplTraitReturn {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The return type as written, e.g. `impl Iterator<Item = &str> + '_`
+    pub return_type: String,
+    /// Bounds declared on the opaque type
+    pub bounds: Vec<String>,
+    /// Supertraits implied by the bounds, which callers may also use
+    pub implied_bounds: Vec<String>,
+    /// Concrete types produced by the body, if inference could determine them
+    pub concrete_types: Vec<String>,
+}
+
+impl Analyzer {
+    /// Inspect functions that return `impl Trait`
+    ///
+    /// With a function name, only matching functions are reported; otherwise
+    /// every workspace function (or every function in `file_path`) returning
+    /// `impl Trait` is listed.
+    pub fn impl_trait_returns(&self, function: Option<&str>, file_path: Option<&str>) -> Result<Vec<ImplTraitReturn>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let candidates: Vec<(FileId, ast::Fn)> = match function {
+            Some(name) => self.find_functions(&sema, name, file_path)?,
+            None => {
+                let files = match file_path {
+                    Some(path) => vec![self.file_id(path)?],
+                    None => self.workspace_files()?,
+                };
+                files
+                    .into_iter()
+                    .flat_map(|file_id| {
+                        sema.parse_guess_edition(file_id)
+                            .syntax()
+                            .descendants()
+                            .filter_map(ast::Fn::cast)
+                            .map(move |func| (file_id, func))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect()
+            }
+        };
+
+        let mut results = Vec::new();
+        for (file_id, func) in candidates {
+            let Some(ret) = func.ret_type().and_then(|r| r.ty()) else { continue };
+            let impl_traits: Vec<ast::ImplTraitType> = ret.syntax().descendants().filter_map(ast::ImplTraitType::cast).collect();
+            if impl_traits.is_empty() {
+                continue;
+            }
+
+            let bounds: Vec<String> = impl_traits
+                .iter()
+                .filter_map(|it| it.type_bound_list())
+                .flat_map(|list| list.bounds())
+                .map(|b| b.syntax().text().to_string())
+                .collect();
+
+            let mut implied_bounds = Vec::new();
+            let mut concrete_types = Vec::new();
+
+            if let Some(def) = sema.to_def(&func) {
+                let krate = def.module(db).krate();
+
+                // Supertraits of the declared bounds are usable on the opaque type too;
+                // comparing resolved traits keeps `ExactSizeIterator` from hiding `Iterator`
+                if let Some(traits) = def.ret_type(db).as_impl_traits(db) {
+                    let declared: Vec<_> = traits.collect();
+                    for trait_ in &declared {
+                        for supertrait in trait_.all_supertraits(db) {
+                            let name = supertrait.name(db).as_str().to_string();
+                            if !declared.contains(&supertrait) && !implied_bounds.contains(&name) {
+                                implied_bounds.push(name);
+                            }
+                        }
+                    }
+                }
+
+                // The concrete type is whatever the body evaluates to
+                for expr in returned_exprs(&func) {
+                    if let Some(ty) = sema.type_of_expr(&expr) {
+                        let rendered = display_type(db, &ty.original, krate);
+                        if !concrete_types.contains(&rendered) {
+                            concrete_types.push(rendered);
+                        }
+                    }
+                }
+            }
+
+            let (start_line, end_line) = self.line_range(file_id, func.syntax().text_range())?;
+            results.push(ImplTraitReturn {
+                function: func.name().map(|n| n.text().to_string()).unwrap_or_default(),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                return_type: ret.syntax().text().to_string(),
+                bounds,
+                implied_bounds,
+                concrete_types,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+/// Expressions whose value leaves the function: the body's tail expression
+/// and the operands of `return`, skipping nested closures and items
+fn returned_exprs(func: &ast::Fn) -> Vec<ast::Expr> {
+    let Some(body) = func.body() else { return Vec::new() };
+    let mut exprs: Vec<ast::Expr> = body.stmt_list().and_then(|s| s.tail_expr()).into_iter().collect();
+
+    let mut preorder = body.syntax().preorder();
+    while let Some(event) = preorder.next() {
+        let ra_ap_syntax::WalkEvent::Enter(node) = event else { continue };
+        if matches!(node.kind(), SyntaxKind::CLOSURE_EXPR | SyntaxKind::FN) {
+            preorder.skip_subtree();
+            continue;
+        }
+        if let Some(expr) = ast::ReturnExpr::cast(node).and_then(|r| r.expr()) {
+            exprs.push(expr);
+        }
+    }
+
+    exprs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::scratch::ScratchProject;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_returned_exprs_skip_closures() {
+        let source = "fn f(x: bool) -> impl Fn() -> u8 { if x { return || 1; } let g = || { return 2; }; move || 3 }";
+        let parse = SourceFile::parse(source, Edition::CURRENT);
+        let func = parse.tree().syntax().descendants().find_map(ast::Fn::cast).unwrap();
+
+        let exprs: Vec<String> = returned_exprs(&func).iter().map(|e| e.syntax().text().to_string()).collect();
+        assert_eq!(exprs, vec!["move || 3".to_string(), "|| 1".to_string()]);
+    }
+
+    #[test]
+    fn test_implied_bounds_of_iterator_subtraits() {
+        let analyzer = ScratchProject::new(
+            "impl-trait",
+            &[(
+                "iters.rs",
+                "pub fn exact_probe() -> impl ExactSizeIterator<Item = u8> { vec![1u8].into_iter() }\n\
+                 pub fn double_ended_probe() -> impl DoubleEndedIterator<Item = u8> { vec![1u8].into_iter() }\n\
+                 pub fn both_probe() -> impl Iterator<Item = u8> + ExactSizeIterator { vec![1u8].into_iter() }\n",
+            )],
+        );
+
+        let implied = |function: &str| analyzer.impl_trait_returns(Some(function), None).unwrap()[0].implied_bounds.clone();
+        assert!(implied("exact_probe").contains(&"Iterator".to_string()));
+        assert!(implied("double_ended_probe").contains(&"Iterator".to_string()));
+        // A bound that is also declared isn't implied
+        assert!(!implied("both_probe").contains(&"Iterator".to_string()));
+    }
+}
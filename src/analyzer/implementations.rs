@@ -0,0 +1,186 @@
+//! Impl blocks of a trait or type
+//!
+//! Lists every `impl Trait for X` block of a trait, resolved through the
+//! crate graph rather than matched by name, so re-exported and renamed traits
+//! are found and same-named unrelated traits are not. Blanket impls such as
+//! `impl<T: Display> ToString for T` are included and flagged.
+//!
+//! For a type, lists the inherent and trait impls targeting it, with their
+//! members: the type's full method surface. Blanket impls are not matched
+//! against a queried type.
+
+use super::reference_index::definition_key;
+use super::{display_type, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, AssocItem, Impl, Semantics, Trait};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{ast, AstNode};
+
+/// One impl block
+#[derive(Debug, Clone)]
+pub struct ImplBlock {
+    /// Qualified path of the implemented trait
+    pub trait_path: Option<String>,
+    /// The implementing type, e.g. `Vec<T>`
+    pub self_type: String,
+    /// The impl header as written, e.g. `impl<T: Display> ToString for T`
+    pub header: String,
+    /// Whether the impl is for a bare type parameter, covering many types
+    pub blanket: bool,
+    /// The impl's members, e.g. `fn len`, `const MAX` or `type Item`
+    pub items: Vec<String>,
+    pub crate_name: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+impl Analyzer {
+    /// List the impl blocks of the trait matching a name, qualified path or
+    /// symbol_id
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several traits match. Impls outside the workspace are only included with `include_library`.
+    pub fn find_implementations(&self, trait_name: &str, include_library: bool) -> Result<Vec<ImplBlock>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let traits: Vec<Trait> = self.resolve_definitions(&sema, trait_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Trait(trait_) => Some(trait_),
+                _ => None,
+            })
+            .collect();
+        if traits.is_empty() {
+            return Err(AnalyzerError::Other(format!("No trait named '{}' found", trait_name)));
+        }
+        self.require_unique(&sema, trait_name, &traits.iter().map(|&t| Definition::Trait(t)).collect::<Vec<_>>())?;
+
+        let mut blocks = Vec::new();
+        for trait_ in traits {
+            for impl_ in Impl::all_for_trait(db, trait_) {
+                if !include_library && !impl_.module(db).krate().origin(db).is_local() {
+                    continue;
+                }
+                blocks.extend(self.impl_block(&sema, impl_)?);
+            }
+        }
+
+        blocks.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        Ok(blocks)
+    }
+
+    /// List the inherent and trait impls of the type matching a name,
+    /// qualified path or symbol_id, inherent impls first
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several types match. Impls outside the workspace are only included with `include_library`.
+    pub fn impls_of_type(&self, type_name: &str, include_library: bool) -> Result<Vec<ImplBlock>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let adts: Vec<Adt> = self.resolve_definitions(&sema, type_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Adt(adt) => Some(adt),
+                _ => None,
+            })
+            .collect();
+        if adts.is_empty() {
+            return Err(AnalyzerError::Other(format!("No type named '{}' found", type_name)));
+        }
+        self.require_unique(&sema, type_name, &adts.iter().map(|&a| Definition::Adt(a)).collect::<Vec<_>>())?;
+
+        let mut blocks = Vec::new();
+        for adt in adts {
+            for impl_ in Impl::all_for_type(db, adt.ty(db)) {
+                if !include_library && !impl_.module(db).krate().origin(db).is_local() {
+                    continue;
+                }
+                blocks.extend(self.impl_block(&sema, impl_)?);
+            }
+        }
+
+        blocks.sort_by(|a, b| {
+            (a.trait_path.is_some(), &a.trait_path, &a.file_path, a.start_line)
+                .cmp(&(b.trait_path.is_some(), &b.trait_path, &b.file_path, b.start_line))
+        });
+        Ok(blocks)
+    }
+
+    /// Describe an impl block; `None` if it has no source
+    fn impl_block(&self, sema: &Semantics<'_, RootDatabase>, impl_: Impl) -> Result<Option<ImplBlock>, AnalyzerError> {
+        let db = sema.db;
+        let Some(source) = sema.source(impl_) else { return Ok(None) };
+        let range = sema.original_range(source.value.syntax());
+        let file_id = range.file_id.file_id(db);
+        let (start_line, end_line) = self.line_range(file_id, range.range)?;
+        let krate = impl_.module(db).krate();
+        let self_ty = impl_.self_ty(db);
+
+        Ok(Some(ImplBlock {
+            trait_path: impl_.trait_(db).and_then(|t| definition_key(db, Definition::Trait(t))),
+            self_type: display_type(db, &self_ty, krate),
+            header: impl_header(&source.value),
+            blanket: self_ty.as_type_param(db).is_some(),
+            items: impl_.items(db).into_iter().filter_map(|item| impl_item(db, item)).collect(),
+            crate_name: krate.display_name(db).map(|n| n.to_string()),
+            file_path: self.file_path(file_id),
+            start_line,
+            end_line,
+        }))
+    }
+}
+
+/// An impl member as `fn name`, `const NAME` or `type Name`
+fn impl_item(db: &RootDatabase, item: AssocItem) -> Option<String> {
+    let keyword = match item {
+        AssocItem::Function(_) => "fn",
+        AssocItem::Const(_) => "const",
+        AssocItem::TypeAlias(_) => "type",
+    };
+    Some(format!("{} {}", keyword, item.name(db)?.as_str()))
+}
+
+/// An impl's header, everything before its body, on one line
+pub(super) fn impl_header(impl_: &ast::Impl) -> String {
+    let text = impl_.syntax().text().to_string();
+    normalize_whitespace(text.split('{').next().unwrap_or(&text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_impl_header() {
+        let file = ra_ap_syntax::SourceFile::parse("impl<T>\n    Trait for Vec<T>\nwhere T: Clone {\n    fn f() {}\n}", ra_ap_syntax::Edition::CURRENT).tree();
+        let impl_ = file.syntax().descendants().find_map(ast::Impl::cast).unwrap();
+        assert_eq!(impl_header(&impl_), "impl<T> Trait for Vec<T> where T: Clone");
+    }
+
+    #[test]
+    fn test_implementations_of_symbol_provider() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let blocks = analyzer.find_implementations("SymbolProvider", false).unwrap();
+        let types: Vec<_> = blocks.iter().map(|b| b.self_type.as_str()).collect();
+        assert!(types.contains(&"Analyzer"));
+        assert!(types.contains(&"QuickIndex"));
+        assert!(blocks.iter().all(|b| !b.blanket && b.header.starts_with("impl SymbolProvider for")));
+    }
+
+    #[test]
+    fn test_impls_of_quick_index() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let blocks = analyzer.impls_of_type("QuickIndex", false).unwrap();
+        // The inherent impl sorts first
+        assert_eq!(blocks[0].trait_path, None);
+        assert!(blocks[0].items.contains(&"fn build".to_string()));
+
+        let provider = blocks.iter().find(|b| b.trait_path.as_deref().is_some_and(|p| p.ends_with("SymbolProvider"))).unwrap();
+        assert!(provider.items.contains(&"fn find_symbol".to_string()));
+    }
+}
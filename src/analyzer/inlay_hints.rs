@@ -0,0 +1,138 @@
+//! Inlay hints for a file or line range
+//!
+//! The annotations an editor draws inline: inferred types of bindings and
+//! closures' return types, parameter names at call sites, the types along
+//! method chains and elided lifetimes in signatures. They spell out what
+//! type inference decided, which the source leaves implicit.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::ClosureStyle;
+use ra_ap_ide::{
+    AdjustmentHints, AdjustmentHintsMode, ClosureReturnTypeHints, DiscriminantHints, GenericParameterHints,
+    InlayFieldsToResolve, InlayHintPosition, InlayHintsConfig, LifetimeElisionHints,
+};
+
+/// An inlay hint, at the position an editor would draw it
+#[derive(Debug, Clone)]
+pub struct InlayHint {
+    /// 0-based position the hint is drawn at
+    pub line: u32,
+    pub column: u32,
+    /// e.g. `type`, `parameter`, `chaining` or `lifetime`
+    pub kind: String,
+    /// The hint text, e.g. `: Vec<String>` or `file_path:`
+    pub label: String,
+}
+
+/// Hints for types, parameter names, method chains, closure return types
+/// and non-trivial elided lifetimes, without truncation; the purely visual
+/// ones (closing braces, adjustments, binding modes, drops) are off
+fn inlay_hints_config() -> InlayHintsConfig {
+    InlayHintsConfig {
+        render_colons: true,
+        type_hints: true,
+        sized_bound: false,
+        discriminant_hints: DiscriminantHints::Never,
+        parameter_hints: true,
+        generic_parameter_hints: GenericParameterHints { type_hints: false, lifetime_hints: false, const_hints: false },
+        chaining_hints: true,
+        adjustment_hints: AdjustmentHints::Never,
+        adjustment_hints_mode: AdjustmentHintsMode::Prefix,
+        adjustment_hints_hide_outside_unsafe: false,
+        closure_return_type_hints: ClosureReturnTypeHints::WithBlock,
+        closure_capture_hints: false,
+        binding_mode_hints: false,
+        implicit_drop_hints: false,
+        lifetime_elision_hints: LifetimeElisionHints::SkipTrivial,
+        param_names_for_lifetime_elision_hints: true,
+        hide_named_constructor_hints: true,
+        hide_closure_initialization_hints: false,
+        hide_closure_parameter_hints: false,
+        range_exclusive_hints: false,
+        closure_style: ClosureStyle::ImplFn,
+        max_length: None,
+        closing_brace_hints_min_lines: None,
+        fields_to_resolve: InlayFieldsToResolve::empty(),
+    }
+}
+
+impl Analyzer {
+    /// The inlay hints of a file, or of the given lines (0-based, inclusive),
+    /// in source order
+    pub fn inlay_hints(&self, file_path: &str, lines: Option<(u32, u32)>) -> Result<Vec<InlayHint>, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let range = match lines {
+            Some((start_line, end_line)) => Some(self.lines_text_range(file_id, start_line, end_line)?),
+            None => None,
+        };
+        let analysis = self.host.analysis();
+        let hints = analysis
+            .inlay_hints(&inlay_hints_config(), file_id, range)
+            .map_err(|_| AnalyzerError::Canceled)?;
+        let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let mut results: Vec<InlayHint> = hints
+            .into_iter()
+            .map(|hint| {
+                let offset = match hint.position {
+                    InlayHintPosition::Before => hint.range.start(),
+                    InlayHintPosition::After => hint.range.end(),
+                };
+                let position = line_index.line_col(offset);
+                InlayHint {
+                    line: position.line,
+                    column: position.col,
+                    kind: kind_name(&format!("{:?}", hint.kind)),
+                    label: hint.label.to_string(),
+                }
+            })
+            .collect();
+        results.sort_by_key(|hint| (hint.line, hint.column));
+        Ok(results)
+    }
+}
+
+/// snake_case name of an `InlayKind` variant, e.g. `ClosureReturnType` ->
+/// `closure_return_type`
+fn kind_name(variant: &str) -> String {
+    let mut name = String::new();
+    for (i, c) in variant.chars().enumerate() {
+        if c.is_ascii_uppercase() && i > 0 {
+            name.push('_');
+        }
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kind_name() {
+        assert_eq!(kind_name("Type"), "type");
+        assert_eq!(kind_name("GenericParamList"), "generic_param_list");
+    }
+
+    #[test]
+    fn test_inlay_hints_for_lines() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `let position = line_index.line_col(offset);` in this file gets a type hint
+        let path = std::fs::canonicalize("src/analyzer/inlay_hints.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("let position = line_index.line_col(offset);")).unwrap() as u32;
+
+        let hints = analyzer.inlay_hints(path, Some((line, line))).unwrap();
+        assert!(hints.iter().any(|h| h.kind == "type" && h.label.contains("LineCol")));
+        assert!(hints.iter().all(|h| h.line == line));
+
+        // `u32::MAX` as the last line runs to the end of the file
+        let rest = analyzer.inlay_hints(path, Some((line, u32::MAX))).unwrap();
+        assert!(rest.iter().any(|h| h.kind == "type" && h.label.contains("LineCol")));
+        assert!(rest.iter().all(|h| h.line >= line));
+    }
+}
@@ -0,0 +1,134 @@
+//! Iterator-chain type explainer
+//!
+//! Lays out the types flowing through a method chain such as
+//! `items.iter().filter(..).map(..).collect()`: for each step, the type of the
+//! expression so far and, when it is an iterator, its `Item` type. A type error
+//! at the end of a chain usually starts a few steps earlier, where the item
+//! type first differs from what the author expected or inference gives up.
+
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::FilePosition;
+use ra_ap_syntax::{algo::find_node_at_offset, ast, AstNode};
+
+/// One step of the chain
+#[derive(Debug, Clone)]
+pub struct ChainStep {
+    /// The receiver expression for the first step, then `.method(..)` calls
+    pub expression: String,
+    /// Line the step starts on (0-based)
+    pub line: u32,
+    /// Type of the chain up to and including this step
+    pub ty: String,
+    /// The iterator's `Item` type, if the value is an iterator
+    pub item: Option<String>,
+    /// Whether inference failed for this step
+    pub unknown: bool,
+}
+
+impl Analyzer {
+    /// Explain the types along the method chain at a position (0-based)
+    pub fn explain_iterator_chain(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<ChainStep>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let FilePosition { file_id, offset } = self.file_position(file_path, line, column)?;
+        let line_index = self.host.analysis().file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+
+        let source = sema.parse_guess_edition(file_id);
+        let call: ast::MethodCallExpr = find_node_at_offset(source.syntax(), offset)
+            .ok_or_else(|| AnalyzerError::Other("No method call chain at this position".to_string()))?;
+        let krate = sema
+            .file_to_module_def(file_id)
+            .map(|m| m.krate())
+            .ok_or_else(|| AnalyzerError::Other("File is not part of a crate".to_string()))?;
+
+        let mut steps = Vec::new();
+        for expr in chain_steps(&outermost_call(call)) {
+            let expression = match &expr {
+                ast::Expr::MethodCallExpr(call) => step_label(call),
+                other => other.syntax().text().to_string(),
+            };
+            let ty = sema.type_of_expr(&expr).map(|info| info.original);
+            let item = ty.as_ref().and_then(|ty| ty.iterator_item(db));
+            steps.push(ChainStep {
+                expression,
+                line: line_index.line_col(expr.syntax().text_range().start()).line,
+                ty: ty.as_ref().map(|ty| display_type(db, ty, krate)).unwrap_or_else(|| "{unknown}".to_string()),
+                item: item.map(|item| display_type(db, &item, krate)),
+                unknown: ty.is_none_or(|ty| ty.is_unknown()),
+            });
+        }
+
+        Ok(steps)
+    }
+}
+
+/// Climb from a method call to the last call of the chain it belongs to
+fn outermost_call(mut call: ast::MethodCallExpr) -> ast::MethodCallExpr {
+    while let Some(parent) = call.syntax().parent().and_then(ast::MethodCallExpr::cast) {
+        if parent.receiver().is_some_and(|r| r.syntax() == call.syntax()) {
+            call = parent;
+        } else {
+            break;
+        }
+    }
+    call
+}
+
+/// The receiver at the start of a chain followed by each call, in order
+fn chain_steps(last: &ast::MethodCallExpr) -> Vec<ast::Expr> {
+    let mut steps = vec![ast::Expr::MethodCallExpr(last.clone())];
+    let mut current = last.receiver();
+    while let Some(expr) = current {
+        current = match &expr {
+            ast::Expr::MethodCallExpr(call) => call.receiver(),
+            _ => None,
+        };
+        steps.push(expr);
+    }
+    steps.reverse();
+    steps
+}
+
+/// `.name(..)` with the arguments elided, keeping turbofish generics
+fn step_label(call: &ast::MethodCallExpr) -> String {
+    let name = call.name_ref().map(|n| n.text().to_string()).unwrap_or_default();
+    let generics = call.generic_arg_list().map(|g| format!("::{}", g.syntax().text().to_string().trim_start_matches("::"))).unwrap_or_default();
+    let args = if call.arg_list().is_some_and(|args| args.args().next().is_some()) { ".." } else { "" };
+    format!(".{}{}({})", name, generics, args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile, TextSize};
+
+    #[test]
+    fn test_chain_steps_from_inner_call() {
+        let src = "fn f() { let v = items.iter().filter(|x| x.ok()).map(|x| x.id).collect::<Vec<_>>(); }";
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        let offset = TextSize::from(src.find("filter").unwrap() as u32);
+        let call: ast::MethodCallExpr = find_node_at_offset(file.syntax(), offset).unwrap();
+
+        let labels: Vec<_> = chain_steps(&outermost_call(call))
+            .iter()
+            .map(|expr| match expr {
+                ast::Expr::MethodCallExpr(call) => step_label(call),
+                other => other.syntax().text().to_string(),
+            })
+            .collect();
+
+        assert_eq!(labels, vec!["items", ".iter()", ".filter(..)", ".map(..)", ".collect::<Vec<_>>()"]);
+    }
+
+    #[test]
+    fn test_closure_calls_are_not_part_of_the_chain() {
+        let src = "fn f() { a.map(|x| x.ok()).count(); }";
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        let offset = TextSize::from(src.find("ok").unwrap() as u32);
+        let call: ast::MethodCallExpr = find_node_at_offset(file.syntax(), offset).unwrap();
+
+        // The call inside the closure forms its own chain
+        assert_eq!(chain_steps(&outermost_call(call)).len(), 2);
+    }
+}
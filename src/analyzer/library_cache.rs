@@ -0,0 +1,306 @@
+//! Shared library symbol cache
+//!
+//! Indexing the symbols of std and every dependency is the slowest part of
+//! startup, and it produces the same result for every project that uses the
+//! same crate build. Library symbols are therefore cached per crate in a
+//! directory shared by all projects, keyed by crate name, version and a hash
+//! of the crate's cfg set (which includes its enabled features), root path
+//! and the contents of its source files. Path and git dependencies can
+//! change without a new version; their edited sources get a new key. A project
+//! whose dependencies were all indexed before, by itself or by a sibling
+//! project, starts without indexing any library.
+//!
+//! Entries also record the toolchain and rust-analyzer versions they were
+//! indexed with (see `fingerprint`); an entry from another version is a miss
+//! and gets reindexed.
+//!
+//! When a library file changes while the server runs, the cache is loaded
+//! again, reindexing just the changed crate.
+
+use super::{stable_hash, Analyzer, AnalyzerError, SearchMode, SearchOptions, SymbolInfo};
+use ra_ap_ide::SourceRootId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Bump when the cache file format changes
+const LIBRARY_CACHE_VERSION: u32 = 4;
+
+/// Outcome of loading the library cache
+#[derive(Debug, Clone, Default)]
+pub struct LibraryCacheStats {
+    /// Library crates loaded from the shared cache
+    pub hits: usize,
+    /// Library crates that had to be indexed and were written to the cache
+    pub misses: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedCrate {
+    version: u32,
+    /// Toolchain digest of the project fingerprint the entry was indexed under
+    toolchain: u64,
+    symbols: Vec<SymbolInfo>,
+}
+
+/// A library crate in the crate graph and where its cache entry lives
+struct LibraryCrate {
+    source_root: SourceRootId,
+    cache_file: PathBuf,
+}
+
+impl Analyzer {
+    /// Load library symbols from the shared cache, indexing missing crates
+    ///
+    /// After this succeeds, library searches are answered from the cache
+    /// rather than rust-analyzer's library symbol index.
+    pub fn load_library_cache(&mut self) -> Result<LibraryCacheStats, AnalyzerError> {
+        let cache_dir = library_cache_dir()
+            .ok_or_else(|| AnalyzerError::Other("No cache directory available".to_string()))?;
+        self.load_library_cache_in(&cache_dir)
+    }
+
+    fn load_library_cache_in(&mut self, cache_dir: &Path) -> Result<LibraryCacheStats, AnalyzerError> {
+        let crates = self.library_crates(cache_dir)?;
+        let toolchain = self.fingerprint.as_ref().map_or(0, |fingerprint| fingerprint.toolchain_digest());
+
+        let mut stats = LibraryCacheStats::default();
+        let mut symbols = Vec::new();
+        let mut missing: HashMap<SourceRootId, PathBuf> = HashMap::new();
+
+        for krate in crates {
+            match read_cached_crate(&krate.cache_file, toolchain) {
+                Some(cached) => {
+                    stats.hits += 1;
+                    symbols.extend(cached);
+                }
+                None => {
+                    stats.misses += 1;
+                    missing.insert(krate.source_root, krate.cache_file);
+                }
+            }
+        }
+
+        if !missing.is_empty() {
+            // Dump every library symbol once and keep those of uncached crates
+            let analysis = self.host.analysis();
+            let mut query = ra_ap_ide::Query::new(String::new());
+            query.libs();
+            let navs = analysis.symbol_search(query, usize::MAX).map_err(|_| AnalyzerError::Canceled)?;
+
+            let crate_versions = self.crates_by_source_root()?;
+            let mut by_crate: HashMap<SourceRootId, Vec<SymbolInfo>> = HashMap::new();
+            for nav in &navs {
+                let root = analysis.source_root_id(nav.file_id).map_err(|_| AnalyzerError::Canceled)?;
+                if !missing.contains_key(&root) {
+                    continue;
+                }
+                if let Some(symbol) = self.nav_to_symbol(&analysis, &crate_versions, nav) {
+                    by_crate.entry(root).or_default().push(symbol);
+                }
+            }
+
+            std::fs::create_dir_all(cache_dir)?;
+            for (root, cache_file) in missing {
+                let crate_symbols = by_crate.remove(&root).unwrap_or_default();
+                let data = serde_json::to_vec(&CachedCrate { version: LIBRARY_CACHE_VERSION, toolchain, symbols: crate_symbols.clone() })
+                    .map_err(|e| AnalyzerError::Other(format!("Failed to serialize library cache: {}", e)))?;
+                // Written to a temporary file first so a concurrently starting
+                // sibling project never reads a half-written entry
+                let tmp = cache_file.with_extension(format!("tmp{}", std::process::id()));
+                std::fs::write(&tmp, data)?;
+                std::fs::rename(&tmp, &cache_file)?;
+                symbols.extend(crate_symbols);
+            }
+        }
+
+        self.library_symbols = Some(symbols);
+        Ok(stats)
+    }
+
+    /// Search the cached library symbols
+    ///
+    /// Exact name matches come first, so the limit never drops one in favour
+    /// of partial matches earlier in the cache.
+    pub(super) fn search_library_cache(&self, name: &str, options: &SearchOptions, limit: usize) -> Vec<SymbolInfo> {
+        let Some(symbols) = &self.library_symbols else { return Vec::new() };
+        let query = name.to_lowercase();
+        let wanted = name.rsplit("::").next().unwrap_or(name);
+
+        let mut matches: Vec<&SymbolInfo> = symbols
+            .iter()
+            .filter(|sym| options.accepts(sym))
+            .filter(|sym| self.signature_matches(sym, &options.signature))
+            .filter(|sym| name_matches(&sym.name.to_lowercase(), &query, options.mode))
+            .collect();
+        // Stable, so the cache order holds within each group
+        matches.sort_by_key(|sym| sym.name != wanted);
+        matches.into_iter().take(limit).cloned().collect()
+    }
+
+    /// Every library crate in the crate graph with its cache file
+    fn library_crates(&self, cache_dir: &Path) -> Result<Vec<LibraryCrate>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let analysis = self.host.analysis();
+        let digests = self.source_root_digests()?;
+        let mut crates = Vec::new();
+
+        for krate in ra_ap_hir::Crate::all(db) {
+            if krate.origin(db).is_local() {
+                continue;
+            }
+
+            let root_file = krate.root_file(db);
+            let name = krate.display_name(db).map(|n| n.to_string()).unwrap_or_else(|| "unnamed".to_string());
+            let version = krate.version(db).unwrap_or_else(|| "0.0.0".to_string());
+
+            // The cfg set covers enabled features; the root path tells apart
+            // crates with the same name from different sources (e.g. sysroots),
+            // and the content digest sources edited in place
+            let source_root = analysis.source_root_id(root_file).map_err(|_| AnalyzerError::Canceled)?;
+            let digest = digests.get(&source_root).copied().unwrap_or_default();
+            let fingerprint = format!("{:?}|{}|{:016x}", krate.cfg(db), self.file_path(root_file), digest);
+            let cache_file = cache_dir.join(format!("{}-{}-{:016x}.json", name, version, stable_hash(&fingerprint)));
+
+            crates.push(LibraryCrate { source_root, cache_file });
+        }
+
+        Ok(crates)
+    }
+}
+
+impl Analyzer {
+    /// Digest of the paths and contents of the files in each library source root
+    fn source_root_digests(&self) -> Result<HashMap<SourceRootId, u64>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let mut files: HashMap<SourceRootId, Vec<(String, u64)>> = HashMap::new();
+        for (file_id, path) in self.vfs.iter() {
+            let root = analysis.source_root_id(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            if analysis.is_local_source_root(root).map_err(|_| AnalyzerError::Canceled)? {
+                continue;
+            }
+            let text = analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            files.entry(root).or_default().push((path.to_string(), stable_hash(&text)));
+        }
+
+        // Sorted by path, as file ids differ between projects
+        Ok(files
+            .into_iter()
+            .map(|(root, mut files)| {
+                files.sort();
+                let listing: Vec<String> = files.iter().map(|(path, hash)| format!("{}:{:016x}", path, hash)).collect();
+                (root, stable_hash(&listing.join("\n")))
+            })
+            .collect())
+    }
+
+    /// Load the library cache again after library files changed, so the
+    /// changed crates are reindexed; falls back to rust-analyzer's library
+    /// index if that fails
+    pub(super) fn refresh_library_cache(&mut self) {
+        if self.library_symbols.is_none() {
+            return;
+        }
+        if let Err(e) = self.load_library_cache() {
+            eprintln!("Warning: Could not refresh library cache: {}", e);
+            self.library_symbols = None;
+        }
+    }
+}
+
+/// Match a lowercased symbol name the way rust-analyzer's symbol search does
+pub(super) fn name_matches(name: &str, query: &str, mode: SearchMode) -> bool {
+    match mode {
+        SearchMode::Exact => name == query,
+        SearchMode::Prefix => name.starts_with(query),
+        SearchMode::Fuzzy => {
+            let mut chars = name.chars();
+            query.chars().all(|q| chars.any(|c| c == q))
+        }
+    }
+}
+
+fn read_cached_crate(path: &Path, toolchain: u64) -> Option<Vec<SymbolInfo>> {
+    let data = std::fs::read(path).ok()?;
+    let cached: CachedCrate = serde_json::from_slice(&data).ok()?;
+    (cached.version == LIBRARY_CACHE_VERSION && cached.toolchain == toolchain).then_some(cached.symbols)
+}
+
+/// The cache directory shared by all projects
+///
+/// `CRATOGRAPHER_CACHE_DIR` overrides the default of
+/// `$XDG_CACHE_HOME/cratographer` (or `~/.cache/cratographer`).
+pub(super) fn library_cache_dir() -> Option<PathBuf> {
+    let base = match std::env::var_os("CRATOGRAPHER_CACHE_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => std::env::var_os("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?
+            .join("cratographer"),
+    };
+    Some(base.join("libraries"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::SymbolKind;
+
+    #[test]
+    fn test_name_matching_modes() {
+        assert!(name_matches("hashmap", "hashmap", SearchMode::Exact));
+        assert!(!name_matches("hashmapext", "hashmap", SearchMode::Exact));
+        assert!(name_matches("hashmapext", "hashmap", SearchMode::Prefix));
+        assert!(name_matches("hashmap", "hsmp", SearchMode::Fuzzy));
+        assert!(!name_matches("hashmap", "pmh", SearchMode::Fuzzy));
+    }
+
+    #[test]
+    fn test_exact_library_matches_survive_the_limit() {
+        let symbol = |name: &str| SymbolInfo {
+            name: name.to_string(),
+            kind: SymbolKind::Struct,
+            file_path: "/lib/src/lib.rs".to_string(),
+            start_line: 0,
+            end_line: 0,
+            documentation: None,
+            crate_name: Some("lib".to_string()),
+            crate_version: Some("1.0.0".to_string()),
+            moniker: None,
+            rustdoc: None,
+            approximate: false,
+            attributes: Vec::new(),
+            owners: Vec::new(),
+            size: None,
+        };
+        let mut analyzer = Analyzer::new();
+        analyzer.library_symbols = Some(vec![symbol("HashMapExt"), symbol("HashMapIter"), symbol("HashMap")]);
+
+        // Partial matches earlier in the cache don't crowd out the exact one
+        let options = SearchOptions { include_library: true, ..Default::default() };
+        let found = analyzer.search_library_cache("HashMap", &options, 2);
+        assert_eq!(found.iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["HashMap", "HashMapExt"]);
+    }
+
+    #[test]
+    fn test_second_load_hits_shared_cache() {
+        let cache_dir = std::env::temp_dir().join(format!("cratographer-library-cache-{}", std::process::id()));
+
+        let mut first = Analyzer::new();
+        assert!(first.load_project(".").is_ok());
+        let stats = first.load_library_cache_in(&cache_dir).expect("Failed to build library cache");
+        assert!(stats.misses > 0, "A cold cache should index library crates");
+
+        // A second project sharing the dependencies starts from the cache
+        let mut second = Analyzer::new();
+        assert!(second.load_project(".").is_ok());
+        let stats = second.load_library_cache_in(&cache_dir).expect("Failed to load library cache");
+        assert_eq!(stats.misses, 0, "A warm cache should not index any library crate");
+
+        let options = SearchOptions { mode: SearchMode::Exact, include_library: true, ..Default::default() };
+        let symbols = second.find_symbol("HashMap", &options).unwrap();
+        assert!(!symbols.is_empty(), "Cached library symbols should be searchable");
+
+        let _ = std::fs::remove_dir_all(&cache_dir);
+    }
+}
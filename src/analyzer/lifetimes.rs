@@ -0,0 +1,441 @@
+//! Lifetime elision expansion
+//!
+//! Rewrites a function signature with every elided lifetime spelled out,
+//! following the elision rules from the Rust reference:
+//!
+//! 1. Each elided lifetime in the parameters becomes a distinct lifetime parameter.
+//! 2. If there is exactly one input lifetime, it is assigned to all elided output lifetimes.
+//! 3. If the receiver is `&self` or `&mut self`, its lifetime is assigned to all
+//!    elided output lifetimes.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{GenericDef, ModuleDef, PathResolution, Semantics};
+use ra_ap_syntax::{
+    ast::{self, HasGenericParams, HasName},
+    AstNode, SyntaxKind, SyntaxNode, TextRange, TextSize, T,
+};
+
+/// A function signature with its elided lifetimes made explicit
+#[derive(Debug, Clone)]
+pub struct LifetimeExpansion {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The signature as written
+    pub original: String,
+    /// The signature with all elided lifetimes named
+    pub expanded: String,
+    /// Lifetime parameters introduced by the expansion
+    pub introduced: Vec<String>,
+    /// Notes about the expansion, e.g. output lifetimes that cannot be elided
+    pub notes: Vec<String>,
+}
+
+impl Analyzer {
+    /// Show the signatures of the named function with elided lifetimes made explicit
+    pub fn expand_lifetimes(&self, function: &str, file_path: Option<&str>) -> Result<Vec<LifetimeExpansion>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut results = Vec::new();
+
+        for (file_id, func) in self.find_functions(&sema, function, file_path)? {
+            // Types like `Formatter` carry a lifetime parameter even when none is written
+            let hidden_lifetimes = |path_type: &ast::PathType| -> usize {
+                let Some(path) = path_type.path() else { return 0 };
+                match sema.resolve_path(&path) {
+                    Some(PathResolution::Def(ModuleDef::Adt(adt))) => GenericDef::from(adt).lifetime_params(db).len(),
+                    _ => 0,
+                }
+            };
+
+            let expansion = expand_signature(&func, hidden_lifetimes);
+            let (start_line, end_line) = self.line_range(file_id, func.syntax().text_range())?;
+
+            results.push(LifetimeExpansion {
+                function: function.to_string(),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                original: expansion.original,
+                expanded: expansion.expanded,
+                introduced: expansion.introduced,
+                notes: expansion.notes,
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+struct Expansion {
+    original: String,
+    expanded: String,
+    introduced: Vec<String>,
+    notes: Vec<String>,
+}
+
+/// A pending textual edit to the signature
+struct Edit {
+    range: TextRange,
+    text: String,
+}
+
+/// Expand the elided lifetimes of a single function signature
+///
+/// `hidden_lifetimes` reports how many lifetime parameters a path type takes
+/// when none are written out (`Formatter` rather than `Formatter<'_>`).
+fn expand_signature(func: &ast::Fn, hidden_lifetimes: impl Fn(&ast::PathType) -> usize) -> Expansion {
+    let signature_range = signature_range(func);
+    let original = signature_text(func, signature_range);
+
+    let existing: Vec<String> = func
+        .generic_param_list()
+        .map(|list| {
+            list.lifetime_params()
+                .filter_map(|p| p.lifetime())
+                .map(|lt| lt.syntax().text().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut fresh = FreshLifetimes { existing, introduced: Vec::new() };
+    let mut edits = Vec::new();
+    let mut input_lifetimes: Vec<String> = Vec::new();
+    let mut self_lifetime = None;
+
+    if let Some(param_list) = func.param_list() {
+        if let Some(self_param) = param_list.self_param() {
+            if let Some(amp) = self_param.amp_token() {
+                let lifetime = match self_param.lifetime() {
+                    Some(lt) if lt.syntax().text() != "'_" => lt.syntax().text().to_string(),
+                    Some(lt) => {
+                        let name = fresh.next();
+                        edits.push(Edit { range: lt.syntax().text_range(), text: name.clone() });
+                        name
+                    }
+                    None => {
+                        let name = fresh.next();
+                        edits.push(Edit { range: TextRange::empty(amp.text_range().end()), text: format!("{} ", name) });
+                        name
+                    }
+                };
+                input_lifetimes.push(lifetime.clone());
+                self_lifetime = Some(lifetime);
+            } else if let Some(ty) = self_param.ty() {
+                let found = collect_input_lifetimes(ty.syntax(), &mut fresh, &mut edits, &hidden_lifetimes);
+                self_lifetime = found.first().cloned();
+                input_lifetimes.extend(found);
+            }
+        }
+
+        for param in param_list.params() {
+            if let Some(ty) = param.ty() {
+                input_lifetimes.extend(collect_input_lifetimes(ty.syntax(), &mut fresh, &mut edits, &hidden_lifetimes));
+            }
+        }
+    }
+
+    // Rules 2 and 3: pick the lifetime for elided outputs
+    let mut unique_inputs = input_lifetimes.clone();
+    unique_inputs.sort();
+    unique_inputs.dedup();
+    let output_lifetime = self_lifetime.or_else(|| (unique_inputs.len() == 1).then(|| unique_inputs[0].clone()));
+
+    let mut notes = Vec::new();
+    if let Some(ret) = func.ret_type() {
+        let elided = elided_positions(ret.syntax(), &hidden_lifetimes);
+        if !elided.is_empty() {
+            match &output_lifetime {
+                Some(lifetime) => {
+                    for position in elided {
+                        edits.push(position.into_edit(lifetime));
+                    }
+                }
+                None => notes.push(format!(
+                    "The return type has {} elided lifetime(s) but there is no unambiguous input lifetime to assign \
+                     (found {} input lifetime(s) and no &self receiver); an explicit lifetime is required",
+                    elided.len(),
+                    unique_inputs.len()
+                )),
+            }
+        }
+    }
+
+    if ret_contains_impl_trait(func) && !input_lifetimes.is_empty() {
+        notes.push("The `impl Trait` return type may capture the input lifetimes; add `+ 'a` or `use<..>` bounds to make this explicit".to_string());
+    }
+
+    // Declare the introduced lifetimes in the generic parameter list
+    if !fresh.introduced.is_empty() {
+        let declared = fresh.introduced.join(", ");
+        match func.generic_param_list() {
+            Some(list) => {
+                if let Some(l_angle) = list.l_angle_token() {
+                    let has_params = list.generic_params().next().is_some();
+                    let text = if has_params { format!("{}, ", declared) } else { declared };
+                    edits.push(Edit { range: TextRange::empty(l_angle.text_range().end()), text });
+                }
+            }
+            None => {
+                if let Some(name) = func.name() {
+                    edits.push(Edit { range: TextRange::empty(name.syntax().text_range().end()), text: format!("<{}>", declared) });
+                }
+            }
+        }
+    }
+
+    let expanded = apply_edits(&original, signature_range.start(), edits);
+
+    Expansion { original, expanded, introduced: fresh.introduced, notes }
+}
+
+/// Generates lifetime names that don't clash with existing parameters
+struct FreshLifetimes {
+    existing: Vec<String>,
+    introduced: Vec<String>,
+}
+
+impl FreshLifetimes {
+    fn next(&mut self) -> String {
+        let mut n = 0usize;
+        loop {
+            let name = lifetime_name(n);
+            if !self.existing.contains(&name) && !self.introduced.contains(&name) {
+                self.introduced.push(name.clone());
+                return name;
+            }
+            n += 1;
+        }
+    }
+}
+
+/// `'a`..`'z`, then `'a1`, `'b1`, ...
+fn lifetime_name(n: usize) -> String {
+    let letter = (b'a' + (n % 26) as u8) as char;
+    match n / 26 {
+        0 => format!("'{}", letter),
+        round => format!("'{}{}", letter, round),
+    }
+}
+
+/// A place in a type where a lifetime was elided
+enum ElidedPosition {
+    /// `&T` with no lifetime: insert after the `&`
+    Reference(TextSize),
+    /// An explicit `'_`: replace it
+    Placeholder(TextRange),
+    /// A path type with hidden lifetime parameters: add generic arguments
+    HiddenInPath { insert_at: TextSize, count: usize, has_args: bool },
+}
+
+impl ElidedPosition {
+    fn into_edit(self, lifetime: &str) -> Edit {
+        match self {
+            ElidedPosition::Reference(offset) => Edit { range: TextRange::empty(offset), text: format!("{} ", lifetime) },
+            ElidedPosition::Placeholder(range) => Edit { range, text: lifetime.to_string() },
+            ElidedPosition::HiddenInPath { insert_at, count, has_args } => {
+                let lifetimes = vec![lifetime; count].join(", ");
+                let text = if has_args { format!("{}, ", lifetimes) } else { format!("<{}>", lifetimes) };
+                Edit { range: TextRange::empty(insert_at), text }
+            }
+        }
+    }
+}
+
+/// Name every lifetime in an input type, giving elided ones fresh names
+///
+/// Returns all lifetimes that appear in the type, explicit or introduced.
+fn collect_input_lifetimes(
+    ty: &SyntaxNode,
+    fresh: &mut FreshLifetimes,
+    edits: &mut Vec<Edit>,
+    hidden_lifetimes: &impl Fn(&ast::PathType) -> usize,
+) -> Vec<String> {
+    let mut lifetimes: Vec<String> = ty
+        .descendants()
+        .filter(|node| !in_nested_elision_scope(node, ty))
+        .filter_map(ast::Lifetime::cast)
+        .map(|lt| lt.syntax().text().to_string())
+        .filter(|name| name != "'_" && name != "'static")
+        .collect();
+
+    for position in elided_positions(ty, hidden_lifetimes) {
+        let count = match &position {
+            ElidedPosition::HiddenInPath { count, .. } => *count,
+            _ => 1,
+        };
+        // Every elided position gets its own lifetime, even inside one path
+        let names: Vec<String> = (0..count).map(|_| fresh.next()).collect();
+        let edit = match position {
+            ElidedPosition::HiddenInPath { insert_at, has_args, .. } => {
+                let joined = names.join(", ");
+                let text = if has_args { format!("{}, ", joined) } else { format!("<{}>", joined) };
+                Edit { range: TextRange::empty(insert_at), text }
+            }
+            other => other.into_edit(&names[0]),
+        };
+        edits.push(edit);
+        lifetimes.extend(names);
+    }
+
+    lifetimes
+}
+
+/// Find every elided lifetime position in a type
+fn elided_positions(ty: &SyntaxNode, hidden_lifetimes: &impl Fn(&ast::PathType) -> usize) -> Vec<ElidedPosition> {
+    let mut positions = Vec::new();
+
+    for node in ty.descendants() {
+        if in_nested_elision_scope(&node, ty) {
+            continue;
+        }
+
+        if let Some(ref_type) = ast::RefType::cast(node.clone()) {
+            if ref_type.lifetime().is_none() {
+                if let Some(amp) = ref_type.amp_token() {
+                    positions.push(ElidedPosition::Reference(amp.text_range().end()));
+                }
+            }
+        } else if let Some(lifetime) = ast::Lifetime::cast(node.clone()) {
+            if lifetime.syntax().text() == "'_" {
+                positions.push(ElidedPosition::Placeholder(lifetime.syntax().text_range()));
+            }
+        } else if let Some(path_type) = ast::PathType::cast(node.clone()) {
+            let Some(segment) = path_type.path().and_then(|p| p.segment()) else { continue };
+            let arg_list = segment.generic_arg_list();
+            let has_lifetime_args = arg_list
+                .as_ref()
+                .is_some_and(|args| args.generic_args().any(|arg| matches!(arg, ast::GenericArg::LifetimeArg(_))));
+            if has_lifetime_args {
+                continue;
+            }
+
+            let count = hidden_lifetimes(&path_type);
+            if count == 0 {
+                continue;
+            }
+
+            let (insert_at, has_args) = match arg_list.as_ref().and_then(|args| args.l_angle_token()) {
+                Some(l_angle) => (l_angle.text_range().end(), true),
+                None => match segment.name_ref() {
+                    Some(name) => (name.syntax().text_range().end(), false),
+                    None => continue,
+                },
+            };
+            positions.push(ElidedPosition::HiddenInPath { insert_at, count, has_args });
+        }
+    }
+
+    positions
+}
+
+/// Fn pointers and `Fn(..)` sugar have their own elision scope, so lifetimes
+/// inside them don't take part in the outer function's elision
+fn in_nested_elision_scope(node: &SyntaxNode, root: &SyntaxNode) -> bool {
+    for ancestor in node.ancestors().skip(1) {
+        let opens_scope = ancestor.kind() == SyntaxKind::FN_PTR_TYPE
+            || (ancestor.parent().is_some_and(|p| p.kind() == SyntaxKind::PATH_SEGMENT)
+                && ancestor.first_token().is_some_and(|t| t.kind() == T!['(']));
+        if opens_scope {
+            return true;
+        }
+        if &ancestor == root {
+            break;
+        }
+    }
+    false
+}
+
+fn ret_contains_impl_trait(func: &ast::Fn) -> bool {
+    func.ret_type()
+        .is_some_and(|ret| ret.syntax().descendants().any(|n| n.kind() == SyntaxKind::IMPL_TRAIT_TYPE))
+}
+
+/// The range covering the signature: from the first keyword up to the body
+fn signature_range(func: &ast::Fn) -> TextRange {
+    let full = func.syntax().text_range();
+    let start = func
+        .syntax()
+        .children_with_tokens()
+        .find(|element| !matches!(element.kind(), SyntaxKind::COMMENT | SyntaxKind::WHITESPACE | SyntaxKind::ATTR))
+        .map(|element| element.text_range().start())
+        .unwrap_or(full.start());
+    let end = func
+        .body()
+        .map(|body| body.syntax().text_range().start())
+        .or_else(|| func.semicolon_token().map(|t| t.text_range().start()))
+        .unwrap_or(full.end());
+    TextRange::new(start, end)
+}
+
+fn signature_text(func: &ast::Fn, range: TextRange) -> String {
+    let full = func.syntax().text_range();
+    let text = func.syntax().text().to_string();
+    let relative = range - full.start();
+    text[relative].trim_end().to_string()
+}
+
+/// Apply edits (in absolute offsets) to the signature text starting at `base`
+fn apply_edits(signature: &str, base: TextSize, mut edits: Vec<Edit>) -> String {
+    let mut text = signature.to_string();
+    // Apply back to front so earlier offsets stay valid
+    edits.sort_by_key(|edit| std::cmp::Reverse(edit.range.start()));
+    for edit in edits {
+        let range = edit.range - base;
+        let (start, end) = (usize::from(range.start()), usize::from(range.end()));
+        if end <= text.len() {
+            text.replace_range(start..end, &edit.text);
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn expand(source: &str) -> Expansion {
+        let parse = SourceFile::parse(source, Edition::CURRENT);
+        let func = parse.tree().syntax().descendants().find_map(ast::Fn::cast).expect("no function in source");
+        expand_signature(&func, |path_type| {
+            // Pretend `Formatter` is the only type with a hidden lifetime
+            let is_formatter = path_type.syntax().text().to_string().starts_with("Formatter");
+            usize::from(is_formatter)
+        })
+    }
+
+    #[test]
+    fn test_single_input_lifetime_flows_to_output() {
+        let expansion = expand("fn first(s: &str) -> &str { s }");
+        assert_eq!(expansion.expanded, "fn first<'a>(s: &'a str) -> &'a str");
+        assert!(expansion.notes.is_empty());
+    }
+
+    #[test]
+    fn test_self_lifetime_flows_to_output() {
+        let expansion = expand("impl S { fn get(&self, key: &str) -> &Value { todo!() } }");
+        assert_eq!(expansion.expanded, "fn get<'a, 'b>(&'a self, key: &'b str) -> &'a Value");
+    }
+
+    #[test]
+    fn test_ambiguous_output_is_reported() {
+        let expansion = expand("fn longest(a: &str, b: &str) -> &str { a }");
+        assert_eq!(expansion.expanded, "fn longest<'a, 'b>(a: &'a str, b: &'b str) -> &str");
+        assert_eq!(expansion.notes.len(), 1);
+    }
+
+    #[test]
+    fn test_existing_generics_and_hidden_path_lifetimes() {
+        let expansion = expand("fn fmt<'a, T>(x: &'a T, f: &mut Formatter) -> Result { todo!() }");
+        assert_eq!(expansion.expanded, "fn fmt<'b, 'c, 'a, T>(x: &'a T, f: &'b mut Formatter<'c>) -> Result");
+        assert_eq!(expansion.introduced, vec!["'b".to_string(), "'c".to_string()]);
+    }
+
+    #[test]
+    fn test_fn_pointer_has_its_own_scope() {
+        let expansion = expand("fn apply(f: fn(&str) -> &str, s: &String) -> &str { f(s) }");
+        assert_eq!(expansion.expanded, "fn apply<'a>(f: fn(&str) -> &str, s: &'a String) -> &'a str");
+    }
+}
@@ -0,0 +1,312 @@
+//! Logging and tracing call sites
+//!
+//! Lists the `log` and `tracing` macro calls of the workspace with their
+//! level, explicit target, message template and enclosing function, plus
+//! `#[instrument]`ed functions, so an observability audit is one query.
+//! Macros are matched by what they resolve to, so an unrelated `error!` or
+//! a renamed import is told apart; when the logging crate isn't loaded they
+//! fall back to matching by name.
+//!
+//! Optionally the workspace functions without any logging are listed too,
+//! answering "which code paths have no tracing?". Test code is left out.
+
+use super::budget::{Budget, Coverage};
+use super::entry_points::attribute_entry;
+use super::features::{module_cfgs, node_cfgs, CfgExpr};
+use super::{normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_syntax::{
+    ast::{self, HasAttrs, HasName},
+    AstNode, NodeOrToken, SyntaxKind, SyntaxNode,
+};
+
+/// Crates whose macros are reported
+const LOGGING_CRATES: &[&str] = &["log", "tracing"];
+
+/// Levels, from least to most severe
+pub const LOG_LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
+/// A logging or tracing call site
+#[derive(Debug, Clone)]
+pub struct LogSite {
+    /// `event` for a log line, `span` for a span macro, `instrument` for an
+    /// `#[instrument]` attribute
+    pub kind: &'static str,
+    /// The macro or attribute name, e.g. `info`, `log`, `info_span`
+    pub macro_name: String,
+    /// One of `LOG_LEVELS`, when known
+    pub level: Option<&'static str>,
+    /// The `target: "..."` argument, if given; otherwise the module path is used
+    pub target: Option<String>,
+    /// The first string literal argument, e.g. `"loaded {} files"`; the span name for spans
+    pub message: Option<String>,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing function, if any
+    pub function: Option<String>,
+}
+
+/// A workspace function without logging
+#[derive(Debug, Clone)]
+pub struct SilentFunction {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// The logging of the workspace files scanned within the budget
+#[derive(Debug, Clone)]
+pub struct LoggingReport {
+    /// By file and line
+    pub sites: Vec<LogSite>,
+    /// Functions with a body and no logging, by file and line; only when asked for
+    pub silent_functions: Vec<SilentFunction>,
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// Find the logging and tracing call sites of the workspace, and with
+    /// `silent_functions` the functions that have none
+    pub fn logging_sites(&self, silent_functions: bool, budget: Budget) -> Result<LoggingReport, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let mut files: Vec<_> = self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect();
+        files.sort();
+
+        let test_cfg = CfgExpr::Atom("test".to_string());
+        let mut report = LoggingReport {
+            sites: Vec::new(),
+            silent_functions: Vec::new(),
+            coverage: Coverage { files_scanned: 0, files_total: files.len() },
+        };
+        for (file_path, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            report.coverage.files_scanned += 1;
+            if module_cfgs(&sema, db, file_id).contains(&test_cfg) {
+                continue;
+            }
+            let (sites, silent) = self.file_logging(&sema, file_id, &file_path)?;
+            report.sites.extend(sites);
+            if silent_functions {
+                report.silent_functions.extend(silent);
+            }
+        }
+        Ok(report)
+    }
+
+    fn file_logging(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        file_id: FileId,
+        file_path: &str,
+    ) -> Result<(Vec<LogSite>, Vec<SilentFunction>), AnalyzerError> {
+        let source = sema.parse_guess_edition(file_id);
+        let test_cfg = CfgExpr::Atom("test".to_string());
+        let in_test = |node: &SyntaxNode| {
+            node_cfgs(node).contains(&test_cfg)
+                || node.ancestors().filter_map(ast::Item::cast).any(|item| attribute_entry(&item) == Some("test"))
+        };
+
+        let mut sites = Vec::new();
+        for call in source.syntax().descendants().filter_map(ast::MacroCall::cast) {
+            let Some(name) = call.path().and_then(|path| path.segment()).and_then(|segment| segment.name_ref()) else { continue };
+            let name = name.text().to_string();
+            let Some(kind) = macro_kind(&name) else { continue };
+            if !is_logging_macro(sema, &call) || in_test(call.syntax()) {
+                continue;
+            }
+            let Some(args) = call.token_tree() else { continue };
+            let args = top_level_args(args.syntax());
+            sites.push(LogSite {
+                kind,
+                level: macro_level(&name).or_else(|| level_arg(&args).and_then(level_of)),
+                macro_name: name,
+                target: named_arg(&args, "target"),
+                message: args.iter().find_map(|arg| string_literal(arg)),
+                file_path: file_path.to_string(),
+                line: self.line_range(file_id, call.syntax().text_range())?.0,
+                function: enclosing_fn(call.syntax()),
+            });
+        }
+
+        let mut silent = Vec::new();
+        for func in source.syntax().descendants().filter_map(ast::Fn::cast) {
+            if in_test(func.syntax()) {
+                continue;
+            }
+            let instrument = func.attrs().find(|attr| {
+                attr.path().and_then(|path| path.segment()).and_then(|s| s.name_ref()).is_some_and(|name| name.text() == "instrument")
+            });
+            if let Some(attr) = &instrument {
+                let args = attr.token_tree().map(|tt| top_level_args(tt.syntax())).unwrap_or_default();
+                sites.push(LogSite {
+                    kind: "instrument",
+                    macro_name: "instrument".to_string(),
+                    level: named_arg(&args, "level").and_then(|level| level_of(&level)),
+                    target: named_arg(&args, "target"),
+                    message: named_arg(&args, "name"),
+                    file_path: file_path.to_string(),
+                    line: self.line_range(file_id, attr.syntax().text_range())?.0,
+                    function: func.name().map(|name| name.text().to_string()),
+                });
+            }
+
+            let Some(body) = func.body() else { continue };
+            // Logging in a nested function counts for it, not for this one
+            let logs = body.syntax().descendants().filter_map(ast::MacroCall::cast).any(|call| {
+                call.path()
+                    .and_then(|path| path.segment())
+                    .and_then(|segment| segment.name_ref())
+                    .is_some_and(|name| macro_kind(name.text().as_str()).is_some())
+                    && call.syntax().ancestors().find_map(ast::Fn::cast).as_ref() == Some(&func)
+                    && is_logging_macro(sema, &call)
+            });
+            if instrument.is_none() && !logs {
+                let (start_line, end_line) = self.line_range(file_id, func.syntax().text_range())?;
+                silent.push(SilentFunction {
+                    name: func.name().map(|name| name.text().to_string()).unwrap_or_default(),
+                    file_path: file_path.to_string(),
+                    start_line,
+                    end_line,
+                });
+            }
+        }
+        sites.sort_by_key(|site| site.line);
+        Ok((sites, silent))
+    }
+}
+
+/// `event` or `span` for the macros of the logging crates, by name
+fn macro_kind(name: &str) -> Option<&'static str> {
+    match name {
+        "trace" | "debug" | "info" | "warn" | "error" | "log" | "event" => Some("event"),
+        "span" | "trace_span" | "debug_span" | "info_span" | "warn_span" | "error_span" => Some("span"),
+        _ => None,
+    }
+}
+
+/// The level a macro's name implies, e.g. `info` for `info!` and `info_span!`
+fn macro_level(name: &str) -> Option<&'static str> {
+    LOG_LEVELS.iter().copied().find(|level| name == *level || name.strip_suffix("_span") == Some(level))
+}
+
+/// The level named by an argument such as `Level::INFO`, `log::Level::Warn` or `"debug"`
+fn level_of(arg: &str) -> Option<&'static str> {
+    let last = arg.rsplit("::").next()?.trim().trim_matches('"').to_ascii_lowercase();
+    LOG_LEVELS.iter().copied().find(|level| *level == last)
+}
+
+/// Whether a macro call resolves to a logging crate's macro; by name alone
+/// when it doesn't resolve
+fn is_logging_macro(sema: &Semantics<'_, RootDatabase>, call: &ast::MacroCall) -> bool {
+    match sema.resolve_macro_call(call) {
+        Some(macro_) => macro_
+            .module(sema.db)
+            .krate()
+            .display_name(sema.db)
+            .is_some_and(|krate| LOGGING_CRATES.contains(&krate.to_string().as_str())),
+        None => true,
+    }
+}
+
+/// The comma-separated arguments of a macro or attribute, each as written
+/// on one line, without the outer delimiters
+fn top_level_args(tt: &SyntaxNode) -> Vec<String> {
+    let mut args = vec![String::new()];
+    let children: Vec<_> = tt.children_with_tokens().collect();
+    let inner = children.get(1..children.len().saturating_sub(1)).unwrap_or_default();
+    for child in inner {
+        match child {
+            NodeOrToken::Token(token) if token.kind() == SyntaxKind::COMMA => args.push(String::new()),
+            NodeOrToken::Token(token) if token.kind() == SyntaxKind::SEMICOLON => args.push(String::new()),
+            _ => args.last_mut().expect("args start non-empty").push_str(&child.to_string()),
+        }
+    }
+    args.into_iter().map(|arg| normalize_whitespace(&arg)).filter(|arg| !arg.is_empty()).collect()
+}
+
+/// The first argument that isn't `name: value`, where `log!` and `event!` take the level
+fn level_arg(args: &[String]) -> Option<&str> {
+    args.iter().map(String::as_str).find(|arg| arg.find(':').is_none_or(|i| arg[i..].starts_with("::")))
+}
+
+/// The value of a `name: value` or `name = value` argument
+fn named_arg(args: &[String], name: &str) -> Option<String> {
+    args.iter().find_map(|arg| {
+        let rest = arg.strip_prefix(name)?.trim_start();
+        let value = rest.strip_prefix(':').or_else(|| rest.strip_prefix('='))?.trim();
+        Some(value.trim_matches('"').to_string())
+    })
+}
+
+/// The contents of an argument that is a plain string literal
+fn string_literal(arg: &str) -> Option<String> {
+    arg.strip_prefix('"')?.strip_suffix('"').map(str::to_string)
+}
+
+/// Name of the function a node is in
+fn enclosing_fn(node: &SyntaxNode) -> Option<String> {
+    node.ancestors().find_map(ast::Fn::cast).and_then(|func| func.name()).map(|name| name.text().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn calls(src: &str) -> Vec<(String, Option<&'static str>, Option<String>, Option<String>)> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        file.syntax()
+            .descendants()
+            .filter_map(ast::MacroCall::cast)
+            .filter_map(|call| {
+                let name = call.path()?.segment()?.name_ref()?.text().to_string();
+                macro_kind(&name)?;
+                let args = top_level_args(call.token_tree()?.syntax());
+                let level = macro_level(&name).or_else(|| level_arg(&args).and_then(level_of));
+                Some((name, level, named_arg(&args, "target"), args.iter().find_map(|arg| string_literal(arg))))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_macro_arguments() {
+        let src = r#"
+fn run() {
+    info!("loaded {} files", count);
+    log::warn!(target: "net", "retrying {}", attempt);
+    tracing::event!(target: "api", Level::ERROR, user = %id, "request failed");
+    let span = debug_span!("parse", file = ?path);
+    log!(log::Level::Trace, "raw: {:?}", bytes);
+    println!("not logging");
+}
+"#;
+        assert_eq!(
+            calls(src),
+            vec![
+                ("info".to_string(), Some("info"), None, Some("loaded {} files".to_string())),
+                ("warn".to_string(), Some("warn"), Some("net".to_string()), Some("retrying {}".to_string())),
+                ("event".to_string(), Some("error"), Some("api".to_string()), Some("request failed".to_string())),
+                ("debug_span".to_string(), Some("debug"), None, Some("parse".to_string())),
+                ("log".to_string(), Some("trace"), None, Some("raw: {:?}".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_logging_sites_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let report = analyzer.logging_sites(true, Budget::unlimited()).unwrap();
+        assert!(report.coverage.complete());
+        assert!(report.sites.iter().all(|site| site.level.is_none_or(|level| LOG_LEVELS.contains(&level))));
+        // Test functions are left out of the silent ones
+        assert!(!report.silent_functions.is_empty());
+        assert!(report.silent_functions.iter().all(|f| !f.name.starts_with("test_")));
+    }
+}
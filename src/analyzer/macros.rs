@@ -0,0 +1,200 @@
+//! Macro expansion at a position
+//!
+//! Shows what a macro call generates: a `macro_rules!` or function-like
+//! macro call, an attribute macro, or the `derive` attribute (all of its
+//! derives at once). Nested macro calls in the expansion are expanded too.
+//! A single derive of a type can also be expanded by name; derives
+//! implemented as proc macros, like serde's, need the proc-macro server.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Semantics};
+use ra_ap_ide::FilePosition;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{ast::{self, HasAttrs}, AstNode, SyntaxKind, SyntaxToken, T};
+
+/// A macro call and the code it expands to
+#[derive(Debug, Clone)]
+pub struct MacroExpansion {
+    /// Name of the expanded macro, e.g. `vec` or `derive`
+    pub name: String,
+    /// The generated source, whitespace-formatted
+    pub expansion: String,
+}
+
+/// What one derive of a type generates
+#[derive(Debug, Clone)]
+pub struct DeriveExpansion {
+    /// Qualified path of the type
+    pub type_path: String,
+    /// The derive as written, e.g. `Serialize` or `serde::Serialize`
+    pub derive: String,
+    /// The generated impls, whitespace-formatted
+    pub expansion: String,
+    /// Every derive of the type, as written
+    pub derives: Vec<String>,
+}
+
+impl Analyzer {
+    /// Expand the macro call whose name is at a position (0-based), or `None`
+    /// if there is no macro call there
+    pub fn expand_macro(&self, file_path: &str, line: u32, column: u32) -> Result<Option<MacroExpansion>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let expanded = self.host.analysis().expand_macro(position).map_err(|_| AnalyzerError::Canceled)?;
+        Ok(expanded.map(|expanded| MacroExpansion { name: expanded.name, expansion: expanded.expansion }))
+    }
+
+    /// Expand one derive of a struct, enum or union
+    ///
+    /// `derive` is matched against each derive as written and against its
+    /// last path segment, so `Serialize` finds `serde::Serialize`. Derives
+    /// under `cfg_attr` are not considered.
+    pub fn expand_derive(&self, type_name: &str, derive: &str) -> Result<DeriveExpansion, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let adts: Vec<Adt> = self.resolve_definitions(&sema, type_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Adt(adt) => Some(adt),
+                _ => None,
+            })
+            .collect();
+        let Some(&adt) = adts.first() else {
+            return Err(AnalyzerError::Other(format!("No type named '{}' found", type_name)));
+        };
+        self.require_unique(&sema, type_name, &adts.iter().map(|&a| Definition::Adt(a)).collect::<Vec<_>>())?;
+        let type_path = definition_key(db, Definition::Adt(adt)).unwrap_or_else(|| type_name.to_string());
+
+        let source = sema.source(adt).ok_or_else(|| AnalyzerError::Other(format!("No source for '{}'", type_path)))?;
+        let Some(file_id) = source.file_id.file_id() else {
+            return Err(AnalyzerError::Other(format!("'{}' is generated by a macro", type_path)));
+        };
+        let derives: Vec<(String, SyntaxToken)> = source
+            .value
+            .attrs()
+            .filter(|attr| attr.path().is_some_and(|path| path.syntax().text() == "derive"))
+            .filter_map(|attr| attr.token_tree())
+            .flat_map(|tt| derive_entries(&tt))
+            .collect();
+
+        let written: Vec<String> = derives.iter().map(|(written, _)| written.clone()).collect();
+        let Some((derive, token)) = derives
+            .into_iter()
+            .find(|(written, _)| written == derive || written.rsplit("::").next() == Some(derive))
+        else {
+            return Err(AnalyzerError::Other(format!(
+                "'{}' does not derive '{}'; it derives: {}",
+                type_path,
+                derive,
+                if written.is_empty() { "nothing".to_string() } else { written.join(", ") }
+            )));
+        };
+
+        let position = FilePosition { file_id: file_id.file_id(db), offset: token.text_range().start() };
+        let expanded = self.host.analysis().expand_macro(position).map_err(|_| AnalyzerError::Canceled)?;
+        match expanded {
+            Some(expanded) if !expanded.expansion.trim().is_empty() => Ok(DeriveExpansion {
+                type_path,
+                derive,
+                expansion: expanded.expansion,
+                derives: written,
+            }),
+            _ => Err(AnalyzerError::Other(match &self.proc_macro_server {
+                Err(reason) => format!("Could not expand '{}' of '{}'; proc macros are not expanded: {}", derive, type_path, reason),
+                Ok(_) => format!("Could not expand '{}' of '{}'", derive, type_path),
+            })),
+        }
+    }
+}
+
+/// The derives listed in `derive(...)`: each path as written, with its last
+/// identifier
+fn derive_entries(tt: &ast::TokenTree) -> Vec<(String, SyntaxToken)> {
+    let mut entries = Vec::new();
+    let mut written = String::new();
+    let mut last_ident = None;
+    let tokens = tt.syntax().children_with_tokens().filter_map(|child| child.into_token());
+    for token in tokens {
+        match token.kind() {
+            T!['('] | T![')'] | SyntaxKind::WHITESPACE | SyntaxKind::COMMENT => {}
+            T![,] => {
+                let entry = std::mem::take(&mut written);
+                if let Some(ident) = last_ident.take() {
+                    entries.push((entry, ident));
+                }
+            }
+            kind => {
+                if kind == SyntaxKind::IDENT {
+                    last_ident = Some(token.clone());
+                }
+                written.push_str(token.text());
+            }
+        }
+    }
+    if let Some(ident) = last_ident {
+        entries.push((written, ident));
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_expand_derive() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `#[derive(Debug, Clone)]` on `MacroExpansion` in this file
+        let path = std::fs::canonicalize("src/analyzer/macros.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.starts_with("#[derive(Debug, Clone)]")).unwrap() as u32;
+
+        let expanded = analyzer.expand_macro(path, line, 2).unwrap().expect("derive attributes expand");
+        assert!(expanded.expansion.contains("Debug for MacroExpansion"));
+        assert!(expanded.expansion.contains("Clone for MacroExpansion"));
+    }
+
+    #[test]
+    fn test_expand_one_derive() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let expanded = analyzer.expand_derive("MacroExpansion", "Clone").unwrap();
+        assert!(expanded.expansion.contains("Clone for MacroExpansion"));
+        assert!(!expanded.expansion.contains("Debug for MacroExpansion"));
+        assert_eq!(expanded.derives, vec!["Debug", "Clone"]);
+
+        let error = analyzer.expand_derive("MacroExpansion", "Serialize").unwrap_err();
+        assert!(error.to_string().contains("Debug, Clone"));
+    }
+
+    #[test]
+    fn test_derive_entries() {
+        let file = SourceFile::parse("#[derive(Debug, serde::Serialize,)] struct S;", Edition::CURRENT).tree();
+        let tt = file.syntax().descendants().find_map(ast::TokenTree::cast).unwrap();
+        let entries: Vec<(String, String)> =
+            derive_entries(&tt).into_iter().map(|(written, ident)| (written, ident.text().to_string())).collect();
+
+        assert_eq!(
+            entries,
+            vec![
+                ("Debug".to_string(), "Debug".to_string()),
+                ("serde::Serialize".to_string(), "Serialize".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_no_macro_at_position() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer/macros.rs").unwrap();
+        assert!(analyzer.expand_macro(path.to_str().unwrap(), 0, 0).unwrap().is_none());
+    }
+}
@@ -0,0 +1,202 @@
+//! Match sites over an enum
+//!
+//! Lists every `match` in the workspace whose scrutinee is a given enum (or a
+//! reference to it) and flags the ones with a catch-all arm: `_`, or a plain
+//! binding such as `other`. Those arms silently absorb a newly added variant
+//! instead of failing to compile, so they are what to review after adding
+//! one. Matches inside macro calls such as `matches!` are not seen.
+//!
+//! Every `match` in the workspace is type-checked, so on a large project the
+//! scan can be time-boxed and return the sites found so far.
+
+use super::budget::{Budget, Coverage};
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Enum, ModuleDef, PathResolution, Semantics, Variant};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode,
+};
+
+/// A `match` over the queried enum
+#[derive(Debug, Clone)]
+pub struct MatchSite {
+    /// Name of the enclosing function, if any
+    pub function: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The scrutinee as written, e.g. `self.kind`
+    pub scrutinee: String,
+    pub arms: usize,
+    /// Pattern of the unguarded catch-all arm, e.g. `_` or `other`
+    pub wildcard: Option<String>,
+    pub wildcard_line: Option<u32>,
+    /// Variants no arm names, so the catch-all handles them
+    pub unnamed_variants: Vec<String>,
+}
+
+/// The match sites of an enum
+#[derive(Debug, Clone)]
+pub struct EnumMatches {
+    /// Qualified path of the enum
+    pub enum_path: String,
+    pub variants: Vec<String>,
+    pub sites: Vec<MatchSite>,
+    /// How many workspace files were scanned before the budget ran out
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// List the workspace `match` expressions over the enum matching a name,
+    /// qualified path or symbol_id
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several enums match.
+    pub fn enum_match_sites(&self, enum_name: &str, budget: Budget) -> Result<EnumMatches, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let enums: Vec<Enum> = self.resolve_definitions(&sema, enum_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Adt(Adt::Enum(enum_)) => Some(enum_),
+                _ => None,
+            })
+            .collect();
+        let Some(&enum_) = enums.first() else {
+            return Err(AnalyzerError::Other(format!("No enum named '{}' found", enum_name)));
+        };
+        self.require_unique(&sema, enum_name, &enums.iter().map(|&e| Definition::Adt(Adt::Enum(e))).collect::<Vec<_>>())?;
+        let variants = enum_.variants(db);
+
+        let files = self.workspace_files()?;
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        let mut sites = Vec::new();
+        for file_id in files {
+            if budget.exhausted() {
+                break;
+            }
+            coverage.files_scanned += 1;
+            let source = sema.parse_guess_edition(file_id);
+            for match_expr in source.syntax().descendants().filter_map(ast::MatchExpr::cast) {
+                let Some(scrutinee) = match_expr.expr() else { continue };
+                let Some(ty) = sema.type_of_expr(&scrutinee) else { continue };
+                if ty.original.strip_references().as_adt() != Some(Adt::Enum(enum_)) {
+                    continue;
+                }
+
+                let arms: Vec<ast::MatchArm> = match_expr.match_arm_list().into_iter().flat_map(|list| list.arms()).collect();
+                let mut named = Vec::new();
+                let mut wildcard = None;
+                for arm in &arms {
+                    let Some(pat) = arm.pat() else { continue };
+                    named.extend(named_variants(&sema, &pat, enum_));
+                    if wildcard.is_none() && arm.guard().is_none() && is_catch_all(&sema, &pat) {
+                        wildcard = Some(pat);
+                    }
+                }
+
+                let (start_line, end_line) = self.line_range(file_id, match_expr.syntax().text_range())?;
+                let wildcard_line = match &wildcard {
+                    Some(pat) => Some(self.line_range(file_id, pat.syntax().text_range())?.0),
+                    None => None,
+                };
+                sites.push(MatchSite {
+                    function: match_expr
+                        .syntax()
+                        .ancestors()
+                        .find_map(ast::Fn::cast)
+                        .and_then(|func| func.name())
+                        .map(|name| name.text().to_string()),
+                    file_path: self.file_path(file_id),
+                    start_line,
+                    end_line,
+                    scrutinee: scrutinee.syntax().text().to_string(),
+                    arms: arms.len(),
+                    wildcard: wildcard.as_ref().map(|pat| pat.syntax().text().to_string()),
+                    wildcard_line,
+                    unnamed_variants: variants
+                        .iter()
+                        .filter(|variant| !named.contains(*variant))
+                        .map(|variant| variant.name(db).as_str().to_string())
+                        .collect(),
+                });
+            }
+        }
+
+        // Sites with a catch-all first, as those are the ones to review
+        sites.sort_by(|a, b| {
+            (a.wildcard.is_none(), &a.file_path, a.start_line).cmp(&(b.wildcard.is_none(), &b.file_path, b.start_line))
+        });
+        Ok(EnumMatches {
+            enum_path: definition_key(db, Definition::Adt(Adt::Enum(enum_))).unwrap_or_else(|| enum_name.to_string()),
+            variants: variants.iter().map(|variant| variant.name(db).as_str().to_string()).collect(),
+            sites,
+            coverage,
+        })
+    }
+}
+
+/// The variants of `enum_` a pattern names, e.g. `Kind::A | Kind::B(_)`
+fn named_variants(sema: &Semantics<'_, RootDatabase>, pat: &ast::Pat, enum_: Enum) -> Vec<Variant> {
+    let db = sema.db;
+    let mut variants = Vec::new();
+    for node in pat.syntax().descendants() {
+        let variant = if let Some(path) = ast::Path::cast(node.clone()) {
+            match sema.resolve_path(&path) {
+                Some(PathResolution::Def(ModuleDef::Variant(variant))) => Some(variant),
+                _ => None,
+            }
+        } else if let Some(ident) = ast::IdentPat::cast(node) {
+            // A bare name is a variant when one is imported under it
+            match sema.resolve_bind_pat_to_const(&ident) {
+                Some(ModuleDef::Variant(variant)) => Some(variant),
+                _ => None,
+            }
+        } else {
+            None
+        };
+        if let Some(variant) = variant.filter(|variant| variant.parent_enum(db) == enum_) {
+            variants.push(variant);
+        }
+    }
+    variants
+}
+
+/// Whether a pattern matches any value: `_`, a binding such as `other` or
+/// `ref x`, `x @ _`, or an or-pattern with one of those
+fn is_catch_all(sema: &Semantics<'_, RootDatabase>, pat: &ast::Pat) -> bool {
+    match pat {
+        ast::Pat::WildcardPat(_) => true,
+        ast::Pat::IdentPat(ident) => {
+            sema.resolve_bind_pat_to_const(ident).is_none() && ident.pat().is_none_or(|sub| is_catch_all(sema, &sub))
+        }
+        ast::Pat::ParenPat(paren) => paren.pat().is_some_and(|inner| is_catch_all(sema, &inner)),
+        ast::Pat::OrPat(or) => or.pats().any(|inner| is_catch_all(sema, &inner)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_match_sites_of_symbol_kind() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let matches = analyzer.enum_match_sites("analyzer::SymbolKind", Budget::unlimited()).unwrap();
+        assert!(matches.variants.contains(&"Struct".to_string()));
+        assert!(!matches.sites.is_empty());
+
+        // Catch-all sites sort first and say which variants they absorb
+        let first_exhaustive = matches.sites.iter().position(|s| s.wildcard.is_none()).unwrap_or(matches.sites.len());
+        assert!(matches.sites[first_exhaustive..].iter().all(|s| s.wildcard.is_none()));
+        for site in &matches.sites[..first_exhaustive] {
+            assert!(site.wildcard_line.is_some_and(|line| line >= site.start_line && line <= site.end_line));
+        }
+    }
+}
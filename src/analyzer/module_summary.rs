@@ -0,0 +1,224 @@
+//! Module architecture summary
+//!
+//! Composes the existing per-item and per-file analyses into one skeleton of
+//! a module (or a whole crate, from its root file): its visible items grouped
+//! by role, where its imports come from, and which of its items the rest of
+//! the workspace leans on most. Meant as raw material for a prose overview.
+
+use super::file_dependencies::file_imports;
+use super::move_item::{has_visibility, item_name_node, module_path};
+use super::reference_index::definition_key;
+use super::{item_label, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide_db::defs::{Definition, NameClass};
+use ra_ap_syntax::{
+    ast::{self, HasName},
+    AstNode,
+};
+
+/// Number of most referenced items reported
+const TOP_REFERENCED_LIMIT: usize = 10;
+
+/// A visible item of the module
+#[derive(Debug, Clone)]
+pub struct ModuleItem {
+    pub name: String,
+    /// Item kind as in `enumerate_file`, e.g. "Struct" or "Function"
+    pub kind: &'static str,
+    /// Path of the module declaring the item
+    pub module: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// The skeleton of a module
+#[derive(Debug, Clone)]
+pub struct ModuleSummary {
+    /// Path of the module, e.g. `crate::analyzer`
+    pub module: String,
+    pub files: Vec<String>,
+    pub submodules: Vec<String>,
+    /// Structs, enums, unions and type aliases
+    pub types: Vec<ModuleItem>,
+    pub traits: Vec<ModuleItem>,
+    pub functions: Vec<ModuleItem>,
+    /// Constants and statics
+    pub constants: Vec<ModuleItem>,
+    /// Crates imported from, with the number of imports from each
+    pub external_dependencies: Vec<(String, usize)>,
+    /// Modules of the same crate outside this one imported from, with counts
+    pub internal_dependencies: Vec<(String, usize)>,
+    /// The module's items with the most references, or `None` if the
+    /// reference index hasn't been built
+    pub top_referenced: Option<Vec<(String, usize)>>,
+}
+
+impl Analyzer {
+    /// Summarize the module defined by `file_path`
+    ///
+    /// With `recursive`, submodules are folded into the summary, so passing a
+    /// crate root summarizes the whole crate.
+    pub fn summarize_module(&self, file_path: &str, recursive: bool) -> Result<ModuleSummary, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let root_file = self.file_id(file_path)?;
+        let module = sema
+            .file_to_module_def(root_file)
+            .ok_or_else(|| AnalyzerError::Other("File is not part of a module".to_string()))?;
+        let module_key = definition_key(db, Definition::Module(module)).unwrap_or_default();
+
+        let mut files = Vec::new();
+        for file_id in self.workspace_files()? {
+            let Some(file_module) = sema.file_to_module_def(file_id) else { continue };
+            if file_module == module || (recursive && file_module.path_to_root(db).contains(&module)) {
+                files.push((file_id, file_module));
+            }
+        }
+
+        let mut summary = ModuleSummary {
+            module: module_path(db, module),
+            files: files.iter().map(|(file_id, _)| self.file_path(*file_id)).collect(),
+            submodules: Vec::new(),
+            types: Vec::new(),
+            traits: Vec::new(),
+            functions: Vec::new(),
+            constants: Vec::new(),
+            external_dependencies: Vec::new(),
+            internal_dependencies: Vec::new(),
+            top_referenced: None,
+        };
+        let mut referenced = Vec::new();
+
+        for (file_id, file_module) in &files {
+            let source = sema.parse_guess_edition(*file_id);
+            for item in source.syntax().descendants().filter_map(ast::Item::cast) {
+                let Some(inline_modules) = enclosing_modules(&item) else { continue };
+                if !recursive && !inline_modules.is_empty() {
+                    continue;
+                }
+                let Some((name, kind)) = item_label(&item) else { continue };
+                let path = std::iter::once(module_path(db, *file_module)).chain(inline_modules).collect::<Vec<_>>().join("::");
+
+                if kind == "Module" {
+                    summary.submodules.push(format!("{}::{}", path, name));
+                    continue;
+                }
+                if !has_visibility(&item) {
+                    continue;
+                }
+                let (start_line, end_line) = self.line_range(*file_id, item.syntax().text_range())?;
+                let entry = ModuleItem {
+                    name,
+                    kind,
+                    module: path,
+                    file_path: self.file_path(*file_id),
+                    start_line,
+                    end_line,
+                };
+                match kind {
+                    "Struct" | "Enum" | "Union" | "TypeAlias" => summary.types.push(entry),
+                    "Trait" => summary.traits.push(entry),
+                    "Function" => summary.functions.push(entry),
+                    "Const" | "Static" => summary.constants.push(entry),
+                    _ => continue,
+                }
+
+                if let Some(index) = &self.references {
+                    let def = item_name_node(&item).and_then(|name| NameClass::classify(&sema, &name)).and_then(NameClass::defined);
+                    if let Some(key) = def.and_then(|def| definition_key(db, def)) {
+                        let count = index.references_to(&key).len();
+                        referenced.push((key, count));
+                    }
+                }
+            }
+
+            for import in file_imports(&sema, *file_id) {
+                let Some(origin) = import.origin else { continue };
+                let counts = if import.external {
+                    &mut summary.external_dependencies
+                } else if origin == module_key || origin.starts_with(&format!("{}::", module_key)) {
+                    continue;
+                } else {
+                    &mut summary.internal_dependencies
+                };
+                match counts.iter_mut().find(|(name, _)| *name == origin) {
+                    Some((_, count)) => *count += 1,
+                    None => counts.push((origin, 1)),
+                }
+            }
+        }
+
+        summary.external_dependencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        summary.internal_dependencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        if self.references.is_some() {
+            referenced.retain(|(_, count)| *count > 0);
+            referenced.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            referenced.truncate(TOP_REFERENCED_LIMIT);
+            summary.top_referenced = Some(referenced);
+        }
+
+        Ok(summary)
+    }
+}
+
+/// Names of the inline modules around a module-level item, outermost first;
+/// `None` for items inside function bodies, impls or traits
+pub(super) fn enclosing_modules(item: &ast::Item) -> Option<Vec<String>> {
+    let mut modules = Vec::new();
+    let mut parent = item.syntax().parent();
+    while let Some(node) = parent {
+        if ast::SourceFile::can_cast(node.kind()) {
+            modules.reverse();
+            return Some(modules);
+        }
+        if let Some(module) = ast::Module::cast(node.clone()) {
+            modules.push(module.name()?.text().to_string());
+        } else if !ast::ItemList::can_cast(node.kind()) {
+            return None;
+        }
+        parent = node.parent();
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_enclosing_modules() {
+        let file = SourceFile::parse(
+            "fn top() {} mod a { mod b { fn deep() {} } } impl S { fn method() {} }",
+            Edition::CURRENT,
+        )
+        .tree();
+        let scopes: Vec<_> = file
+            .syntax()
+            .descendants()
+            .filter_map(ast::Fn::cast)
+            .map(|f| enclosing_modules(&ast::Item::Fn(f)))
+            .collect();
+
+        assert_eq!(
+            scopes,
+            vec![Some(vec![]), Some(vec!["a".to_string(), "b".to_string()]), None]
+        );
+    }
+
+    #[test]
+    fn test_summarize_analyzer_module() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer.rs").unwrap();
+        let summary = analyzer.summarize_module(path.to_str().unwrap(), false).unwrap();
+
+        assert_eq!(summary.module, "crate::analyzer");
+        assert!(summary.types.iter().any(|t| t.name == "SymbolInfo"));
+        assert!(summary.submodules.iter().any(|m| m == "crate::analyzer::monikers"));
+        assert!(summary.external_dependencies.iter().any(|(name, _)| name.starts_with("ra_ap")));
+        assert!(summary.top_referenced.is_none(), "No reference index was built");
+    }
+}
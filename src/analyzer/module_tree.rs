@@ -0,0 +1,112 @@
+//! Module hierarchy of a crate
+//!
+//! Walks a crate's def map from its root module down, so the nesting of
+//! modules and the file each one lives in can be read at a glance instead of
+//! by following `mod` declarations file by file. Modules disabled by `cfg`
+//! aren't part of the def map and so aren't listed; inline modules such as
+//! `mod tests { ... }` are, with the lines they span.
+
+use super::move_item::module_path;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Crate, Module, Visibility};
+use ra_ap_ide::RootDatabase;
+
+/// A module and its submodules
+#[derive(Debug, Clone)]
+pub struct ModuleNode {
+    /// The module's name; the crate name for the root
+    pub name: String,
+    /// Path of the module, e.g. `crate::analyzer`
+    pub path: String,
+    /// The file the module's items are in
+    pub file_path: String,
+    /// Lines the module spans: the whole file, or the `mod` block if inline
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Whether the module is declared with a `{ ... }` body rather than in its own file
+    pub inline: bool,
+    pub public: bool,
+    /// Submodules, by name
+    pub children: Vec<ModuleNode>,
+}
+
+impl Analyzer {
+    /// The module tree of each workspace crate, or of the crates named
+    /// `crate_name` (a package's library and binaries share the name)
+    pub fn module_tree(&self, crate_name: Option<&str>) -> Result<Vec<ModuleNode>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let wanted = crate_name.map(|name| name.replace('-', "_"));
+
+        let mut trees = Vec::new();
+        for krate in Crate::all(db).into_iter().filter(|krate| krate.origin(db).is_local()) {
+            let name = krate.display_name(db).map(|name| name.to_string().replace('-', "_")).unwrap_or_default();
+            if wanted.as_ref().is_some_and(|wanted| *wanted != name) {
+                continue;
+            }
+            trees.push(self.module_node(db, krate.root_module(), name)?);
+        }
+        if trees.is_empty() {
+            if let Some(crate_name) = crate_name {
+                return Err(AnalyzerError::Other(format!("No workspace crate named '{}'", crate_name)));
+            }
+        }
+
+        trees.sort_by(|a, b| (&a.name, &a.file_path).cmp(&(&b.name, &b.file_path)));
+        Ok(trees)
+    }
+
+    fn module_node(&self, db: &RootDatabase, module: Module, name: String) -> Result<ModuleNode, AnalyzerError> {
+        let range = module.definition_source_range(db).original_node_file_range_rooted(db);
+        let file_id = range.file_id.file_id(db);
+        let (start_line, end_line) = self.line_range(file_id, range.range)?;
+
+        let mut children = module
+            .children(db)
+            .map(|child| {
+                let name = child.name(db).map(|name| name.as_str().to_string()).unwrap_or_default();
+                self.module_node(db, child, name)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        children.sort_by(|a, b| a.name.cmp(&b.name));
+
+        Ok(ModuleNode {
+            name,
+            path: module_path(db, module),
+            file_path: self.file_path(file_id),
+            start_line,
+            end_line,
+            inline: module.is_inline(db),
+            public: matches!(module.visibility(db), Visibility::Public),
+            children,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_tree_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let trees = analyzer.module_tree(Some("cratographer")).unwrap();
+        let root = &trees[0];
+        assert_eq!(root.path, "crate");
+        assert!(root.file_path.ends_with("src/main.rs"));
+
+        let analyzer_module = root.children.iter().find(|m| m.name == "analyzer").unwrap();
+        assert!(analyzer_module.file_path.ends_with("src/analyzer.rs"));
+        assert!(!analyzer_module.inline);
+
+        let this = analyzer_module.children.iter().find(|m| m.name == "module_tree").unwrap();
+        assert_eq!(this.path, "crate::analyzer::module_tree");
+        assert!(this.file_path.ends_with("src/analyzer/module_tree.rs"));
+        let tests = this.children.iter().find(|m| m.name == "tests").unwrap();
+        assert!(tests.inline);
+        assert!(tests.start_line > 0);
+
+        assert!(analyzer.module_tree(Some("no_such_crate")).is_err());
+    }
+}
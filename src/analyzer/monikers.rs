@@ -0,0 +1,261 @@
+//! Stable symbol monikers
+//!
+//! A moniker names a definition in a way that survives server restarts, so
+//! external systems can store it and resolve it again later. The format is
+//!
+//! ```text
+//! crate@version::path::to::Item#kind
+//! ```
+//!
+//! - `crate@version` is the owning crate; `@version` is left out for crates
+//!   without one (such as the sysroot crates).
+//! - The path lists the modules below the crate root, then the owner of
+//!   associated items (the impl's self type or the trait), enum variants and
+//!   fields, then the item name. The crate root module has an empty path.
+//! - `kind` tells apart items sharing a path in different namespaces: one of
+//!   `mod`, `struct`, `enum`, `union`, `variant`, `field`, `trait`, `type`,
+//!   `fn`, `const`, `static` or `macro`. Items of trait impls append the trait
+//!   name and its generic arguments, as in `#fn:Display` or `#fn:From<u8>`,
+//!   since several impls may share a self type.
+//!
+//! Examples: `cratographer@0.1.0::analyzer::Analyzer::new#fn`,
+//! `core::option::Option::Some#variant`.
+
+use super::reference_index::definition_path;
+use super::rustdoc::docs_url;
+use super::{definition_at, display_type, Analyzer, AnalyzerError, RustdocLink, SymbolInfo, SymbolKind, SymbolSize, DEFINITION_SEARCH_LIMIT};
+use ra_ap_hir::{Adt, AssocItemContainer, Semantics};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::TextSize;
+
+/// A moniker split into its components
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Moniker {
+    crate_name: String,
+    version: Option<String>,
+    path: Vec<String>,
+    kind: String,
+}
+
+impl Moniker {
+    fn parse(text: &str) -> Option<Self> {
+        let (qualified, kind) = text.rsplit_once('#')?;
+        let mut segments = qualified.split("::");
+        let first = segments.next()?;
+        let (crate_name, version) = match first.split_once('@') {
+            Some((name, version)) => (name, Some(version.to_string())),
+            None => (first, None),
+        };
+        let path: Vec<String> = segments.map(str::to_string).collect();
+
+        if crate_name.is_empty() || kind.is_empty() || path.iter().any(String::is_empty) {
+            return None;
+        }
+        Some(Self { crate_name: crate_name.to_string(), version, path, kind: kind.to_string() })
+    }
+}
+
+impl Analyzer {
+    /// Resolve a moniker produced by an earlier query back to its symbol
+    pub fn resolve_moniker(&self, moniker: &str) -> Result<SymbolInfo, AnalyzerError> {
+        let parsed = Moniker::parse(moniker)
+            .ok_or_else(|| AnalyzerError::Other(format!("Malformed moniker: {}", moniker)))?;
+        let not_found = || AnalyzerError::Other(format!("No symbol found for moniker: {}", moniker));
+
+        let Some(name) = parsed.path.last() else {
+            return self.resolve_crate_root(&parsed).ok_or_else(not_found);
+        };
+
+        // Candidates are all symbols with the item's name, in the workspace
+        // and in libraries; the one whose moniker matches is the answer
+        let analysis = self.host.analysis();
+        let crates = self.crates_by_source_root()?;
+        for libs in [false, true] {
+            let mut query = ra_ap_ide::Query::new(name.clone());
+            query.exact();
+            if libs {
+                query.libs();
+            }
+            let navs = analysis.symbol_search(query, DEFINITION_SEARCH_LIMIT).map_err(|_| AnalyzerError::Canceled)?;
+            let found = navs
+                .iter()
+                .filter_map(|nav| self.nav_to_symbol(&analysis, &crates, nav))
+                .find(|sym| sym.moniker.as_deref() == Some(moniker));
+            if let Some(symbol) = found {
+                return Ok(symbol);
+            }
+        }
+
+        Err(not_found())
+    }
+
+    /// Moniker of the definition whose name is at `offset` in a file
+    pub(super) fn moniker_at(&self, sema: &Semantics<'_, RootDatabase>, file_id: FileId, offset: TextSize) -> Option<String> {
+        definition_moniker(sema.db, definition_at(sema, file_id, offset)?)
+    }
+
+    /// The root module of the crate a moniker with an empty path names
+    fn resolve_crate_root(&self, moniker: &Moniker) -> Option<SymbolInfo> {
+        if moniker.kind != "mod" {
+            return None;
+        }
+
+        let db = self.host.raw_database();
+        let krate = ra_ap_hir::Crate::all(db).into_iter().find(|krate| {
+            krate.display_name(db).is_some_and(|n| n.to_string() == moniker.crate_name)
+                && krate.version(db) == moniker.version
+        })?;
+
+        let root_file = krate.root_file(db);
+        let text = self.host.analysis().file_text(root_file).ok()?;
+        let end_line = text.lines().count().saturating_sub(1) as u32;
+        let url = format!("{}/index.html", moniker.crate_name.replace('-', "_"));
+
+        Some(SymbolInfo {
+            name: moniker.crate_name.clone(),
+            kind: SymbolKind::Module,
+            file_path: self.file_path(root_file),
+            start_line: 0,
+            end_line,
+            documentation: None,
+            crate_name: Some(moniker.crate_name.clone()),
+            crate_version: moniker.version.clone(),
+            moniker: Some(render(moniker)),
+            rustdoc: Some(RustdocLink {
+                path: moniker.crate_name.replace('-', "_"),
+                docs_url: docs_url(db, krate, &url),
+                url,
+            }),
+            approximate: false,
+            attributes: Vec::new(),
+            owners: Vec::new(),
+            size: Some(SymbolSize::of(&text)),
+        })
+    }
+}
+
+/// The item name a moniker ends with; `None` if the text is not a moniker
+/// or names a crate root
+pub(super) fn moniker_item_name(text: &str) -> Option<String> {
+    Moniker::parse(text)?.path.pop()
+}
+
+/// Build the moniker of a definition
+pub(super) fn definition_moniker(db: &RootDatabase, def: Definition) -> Option<String> {
+    let kind = definition_kind(def)?;
+
+    // Items of trait impls are qualified by the implemented trait and its
+    // arguments, so `From<A>` and `From<B>` impls for one type stay apart
+    let container = match def {
+        Definition::Function(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::Const(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::TypeAlias(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        _ => None,
+    };
+    let kind = match container {
+        Some(AssocItemContainer::Impl(impl_)) => match impl_.trait_(db) {
+            Some(trait_) => {
+                let krate = impl_.module(db).krate();
+                let args: Vec<String> = impl_
+                    .trait_ref(db)
+                    .map(|trait_ref| (1..).map_while(|i| trait_ref.get_type_argument(i)).map(|ty| display_type(db, &ty, krate)).collect())
+                    .unwrap_or_default();
+                if args.is_empty() {
+                    format!("{}:{}", kind, trait_.name(db).as_str())
+                } else {
+                    format!("{}:{}<{}>", kind, trait_.name(db).as_str(), args.join(", "))
+                }
+            }
+            None => kind.to_string(),
+        },
+        _ => kind.to_string(),
+    };
+
+    let (krate, path) = definition_path(db, def)?;
+    Some(render(&Moniker {
+        crate_name: krate.display_name(db)?.to_string(),
+        version: krate.version(db),
+        path,
+        kind,
+    }))
+}
+
+/// The moniker kind of a definition, e.g. "struct" or "fn"; `None` for
+/// locals, generic parameters and other things without a moniker
+pub(super) fn definition_kind(def: Definition) -> Option<&'static str> {
+    Some(match def {
+        Definition::Module(_) => "mod",
+        Definition::Adt(Adt::Struct(_)) => "struct",
+        Definition::Adt(Adt::Enum(_)) => "enum",
+        Definition::Adt(Adt::Union(_)) => "union",
+        Definition::Variant(_) => "variant",
+        Definition::Field(_) => "field",
+        Definition::Trait(_) => "trait",
+        Definition::TypeAlias(_) => "type",
+        Definition::Function(_) => "fn",
+        Definition::Const(_) => "const",
+        Definition::Static(_) => "static",
+        Definition::Macro(_) => "macro",
+        _ => return None,
+    })
+}
+
+fn render(moniker: &Moniker) -> String {
+    let mut text = moniker.crate_name.clone();
+    if let Some(version) = &moniker.version {
+        text.push('@');
+        text.push_str(version);
+    }
+    for segment in &moniker.path {
+        text.push_str("::");
+        text.push_str(segment);
+    }
+    text.push('#');
+    text.push_str(&moniker.kind);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{SearchMode, SearchOptions};
+
+    #[test]
+    fn test_parse_and_render_round_trip() {
+        for text in ["serde@1.0.210::de::Deserialize#trait", "core::fmt::Display#trait", "cratographer@0.1.0#mod", "a@1.0.0::Foo::fmt#fn:Debug", "a@1.0.0::Foo::from#fn:From<Vec<u8>>"] {
+            let moniker = Moniker::parse(text).unwrap_or_else(|| panic!("Failed to parse {}", text));
+            assert_eq!(render(&moniker), text);
+        }
+
+        let parsed = Moniker::parse("a@1.0.0::Foo::fmt#fn:Debug").unwrap();
+        assert_eq!(parsed.crate_name, "a");
+        assert_eq!(parsed.version.as_deref(), Some("1.0.0"));
+        assert_eq!(parsed.path, vec!["Foo", "fmt"]);
+        assert_eq!(parsed.kind, "fn:Debug");
+
+        assert!(Moniker::parse("no_kind::Foo").is_none());
+        assert!(Moniker::parse("a::::Foo#fn").is_none());
+    }
+
+    #[test]
+    fn test_moniker_resolves_after_restart() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let options = SearchOptions { mode: SearchMode::Exact, ..Default::default() };
+        let symbols = analyzer.find_symbol("enumerate_file", &options).unwrap();
+        let moniker = symbols
+            .iter()
+            .find_map(|s| s.moniker.clone().filter(|m| m.contains("Analyzer::enumerate_file")))
+            .expect("Analyzer::enumerate_file should have a moniker");
+        assert_eq!(moniker, format!("cratographer@{}::analyzer::Analyzer::enumerate_file#fn", env!("CARGO_PKG_VERSION")));
+
+        // A fresh analyzer resolves the stored moniker to the same place
+        let mut restarted = Analyzer::new();
+        assert!(restarted.load_project(".").is_ok());
+        let resolved = restarted.resolve_moniker(&moniker).expect("Moniker should resolve");
+        assert_eq!(resolved.name, "enumerate_file");
+        assert!(resolved.file_path.ends_with("analyzer.rs"));
+    }
+}
@@ -0,0 +1,263 @@
+//! Monomorphization potential of generic functions
+//!
+//! Each distinct set of type arguments a generic function is called with
+//! compiles to another copy of its body, so a large generic function called
+//! with many different types costs code size and compile time. Taking a
+//! `dyn Trait` or `&dyn Trait`, or moving the bulk of the body into a
+//! non-generic inner function, trades that for dynamic dispatch.
+//!
+//! The instantiations are estimated from the workspace's call sites: the
+//! turbofish when written, otherwise the inferred types of the arguments
+//! passed for generic parameters and of the result when the return type is
+//! generic. Calls through function pointers, instantiations from other
+//! generic code (`T` seen as one type) and calls from outside the workspace
+//! aren't counted, so the estimates are lower bounds.
+
+use super::budget::{Budget, Coverage};
+use super::reference_index::definition_key;
+use super::{display_type, normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Function, ModuleDef, PathResolution, Semantics};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasArgList, HasGenericParams, HasName},
+    AstNode,
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Instantiations listed per function at most
+const MAX_EXAMPLES: usize = 5;
+
+/// A generic function with its estimated instantiations
+#[derive(Debug, Clone)]
+pub struct GenericInstantiations {
+    /// Qualified path of the function
+    pub path: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Type parameters, `impl Trait` arguments included as `impl Trait`
+    pub type_params: Vec<String>,
+    /// Lines of the function's body, copied per instantiation
+    pub body_lines: u32,
+    pub call_sites: usize,
+    /// Distinct type arguments seen at the call sites
+    pub instantiations: usize,
+    /// Body lines times instantiations, the ranking key
+    pub estimated_lines: u32,
+    /// The most frequent type arguments with their call counts, e.g. `<String>` called 4 times
+    pub examples: Vec<(String, usize)>,
+}
+
+/// Generic functions of the workspace files scanned within the budget
+#[derive(Debug, Clone)]
+pub struct MonomorphizationReport {
+    /// By estimated lines, largest first
+    pub functions: Vec<GenericInstantiations>,
+    pub coverage: Coverage,
+}
+
+/// Which parts of a generic function's signature mention its type parameters
+struct GenericShape {
+    type_params: Vec<String>,
+    /// Per parameter, `self` first for methods
+    generic_params: Vec<bool>,
+    generic_return: bool,
+}
+
+impl Analyzer {
+    /// Estimate the instantiations of every generic workspace function from
+    /// its call sites, keeping those with at least `min_instantiations`
+    pub fn monomorphization_candidates(&self, min_instantiations: usize, budget: Budget) -> Result<MonomorphizationReport, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let files = self.workspace_files()?;
+        let workspace: HashSet<FileId> = files.iter().copied().collect();
+
+        let mut shapes: HashMap<Function, Option<GenericShape>> = HashMap::new();
+        // Per function, the call count of each set of type arguments
+        let mut calls: HashMap<Function, BTreeMap<String, usize>> = HashMap::new();
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        for &file_id in &files {
+            if budget.exhausted() {
+                break;
+            }
+            coverage.files_scanned += 1;
+            let source = sema.parse_guess_edition(file_id);
+            let krate = sema.file_to_module_def(file_id).map(|module| module.krate());
+
+            for node in source.syntax().descendants() {
+                let (function, turbofish, args, call) = if let Some(call) = ast::CallExpr::cast(node.clone()) {
+                    let Some(ast::Expr::PathExpr(callee)) = call.expr() else { continue };
+                    let Some(path) = callee.path() else { continue };
+                    let Some(PathResolution::Def(ModuleDef::Function(function))) = sema.resolve_path(&path) else { continue };
+                    let turbofish = path.segment().and_then(|segment| segment.generic_arg_list());
+                    let args: Vec<ast::Expr> = call.arg_list().into_iter().flat_map(|list| list.args()).collect();
+                    (function, turbofish, args, ast::Expr::CallExpr(call))
+                } else if let Some(call) = ast::MethodCallExpr::cast(node.clone()) {
+                    let Some(function) = sema.resolve_method_call(&call) else { continue };
+                    let args: Vec<ast::Expr> =
+                        call.receiver().into_iter().chain(call.arg_list().into_iter().flat_map(|list| list.args())).collect();
+                    (function, call.generic_arg_list(), args, ast::Expr::MethodCallExpr(call))
+                } else {
+                    continue;
+                };
+
+                let shape = shapes.entry(function).or_insert_with(|| generic_shape(&sema, function, &workspace));
+                let Some(shape) = shape else { continue };
+
+                let key = match turbofish {
+                    Some(args) => normalize_whitespace(&args.syntax().text().to_string()),
+                    None => {
+                        let display = |expr: &ast::Expr| match (sema.type_of_expr(expr), krate) {
+                            (Some(info), Some(krate)) => display_type(db, &info.original, krate),
+                            _ => "{unknown}".to_string(),
+                        };
+                        // Arguments line up with the parameters, `self` included, whether
+                        // a method is called with a receiver or as a path function
+                        let mut types: Vec<String> = args
+                            .iter()
+                            .enumerate()
+                            .filter(|(i, _)| shape.generic_params.get(*i).copied().unwrap_or(false))
+                            .map(|(_, arg)| display(arg))
+                            .collect();
+                        if shape.generic_return {
+                            types.push(format!("-> {}", display(&call)));
+                        }
+                        format!("({})", types.join(", "))
+                    }
+                };
+                *calls.entry(function).or_default().entry(key).or_insert(0) += 1;
+            }
+        }
+
+        let mut functions = Vec::new();
+        for (function, keys) in calls {
+            let instantiations = keys.len();
+            if instantiations < min_instantiations.max(1) {
+                continue;
+            }
+            let Some(Some(shape)) = shapes.remove(&function) else { continue };
+            let Some(source) = sema.source(function) else { continue };
+            let range = sema.original_range(source.value.syntax());
+            let file_id = range.file_id.file_id(db);
+            let (start_line, end_line) = self.line_range(file_id, range.range)?;
+            let body_lines = match source.value.body() {
+                Some(body) => {
+                    let (start, end) = self.line_range(file_id, sema.original_range(body.syntax()).range)?;
+                    end - start + 1
+                }
+                None => 0,
+            };
+
+            let mut examples: Vec<(String, usize)> = keys.into_iter().collect();
+            examples.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+            functions.push(GenericInstantiations {
+                path: definition_key(db, Definition::Function(function)).unwrap_or_else(|| function.name(db).as_str().to_string()),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                type_params: shape.type_params,
+                body_lines,
+                call_sites: examples.iter().map(|(_, count)| count).sum(),
+                instantiations,
+                estimated_lines: body_lines.saturating_mul(instantiations as u32),
+                examples: examples.into_iter().take(MAX_EXAMPLES).collect(),
+            });
+        }
+        functions.sort_by(|a, b| {
+            (b.estimated_lines, b.instantiations).cmp(&(a.estimated_lines, a.instantiations)).then_with(|| a.path.cmp(&b.path))
+        });
+
+        Ok(MonomorphizationReport { functions, coverage })
+    }
+}
+
+/// The generic shape of a workspace function; `None` if it has no type
+/// parameters of its own or is defined outside the workspace
+fn generic_shape(sema: &Semantics<'_, RootDatabase>, function: Function, workspace: &HashSet<FileId>) -> Option<GenericShape> {
+    let source = sema.source(function)?;
+    if !workspace.contains(&sema.original_range(source.value.syntax()).file_id.file_id(sema.db)) {
+        return None;
+    }
+    shape_of(&source.value)
+}
+
+/// Which parameters and whether the return type mention the function's
+/// type parameters or `impl Trait`
+fn shape_of(func: &ast::Fn) -> Option<GenericShape> {
+    let names: Vec<String> = func
+        .generic_param_list()
+        .into_iter()
+        .flat_map(|list| list.generic_params())
+        .filter_map(|param| match param {
+            ast::GenericParam::TypeParam(param) => param.name().map(|name| name.text().to_string()),
+            _ => None,
+        })
+        .collect();
+    let mentions_generic = |ty: &ast::Type| {
+        ty.syntax().descendants().any(|node| {
+            ast::ImplTraitType::can_cast(node.kind())
+                || ast::NameRef::cast(node).is_some_and(|name| names.iter().any(|n| *n == name.text().as_str()))
+        })
+    };
+
+    let params = func.param_list();
+    let self_param = params.as_ref().and_then(|list| list.self_param()).map(|param| param.ty().is_some_and(|ty| mentions_generic(&ty)));
+    let mut generic_params: Vec<bool> = self_param.into_iter().collect();
+    let mut impl_traits = Vec::new();
+    for param in params.iter().flat_map(|list| list.params()) {
+        let ty = param.ty();
+        generic_params.push(ty.as_ref().is_some_and(mentions_generic));
+        impl_traits.extend(
+            ty.iter()
+                .flat_map(|ty| ty.syntax().descendants().filter_map(ast::ImplTraitType::cast))
+                .map(|impl_trait| normalize_whitespace(&impl_trait.syntax().text().to_string())),
+        );
+    }
+    let type_params: Vec<String> = names.iter().cloned().chain(impl_traits).collect();
+    if type_params.is_empty() {
+        return None;
+    }
+    let generic_return = func.ret_type().and_then(|ret| ret.ty()).is_some_and(|ty| {
+        // `impl Trait` in return position is one opaque type, not a parameter
+        !ast::ImplTraitType::can_cast(ty.syntax().kind()) && mentions_generic(&ty)
+    });
+    Some(GenericShape { type_params, generic_params, generic_return })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn shape(src: &str) -> Option<GenericShape> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        file.syntax().descendants().find_map(ast::Fn::cast).and_then(|func| shape_of(&func))
+    }
+
+    #[test]
+    fn test_generic_shapes() {
+        assert!(shape("fn plain(x: u32) -> u32 { x }").is_none());
+
+        let shape_ = shape("fn f<T: Clone, const N: usize>(a: &[T; N], b: u8, c: impl AsRef<str>) -> Vec<T> { todo!() }").unwrap();
+        assert_eq!(shape_.type_params, vec!["T", "impl AsRef<str>"]);
+        assert_eq!(shape_.generic_params, vec![true, false, true]);
+        assert!(shape_.generic_return);
+
+        let method = shape("impl S { fn m<U>(&self, u: U) -> impl Iterator<Item = U> { todo!() } }").unwrap();
+        assert_eq!(method.generic_params, vec![false, true]);
+        assert!(!method.generic_return);
+    }
+
+    #[test]
+    fn test_monomorphization_candidates_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let report = analyzer.monomorphization_candidates(2, Budget::unlimited()).unwrap();
+        assert!(report.coverage.complete());
+        assert!(report.functions.iter().all(|f| f.instantiations >= 2 && f.call_sites >= f.instantiations));
+        assert!(report.functions.windows(2).all(|w| w[0].estimated_lines >= w[1].estimated_lines));
+    }
+}
@@ -0,0 +1,685 @@
+//! Move-item refactoring
+//!
+//! Computes the edits that move an item (function, type, trait, module, ...)
+//! into another module of the same crate: the removal from the source file,
+//! the insertion into the target file, and the fix-ups of every reference in
+//! the workspace. `use` declarations naming the item are repointed, qualified
+//! paths are rewritten, files that relied on the item being in scope get an
+//! import, and the imports the item itself needs are carried along.
+//!
+//! The edits are only computed, never applied. Cases that can't be rewritten
+//! mechanically are reported as warnings rather than guessed at.
+
+use super::file_dependencies::written_path;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{Module, Semantics, Visibility};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::{Definition, NameClass, NameRefClass};
+use ra_ap_syntax::{
+    ast::{self, HasModuleItem, HasName, HasVisibility},
+    AstNode, SyntaxKind, SyntaxNode, TextRange, TextSize,
+};
+use std::collections::{HashMap, HashSet};
+
+/// A text replacement in one file, in positions of the unmodified file
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub file_path: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Replacement text; empty for deletions
+    pub new_text: String,
+}
+
+/// Everything needed to move an item
+#[derive(Debug, Clone)]
+pub struct MovePlan {
+    pub item: String,
+    /// Module path the item is moved from, e.g. `crate::analyzer`
+    pub from_module: String,
+    /// Module path the item is moved to
+    pub to_module: String,
+    /// Non-overlapping edits, sorted by file and position
+    pub edits: Vec<TextEdit>,
+    /// Things to check by hand after applying the edits
+    pub warnings: Vec<String>,
+}
+
+/// Edits and warnings collected while planning a move
+#[derive(Default)]
+pub(super) struct PlannedEdits {
+    pub(super) files: HashMap<FileId, FileEdits>,
+    pub(super) warnings: Vec<String>,
+}
+
+/// Edits collected per file before conversion to line/column positions
+#[derive(Default)]
+pub(super) struct FileEdits {
+    pub(super) replacements: Vec<(TextRange, String)>,
+    /// `use` lines to add to the file
+    pub(super) imports: Vec<String>,
+    /// `use` lines to add inside inline modules, by insertion offset
+    pub(super) nested_imports: Vec<(TextSize, String)>,
+}
+
+impl Analyzer {
+    /// Plan moving the item `name` into the module defined by `target_file`
+    ///
+    /// `file_path` picks the file holding the item when the name is ambiguous.
+    pub fn plan_move_item(&self, name: &str, file_path: Option<&str>, target_file: &str) -> Result<MovePlan, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let (source_file, item, def) = self.find_movable_item(&sema, name, file_path)?;
+        let target_file = self.file_id(target_file)?;
+
+        let source_module = def.module(db).ok_or_else(|| AnalyzerError::Other(format!("'{}' is not inside a module", name)))?;
+        let target_module = sema
+            .file_to_module_def(target_file)
+            .ok_or_else(|| AnalyzerError::Other("Target file is not part of a module".to_string()))?;
+        if source_module == target_module {
+            return Err(AnalyzerError::Other(format!("'{}' already lives in the target module", name)));
+        }
+        if source_module.krate() != target_module.krate() {
+            return Err(AnalyzerError::Other("Items can only be moved within one crate".to_string()));
+        }
+
+        let item_range = item.syntax().text_range();
+        let item_name = name.rsplit("::").next().unwrap_or(name).to_string();
+        let new_path = format!("{}::{}", module_path(db, target_module), item_name);
+        let mut planned = PlannedEdits::default();
+
+        let moved = [(source_file, item_range)];
+        let referenced_outside_target = self.repoint_references(&sema, def, &moved, &new_path, Some(target_file), &mut planned)?;
+        let PlannedEdits { mut files, mut warnings } = planned;
+
+        // The item leaves its file and joins the target, keeping the imports
+        // and sibling items it relies on reachable
+        let mut item_text = item.syntax().text().to_string();
+        if referenced_outside_target && !has_visibility(&item) {
+            let offset = visibility_insertion_offset(&item);
+            item_text.insert_str(offset, "pub(crate) ");
+            warnings.push(format!("'{}' was private and is made pub(crate) so existing references still resolve", item_name));
+        }
+        files.entry(source_file).or_default().replacements.push((removal_range(&item), String::new()));
+
+        let source_root = sema.parse_guess_edition(source_file);
+        let target_root = sema.parse_guess_edition(target_file);
+        let target_bound = names_bound_by_imports(&target_root);
+        let target_edits = files.entry(target_file).or_default();
+        for import in imports_used_by(&source_root, &item, &target_bound) {
+            if import.contains("self::") || import.contains("super::") {
+                warnings.push(format!("Copied relative import may need adjusting: {}", import));
+            }
+            target_edits.imports.push(import);
+        }
+        for (sibling, private) in sibling_dependencies(&sema, &item, &[def], source_module) {
+            if target_bound.contains(&sibling) {
+                continue;
+            }
+            target_edits.imports.push(format!("use {}::{};", module_path(db, source_module), sibling));
+            if private {
+                warnings.push(format!("'{}' is not public; check that it is visible from the target module", sibling));
+            }
+        }
+        let end = target_root.syntax().text_range().end();
+        target_edits.replacements.push((TextRange::empty(end), format!("\n{}\n", item_text)));
+
+        let edits = self.text_edits(&sema, files)?;
+
+        Ok(MovePlan {
+            item: item_name,
+            from_module: module_path(db, source_module),
+            to_module: module_path(db, target_module),
+            edits,
+            warnings,
+        })
+    }
+
+    /// Update every reference to `def` for its new path
+    ///
+    /// References inside the `moved` ranges travel with the items and are left
+    /// alone. Unqualified references that don't go through a repointed `use`,
+    /// whether in the source module or in a module seeing the item through a
+    /// glob import, get an import of their own. Returns whether anything
+    /// outside the target file refers to the item, which means it must be
+    /// visible from outside its new module.
+    pub(super) fn repoint_references(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        def: Definition,
+        moved: &[(FileId, TextRange)],
+        new_path: &str,
+        target_file: Option<FileId>,
+        planned: &mut PlannedEdits,
+    ) -> Result<bool, AnalyzerError> {
+        let mut referenced_outside_target = false;
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            let references: Vec<_> = source
+                .syntax()
+                .descendants()
+                .filter_map(ast::NameRef::cast)
+                .filter(|name_ref| matches!(NameRefClass::classify(sema, name_ref), Some(NameRefClass::Definition(d, ..)) if d == def))
+                .collect();
+            // Modules importing the item by name; those imports get repointed
+            let importing: Vec<_> = references
+                .iter()
+                .filter(|name_ref| name_ref.syntax().ancestors().any(|n| ast::UseTree::can_cast(n.kind())))
+                .filter_map(|name_ref| sema.scope(name_ref.syntax()).map(|scope| scope.module()))
+                .collect();
+
+            for name_ref in references {
+                let range = name_ref.syntax().text_range();
+                if moved.iter().any(|(file, moved_range)| *file == file_id && moved_range.contains_range(range)) {
+                    continue;
+                }
+                let in_target = Some(file_id) == target_file;
+                referenced_outside_target |= !in_target;
+
+                let edits = planned.files.entry(file_id).or_default();
+                match rewrite_reference(&name_ref, new_path, in_target) {
+                    Rewrite::Replace(range, text) => edits.replacements.push((range, text)),
+                    Rewrite::RemoveUseLeaf(range, alias) => {
+                        edits.replacements.push((range, String::new()));
+                        if !in_target {
+                            edits.imports.push(format!("use {}{};", new_path, alias));
+                        }
+                    }
+                    Rewrite::InScope => {
+                        let Some(module) = sema.scope(name_ref.syntax()).map(|scope| scope.module()) else { continue };
+                        if in_target || importing.contains(&module) {
+                            continue;
+                        }
+                        // Declared here before the move, or seen through a glob import
+                        let import = format!("use {};", new_path);
+                        match name_ref.syntax().ancestors().find_map(ast::Module::cast) {
+                            None => edits.imports.push(import),
+                            Some(inline) => match nested_import_insertion(&inline) {
+                                Some((at, indent)) => edits.nested_imports.push((at, format!("{}{}\n", indent, import))),
+                                None => planned.warnings.push(format!(
+                                    "{}:{}: module '{}' needs `{}`",
+                                    self.file_path(file_id),
+                                    self.line_range(file_id, range)?.0,
+                                    inline.name().map(|n| n.text().to_string()).unwrap_or_default(),
+                                    import
+                                )),
+                            },
+                        }
+                    }
+                    Rewrite::Unsupported(what) => planned.warnings.push(format!(
+                        "{}:{}: {} needs updating by hand",
+                        self.file_path(file_id),
+                        self.line_range(file_id, range)?.0,
+                        what
+                    )),
+                }
+            }
+        }
+        Ok(referenced_outside_target)
+    }
+
+    /// Convert collected edits to line/column edits, inserting new imports
+    /// after the existing ones
+    pub(super) fn text_edits(&self, sema: &Semantics<'_, RootDatabase>, files: HashMap<FileId, FileEdits>) -> Result<Vec<TextEdit>, AnalyzerError> {
+        let mut edits = Vec::new();
+        for (file_id, mut file_edits) in files {
+            file_edits.imports.sort();
+            file_edits.imports.dedup();
+            if !file_edits.imports.is_empty() {
+                let at = import_insertion_offset(&sema.parse_guess_edition(file_id));
+                let text = file_edits.imports.iter().map(|import| format!("{}\n", import)).collect::<String>();
+                file_edits.replacements.push((TextRange::empty(at), text));
+            }
+            file_edits.nested_imports.sort();
+            file_edits.nested_imports.dedup();
+            let mut nested = file_edits.nested_imports.into_iter().peekable();
+            while let Some((at, mut text)) = nested.next() {
+                while let Some((_, more)) = nested.next_if(|(next_at, _)| *next_at == at) {
+                    text.push_str(&more);
+                }
+                file_edits.replacements.push((TextRange::empty(at), text));
+            }
+
+            let file_path = self.file_path(file_id);
+            let line_index = self.host.analysis().file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            for (range, new_text) in file_edits.replacements {
+                let start = line_index.line_col(range.start());
+                let end = line_index.line_col(range.end());
+                edits.push(TextEdit {
+                    file_path: file_path.clone(),
+                    start_line: start.line,
+                    start_column: start.col,
+                    end_line: end.line,
+                    end_column: end.col,
+                    new_text,
+                });
+            }
+        }
+        edits.sort_by_key(|e| (e.file_path.clone(), e.start_line, e.start_column));
+        Ok(edits)
+    }
+
+    /// Locate a movable item by name, returning its file, syntax and definition
+    pub(super) fn find_movable_item(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        name: &str,
+        file_path: Option<&str>,
+    ) -> Result<(FileId, ast::Item, Definition), AnalyzerError> {
+        let files = match file_path {
+            Some(path) => vec![self.file_id(path)?],
+            None => self.workspace_files()?,
+        };
+        let item_name = name.rsplit("::").next().unwrap_or(name);
+
+        let mut found = Vec::new();
+        for file_id in files {
+            let source = sema.parse_guess_edition(file_id);
+            for item in source.syntax().descendants().filter_map(ast::Item::cast) {
+                // Only module-level items can be moved between modules
+                if !item.syntax().parent().is_some_and(|p| ast::SourceFile::can_cast(p.kind()) || ast::ItemList::can_cast(p.kind())) {
+                    continue;
+                }
+                let Some(item_name_node) = item_name_node(&item) else { continue };
+                if item_name_node.text() != item_name {
+                    continue;
+                }
+                let Some(def) = NameClass::classify(sema, &item_name_node).and_then(NameClass::defined) else { continue };
+                let matches_path = !name.contains("::")
+                    || super::reference_index::definition_key(sema.db, def).is_some_and(|key| key.ends_with(&format!("::{}", name)));
+                if matches_path {
+                    found.push((file_id, item, def));
+                }
+            }
+        }
+
+        match found.len() {
+            0 => Err(AnalyzerError::Other(format!("No movable item named '{}' found", name))),
+            1 => Ok(found.remove(0)),
+            n => Err(AnalyzerError::Other(format!(
+                "'{}' is ambiguous ({} items); pass file_path or a qualified name",
+                name, n
+            ))),
+        }
+    }
+}
+
+/// How to update one reference
+enum Rewrite {
+    /// Replace a range with new text
+    Replace(TextRange, String),
+    /// Remove a leaf of a nested `use` tree and import the item on its own,
+    /// with the given ` as Alias` suffix
+    RemoveUseLeaf(TextRange, String),
+    /// An unqualified reference that resolves because the item is in scope
+    InScope,
+    Unsupported(&'static str),
+}
+
+fn rewrite_reference(name_ref: &ast::NameRef, new_path: &str, in_target: bool) -> Rewrite {
+    let Some(segment) = name_ref.syntax().parent().and_then(ast::PathSegment::cast) else {
+        return Rewrite::Unsupported("a reference outside a path");
+    };
+    let path = segment.parent_path();
+
+    let Some(tree) = path.syntax().ancestors().find_map(ast::UseTree::cast) else {
+        // Outside `use`: rewrite qualified paths, leave in-scope names alone
+        return match path.qualifier() {
+            Some(_) => Rewrite::Replace(path.syntax().text_range(), new_path.to_string()),
+            None => Rewrite::InScope,
+        };
+    };
+
+    let alias = tree.rename().map(|r| format!(" {}", r.syntax().text())).unwrap_or_default();
+    let is_leaf = tree.path().as_ref() == Some(&path) && tree.use_tree_list().is_none() && tree.star_token().is_none();
+    let top_level = tree.syntax().parent().is_some_and(|p| ast::Use::can_cast(p.kind()));
+
+    if in_target {
+        // The item now lives here, so imports of it must go
+        return match (is_leaf, top_level) {
+            (true, true) => Rewrite::Replace(use_removal_range(&tree), String::new()),
+            (true, false) => Rewrite::RemoveUseLeaf(leaf_removal_range(&tree), alias),
+            _ => Rewrite::Unsupported("an import through the moved item"),
+        };
+    }
+    if top_level {
+        return Rewrite::Replace(path.syntax().text_range(), new_path.to_string());
+    }
+    if is_leaf {
+        return Rewrite::RemoveUseLeaf(leaf_removal_range(&tree), alias);
+    }
+    Rewrite::Unsupported("a nested import through the moved item")
+}
+
+/// Range of a whole `use` declaration including its trailing newline
+fn use_removal_range(tree: &ast::UseTree) -> TextRange {
+    let node = tree.syntax().parent().unwrap_or_else(|| tree.syntax().clone());
+    with_trailing_newline(&node)
+}
+
+/// Range of a leaf in a `{...}` list, together with one adjacent comma
+pub(super) fn leaf_removal_range(tree: &ast::UseTree) -> TextRange {
+    let range = tree.syntax().text_range();
+    let mut next = tree.syntax().next_sibling_or_token();
+    while let Some(element) = next.clone() {
+        match element.kind() {
+            SyntaxKind::WHITESPACE => next = element.next_sibling_or_token(),
+            SyntaxKind::COMMA => {
+                let mut end = element.text_range().end();
+                if let Some(ws) = element.next_sibling_or_token().filter(|e| e.kind() == SyntaxKind::WHITESPACE) {
+                    end = ws.text_range().end();
+                }
+                return TextRange::new(range.start(), end);
+            }
+            _ => break,
+        }
+    }
+    let mut prev = tree.syntax().prev_sibling_or_token();
+    while let Some(element) = prev.clone() {
+        match element.kind() {
+            SyntaxKind::WHITESPACE => prev = element.prev_sibling_or_token(),
+            SyntaxKind::COMMA => return TextRange::new(element.text_range().start(), range.end()),
+            _ => break,
+        }
+    }
+    range
+}
+
+/// Range of an item including the newline that ends its last line
+pub(super) fn removal_range(item: &ast::Item) -> TextRange {
+    with_trailing_newline(item.syntax())
+}
+
+fn with_trailing_newline(node: &SyntaxNode) -> TextRange {
+    let range = node.text_range();
+    match node.next_sibling_or_token() {
+        Some(ws) if ws.kind() == SyntaxKind::WHITESPACE => {
+            let text = ws.to_string();
+            let newline = text.find('\n').map_or(0, |i| i + 1);
+            TextRange::new(range.start(), range.end() + TextSize::from(newline as u32))
+        }
+        _ => range,
+    }
+}
+
+/// Where new `use` lines go: after the last top-level `use`, or at the top
+pub(super) fn import_insertion_offset(root: &ast::SourceFile) -> TextSize {
+    match root.items().filter_map(|item| match item {
+        ast::Item::Use(it) => Some(it),
+        _ => None,
+    }).last() {
+        Some(last) => with_trailing_newline(last.syntax()).end(),
+        None => root
+            .items()
+            .next()
+            .map(|first| first.syntax().text_range().start())
+            .unwrap_or_else(|| root.syntax().text_range().end()),
+    }
+}
+
+/// Where new `use` lines go inside an inline module, and their indentation:
+/// after its last `use`, or before its first item
+///
+/// `None` when the module's items don't start on lines of their own.
+fn nested_import_insertion(module: &ast::Module) -> Option<(TextSize, String)> {
+    let items = module.item_list()?;
+    let last_use = items.items().filter(|item| matches!(item, ast::Item::Use(_))).last();
+    let anchor = last_use.clone().or_else(|| items.items().next())?;
+    let whitespace = anchor.syntax().prev_sibling_or_token().filter(|e| e.kind() == SyntaxKind::WHITESPACE)?;
+    let whitespace = whitespace.to_string();
+    let indent = &whitespace[whitespace.rfind('\n')? + 1..];
+    let at = match last_use {
+        Some(last) => with_trailing_newline(last.syntax()).end(),
+        None => anchor.syntax().text_range().start() - TextSize::of(indent),
+    };
+    Some((at, indent.to_string()))
+}
+
+pub(super) fn item_name_node(item: &ast::Item) -> Option<ast::Name> {
+    match item {
+        ast::Item::Const(it) => it.name(),
+        ast::Item::Enum(it) => it.name(),
+        ast::Item::Fn(it) => it.name(),
+        ast::Item::Module(it) => it.name(),
+        ast::Item::Static(it) => it.name(),
+        ast::Item::Struct(it) => it.name(),
+        ast::Item::Trait(it) => it.name(),
+        ast::Item::TypeAlias(it) => it.name(),
+        ast::Item::Union(it) => it.name(),
+        _ => None,
+    }
+}
+
+pub(super) fn has_visibility(item: &ast::Item) -> bool {
+    let visibility = match item {
+        ast::Item::Const(it) => it.visibility(),
+        ast::Item::Enum(it) => it.visibility(),
+        ast::Item::Fn(it) => it.visibility(),
+        ast::Item::Module(it) => it.visibility(),
+        ast::Item::Static(it) => it.visibility(),
+        ast::Item::Struct(it) => it.visibility(),
+        ast::Item::Trait(it) => it.visibility(),
+        ast::Item::TypeAlias(it) => it.visibility(),
+        ast::Item::Union(it) => it.visibility(),
+        _ => None,
+    };
+    visibility.is_some()
+}
+
+/// Offset within the item text after its doc comments and attributes
+pub(super) fn visibility_insertion_offset(item: &ast::Item) -> usize {
+    let start = item.syntax().text_range().start();
+    item.syntax()
+        .children_with_tokens()
+        .find(|element| !matches!(element.kind(), SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE))
+        .map_or(0, |element| usize::from(element.text_range().start() - start))
+}
+
+/// The name a `use` tree leaf brings into scope; `None` for globs, `_`
+/// imports and nested lists
+fn bound_name(tree: &ast::UseTree) -> Option<String> {
+    if tree.use_tree_list().is_some() || tree.star_token().is_some() {
+        return None;
+    }
+    match tree.rename() {
+        Some(rename) => rename.name().map(|n| n.text().to_string()),
+        None => written_path(tree).rsplit("::").next().map(str::to_string),
+    }
+}
+
+/// Names the top-level `use` declarations of a file bring into scope
+pub(super) fn names_bound_by_imports(root: &ast::SourceFile) -> HashSet<String> {
+    root.items()
+        .filter_map(|item| match item {
+            ast::Item::Use(it) => Some(it),
+            _ => None,
+        })
+        .flat_map(|use_| use_.syntax().descendants().filter_map(ast::UseTree::cast).filter_map(|tree| bound_name(&tree)).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Top-level `use` declarations of the source file that the item relies on
+///
+/// A declaration is kept if any name it brings into scope appears as the
+/// first segment of a path inside the item and isn't in `already_bound`, the
+/// names the destination file imports itself. Of nested declarations only
+/// the leaves that are needed are kept, each as a `use` of its own.
+pub(super) fn imports_used_by(root: &ast::SourceFile, item: &ast::Item, already_bound: &HashSet<String>) -> Vec<String> {
+    let used: Vec<String> = item
+        .syntax()
+        .descendants()
+        .filter_map(ast::Path::cast)
+        .filter(|path| path.qualifier().is_none())
+        .filter_map(|path| path.segment()?.name_ref().map(|n| n.text().to_string()))
+        .collect();
+
+    let mut imports = Vec::new();
+    for use_ in root.items().filter_map(|item| match item {
+        ast::Item::Use(it) => Some(it),
+        _ => None,
+    }) {
+        let needed: Vec<_> = use_
+            .syntax()
+            .descendants()
+            .filter_map(ast::UseTree::cast)
+            .filter(|tree| bound_name(tree).is_some_and(|name| used.contains(&name) && !already_bound.contains(&name)))
+            .collect();
+        if needed.is_empty() {
+            continue;
+        }
+        if use_.use_tree().is_some_and(|tree| tree.use_tree_list().is_none()) {
+            imports.push(use_.syntax().text().to_string());
+        } else {
+            for tree in needed {
+                let alias = tree.rename().map(|r| format!(" {}", r.syntax().text())).unwrap_or_default();
+                imports.push(format!("use {}{};", written_path(&tree), alias));
+            }
+        }
+    }
+    imports
+}
+
+/// Items of the source module the moved item refers to by bare name, and
+/// whether each is restricted to less than public visibility
+pub(super) fn sibling_dependencies(sema: &Semantics<'_, RootDatabase>, item: &ast::Item, moved: &[Definition], source_module: Module) -> Vec<(String, bool)> {
+    let db = sema.db;
+    let mut siblings = Vec::new();
+    for path in item.syntax().descendants().filter_map(ast::Path::cast) {
+        if path.qualifier().is_some() {
+            continue;
+        }
+        let Some(name_ref) = path.segment().and_then(|s| s.name_ref()) else { continue };
+        let def = match NameRefClass::classify(sema, &name_ref) {
+            Some(NameRefClass::Definition(def, ..)) => def,
+            _ => continue,
+        };
+        if moved.contains(&def) || matches!(def, Definition::Local(_) | Definition::GenericParam(_) | Definition::SelfType(_)) {
+            continue;
+        }
+        // Only items declared directly in the source module, not imports into it
+        let declared_here = def.module(db) == Some(source_module)
+            && !matches!(def, Definition::Module(m) if m.parent(db) != Some(source_module));
+        if !declared_here {
+            continue;
+        }
+        let name = name_ref.text().to_string();
+        let private = !matches!(def.visibility(db), Some(Visibility::Public) | None);
+        if !siblings.iter().any(|(n, _)| n == &name) {
+            siblings.push((name, private));
+        }
+    }
+    siblings
+}
+
+/// `crate::a::b` style path of a module
+pub(super) fn module_path(db: &RootDatabase, module: Module) -> String {
+    let mut segments = vec!["crate".to_string()];
+    segments.extend(
+        module
+            .path_to_root(db)
+            .into_iter()
+            .rev()
+            .filter_map(|m| m.name(db))
+            .map(|n| n.as_str().to_string()),
+    );
+    segments.join("::")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn parse(src: &str) -> SourceFile {
+        SourceFile::parse(src, Edition::CURRENT).tree()
+    }
+
+    #[test]
+    fn test_leaf_removal_takes_one_comma() {
+        let src = "use a::{b::Item, c};";
+        let file = parse(src);
+        let leaf = file.syntax().descendants().filter_map(ast::UseTree::cast).find(|t| t.syntax().text() == "b::Item").unwrap();
+        let range = leaf_removal_range(&leaf);
+
+        let mut edited = src.to_string();
+        edited.replace_range(std::ops::Range::<usize>::from(range), "");
+        assert_eq!(edited, "use a::{c};");
+    }
+
+    #[test]
+    fn test_rewrite_kinds() {
+        let file = parse("use a::Item;\nuse b::{Item as Other, x};\nfn f() { a::Item::new(); Item; }");
+        let refs: Vec<_> = file.syntax().descendants().filter_map(ast::NameRef::cast).filter(|n| n.text() == "Item").collect();
+
+        let kinds: Vec<_> = refs
+            .iter()
+            .map(|name_ref| match rewrite_reference(name_ref, "crate::target::Item", false) {
+                Rewrite::Replace(_, text) => format!("replace:{}", text),
+                Rewrite::RemoveUseLeaf(_, alias) => format!("remove-leaf:{}", alias),
+                Rewrite::InScope => "in-scope".to_string(),
+                Rewrite::Unsupported(what) => what.to_string(),
+            })
+            .collect();
+
+        assert_eq!(
+            kinds,
+            vec![
+                "replace:crate::target::Item",
+                "remove-leaf: as Other",
+                "replace:crate::target::Item",
+                "in-scope",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_imports_used_by_item() {
+        let file = parse("use std::collections::HashMap;\nuse std::fmt::Display as Show;\nuse std::io;\nfn f(m: HashMap<u8, u8>) -> impl Show { todo!() }");
+        let item = file.items().find(|i| matches!(i, ast::Item::Fn(_))).unwrap();
+        assert_eq!(
+            imports_used_by(&file, &item, &HashSet::new()),
+            vec!["use std::collections::HashMap;", "use std::fmt::Display as Show;"]
+        );
+    }
+
+    #[test]
+    fn test_imports_already_bound_are_skipped() {
+        let file = parse("use std::collections::{HashMap, HashSet, BTreeMap};\nuse std::io;\nfn f(m: HashMap<u8, u8>, s: HashSet<u8>) -> io::Result<()> { todo!() }");
+        let item = file.items().find(|i| matches!(i, ast::Item::Fn(_))).unwrap();
+        let target = parse("use std::collections::HashMap;\n");
+        assert_eq!(
+            imports_used_by(&file, &item, &names_bound_by_imports(&target)),
+            vec!["use std::collections::HashSet;", "use std::io;"]
+        );
+    }
+
+    #[test]
+    fn test_visibility_goes_after_attributes() {
+        let file = parse("/// Docs\n#[inline]\nfn f() {}");
+        let item = file.items().next().unwrap();
+        let mut text = item.syntax().text().to_string();
+        text.insert_str(visibility_insertion_offset(&item), "pub(crate) ");
+        assert_eq!(text, "/// Docs\n#[inline]\npub(crate) fn f() {}");
+    }
+
+    #[test]
+    fn test_plan_move_within_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let target = std::fs::canonicalize("src/analyzer/monikers.rs").unwrap();
+        let plan = analyzer.plan_move_item("stable_hash", None, target.to_str().unwrap()).unwrap();
+
+        assert_eq!(plan.from_module, "crate::analyzer");
+        assert_eq!(plan.to_module, "crate::analyzer::monikers");
+        assert!(plan.edits.iter().any(|e| e.file_path.ends_with("monikers.rs") && e.new_text.contains("fn stable_hash")));
+        assert!(plan.edits.iter().any(|e| e.file_path.ends_with("analyzer.rs") && e.new_text.is_empty()));
+        // `mod tests` sees stable_hash through `use super::*` and needs its own import
+        assert!(plan
+            .edits
+            .iter()
+            .any(|e| e.file_path.ends_with("src/analyzer.rs") && e.new_text == "    use crate::analyzer::monikers::stable_hash;\n"));
+    }
+}
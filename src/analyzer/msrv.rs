@@ -0,0 +1,291 @@
+//! Edition and MSRV-relevant usage
+//!
+//! Reports each workspace crate's edition and the language features and
+//! standard library items it uses that raise its minimum supported Rust
+//! version: syntax such as let-else or async functions in traits, found in
+//! the syntax tree, and library items such as `OnceLock` or
+//! `Option::is_some_and`, found by resolving names. Only a fixed table of
+//! notable stabilizations is checked, so the result is a lower bound for the
+//! MSRV rather than the exact figure.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{AssocItemContainer, Crate, Semantics};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::{Definition, NameRefClass};
+use ra_ap_project_model::ProjectWorkspaceKind;
+use ra_ap_syntax::{ast, AstNode, AstToken, SyntaxNode};
+use std::collections::HashMap;
+
+/// Library items and the release that stabilized them: name, the type or
+/// trait owning it (for methods), and version
+const LIBRARY_ITEMS: &[(&str, Option<&str>, (u32, u32))] = &[
+    ("scope", None, (1, 63)),
+    ("black_box", None, (1, 66)),
+    ("OnceCell", None, (1, 70)),
+    ("OnceLock", None, (1, 70)),
+    ("IsTerminal", None, (1, 70)),
+    ("is_some_and", Some("Option"), (1, 70)),
+    ("is_ok_and", Some("Result"), (1, 70)),
+    ("is_err_and", Some("Result"), (1, 70)),
+    ("inspect_err", Some("Result"), (1, 76)),
+    ("NonZero", None, (1, 79)),
+    ("LazyCell", None, (1, 80)),
+    ("LazyLock", None, (1, 80)),
+    ("is_none_or", Some("Option"), (1, 82)),
+    ("repeat_n", None, (1, 82)),
+    ("get_or_insert_default", Some("Option"), (1, 83)),
+    ("AsyncFn", None, (1, 85)),
+    ("pop_if", Some("Vec"), (1, 86)),
+];
+
+/// Where a crate uses one MSRV-relevant feature
+#[derive(Debug, Clone)]
+pub struct MsrvFeature {
+    /// e.g. `let-else`, `OnceLock` or `Option::is_some_and`
+    pub feature: String,
+    /// The release that stabilized it, e.g. `1.65`
+    pub version: String,
+    /// `(file_path, line)` of each use (line 0-based)
+    pub uses: Vec<(String, u32)>,
+}
+
+/// The edition and MSRV-relevant usage of one crate
+#[derive(Debug, Clone)]
+pub struct CrateMsrv {
+    pub crate_name: String,
+    pub edition: String,
+    /// The `rust-version` declared in the crate's Cargo.toml, if any
+    pub rust_version: Option<String>,
+    /// The highest version among the features used: a lower bound for the MSRV
+    pub required: Option<String>,
+    /// Features used, newest first
+    pub features: Vec<MsrvFeature>,
+}
+
+impl Analyzer {
+    /// Report each workspace crate's edition and the features it uses that
+    /// were stabilized after `min_version`, e.g. `1.70` (all of them when `None`)
+    pub fn msrv_report(&self, min_version: Option<&str>) -> Result<Vec<CrateMsrv>, AnalyzerError> {
+        let min_version = match min_version {
+            Some(version) => Some(
+                parse_version(version).ok_or_else(|| AnalyzerError::Other(format!("Invalid Rust version '{}'", version)))?,
+            ),
+            None => None,
+        };
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut crates: HashMap<Crate, HashMap<(String, (u32, u32)), Vec<(String, u32)>>> = HashMap::new();
+        for file_id in self.workspace_files()? {
+            let Some(module) = sema.file_to_module_def(file_id) else { continue };
+            let features = crates.entry(module.krate()).or_default();
+            let source_file = sema.parse_guess_edition(file_id);
+
+            let mut found = syntax_features(source_file.syntax());
+            for name_ref in source_file.syntax().descendants().filter_map(ast::NameRef::cast) {
+                let Some(NameRefClass::Definition(def, ..)) = NameRefClass::classify(&sema, &name_ref) else { continue };
+                if let Some((feature, version)) = library_feature(db, def) {
+                    found.push((feature, version, name_ref.syntax().clone()));
+                }
+            }
+
+            for (feature, version, node) in found {
+                if min_version.is_some_and(|min| version <= min) {
+                    continue;
+                }
+                let line = self.line_range(file_id, node.text_range())?.0;
+                features.entry((feature, version)).or_default().push((self.file_path(file_id), line));
+            }
+        }
+
+        let mut reports: Vec<CrateMsrv> = crates
+            .into_iter()
+            .map(|(krate, features)| {
+                let mut features: Vec<_> = features.into_iter().collect();
+                features.sort_by(|((a, a_version), _), ((b, b_version), _)| b_version.cmp(a_version).then(a.cmp(b)));
+                let features: Vec<MsrvFeature> = features
+                    .into_iter()
+                    .map(|((feature, version), mut uses)| {
+                        uses.sort();
+                        uses.dedup();
+                        MsrvFeature { feature, version: format_version(version), uses }
+                    })
+                    .collect();
+                CrateMsrv {
+                    crate_name: krate.display_name(db).map(|n| n.to_string()).unwrap_or_default(),
+                    edition: krate.edition(db).to_string(),
+                    rust_version: self.declared_rust_version(krate),
+                    required: features.first().map(|f| f.version.clone()),
+                    features,
+                }
+            })
+            .collect();
+        reports.sort_by(|a, b| a.crate_name.cmp(&b.crate_name));
+        Ok(reports)
+    }
+
+    /// The `rust-version` cargo reports for the package holding a crate's
+    /// root, which covers versions inherited from the workspace
+    fn declared_rust_version(&self, krate: Crate) -> Option<String> {
+        let workspace = self.workspace.as_ref()?;
+        let ProjectWorkspaceKind::Cargo { cargo, .. } = &workspace.kind else { return None };
+        let root = self.file_path(krate.root_file(self.host.raw_database()));
+        let package = cargo
+            .packages()
+            .find(|&package| cargo[package].targets.iter().any(|&target| cargo[target].root.as_str() == root))?;
+        let version = cargo[package].rust_version.as_ref()?;
+        // Cargo fills in a patch of 0 for the usual `major.minor`
+        Some(if version.patch == 0 { format!("{}.{}", version.major, version.minor) } else { version.to_string() })
+    }
+}
+
+/// The syntax in a file that was stabilized after Rust 1.56 (edition 2021),
+/// with the node using it
+fn syntax_features(root: &SyntaxNode) -> Vec<(String, (u32, u32), SyntaxNode)> {
+    let mut found = Vec::new();
+    let mut add = |feature: &str, version, node: &SyntaxNode| found.push((feature.to_string(), version, node.clone()));
+    for node in root.descendants() {
+        let in_trait = node
+            .parent()
+            .and_then(ast::AssocItemList::cast)
+            .and_then(|list| list.syntax().parent())
+            .is_some_and(|parent| ast::Trait::can_cast(parent.kind()));
+
+        if let Some(let_stmt) = ast::LetStmt::cast(node.clone()) {
+            if let_stmt.let_else().is_some() {
+                add("let-else", (1, 65), &node);
+            }
+        } else if let Some(let_expr) = ast::LetExpr::cast(node.clone()) {
+            let chained = let_expr.syntax().parent().and_then(ast::BinExpr::cast).is_some_and(|bin| {
+                bin.op_kind() == Some(ast::BinaryOp::LogicOp(ast::LogicOp::And))
+            });
+            if chained {
+                add("let chains (edition 2024)", (1, 88), &node);
+            }
+        } else if let Some(func) = ast::Fn::cast(node.clone()) {
+            if in_trait && func.async_token().is_some() {
+                add("async fn in trait", (1, 75), &node);
+            }
+            if in_trait && matches!(func.ret_type().and_then(|ret| ret.ty()), Some(ast::Type::ImplTraitType(_))) {
+                add("impl Trait return in trait", (1, 75), &node);
+            }
+        } else if let Some(alias) = ast::TypeAlias::cast(node.clone()) {
+            if in_trait && alias.generic_param_list().is_some() {
+                add("generic associated types", (1, 65), &node);
+            }
+        } else if let Some(closure) = ast::ClosureExpr::cast(node.clone()) {
+            if closure.async_token().is_some() {
+                add("async closures", (1, 85), &node);
+            }
+        } else if let Some(block) = ast::BlockExpr::cast(node.clone()) {
+            if block.const_token().is_some() {
+                add("inline const blocks", (1, 79), &node);
+            }
+            if block.label().is_some() {
+                add("labeled block break", (1, 65), &node);
+            }
+        } else if let Some(ref_expr) = ast::RefExpr::cast(node.clone()) {
+            if ref_expr.raw_token().is_some() {
+                add("&raw pointers", (1, 82), &node);
+            }
+        } else if let Some(extern_block) = ast::ExternBlock::cast(node.clone()) {
+            if extern_block.unsafe_token().is_some() {
+                add("unsafe extern blocks", (1, 82), &node);
+            }
+        }
+    }
+
+    for token in root.descendants_with_tokens().filter_map(|e| e.into_token()) {
+        if let Some(c_string) = ast::CString::cast(token) {
+            if let Some(parent) = c_string.syntax().parent() {
+                add("C string literals", (1, 77), &parent);
+            }
+        }
+    }
+    found
+}
+
+/// The table entry a standard library definition matches, as a feature name
+/// such as `Option::is_some_and`
+fn library_feature(db: &RootDatabase, def: Definition) -> Option<(String, (u32, u32))> {
+    if !def.krate(db)?.origin(db).is_lang() {
+        return None;
+    }
+    let name = def.name(db)?.as_str().to_string();
+    let owner = match def {
+        Definition::Function(func) => match func.as_assoc_item(db).map(|item| item.container(db)) {
+            Some(AssocItemContainer::Impl(impl_)) => impl_.self_ty(db).as_adt().map(|adt| adt.name(db).as_str().to_string()),
+            Some(AssocItemContainer::Trait(trait_)) => Some(trait_.name(db).as_str().to_string()),
+            None => None,
+        },
+        _ => None,
+    };
+
+    LIBRARY_ITEMS.iter().find_map(|&(item, item_owner, version)| {
+        (item == name && item_owner == owner.as_deref()).then(|| match item_owner {
+            Some(owner) => (format!("{}::{}", owner, item), version),
+            None => (item.to_string(), version),
+        })
+    })
+}
+
+fn format_version((major, minor): (u32, u32)) -> String {
+    format!("{}.{}", major, minor)
+}
+
+/// Parse a `major.minor` version, ignoring any patch part
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.trim().split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().map_or(Some(0), |minor| minor.parse().ok())?;
+    Some((major, minor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    #[test]
+    fn test_syntax_features() {
+        let source = "
+            trait Store { async fn load(&self); type Item<'a>; }
+            fn f(x: Option<u8>) { let Some(y) = x else { return }; }
+        ";
+        let parse = SourceFile::parse(source, Edition::CURRENT);
+        let features: Vec<_> = syntax_features(parse.tree().syntax()).into_iter().map(|(f, v, _)| (f, v)).collect();
+        assert!(features.contains(&("let-else".to_string(), (1, 65))));
+        assert!(features.contains(&("async fn in trait".to_string(), (1, 75))));
+        assert!(features.contains(&("generic associated types".to_string(), (1, 65))));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.70"), Some((1, 70)));
+        assert_eq!(parse_version("1.65.0"), Some((1, 65)));
+        assert_eq!(parse_version("1"), Some((1, 0)));
+        assert_eq!(parse_version("one"), None);
+    }
+
+    #[test]
+    fn test_msrv_report_of_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let reports = analyzer.msrv_report(None).unwrap();
+        let report = reports.iter().find(|r| r.crate_name == "cratographer").unwrap();
+        assert_eq!(report.edition, "2021");
+        // Cargo.toml declares no rust-version
+        assert_eq!(report.rust_version, None);
+        // This crate uses let-else and `Option::is_none_or`
+        assert!(report.features.iter().any(|f| f.feature == "let-else"));
+        assert!(report.features.iter().any(|f| f.feature == "Option::is_none_or"));
+        assert!(parse_version(report.required.as_deref().unwrap()) >= Some((1, 82)));
+
+        // Filtering drops the older features
+        let reports = analyzer.msrv_report(Some("1.70")).unwrap();
+        let report = reports.iter().find(|r| r.crate_name == "cratographer").unwrap();
+        assert!(report.features.iter().all(|f| parse_version(&f.version) > Some((1, 70))));
+        assert!(analyzer.msrv_report(Some("latest")).is_err());
+    }
+}
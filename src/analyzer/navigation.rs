@@ -0,0 +1,135 @@
+//! Position-based navigation
+//!
+//! Resolves a file position, such as one taken from a compiler error, straight
+//! to the symbol it names or to the definition of its type, without first
+//! knowing any names.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{Analysis, NavigationTarget};
+
+/// Where a navigation lands
+///
+/// Unlike `SymbolInfo` this covers every kind of definition, including
+/// fields, variants, locals and parameters.
+#[derive(Debug, Clone)]
+pub struct NavigationResult {
+    pub name: String,
+    /// rust-analyzer's symbol kind, e.g. "Struct", "Field" or "Local"
+    pub kind: String,
+    /// Name of the enclosing item, e.g. the impl's type for a method
+    pub container: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Position of the definition's name (0-based)
+    pub line: u32,
+    pub column: u32,
+    pub documentation: Option<String>,
+    pub crate_name: Option<String>,
+    pub crate_version: Option<String>,
+    /// Stable moniker of the definition, as for `resolve_moniker`; `None`
+    /// for locals and parameters
+    pub moniker: Option<String>,
+}
+
+impl Analyzer {
+    /// Resolve the symbol at a position (0-based) to its definitions
+    ///
+    /// Usually a single definition; more for items defined under several
+    /// `cfg`s or names bound in several patterns.
+    pub fn goto_definition(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<NavigationResult>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+        let targets = analysis.goto_definition(position).map_err(|_| AnalyzerError::Canceled)?;
+        self.navigation_results(&analysis, targets.map(|t| t.info).unwrap_or_default())
+    }
+
+    /// Resolve the expression or binding at a position (0-based) to the
+    /// definitions of its type
+    ///
+    /// Several targets come back for types built from more than one
+    /// definition, e.g. `Option<Foo>` yields both `Option` and `Foo`.
+    pub fn goto_type_definition(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<NavigationResult>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+        let targets = analysis.goto_type_definition(position).map_err(|_| AnalyzerError::Canceled)?;
+        self.navigation_results(&analysis, targets.map(|t| t.info).unwrap_or_default())
+    }
+
+    fn navigation_results(&self, analysis: &Analysis, navs: Vec<NavigationTarget>) -> Result<Vec<NavigationResult>, AnalyzerError> {
+        let crates = self.crates_by_source_root()?;
+        let sema = Semantics::new(self.host.raw_database());
+        let mut results = Vec::new();
+
+        for nav in navs {
+            let line_index = analysis.file_line_index(nav.file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let start = line_index.line_col(nav.full_range.start());
+            let end = line_index.line_col(nav.full_range.end());
+            let focus = line_index.line_col(nav.focus_or_full_range().start());
+            let (crate_name, crate_version) = analysis
+                .source_root_id(nav.file_id)
+                .ok()
+                .and_then(|root| crates.get(&root).cloned())
+                .unwrap_or_default();
+
+            results.push(NavigationResult {
+                name: nav.name.to_string(),
+                kind: nav.kind.map(|kind| format!("{:?}", kind)).unwrap_or_else(|| "Unknown".to_string()),
+                container: nav.container_name.as_ref().map(|c| c.to_string()),
+                file_path: self.file_path(nav.file_id),
+                start_line: start.line,
+                end_line: end.line,
+                line: focus.line,
+                column: focus.col,
+                documentation: nav.docs.as_ref().map(|d| d.as_str().to_string()),
+                crate_name,
+                crate_version,
+                moniker: self.moniker_at(&sema, nav.file_id, nav.focus_or_full_range().start()),
+            });
+        }
+
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_goto_definition_of_import() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `use reference_index::ReferenceIndex;` in analyzer.rs
+        let path = std::fs::canonicalize("src/analyzer.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l == "use reference_index::ReferenceIndex;").unwrap() as u32;
+        let column = "use reference_index::".len() as u32;
+
+        let targets = analyzer.goto_definition(path, line, column).unwrap();
+        assert_eq!(targets.len(), 1);
+        assert_eq!(targets[0].name, "ReferenceIndex");
+        assert_eq!(targets[0].kind, "Struct");
+        assert!(targets[0].file_path.ends_with("reference_index.rs"));
+        assert!(targets[0].moniker.as_ref().unwrap().ends_with("::analyzer::reference_index::ReferenceIndex#struct"));
+    }
+
+    #[test]
+    fn test_goto_type_definition_of_binding() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `let workspace: HashSet<_> = ...` in references.rs
+        let path = std::fs::canonicalize("src/analyzer/references.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.trim_start().starts_with("let workspace: HashSet<_>")).unwrap() as u32;
+        let column = text.lines().nth(line as usize).unwrap().find("workspace").unwrap() as u32;
+
+        let targets = analyzer.goto_type_definition(path, line, column).unwrap();
+        assert!(targets.iter().any(|t| t.name == "HashSet" && t.kind == "Struct"));
+    }
+}
@@ -0,0 +1,211 @@
+//! Traits that can't be used as `dyn Trait`
+//!
+//! A trait is object-safe (dyn-compatible) only if every method can be
+//! called through a vtable: no generic methods, no `Self` in argument or
+//! return position, a `self` receiver, and so on, unless the method opts out
+//! with `where Self: Sized`. Adding one offending method to a trait that is
+//! boxed all over the code base breaks every `dyn` use at once, so this
+//! reports the workspace traits used as `dyn Trait` that are not object-safe,
+//! with each violation pointing at the method or item at fault. The
+//! violations come from rust-analyzer's own dyn-compatibility check.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::{
+    Const, DynCompatibilityViolation, Function, MethodViolationCode, ModuleDef, PathResolution, Semantics, Trait,
+    TypeAlias,
+};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{ast, AstNode, SyntaxNode};
+use std::collections::HashMap;
+
+/// One reason a trait is not object-safe
+#[derive(Debug, Clone)]
+pub struct SafetyViolation {
+    /// Name of the offending method, associated item or supertrait; the
+    /// trait's own name for trait-level violations
+    pub item: String,
+    pub reason: String,
+    pub file_path: String,
+    pub line: u32,
+}
+
+/// A trait that is not object-safe
+#[derive(Debug, Clone)]
+pub struct UnsafeTrait {
+    /// Qualified path of the trait
+    pub trait_path: String,
+    pub file_path: String,
+    pub line: u32,
+    pub violations: Vec<SafetyViolation>,
+    /// `(file_path, line)` of each `dyn Trait` naming it in the workspace
+    pub dyn_uses: Vec<(String, u32)>,
+}
+
+impl Analyzer {
+    /// List the traits used as `dyn Trait` in the workspace that are not
+    /// object-safe, or check one trait by name, qualified path or symbol_id
+    /// whether or not it is used as `dyn`
+    ///
+    /// A checked trait that is object-safe yields an empty list. Fails with
+    /// `AnalyzerError::Ambiguous` if several traits match `trait_name`.
+    pub fn object_safety(&self, trait_name: Option<&str>) -> Result<Vec<UnsafeTrait>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let mut dyn_uses: HashMap<Trait, Vec<(String, u32)>> = HashMap::new();
+        for file_id in self.workspace_files()? {
+            let source = sema.parse_guess_edition(file_id);
+            for dyn_type in source.syntax().descendants().filter_map(ast::DynTraitType::cast) {
+                let bounds = dyn_type.type_bound_list().into_iter().flat_map(|list| list.bounds());
+                for bound in bounds {
+                    let Some(ast::Type::PathType(path_type)) = bound.ty() else { continue };
+                    let Some(path) = path_type.path() else { continue };
+                    let Some(PathResolution::Def(ModuleDef::Trait(trait_))) = sema.resolve_path(&path) else { continue };
+                    let line = self.line_range(file_id, dyn_type.syntax().text_range())?.0;
+                    dyn_uses.entry(trait_).or_default().push((self.file_path(file_id), line));
+                }
+            }
+        }
+
+        let traits: Vec<Trait> = match trait_name {
+            Some(name) => {
+                let traits: Vec<Trait> = self.resolve_definitions(&sema, name)?
+                    .into_iter()
+                    .filter_map(|def| match def {
+                        Definition::Trait(trait_) => Some(trait_),
+                        _ => None,
+                    })
+                    .collect();
+                if traits.is_empty() {
+                    return Err(AnalyzerError::Other(format!("No trait named '{}' found", name)));
+                }
+                self.require_unique(&sema, name, &traits.iter().map(|&t| Definition::Trait(t)).collect::<Vec<_>>())?;
+                traits
+            }
+            None => dyn_uses.keys().copied().filter(|trait_| trait_.module(db).krate().origin(db).is_local()).collect(),
+        };
+
+        let mut unsafe_traits = Vec::new();
+        for trait_ in traits {
+            let Some(violations) = trait_.dyn_compatibility_all_violations(db) else { continue };
+            let Some(source) = sema.source(trait_) else { continue };
+            let (file_path, line) = self.node_line(&sema, source.value.syntax())?;
+            let trait_path = definition_key(db, Definition::Trait(trait_)).unwrap_or_else(|| trait_.name(db).as_str().to_string());
+
+            let mut reported = Vec::new();
+            for violation in violations {
+                let Some(violation) = self.violation(&sema, trait_, violation)? else { continue };
+                reported.push(violation);
+            }
+            let mut uses = dyn_uses.remove(&trait_).unwrap_or_default();
+            uses.sort();
+            uses.dedup();
+            unsafe_traits.push(UnsafeTrait { trait_path, file_path, line, violations: reported, dyn_uses: uses });
+        }
+
+        unsafe_traits.sort_by(|a, b| a.trait_path.cmp(&b.trait_path));
+        Ok(unsafe_traits)
+    }
+
+    /// Describe a violation, located at the item at fault
+    fn violation(
+        &self,
+        sema: &Semantics<'_, RootDatabase>,
+        trait_: Trait,
+        violation: DynCompatibilityViolation,
+    ) -> Result<Option<SafetyViolation>, AnalyzerError> {
+        let db = sema.db;
+        let trait_name = trait_.name(db).as_str().to_string();
+        let (item, reason, node) = match violation {
+            DynCompatibilityViolation::SizedSelf => {
+                (trait_name, "requires `Self: Sized`".to_string(), sema.source(trait_).map(|s| s.value.syntax().clone()))
+            }
+            DynCompatibilityViolation::SelfReferential => (
+                trait_name,
+                "a supertrait or where clause uses `Self` as a type argument, e.g. `PartialEq`".to_string(),
+                sema.source(trait_).map(|s| s.value.syntax().clone()),
+            ),
+            DynCompatibilityViolation::Method(id, code) => {
+                let func = Function::from(id);
+                let reason = match code {
+                    MethodViolationCode::StaticMethod => "has no `self` receiver",
+                    MethodViolationCode::ReferencesSelfInput => "takes `Self` as an argument",
+                    MethodViolationCode::ReferencesSelfOutput => "returns `Self`",
+                    MethodViolationCode::ReferencesImplTraitInTrait => "returns `impl Trait`",
+                    MethodViolationCode::AsyncFn => "is `async`",
+                    MethodViolationCode::WhereClauseReferencesSelf => "has a where clause bounding `Self`",
+                    MethodViolationCode::Generic => "has type parameters",
+                    MethodViolationCode::UndispatchableReceiver => "has a receiver type that can't be dispatched on",
+                };
+                (
+                    func.name(db).as_str().to_string(),
+                    format!("method {}; add `where Self: Sized` to keep it off the vtable", reason),
+                    sema.source(func).map(|s| s.value.syntax().clone()),
+                )
+            }
+            DynCompatibilityViolation::AssocConst(id) => {
+                let konst = Const::from(id);
+                (
+                    konst.name(db).map(|n| n.as_str().to_string()).unwrap_or_default(),
+                    "associated constant".to_string(),
+                    sema.source(konst).map(|s| s.value.syntax().clone()),
+                )
+            }
+            DynCompatibilityViolation::GAT(id) => {
+                let alias = TypeAlias::from(id);
+                (
+                    alias.name(db).as_str().to_string(),
+                    "generic associated type".to_string(),
+                    sema.source(alias).map(|s| s.value.syntax().clone()),
+                )
+            }
+            DynCompatibilityViolation::HasNonCompatibleSuperTrait(id) => {
+                let supertrait = Trait::from(id);
+                (
+                    supertrait.name(db).as_str().to_string(),
+                    "supertrait is not object-safe".to_string(),
+                    sema.source(supertrait).map(|s| s.value.syntax().clone()),
+                )
+            }
+        };
+
+        let Some(node) = node else { return Ok(None) };
+        let (file_path, line) = self.node_line(sema, &node)?;
+        Ok(Some(SafetyViolation { item, reason, file_path, line }))
+    }
+
+    /// File and first line of a node from `Semantics::source`
+    fn node_line(&self, sema: &Semantics<'_, RootDatabase>, node: &SyntaxNode) -> Result<(String, u32), AnalyzerError> {
+        let range = sema.original_range(node);
+        let file_id = range.file_id.file_id(sema.db);
+        Ok((self.file_path(file_id), self.line_range(file_id, range.range)?.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_object_safe_trait_has_no_violations() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `SymbolProvider` is boxed as `dyn SymbolProvider`, so must be object-safe
+        assert!(analyzer.object_safety(Some("SymbolProvider")).unwrap().is_empty());
+        assert!(analyzer.object_safety(None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_unsafe_library_trait() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `Clone` requires `Self: Sized`
+        let traits = analyzer.object_safety(Some("core::clone::Clone")).unwrap();
+        assert_eq!(traits.len(), 1);
+        assert!(traits[0].violations.iter().any(|v| v.reason.contains("Sized")));
+    }
+}
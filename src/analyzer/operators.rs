@@ -0,0 +1,170 @@
+//! Operator and Deref impl discovery
+//!
+//! Tells whether `a + b`, `a[i]`, `a == b` or `format!("{}", a)` is valid for
+//! a type and what it resolves to: the operator-trait impls with the type as
+//! `Self`, their right-hand side and `Output` types, and the chain of types
+//! auto-deref walks through, whose methods and operators apply as well.
+
+use super::conversions::assoc_type;
+use super::reference_index::definition_key;
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, Impl, Semantics};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::AstNode;
+
+/// Maximum number of steps followed along the deref chain
+const MAX_DEREF_STEPS: usize = 8;
+
+/// Operator traits with the syntax they enable
+const OPERATOR_TRAITS: &[(&str, &str)] = &[
+    ("ops::Add", "a + b"),
+    ("ops::Sub", "a - b"),
+    ("ops::Mul", "a * b"),
+    ("ops::Div", "a / b"),
+    ("ops::Rem", "a % b"),
+    ("ops::Neg", "-a"),
+    ("ops::Not", "!a"),
+    ("ops::BitAnd", "a & b"),
+    ("ops::BitOr", "a | b"),
+    ("ops::BitXor", "a ^ b"),
+    ("ops::Shl", "a << b"),
+    ("ops::Shr", "a >> b"),
+    ("ops::AddAssign", "a += b"),
+    ("ops::SubAssign", "a -= b"),
+    ("ops::MulAssign", "a *= b"),
+    ("ops::DivAssign", "a /= b"),
+    ("ops::RemAssign", "a %= b"),
+    ("ops::Index", "a[i]"),
+    ("ops::IndexMut", "a[i] = v"),
+    ("ops::Deref", "*a"),
+    ("ops::DerefMut", "*a = v"),
+    ("cmp::PartialEq", "a == b"),
+    ("cmp::Eq", "a == b (total)"),
+    ("cmp::PartialOrd", "a < b"),
+    ("cmp::Ord", "a.cmp(&b)"),
+    ("fmt::Display", "format!(\"{}\", a)"),
+    ("fmt::Debug", "format!(\"{:?}\", a)"),
+];
+
+/// An operator trait implemented for the type
+#[derive(Debug, Clone)]
+pub struct OperatorImpl {
+    /// The trait, e.g. "Add" or "PartialEq"
+    pub trait_name: String,
+    /// The syntax it enables, e.g. "a + b"
+    pub operator: &'static str,
+    /// Right-hand side (or index) type, for binary operators
+    pub rhs: Option<String>,
+    /// What the operation evaluates to, from the impl's `Output` or `Target`
+    pub output: Option<String>,
+    /// Whether the impl comes from a macro such as `#[derive]`
+    pub derived: bool,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Operators and deref behavior of a type
+#[derive(Debug, Clone)]
+pub struct TypeOperators {
+    pub type_path: String,
+    pub operators: Vec<OperatorImpl>,
+    /// Types reached by repeated auto-deref, in order, excluding the type itself
+    pub deref_chain: Vec<String>,
+}
+
+impl Analyzer {
+    /// Report the operator-trait impls and the deref chain of a type
+    pub fn type_operators(&self, type_name: &str) -> Result<TypeOperators, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let adts: Vec<Adt> = self.resolve_definitions(&sema, type_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Adt(adt) => Some(adt),
+                _ => None,
+            })
+            .collect();
+        let Some(&adt) = adts.first() else {
+            return Err(AnalyzerError::Other(format!("No type named '{}' found", type_name)));
+        };
+        self.require_unique(&sema, type_name, &adts.iter().map(|&a| Definition::Adt(a)).collect::<Vec<_>>())?;
+        let krate = adt.module(db).krate();
+
+        let mut operators = Vec::new();
+        for (path, operator) in OPERATOR_TRAITS {
+            let trait_name = path.rsplit("::").next().unwrap_or(path);
+            let traits = self.resolve_definitions(&sema, path)?.into_iter().filter_map(|def| match def {
+                Definition::Trait(trait_) if definition_key(db, def).is_some_and(|key| key.starts_with("core::")) => Some(trait_),
+                _ => None,
+            });
+
+            for trait_ in traits {
+                for impl_ in Impl::all_for_trait(db, trait_) {
+                    if impl_.self_ty(db).strip_references().as_adt() != Some(adt) {
+                        continue;
+                    }
+                    let Some(source) = sema.source(impl_) else { continue };
+                    let range = sema.original_range(source.value.syntax());
+                    let file_id = range.file_id.file_id(db);
+                    let (start_line, end_line) = self.line_range(file_id, range.range)?;
+
+                    let rhs = impl_.trait_ref(db).and_then(|trait_ref| trait_ref.get_type_argument(1));
+                    let output = assoc_type(db, impl_, "Output").or_else(|| assoc_type(db, impl_, "Target"));
+                    operators.push(OperatorImpl {
+                        trait_name: trait_name.to_string(),
+                        operator,
+                        rhs: rhs.map(|ty| display_type(db, &ty, krate)),
+                        output: output.map(|ty| display_type(db, &ty, krate)),
+                        derived: source.file_id.is_macro(),
+                        file_path: self.file_path(file_id),
+                        start_line,
+                        end_line,
+                    });
+                }
+            }
+        }
+
+        let deref_chain = adt
+            .ty(db)
+            .autoderef(db)
+            .skip(1)
+            .take(MAX_DEREF_STEPS)
+            .map(|ty| display_type(db, &ty, krate))
+            .collect();
+
+        Ok(TypeOperators {
+            type_path: definition_key(db, Definition::Adt(adt)).unwrap_or_else(|| type_name.to_string()),
+            operators,
+            deref_chain,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_operator_traits_are_unique() {
+        let mut paths: Vec<_> = OPERATOR_TRAITS.iter().map(|(path, _)| *path).collect();
+        paths.sort();
+        paths.dedup();
+        assert_eq!(paths.len(), OPERATOR_TRAITS.len());
+    }
+
+    #[test]
+    fn test_operators_of_symbol_kind() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // SymbolKind derives Debug, Clone, Copy, PartialEq and Eq
+        let report = analyzer.type_operators("analyzer::SymbolKind").unwrap();
+        let eq = report.operators.iter().find(|o| o.trait_name == "PartialEq").expect("SymbolKind is PartialEq");
+        assert!(eq.derived);
+        assert!(report.operators.iter().any(|o| o.trait_name == "Debug"));
+        assert!(!report.operators.iter().any(|o| o.trait_name == "Add"));
+        assert!(report.deref_chain.is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+//! Code ownership from CODEOWNERS
+//!
+//! Reads the project's CODEOWNERS file (at the root, in `.github/` or in
+//! `docs/`, the places GitHub and GitLab look) so results can carry the
+//! owners of the file a symbol lives in, and the symbols a team owns can be
+//! listed. Rules follow GitHub's semantics: gitignore-style patterns, and
+//! the last matching rule wins.
+//!
+//! Owners are the handles CODEOWNERS lists, e.g. `@org/payments`. An
+//! optional `.cratographer/teams.json` maps team names to handles, e.g.
+//! `{"payments": ["@org/payments", "@alice"]}`; owners are then reported
+//! as team names where a mapping exists.
+
+use super::{Analyzer, AnalyzerError, SymbolInfo};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Where CODEOWNERS may live, relative to the project root, in lookup order
+const CODEOWNERS_LOCATIONS: &[&str] = &["CODEOWNERS", ".github/CODEOWNERS", "docs/CODEOWNERS"];
+
+/// The optional team mapping, relative to the project root
+const TEAMS_FILE: &str = ".cratographer/teams.json";
+
+/// A line of CODEOWNERS
+#[derive(Debug, Clone)]
+struct OwnerRule {
+    pattern: String,
+    owners: Vec<String>,
+}
+
+/// The ownership rules of a project
+#[derive(Debug, Clone, Default)]
+pub(super) struct CodeOwners {
+    root: PathBuf,
+    rules: Vec<OwnerRule>,
+    /// Handle → team name, from the team mapping
+    teams: HashMap<String, String>,
+    /// Why the team mapping couldn't be read, reported by `owned_symbols`
+    /// rather than failing the project load
+    teams_error: Option<String>,
+}
+
+impl CodeOwners {
+    /// Load the rules of the project at `root`; none if it has no CODEOWNERS
+    pub(super) fn load(root: &Path) -> Self {
+        let mut owners = CodeOwners { root: root.to_path_buf(), ..Default::default() };
+        if let Some(text) = CODEOWNERS_LOCATIONS.iter().find_map(|location| std::fs::read_to_string(root.join(location)).ok()) {
+            owners.rules = parse_rules(&text);
+        }
+        if let Ok(text) = std::fs::read_to_string(root.join(TEAMS_FILE)) {
+            match serde_json::from_str::<HashMap<String, Vec<String>>>(&text) {
+                Ok(teams) => {
+                    for (team, handles) in teams {
+                        owners.teams.extend(handles.into_iter().map(|handle| (handle, team.clone())));
+                    }
+                }
+                Err(e) => owners.teams_error = Some(format!("Invalid {}: {}", TEAMS_FILE, e)),
+            }
+        }
+        owners
+    }
+
+    /// The owners of a file, as team names where mapped; none for files
+    /// outside the project or not covered by any rule
+    pub(super) fn owners_of(&self, file_path: &str) -> Vec<String> {
+        let Some(rule) = self.matching_rule(file_path) else { return Vec::new() };
+        let mut owners: Vec<String> = Vec::new();
+        for owner in &rule.owners {
+            let owner = self.teams.get(owner).unwrap_or(owner);
+            if !owners.contains(owner) {
+                owners.push(owner.clone());
+            }
+        }
+        owners
+    }
+
+    /// Whether `owner`, a team name or handle, owns a file
+    pub(super) fn owns(&self, owner: &str, file_path: &str) -> bool {
+        self.matching_rule(file_path).is_some_and(|rule| {
+            rule.owners.iter().any(|handle| handle == owner || self.teams.get(handle).is_some_and(|team| team == owner))
+        })
+    }
+
+    /// The last rule matching a file, which is the one that applies
+    fn matching_rule(&self, file_path: &str) -> Option<&OwnerRule> {
+        let relative = Path::new(file_path).strip_prefix(&self.root).ok()?;
+        let relative = relative.to_string_lossy().replace('\\', "/");
+        self.rules.iter().rev().find(|rule| pattern_matches(&rule.pattern, &relative))
+    }
+}
+
+impl Analyzer {
+    /// The owners of a file according to CODEOWNERS
+    pub fn owners_of(&self, file_path: &str) -> Vec<String> {
+        self.code_owners.owners_of(file_path)
+    }
+
+    /// The workspace files `owner` (a team name or CODEOWNERS handle) owns,
+    /// by path, each with its symbols
+    pub fn owned_symbols(&self, owner: &str) -> Result<Vec<(String, Vec<SymbolInfo>)>, AnalyzerError> {
+        if let Some(error) = &self.code_owners.teams_error {
+            return Err(AnalyzerError::Other(error.clone()));
+        }
+        let mut files: Vec<(String, ra_ap_ide::FileId)> = self
+            .workspace_files()?
+            .into_iter()
+            .map(|file_id| (self.file_path(file_id), file_id))
+            .filter(|(path, _)| self.code_owners.owns(owner, path))
+            .collect();
+        files.sort();
+
+        files
+            .into_iter()
+            .map(|(path, file_id)| {
+                let symbols = self.file_symbols(file_id, &path)?;
+                Ok((path, symbols))
+            })
+            .collect()
+    }
+}
+
+fn parse_rules(text: &str) -> Vec<OwnerRule> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default().trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('['))
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let pattern = parts.next()?.to_string();
+            Some(OwnerRule { pattern, owners: parts.map(str::to_string).collect() })
+        })
+        .collect()
+}
+
+/// Whether a gitignore-style CODEOWNERS pattern matches a path relative to the root
+///
+/// A pattern with a slash other than a trailing one is anchored at the root;
+/// one without matches at any depth. A pattern matching a directory covers
+/// everything below it.
+fn pattern_matches(pattern: &str, path: &str) -> bool {
+    let anchored = pattern.trim_end_matches('/').contains('/');
+    let pattern = pattern.trim_start_matches('/').trim_end_matches('/');
+    if pattern.is_empty() {
+        return false;
+    }
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+
+    let starts: Vec<usize> = if anchored { vec![0] } else { (0..path.len()).collect() };
+    starts.into_iter().any(|start| {
+        // Matching a prefix of the path means matching a directory it is in
+        (start + 1..=path.len()).any(|end| segments_match(&pattern, &path[start..end]))
+    })
+}
+
+/// Match path segments against pattern segments, where `**` spans any number of segments
+fn segments_match(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|skip| segments_match(rest, &path[skip..])),
+        Some((first, rest)) => {
+            path.split_first().is_some_and(|(segment, path)| glob_matches(first, segment) && segments_match(rest, path))
+        }
+    }
+}
+
+/// Match one path segment against a glob with `*` and `?`
+fn glob_matches(glob: &str, text: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut g, mut t) = (0, 0);
+    // Position of the last `*` and the text position it was tried at
+    let mut backtrack: Option<(usize, usize)> = None;
+    while t < text.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == text[t]) {
+            g += 1;
+            t += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            backtrack = Some((g, t));
+            g += 1;
+        } else if let Some((star, tried)) = backtrack {
+            g = star + 1;
+            t = tried + 1;
+            backtrack = Some((star, tried + 1));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern_matching() {
+        assert!(pattern_matches("*.rs", "src/analyzer/hover.rs"));
+        assert!(!pattern_matches("*.rs", "README.md"));
+        assert!(pattern_matches("/src/analyzer/", "src/analyzer/hover.rs"));
+        assert!(!pattern_matches("/analyzer/", "src/analyzer/hover.rs"));
+        assert!(pattern_matches("analyzer/", "src/analyzer/hover.rs"));
+        assert!(pattern_matches("src/**/hover.rs", "src/analyzer/hover.rs"));
+        assert!(pattern_matches("src/**/hover.rs", "src/hover.rs"));
+        assert!(pattern_matches("/src/main.r?", "src/main.rs"));
+        assert!(!pattern_matches("/src/main.rs", "src/main.rs.bak"));
+    }
+
+    #[test]
+    fn test_last_matching_rule_wins_and_teams_map_handles() {
+        let root = std::env::temp_dir().join(format!("cratographer-codeowners-{}", std::process::id()));
+        std::fs::create_dir_all(root.join(".github")).unwrap();
+        std::fs::create_dir_all(root.join(".cratographer")).unwrap();
+        std::fs::write(
+            root.join(".github/CODEOWNERS"),
+            "# Default owners\n* @org/core\n/src/payments/ @org/payments-eng @alice\n/src/payments/legacy.rs\n",
+        )
+        .unwrap();
+        std::fs::write(root.join(".cratographer/teams.json"), r#"{"payments": ["@org/payments-eng", "@alice"]}"#).unwrap();
+
+        let owners = CodeOwners::load(&root);
+        let path = |relative: &str| root.join(relative).to_string_lossy().into_owned();
+        assert_eq!(owners.owners_of(&path("src/main.rs")), vec!["@org/core"]);
+        assert_eq!(owners.owners_of(&path("src/payments/charge.rs")), vec!["payments"]);
+        assert!(owners.owners_of(&path("src/payments/legacy.rs")).is_empty());
+        assert!(owners.owners_of("/elsewhere/src/main.rs").is_empty());
+        assert!(owners.owns("payments", &path("src/payments/charge.rs")));
+        assert!(owners.owns("@alice", &path("src/payments/charge.rs")));
+        assert!(!owners.owns("@org/core", &path("src/payments/charge.rs")));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
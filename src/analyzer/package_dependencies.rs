@@ -0,0 +1,105 @@
+//! Direct dependencies of a workspace package
+//!
+//! What a package's Cargo.toml pulls in, as resolved by Cargo: the package
+//! behind each dependency, its locked version, whether it is a normal, dev
+//! or build dependency, and where it comes from. The source is read from
+//! Cargo's package id, which spells it out (`registry+...`, `git+...`,
+//! `path+...`).
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_project_model::{DepKind, ProjectWorkspaceKind};
+
+/// A direct dependency
+#[derive(Debug, Clone)]
+pub struct PackageDependency {
+    /// The name the package imports it under, e.g. `serde_json`
+    pub name: String,
+    /// The dependency's package name, which differs from `name` for renamed dependencies
+    pub package: String,
+    /// The resolved version
+    pub version: String,
+    /// "normal", "dev" or "build"
+    pub kind: &'static str,
+    /// "workspace", "crates.io", "registry" (another registry), "git" or "path"
+    pub source: &'static str,
+}
+
+impl Analyzer {
+    /// The direct dependencies of the workspace package `package_name`:
+    /// normal ones first, then build and dev dependencies, each by name
+    pub fn package_dependencies(&self, package_name: &str) -> Result<Vec<PackageDependency>, AnalyzerError> {
+        let workspace = self.workspace.as_ref().ok_or_else(|| AnalyzerError::Other("No project loaded".to_string()))?;
+        let ProjectWorkspaceKind::Cargo { cargo, .. } = &workspace.kind else {
+            return Err(AnalyzerError::Other("The project wasn't loaded from Cargo metadata".to_string()));
+        };
+        let wanted = package_name.replace('-', "_");
+        let package = cargo
+            .packages()
+            .find(|&package| cargo[package].is_member && cargo[package].name.replace('-', "_") == wanted)
+            .ok_or_else(|| AnalyzerError::Other(format!("No workspace package named '{}'", package_name)))?;
+
+        let mut dependencies: Vec<PackageDependency> = cargo[package]
+            .dependencies
+            .iter()
+            .map(|dep| {
+                let data = &cargo[dep.pkg];
+                PackageDependency {
+                    name: dep.name.clone(),
+                    package: data.name.clone(),
+                    version: data.version.to_string(),
+                    kind: match dep.kind {
+                        DepKind::Normal => "normal",
+                        DepKind::Dev => "dev",
+                        DepKind::Build => "build",
+                    },
+                    source: if data.is_member { "workspace" } else { source_kind(&data.id.to_string()) },
+                }
+            })
+            .collect();
+        dependencies.sort_by(|a, b| (a.kind != "normal", a.kind, &a.name).cmp(&(b.kind != "normal", b.kind, &b.name)));
+        Ok(dependencies)
+    }
+}
+
+/// Where a package comes from, given its Cargo package id, either
+/// `registry+https://...#serde@1.0.0` or the older `serde 1.0.0 (registry+https://...)`
+fn source_kind(package_id: &str) -> &'static str {
+    if package_id.contains("git+") {
+        "git"
+    } else if package_id.contains("path+") {
+        "path"
+    } else if package_id.contains("github.com/rust-lang/crates.io-index") || package_id.contains("index.crates.io") {
+        "crates.io"
+    } else {
+        "registry"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_kind() {
+        assert_eq!(source_kind("registry+https://github.com/rust-lang/crates.io-index#serde@1.0.210"), "crates.io");
+        assert_eq!(source_kind("sparse+https://index.crates.io/#serde@1.0.210"), "crates.io");
+        assert_eq!(source_kind("serde 1.0.210 (registry+https://github.com/rust-lang/crates.io-index)"), "crates.io");
+        assert_eq!(source_kind("git+https://github.com/tokio-rs/tokio?branch=master#0123abc"), "git");
+        assert_eq!(source_kind("path+file:///work/helpers#0.1.0"), "path");
+        assert_eq!(source_kind("sparse+https://registry.example.com/index/#internal@2.0.0"), "registry");
+    }
+
+    #[test]
+    fn test_dependencies_of_this_package() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let dependencies = analyzer.package_dependencies("cratographer").unwrap();
+        let serde = dependencies.iter().find(|dep| dep.name == "serde").unwrap();
+        assert_eq!(serde.kind, "normal");
+        assert_eq!(serde.source, "crates.io");
+        assert!(serde.version.starts_with("1."));
+
+        assert!(analyzer.package_dependencies("no-such-package").is_err());
+    }
+}
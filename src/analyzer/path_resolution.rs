@@ -0,0 +1,146 @@
+//! Resolving a fully qualified path
+//!
+//! A name search for `Sender` finds a dozen items; `tokio::sync::mpsc::Sender`
+//! names exactly one. The path is resolved the way the compiler would from
+//! outside the crate: from the crate's root module through each segment's
+//! module scope, which includes what a module re-exports, so a path through
+//! `pub use` resolves to the item where it is defined. The last segments may
+//! also name an enum variant or an associated item, as in `Vec::new`.
+
+use super::monikers::definition_moniker;
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError, SymbolInfo};
+use ra_ap_hir::{Adt, AssocItem, Crate, Impl, ModuleDef, ScopeDef};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+
+/// A path resolved to the item it names
+#[derive(Debug, Clone)]
+pub struct ResolvedPath {
+    pub symbol: SymbolInfo,
+    /// Path of the item where it is defined, e.g. `tokio::sync::mpsc::bounded::Sender`
+    pub canonical_path: String,
+    /// Whether the path reaches the item through a re-export, so differs from the canonical path
+    pub reexported: bool,
+}
+
+impl Analyzer {
+    /// Resolve a path starting with a crate name, such as
+    /// `tokio::sync::mpsc::Sender` or `std::vec::Vec::new`
+    ///
+    /// When several versions of the crate are loaded, workspace crates are
+    /// tried first, then the newest version.
+    pub fn resolve_path(&self, path: &str) -> Result<ResolvedPath, AnalyzerError> {
+        let db = self.host.raw_database();
+        let not_found = || AnalyzerError::Other(format!("Path '{}' does not resolve to an item", path));
+        let segments: Vec<&str> = path.trim().trim_start_matches("::").split("::").map(str::trim).collect();
+        if segments.iter().any(|segment| segment.is_empty()) {
+            return Err(AnalyzerError::Other(format!("Malformed path: {}", path)));
+        }
+        let wanted = segments[0].replace('-', "_");
+
+        let mut crates: Vec<Crate> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| krate.display_name(db).is_some_and(|name| name.to_string().replace('-', "_") == wanted))
+            .collect();
+        if crates.is_empty() {
+            return Err(AnalyzerError::Other(format!("No crate named '{}' in the crate graph", segments[0])));
+        }
+        crates.sort_by_key(|krate| (!krate.origin(db).is_local(), std::cmp::Reverse(krate.version(db))));
+
+        let def = crates
+            .into_iter()
+            .find_map(|krate| resolve_segments(db, ModuleDef::Module(krate.root_module()), &segments[1..]))
+            .ok_or_else(not_found)?;
+        let def = Definition::from(def);
+
+        let moniker = definition_moniker(db, def).ok_or_else(not_found)?;
+        let symbol = self.resolve_moniker(&moniker)?;
+        let canonical_path = definition_key(db, def).unwrap_or_else(|| path.to_string());
+        let written = std::iter::once(wanted.as_str()).chain(segments[1..].iter().copied()).collect::<Vec<_>>().join("::");
+        Ok(ResolvedPath { symbol, reexported: canonical_path != written, canonical_path })
+    }
+}
+
+/// Follow `segments` from `from`, trying every item a segment may name
+/// (a module and a function can share a name) until the rest resolves
+fn resolve_segments(db: &RootDatabase, from: ModuleDef, segments: &[&str]) -> Option<ModuleDef> {
+    let Some((segment, rest)) = segments.split_first() else { return Some(from) };
+    members(db, from, segment).into_iter().find_map(|member| resolve_segments(db, member, rest))
+}
+
+/// The items named `name` inside `parent`: a module's items and re-exports,
+/// an enum's variants, or the associated items of a trait or type
+fn members(db: &RootDatabase, parent: ModuleDef, name: &str) -> Vec<ModuleDef> {
+    let assoc_named = |items: Vec<AssocItem>| -> Vec<ModuleDef> {
+        items
+            .into_iter()
+            .filter(|item| item.name(db).is_some_and(|n| n.as_str() == name))
+            .map(|item| match item {
+                AssocItem::Function(it) => ModuleDef::Function(it),
+                AssocItem::Const(it) => ModuleDef::Const(it),
+                AssocItem::TypeAlias(it) => ModuleDef::TypeAlias(it),
+            })
+            .collect()
+    };
+
+    match parent {
+        ModuleDef::Module(module) => module
+            .scope(db, None)
+            .into_iter()
+            .filter(|(n, _)| n.as_str() == name)
+            .filter_map(|(_, def)| match def {
+                ScopeDef::ModuleDef(def) => Some(def),
+                _ => None,
+            })
+            .collect(),
+        ModuleDef::Trait(trait_) => assoc_named(trait_.items(db)),
+        ModuleDef::Adt(adt) => {
+            let mut found: Vec<ModuleDef> = match adt {
+                Adt::Enum(enum_) => enum_
+                    .variants(db)
+                    .into_iter()
+                    .filter(|variant| variant.name(db).as_str() == name)
+                    .map(ModuleDef::Variant)
+                    .collect(),
+                _ => Vec::new(),
+            };
+            // Inherent impls only; trait methods are reached through the trait
+            let inherent = Impl::all_for_type(db, adt.ty(db)).into_iter().filter(|impl_| impl_.trait_(db).is_none());
+            found.extend(inherent.flat_map(|impl_| assoc_named(impl_.items(db))));
+            found
+        }
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_paths_through_reexports() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let analyzer_struct = analyzer.resolve_path("cratographer::analyzer::Analyzer").unwrap();
+        assert_eq!(analyzer_struct.symbol.name, "Analyzer");
+        assert!(analyzer_struct.symbol.file_path.ends_with("src/analyzer.rs"));
+        assert!(!analyzer_struct.reexported);
+
+        // Re-exported from its defining module
+        let budget = analyzer.resolve_path("cratographer::analyzer::Budget").unwrap();
+        assert!(budget.symbol.file_path.ends_with("src/analyzer/budget.rs"));
+        assert_eq!(budget.canonical_path, "cratographer::analyzer::budget::Budget");
+        assert!(budget.reexported);
+
+        // Associated items of a type
+        let method = analyzer.resolve_path("cratographer::analyzer::Analyzer::resolve_path").unwrap();
+        assert_eq!(method.symbol.name, "resolve_path");
+        assert!(method.symbol.file_path.ends_with("src/analyzer/path_resolution.rs"));
+
+        assert!(analyzer.resolve_path("cratographer::analyzer::NoSuchItem").is_err());
+        assert!(analyzer.resolve_path("no_such_crate::Item").is_err());
+        assert!(analyzer.resolve_path("serde_json::from_str").is_ok());
+    }
+}
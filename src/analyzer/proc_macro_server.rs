@@ -0,0 +1,30 @@
+//! The proc-macro server
+//!
+//! Proc-macro derives and attributes, such as serde's `Serialize`, are
+//! expanded by running the compiled macro crates in a separate server
+//! process, `rust-analyzer-proc-macro-srv`, shipped with the toolchain's
+//! sysroot. Without it, items generated by proc macros are missing from the
+//! index and their expansions are empty; built-in derives such as `Debug`
+//! and `Clone` expand either way. The macros run code from dependencies, so
+//! the server can be turned off with `CRATOGRAPHER_DISABLE_PROC_MACROS=1`.
+
+use ra_ap_ide_db::FxHashMap;
+use ra_ap_proc_macro_api::ProcMacroClient;
+use ra_ap_project_model::ProjectWorkspace;
+
+/// Start the sysroot's proc-macro server for a workspace, or say why it
+/// isn't running
+pub(super) fn spawn(
+    workspace: &ProjectWorkspace,
+    extra_env: &FxHashMap<String, Option<String>>,
+) -> Result<ProcMacroClient, String> {
+    if std::env::var("CRATOGRAPHER_DISABLE_PROC_MACROS").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true")) {
+        return Err("disabled by CRATOGRAPHER_DISABLE_PROC_MACROS".to_string());
+    }
+    let path = workspace
+        .find_sysroot_proc_macro_srv()
+        .ok_or("the workspace has no sysroot")?
+        .map_err(|e| format!("no proc-macro server in the sysroot: {}", e))?;
+    ProcMacroClient::spawn(&path, extra_env, workspace.toolchain.as_ref(), 1)
+        .map_err(|e| format!("could not start {}: {}", path, e))
+}
@@ -0,0 +1,154 @@
+//! Pluggable symbol backends
+//!
+//! The basic symbol queries (`find_symbol`, `enumerate_file`,
+//! `enumerate_files`) go through the `SymbolProvider` trait rather than
+//! `Analyzer` directly, so another backend can answer them. The intended use
+//! is a two-tier startup: a fast backend that is ready almost instantly serves
+//! queries while rust-analyzer loads the workspace, and is swapped out for the
+//! full `Analyzer` once loading finishes.
+
+use super::quick_index::rust_files;
+use super::{Analyzer, AnalyzerError, SearchOptions, SymbolInfo};
+use std::path::Path;
+
+/// The symbols of the Rust files below a directory
+#[derive(Debug)]
+pub struct DirectorySymbols {
+    /// Each enumerated file, by path, with its own result
+    pub files: Vec<(String, Result<Vec<SymbolInfo>, AnalyzerError>)>,
+    /// Number of Rust files below the directory, which exceeds the number
+    /// enumerated when the file limit was hit
+    pub total_files: usize,
+}
+
+/// A backend answering symbol queries
+pub trait SymbolProvider: Send {
+    /// Short name of the backend, e.g. "rust-analyzer", reported to clients
+    fn backend(&self) -> &'static str;
+
+    /// Root directory of the loaded workspace, if any
+    fn project_root(&self) -> Option<&Path>;
+
+    /// Revision of the backend's index; results computed at one revision
+    /// stay valid until it changes
+    fn revision(&self) -> u64;
+
+    /// Find symbols by name, see `Analyzer::find_symbol`
+    fn find_symbol(&self, name: &str, options: &SearchOptions) -> Result<Vec<SymbolInfo>, AnalyzerError>;
+
+    /// Indexed names close to one that found nothing, best first; none by default
+    fn suggest_names(&self, _name: &str, _options: &SearchOptions) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// List the symbols defined in a file, given by absolute path
+    fn enumerate_file(&self, file_path: &str) -> Result<Vec<SymbolInfo>, AnalyzerError>;
+
+    /// List the symbols of several files, each with its own result
+    ///
+    /// Relative paths are resolved against the project root; the returned
+    /// paths are the resolved ones.
+    fn enumerate_files(&self, file_paths: &[String]) -> Vec<(String, Result<Vec<SymbolInfo>, AnalyzerError>)> {
+        file_paths
+            .iter()
+            .map(|path| {
+                let absolute = match (self.project_root(), Path::new(path).is_relative()) {
+                    (Some(root), true) => root.join(path).to_string_lossy().into_owned(),
+                    _ => path.clone(),
+                };
+                let result = self.enumerate_file(&absolute);
+                (absolute, result)
+            })
+            .collect()
+    }
+
+    /// List the symbols of every Rust file below a directory, by path, up to `max_files` files
+    ///
+    /// A relative directory is resolved against the project root. Build
+    /// output (`target`) and `.git` are skipped.
+    fn enumerate_directory(&self, directory: &str, max_files: usize) -> Result<DirectorySymbols, AnalyzerError> {
+        let directory = match self.project_root() {
+            Some(root) if Path::new(directory).is_relative() => root.join(directory),
+            _ => Path::new(directory).to_path_buf(),
+        };
+        if !directory.is_dir() {
+            return Err(AnalyzerError::Other(format!("Not a directory: {}", directory.display())));
+        }
+
+        let paths = rust_files(&directory)?;
+        let files = paths
+            .iter()
+            .take(max_files)
+            .map(|path| {
+                let path = path.to_string_lossy().into_owned();
+                let result = self.enumerate_file(&path);
+                (path, result)
+            })
+            .collect();
+        Ok(DirectorySymbols { files, total_files: paths.len() })
+    }
+}
+
+impl SymbolProvider for Analyzer {
+    fn backend(&self) -> &'static str {
+        "rust-analyzer"
+    }
+
+    fn project_root(&self) -> Option<&Path> {
+        self.project_root.as_deref()
+    }
+
+    fn revision(&self) -> u64 {
+        Analyzer::revision(self)
+    }
+
+    fn find_symbol(&self, name: &str, options: &SearchOptions) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        Analyzer::find_symbol(self, name, options)
+    }
+
+    fn suggest_names(&self, name: &str, options: &SearchOptions) -> Vec<String> {
+        Analyzer::suggest_names(self, name, options)
+    }
+
+    fn enumerate_file(&self, file_path: &str) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        Analyzer::enumerate_file(self, file_path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyzer_as_provider() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let provider: &dyn SymbolProvider = &analyzer;
+        assert_eq!(provider.backend(), "rust-analyzer");
+        assert!(provider.project_root().is_some());
+
+        let results = provider.enumerate_files(&["src/analyzer/provider.rs".to_string()]);
+        let (path, symbols) = &results[0];
+        assert!(Path::new(path).is_absolute());
+        assert!(symbols.as_ref().unwrap().iter().any(|s| s.name == "SymbolProvider"));
+    }
+
+    #[test]
+    fn test_enumerate_directory() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let listing = analyzer.enumerate_directory("src/analyzer", usize::MAX).unwrap();
+        assert_eq!(listing.files.len(), listing.total_files);
+        let (_, symbols) = listing.files.iter().find(|(path, _)| path.ends_with("src/analyzer/provider.rs")).unwrap();
+        assert!(symbols.as_ref().unwrap().iter().any(|s| s.name == "DirectorySymbols"));
+
+        // The file limit caps the files enumerated, not the count reported
+        let capped = analyzer.enumerate_directory("src/analyzer", 2).unwrap();
+        assert_eq!(capped.files.len(), 2);
+        assert_eq!(capped.total_files, listing.total_files);
+
+        assert!(analyzer.enumerate_directory("no/such/directory", 10).is_err());
+    }
+}
@@ -0,0 +1,233 @@
+//! Syntactic quick index
+//!
+//! A `SymbolProvider` that only parses: it walks the project directory, parses
+//! every `.rs` file on its own with rust-analyzer's standalone parser and
+//! records the items it declares. No crate graph, name resolution or
+//! dependency loading is involved, so it is ready within a second or so of
+//! startup and can answer `find_symbol` and `enumerate_file` while the full
+//! `Analyzer` is still loading.
+//!
+//! Results are approximate: files not reachable from any crate root and items
+//! under inactive `cfg`s are included, macro-generated items are missing,
+//! library symbols are not indexed, and symbols carry no crate, moniker or
+//! rustdoc link. Every symbol is marked `approximate`.
+
+use super::library_cache::name_matches;
+use super::provider::SymbolProvider;
+use super::signature_filter::fn_attributes;
+use super::suggestions::closest_names;
+use super::{item_label, AnalyzerError, SearchOptions, SignatureFilter, SymbolInfo, SymbolKind, SymbolSize, SYMBOL_SEARCH_LIMIT};
+use ra_ap_syntax::{ast, AstNode, Edition, SourceFile};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Directories never indexed: build output and version control
+const SKIPPED_DIRECTORIES: &[&str] = &["target", ".git"];
+
+/// Symbols of every Rust file below a project directory, by absolute path
+pub struct QuickIndex {
+    project_root: PathBuf,
+    files: HashMap<String, Vec<SymbolInfo>>,
+}
+
+impl QuickIndex {
+    /// Parse every Rust file below `project_path`
+    pub fn build(project_path: impl Into<PathBuf>) -> Result<Self, AnalyzerError> {
+        let project_root = project_path.into().canonicalize()?;
+        let mut files = HashMap::new();
+
+        for path in rust_files(&project_root)? {
+            // Unreadable files are left out rather than failing the index
+            let Ok(text) = std::fs::read_to_string(&path) else { continue };
+            let file_path = path.to_string_lossy().into_owned();
+            let symbols = file_symbols(&file_path, &text);
+            files.insert(file_path, symbols);
+        }
+
+        Ok(Self { project_root, files })
+    }
+}
+
+/// Every `.rs` file below `directory`, by path, skipping build output and version control
+pub(super) fn rust_files(directory: &Path) -> Result<Vec<PathBuf>, AnalyzerError> {
+    let mut files = Vec::new();
+    let mut directories = vec![directory.to_path_buf()];
+
+    while let Some(directory) = directories.pop() {
+        for entry in std::fs::read_dir(&directory)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            let path = entry.path();
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            if file_type.is_dir() {
+                if !SKIPPED_DIRECTORIES.contains(&name) {
+                    directories.push(path);
+                }
+            } else if file_type.is_symlink() && path.is_dir() {
+                // Symlinked directories aren't followed, as they may form a cycle
+                continue;
+            } else if path.extension().is_some_and(|ext| ext == "rs") {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+impl SymbolProvider for QuickIndex {
+    fn backend(&self) -> &'static str {
+        "quick-index"
+    }
+
+    fn project_root(&self) -> Option<&Path> {
+        Some(&self.project_root)
+    }
+
+    fn revision(&self) -> u64 {
+        0
+    }
+
+    fn find_symbol(&self, name: &str, options: &SearchOptions) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        let query = name.to_lowercase();
+        let mut results: Vec<SymbolInfo> = self
+            .files
+            .values()
+            .flatten()
+            .filter(|sym| name_matches(&sym.name.to_lowercase(), &query, options.mode))
+            .filter(|sym| options.accepts(sym))
+            .filter(|sym| signature_matches(sym, &options.signature))
+            .cloned()
+            .collect();
+
+        // Map iteration order is arbitrary; keep results stable across calls
+        results.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+        results.truncate(SYMBOL_SEARCH_LIMIT);
+        Ok(results)
+    }
+
+    fn suggest_names(&self, name: &str, _options: &SearchOptions) -> Vec<String> {
+        let names = self.files.values().flatten().filter(|sym| sym.kind != SymbolKind::Impl).map(|sym| sym.name.as_str());
+        closest_names(name, names)
+    }
+
+    fn enumerate_file(&self, file_path: &str) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        self.files
+            .get(file_path)
+            .cloned()
+            .ok_or_else(|| AnalyzerError::Other(format!("File not found in quick index: {}", file_path)))
+    }
+}
+
+/// Items declared in a file, outside function bodies
+pub(super) fn file_symbols(file_path: &str, text: &str) -> Vec<SymbolInfo> {
+    let file = SourceFile::parse(text, Edition::CURRENT).tree();
+    let line_starts: Vec<usize> =
+        std::iter::once(0).chain(text.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let line_of = |offset: usize| line_starts.partition_point(|&start| start <= offset).saturating_sub(1) as u32;
+
+    file.syntax()
+        .descendants()
+        .filter_map(ast::Item::cast)
+        .filter(|item| !item.syntax().ancestors().skip(1).any(|node| ast::BlockExpr::can_cast(node.kind())))
+        .filter_map(|item| {
+            let (name, label) = item_label(&item)?;
+            let in_assoc_list = item.syntax().parent().is_some_and(|p| ast::AssocItemList::can_cast(p.kind()));
+            let kind = match label {
+                "Const" => SymbolKind::Const,
+                "Enum" => SymbolKind::Enum,
+                "Function" if in_assoc_list => SymbolKind::Method,
+                "Function" => SymbolKind::Function,
+                "Impl" => SymbolKind::Impl,
+                "Module" => SymbolKind::Module,
+                "Static" => SymbolKind::Static,
+                "Struct" => SymbolKind::Struct,
+                "Trait" => SymbolKind::Trait,
+                "TypeAlias" => SymbolKind::TypeAlias,
+                _ => return None,
+            };
+            let range = item.syntax().text_range();
+            Some(SymbolInfo {
+                name,
+                kind,
+                file_path: file_path.to_string(),
+                start_line: line_of(usize::from(range.start())),
+                end_line: line_of(usize::from(range.end())),
+                documentation: None,
+                crate_name: None,
+                crate_version: None,
+                moniker: None,
+                rustdoc: None,
+                approximate: true,
+                attributes: ast::Fn::cast(item.syntax().clone()).map(|func| fn_attributes(&func)).unwrap_or_default(),
+                owners: Vec::new(),
+                size: Some(SymbolSize::of(&text[range])),
+            })
+        })
+        .collect()
+}
+
+/// Whether a symbol is a function whose signature passes the filter,
+/// reparsing its file from disk
+fn signature_matches(symbol: &SymbolInfo, filter: &SignatureFilter) -> bool {
+    if filter.is_empty() {
+        return true;
+    }
+    let Ok(text) = std::fs::read_to_string(&symbol.file_path) else { return false };
+    let file = SourceFile::parse(&text, Edition::CURRENT).tree();
+    file.syntax()
+        .descendants()
+        .filter_map(ast::Fn::cast)
+        .filter(|func| text[..usize::from(func.syntax().text_range().start())].matches('\n').count() as u32 == symbol.start_line)
+        .any(|func| filter.matches(&func))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::SearchMode;
+
+    #[test]
+    fn test_file_symbols() {
+        let src = "struct S;\nimpl S {\n    fn method(&self) {\n        fn nested() {}\n    }\n}\nmacro_rules! m { () => {} }\n";
+        let symbols: Vec<_> = file_symbols("/p/lib.rs", src).into_iter().map(|s| (s.name, s.kind, s.start_line, s.end_line)).collect();
+
+        assert_eq!(
+            symbols,
+            vec![
+                ("S".to_string(), SymbolKind::Struct, 0, 0),
+                ("impl S".to_string(), SymbolKind::Impl, 1, 5),
+                ("method".to_string(), SymbolKind::Method, 2, 4),
+            ]
+        );
+        assert_eq!(file_symbols("/p/lib.rs", src)[0].size, Some(SymbolSize { lines: 1, bytes: 9, tokens: 3 }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_cycle_is_not_followed() {
+        let root = std::env::temp_dir().join(format!("cratographer-quick-index-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "fn f() {}\n").unwrap();
+        let _ = std::os::unix::fs::symlink(&root, root.join("src/loop"));
+
+        let files = rust_files(&root).unwrap();
+        assert_eq!(files, vec![root.join("src/lib.rs")]);
+        let _ = std::fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_quick_index_of_cratographer() {
+        let index = QuickIndex::build(".").unwrap();
+
+        let options = SearchOptions { mode: SearchMode::Exact, ..Default::default() };
+        let results = index.find_symbol("QuickIndex", &options).unwrap();
+        assert!(results.iter().any(|s| s.kind == SymbolKind::Struct && s.file_path.ends_with("quick_index.rs")));
+        assert!(results.iter().all(|s| s.approximate));
+
+        let path = std::fs::canonicalize("src/analyzer/quick_index.rs").unwrap();
+        let symbols = index.enumerate_file(path.to_str().unwrap()).unwrap();
+        assert!(symbols.iter().any(|s| s.name == "build" && s.kind == SymbolKind::Method));
+    }
+}
@@ -0,0 +1,654 @@
+//! Persisted cross-reference index
+//!
+//! Maps every workspace definition to the places that reference it, so
+//! reference queries are a hash lookup instead of a workspace-wide search.
+//! The index is stored under `target/cratographer/` and reloaded on startup;
+//! only files whose contents changed since it was written are rescanned.
+//!
+//! Incremental updates rescan the changed files. References in unchanged
+//! files stay as they were, which is only wrong when a changed file adds,
+//! removes, or renames definitions; that case is detected and queues a full
+//! rescan, run in batches like a cold build while the current index keeps
+//! answering queries.
+//!
+//! The index is stamped with the project fingerprint; one written under a
+//! different toolchain, rust-analyzer version, Cargo.lock or feature set is
+//! discarded and rebuilt from scratch.
+//!
+//! A cold build scans the whole workspace. The server builds it in batches
+//! (`ReferenceIndexBuild`), locking the analyzer for one batch at a time, so
+//! queries are answered in between; files that change meanwhile are queued
+//! again before the index is put in place.
+
+use super::fingerprint::PersistedStatus;
+use super::hidden_api::is_doc_hidden;
+use super::{display_type, stable_hash, Analyzer, AnalyzerError};
+use ra_ap_hir::{AssocItemContainer, Crate, Semantics};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::{Definition, NameClass, NameRefClass};
+use ra_ap_syntax::{ast, AstNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Bump when the on-disk format or the key scheme changes
+const INDEX_FORMAT_VERSION: u32 = 3;
+
+/// A single place where a definition is referenced
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReferenceSite {
+    pub file_path: String,
+    pub line: u32,
+    pub column: u32,
+    /// Whether the reference is part of a `use` declaration
+    pub is_import: bool,
+}
+
+/// All indexed references to one definition
+#[derive(Debug, Clone)]
+pub struct IndexedReferences {
+    /// Qualified path of the definition, e.g. `cratographer::analyzer::Analyzer::new`
+    pub definition: String,
+    pub references: Vec<ReferenceSite>,
+}
+
+/// What the index knows about one file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FileEntry {
+    /// Hash of the file text the entry was built from
+    content_hash: u64,
+    /// Hash of the definitions the file declares; a change means references
+    /// elsewhere may now resolve differently
+    definitions_hash: u64,
+    /// (definition key, reference site) pairs originating in this file
+    references: Vec<(String, ReferenceSite)>,
+    /// Keys of the referenced definitions that are `#[doc(hidden)]` items of
+    /// dependencies
+    hidden: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct PersistedIndex {
+    version: u32,
+    /// Digest of the project fingerprint the index was built under
+    fingerprint: u64,
+    files: HashMap<String, FileEntry>,
+}
+
+/// `PersistedIndex` borrowing the files of the index being saved
+#[derive(Serialize)]
+struct PersistedIndexRef<'a> {
+    version: u32,
+    fingerprint: u64,
+    files: &'a HashMap<String, FileEntry>,
+}
+
+/// Definition → references index over the workspace
+#[derive(Debug, Clone, Default)]
+pub struct ReferenceIndex {
+    files: HashMap<String, FileEntry>,
+    by_definition: HashMap<String, Vec<ReferenceSite>>,
+    /// Last path segment → definition keys, for lookups by bare name
+    by_name: HashMap<String, Vec<String>>,
+}
+
+impl ReferenceIndex {
+    fn insert_file(&mut self, path: String, entry: FileEntry) {
+        self.remove_file(&path);
+        for (key, site) in &entry.references {
+            self.by_definition.entry(key.clone()).or_default().push(site.clone());
+            let name = key.rsplit("::").next().unwrap_or(key).to_string();
+            let keys = self.by_name.entry(name).or_default();
+            if !keys.contains(key) {
+                keys.push(key.clone());
+            }
+        }
+        self.files.insert(path, entry);
+    }
+
+    fn remove_file(&mut self, path: &str) {
+        let Some(old) = self.files.remove(path) else { return };
+        for (key, _) in old.references {
+            if let Some(sites) = self.by_definition.get_mut(&key) {
+                sites.retain(|site| site.file_path != path);
+                if sites.is_empty() {
+                    self.by_definition.remove(&key);
+                    let name = key.rsplit("::").next().unwrap_or(&key).to_string();
+                    if let Some(keys) = self.by_name.get_mut(&name) {
+                        keys.retain(|k| k != &key);
+                    }
+                }
+            }
+        }
+    }
+
+    /// References to every definition matching `name`
+    ///
+    /// A bare name matches the last path segment; a path such as
+    /// `Analyzer::new` matches qualified keys ending in it.
+    pub fn lookup(&self, name: &str) -> Vec<IndexedReferences> {
+        let suffix = format!("::{}", name);
+        let keys: Vec<&String> = match self.by_name.get(name) {
+            Some(keys) => keys.iter().collect(),
+            None => self.by_definition.keys().filter(|k| k.as_str() == name || k.ends_with(&suffix)).collect(),
+        };
+
+        keys.into_iter()
+            .filter_map(|key| {
+                self.by_definition.get(key).map(|sites| IndexedReferences {
+                    definition: key.clone(),
+                    references: sites.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// References to every `#[doc(hidden)]` dependency item the workspace uses
+    pub(super) fn hidden_uses(&self) -> Vec<IndexedReferences> {
+        let keys: BTreeSet<&String> = self.files.values().flat_map(|entry| &entry.hidden).collect();
+        keys.into_iter()
+            .filter_map(|key| {
+                self.by_definition.get(key).map(|sites| IndexedReferences {
+                    definition: key.clone(),
+                    references: sites.clone(),
+                })
+            })
+            .collect()
+    }
+
+    /// References to the definition with exactly this key
+    pub(super) fn references_to(&self, key: &str) -> &[ReferenceSite] {
+        self.by_definition.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    fn load(path: &Path, fingerprint: u64) -> Option<Self> {
+        let data = std::fs::read(path).ok()?;
+        let persisted: PersistedIndex = serde_json::from_slice(&data).ok()?;
+        if persisted.version != INDEX_FORMAT_VERSION || persisted.fingerprint != fingerprint {
+            return None;
+        }
+
+        let mut index = ReferenceIndex::default();
+        for (path, entry) in persisted.files {
+            index.insert_file(path, entry);
+        }
+        Some(index)
+    }
+
+    fn save(&self, path: &Path, fingerprint: u64) -> Result<(), AnalyzerError> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let persisted = PersistedIndexRef {
+            version: INDEX_FORMAT_VERSION,
+            fingerprint,
+            files: &self.files,
+        };
+        let data = serde_json::to_vec(&persisted)
+            .map_err(|e| AnalyzerError::Other(format!("Failed to serialize reference index: {}", e)))?;
+        // Written to a temporary file first so another server on the same
+        // project, or another thread of this one, never reads a half-written
+        // index; the counter keeps threads' temporary files apart
+        static SAVES: AtomicU64 = AtomicU64::new(0);
+        let tmp = path.with_extension(format!("tmp{}-{}", std::process::id(), SAVES.fetch_add(1, Ordering::Relaxed)));
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+/// A reference index being built a batch of files at a time
+pub struct ReferenceIndexBuild {
+    index: ReferenceIndex,
+    /// Files still to scan
+    pending: Vec<FileId>,
+    /// Whether every file must be scanned once `pending` is done, because a
+    /// definition changed or a file went away
+    rescan_all: bool,
+    /// Analyzer revision `pending` was computed at
+    revision: u64,
+    /// Whether the build started from an empty index and no file changed
+    /// since, so every entry is scanned against the same revision and files
+    /// new to the index don't call for another pass
+    cold: bool,
+}
+
+impl Analyzer {
+    /// Build the reference index, reusing the persisted copy where possible
+    ///
+    /// Files whose contents match the persisted index are not rescanned.
+    pub fn build_reference_index(&mut self) -> Result<(), AnalyzerError> {
+        let mut build = self.begin_reference_index()?;
+        while !self.continue_reference_index(&mut build, usize::MAX)? {}
+        Ok(())
+    }
+
+    /// Start building the reference index from the persisted copy, to be
+    /// completed by `continue_reference_index`
+    pub fn begin_reference_index(&self) -> Result<ReferenceIndexBuild, AnalyzerError> {
+        let fingerprint = self.index_fingerprint();
+        let index = self.reference_index_path()
+            .and_then(|path| ReferenceIndex::load(&path, fingerprint))
+            .unwrap_or_default();
+        let cold = index.files.is_empty();
+        let mut build = ReferenceIndexBuild { index, pending: Vec::new(), rescan_all: false, revision: self.revision, cold };
+        self.queue_outdated(&mut build)?;
+        Ok(build)
+    }
+
+    /// Scan up to `batch` more files of a build; once none are left, save the
+    /// index and put it in place
+    ///
+    /// Returns whether the index is complete. Files changed since the last
+    /// call are queued again first.
+    pub fn continue_reference_index(&mut self, build: &mut ReferenceIndexBuild, batch: usize) -> Result<bool, AnalyzerError> {
+        if build.revision != self.revision {
+            build.revision = self.revision;
+            build.cold = false;
+            self.queue_outdated(build)?;
+        }
+
+        if build.pending.is_empty() && build.rescan_all {
+            build.pending = self.workspace_files()?;
+            build.rescan_all = false;
+        }
+        if !build.pending.is_empty() {
+            let files: Vec<FileId> = build.pending.drain(..batch.min(build.pending.len())).collect();
+            // A changed definition invalidates references in unchanged files too
+            if self.rescan_entries(&mut build.index, &files)? && !build.cold {
+                build.rescan_all = true;
+            }
+            return Ok(false);
+        }
+
+        let index = std::mem::take(&mut build.index);
+        if let Some(path) = self.reference_index_path() {
+            index.save(&path, self.index_fingerprint())?;
+        }
+        self.references = Some(index);
+        self.reference_generation += 1;
+        Ok(true)
+    }
+
+    /// Scan one more batch of a full rescan queued by a file change
+    ///
+    /// Returns whether no rescan is left, i.e. the index is up to date.
+    pub fn continue_reference_rescan(&mut self, batch: usize) -> Result<bool, AnalyzerError> {
+        let Some(mut build) = self.reference_rescan.take() else { return Ok(true) };
+        let done = self.continue_reference_index(&mut build, batch)?;
+        if !done {
+            self.reference_rescan = Some(build);
+        }
+        Ok(done)
+    }
+
+    /// Queue the files whose contents differ from what a build has indexed,
+    /// and drop files that no longer exist
+    fn queue_outdated(&self, build: &mut ReferenceIndexBuild) -> Result<(), AnalyzerError> {
+        let files = self.workspace_files()?;
+        let current: Vec<String> = files.iter().map(|&file_id| self.file_path(file_id)).collect();
+        let current_paths: HashSet<&String> = current.iter().collect();
+        let current_files: HashSet<FileId> = files.iter().copied().collect();
+
+        let stale: Vec<String> = build.index.files.keys().filter(|path| !current_paths.contains(path)).cloned().collect();
+        for path in &stale {
+            build.index.remove_file(path);
+        }
+        build.rescan_all |= !stale.is_empty();
+        build.pending.retain(|file_id| current_files.contains(file_id));
+
+        let analysis = self.host.analysis();
+        let mut pending: HashSet<FileId> = build.pending.iter().copied().collect();
+        for (&file_id, path) in files.iter().zip(&current) {
+            let text = analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let up_to_date = build.index.files.get(path).is_some_and(|entry| entry.content_hash == stable_hash(&text));
+            if !up_to_date && pending.insert(file_id) {
+                build.pending.push(file_id);
+            }
+        }
+        Ok(())
+    }
+
+    /// Update the reference index after files changed on disk
+    ///
+    /// Only the changed files are rescanned here. When their definitions
+    /// changed, a full rescan is queued for `continue_reference_rescan`,
+    /// which saves the index once done. Does nothing until the index has
+    /// been built.
+    pub(super) fn update_reference_index(&mut self, changed: &[FileId], deleted: &[FileId]) -> Result<(), AnalyzerError> {
+        let Some(mut index) = self.references.take() else { return Ok(()) };
+
+        for &file_id in deleted {
+            index.remove_file(&self.file_path(file_id));
+        }
+
+        let workspace = self.workspace_files()?;
+        let in_workspace: HashSet<FileId> = workspace.iter().copied().collect();
+        let changed: Vec<FileId> = changed.iter().copied().filter(|id| in_workspace.contains(id)).collect();
+
+        let result = self.rescan_entries(&mut index, &changed).and_then(|definitions_changed| {
+            // References elsewhere may now resolve differently
+            if (definitions_changed || !deleted.is_empty()) && self.reference_rescan.is_none() {
+                self.reference_rescan = Some(ReferenceIndexBuild {
+                    index: index.clone(),
+                    pending: workspace,
+                    rescan_all: false,
+                    revision: self.revision,
+                    cold: false,
+                });
+            }
+            // A queued rescan saves the index once it is done
+            match (&self.reference_rescan, self.reference_index_path()) {
+                (None, Some(path)) => index.save(&path, self.index_fingerprint()),
+                _ => Ok(()),
+            }
+        });
+
+        self.references = Some(index);
+        self.reference_generation += 1;
+        result
+    }
+
+    /// Whether the reference index has been built
+    pub fn has_reference_index(&self) -> bool {
+        self.references.is_some()
+    }
+
+    /// Whether the reference index has been built and no rescan is pending,
+    /// so results answered from it won't change until the next file change
+    pub fn reference_index_settled(&self) -> bool {
+        self.references.is_some() && self.reference_rescan.is_none()
+    }
+
+    /// Generation of the reference index in place
+    ///
+    /// Moves on every time an index is put in place, including when a build
+    /// or rescan completes without the revision changing.
+    pub fn reference_generation(&self) -> u64 {
+        self.reference_generation
+    }
+
+    /// Look up indexed references by definition name or qualified path
+    pub fn indexed_references(&self, name: &str) -> Result<Vec<IndexedReferences>, AnalyzerError> {
+        let index = self.references.as_ref()
+            .ok_or_else(|| AnalyzerError::Other("Reference index has not been built yet".to_string()))?;
+        Ok(index.lookup(name))
+    }
+
+    /// Rescan the given files into the index
+    ///
+    /// Returns whether any file's set of declared definitions changed, or a
+    /// file new to the index declares any.
+    fn rescan_entries(&self, index: &mut ReferenceIndex, files: &[FileId]) -> Result<bool, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let mut definitions_changed = false;
+
+        for &file_id in files {
+            let path = self.file_path(file_id);
+            let text = analysis.file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let source_file = sema.parse_guess_edition(file_id);
+
+            let mut references = Vec::new();
+            let mut hidden = Vec::new();
+            for name_ref in source_file.syntax().descendants().filter_map(ast::NameRef::cast) {
+                let def = match NameRefClass::classify(&sema, &name_ref) {
+                    Some(NameRefClass::Definition(def, ..)) => def,
+                    Some(NameRefClass::FieldShorthand { field_ref, .. }) => Definition::Field(field_ref),
+                    _ => continue,
+                };
+                let Some(key) = definition_key(db, def) else { continue };
+                let external = def.krate(db).is_some_and(|krate| !krate.origin(db).is_local());
+                if external && !hidden.contains(&key) && is_doc_hidden(db, def) {
+                    hidden.push(key.clone());
+                }
+
+                let position = line_index.line_col(name_ref.syntax().text_range().start());
+                references.push((key, ReferenceSite {
+                    file_path: path.clone(),
+                    line: position.line,
+                    column: position.col,
+                    is_import: name_ref.syntax().ancestors().any(|n| ast::Use::can_cast(n.kind())),
+                }));
+            }
+
+            let mut definitions: Vec<String> = source_file
+                .syntax()
+                .descendants()
+                .filter_map(ast::Name::cast)
+                .filter_map(|name| match NameClass::classify(&sema, &name) {
+                    Some(NameClass::Definition(def)) => definition_key(db, def),
+                    _ => None,
+                })
+                .collect();
+            definitions.sort();
+            let definitions_hash = stable_hash(&definitions.join("\n"));
+
+            // A file new to the index changes what other files can resolve
+            // as much as one whose definitions changed
+            let changed = match index.files.get(&path) {
+                Some(old) => old.definitions_hash != definitions_hash,
+                None => !definitions.is_empty(),
+            };
+            definitions_changed |= changed;
+
+            index.insert_file(path, FileEntry {
+                content_hash: stable_hash(&text),
+                definitions_hash,
+                references,
+                hidden,
+            });
+        }
+
+        Ok(definitions_changed)
+    }
+
+    /// Whether the persisted index was built under the current project fingerprint
+    pub fn reference_index_status(&self) -> PersistedStatus {
+        /// Just the header of a persisted index
+        #[derive(Deserialize)]
+        struct Stamp {
+            version: u32,
+            fingerprint: u64,
+        }
+
+        let Some(data) = self.reference_index_path().and_then(|path| std::fs::read(path).ok()) else {
+            return PersistedStatus::Missing;
+        };
+        match serde_json::from_slice::<Stamp>(&data) {
+            Ok(stamp) if stamp.version == INDEX_FORMAT_VERSION && stamp.fingerprint == self.index_fingerprint() => PersistedStatus::Current,
+            _ => PersistedStatus::Stale,
+        }
+    }
+
+    fn index_fingerprint(&self) -> u64 {
+        self.fingerprint.as_ref().map_or(0, |fingerprint| fingerprint.digest())
+    }
+
+    /// Where the persisted index lives, if the project root is known
+    fn reference_index_path(&self) -> Option<PathBuf> {
+        self.project_root.as_ref().map(|root| root.join("target").join("cratographer").join("references.json"))
+    }
+}
+
+/// Qualified path identifying a definition across restarts
+pub(super) fn definition_key(db: &RootDatabase, def: Definition) -> Option<String> {
+    let (krate, path) = definition_path(db, def)?;
+    let mut segments = vec![krate.display_name(db)?.to_string()];
+    segments.extend(path);
+    Some(segments.join("::"))
+}
+
+/// Owning crate of a definition and its path below the crate root
+///
+/// Locals, generic parameters, labels and builtins have no path: they can
+/// only be referenced from within the file that declares them.
+pub(super) fn definition_path(db: &RootDatabase, def: Definition) -> Option<(Crate, Vec<String>)> {
+    if matches!(
+        def,
+        Definition::Local(_) | Definition::GenericParam(_) | Definition::Label(_) | Definition::BuiltinType(_) | Definition::SelfType(_)
+    ) {
+        return None;
+    }
+
+    let module = def.module(db)?;
+    let krate = module.krate();
+    let mut segments: Vec<String> = module
+        .path_to_root(db)
+        .into_iter()
+        .rev()
+        .filter_map(|m| m.name(db))
+        .map(|n| n.as_str().to_string())
+        .collect();
+
+    // Associated items are qualified by their impl's self type or their trait
+    let container = match def {
+        Definition::Function(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::Const(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        Definition::TypeAlias(it) => it.as_assoc_item(db).map(|a| a.container(db)),
+        _ => None,
+    };
+    match container {
+        Some(AssocItemContainer::Impl(impl_)) => segments.push(display_type(db, &impl_.self_ty(db), krate)),
+        Some(AssocItemContainer::Trait(trait_)) => segments.push(trait_.name(db).as_str().to_string()),
+        None => {}
+    }
+    if let Definition::Field(field) = def {
+        segments.push(field.parent_def(db).name(db).as_str().to_string());
+    }
+    if let Definition::Variant(variant) = def {
+        segments.push(variant.parent_enum(db).name(db).as_str().to_string());
+    }
+
+    if !matches!(def, Definition::Module(_)) {
+        segments.push(def.name(db)?.as_str().to_string());
+    }
+
+    Some((krate, segments))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn site(file: &str, line: u32) -> ReferenceSite {
+        ReferenceSite { file_path: file.to_string(), line, column: 0, is_import: false }
+    }
+
+    #[test]
+    fn test_incremental_file_replacement() {
+        let mut index = ReferenceIndex::default();
+        index.insert_file("a.rs".to_string(), FileEntry {
+            content_hash: 1,
+            definitions_hash: 0,
+            references: vec![("krate::Foo".to_string(), site("a.rs", 3)), ("krate::bar".to_string(), site("a.rs", 5))],
+            hidden: vec!["krate::bar".to_string()],
+        });
+        index.insert_file("b.rs".to_string(), FileEntry {
+            content_hash: 2,
+            definitions_hash: 0,
+            references: vec![("krate::Foo".to_string(), site("b.rs", 1))],
+            hidden: Vec::new(),
+        });
+        assert_eq!(index.lookup("Foo")[0].references.len(), 2);
+        assert_eq!(index.lookup("krate::bar").len(), 1);
+        assert_eq!(index.lookup("rate::bar").len(), 0);
+        assert_eq!(index.hidden_uses()[0].definition, "krate::bar");
+
+        // Re-inserting a file replaces its previous references
+        index.insert_file("a.rs".to_string(), FileEntry {
+            content_hash: 3,
+            definitions_hash: 0,
+            references: vec![("krate::Foo".to_string(), site("a.rs", 4))],
+            hidden: Vec::new(),
+        });
+        let foo = index.lookup("Foo");
+        assert_eq!(foo[0].references.len(), 2);
+        assert!(foo[0].references.iter().any(|s| s.file_path == "a.rs" && s.line == 4));
+        assert!(index.lookup("bar").is_empty());
+        assert!(index.hidden_uses().is_empty());
+
+        index.remove_file("b.rs");
+        assert_eq!(index.lookup("Foo")[0].references.len(), 1);
+    }
+
+    #[test]
+    fn test_index_from_other_fingerprint_is_discarded() {
+        let path = std::env::temp_dir().join(format!("cratographer-references-{}.json", std::process::id()));
+        let mut index = ReferenceIndex::default();
+        index.insert_file("a.rs".to_string(), FileEntry {
+            content_hash: 1,
+            definitions_hash: 0,
+            references: vec![("krate::Foo".to_string(), site("a.rs", 3))],
+            hidden: Vec::new(),
+        });
+        index.save(&path, 42).unwrap();
+
+        assert!(ReferenceIndex::load(&path, 42).is_some_and(|index| index.lookup("Foo").len() == 1));
+        assert!(ReferenceIndex::load(&path, 43).is_none());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_build_and_query_reference_index() {
+        let mut analyzer = Analyzer::new();
+        let result = analyzer.load_project(".");
+        assert!(result.is_ok(), "Failed to load project: {:?}", result.err());
+
+        let built = analyzer.build_reference_index();
+        assert!(built.is_ok(), "Failed to build reference index: {:?}", built.err());
+
+        // Analyzer::new is called throughout the test suite
+        let results = analyzer.indexed_references("new").unwrap();
+        let analyzer_new = results.iter().find(|r| r.definition.ends_with("Analyzer::new"));
+        assert!(analyzer_new.is_some_and(|r| !r.references.is_empty()), "Should index references to Analyzer::new: {:?}", results);
+    }
+
+    #[test]
+    fn test_build_in_batches() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Not in place until the last batch is done; with an up-to-date
+        // persisted index that is the first
+        let mut build = analyzer.begin_reference_index().unwrap();
+        while !analyzer.continue_reference_index(&mut build, 4).unwrap() {
+            assert!(analyzer.indexed_references("new").is_err());
+        }
+        assert!(!analyzer.indexed_references("new").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_definition_change_queues_a_batched_rescan() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        analyzer.build_reference_index().unwrap();
+
+        // Adding a function changes the file's definitions; the full rescan is
+        // only queued, and the index keeps answering meanwhile
+        let path = std::fs::canonicalize("src/snapshots.rs").unwrap();
+        let text = std::fs::read_to_string(&path).unwrap() + "\npub fn rescan_probe() {}\n";
+        let change = (ra_ap_paths::AbsPathBuf::assert_utf8(path), Some(text.into_bytes()));
+        analyzer.apply_file_changes(vec![change]).unwrap();
+        assert!(analyzer.reference_rescan.is_some());
+        assert!(!analyzer.reference_index_settled());
+        assert!(!analyzer.indexed_references("new").unwrap().is_empty());
+
+        // Putting the rescanned index in place moves the generation on, though
+        // not the revision
+        let (revision, generation) = (analyzer.revision(), analyzer.reference_generation());
+        let mut batches = 0;
+        while !analyzer.continue_reference_rescan(4).unwrap() {
+            batches += 1;
+        }
+        assert!(batches > 1);
+        assert!(analyzer.reference_rescan.is_none());
+        assert!(analyzer.reference_index_settled());
+        assert_eq!(analyzer.revision(), revision);
+        assert!(analyzer.reference_generation() > generation);
+        assert!(!analyzer.indexed_references("new").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,142 @@
+//! Live reference search
+//!
+//! Wraps rust-analyzer's find-all-references for a symbol given by name or by
+//! position. Unlike the precomputed cross-reference index this always reflects
+//! the current state of the files and classifies each site as a read, write or
+//! import.
+
+use super::reference_index::definition_key;
+use super::{definition_at, Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FileId, FilePosition, ReferenceCategory};
+use ra_ap_syntax::TextSize;
+use std::collections::HashSet;
+
+/// One place a definition is referenced (0-based line and column)
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub file_path: String,
+    pub line: u32,
+    pub column: u32,
+    /// "import", "write", "read", or "reference" for other uses such as calls
+    /// and type mentions; "declaration" for the declaration itself
+    pub kind: &'static str,
+}
+
+/// The references to one definition
+#[derive(Debug, Clone)]
+pub struct DefinitionReferences {
+    /// Qualified path of the definition, when it has one
+    pub definition: Option<String>,
+    /// Where the definition is declared
+    pub declaration: Option<Reference>,
+    pub references: Vec<Reference>,
+}
+
+impl Analyzer {
+    /// Find workspace references to the definition matching a name, qualified
+    /// path or symbol_id
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several definitions match.
+    pub fn find_references(&self, name: &str) -> Result<Vec<DefinitionReferences>, AnalyzerError> {
+        let sema = Semantics::new(self.host.raw_database());
+        let (definitions, positions): (Vec<_>, Vec<_>) = self.definition_positions(&sema, name)?.into_iter().unzip();
+        if positions.is_empty() {
+            return Err(AnalyzerError::Other(format!("No definition named '{}' found", name)));
+        }
+        self.require_unique(&sema, name, &definitions)?;
+        self.references_from(&positions)
+    }
+
+    /// Find workspace references to the symbol at a position (0-based)
+    pub fn find_references_at(&self, file_path: &str, line: u32, column: u32) -> Result<Vec<DefinitionReferences>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        self.references_from(&[position])
+    }
+
+    fn references_from(&self, positions: &[FilePosition]) -> Result<Vec<DefinitionReferences>, AnalyzerError> {
+        let analysis = self.host.analysis();
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let workspace: HashSet<_> = self.workspace_files()?.into_iter().collect();
+
+        let mut results = Vec::new();
+        for &position in positions {
+            let searches = analysis.find_all_refs(position, None).map_err(|_| AnalyzerError::Canceled)?.unwrap_or_default();
+            for search in searches {
+                let mut definition = None;
+                let mut declaration = None;
+                if let Some(decl) = &search.declaration {
+                    let focus = decl.nav.focus_or_full_range();
+                    definition = definition_at(&sema, decl.nav.file_id, focus.start()).and_then(|def| definition_key(db, def));
+                    declaration = Some(self.reference(decl.nav.file_id, focus.start(), "declaration")?);
+                }
+
+                let mut references = Vec::new();
+                for (file_id, ranges) in search.references {
+                    if !workspace.contains(&file_id) {
+                        continue;
+                    }
+                    for (range, category) in ranges {
+                        references.push(self.reference(file_id, range.start(), reference_kind(category))?);
+                    }
+                }
+                references.sort_by(|a, b| (&a.file_path, a.line, a.column).cmp(&(&b.file_path, b.line, b.column)));
+
+                results.push(DefinitionReferences { definition, declaration, references });
+            }
+        }
+
+        Ok(results)
+    }
+
+    fn reference(&self, file_id: FileId, offset: TextSize, kind: &'static str) -> Result<Reference, AnalyzerError> {
+        let line_index = self.host.analysis().file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let position = line_index.line_col(offset);
+        Ok(Reference {
+            file_path: self.file_path(file_id),
+            line: position.line,
+            column: position.col,
+            kind,
+        })
+    }
+}
+
+/// How a reference uses its symbol: `import`, `write`, `read` or plain `reference`
+pub(super) fn reference_kind(category: ReferenceCategory) -> &'static str {
+    if category.contains(ReferenceCategory::IMPORT) {
+        "import"
+    } else if category.contains(ReferenceCategory::WRITE) {
+        "write"
+    } else if category.contains(ReferenceCategory::READ) {
+        "read"
+    } else {
+        "reference"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reference_kinds() {
+        assert_eq!(reference_kind(ReferenceCategory::IMPORT), "import");
+        assert_eq!(reference_kind(ReferenceCategory::WRITE | ReferenceCategory::READ), "write");
+        assert_eq!(reference_kind(ReferenceCategory::READ), "read");
+        assert_eq!(reference_kind(ReferenceCategory::empty()), "reference");
+    }
+
+    #[test]
+    fn test_find_references_by_name() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let results = analyzer.find_references("QueryCache").unwrap();
+        let cache = results
+            .iter()
+            .find(|r| r.definition.as_deref().is_some_and(|d| d.ends_with("query_cache::QueryCache")))
+            .expect("QueryCache is defined in the workspace");
+        assert!(cache.references.iter().any(|r| r.file_path.ends_with("main.rs") && r.kind == "import"));
+    }
+}
@@ -0,0 +1,122 @@
+//! Rename dry runs
+//!
+//! Computes every edit rust-analyzer's rename would make — the definition,
+//! all references across the workspace, field shorthands and `use` renames
+//! that keep the old name working — without applying any of them. Renaming
+//! a module also moves its file, which is reported separately from the text
+//! edits. Names rust-analyzer refuses, such as keywords or renames of
+//! library items, come back as errors.
+
+use super::move_item::{FileEdits, TextEdit};
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{AnchoredPathBuf, FileId, FileSystemEdit};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// A file rename that comes with renaming a module
+#[derive(Debug, Clone)]
+pub struct FileMove {
+    pub from: String,
+    pub to: String,
+}
+
+/// Everything a rename would change
+#[derive(Debug, Clone)]
+pub struct RenamePlan {
+    /// The name being replaced
+    pub old_name: String,
+    /// Non-overlapping edits, sorted by file and position
+    pub edits: Vec<TextEdit>,
+    /// Files to move, for renamed modules
+    pub file_moves: Vec<FileMove>,
+}
+
+impl Analyzer {
+    /// Plan renaming the symbol at a position (0-based) to `new_name`
+    pub fn preview_rename(&self, file_path: &str, line: u32, column: u32, new_name: &str) -> Result<RenamePlan, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+
+        let old_range = analysis
+            .prepare_rename(position)
+            .map_err(|_| AnalyzerError::Canceled)?
+            .map_err(|e| AnalyzerError::Other(e.to_string()))?;
+        let text = analysis.file_text(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let old_name = text[old_range.range].to_string();
+
+        let change = analysis
+            .rename(position, new_name)
+            .map_err(|_| AnalyzerError::Canceled)?
+            .map_err(|e| AnalyzerError::Other(e.to_string()))?;
+
+        let mut files: HashMap<FileId, FileEdits> = HashMap::new();
+        for (file_id, (edit, _)) in change.source_file_edits {
+            let replacements = edit.iter().map(|indel| (indel.delete, indel.insert.clone())).collect();
+            files.insert(file_id, FileEdits { replacements, ..Default::default() });
+        }
+        let sema = Semantics::new(self.host.raw_database());
+        let edits = self.text_edits(&sema, files)?;
+
+        let file_moves = change
+            .file_system_edits
+            .into_iter()
+            .filter_map(|edit| match edit {
+                FileSystemEdit::MoveFile { src, dst } => Some(FileMove { from: self.file_path(src), to: self.anchored_path(&dst) }),
+                FileSystemEdit::MoveDir { src, dst, .. } => {
+                    Some(FileMove { from: self.anchored_path(&src), to: self.anchored_path(&dst) })
+                }
+                FileSystemEdit::CreateFile { .. } => None,
+            })
+            .collect();
+
+        Ok(RenamePlan { old_name, edits, file_moves })
+    }
+
+    /// The path a file system edit names, relative to its anchor file's directory
+    fn anchored_path(&self, path: &AnchoredPathBuf) -> String {
+        let anchor = self.file_path(path.anchor);
+        let dir = Path::new(&anchor).parent().unwrap_or(Path::new(""));
+        dir.join(&path.path).to_string_lossy().into_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preview_rename_of_method() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // The declaration of `anchored_path` in this file, which one call site uses
+        let path = std::fs::canonicalize("src/analyzer/rename.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("fn anchored_path(")).unwrap();
+        let column = text.lines().nth(line).unwrap().find("anchored_path").unwrap() as u32;
+
+        let plan = analyzer.preview_rename(path, line as u32, column, "resolve_anchored").unwrap();
+        assert_eq!(plan.old_name, "anchored_path");
+        assert!(plan.edits.len() >= 4);
+        assert!(plan.edits.iter().all(|e| e.file_path == path && e.new_text == "resolve_anchored"));
+        assert!(plan.file_moves.is_empty());
+
+        // Nothing was applied
+        assert_eq!(std::fs::read_to_string(path).unwrap(), text);
+    }
+
+    #[test]
+    fn test_preview_rename_rejects_keywords() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer/rename.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("pub struct FileMove")).unwrap();
+        let column = text.lines().nth(line).unwrap().find("FileMove").unwrap() as u32;
+        assert!(analyzer.preview_rename(path, line as u32, column, "fn").is_err());
+    }
+}
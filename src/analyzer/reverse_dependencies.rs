@@ -0,0 +1,109 @@
+//! Reverse dependency lookup
+//!
+//! Which workspace crates depend on a given crate, directly or through other
+//! crates, read off the crate graph. Before changing a shared crate this
+//! tells what else may break; for a library it tells which parts of the
+//! workspace would be affected by upgrading or dropping it.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Crate;
+use std::collections::{HashMap, VecDeque};
+
+/// A workspace crate depending on the looked-up crate
+#[derive(Debug, Clone)]
+pub struct Dependent {
+    pub name: String,
+    pub root_file: String,
+    /// Whether the crate is a direct dependency rather than reached through other crates
+    pub direct: bool,
+    /// The chain of crates from the dependent down to the looked-up crate,
+    /// e.g. `["app", "helpers", "serde"]`, along one shortest path
+    pub path: Vec<String>,
+}
+
+impl Analyzer {
+    /// The workspace crates depending on the crates named `crate_name`
+    /// (workspace or library); with `transitive`, also those depending on
+    /// them through other crates
+    ///
+    /// Direct dependents come first, then by name.
+    pub fn reverse_dependencies(&self, crate_name: &str, transitive: bool) -> Result<Vec<Dependent>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let name_of = |krate: Crate| krate.display_name(db).map(|name| name.to_string()).unwrap_or_else(|| "unnamed".to_string());
+        let wanted = crate_name.replace('-', "_");
+
+        let all = Crate::all(db);
+        let targets: Vec<Crate> = all.iter().copied().filter(|&krate| name_of(krate).replace('-', "_") == wanted).collect();
+        if targets.is_empty() {
+            return Err(AnalyzerError::Other(format!("No crate named '{}' in the crate graph", crate_name)));
+        }
+
+        let mut dependents_of: HashMap<Crate, Vec<Crate>> = HashMap::new();
+        for &krate in &all {
+            for dep in krate.dependencies(db) {
+                dependents_of.entry(dep.krate).or_default().push(krate);
+            }
+        }
+
+        // Breadth-first up the reversed edges, remembering for each crate the
+        // next one on a shortest path down to a target
+        let mut next_hop: HashMap<Crate, Option<Crate>> = targets.iter().map(|&target| (target, None)).collect();
+        let mut queue: VecDeque<(Crate, usize)> = targets.iter().map(|&target| (target, 0)).collect();
+        while let Some((krate, depth)) = queue.pop_front() {
+            if depth == 1 && !transitive {
+                continue;
+            }
+            for &dependent in dependents_of.get(&krate).into_iter().flatten() {
+                if !next_hop.contains_key(&dependent) {
+                    next_hop.insert(dependent, Some(krate));
+                    queue.push_back((dependent, depth + 1));
+                }
+            }
+        }
+
+        let mut dependents: Vec<Dependent> = next_hop
+            .keys()
+            .copied()
+            .filter(|&krate| krate.origin(db).is_local() && next_hop[&krate].is_some())
+            .map(|krate| {
+                let mut path = vec![name_of(krate)];
+                let mut current = krate;
+                while let Some(next) = next_hop[&current] {
+                    path.push(name_of(next));
+                    current = next;
+                }
+                Dependent {
+                    name: name_of(krate),
+                    root_file: self.file_path(krate.root_file(db)),
+                    direct: path.len() == 2,
+                    path,
+                }
+            })
+            .collect();
+        dependents.sort_by(|a, b| (!a.direct, &a.name, &a.root_file).cmp(&(!b.direct, &b.name, &b.root_file)));
+        Ok(dependents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reverse_dependencies_of_library() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let direct = analyzer.reverse_dependencies("serde_json", false).unwrap();
+        let this = direct.iter().find(|dep| dep.name == "cratographer").unwrap();
+        assert!(this.direct);
+        assert_eq!(this.path, vec!["cratographer", "serde_json"]);
+
+        // itoa is only reached through serde_json
+        let transitive = analyzer.reverse_dependencies("itoa", true).unwrap();
+        assert!(transitive.iter().any(|dep| dep.name == "cratographer" && dep.path.len() > 2));
+        assert!(analyzer.reverse_dependencies("itoa", false).unwrap().is_empty());
+
+        assert!(analyzer.reverse_dependencies("no_such_crate", true).is_err());
+    }
+}
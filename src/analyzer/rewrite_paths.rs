@@ -0,0 +1,227 @@
+//! Path rewrites after a manual move
+//!
+//! Once an item or module has been moved by hand, its old path no longer
+//! resolves, so the rewrite is syntactic: every `use` tree and qualified
+//! path spelling the old path (or a path below it, for modules) is rewritten
+//! to the new one. A path starting with a crate name is matched as
+//! `crate::...` inside that crate and by crate name elsewhere; a `crate::`
+//! path is matched as written.
+//! Nested `use` leaves are split out into their own `use` declaration.
+//!
+//! Relative paths (`self::`, `super::`) aren't rewritten; those that may
+//! point at the old location are reported as warnings.
+
+use super::move_item::{leaf_removal_range, FileEdits, TextEdit};
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{ast, AstNode, TextRange};
+use std::collections::HashMap;
+
+/// The edits updating every spelling of a moved path
+#[derive(Debug, Clone)]
+pub struct PathRewrite {
+    /// Non-overlapping edits, sorted by file and position
+    pub edits: Vec<TextEdit>,
+    /// Things to check by hand after applying the edits
+    pub warnings: Vec<String>,
+}
+
+impl Analyzer {
+    /// Plan rewriting `old_path` to `new_path` across the workspace, e.g.
+    /// `crate::util::parse` to `crate::parsing::parse`
+    pub fn rewrite_paths(&self, old_path: &str, new_path: &str) -> Result<PathRewrite, AnalyzerError> {
+        let (old_path, new_path) = (compact(old_path), compact(new_path));
+        if !is_path(&old_path) || !is_path(&new_path) {
+            return Err(AnalyzerError::Other("Expected paths like `crate::module::Item`".to_string()));
+        }
+        if old_path == new_path {
+            return Err(AnalyzerError::Other("The old and new paths are the same".to_string()));
+        }
+        let last_segment = old_path.rsplit("::").next().unwrap_or(&old_path).to_string();
+
+        let sema = Semantics::new(self.host.raw_database());
+        let crates = self.crates_by_source_root()?;
+        let analysis = self.host.analysis();
+        let mut files: HashMap<FileId, FileEdits> = HashMap::new();
+        let mut warnings = Vec::new();
+        for file_id in self.workspace_files()? {
+            let crate_name = analysis
+                .source_root_id(file_id)
+                .ok()
+                .and_then(|root| crates.get(&root))
+                .and_then(|(name, _)| name.as_ref())
+                .map(|name| name.replace('-', "_"));
+            let (old, new) = (local_spelling(&old_path, crate_name.as_deref()), local_spelling(&new_path, crate_name.as_deref()));
+
+            let source = sema.parse_guess_edition(file_id);
+            let edits = files.entry(file_id).or_default();
+            let mut relative = Vec::new();
+            for use_ in source.syntax().descendants().filter_map(ast::Use::cast) {
+                if let Some(tree) = use_.use_tree() {
+                    rewrite_use_tree(&tree, "", &old, &new, true, edits, &mut relative);
+                }
+            }
+
+            // Qualified paths outside `use`: the one spelling the old path is
+            // the whole path for an item, or a qualifier for items below a module
+            for path in source.syntax().descendants().filter_map(ast::Path::cast) {
+                if path.syntax().ancestors().any(|node| ast::UseTree::can_cast(node.kind())) {
+                    continue;
+                }
+                let text = compact(&path.syntax().text().to_string());
+                if text == old {
+                    edits.replacements.push((path.syntax().text_range(), new.clone()));
+                } else if path.parent_path().is_none() && is_relative(&text) {
+                    relative.push((path.syntax().text_range(), text));
+                }
+            }
+
+            for (range, text) in relative {
+                if text.split("::").any(|segment| segment == last_segment) {
+                    warnings.push(format!(
+                        "{}:{}: relative path `{}` may need updating by hand",
+                        self.file_path(file_id),
+                        self.line_range(file_id, range)?.0,
+                        text
+                    ));
+                }
+            }
+        }
+        files.retain(|_, edits| !edits.replacements.is_empty() || !edits.imports.is_empty());
+
+        let edits = self.text_edits(&sema, files)?;
+        Ok(PathRewrite { edits, warnings })
+    }
+}
+
+/// Rewrite the parts of a `use` tree under `old`, given the path of its
+/// enclosing trees; relative paths are collected for review instead
+fn rewrite_use_tree(
+    tree: &ast::UseTree,
+    prefix: &str,
+    old: &str,
+    new: &str,
+    top_level: bool,
+    edits: &mut FileEdits,
+    relative: &mut Vec<(TextRange, String)>,
+) {
+    let own = tree.path().map(|path| compact(&path.syntax().text().to_string())).unwrap_or_default();
+    let full = match (prefix, own.as_str()) {
+        ("", _) => own.clone(),
+        (_, "") | (_, "self") => prefix.to_string(),
+        _ => format!("{}::{}", prefix, own),
+    };
+
+    if let Some(rest) = full.strip_prefix(old).filter(|rest| rest.is_empty() || rest.starts_with("::")) {
+        let rewritten = format!("{}{}", new, rest);
+        match (top_level, tree.path()) {
+            (true, Some(path)) => edits.replacements.push((path.syntax().text_range(), rewritten)),
+            _ => {
+                let suffix = match (tree.use_tree_list(), tree.star_token(), tree.rename()) {
+                    (Some(list), _, _) => format!("::{}", list.syntax().text()),
+                    (None, Some(_), _) => "::*".to_string(),
+                    (None, None, Some(rename)) => format!(" {}", rename.syntax().text()),
+                    (None, None, None) => String::new(),
+                };
+                edits.replacements.push((leaf_removal_range(tree), String::new()));
+                edits.imports.push(format!("use {}{};", rewritten, suffix));
+            }
+        }
+        return;
+    }
+
+    match tree.use_tree_list() {
+        Some(list) => {
+            for child in list.use_trees() {
+                rewrite_use_tree(&child, &full, old, new, false, edits, relative);
+            }
+        }
+        None if is_relative(&full) => relative.push((tree.syntax().text_range(), full)),
+        None => {}
+    }
+}
+
+/// How a path is spelled in a file of `crate_name`: `crate::` paths inside
+/// the crate, the crate name outside of it
+fn local_spelling(path: &str, crate_name: Option<&str>) -> String {
+    let Some(crate_name) = crate_name else { return path.to_string() };
+    match path.split_once("::") {
+        Some((first, rest)) if first == crate_name => format!("crate::{}", rest),
+        _ => path.to_string(),
+    }
+}
+
+fn is_relative(path: &str) -> bool {
+    path.starts_with("self::") || path.starts_with("super::")
+}
+
+fn is_path(path: &str) -> bool {
+    !path.is_empty()
+        && path
+            .split("::")
+            .enumerate()
+            .all(|(i, segment)| !segment.is_empty() && (i > 0 || segment != "self") && segment.chars().all(|c| c == '_' || c.is_alphanumeric()))
+}
+
+/// Path text with all whitespace removed
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ra_ap_syntax::{Edition, SourceFile};
+
+    fn rewrite(src: &str, old: &str, new: &str) -> (Vec<(TextRange, String)>, Vec<String>) {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        let mut edits = FileEdits::default();
+        let mut relative = Vec::new();
+        for use_ in file.syntax().descendants().filter_map(ast::Use::cast) {
+            rewrite_use_tree(&use_.use_tree().unwrap(), "", old, new, true, &mut edits, &mut relative);
+        }
+        (edits.replacements, edits.imports)
+    }
+
+    #[test]
+    fn test_top_level_uses_are_rewritten_in_place() {
+        let (replacements, imports) = rewrite("use crate::util::parse;\nuse crate::util::parse::Options as Opts;", "crate::util::parse", "crate::parsing::parse");
+        let texts: Vec<&str> = replacements.iter().map(|(_, text)| text.as_str()).collect();
+        assert_eq!(texts, vec!["crate::parsing::parse", "crate::parsing::parse::Options"]);
+        assert!(imports.is_empty());
+    }
+
+    #[test]
+    fn test_nested_uses_are_split_out() {
+        let src = "use crate::util::{parse as p, Other};";
+        let (replacements, imports) = rewrite(src, "crate::util::parse", "crate::parsing::parse");
+        assert_eq!(&src[replacements[0].0], "parse as p, ");
+        assert_eq!(imports, vec!["use crate::parsing::parse as p;"]);
+
+        let (_, imports) = rewrite("use crate::{util::{a, b}, Other};", "crate::util", "crate::helpers");
+        assert_eq!(imports, vec!["use crate::helpers::{a, b};"]);
+    }
+
+    #[test]
+    fn test_spellings() {
+        assert_eq!(local_spelling("cratographer::analyzer::Analyzer", Some("cratographer")), "crate::analyzer::Analyzer");
+        assert_eq!(local_spelling("cratographer::analyzer::Analyzer", Some("other")), "cratographer::analyzer::Analyzer");
+        assert!(is_path("crate::a::B"));
+        assert!(!is_path("crate::a::"));
+        assert!(!is_path("self::a"));
+    }
+
+    #[test]
+    fn test_rewrite_paths_in_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `use super::move_item::{leaf_removal_range, FileEdits, TextEdit};` above is relative, so only warned about
+        let plan = analyzer.rewrite_paths("crate::analyzer::move_item::FileEdits", "crate::analyzer::edits::FileEdits").unwrap();
+        assert!(plan.edits.is_empty());
+        assert!(plan.warnings.iter().any(|w| w.contains("rewrite_paths.rs")));
+
+        assert!(analyzer.rewrite_paths("crate::a", "crate::a").is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Runnables: tests, benchmarks, doctests and binaries
+//!
+//! rust-analyzer's runnables are what an editor shows "Run" and "Debug"
+//! lenses for. Each comes here with the exact cargo command line that runs
+//! it, and nothing else, so after an edit the right test can be run without
+//! working out the package, target and test path by hand.
+
+use super::features::CfgExpr;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FileId, RunnableKind};
+use ra_ap_project_model::{CargoWorkspace, ProjectWorkspaceKind, TargetKind};
+
+/// Kinds of runnable
+pub const RUNNABLE_KINDS: &[&str] = &["test", "test_module", "bench", "doctest", "bin"];
+
+/// A test, benchmark, doctest or binary
+#[derive(Debug, Clone)]
+pub struct Runnable {
+    /// One of `RUNNABLE_KINDS`
+    pub kind: &'static str,
+    /// Test path within its target (e.g. `analyzer::tests::test_new`), or
+    /// the binary's name
+    pub name: String,
+    pub crate_name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The `cfg` the runnable is gated on, e.g. `feature = "serde"`
+    pub cfg: Option<String>,
+    /// The command line running just this, as arguments; `None` outside a Cargo workspace
+    pub command: Option<Vec<String>>,
+}
+
+impl Runnable {
+    /// The command line as one string for a POSIX shell
+    pub fn command_line(&self) -> Option<String> {
+        self.command.as_deref().map(shell_command)
+    }
+}
+
+impl Analyzer {
+    /// The runnables of one file, in file order
+    pub fn file_runnables(&self, file_path: &str) -> Result<Vec<Runnable>, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        self.runnables_in(file_id)
+    }
+
+    /// The runnables of every file of the workspace crate `crate_name`, by
+    /// file and then in file order
+    pub fn crate_runnables(&self, crate_name: &str) -> Result<Vec<Runnable>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let wanted = crate_name.replace('-', "_");
+        let mut files: Vec<(String, FileId)> = self
+            .workspace_files()?
+            .into_iter()
+            .filter(|&file_id| {
+                sema.file_to_module_def(file_id).is_some_and(|module| {
+                    module.krate().display_name(db).is_some_and(|name| name.to_string().replace('-', "_") == wanted)
+                })
+            })
+            .map(|file_id| (self.file_path(file_id), file_id))
+            .collect();
+        if files.is_empty() {
+            return Err(AnalyzerError::Other(format!("No workspace crate named '{}'", crate_name)));
+        }
+        files.sort();
+
+        let mut runnables = Vec::new();
+        for (_, file_id) in files {
+            runnables.extend(self.runnables_in(file_id)?);
+        }
+        Ok(runnables)
+    }
+
+    pub(super) fn runnables_in(&self, file_id: FileId) -> Result<Vec<Runnable>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let analysis = self.host.analysis();
+        let krate = sema.file_to_module_def(file_id).map(|module| module.krate());
+        let crate_name = krate
+            .and_then(|krate| krate.display_name(db))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| "unnamed".to_string());
+        let target = krate.and_then(|krate| self.cargo_target(&self.file_path(krate.root_file(db))));
+
+        let mut runnables = Vec::new();
+        for runnable in analysis.runnables(file_id).map_err(|_| AnalyzerError::Canceled)? {
+            let (kind, name) = match &runnable.kind {
+                RunnableKind::Test { test_id, .. } => ("test", test_id.to_string()),
+                RunnableKind::TestMod { path } => ("test_module", path.clone()),
+                RunnableKind::Bench { test_id } => ("bench", test_id.to_string()),
+                RunnableKind::DocTest { test_id } => ("doctest", test_id.to_string()),
+                RunnableKind::Bin => {
+                    ("bin", target.as_ref().map(|t| t.name.clone()).unwrap_or_else(|| crate_name.clone()))
+                }
+            };
+            let (start_line, end_line) = self.line_range(runnable.nav.file_id, runnable.nav.full_range)?;
+            let cfg = runnable.cfg.as_ref().map(|cfg| cfg.to_string());
+            let features = cfg.as_deref().and_then(CfgExpr::parse).map(|cfg| required_features(&cfg)).unwrap_or_default();
+            let command = target.as_ref().map(|target| target.command(kind, &name, &features));
+            runnables.push(Runnable {
+                kind,
+                name,
+                crate_name: crate_name.clone(),
+                file_path: self.file_path(runnable.nav.file_id),
+                start_line,
+                end_line,
+                cfg,
+                command,
+            });
+        }
+        Ok(runnables)
+    }
+
+    /// The Cargo package and target whose root file is `root`
+    fn cargo_target(&self, root: &str) -> Option<CargoTarget> {
+        let workspace = self.workspace.as_ref()?;
+        let ProjectWorkspaceKind::Cargo { cargo, .. } = &workspace.kind else { return None };
+        CargoTarget::find(cargo, root)
+    }
+}
+
+/// Where a runnable lives, as cargo addresses it
+#[derive(Debug, Clone)]
+struct CargoTarget {
+    package: String,
+    name: String,
+    kind: TargetKind,
+}
+
+impl CargoTarget {
+    fn find(cargo: &CargoWorkspace, root: &str) -> Option<Self> {
+        cargo.packages().find_map(|package| {
+            cargo[package].targets.iter().map(|&target| &cargo[target]).find(|target| target.root.as_str() == root).map(
+                |target| CargoTarget { package: cargo[package].name.clone(), name: target.name.clone(), kind: target.kind },
+            )
+        })
+    }
+
+    /// The arguments selecting the target, e.g. `--lib` or `--test integration`
+    fn selector(&self) -> Vec<String> {
+        match self.kind {
+            TargetKind::Lib { .. } => vec!["--lib".to_string()],
+            TargetKind::Bin => vec!["--bin".to_string(), self.name.clone()],
+            TargetKind::Test => vec!["--test".to_string(), self.name.clone()],
+            TargetKind::Bench => vec!["--bench".to_string(), self.name.clone()],
+            TargetKind::Example => vec!["--example".to_string(), self.name.clone()],
+            TargetKind::BuildScript | TargetKind::Other => Vec::new(),
+        }
+    }
+
+    /// The cargo command line running a runnable of this target
+    fn command(&self, kind: &str, name: &str, features: &[String]) -> Vec<String> {
+        let (subcommand, selector) = match kind {
+            "bin" => ("run", self.selector()),
+            "bench" => ("bench", self.selector()),
+            "doctest" => ("test", vec!["--doc".to_string()]),
+            _ => ("test", self.selector()),
+        };
+        let mut args = vec!["cargo".to_string(), subcommand.to_string(), "--package".to_string(), self.package.clone()];
+        args.extend(selector);
+        if !features.is_empty() {
+            args.extend(["--features".to_string(), features.join(",")]);
+        }
+        match kind {
+            "test" | "bench" => args.extend(["--".to_string(), name.to_string(), "--exact".to_string()]),
+            // A module's path selects every test below it
+            "test_module" | "doctest" => args.extend(["--".to_string(), name.to_string()]),
+            _ => {}
+        }
+        args
+    }
+}
+
+/// The features a `cfg` requires to be enabled, as opposed to merely mentions
+fn required_features(cfg: &CfgExpr) -> Vec<String> {
+    match cfg {
+        CfgExpr::KeyValue(key, value) if key == "feature" => vec![value.clone()],
+        CfgExpr::All(exprs) => exprs.iter().flat_map(required_features).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Render a command line for a POSIX shell, quoting arguments where needed
+fn shell_command(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            let plain = !arg.is_empty()
+                && arg.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | ':' | '.' | '/' | ',' | '='));
+            if plain {
+                arg.clone()
+            } else {
+                format!("'{}'", arg.replace('\'', "'\\''"))
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_commands() {
+        let lib = CargoTarget { package: "app".to_string(), name: "app".to_string(), kind: TargetKind::Lib { is_proc_macro: false } };
+        assert_eq!(
+            shell_command(&lib.command("test", "tests::it_works", &[])),
+            "cargo test --package app --lib -- tests::it_works --exact"
+        );
+        assert_eq!(
+            shell_command(&lib.command("doctest", "Parser<T>::new", &["serde".to_string()])),
+            "cargo test --package app --doc --features serde -- 'Parser<T>::new'"
+        );
+        let bin = CargoTarget { package: "app".to_string(), name: "cli".to_string(), kind: TargetKind::Bin };
+        assert_eq!(shell_command(&bin.command("bin", "cli", &[])), "cargo run --package app --bin cli");
+
+        let cfg = CfgExpr::parse("all(feature = \"a\", not(feature = \"b\"), unix)").unwrap();
+        assert_eq!(required_features(&cfg), vec!["a"]);
+    }
+
+    #[test]
+    fn test_runnables_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/runnables.rs").unwrap();
+
+        let runnables = analyzer.file_runnables(path.to_str().unwrap()).unwrap();
+        let test = runnables.iter().find(|r| r.kind == "test" && r.name.ends_with("test_commands")).unwrap();
+        let command = test.command.as_ref().unwrap();
+        assert_eq!(&command[..5], ["cargo", "test", "--package", "cratographer", "--bin"]);
+        assert!(command.contains(&"--exact".to_string()));
+
+        let all = analyzer.crate_runnables("cratographer").unwrap();
+        assert!(all.iter().any(|r| r.kind == "bin" && r.name == "cratographer"));
+        assert!(analyzer.crate_runnables("no_such_crate").is_err());
+    }
+}
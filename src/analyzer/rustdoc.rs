@@ -0,0 +1,243 @@
+//! Rustdoc cross-links
+//!
+//! Maps a definition to where `cargo doc` puts it, so results can deep-link
+//! into a locally generated `target/doc` or an internal doc host. Follows
+//! rustdoc's URL scheme:
+//!
+//! - Items get their own page, `crate/module/<kind>.<Name>.html`, with kinds
+//!   `struct`, `enum`, `union`, `trait`, `type`, `fn`, `constant`, `static`
+//!   and `macro`; modules get `crate/module/index.html`.
+//! - Items are documented in the module defining them when every module on
+//!   the way there is public. Otherwise rustdoc inlines them where they're
+//!   re-exported, so `std::collections::HashMap`, defined in the private
+//!   `std::collections::hash::map`, is linked at its shortest path instead.
+//! - Macros are documented at the crate root, where `#[macro_export]` puts them.
+//! - Members live on their parent's page behind an anchor: `#method.name`
+//!   (`#tymethod.name` for required trait methods), `#associatedconstant.NAME`,
+//!   `#associatedtype.Name`, `#structfield.name`, `#variant.Name` and
+//!   `#variant.Name.field.name`.
+//!
+//! Items only reachable through private modules are only documented with
+//! `--document-private-items`; their links are still reported.
+//!
+//! Library items also get the absolute URL of their published docs: the
+//! standard library's crates on doc.rust-lang.org, other crates on docs.rs
+//! at the version loaded, so the page matches the code being analyzed.
+
+use super::{definition_at, Analyzer};
+use ra_ap_hir::{
+    Adt, AssocItemContainer, Crate, FindPathConfig, HasVisibility, ItemInNs, Module, ModuleDef, PathKind, Semantics, VariantDef, Visibility,
+};
+use ra_ap_ide::{FileId, RootDatabase};
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::TextSize;
+use serde::{Deserialize, Serialize};
+
+/// Where rustdoc documents a definition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RustdocLink {
+    /// Item path as rustdoc shows it, e.g. `cratographer::analyzer::Analyzer::new`
+    pub path: String,
+    /// Page relative to the doc root, with an anchor for members, e.g.
+    /// `cratographer/analyzer/struct.Analyzer.html#method.new`
+    pub url: String,
+    /// Published docs of a library item, e.g.
+    /// `https://docs.rs/serde/1.0.219/serde/trait.Serialize.html`; `None` for workspace items
+    #[serde(default)]
+    pub docs_url: Option<String>,
+}
+
+impl Analyzer {
+    /// Rustdoc link of the definition whose name is at `offset` in a file
+    pub(super) fn rustdoc_at(&self, sema: &Semantics<'_, RootDatabase>, file_id: FileId, offset: TextSize) -> Option<RustdocLink> {
+        rustdoc_link(sema.db, definition_at(sema, file_id, offset)?)
+    }
+}
+
+/// Build the rustdoc link of a definition
+fn rustdoc_link(db: &RootDatabase, def: Definition) -> Option<RustdocLink> {
+    let (page, member, anchor) = match def {
+        Definition::Variant(variant) => {
+            let name = variant.name(db).as_str().to_string();
+            (Definition::Adt(Adt::Enum(variant.parent_enum(db))), vec![name.clone()], format!("variant.{}", name))
+        }
+        Definition::Field(field) => {
+            let name = field.name(db).as_str().to_string();
+            match field.parent_def(db) {
+                VariantDef::Variant(variant) => {
+                    let variant_name = variant.name(db).as_str().to_string();
+                    let anchor = format!("variant.{}.field.{}", variant_name, name);
+                    (Definition::Adt(Adt::Enum(variant.parent_enum(db))), vec![variant_name, name], anchor)
+                }
+                VariantDef::Struct(it) => (Definition::Adt(Adt::Struct(it)), vec![name.clone()], format!("structfield.{}", name)),
+                VariantDef::Union(it) => (Definition::Adt(Adt::Union(it)), vec![name.clone()], format!("structfield.{}", name)),
+            }
+        }
+        Definition::Function(it) if it.as_assoc_item(db).is_some() => {
+            let container = it.as_assoc_item(db)?.container(db);
+            let kind = match container {
+                AssocItemContainer::Trait(_) if !it.has_body(db) => "tymethod",
+                _ => "method",
+            };
+            member_of(db, container, def, kind)?
+        }
+        Definition::Const(it) if it.as_assoc_item(db).is_some() => {
+            member_of(db, it.as_assoc_item(db)?.container(db), def, "associatedconstant")?
+        }
+        Definition::TypeAlias(it) if it.as_assoc_item(db).is_some() => {
+            member_of(db, it.as_assoc_item(db)?.container(db), def, "associatedtype")?
+        }
+        _ => return page_link(db, def),
+    };
+
+    let parent = page_link(db, page)?;
+    Some(RustdocLink {
+        path: std::iter::once(parent.path).chain(member).collect::<Vec<_>>().join("::"),
+        url: format!("{}#{}", parent.url, anchor),
+        docs_url: parent.docs_url.map(|docs_url| format!("{}#{}", docs_url, anchor)),
+    })
+}
+
+/// Page, path segments and anchor of an associated item
+///
+/// Items of impls are documented on the page of the impl's self type, so
+/// impls on types without a page (primitives, references) have no link.
+fn member_of(
+    db: &RootDatabase,
+    container: AssocItemContainer,
+    def: Definition,
+    kind: &str,
+) -> Option<(Definition, Vec<String>, String)> {
+    let page = match container {
+        AssocItemContainer::Trait(trait_) => Definition::Trait(trait_),
+        AssocItemContainer::Impl(impl_) => Definition::Adt(impl_.self_ty(db).as_adt()?),
+    };
+    let name = def.name(db)?.as_str().to_string();
+    Some((page, vec![name.clone()], format!("{}.{}", kind, name)))
+}
+
+/// Link of an item with its own page
+fn page_link(db: &RootDatabase, def: Definition) -> Option<RustdocLink> {
+    let (prefix, item) = match def {
+        Definition::Module(it) => (None, ModuleDef::Module(it)),
+        Definition::Adt(it @ Adt::Struct(_)) => (Some("struct"), ModuleDef::Adt(it)),
+        Definition::Adt(it @ Adt::Enum(_)) => (Some("enum"), ModuleDef::Adt(it)),
+        Definition::Adt(it @ Adt::Union(_)) => (Some("union"), ModuleDef::Adt(it)),
+        Definition::Trait(it) => (Some("trait"), ModuleDef::Trait(it)),
+        Definition::TypeAlias(it) => (Some("type"), ModuleDef::TypeAlias(it)),
+        Definition::Function(it) => (Some("fn"), ModuleDef::Function(it)),
+        Definition::Const(it) => (Some("constant"), ModuleDef::Const(it)),
+        Definition::Static(it) => (Some("static"), ModuleDef::Static(it)),
+        Definition::Macro(it) => (Some("macro"), ModuleDef::Macro(it)),
+        _ => return None,
+    };
+
+    let module = def.module(db)?;
+    let krate = module.krate();
+    let mut segments = vec![krate.display_name(db)?.to_string().replace('-', "_")];
+    if !matches!(def, Definition::Macro(_)) {
+        let defining: Vec<Module> = module.path_to_root(db).into_iter().rev().collect();
+        let names = || -> Vec<String> { defining.iter().filter_map(|m| m.name(db)).map(|n| n.as_str().to_string()).collect() };
+        if defining.iter().all(|m| m.is_crate_root(db) || m.visibility(db) == Visibility::Public) {
+            segments.extend(names());
+        } else {
+            segments.extend(reexport_modules(db, item, module).unwrap_or_else(names));
+        }
+    }
+    // `def.module` of a module is its parent, so a module's own name comes last like any item's
+    let name = def.name(db)?.as_str().to_string();
+    let url = match prefix {
+        None => format!("{}/{}/index.html", segments.join("/"), name),
+        Some(prefix) => format!("{}/{}.{}.html", segments.join("/"), prefix, name),
+    };
+    segments.push(name);
+    let path = segments.join("::");
+    Some(RustdocLink { docs_url: docs_url(db, krate, &url), path, url })
+}
+
+/// Modules on the shortest path to an item from its crate's root
+///
+/// Only modules visible from the root are followed, so an item defined in a
+/// private module is found through its re-export.
+fn reexport_modules(db: &RootDatabase, item: ModuleDef, module: Module) -> Option<Vec<String>> {
+    let root = module.krate().root_module(db);
+    let config = FindPathConfig { prefer_no_std: false, prefer_prelude: false, prefer_absolute: false, allow_unstable: true };
+    let path = root.find_path(db, ItemInNs::from(item), config)?;
+    if path.kind != PathKind::Plain || path.segments().is_empty() {
+        return None;
+    }
+    let (_, modules) = path.segments().split_last()?;
+    Some(modules.iter().map(|name| name.as_str().to_string()).collect())
+}
+
+/// Absolute URL of a page of a library crate's published docs
+pub(super) fn docs_url(db: &RootDatabase, krate: Crate, url: &str) -> Option<String> {
+    let origin = krate.origin(db);
+    if origin.is_local() {
+        return None;
+    }
+    if origin.is_lang() {
+        return Some(format!("https://doc.rust-lang.org/stable/{}", url));
+    }
+    // docs.rs knows packages by their published name, not the name they're imported under
+    let name = krate.display_name(db)?.canonical_name().as_str().to_string();
+    let version = krate.version(db).unwrap_or_else(|| "latest".to_string());
+    Some(format!("https://docs.rs/{}/{}/{}", name, version, url))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::{Analyzer, SearchMode, SearchOptions};
+
+    #[test]
+    fn test_rustdoc_links_of_cratographer_items() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let options = SearchOptions { mode: SearchMode::Exact, ..Default::default() };
+
+        let link = |name: &str, path: &str| {
+            analyzer
+                .find_symbol(name, &options)
+                .unwrap()
+                .into_iter()
+                .filter_map(|s| s.rustdoc)
+                .find(|link| link.path == path)
+                .unwrap_or_else(|| panic!("No rustdoc link for {}", path))
+                .url
+        };
+
+        assert_eq!(link("SymbolInfo", "cratographer::analyzer::SymbolInfo"), "cratographer/analyzer/struct.SymbolInfo.html");
+        assert_eq!(
+            link("enumerate_file", "cratographer::analyzer::Analyzer::enumerate_file"),
+            "cratographer/analyzer/struct.Analyzer.html#method.enumerate_file"
+        );
+        assert_eq!(link("analyzer", "cratographer::analyzer"), "cratographer/analyzer/index.html");
+    }
+
+    #[test]
+    fn test_docs_urls_of_library_items() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let options = SearchOptions { mode: SearchMode::Exact, include_library: true, ..Default::default() };
+
+        let links = |name: &str| -> Vec<_> {
+            analyzer.find_symbol(name, &options).unwrap().into_iter().filter_map(|s| s.rustdoc).collect()
+        };
+
+        // Standard library items link to doc.rust-lang.org, at their public
+        // path rather than the private module defining them
+        let hash_map = links("HashMap").into_iter().find(|link| link.path == "std::collections::HashMap").unwrap();
+        assert_eq!(hash_map.url, "std/collections/struct.HashMap.html");
+        assert_eq!(hash_map.docs_url.as_deref(), Some("https://doc.rust-lang.org/stable/std/collections/struct.HashMap.html"));
+        assert!(links("HashMap").iter().all(|link| !link.url.contains("/hash/map/")));
+
+        // Dependencies link to docs.rs at the version loaded
+        let serialize = links("Serialize").into_iter().find(|link| link.path == "serde_core::ser::Serialize").unwrap();
+        assert_eq!(serialize.url, "serde_core/ser/trait.Serialize.html");
+        let (version, page) = serialize.docs_url.as_deref().unwrap().strip_prefix("https://docs.rs/serde_core/").unwrap().split_once('/').unwrap();
+        assert!(version.split('.').all(|part| part.parse::<u32>().is_ok()));
+        assert_eq!(page, "serde_core/ser/trait.Serialize.html");
+        // Workspace items have no published docs
+        assert!(links("SymbolInfo").iter().all(|link| link.docs_url.is_none()));
+    }
+}
@@ -0,0 +1,322 @@
+//! Scratch roots analyzed against the project
+//!
+//! A scratch directory holds candidate code that isn't part of the tree yet,
+//! such as a file an agent is about to propose. Registering it against a
+//! workspace crate mounts each of its `.rs` files in memory as a module of
+//! that crate: the file is added to the VFS next to the crate root under a
+//! reserved name, and a `mod` declaration for it is appended to the
+//! in-memory text of the crate root. Nothing is written to disk.
+//!
+//! A mounted file resolves `crate::` paths, the crate's dependencies and std
+//! like any module of the crate, so diagnostics, hovers and navigation work
+//! on it before it is written into the real tree. Tools take and report the
+//! scratch file's own path. Scratch directories aren't watched; registering
+//! one again picks up edits.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Crate;
+use ra_ap_ide::FileId;
+use ra_ap_paths::{AbsPathBuf, Utf8PathBuf};
+use std::path::{Path, PathBuf};
+
+/// Prefix of the modules scratch files are mounted as
+const SCRATCH_MODULE_PREFIX: &str = "__scratch_";
+
+/// A scratch file mounted into a workspace crate
+#[derive(Debug, Clone)]
+pub struct ScratchFile {
+    /// Path of the file in the scratch directory
+    pub file_path: String,
+    /// The module it is mounted as, e.g. `crate::__scratch_candidate`
+    pub module: String,
+    /// The crate it is analyzed as part of
+    pub crate_name: String,
+}
+
+#[derive(Debug, Clone)]
+struct MountedFile {
+    directory: PathBuf,
+    file: ScratchFile,
+    /// Where the file lives in the VFS, next to the crate root
+    virtual_path: String,
+    /// Path of the crate root the `mod` declaration is appended to
+    host_root: String,
+}
+
+/// The scratch files currently mounted
+#[derive(Debug, Default)]
+pub(super) struct ScratchMounts {
+    files: Vec<MountedFile>,
+}
+
+impl ScratchMounts {
+    /// The VFS path a scratch file is mounted at
+    pub(super) fn virtual_path(&self, file_path: &str) -> Option<&str> {
+        self.files.iter().find(|m| m.file.file_path == file_path).map(|m| m.virtual_path.as_str())
+    }
+
+    /// The scratch file mounted at a VFS path
+    pub(super) fn scratch_path(&self, virtual_path: &str) -> Option<&str> {
+        self.files.iter().find(|m| m.virtual_path == virtual_path).map(|m| m.file.file_path.as_str())
+    }
+
+    /// `text` of a crate root with the `mod` declarations of the files mounted into it
+    pub(super) fn with_declarations(&self, root_path: &str, text: &str) -> Option<String> {
+        let mut mounted = self.files.iter().filter(|m| m.host_root == root_path).peekable();
+        mounted.peek()?;
+        let mut text = text.to_string();
+        if !text.ends_with('\n') {
+            text.push('\n');
+        }
+        for m in mounted {
+            let name = m.file.module.rsplit("::").next().unwrap_or_default();
+            text.push_str(&format!("#[allow(dead_code, unused_imports)]\nmod {};\n", name));
+        }
+        Some(text)
+    }
+}
+
+impl Analyzer {
+    /// Mount the `.rs` files of `directory` as modules of the workspace crate
+    /// `crate_name`, replacing any earlier mount of the same directory
+    ///
+    /// Returns every file mounted from the directory.
+    pub fn add_scratch_root(&mut self, directory: &str, crate_name: &str) -> Result<Vec<ScratchFile>, AnalyzerError> {
+        let directory = Path::new(directory).canonicalize()?;
+        let host_root = self.workspace_crate_root(crate_name)?;
+        let root_dir = Path::new(&host_root).parent().map(Path::to_path_buf).unwrap_or_default();
+
+        let mut sources: Vec<PathBuf> = std::fs::read_dir(&directory)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "rs"))
+            .collect();
+        sources.sort();
+        if sources.is_empty() {
+            return Err(AnalyzerError::Other(format!("No .rs files in {}", directory.display())));
+        }
+
+        // Nothing is mounted until every file has been read, so a failure
+        // leaves the earlier mounts as they were
+        let (replaced, mut files): (Vec<MountedFile>, Vec<MountedFile>) =
+            self.scratch.files.iter().cloned().partition(|m| m.directory == directory);
+        let mut changes = unmount_changes(&replaced);
+        let mut mounted = Vec::new();
+        for source in sources {
+            let stem = source.file_stem().map(|stem| stem.to_string_lossy().into_owned()).unwrap_or_default();
+            let name: String = stem.chars().map(|c| if c.is_alphanumeric() { c } else { '_' }).collect();
+            // Files of other scratch directories, or `a-b.rs` next to `a_b.rs`,
+            // may sanitize to a module already mounted; number the later ones
+            let mut module_name = format!("{}{}", SCRATCH_MODULE_PREFIX, name);
+            let mut virtual_path = root_dir.join(format!("{}.rs", module_name));
+            let mut suffix = 1;
+            while files.iter().any(|m| Path::new(&m.virtual_path) == virtual_path) {
+                suffix += 1;
+                module_name = format!("{}{}_{}", SCRATCH_MODULE_PREFIX, name, suffix);
+                virtual_path = root_dir.join(format!("{}.rs", module_name));
+            }
+            if virtual_path.exists() {
+                return Err(AnalyzerError::Other(format!("{} already exists in the crate", virtual_path.display())));
+            }
+
+            let file = ScratchFile {
+                file_path: source.to_string_lossy().into_owned(),
+                module: format!("crate::{}", module_name),
+                crate_name: crate_name.to_string(),
+            };
+            changes.push((abs_path(&virtual_path), Some(std::fs::read(&source)?)));
+            files.push(MountedFile {
+                directory: directory.clone(),
+                file: file.clone(),
+                virtual_path: virtual_path.to_string_lossy().into_owned(),
+                host_root: host_root.clone(),
+            });
+            mounted.push(file);
+        }
+
+        // The crate root's text from disk, with the declarations appended by
+        // `apply_file_changes`
+        changes.push((abs_path(Path::new(&host_root)), Some(std::fs::read(&host_root)?)));
+        self.scratch.files = files;
+        self.apply_file_changes(changes)?;
+        Ok(mounted)
+    }
+
+    /// Unmount the files of a scratch directory, restoring the crate roots they were mounted into
+    pub fn remove_scratch_root(&mut self, directory: &str) -> Result<Vec<ScratchFile>, AnalyzerError> {
+        let directory = Path::new(directory).canonicalize()?;
+        let removed: Vec<ScratchFile> =
+            self.scratch.files.iter().filter(|m| m.directory == directory).map(|m| m.file.clone()).collect();
+        if removed.is_empty() {
+            return Err(AnalyzerError::Other(format!("{} is not a scratch root", directory.display())));
+        }
+
+        let changes = self.unmount_directory(&directory);
+        self.apply_file_changes(changes)?;
+        Ok(removed)
+    }
+
+    /// The mounted scratch files
+    pub fn scratch_files(&self) -> Vec<ScratchFile> {
+        self.scratch.files.iter().map(|m| m.file.clone()).collect()
+    }
+
+    /// Forget the mounts of a directory, returning the VFS changes that undo them
+    fn unmount_directory(&mut self, directory: &Path) -> Vec<(AbsPathBuf, Option<Vec<u8>>)> {
+        let (removed, kept): (Vec<MountedFile>, Vec<MountedFile>) =
+            std::mem::take(&mut self.scratch.files).into_iter().partition(|m| m.directory == directory);
+        self.scratch.files = kept;
+        unmount_changes(&removed)
+    }
+
+    /// Path of the root file of the workspace crate `crate_name`; for a
+    /// package with a library and binaries, the library's
+    fn workspace_crate_root(&self, crate_name: &str) -> Result<String, AnalyzerError> {
+        let db = self.host.raw_database();
+        let wanted = crate_name.replace('-', "_");
+        let mut roots: Vec<FileId> = Crate::all(db)
+            .into_iter()
+            .filter(|krate| krate.origin(db).is_local())
+            .filter(|krate| krate.display_name(db).is_some_and(|name| name.to_string().replace('-', "_") == wanted))
+            .map(|krate| krate.root_file(db))
+            .collect();
+        roots.sort_by_key(|&root| !self.file_path(root).ends_with("lib.rs"));
+        roots
+            .first()
+            .map(|&root| self.file_path(root))
+            .ok_or_else(|| AnalyzerError::Other(format!("No workspace crate named '{}'", crate_name)))
+    }
+}
+
+fn abs_path(path: &Path) -> AbsPathBuf {
+    AbsPathBuf::assert(Utf8PathBuf::from(path.to_string_lossy().into_owned()))
+}
+
+/// The VFS changes that delete unmounted files and restore the crate roots
+/// they were mounted into
+fn unmount_changes(removed: &[MountedFile]) -> Vec<(AbsPathBuf, Option<Vec<u8>>)> {
+    let mut changes: Vec<(AbsPathBuf, Option<Vec<u8>>)> =
+        removed.iter().map(|m| (abs_path(Path::new(&m.virtual_path)), None)).collect();
+    let mut roots: Vec<&str> = removed.iter().map(|m| m.host_root.as_str()).collect();
+    roots.dedup();
+    for root in roots {
+        changes.push((abs_path(Path::new(root)), std::fs::read(root).ok()));
+    }
+    changes
+}
+
+/// This project's analyzer with a temporary scratch directory of probe files
+/// mounted into the `cratographer` crate
+///
+/// The directory is unmounted and deleted on drop, so a failing assertion
+/// doesn't leave it behind.
+#[cfg(test)]
+pub(super) struct ScratchProject {
+    analyzer: Analyzer,
+    directory: PathBuf,
+}
+
+#[cfg(test)]
+impl ScratchProject {
+    /// Load the project and mount `files`, given as (file name, contents),
+    /// from a directory named after `name` and the process
+    pub(super) fn new(name: &str, files: &[(&str, &str)]) -> Self {
+        let directory = std::env::temp_dir().join(format!("cratographer-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (file_name, contents) in files {
+            std::fs::write(directory.join(file_name), contents).unwrap();
+        }
+
+        // Built before mounting, so the directory is cleaned up if that fails
+        let mut project = ScratchProject { analyzer: Analyzer::new(), directory };
+        assert!(project.analyzer.load_project(".").is_ok());
+        project.analyzer.add_scratch_root(project.directory.to_str().unwrap(), "cratographer").unwrap();
+        project
+    }
+}
+
+#[cfg(test)]
+impl std::ops::Deref for ScratchProject {
+    type Target = Analyzer;
+
+    fn deref(&self) -> &Analyzer {
+        &self.analyzer
+    }
+}
+
+#[cfg(test)]
+impl std::ops::DerefMut for ScratchProject {
+    fn deref_mut(&mut self) -> &mut Analyzer {
+        &mut self.analyzer
+    }
+}
+
+#[cfg(test)]
+impl Drop for ScratchProject {
+    fn drop(&mut self) {
+        if let Some(directory) = self.directory.to_str() {
+            let _ = self.analyzer.remove_scratch_root(directory);
+        }
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scratch_file_is_analyzed_against_the_crate() {
+        let scratch = std::env::temp_dir().join(format!("cratographer-scratch-{}", std::process::id()));
+        std::fs::create_dir_all(&scratch).unwrap();
+        std::fs::write(scratch.join("candidate.rs"), "pub fn make() -> crate::analyzer::Analyzer {\n    crate::analyzer::Analyzer::new()\n}\n").unwrap();
+
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let mounted = analyzer.add_scratch_root(scratch.to_str().unwrap(), "cratographer").unwrap();
+        assert_eq!(mounted.len(), 1);
+        assert_eq!(mounted[0].module, "crate::__scratch_candidate");
+
+        // The scratch file is addressed by its own path and resolves project items
+        let file_path = &mounted[0].file_path;
+        let symbols = analyzer.enumerate_file(file_path).unwrap();
+        assert!(symbols.iter().any(|sym| sym.name == "make"));
+        let definitions = analyzer.goto_definition(file_path, 1, 22).unwrap();
+        assert!(definitions.iter().any(|def| def.file_path.ends_with("src/analyzer.rs")));
+
+        analyzer.remove_scratch_root(scratch.to_str().unwrap()).unwrap();
+        assert!(analyzer.enumerate_file(file_path).is_err());
+        let _ = std::fs::remove_dir_all(&scratch);
+    }
+
+    #[test]
+    fn test_colliding_scratch_files_get_distinct_modules() {
+        let base = std::env::temp_dir().join(format!("cratographer-scratch-collisions-{}", std::process::id()));
+        let (first, second) = (base.join("first"), base.join("second"));
+        std::fs::create_dir_all(&first).unwrap();
+        std::fs::create_dir_all(&second).unwrap();
+        std::fs::write(first.join("util.rs"), "pub fn first() {}\n").unwrap();
+        std::fs::write(first.join("a-b.rs"), "pub fn dashed() {}\n").unwrap();
+        std::fs::write(first.join("a_b.rs"), "pub fn underscored() {}\n").unwrap();
+        std::fs::write(second.join("util.rs"), "pub fn second() {}\n").unwrap();
+
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        analyzer.add_scratch_root(first.to_str().unwrap(), "cratographer").unwrap();
+        let mounted = analyzer.add_scratch_root(second.to_str().unwrap(), "cratographer").unwrap();
+        assert_eq!(mounted[0].module, "crate::__scratch_util_2");
+
+        let files = analyzer.scratch_files();
+        let mut modules: Vec<_> = files.iter().map(|f| f.module.as_str()).collect();
+        modules.sort();
+        assert_eq!(modules, vec!["crate::__scratch_a_b", "crate::__scratch_a_b_2", "crate::__scratch_util", "crate::__scratch_util_2"]);
+
+        // Both util.rs files keep their own contents
+        for (module, function) in [("crate::__scratch_util", "first"), ("crate::__scratch_util_2", "second")] {
+            let file = files.iter().find(|f| f.module == module).unwrap();
+            let symbols = analyzer.enumerate_file(&file.file_path).unwrap();
+            assert!(symbols.iter().any(|sym| sym.name == function));
+        }
+
+        let _ = std::fs::remove_dir_all(&base);
+    }
+}
@@ -0,0 +1,107 @@
+//! Semantic classification of a token
+//!
+//! What an editor's semantic highlighting knows about an identifier: whether
+//! it is a local, a field, a const generic parameter, a macro, a trait
+//! method and so on, and modifiers such as `mutable`, `declaration`,
+//! `unsafe` or `library`. This answers "what is this name" without hovering
+//! or jumping to the definition.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide::{FileRange, HighlightConfig};
+use ra_ap_syntax::AstNode;
+
+/// The classification of the token at a position
+#[derive(Debug, Clone)]
+pub struct TokenClass {
+    /// The token's text
+    pub text: String,
+    /// What it is, e.g. `variable`, `field`, `const_param`, `macro` or `method`
+    pub tag: String,
+    /// e.g. `mutable`, `declaration`, `reference`, `library` or `trait`
+    pub modifiers: Vec<String>,
+    /// Extent of the token (0-based)
+    pub line: u32,
+    pub start_column: u32,
+    pub end_column: u32,
+}
+
+/// Semantic highlighting of names and literals, with operators and
+/// punctuation told apart and doc comment code blocks left alone
+fn highlight_config() -> HighlightConfig {
+    HighlightConfig {
+        strings: true,
+        comments: true,
+        punctuation: true,
+        specialize_punctuation: true,
+        specialize_operator: true,
+        operator: true,
+        inject_doc_comment: false,
+        macro_bang: true,
+        syntactic_name_ref_highlighting: false,
+    }
+}
+
+impl Analyzer {
+    /// Classify the token at a position (0-based), or `None` on whitespace
+    /// and other tokens without a classification
+    pub fn classify_token(&self, file_path: &str, line: u32, column: u32) -> Result<Option<TokenClass>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let analysis = self.host.analysis();
+        let source = analysis.parse(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let Some(token) = source.syntax().token_at_offset(position.offset).right_biased() else { return Ok(None) };
+        let range = FileRange { file_id: position.file_id, range: token.text_range() };
+
+        let highlights = analysis.highlight_range(highlight_config(), range).map_err(|_| AnalyzerError::Canceled)?;
+        let Some(highlight) = highlights
+            .into_iter()
+            .filter(|hl| hl.range.contains_inclusive(position.offset))
+            .min_by_key(|hl| hl.range.len())
+        else {
+            return Ok(None);
+        };
+
+        // Rendered as the tag followed by its modifiers, e.g. `variable.mutable.declaration`
+        let rendered = highlight.highlight.to_string();
+        let mut parts = rendered.split('.').map(str::to_string);
+        let tag = parts.next().unwrap_or_default();
+        let line_index = analysis.file_line_index(position.file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let start = line_index.line_col(highlight.range.start());
+        let end = line_index.line_col(highlight.range.end());
+        Ok(Some(TokenClass {
+            text: token.text().to_string(),
+            tag,
+            modifiers: parts.collect(),
+            line: start.line,
+            start_column: start.col,
+            end_column: end.col,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_local_and_method() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // `let position = self.file_position(...)` in this file
+        let path = std::fs::canonicalize("src/analyzer/semantic_tokens.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("let position = self.file_position(")).unwrap();
+        let source_line = text.lines().nth(line).unwrap();
+
+        let column = source_line.find("position").unwrap() as u32;
+        let local = analyzer.classify_token(path, line as u32, column).unwrap().expect("Bindings are classified");
+        assert_eq!(local.text, "position");
+        assert_eq!(local.tag, "variable");
+        assert!(local.modifiers.contains(&"declaration".to_string()));
+
+        let column = source_line.find("file_position").unwrap() as u32;
+        let method = analyzer.classify_token(path, line as u32, column).unwrap().expect("Calls are classified");
+        assert_eq!(method.tag, "method");
+    }
+}
@@ -0,0 +1,231 @@
+//! Signature filters for symbol search
+//!
+//! Narrows `find_symbol` results to functions of a given shape — parameter
+//! count, a parameter type, asyncness, attributes carried or missing — so
+//! they can be found when the name is only vaguely known. This is a
+//! syntactic check of the signature as written, much lighter than a full
+//! type-based signature search.
+//!
+//! With an empty name, every workspace function is checked instead of the
+//! symbol search's top matches, for audits such as "async fns missing
+//! `#[tracing::instrument]`".
+
+use super::{Analyzer, AnalyzerError, SearchOptions, SymbolInfo, SymbolKind};
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{algo::find_node_at_range, ast::{self, HasAttrs}, AstNode, TextRange};
+
+/// Constraints on a function's signature; unset fields match anything
+#[derive(Debug, Clone, Default)]
+pub struct SignatureFilter {
+    /// A parameter's type must contain this text, e.g. "&mut Vec<u8>"
+    /// (whitespace is ignored)
+    pub params_contains: Option<String>,
+    /// Exact number of parameters, not counting a `self` receiver
+    pub num_params: Option<usize>,
+    /// Whether the function must (or must not) be `async`
+    pub is_async: Option<bool>,
+    /// An attribute the function must carry, by path, e.g. "must_use";
+    /// "instrument" also matches `tracing::instrument`
+    pub has_attribute: Option<String>,
+    /// An attribute the function must not carry, matched the same way
+    pub missing_attribute: Option<String>,
+}
+
+impl SignatureFilter {
+    /// Whether no constraint is set
+    pub fn is_empty(&self) -> bool {
+        self.params_contains.is_none()
+            && self.num_params.is_none()
+            && self.is_async.is_none()
+            && self.has_attribute.is_none()
+            && self.missing_attribute.is_none()
+    }
+
+    /// Whether a function's signature satisfies every constraint
+    pub(super) fn matches(&self, func: &ast::Fn) -> bool {
+        let params: Vec<String> = func
+            .param_list()
+            .into_iter()
+            .flat_map(|list| list.params())
+            .map(|param| param.ty().map(|ty| compact(&ty.syntax().text().to_string())).unwrap_or_default())
+            .collect();
+
+        self.num_params.is_none_or(|n| params.len() == n)
+            && self.is_async.is_none_or(|is_async| func.async_token().is_some() == is_async)
+            && self.params_contains.as_deref().is_none_or(|needle| {
+                let needle = compact(needle);
+                params.iter().any(|ty| ty.contains(&needle))
+            })
+            && self.attributes_match(&fn_attributes(func))
+    }
+
+    /// Whether a function's attributes satisfy the attribute constraints
+    fn attributes_match(&self, attributes: &[String]) -> bool {
+        let carries = |wanted: &str| attributes.iter().any(|attr| attribute_is(attr, wanted));
+        self.has_attribute.as_deref().is_none_or(carries) && !self.missing_attribute.as_deref().is_some_and(carries)
+    }
+}
+
+/// The attributes of a function as written, without `#[...]` and `doc`
+/// attributes, e.g. `inline(always)` or `tracing::instrument(skip(self))`
+pub(super) fn fn_attributes(func: &ast::Fn) -> Vec<String> {
+    func.attrs()
+        .filter_map(|attr| attr.meta())
+        .map(|meta| meta.syntax().text().to_string().split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|attr| attribute_path(attr) != "doc")
+        .collect()
+}
+
+/// The path of a rendered attribute, e.g. `tracing::instrument` for
+/// `tracing::instrument(skip(self))`
+fn attribute_path(attr: &str) -> String {
+    compact(attr.split(['(', '=']).next().unwrap_or(attr))
+}
+
+/// Whether an attribute has the wanted path, or ends with it
+fn attribute_is(attr: &str, wanted: &str) -> bool {
+    let path = attribute_path(attr);
+    let wanted = compact(wanted.trim_start_matches("#[").trim_end_matches(']'));
+    path == wanted || path.ends_with(&format!("::{}", wanted))
+}
+
+impl Analyzer {
+    /// Whether a symbol is a function whose signature passes the filter
+    pub(super) fn signature_matches(&self, symbol: &SymbolInfo, filter: &SignatureFilter) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+        let analysis = self.host.analysis();
+        let Ok(file_id) = self.file_id(&symbol.file_path) else { return false };
+        let (Ok(line_index), Ok(source)) = (analysis.file_line_index(file_id), analysis.parse(file_id)) else { return false };
+
+        source
+            .syntax()
+            .descendants()
+            .filter_map(ast::Fn::cast)
+            .filter(|func| line_index.line_col(func.syntax().text_range().start()).line == symbol.start_line)
+            .any(|func| filter.matches(&func))
+    }
+
+    /// The attributes of the function spanning `range` in a file, or none
+    /// if no function spans exactly that range
+    pub(super) fn fn_attributes_at(&self, file_id: FileId, range: TextRange) -> Vec<String> {
+        let Ok(source) = self.host.analysis().parse(file_id) else { return Vec::new() };
+        find_node_at_range::<ast::Fn>(source.syntax(), range)
+            .filter(|func| func.syntax().text_range() == range)
+            .map(|func| fn_attributes(&func))
+            .unwrap_or_default()
+    }
+
+    /// Every workspace function passing the search options, for searches
+    /// without a name
+    pub(super) fn find_workspace_functions(&self, options: &SearchOptions) -> Result<Vec<SymbolInfo>, AnalyzerError> {
+        let mut results = Vec::new();
+        for file_id in self.workspace_files()? {
+            let file_path = self.file_path(file_id);
+            results.extend(
+                self.file_symbols(file_id, &file_path)?
+                    .into_iter()
+                    .filter(|sym| matches!(sym.kind, SymbolKind::Function | SymbolKind::Method))
+                    .filter(|sym| options.accepts(sym))
+                    .filter(|sym| self.signature_matches(sym, &options.signature)),
+            );
+        }
+        Ok(results)
+    }
+}
+
+/// Type text with all whitespace removed, so `& mut T` and `&mut T` compare equal
+fn compact(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::{SearchMode, SymbolFilter};
+    use ra_ap_syntax::{ast::HasName, Edition, SourceFile};
+
+    fn matching(filter: &SignatureFilter, src: &str) -> Vec<String> {
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        file.syntax()
+            .descendants()
+            .filter_map(ast::Fn::cast)
+            .filter(|f| filter.matches(f))
+            .map(|f| f.syntax().text().to_string().split('(').next().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_signature_constraints() {
+        let src = "fn write(buf: &mut Vec<u8>, n: usize) {}
+                   async fn fetch(url: &str) {}
+                   fn method(&self, out: & mut Vec < u8 >) {}";
+
+        let by_type = SignatureFilter { params_contains: Some("&mut Vec<u8>".to_string()), ..Default::default() };
+        assert_eq!(matching(&by_type, src), vec!["fn write", "fn method"]);
+
+        let two = SignatureFilter { num_params: Some(2), ..Default::default() };
+        assert_eq!(matching(&two, src), vec!["fn write"]);
+
+        let asynchronous = SignatureFilter { is_async: Some(true), ..Default::default() };
+        assert_eq!(matching(&asynchronous, src), vec!["async fn fetch"]);
+    }
+
+    #[test]
+    fn test_attribute_constraints() {
+        let src = "#[inline(always)] #[must_use] fn first() {}
+                   #[tracing::instrument(skip(self))] async fn traced(&self) {}
+                   /// Docs
+                   async fn untraced() {}";
+
+        let file = SourceFile::parse(src, Edition::CURRENT).tree();
+        let attributes: Vec<Vec<String>> = file.syntax().descendants().filter_map(ast::Fn::cast).map(|f| fn_attributes(&f)).collect();
+        assert_eq!(attributes[0], vec!["inline(always)", "must_use"]);
+        assert_eq!(attributes[1], vec!["tracing::instrument(skip(self))"]);
+        assert!(attributes[2].is_empty());
+
+        let names = |filter: &SignatureFilter| -> Vec<String> {
+            file.syntax()
+                .descendants()
+                .filter_map(ast::Fn::cast)
+                .filter(|f| filter.matches(f))
+                .filter_map(|f| f.name().map(|name| name.text().to_string()))
+                .collect()
+        };
+        let must_use = SignatureFilter { has_attribute: Some("must_use".to_string()), ..Default::default() };
+        assert_eq!(names(&must_use), vec!["first"]);
+
+        // The last path segment is enough
+        let untraced = SignatureFilter {
+            is_async: Some(true),
+            missing_attribute: Some("instrument".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(names(&untraced), vec!["untraced"]);
+        let untraced = SignatureFilter { missing_attribute: Some("#[tracing::instrument]".to_string()), ..untraced };
+        assert_eq!(names(&untraced), vec!["untraced"]);
+    }
+
+    #[test]
+    fn test_find_symbol_with_signature_filter() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let options = SearchOptions {
+            mode: SearchMode::Exact,
+            include_library: false,
+            filter: SymbolFilter::Functions,
+            crate_version: None,
+            signature: SignatureFilter { num_params: Some(1), ..Default::default() },
+            crates_include: Vec::new(),
+            crates_exclude: Vec::new(),
+            prefer_workspace: false,
+        };
+        let results = analyzer.find_symbol("normalize_whitespace", &options).unwrap();
+        assert_eq!(results.len(), 1);
+
+        let options = SearchOptions { signature: SignatureFilter { num_params: Some(3), ..Default::default() }, ..options };
+        assert!(analyzer.find_symbol("normalize_whitespace", &options).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,91 @@
+//! Signature help at a call site
+//!
+//! What an editor shows while typing a call's arguments: the callee's
+//! signature, its parameters and which of them the position is in. Works
+//! for function and method calls, tuple struct and variant constructors,
+//! and generic argument lists.
+
+use super::{Analyzer, AnalyzerError};
+
+/// One parameter of the callee
+#[derive(Debug, Clone)]
+pub struct SignatureParameter {
+    /// The parameter as the signature shows it, e.g. `line: u32`
+    pub label: String,
+    /// The pattern before the colon, e.g. `line`; the whole label for
+    /// parameters without one, such as `&self` or a generic parameter
+    pub name: String,
+    pub ty: Option<String>,
+}
+
+/// The signature of the call around a position
+#[derive(Debug, Clone)]
+pub struct SignatureHelp {
+    /// The callee's signature, e.g. `fn hover(&self, file_path: &str, line: u32, column: u32) -> ...`
+    pub signature: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<SignatureParameter>,
+    /// Index into `parameters` of the argument the position is in, if any
+    pub active_parameter: Option<usize>,
+}
+
+impl Analyzer {
+    /// Signature help at a position (0-based) inside a call's argument list,
+    /// or `None` outside of one
+    pub fn signature_help(&self, file_path: &str, line: u32, column: u32) -> Result<Option<SignatureHelp>, AnalyzerError> {
+        let position = self.file_position(file_path, line, column)?;
+        let Some(help) = self.host.analysis().signature_help(position).map_err(|_| AnalyzerError::Canceled)? else {
+            return Ok(None);
+        };
+
+        let parameters = help
+            .parameter_labels()
+            .map(|label| {
+                let (name, ty) = match label.split_once(": ") {
+                    Some((name, ty)) => (name.to_string(), Some(ty.to_string())),
+                    None => (label.to_string(), None),
+                };
+                SignatureParameter { label: label.to_string(), name, ty }
+            })
+            .collect();
+        Ok(Some(SignatureHelp {
+            signature: help.signature.clone(),
+            documentation: help.doc.as_ref().map(|doc| doc.as_str().to_string()),
+            parameters,
+            active_parameter: help.active_parameter,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_help_in_call() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        // Inside the arguments of `self.file_position(file_path, line, column)` in this file
+        let path = std::fs::canonicalize("src/analyzer/signature_help.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+        let line = text.lines().position(|l| l.contains("self.file_position(file_path, line, column)")).unwrap();
+        let column = text.lines().nth(line).unwrap().find(", line").unwrap() as u32 + 2;
+
+        let help = analyzer.signature_help(path, line as u32, column).unwrap().expect("Calls have signature help");
+        assert!(help.signature.contains("file_position"));
+        assert_eq!(help.active_parameter, Some(1));
+        assert_eq!(help.parameters[1].name, "line");
+        assert_eq!(help.parameters[1].ty.as_deref(), Some("u32"));
+    }
+
+    #[test]
+    fn test_no_signature_help_outside_calls() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer/signature_help.rs").unwrap();
+        assert!(analyzer.signature_help(path.to_str().unwrap(), 0, 0).unwrap().is_none());
+    }
+}
@@ -0,0 +1,129 @@
+//! Checking a code snippet in the context of a module
+//!
+//! Finding out whether proposed code compiles normally means writing it into
+//! the tree and running cargo. Instead, the snippet is placed in memory as
+//! an inline module at the end of the target module's file, with a glob
+//! import of the target module, so it sees the same names, private items
+//! included, that code written there would. rust-analyzer's diagnostics are
+//! computed on the overlaid file, and the file's text is restored
+//! afterwards; nothing touches disk.
+//!
+//! Snippets that aren't items (statements, an expression) are checked as the
+//! body of a function.
+
+use super::diagnostics::FileDiagnostic;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_ide_db::ChangeWithProcMacros;
+use ra_ap_syntax::{Edition, SourceFile};
+
+/// Name of the inline module the snippet is placed in
+const SNIPPET_MODULE: &str = "__cratographer_snippet";
+
+/// Diagnostics of a checked snippet
+#[derive(Debug, Clone)]
+pub struct SnippetCheck {
+    /// Diagnostics within the snippet, with lines relative to its first line (0-based)
+    pub diagnostics: Vec<FileDiagnostic>,
+    /// Whether the snippet was checked as the body of a function, not being items
+    pub wrapped_in_function: bool,
+}
+
+impl Analyzer {
+    /// Check `snippet` as if it were written in the module of `file_path`
+    pub fn check_snippet(&mut self, snippet: &str, file_path: &str, experimental: bool) -> Result<SnippetCheck, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let original = self.host.analysis().file_text(file_id).map_err(|_| AnalyzerError::Canceled)?.to_string();
+
+        let wrapped_in_function = !parses_as_items(snippet) && parses_as_items(&format!("fn f() {{\n{}\n}}", snippet));
+        let (overlaid, first_line) = overlay(&original, snippet, wrapped_in_function);
+        let last_line = first_line + snippet.lines().count().max(1) as u32 - 1;
+
+        self.set_file_text(file_id, overlaid);
+        let result = self.file_diagnostics(file_id, experimental);
+        self.set_file_text(file_id, original);
+
+        let diagnostics = result?
+            .into_iter()
+            .filter(|d| d.start_line >= first_line && d.start_line <= last_line)
+            .map(|mut d| {
+                d.start_line -= first_line;
+                d.end_line = d.end_line.saturating_sub(first_line).min(last_line - first_line);
+                d
+            })
+            .collect();
+        Ok(SnippetCheck { diagnostics, wrapped_in_function })
+    }
+
+    /// Replace a file's text in the analysis, bypassing the VFS
+    fn set_file_text(&mut self, file_id: ra_ap_ide::FileId, text: String) {
+        let mut change = ChangeWithProcMacros::default();
+        change.change_file(file_id, Some(text));
+        self.host.apply_change(change);
+    }
+}
+
+/// Whether `text` parses cleanly as a sequence of items
+fn parses_as_items(text: &str) -> bool {
+    SourceFile::parse(text, Edition::CURRENT).errors().is_empty()
+}
+
+/// A module's text with the snippet appended as an inline module, and the
+/// line the snippet starts at
+///
+/// The snippet is inserted unindented, so its columns are unchanged.
+fn overlay(original: &str, snippet: &str, wrapped_in_function: bool) -> (String, u32) {
+    let mut text = original.to_string();
+    if !text.ends_with('\n') {
+        text.push('\n');
+    }
+    text.push_str(&format!("#[allow(dead_code, unused_imports)]\nmod {} {{\nuse super::*;\n", SNIPPET_MODULE));
+    if wrapped_in_function {
+        text.push_str("fn __snippet() {\n");
+    }
+    let first_line = text.lines().count() as u32;
+    text.push_str(snippet);
+    text.push('\n');
+    if wrapped_in_function {
+        text.push_str("}\n");
+    }
+    text.push_str("}\n");
+    (text, first_line)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_keeps_snippet_lines() {
+        let (text, first_line) = overlay("fn a() {}", "fn b() {}\nfn c() {}", false);
+        assert_eq!(text.lines().nth(first_line as usize), Some("fn b() {}"));
+        assert!(parses_as_items(&text));
+
+        let (text, first_line) = overlay("fn a() {}\n", "let x = 1;", true);
+        assert_eq!(text.lines().nth(first_line as usize), Some("let x = 1;"));
+        assert!(parses_as_items(&text));
+    }
+
+    #[test]
+    fn test_check_snippet_in_module_context() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/snippet.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let before = analyzer.diagnostics(path, false).unwrap().len();
+
+        // Private items of the module are in scope
+        let check = analyzer.check_snippet("fn ok() -> bool {\n    parses_as_items(\"\")\n}", path, false).unwrap();
+        assert!(check.diagnostics.iter().all(|d| d.severity != "error"), "{:?}", check.diagnostics);
+        assert!(!check.wrapped_in_function);
+
+        let check = analyzer.check_snippet("let n: u32 = \"text\";\nundefined_function();", path, false).unwrap();
+        assert!(check.wrapped_in_function);
+        assert!(check.diagnostics.iter().any(|d| d.severity == "error" && d.start_line == 0));
+        assert!(check.diagnostics.iter().any(|d| d.severity == "error" && d.start_line == 1));
+
+        // The file itself is left as it was
+        assert_eq!(analyzer.diagnostics(path, false).unwrap().len(), before);
+    }
+}
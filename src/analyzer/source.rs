@@ -0,0 +1,195 @@
+//! The source text of a symbol's definition
+//!
+//! Reading a definition otherwise takes a lookup for its location and then
+//! a read of the file sliced to the reported lines. This returns the text of
+//! the whole definition directly, doc comments and attributes included, and
+//! optionally some lines around it.
+//!
+//! Often the header alone is wanted: a function's signature without its
+//! body, a struct's or trait's declaration without its fields or items.
+//! That is cut from the same syntax node, ending where the body begins.
+
+use super::reference_index::definition_key;
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::FileId;
+use ra_ap_syntax::{ast, AstNode, NodeOrToken, SyntaxKind, SyntaxNode, TextRange, TextSize, T};
+
+/// The source of a definition
+#[derive(Debug, Clone)]
+pub struct SymbolSource {
+    /// Qualified path of the symbol
+    pub path: String,
+    pub file_path: String,
+    /// Lines of the definition, doc comments and attributes included
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The definition exactly as written
+    pub source: String,
+    /// Up to the requested number of lines before and after the definition
+    pub context_before: Vec<String>,
+    pub context_after: Vec<String>,
+}
+
+/// The header of a definition
+#[derive(Debug, Clone)]
+pub struct SymbolSignature {
+    /// Qualified path of the symbol
+    pub path: String,
+    pub file_path: String,
+    /// Line the signature starts on, after doc comments and attributes
+    pub line: u32,
+    /// The signature as written, e.g. `pub fn parse<T>(input: &str) -> Result<T> where T: FromStr`
+    pub signature: String,
+}
+
+impl Analyzer {
+    /// The source of the symbol matching a name, qualified path or symbol_id,
+    /// with up to `context_lines` lines on either side
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several symbols match.
+    pub fn symbol_source(&self, name: &str, context_lines: u32) -> Result<SymbolSource, AnalyzerError> {
+        let (path, file_id, node) = self.definition_node(name)?;
+        let (start_line, end_line) = self.line_range(file_id, node.text_range())?;
+
+        let text = self.host.analysis().file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let lines: Vec<&str> = text.lines().collect();
+        let context = |from: u32, to: u32| -> Vec<String> {
+            lines.iter().take(to as usize).skip(from as usize).map(|line| line.to_string()).collect()
+        };
+
+        Ok(SymbolSource {
+            path,
+            file_path: self.file_path(file_id),
+            start_line,
+            end_line,
+            source: node.text().to_string(),
+            context_before: context(start_line.saturating_sub(context_lines), start_line),
+            context_after: context(end_line + 1, end_line.saturating_add(1).saturating_add(context_lines)),
+        })
+    }
+
+    /// The signature of the symbol matching a name, qualified path or
+    /// symbol_id: its definition up to where the body begins
+    ///
+    /// Fails with `AnalyzerError::Ambiguous` if several symbols match.
+    pub fn symbol_signature(&self, name: &str) -> Result<SymbolSignature, AnalyzerError> {
+        let (path, file_id, node) = self.definition_node(name)?;
+        let (start, end) = signature_range(&node);
+        let (line, _) = self.line_range(file_id, TextRange::new(start, end))?;
+        let text = node.text().to_string();
+        let offset = |pos: TextSize| usize::from(pos - node.text_range().start());
+
+        Ok(SymbolSignature {
+            path,
+            file_path: self.file_path(file_id),
+            line,
+            signature: text[offset(start)..offset(end)].trim_end().to_string(),
+        })
+    }
+
+    /// The qualified path, file and syntax node of the one definition
+    /// matching a name, qualified path or symbol_id
+    fn definition_node(&self, name: &str) -> Result<(String, FileId, SyntaxNode), AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let definitions = self.definition_positions(&sema, name)?;
+        self.require_unique(&sema, name, &definitions.iter().map(|(def, _)| *def).collect::<Vec<_>>())?;
+        let Some(&(def, position)) = definitions.first() else {
+            return Err(AnalyzerError::Other(format!("No symbol named '{}' found", name)));
+        };
+
+        let source = sema.parse_guess_edition(position.file_id);
+        let node = source
+            .syntax()
+            .token_at_offset(position.offset)
+            .right_biased()
+            .and_then(|token| token.parent_ancestors().find(is_definition_node))
+            .ok_or_else(|| AnalyzerError::Other(format!("No definition found for '{}'", name)))?;
+        let path = definition_key(db, def).unwrap_or_else(|| name.to_string());
+        Ok((path, position.file_id, node))
+    }
+}
+
+/// Whether a node is a whole definition: an item, or an enum variant, field or
+/// associated item declared inside one
+fn is_definition_node(node: &SyntaxNode) -> bool {
+    ast::Item::can_cast(node.kind())
+        || matches!(node.kind(), SyntaxKind::VARIANT | SyntaxKind::RECORD_FIELD | SyntaxKind::TUPLE_FIELD)
+}
+
+/// The range of a definition's signature: from after its doc comments and
+/// attributes to where its body, field list or value begins
+fn signature_range(node: &SyntaxNode) -> (TextSize, TextSize) {
+    let mut children = node.children_with_tokens().skip_while(|child| {
+        matches!(child.kind(), SyntaxKind::ATTR | SyntaxKind::COMMENT | SyntaxKind::WHITESPACE)
+    });
+    let start = children.next().map_or(node.text_range().start(), |child| child.text_range().start());
+    let end = node
+        .children_with_tokens()
+        .find(|child| match child {
+            NodeOrToken::Node(child) => matches!(
+                child.kind(),
+                SyntaxKind::BLOCK_EXPR
+                    | SyntaxKind::RECORD_FIELD_LIST
+                    | SyntaxKind::VARIANT_LIST
+                    | SyntaxKind::ASSOC_ITEM_LIST
+                    | SyntaxKind::ITEM_LIST
+                    | SyntaxKind::EXTERN_ITEM_LIST
+                    | SyntaxKind::TOKEN_TREE
+            ),
+            // A const's or static's value; a type alias keeps its aliased type
+            NodeOrToken::Token(token) => {
+                token.kind() == T![;] || (token.kind() == T![=] && node.kind() != SyntaxKind::TYPE_ALIAS)
+            }
+        })
+        .map_or(node.text_range().end(), |child| child.text_range().start());
+    (start, end.max(start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_ranges() {
+        let signature = |code: &str| {
+            let file = ra_ap_syntax::SourceFile::parse(code, ra_ap_syntax::Edition::CURRENT).tree();
+            let node = file.syntax().children().find(is_definition_node).unwrap();
+            let (start, end) = signature_range(&node);
+            code[usize::from(start)..usize::from(end)].trim_end().to_string()
+        };
+
+        assert_eq!(
+            signature("/// Docs\n#[inline]\npub fn f<T>(x: T) -> T\nwhere\n    T: Copy,\n{\n    x\n}"),
+            "pub fn f<T>(x: T) -> T\nwhere\n    T: Copy,"
+        );
+        assert_eq!(signature("pub struct S<T: Clone> {\n    t: T,\n}"), "pub struct S<T: Clone>");
+        assert_eq!(signature("struct Unit;"), "struct Unit");
+        assert_eq!(signature("pub trait Tr: Sized {\n    fn m(&self);\n}"), "pub trait Tr: Sized");
+        assert_eq!(signature("const N: usize = 4;"), "const N: usize");
+        assert_eq!(signature("type Alias = Vec<u8>;"), "type Alias = Vec<u8>");
+        assert_eq!(signature("impl<T> Tr for S<T> {}"), "impl<T> Tr for S<T>");
+    }
+
+    #[test]
+    fn test_symbol_source_with_context() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let source = analyzer.symbol_source("is_definition_node", 2).unwrap();
+        assert!(source.file_path.ends_with("src/analyzer/source.rs"));
+        assert!(source.source.starts_with("/// Whether a node is a whole definition"));
+        assert!(source.source.ends_with('}'));
+        assert_eq!(source.source.lines().count() as u32, source.end_line - source.start_line + 1);
+        assert_eq!(source.context_before.len(), 2);
+        assert_eq!(source.context_after.len(), 2);
+        assert!(source.context_after[1].starts_with("#[cfg(test)]"));
+
+        assert!(analyzer.symbol_source("no_such_symbol_anywhere", 0).is_err());
+
+        let signature = analyzer.symbol_signature("is_definition_node").unwrap();
+        assert_eq!(signature.signature, "fn is_definition_node(node: &SyntaxNode) -> bool");
+        assert_eq!(signature.line, source.start_line + 2);
+    }
+}
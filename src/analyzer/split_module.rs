@@ -0,0 +1,184 @@
+//! Split-module refactoring
+//!
+//! Decomposes a large file by moving a set of its items into a new submodule.
+//! Builds on the move-item machinery: the items are removed from the file and
+//! written to a new file, a `mod` declaration is added, references across the
+//! workspace are repointed to the new module, and the imports and sibling
+//! items the moved code depends on are imported into the new file.
+
+use super::move_item::{
+    has_visibility, import_insertion_offset, imports_used_by, module_path, removal_range, sibling_dependencies,
+    visibility_insertion_offset, PlannedEdits, TextEdit,
+};
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_syntax::{
+    ast::{self, HasModuleItem, HasName},
+    AstNode, TextRange,
+};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Everything needed to split items out into a new submodule
+#[derive(Debug, Clone)]
+pub struct SplitPlan {
+    /// Path of the new module, e.g. `crate::analyzer::helpers`
+    pub module: String,
+    /// Path of the file to create
+    pub new_file: String,
+    /// Full contents of the file to create
+    pub new_file_text: String,
+    /// Edits to existing files, sorted by file and position
+    pub edits: Vec<TextEdit>,
+    /// Things to check by hand after applying the edits
+    pub warnings: Vec<String>,
+}
+
+impl Analyzer {
+    /// Plan moving the named items of a file into a new submodule `module_name`
+    pub fn plan_split_module(&self, file_path: &str, symbols: &[String], module_name: &str) -> Result<SplitPlan, AnalyzerError> {
+        if symbols.is_empty() {
+            return Err(AnalyzerError::Other("No symbols given to split out".to_string()));
+        }
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let file_id = self.file_id(file_path)?;
+        let source_module = sema
+            .file_to_module_def(file_id)
+            .ok_or_else(|| AnalyzerError::Other("File is not part of a module".to_string()))?;
+        let root = sema.parse_guess_edition(file_id);
+
+        if root.items().any(|item| matches!(&item, ast::Item::Module(m) if m.name().is_some_and(|n| n.text() == module_name))) {
+            return Err(AnalyzerError::Other(format!("Module '{}' is already declared in the file", module_name)));
+        }
+        let new_file = submodule_file(&self.file_path(file_id), source_module.parent(db).is_none(), module_name);
+        if Path::new(&new_file).exists() {
+            return Err(AnalyzerError::Other(format!("'{}' already exists", new_file)));
+        }
+
+        let mut items = Vec::new();
+        for symbol in symbols {
+            let (found_file, item, def) = self.find_movable_item(&sema, symbol, Some(file_path))?;
+            if found_file != file_id || item.syntax().parent().is_none_or(|p| !ast::SourceFile::can_cast(p.kind())) {
+                return Err(AnalyzerError::Other(format!("'{}' is not a top-level item of the file", symbol)));
+            }
+            items.push((symbol.rsplit("::").next().unwrap_or(symbol).to_string(), item, def));
+        }
+
+        let module = format!("{}::{}", module_path(db, source_module), module_name);
+        let moved: Vec<_> = items.iter().map(|(_, item, _)| (file_id, item.syntax().text_range())).collect();
+        let defs: Vec<_> = items.iter().map(|(_, _, def)| *def).collect();
+        let mut planned = PlannedEdits::default();
+
+        // Repoint references; the new file doesn't exist yet, so every one of
+        // them is outside the target
+        let mut item_texts = Vec::new();
+        for (name, item, def) in &items {
+            let new_path = format!("{}::{}", module, name);
+            let referenced = self.repoint_references(&sema, *def, &moved, &new_path, None, &mut planned)?;
+
+            let mut text = item.syntax().text().to_string();
+            if referenced && !has_visibility(item) {
+                text.insert_str(visibility_insertion_offset(item), "pub(crate) ");
+                planned.warnings.push(format!("'{}' was private and is made pub(crate) so existing references still resolve", name));
+            }
+            item_texts.push(text);
+        }
+
+        // Imports for the new file: what the moved items used from the
+        // original file, plus the items left behind that they refer to
+        let mut imports = Vec::new();
+        for (_, item, _) in &items {
+            for import in imports_used_by(&root, item, &HashSet::new()) {
+                if import.contains("self::") || import.contains("super::") {
+                    planned.warnings.push(format!("Copied relative import may need adjusting: {}", import));
+                }
+                imports.push(import);
+            }
+            // A child module sees its parent's private items, so no visibility changes
+            for (sibling, _) in sibling_dependencies(&sema, item, &defs, source_module) {
+                imports.push(format!("use super::{};", sibling));
+            }
+        }
+        imports.sort();
+        imports.dedup();
+
+        let edits = planned.files.entry(file_id).or_default();
+        for (_, item, _) in &items {
+            edits.replacements.push((removal_range(item), String::new()));
+        }
+        let declaration_at = root
+            .items()
+            .filter(|item| matches!(item, ast::Item::Module(_)))
+            .last()
+            .map(|last| removal_range(&last).end())
+            .unwrap_or_else(|| import_insertion_offset(&root));
+        edits.replacements.push((TextRange::empty(declaration_at), format!("mod {};\n", module_name)));
+
+        let mut new_file_text = String::new();
+        if !imports.is_empty() {
+            new_file_text.push_str(&imports.join("\n"));
+            new_file_text.push_str("\n\n");
+        }
+        new_file_text.push_str(&item_texts.join("\n\n"));
+        new_file_text.push('\n');
+
+        let PlannedEdits { files, warnings } = planned;
+        Ok(SplitPlan {
+            module,
+            new_file,
+            new_file_text,
+            edits: self.text_edits(&sema, files)?,
+            warnings,
+        })
+    }
+}
+
+/// Where the file of a new submodule goes
+///
+/// Crate roots and `mod.rs` files own their directory; any other `foo.rs`
+/// keeps its submodules in `foo/`.
+fn submodule_file(file_path: &str, crate_root: bool, module_name: &str) -> String {
+    let path = Path::new(file_path);
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let owns_dir = crate_root || path.file_name().is_some_and(|n| n == "mod.rs");
+    let dir = if owns_dir {
+        dir.to_path_buf()
+    } else {
+        dir.join(path.file_stem().unwrap_or_default())
+    };
+    dir.join(format!("{}.rs", module_name)).to_string_lossy().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_submodule_file_locations() {
+        assert_eq!(submodule_file("/p/src/main.rs", true, "helpers"), "/p/src/helpers.rs");
+        assert_eq!(submodule_file("/p/src/net/mod.rs", false, "tcp"), "/p/src/net/tcp.rs");
+        assert_eq!(submodule_file("/p/src/analyzer.rs", false, "helpers"), "/p/src/analyzer/helpers.rs");
+    }
+
+    #[test]
+    fn test_plan_split_of_analyzer_helpers() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let path = std::fs::canonicalize("src/analyzer.rs").unwrap();
+        let symbols = vec!["stable_hash".to_string(), "normalize_whitespace".to_string()];
+        let plan = analyzer.plan_split_module(path.to_str().unwrap(), &symbols, "text").unwrap();
+
+        assert_eq!(plan.module, "crate::analyzer::text");
+        assert!(plan.new_file.ends_with("src/analyzer/text.rs"));
+        assert!(plan.new_file_text.contains("fn stable_hash"));
+        assert!(plan.new_file_text.contains("fn normalize_whitespace"));
+        assert!(plan.edits.iter().any(|e| e.new_text == "mod text;\n"));
+        // `mod tests` sees stable_hash through `use super::*` and needs its own import
+        assert!(plan
+            .edits
+            .iter()
+            .any(|e| e.file_path.ends_with("src/analyzer.rs") && e.new_text.contains("    use crate::analyzer::text::stable_hash;\n")));
+    }
+}
@@ -0,0 +1,196 @@
+//! Structural search
+//!
+//! rust-analyzer's structural search (SSR) matches code by syntax tree and
+//! name resolution rather than by text: `$a.unwrap()` finds every `unwrap`
+//! call whatever the receiver and however it is formatted, and paths in the
+//! pattern match the items they resolve to, so `foo::Bar::new($x)` also
+//! finds `Bar::new(x)` where `Bar` is imported. Placeholders are written
+//! `$name`; a type pattern such as `Option<$t>` matches types.
+//!
+//! Paths in a pattern are resolved from the root of the file being searched,
+//! so they are best written fully qualified. Each file is searched on its
+//! own, which lets a long workspace search be time-boxed.
+//!
+//! A rule such as `$a.unwrap() ==>> $a.expect("checked")` also replaces the
+//! matches; the edits are computed, never applied, so they can be reviewed
+//! first. Paths in the replacement are shortened to what is in scope.
+
+use super::budget::{Budget, Coverage};
+use super::move_item::{FileEdits, TextEdit};
+use super::{Analyzer, AnalyzerError};
+use ra_ap_hir::Semantics;
+use ra_ap_ide::{FileId, FilePosition, FileRange, TextRange, TextSize};
+use ra_ap_ide_ssr::{MatchFinder, SsrPattern, SsrRule};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// A piece of code matching the pattern
+#[derive(Debug, Clone)]
+pub struct StructuralMatch {
+    pub file_path: String,
+    /// 0-based start and end of the match
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// The matched code as written
+    pub text: String,
+}
+
+/// The matches of a structural search
+#[derive(Debug, Clone)]
+pub struct StructuralMatches {
+    /// By file, then in source order
+    pub matches: Vec<StructuralMatch>,
+    pub coverage: Coverage,
+}
+
+/// The edits of a structural replace
+#[derive(Debug, Clone)]
+pub struct StructuralReplacement {
+    /// Number of matches replaced
+    pub matches: usize,
+    /// Non-overlapping edits in positions of the unmodified files, sorted by file and position
+    pub edits: Vec<TextEdit>,
+    pub coverage: Coverage,
+}
+
+impl Analyzer {
+    /// Find the code matching an SSR pattern, such as `$a.unwrap()`, in one
+    /// file or in every workspace file until the budget runs out
+    pub fn structural_search(&self, pattern: &str, file_path: Option<&str>, budget: Budget) -> Result<StructuralMatches, AnalyzerError> {
+        let parse = || SsrPattern::from_str(pattern).map_err(|e| AnalyzerError::Other(format!("Invalid pattern: {}", e)));
+        parse()?;
+        let files = self.ssr_files(file_path)?;
+        let analysis = self.host.analysis();
+
+        let mut coverage = Coverage { files_scanned: 0, files_total: files.len() };
+        let mut matches = Vec::new();
+        for (file_path, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            let mut finder = self.match_finder(file_id)?;
+            finder.add_search_pattern(parse()?).map_err(|e| AnalyzerError::Other(format!("Invalid pattern: {}", e)))?;
+
+            let line_index = analysis.file_line_index(file_id).map_err(|_| AnalyzerError::Canceled)?;
+            let mut found: Vec<StructuralMatch> = finder
+                .matches()
+                .flattened()
+                .matches
+                .into_iter()
+                .map(|m| {
+                    let range = m.matched_node.text_range();
+                    let start = line_index.line_col(range.start());
+                    let end = line_index.line_col(range.end());
+                    StructuralMatch {
+                        file_path: file_path.clone(),
+                        start_line: start.line,
+                        start_column: start.col,
+                        end_line: end.line,
+                        end_column: end.col,
+                        text: m.matched_text(),
+                    }
+                })
+                .collect();
+            found.sort_by_key(|m| (m.start_line, m.start_column));
+            matches.extend(found);
+            coverage.files_scanned += 1;
+        }
+        Ok(StructuralMatches { matches, coverage })
+    }
+
+    /// Compute the edits of an SSR rule, such as `$a.unwrap() ==>> $a.expect("checked")`,
+    /// in one file or in every workspace file until the budget runs out,
+    /// without applying them
+    pub fn structural_replace(&self, rule: &str, file_path: Option<&str>, budget: Budget) -> Result<StructuralReplacement, AnalyzerError> {
+        let parse = || SsrRule::from_str(rule).map_err(|e| AnalyzerError::Other(format!("Invalid rule: {}", e)));
+        parse()?;
+        let files = self.ssr_files(file_path)?;
+
+        let mut replacement = StructuralReplacement {
+            matches: 0,
+            edits: Vec::new(),
+            coverage: Coverage { files_scanned: 0, files_total: files.len() },
+        };
+        let mut file_edits: HashMap<FileId, FileEdits> = HashMap::new();
+        for (_, file_id) in files {
+            if budget.exhausted() {
+                break;
+            }
+            let mut finder = self.match_finder(file_id)?;
+            finder.add_rule(parse()?).map_err(|e| AnalyzerError::Other(format!("Invalid rule: {}", e)))?;
+
+            replacement.matches += finder.matches().flattened().matches.len();
+            for (file_id, edit) in finder.edits() {
+                let replacements = edit.iter().map(|indel| (indel.delete, indel.insert.clone()));
+                file_edits.entry(file_id).or_default().replacements.extend(replacements);
+            }
+            replacement.coverage.files_scanned += 1;
+        }
+        let sema = Semantics::new(self.host.raw_database());
+        replacement.edits = self.text_edits(&sema, file_edits)?;
+        Ok(replacement)
+    }
+
+    /// The files to search, by path: the given one or every workspace file
+    pub(super) fn ssr_files(&self, file_path: Option<&str>) -> Result<Vec<(String, FileId)>, AnalyzerError> {
+        let mut files: Vec<_> = match file_path {
+            Some(file_path) => vec![(file_path.to_string(), self.file_id(file_path)?)],
+            None => self.workspace_files()?.into_iter().map(|file_id| (self.file_path(file_id), file_id)).collect(),
+        };
+        files.sort();
+        Ok(files)
+    }
+
+    /// A match finder searching one file, resolving pattern paths from its root
+    pub(super) fn match_finder(&self, file_id: FileId) -> Result<MatchFinder<'_>, AnalyzerError> {
+        let db = self.host.raw_database();
+        let text = self.host.analysis().file_text(file_id).map_err(|_| AnalyzerError::Canceled)?;
+        let whole_file = FileRange { file_id, range: TextRange::up_to(TextSize::of(&*text)) };
+        MatchFinder::in_context(db, FilePosition { file_id, offset: TextSize::from(0) }, vec![whole_file])
+            .map_err(|e| AnalyzerError::Other(format!("Structural search failed: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_structural_search_in_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/structural_search.rs").unwrap();
+        let path = path.to_str().unwrap();
+
+        let found = analyzer.structural_search("$a.flattened()", Some(path), Budget::unlimited()).unwrap();
+        assert!(found.coverage.complete());
+        // In structural_search and structural_replace
+        assert_eq!(found.matches.len(), 2);
+        assert!(found.matches[0].text.ends_with(".flattened()"));
+        assert!(found.matches[0].start_line <= found.matches[0].end_line);
+
+        assert!(analyzer.structural_search("$a.(", Some(path), Budget::unlimited()).is_err());
+    }
+
+    #[test]
+    fn test_structural_replace_in_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/structural_search.rs").unwrap();
+        let path = path.to_str().unwrap();
+        let text = std::fs::read_to_string(path).unwrap();
+
+        let replacement = analyzer.structural_replace("$a.flattened() ==>> $a.flattened_matches()", Some(path), Budget::unlimited()).unwrap();
+        assert!(replacement.coverage.complete());
+        assert_eq!(replacement.matches, 2);
+        assert!(!replacement.edits.is_empty());
+        assert!(replacement.edits.iter().all(|e| e.file_path == path && e.new_text.contains("flattened_matches")));
+
+        // Nothing was applied
+        assert_eq!(std::fs::read_to_string(path).unwrap(), text);
+        // A pattern without a replacement isn't a rule
+        assert!(analyzer.structural_replace("$a.flattened()", Some(path), Budget::unlimited()).is_err());
+    }
+}
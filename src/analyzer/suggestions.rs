@@ -0,0 +1,157 @@
+//! "Did you mean" suggestions for searches without results
+//!
+//! An empty result reads as proof that a symbol doesn't exist, when the name
+//! was often just slightly off: a typo, a different word order, singular for
+//! plural. When a search finds nothing, the indexed names closest to the
+//! query are suggested instead, scored by edit distance and by the words
+//! they share (so `SymbolFinder` suggests `find_symbol`).
+
+use super::quick_index::file_symbols;
+use super::{Analyzer, SearchOptions, SymbolInfo, SymbolKind};
+
+/// Number of suggestions returned
+const SUGGESTION_LIMIT: usize = 5;
+
+/// Minimum similarity, between 0 and 1, for a name to be suggested
+const MIN_SIMILARITY: f64 = 0.5;
+
+impl Analyzer {
+    /// The workspace names closest to `name`, and library names too when
+    /// the search includes libraries and the library cache is loaded
+    ///
+    /// Only names the search itself would accept are suggested: the kind
+    /// filter, version pin and crate lists apply.
+    pub fn suggest_names(&self, name: &str, options: &SearchOptions) -> Vec<String> {
+        let analysis = self.host.analysis();
+        let crates = self.crates_by_source_root().unwrap_or_default();
+        let mut names = Vec::new();
+        for file_id in self.workspace_files().unwrap_or_default() {
+            let Ok(text) = analysis.file_text(file_id) else { continue };
+            let (crate_name, crate_version) =
+                analysis.source_root_id(file_id).ok().and_then(|root| crates.get(&root).cloned()).unwrap_or_default();
+            names.extend(
+                file_symbols(&self.file_path(file_id), &text)
+                    .into_iter()
+                    .filter(|sym| sym.kind != SymbolKind::Impl)
+                    .map(|sym| SymbolInfo { crate_name: crate_name.clone(), crate_version: crate_version.clone(), ..sym })
+                    .filter(|sym| options.accepts(sym))
+                    .map(|sym| sym.name),
+            );
+        }
+        if options.searches_libraries() {
+            if let Some(symbols) = &self.library_symbols {
+                names.extend(symbols.iter().filter(|sym| options.accepts(sym)).map(|sym| sym.name.clone()));
+            }
+        }
+        closest_names(name, names.iter().map(String::as_str))
+    }
+}
+
+/// The candidates most similar to `query`, best first
+pub(super) fn closest_names<'a>(query: &str, candidates: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut scored: Vec<(f64, &str)> = candidates
+        .map(|candidate| (similarity(query, candidate), candidate))
+        .filter(|(score, _)| *score >= MIN_SIMILARITY)
+        .collect();
+    scored.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(b.1)));
+
+    let mut names: Vec<String> = Vec::new();
+    for (_, candidate) in scored {
+        if !names.iter().any(|name| name == candidate) {
+            names.push(candidate.to_string());
+        }
+        if names.len() == SUGGESTION_LIMIT {
+            break;
+        }
+    }
+    names
+}
+
+/// How alike two names are, from 0 to 1: the better of their spelling
+/// similarity (ignoring case) and the share of words they have in common
+fn similarity(query: &str, candidate: &str) -> f64 {
+    let (a, b) = (query.to_lowercase(), candidate.to_lowercase());
+    let longest = a.chars().count().max(b.chars().count()).max(1);
+    let spelling = 1.0 - edit_distance(&a, &b) as f64 / longest as f64;
+
+    let (query_words, candidate_words) = (words(query), words(candidate));
+    let shared = query_words.iter().filter(|word| candidate_words.contains(word)).count();
+    let overlap = shared as f64 / query_words.len().max(candidate_words.len()).max(1) as f64;
+    spelling.max(overlap)
+}
+
+/// Levenshtein distance, in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// The lowercase words of a snake_case or CamelCase name
+fn words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut previous_lower = false;
+    for c in name.chars() {
+        let boundary = c == '_' || c == ':' || (c.is_uppercase() && previous_lower);
+        if boundary && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        if c != '_' && c != ':' {
+            current.extend(c.to_lowercase());
+        }
+        previous_lower = c.is_lowercase() || c.is_ascii_digit();
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analyzer::SymbolFilter;
+
+    #[test]
+    fn test_edit_distance_and_words() {
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(words("find_symbol"), vec!["find", "symbol"]);
+        assert_eq!(words("SymbolFinder"), vec!["symbol", "finder"]);
+        assert_eq!(words("HTTPServer2"), vec!["httpserver2"]);
+    }
+
+    #[test]
+    fn test_closest_names() {
+        let candidates = ["find_symbol", "SymbolInfo", "enumerate_file", "Analyzer"];
+        assert_eq!(closest_names("find_symbols", candidates.into_iter())[0], "find_symbol");
+        assert_eq!(closest_names("analyser", candidates.into_iter()), vec!["Analyzer"]);
+        assert!(closest_names("SymbolFinder", candidates.into_iter()).contains(&"find_symbol".to_string()));
+        assert!(closest_names("zzz", candidates.into_iter()).is_empty());
+    }
+
+    #[test]
+    fn test_suggest_names_from_workspace() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let suggestions = analyzer.suggest_names("suggest_name", &SearchOptions::default());
+        assert_eq!(suggestions[0], "suggest_names");
+
+        // Suggestions respect the kind filter and crate lists
+        let types = SearchOptions { filter: SymbolFilter::Types, ..Default::default() };
+        assert!(!analyzer.suggest_names("suggest_name", &types).contains(&"suggest_names".to_string()));
+        let excluded = SearchOptions { crates_exclude: vec!["cratographer".to_string()], ..Default::default() };
+        assert!(analyzer.suggest_names("suggest_name", &excluded).is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+//! Trait override matrix
+//!
+//! For a trait with many implementors, shows which implementors override
+//! which members: rows are impls, columns are the trait's methods, constants
+//! and associated types, and each cell says whether the impl provides the
+//! member, falls back to the trait's default, or is missing a required one
+//! (as in an impl still being written). Useful for auditing consistency
+//! across the implementors of an internal trait.
+
+use super::reference_index::definition_key;
+use super::{display_type, Analyzer, AnalyzerError};
+use ra_ap_hir::{AssocItem, Semantics, Trait};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::AstNode;
+
+/// How an impl treats one trait member
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverrideStatus {
+    /// The impl defines the member
+    Overridden,
+    /// The impl relies on the trait's default
+    Defaulted,
+    /// The impl lacks a member the trait requires
+    Missing,
+}
+
+/// A member of the trait, one column of the matrix
+#[derive(Debug, Clone)]
+pub struct TraitMember {
+    pub name: String,
+    /// "fn", "const" or "type"
+    pub kind: &'static str,
+    /// Whether the trait provides a default
+    pub has_default: bool,
+}
+
+/// One implementor, a row of the matrix
+#[derive(Debug, Clone)]
+pub struct Implementor {
+    /// The implementing type as rust-analyzer displays it
+    pub self_type: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// One status per trait member, in the order of `OverrideMatrix::members`
+    pub cells: Vec<OverrideStatus>,
+}
+
+/// Members of a trait against its implementors
+#[derive(Debug, Clone)]
+pub struct OverrideMatrix {
+    pub trait_path: String,
+    pub members: Vec<TraitMember>,
+    pub implementors: Vec<Implementor>,
+}
+
+impl Analyzer {
+    /// Build the override matrix of a trait
+    ///
+    /// Impls outside the workspace are only included with `include_library`.
+    pub fn trait_override_matrix(&self, trait_name: &str, include_library: bool) -> Result<OverrideMatrix, AnalyzerError> {
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+
+        let traits: Vec<Trait> = self.resolve_definitions(&sema, trait_name)?
+            .into_iter()
+            .filter_map(|def| match def {
+                Definition::Trait(trait_) => Some(trait_),
+                _ => None,
+            })
+            .collect();
+        let Some(&trait_) = traits.first() else {
+            return Err(AnalyzerError::Other(format!("No trait named '{}' found", trait_name)));
+        };
+        self.require_unique(&sema, trait_name, &traits.iter().map(|&t| Definition::Trait(t)).collect::<Vec<_>>())?;
+
+        let members: Vec<TraitMember> = trait_.items(db).into_iter().filter_map(|item| member(&sema, item)).collect();
+
+        let mut implementors = Vec::new();
+        for impl_ in ra_ap_hir::Impl::all_for_trait(db, trait_) {
+            let krate = impl_.module(db).krate();
+            if !include_library && !krate.origin(db).is_local() {
+                continue;
+            }
+            let Some(source) = sema.source(impl_) else { continue };
+            let range = sema.original_range(source.value.syntax());
+            let file_id = range.file_id.file_id(db);
+            let (start_line, end_line) = self.line_range(file_id, range.range)?;
+
+            let provided: Vec<String> = impl_.items(db).iter().filter_map(|item| item.name(db)).map(|n| n.as_str().to_string()).collect();
+            let cells = members.iter().map(|member| status(provided.contains(&member.name), member.has_default)).collect();
+
+            implementors.push(Implementor {
+                self_type: display_type(db, &impl_.self_ty(db), krate),
+                file_path: self.file_path(file_id),
+                start_line,
+                end_line,
+                cells,
+            });
+        }
+        implementors.sort_by(|a, b| (&a.file_path, a.start_line).cmp(&(&b.file_path, b.start_line)));
+
+        Ok(OverrideMatrix {
+            trait_path: definition_key(db, Definition::Trait(trait_)).unwrap_or_else(|| trait_name.to_string()),
+            members,
+            implementors,
+        })
+    }
+}
+
+/// Describe a trait member, reading its default from the trait's source
+fn member(sema: &Semantics<'_, RootDatabase>, item: AssocItem) -> Option<TraitMember> {
+    let db = sema.db;
+    let name = item.name(db)?.as_str().to_string();
+    let (kind, has_default) = match item {
+        AssocItem::Function(func) => ("fn", sema.source(func).is_some_and(|s| s.value.body().is_some())),
+        AssocItem::Const(konst) => ("const", sema.source(konst).is_some_and(|s| s.value.body().is_some())),
+        AssocItem::TypeAlias(alias) => ("type", sema.source(alias).is_some_and(|s| s.value.ty().is_some())),
+    };
+    Some(TraitMember { name, kind, has_default })
+}
+
+fn status(provided: bool, has_default: bool) -> OverrideStatus {
+    if provided {
+        OverrideStatus::Overridden
+    } else if has_default {
+        OverrideStatus::Defaulted
+    } else {
+        OverrideStatus::Missing
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cell_status() {
+        assert_eq!(status(true, true), OverrideStatus::Overridden);
+        assert_eq!(status(true, false), OverrideStatus::Overridden);
+        assert_eq!(status(false, true), OverrideStatus::Defaulted);
+        assert_eq!(status(false, false), OverrideStatus::Missing);
+    }
+
+    #[test]
+    fn test_override_matrix_of_std_trait() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+
+        let matrix = analyzer.trait_override_matrix("fmt::Display", false).unwrap();
+        let fmt = matrix.members.iter().position(|m| m.name == "fmt").expect("Display has fmt");
+        assert!(!matrix.members[fmt].has_default);
+
+        let error = matrix
+            .implementors
+            .iter()
+            .find(|row| row.self_type == "AnalyzerError")
+            .expect("AnalyzerError implements Display");
+        assert_eq!(error.cells[fmt], OverrideStatus::Overridden);
+    }
+}
@@ -0,0 +1,197 @@
+//! The `use` declarations of a file, resolved
+//!
+//! Cleaning up imports programmatically needs more than their text: what
+//! each imported name resolves to, whether anything in scope still refers
+//! to it, and whether it is re-exported (in which case it is part of the
+//! module's interface and must stay, used or not).
+//!
+//! An import counts as used when a path in its scope starts with the
+//! imported name and resolves to the same item; a glob import, when such a
+//! path resolves to an item of the globbed module or enum; a trait import,
+//! when a method call or path in scope resolves to one of the trait's
+//! methods or associated items.
+
+use super::file_dependencies::written_path;
+use super::reference_index::definition_key;
+use super::{normalize_whitespace, Analyzer, AnalyzerError};
+use ra_ap_hir::{Adt, AssocItemContainer, ModuleDef, PathResolution, Semantics, Trait};
+use ra_ap_ide::RootDatabase;
+use ra_ap_ide_db::defs::Definition;
+use ra_ap_syntax::{
+    ast::{self, HasName, HasVisibility},
+    AstNode, SyntaxNode,
+};
+use std::collections::HashMap;
+
+/// One name a `use` declaration imports
+#[derive(Debug, Clone)]
+pub struct UseImport {
+    /// The imported path as written, e.g. `std::collections::HashMap` or `std::io::*`
+    pub path: String,
+    /// The name it is imported as with `as`, `_` included
+    pub alias: Option<String>,
+    /// Qualified path of what it resolves to, if it resolves
+    pub resolved: Option<String>,
+    /// Whether it comes from another crate
+    pub external: bool,
+    /// Whether anything in the declaration's scope refers to it
+    pub used: bool,
+}
+
+/// A `use` declaration and what it imports
+#[derive(Debug, Clone)]
+pub struct UseDeclaration {
+    /// The declaration as written, on one line
+    pub text: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Visibility as written, e.g. `pub` or `pub(crate)`; `None` for private imports
+    pub visibility: Option<String>,
+    /// Whether the declaration re-exports what it imports
+    pub re_exported: bool,
+    pub imports: Vec<UseImport>,
+}
+
+/// What the code in a scope refers to, other than through `use` declarations
+struct ScopeUses {
+    /// Resolutions of paths starting with a bare name, e.g. `HashMap` in `HashMap::new`
+    unqualified: Vec<Definition>,
+    /// Traits whose methods or associated items are used
+    traits: Vec<Trait>,
+}
+
+impl Analyzer {
+    /// The `use` declarations of a file, in file order, with their imports
+    pub fn use_declarations(&self, file_path: &str) -> Result<Vec<UseDeclaration>, AnalyzerError> {
+        let file_id = self.file_id(file_path)?;
+        let db = self.host.raw_database();
+        let sema = Semantics::new(db);
+        let source = sema.parse_guess_edition(file_id);
+        let krate = sema.file_to_module_def(file_id).map(|module| module.krate());
+
+        let mut scopes: HashMap<SyntaxNode, ScopeUses> = HashMap::new();
+        let mut declarations = Vec::new();
+        for item in source.syntax().descendants().filter_map(ast::Use::cast) {
+            // A use declaration is in scope throughout the item list or block containing it
+            let Some(scope) = item.syntax().parent() else { continue };
+            let uses = scopes.entry(scope.clone()).or_insert_with(|| scope_uses(&sema, &scope));
+
+            let mut imports = Vec::new();
+            for tree in item.syntax().descendants().filter_map(ast::UseTree::cast) {
+                if tree.use_tree_list().is_some() {
+                    continue;
+                }
+                let Some(path) = tree.path() else { continue };
+                let def = match sema.resolve_path(&path) {
+                    Some(PathResolution::Def(def)) => Some(def),
+                    _ => None,
+                };
+                let glob = tree.star_token().is_some();
+                imports.push(UseImport {
+                    path: written_path(&tree),
+                    alias: tree.rename().map(|rename| match rename.name() {
+                        Some(name) => name.text().to_string(),
+                        None => "_".to_string(),
+                    }),
+                    resolved: def.and_then(|def| definition_key(db, Definition::from(def))),
+                    external: def.and_then(|def| def.module(db)).is_some_and(|module| Some(module.krate()) != krate),
+                    used: def.is_some_and(|def| is_used(db, def, glob, uses)),
+                });
+            }
+
+            let (start_line, end_line) = self.line_range(file_id, item.syntax().text_range())?;
+            let visibility = item.visibility().map(|vis| vis.syntax().text().to_string());
+            declarations.push(UseDeclaration {
+                text: normalize_whitespace(&item.syntax().text().to_string()),
+                start_line,
+                end_line,
+                re_exported: visibility.is_some(),
+                visibility,
+                imports,
+            });
+        }
+        Ok(declarations)
+    }
+}
+
+/// Collect what the code in `scope` refers to, skipping `use` declarations
+fn scope_uses(sema: &Semantics<'_, RootDatabase>, scope: &SyntaxNode) -> ScopeUses {
+    let db = sema.db;
+    let mut uses = ScopeUses { unqualified: Vec::new(), traits: Vec::new() };
+    let add_trait_of = |def: Definition, traits: &mut Vec<Trait>| {
+        let assoc = match def {
+            Definition::Function(it) => it.as_assoc_item(db),
+            Definition::Const(it) => it.as_assoc_item(db),
+            Definition::TypeAlias(it) => it.as_assoc_item(db),
+            _ => None,
+        };
+        let trait_ = assoc.and_then(|assoc| match assoc.container(db) {
+            AssocItemContainer::Trait(trait_) => Some(trait_),
+            AssocItemContainer::Impl(impl_) => impl_.trait_(db),
+        });
+        if let Some(trait_) = trait_.filter(|trait_| !traits.contains(trait_)) {
+            traits.push(trait_);
+        }
+    };
+
+    for node in scope.descendants() {
+        if node.ancestors().any(|ancestor| ast::Use::can_cast(ancestor.kind())) {
+            continue;
+        }
+        if let Some(path) = ast::Path::cast(node.clone()) {
+            let Some(PathResolution::Def(def)) = sema.resolve_path(&path) else { continue };
+            let def = Definition::from(def);
+            if path.qualifier().is_none() && !uses.unqualified.contains(&def) {
+                uses.unqualified.push(def);
+            }
+            add_trait_of(def, &mut uses.traits);
+        } else if let Some(call) = ast::MethodCallExpr::cast(node) {
+            if let Some(func) = sema.resolve_method_call(&call) {
+                add_trait_of(Definition::Function(func), &mut uses.traits);
+            }
+        }
+    }
+    uses
+}
+
+/// Whether an import of `def` is used by the code of its scope
+fn is_used(db: &RootDatabase, def: ModuleDef, glob: bool, uses: &ScopeUses) -> bool {
+    if glob {
+        return uses.unqualified.iter().any(|used| match (def, used) {
+            (ModuleDef::Module(module), used) => used.module(db) == Some(module) && *used != Definition::Module(module),
+            (ModuleDef::Adt(Adt::Enum(enum_)), Definition::Variant(variant)) => variant.parent_enum(db) == enum_,
+            _ => false,
+        });
+    }
+    if let ModuleDef::Trait(trait_) = def {
+        if uses.traits.contains(&trait_) {
+            return true;
+        }
+    }
+    uses.unqualified.contains(&Definition::from(def))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_use_declarations_of_this_file() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.load_project(".").is_ok());
+        let path = std::fs::canonicalize("src/analyzer/use_graph.rs").unwrap();
+
+        let declarations = analyzer.use_declarations(path.to_str().unwrap()).unwrap();
+        let imports: Vec<&UseImport> = declarations.iter().flat_map(|d| &d.imports).collect();
+
+        let semantics = imports.iter().find(|i| i.path == "ra_ap_hir::Semantics").unwrap();
+        assert!(semantics.external && semantics.used);
+        assert!(semantics.resolved.as_ref().is_some_and(|resolved| resolved.ends_with("::Semantics")));
+
+        // Only its `name()` method is called, which is enough for a trait import
+        let has_name = imports.iter().find(|i| i.path == "ra_ap_syntax::ast::HasName").unwrap();
+        assert!(has_name.used);
+
+        assert!(declarations.iter().all(|d| !d.re_exported && d.visibility.is_none()));
+    }
+}
@@ -0,0 +1,105 @@
+//! Cargo workspace members
+//!
+//! The packages of the workspace as Cargo sees them, from the metadata kept
+//! after loading: name, version, manifest and targets. Unlike the crate
+//! graph, which has one crate per target, this groups a package's library,
+//! binaries, tests, benches and examples together.
+
+use super::{Analyzer, AnalyzerError};
+use ra_ap_project_model::{ProjectWorkspaceKind, TargetKind};
+
+/// A target of a package
+#[derive(Debug, Clone)]
+pub struct MemberTarget {
+    pub name: String,
+    /// "lib", "proc-macro", "bin", "test", "bench", "example", "build-script" or "other"
+    pub kind: &'static str,
+    /// The target's root source file
+    pub root: String,
+}
+
+/// A package of the workspace
+#[derive(Debug, Clone)]
+pub struct WorkspaceMember {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    /// Absolute path of the package's Cargo.toml
+    pub manifest_path: String,
+    pub targets: Vec<MemberTarget>,
+}
+
+impl Analyzer {
+    /// The members of the loaded Cargo workspace, by name
+    ///
+    /// Projects loaded from something other than Cargo metadata (such as a
+    /// `rust-project.json`) have no members.
+    pub fn workspace_members(&self) -> Result<Vec<WorkspaceMember>, AnalyzerError> {
+        let workspace = self.workspace.as_ref().ok_or_else(|| AnalyzerError::Other("No project loaded".to_string()))?;
+        let ProjectWorkspaceKind::Cargo { cargo, .. } = &workspace.kind else { return Ok(Vec::new()) };
+
+        let mut members: Vec<WorkspaceMember> = cargo
+            .packages()
+            .filter(|&package| cargo[package].is_member)
+            .map(|package| {
+                let data = &cargo[package];
+                let targets = data
+                    .targets
+                    .iter()
+                    .map(|&target| {
+                        let target = &cargo[target];
+                        MemberTarget {
+                            name: target.name.clone(),
+                            kind: target_kind(target.kind),
+                            root: target.root.to_string(),
+                        }
+                    })
+                    .collect();
+                WorkspaceMember {
+                    name: data.name.clone(),
+                    version: data.version.to_string(),
+                    edition: data.edition.to_string(),
+                    manifest_path: data.manifest.to_string(),
+                    targets,
+                }
+            })
+            .collect();
+        members.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(members)
+    }
+}
+
+fn target_kind(kind: TargetKind) -> &'static str {
+    match kind {
+        TargetKind::Lib { is_proc_macro: true } => "proc-macro",
+        TargetKind::Lib { is_proc_macro: false } => "lib",
+        TargetKind::Bin => "bin",
+        TargetKind::Test => "test",
+        TargetKind::Bench => "bench",
+        TargetKind::Example => "example",
+        TargetKind::BuildScript => "build-script",
+        TargetKind::Other => "other",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace_members_of_this_project() {
+        let mut analyzer = Analyzer::new();
+        assert!(analyzer.workspace_members().is_err());
+        assert!(analyzer.load_project(".").is_ok());
+
+        let members = analyzer.workspace_members().unwrap();
+        assert_eq!(members.len(), 1);
+        let member = &members[0];
+        assert_eq!(member.name, "cratographer");
+        assert_eq!(member.edition, "2021");
+        assert!(member.manifest_path.ends_with("Cargo.toml"));
+        let bin = member.targets.iter().find(|t| t.kind == "bin").unwrap();
+        assert_eq!(bin.name, "cratographer");
+        assert!(bin.root.ends_with("src/main.rs"));
+    }
+}
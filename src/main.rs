@@ -1,19 +1,35 @@
 mod analyzer;
+mod outputs;
+mod query_cache;
+mod snapshots;
 
-use analyzer::{Analyzer, SearchMode, SearchOptions, SymbolFilter};
+use analyzer::{
+    Analyzer, AnalyzerError, Budget, Candidate, ClosureShapeQuery, ConstantQuery, Coverage, DuplicateStatus, ImplBlock,
+    IndexEventKind, ModuleItem, ModuleNode, OverrideStatus, QuickIndex, Reference, Related, ScratchFile, SearchMode,
+    SearchOptions, SignatureFilter, SymbolFilter, SymbolInfo, SymbolProvider, BUILD_ENV_MACROS,
+    EMBEDDING_MACROS, ENTRY_POINT_KINDS, ERROR_HANDLING_KINDS, GLOB_CONFLICT_KINDS, LOG_LEVELS, RA_AP_VERSION,
+    RUNNABLE_KINDS,
+};
 use rmcp::{
     handler::server::{
         router::tool::ToolRouter,
+        tool::cached_schema_for_type,
         wrapper::Parameters,
     },
-    model::{CallToolResult, Content, ErrorCode, ErrorData as McpError, Implementation, ProtocolVersion, ServerCapabilities, ServerInfo},
+    model::{
+        CallToolResult, Content, ErrorCode, ErrorData as McpError, Implementation, JsonObject, ProtocolVersion, ServerCapabilities,
+        ServerInfo,
+    },
     tool, tool_handler, tool_router, ServerHandler, ServiceExt,
     transport::stdio,
 };
+use outputs::SymbolOutput;
+use query_cache::QueryCache;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use snapshots::Snapshots;
 use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Initialization state for the analyzer
 #[derive(Debug, Clone)]
@@ -24,9 +40,10 @@ enum InitState {
 }
 
 /// Parameters for the find_symbol tool
-#[derive(Serialize, Deserialize, JsonSchema)]
+#[derive(Default, Serialize, Deserialize, JsonSchema)]
 struct FindSymbolParams {
-    /// The name of the symbol to search for
+    /// The name of the symbol to search for; empty, together with a signature or
+    /// attribute filter, to check every workspace function
     name: String,
     /// Search mode: "exact", "fuzzy", or "prefix" (default: "fuzzy")
     #[serde(default)]
@@ -37,6 +54,43 @@ struct FindSymbolParams {
     /// Filter by symbol kind: "types", "implementations", "functions", or "all" (default: "all")
     #[serde(default)]
     filter: Option<String>,
+    /// Only return symbols from crates with this version, e.g. "2" or "2.0.39",
+    /// for when several versions of a dependency are in the graph (default: any)
+    #[serde(default)]
+    crate_version: Option<String>,
+    /// Only return functions with a parameter whose type contains this text, e.g. "&mut Vec<u8>" (optional)
+    #[serde(default)]
+    params_contains: Option<String>,
+    /// Only return functions with exactly this many parameters, not counting `self` (optional)
+    #[serde(default)]
+    num_params: Option<usize>,
+    /// Only return async (true) or non-async (false) functions (optional)
+    #[serde(default)]
+    is_async: Option<bool>,
+    /// Only return functions carrying this attribute, e.g. "must_use"; the last path
+    /// segment is enough, so "instrument" matches `tracing::instrument` (optional)
+    #[serde(default)]
+    has_attribute: Option<String>,
+    /// Only return functions without this attribute, matched like has_attribute (optional)
+    #[serde(default)]
+    missing_attribute: Option<String>,
+    /// Only return symbols from these crates, e.g. ["tokio", "hyper"]; library symbols
+    /// are searched for them even without include_library (default: any crate)
+    #[serde(default)]
+    crates_include: Option<Vec<String>>,
+    /// Never return symbols from these crates (default: none)
+    #[serde(default)]
+    crates_exclude: Option<Vec<String>>,
+    /// With library symbols in the search, list exact name matches first and the
+    /// workspace's own symbols before dependencies' (default: true)
+    #[serde(default)]
+    prefer_workspace: Option<bool>,
+    /// Fields to include per symbol, e.g. ["name", "file_path", "start_line"]; one of
+    /// "name", "kind", "file_path", "start_line", "end_line", "documentation", "crate",
+    /// "crate_version", "moniker", "rustdoc_path", "rustdoc_url", "docs_url", "attributes", "owners", "size"
+    /// (default: all)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
 }
 
 /// Parameters for the enumerate_file tool
@@ -44,271 +98,5451 @@ struct FindSymbolParams {
 struct EnumerateFileParams {
     /// The absolute path to the file to enumerate
     file_path: String,
+    /// Fields to include per symbol, e.g. ["name", "file_path", "start_line"]; one of
+    /// "name", "kind", "file_path", "start_line", "end_line", "documentation", "crate",
+    /// "crate_version", "moniker", "rustdoc_path", "rustdoc_url", "docs_url", "attributes", "owners", "size"
+    /// (default: name, kind, lines, moniker, rustdoc links, attributes and size)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
 }
 
-/// Spawn background task to watch for file changes and update the index
-fn spawn_file_watcher(
-    analyzer: Arc<Mutex<Analyzer>>,
-    receiver: crossbeam_channel::Receiver<ra_ap_vfs::loader::Message>,
-) -> Result<(), Box<dyn std::error::Error>> {
-    // Check if we're in a tokio runtime context
-    let _handle = tokio::runtime::Handle::try_current()
-        .map_err(|_| "No tokio runtime available")?;
+/// Parameters for the enumerate_files tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EnumerateFilesParams {
+    /// Paths of the files to enumerate, absolute or relative to the project root
+    /// (e.g. the output of `git diff --name-only`)
+    file_paths: Vec<String>,
+    /// Fields to include per symbol, e.g. ["name", "file_path", "start_line"]; one of
+    /// "name", "kind", "file_path", "start_line", "end_line", "documentation", "crate",
+    /// "crate_version", "moniker", "rustdoc_path", "rustdoc_url", "docs_url", "attributes", "owners", "size"
+    /// (default: name, kind, lines, moniker, rustdoc links, attributes and size)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
 
-    tokio::spawn(async move {
-        use ra_ap_vfs::loader::Message;
+/// Parameters for the enumerate_directory tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EnumerateDirectoryParams {
+    /// Directory to enumerate recursively, absolute or relative to the project root (e.g. "src/analyzer")
+    directory: String,
+    /// Maximum number of files to enumerate (default: 100)
+    #[serde(default)]
+    max_files: Option<usize>,
+    /// Fields to include per symbol, as for enumerate_files (default: name, kind, lines, moniker, rustdoc
+    /// links, attributes and size)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
 
-        loop {
-            // Block on channel receive (runs in tokio threadpool)
-            let msg = match receiver.recv() {
-                Ok(msg) => msg,
-                Err(_) => {
-                    eprintln!("File watcher channel closed");
-                    break;
-                }
-            };
+/// Parameters for the analyze_closures tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct AnalyzeClosuresParams {
+    /// Name of the function whose closures should be listed
+    function: String,
+    /// Absolute path of the file containing the function (default: search the whole workspace)
+    #[serde(default)]
+    file_path: Option<String>,
+}
 
-            match msg {
-                Message::Changed { files } => {
-                    // Apply incremental changes
-                    let mut analyzer = analyzer.lock().unwrap();
-                    if let Err(e) = analyzer.apply_file_changes(files) {
-                        eprintln!("Error applying file changes: {}", e);
-                    }
-                }
-                Message::Progress { .. } => {
-                    // Ignore progress messages during incremental updates
-                }
-                Message::Loaded { .. } => {
-                    // Initial load messages, ignore (already processed during init)
-                }
-            }
-        }
-    });
+/// Parameters for the find_closure_apis tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FindClosureApisParams {
+    /// Only match this closure trait: "Fn", "FnMut", or "FnOnce" (default: any)
+    #[serde(default)]
+    fn_trait: Option<String>,
+    /// Only match closures taking exactly this many arguments (default: any)
+    #[serde(default)]
+    arity: Option<usize>,
+}
 
-    Ok(())
+/// Parameters for the expand_lifetimes tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ExpandLifetimesParams {
+    /// Name of the function whose signature should be expanded
+    function: String,
+    /// Absolute path of the file containing the function (default: search the whole workspace)
+    #[serde(default)]
+    file_path: Option<String>,
 }
 
-/// Cratographer MCP Server
-/// Provides tools for indexing and querying Rust code symbols
-#[derive(Clone)]
-struct CratographerServer {
-    tool_router: ToolRouter<Self>,
-    analyzer: Arc<Mutex<Analyzer>>,
-    init_state: Arc<Mutex<InitState>>,
+/// Parameters for the inspect_impl_trait tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct InspectImplTraitParams {
+    /// Name of the function to inspect (default: every function returning `impl Trait`)
+    #[serde(default)]
+    function: Option<String>,
+    /// Absolute path of a file to restrict the search to (default: the whole workspace)
+    #[serde(default)]
+    file_path: Option<String>,
 }
 
-#[tool_router]
-impl CratographerServer {
-    fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        // Create empty analyzer - will be populated by background task
-        let analyzer = Arc::new(Mutex::new(Analyzer::new()));
-        let init_state = Arc::new(Mutex::new(InitState::InProgress));
+/// Parameters for the feature_matrix tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FeatureMatrixParams {
+    /// Feature combinations to evaluate, each a list of enabled feature names
+    /// (default: no features, each feature alone, and all features)
+    #[serde(default)]
+    combinations: Option<Vec<Vec<String>>>,
+}
 
-        // Spawn background task to perform the slow initialization
-        let analyzer_clone = analyzer.clone();
-        let state_clone = init_state.clone();
-        tokio::spawn(async move {
-            eprintln!("Starting background initialization...");
+/// Parameters for the indexed_references tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct IndexedReferencesParams {
+    /// Definition name (e.g. "new") or qualified path (e.g. "cratographer::analyzer::Analyzer::new")
+    name: String,
+}
 
-            // Load the project in the background
-            let receiver = {
-                let mut analyzer = analyzer_clone.lock().unwrap();
-                match analyzer.load_project(".") {
-                    Ok(receiver) => receiver,
-                    Err(e) => {
-                        eprintln!("Failed to load project: {}", e);
-                        *state_clone.lock().unwrap() = InitState::Failed(format!("{}", e));
-                        return;
-                    }
-                }
-            };
+/// Parameters for the public_api_uses tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct PublicApiUsesParams {
+    /// Type name (e.g. "SymbolInfo") or qualified path (e.g. "analyzer::SymbolInfo")
+    type_name: String,
+}
+
+/// Parameters for the check_impl_coherence tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CheckImplCoherenceParams {
+    /// The impl header to check, e.g. "impl Display for Config" or "impl<T> From<T> for Wrapper<T>"
+    impl_header: String,
+    /// Absolute path of a file in the crate the impl would be added to (default: the first workspace crate)
+    #[serde(default)]
+    file_path: Option<String>,
+}
+
+/// Parameters for the cfg_duplicates tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CfgDuplicatesParams {
+    /// Only report duplicates that conflict in some configuration (default: false)
+    #[serde(default)]
+    only_conflicts: Option<bool>,
+}
+
+/// Parameters for the glob_conflicts tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct GlobConflictsParams {
+    /// The absolute path to the file to check (default: every workspace file)
+    #[serde(default)]
+    file_path: Option<String>,
+    /// When checking the whole workspace, stop after this many milliseconds and return the conflicts found so
+    /// far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the monomorphization_candidates tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct MonomorphizationCandidatesParams {
+    /// Only list functions called with at least this many distinct type arguments (default: 2)
+    #[serde(default)]
+    min_instantiations: Option<usize>,
+    /// Stop after this many milliseconds and return the estimates from the files scanned so far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the delegating_impls tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct DelegatingImplsParams {
+    /// Also list impls where only some methods forward to a field (default: false, only fully forwarding impls)
+    #[serde(default)]
+    include_partial: bool,
+    /// Stop after this many milliseconds and return the impls found so far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the error_handling_inventory tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ErrorHandlingInventoryParams {
+    /// Only list sites of these kinds: "unwrap", "expect", "try_operator", "anyhow_macro", "error_type",
+    /// "boxed_error" (default: all); counts always cover every kind
+    #[serde(default)]
+    kinds: Option<Vec<String>>,
+    /// Also count test code: #[test] functions and #[cfg(test)] modules (default: false)
+    #[serde(default)]
+    include_tests: bool,
+    /// Stop after this many milliseconds and return the inventory of the files scanned so far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the structural_search tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct StructuralSearchParams {
+    /// SSR pattern with `$name` placeholders, e.g. "$a.unwrap()", "Option<$t>" or "std::mem::replace($a, $b)";
+    /// paths are best written fully qualified
+    pattern: String,
+    /// The absolute path to the file to search (default: every workspace file)
+    #[serde(default)]
+    file_path: Option<String>,
+    /// When searching the whole workspace, stop after this many milliseconds and return the matches found so
+    /// far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the structural_replace tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct StructuralReplaceParams {
+    /// SSR rule `pattern ==>> replacement`, e.g. "$a.unwrap() ==>> $a.expect(\"checked\")"; paths are best
+    /// written fully qualified
+    rule: String,
+    /// The absolute path to the file to rewrite (default: every workspace file)
+    #[serde(default)]
+    file_path: Option<String>,
+    /// When rewriting the whole workspace, stop after this many milliseconds and return the edits found so far
+    /// (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the logging_sites tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct LoggingSitesParams {
+    /// Only list sites at this level or above: "trace", "debug", "info", "warn" or "error" (default: all,
+    /// including sites whose level isn't known)
+    #[serde(default)]
+    min_level: Option<String>,
+    /// Also list the functions that have no logging, tracing span or #[instrument] (default: false)
+    #[serde(default)]
+    silent_functions: bool,
+    /// Stop after this many milliseconds and return the sites of the files scanned so far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the file_dependencies tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FileDependenciesParams {
+    /// The absolute path to the file to summarize
+    file_path: String,
+}
+
+/// Parameters for the use_declarations tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct UseDeclarationsParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Only report declarations with an unused import (default: false)
+    #[serde(default)]
+    only_unused: bool,
+}
+
+/// Parameters for the move_item tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct MoveItemParams {
+    /// Name of the item to move, optionally qualified (e.g. "normalize_whitespace" or "analyzer::display_type")
+    name: String,
+    /// Absolute path of the file holding the item, to disambiguate same-named items (optional)
+    #[serde(default)]
+    file_path: Option<String>,
+    /// Absolute path of the file whose module receives the item
+    target_file: String,
+}
+
+/// Parameters for the split_module tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct SplitModuleParams {
+    /// The absolute path to the file to split
+    file_path: String,
+    /// Names of the top-level items to move into the new submodule
+    symbols: Vec<String>,
+    /// Name of the submodule to create
+    module_name: String,
+}
+
+/// Parameters for the find_constants tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FindConstantsParams {
+    /// Exact value to look for, e.g. "4096", "0x1000" or "\"utf-8\"" (optional)
+    #[serde(default)]
+    value: Option<String>,
+    /// Smallest accepted integer value (optional)
+    #[serde(default)]
+    min: Option<i64>,
+    /// Largest accepted integer value (optional)
+    #[serde(default)]
+    max: Option<i64>,
+    /// Case-insensitive substring the name must contain, e.g. "timeout" (optional)
+    #[serde(default)]
+    name_contains: Option<String>,
+}
+
+/// Parameters for the trait_override_matrix tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct TraitOverrideMatrixParams {
+    /// Name, qualified path or symbol_id of the trait, e.g. "Handler" or "fmt::Display"
+    trait_name: String,
+    /// Include impls from dependencies and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the summarize_module tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct SummarizeModuleParams {
+    /// The absolute path to the file defining the module (the crate root to summarize a whole crate)
+    file_path: String,
+    /// Fold submodules into the summary (default: true)
+    #[serde(default)]
+    recursive: Option<bool>,
+}
+
+/// Parameters for the find_conversions tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FindConversionsParams {
+    /// Type name (e.g. "AnalyzerError"), qualified path (e.g. "analyzer::AnalyzerError") or symbol_id
+    type_name: String,
+}
+
+/// Parameters for the type_operators tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct TypeOperatorsParams {
+    /// Type name (e.g. "SymbolInfo"), qualified path (e.g. "analyzer::SymbolInfo") or symbol_id
+    type_name: String,
+}
+
+/// Parameters for the explain_iterator_chain tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ExplainIteratorChainParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of any method call in the chain (0-based)
+    line: u32,
+    /// Column on that line (0-based)
+    column: u32,
+}
+
+/// Parameters for the find_references tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FindReferencesParams {
+    /// Definition name (e.g. "new"), qualified path (e.g. "analyzer::Analyzer::new") or
+    /// symbol_id; alternatively give file_path, line and column
+    #[serde(default)]
+    name: Option<String>,
+    /// The absolute path to the file containing the symbol
+    #[serde(default)]
+    file_path: Option<String>,
+    /// Line of the symbol (0-based)
+    #[serde(default)]
+    line: Option<u32>,
+    /// Column of the symbol (0-based)
+    #[serde(default)]
+    column: Option<u32>,
+}
+
+/// Parameters for the entry_points tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EntryPointsParams {
+    /// Only report these kinds: "main", "build_script", "public_api", "no_mangle", "test" (default: all)
+    #[serde(default)]
+    kinds: Option<Vec<String>>,
+    /// Only report entry points of this crate
+    #[serde(default)]
+    crate_name: Option<String>,
+}
+
+/// Parameters for the runnables tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct RunnablesParams {
+    /// List the runnables of this file (absolute path); give this or `crate_name`
+    #[serde(default)]
+    file_path: Option<String>,
+    /// List the runnables of every file of this workspace crate; give this or `file_path`
+    #[serde(default)]
+    crate_name: Option<String>,
+    /// Only report these kinds: "test", "test_module", "bench", "doctest", "bin" (default: all)
+    #[serde(default)]
+    kinds: Option<Vec<String>>,
+}
+
+/// Parameters for the goto_definition tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct GotoDefinitionParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the symbol (0-based)
+    line: u32,
+    /// Column of the symbol (0-based)
+    column: u32,
+}
+
+/// Parameters for the highlight_related tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct HighlightRelatedParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the identifier or keyword (0-based)
+    line: u32,
+    /// Column of the identifier or keyword (0-based)
+    column: u32,
+}
+
+/// Parameters for the annotations tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct AnnotationsParams {
+    /// The absolute path to the file
+    file_path: String,
+}
+
+/// Parameters for the goto_type_definition tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct GotoTypeDefinitionParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the expression or variable (0-based)
+    line: u32,
+    /// Column of the expression or variable (0-based)
+    column: u32,
+}
+
+/// Parameters for the hover tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct HoverParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the symbol or expression (0-based)
+    line: u32,
+    /// Column of the symbol or expression (0-based)
+    column: u32,
+}
+
+/// Parameters for the callers_of tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CallersOfParams {
+    /// Function name (e.g. "parse"), qualified path (e.g. "analyzer::Analyzer::find_symbol") or symbol_id
+    name: String,
+    /// Leave out calls from test functions (default: false)
+    #[serde(default)]
+    exclude_tests: Option<bool>,
+}
+
+/// Parameters for the who_exports tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct WhoExportsParams {
+    /// Bare identifier, e.g. "Error"
+    name: String,
+    /// Absolute path of the file to import into; import paths are computed
+    /// from its module (default: the root of the first workspace crate)
+    #[serde(default)]
+    file_path: Option<String>,
+}
+
+/// Parameters for the type_hierarchy tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct TypeHierarchyParams {
+    /// Name, qualified path or symbol_id of a trait (e.g. "SymbolProvider") or type (e.g. "analyzer::QuickIndex")
+    name: String,
+    /// Include subtraits, implementors and implemented traits from dependencies
+    /// and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the find_implementations tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FindImplementationsParams {
+    /// Name, qualified path or symbol_id of the trait, e.g. "SymbolProvider" or "fmt::Display"
+    trait_name: String,
+    /// Include impls from dependencies and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the impls_of_type tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ImplsOfTypeParams {
+    /// Type name (e.g. "QuickIndex"), qualified path (e.g. "analyzer::QuickIndex") or symbol_id
+    type_name: String,
+    /// Include impls from dependencies and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the expand_macro tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ExpandMacroParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the macro name or `derive` attribute (0-based)
+    line: u32,
+    /// Column of the macro name or `derive` attribute (0-based)
+    column: u32,
+}
+
+/// Parameters for the expand_derive tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ExpandDeriveParams {
+    /// Struct, enum or union name (e.g. "SymbolInfo"), qualified path (e.g. "analyzer::SymbolInfo") or symbol_id
+    type_name: String,
+    /// The derive to expand, e.g. "Serialize" or "serde::Serialize"
+    derive: String,
+}
+
+/// Parameters for the enum_match_sites tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EnumMatchSitesParams {
+    /// Enum name (e.g. "SymbolKind"), qualified path (e.g. "analyzer::SymbolKind") or symbol_id
+    enum_name: String,
+    /// Stop scanning after this many milliseconds and return the sites found so far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the view_hir tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ViewHirParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Any line inside the function body (0-based)
+    line: u32,
+    /// Column on that line (0-based)
+    column: u32,
+}
+
+/// Parameters for the feature_gated_api tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FeatureGatedApiParams {
+    /// Only report this workspace crate (default: all of them)
+    #[serde(default)]
+    crate_name: Option<String>,
+}
+
+/// Parameters for the view_mir tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ViewMirParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Any line inside the function (0-based)
+    line: u32,
+    /// Column on that line (0-based)
+    column: u32,
+}
+
+/// Parameters for the hidden_api_uses tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct HiddenApiUsesParams {
+    /// Only report hidden items of this dependency (default: all dependencies)
+    #[serde(default)]
+    crate_name: Option<String>,
+}
+
+/// Parameters for the view_syntax_tree tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ViewSyntaxTreeParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// First line of the range to show (0-based); the whole file if omitted
+    #[serde(default)]
+    start_line: Option<u32>,
+    /// Last line of the range to show (0-based, inclusive; default: start_line)
+    #[serde(default)]
+    end_line: Option<u32>,
+}
+
+/// Parameters for the build_scripts tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BuildScriptsParams {
+    /// Only report the build script of this workspace package (default: all of them)
+    #[serde(default)]
+    crate_name: Option<String>,
+}
+
+/// Parameters for the build_env_usages tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BuildEnvUsagesParams {
+    /// Only report these macros: "env", "option_env", "include_str", "include_bytes", "include" (default: all)
+    #[serde(default)]
+    macros: Option<Vec<String>>,
+}
+
+/// Parameters for the embedded_files tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EmbeddedFilesParams {
+    /// Only report embeddings of this file, or of the files below this directory; absolute or relative to the
+    /// project root, e.g. "queries/get_user.sql" (default: all)
+    #[serde(default)]
+    path: Option<String>,
+    /// Only report these macros: "include_str", "include_bytes", "include", "query_file", "query_file_as",
+    /// "query_file_scalar", "query_file_unchecked", "query_file_as_unchecked", "query_file_scalar_unchecked",
+    /// "migrate" (default: all)
+    #[serde(default)]
+    macros: Option<Vec<String>>,
+}
+
+/// Parameters for the diagnostics tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct DiagnosticsParams {
+    /// The absolute path to the file (default: every workspace file)
+    #[serde(default)]
+    file_path: Option<String>,
+    /// Include rust-analyzer's experimental diagnostics, which are prone to false positives (default: false)
+    #[serde(default)]
+    experimental: Option<bool>,
+    /// When checking the whole workspace, stop after this many milliseconds and return the files checked so
+    /// far (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the check_snippet tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CheckSnippetParams {
+    /// The code to check: items, or statements checked as a function body
+    snippet: String,
+    /// Absolute path of the file of the module the snippet would be written in
+    file_path: String,
+    /// Include rust-analyzer's experimental diagnostics, which are prone to false positives (default: false)
+    #[serde(default)]
+    experimental: Option<bool>,
+}
+
+/// Parameters for the msrv_report tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct MsrvReportParams {
+    /// Only report this workspace crate (default: all of them)
+    #[serde(default)]
+    crate_name: Option<String>,
+    /// Only report features stabilized after this Rust version, e.g. "1.70" (default: all of them)
+    #[serde(default)]
+    since: Option<String>,
+}
+
+/// Parameters for the object_safety tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ObjectSafetyParams {
+    /// Check only this trait, whether or not it is used as `dyn`: a name, qualified path or symbol_id
+    /// (default: every workspace trait used as `dyn Trait`)
+    #[serde(default)]
+    trait_name: Option<String>,
+}
+
+/// Parameters for the signature_help tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct SignatureHelpParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line inside the call's argument list (0-based)
+    line: u32,
+    /// Column inside the call's argument list (0-based)
+    column: u32,
+}
+
+/// Parameters for the drop_types tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct DropTypesParams {
+    /// Also count Drop impls from dependencies and the standard library, such as MutexGuard or File
+    /// (default: false; note that Vec, Box and the like qualify too, so most types will)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the inlay_hints tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct InlayHintsParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// First line of the range (0-based); the whole file if omitted
+    #[serde(default)]
+    start_line: Option<u32>,
+    /// Last line of the range (0-based, inclusive; default: start_line)
+    #[serde(default)]
+    end_line: Option<u32>,
+}
+
+/// Parameters for the derive_usage tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct DeriveUsageParams {
+    /// Only report this derive, by name or path, e.g. "Serialize" or "serde::Serialize" (default: all derives)
+    #[serde(default)]
+    derive: Option<String>,
+    /// Only count types at least this many bytes in size; types of unknown size, such as generic ones,
+    /// are left out (default: no limit)
+    #[serde(default)]
+    min_size: Option<u64>,
+}
+
+/// Parameters for the fmt_coverage tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct FmtCoverageParams {
+    /// Only report this workspace crate (default: every workspace crate)
+    #[serde(default)]
+    crate_name: Option<String>,
+    /// Only count types declared in this module or below it, e.g. "analyzer" or "crate::config" (default: all)
+    #[serde(default)]
+    module: Option<String>,
+    /// Only list types lacking this impl: "debug" or "display" (default: lacking either)
+    #[serde(default)]
+    missing: Option<String>,
+}
+
+/// Parameters for the classify_token tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ClassifyTokenParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the token (0-based)
+    line: u32,
+    /// Column of the token (0-based)
+    column: u32,
+}
+
+/// Parameters for the preview_rename tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct PreviewRenameParams {
+    /// The absolute path to the file
+    file_path: String,
+    /// Line of the symbol to rename, at its definition or any reference (0-based)
+    line: u32,
+    /// Column of the symbol to rename (0-based)
+    column: u32,
+    /// The new name
+    new_name: String,
+}
+
+/// Parameters for the rewrite_paths tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct RewritePathsParams {
+    /// Path the symbol or module had before the move, e.g. "cratographer::util::parse"
+    /// or "crate::util::parse"
+    old_path: String,
+    /// Path it has now, e.g. "cratographer::parsing::parse"
+    new_path: String,
+}
+
+/// Parameters for the module_tree tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ModuleTreeParams {
+    /// Only return the tree of this workspace crate (default: all of them)
+    #[serde(default)]
+    crate_name: Option<String>,
+}
+
+/// Parameters for the crate_graph tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CrateGraphParams {
+    /// Also list library crates: dependencies and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the crate_cfg tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CrateCfgParams {
+    /// Only report this crate, workspace or library, e.g. "tokio" (default: every workspace crate)
+    #[serde(default)]
+    crate_name: Option<String>,
+    /// Also report library crates: dependencies and the standard library (default: false)
+    #[serde(default)]
+    include_library: Option<bool>,
+}
+
+/// Parameters for the enumerate_crate tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EnumerateCrateParams {
+    /// Name of a workspace crate or dependency, e.g. "serde_json"
+    crate_name: String,
+    /// Version to pick when several versions of the crate are loaded (default: the newest)
+    #[serde(default)]
+    version: Option<String>,
+    /// Number of items to skip, from `next_offset` of the previous page (default: 0)
+    #[serde(default)]
+    offset: Option<usize>,
+    /// Maximum number of items per page (default: 200)
+    #[serde(default)]
+    limit: Option<usize>,
+}
+
+/// Parameters for the crate_dependencies tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct CrateDependenciesParams {
+    /// Name of the workspace package, e.g. "cratographer"
+    crate_name: String,
+}
+
+/// Parameters for the add_scratch_root tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct AddScratchRootParams {
+    /// Directory of candidate .rs files, e.g. "/tmp/scratch"
+    directory: String,
+    /// Workspace crate the files are analyzed as modules of, e.g. "cratographer"
+    crate_name: String,
+}
+
+/// Parameters for the remove_scratch_root tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct RemoveScratchRootParams {
+    /// Directory previously passed to add_scratch_root
+    directory: String,
+}
+
+/// Parameters for the reverse_dependencies tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ReverseDependenciesParams {
+    /// Name of a workspace or library crate, e.g. "serde"
+    crate_name: String,
+    /// Also list crates depending on it through other crates (default: true)
+    #[serde(default)]
+    transitive: Option<bool>,
+}
+/// Parameters for the owned_symbols tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct OwnedSymbolsParams {
+    /// Team name from .cratographer/teams.json or CODEOWNERS handle, e.g. "payments" or "@org/payments"
+    owner: String,
+    /// Fields to include per symbol, as for enumerate_files (default: name, kind, lines, moniker, rustdoc
+    /// links, attributes and size)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
+
+/// Parameters for the get_source tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct GetSourceParams {
+    /// Symbol name (e.g. "normalize_whitespace"), qualified path (e.g. "analyzer::Analyzer::new") or symbol_id,
+    /// such as the moniker of a find_symbol result
+    name: String,
+    /// Lines of surrounding context to return on either side of the definition (default: 0)
+    #[serde(default)]
+    context_lines: Option<u32>,
+}
+
+/// Parameters for the get_signature tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct GetSignatureParams {
+    /// Symbol name (e.g. "normalize_whitespace"), qualified path (e.g. "analyzer::Analyzer::new") or symbol_id,
+    /// such as the moniker of a find_symbol result
+    name: String,
+}
+
+/// Parameters for the tests_for_function tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct TestsForFunctionParams {
+    /// Function name (e.g. "parse_config"), qualified path (e.g. "config::Config::parse") or symbol_id
+    name: String,
+    /// Maximum number of calls between a test and the function; 1 finds only tests calling it directly (default: 3)
+    #[serde(default)]
+    depth: Option<usize>,
+    /// Stop walking the call hierarchy after this many milliseconds and return the tests found so far, marked
+    /// truncated (default: no limit)
+    #[serde(default)]
+    budget_ms: Option<u64>,
+}
+
+/// Parameters for the resolve_moniker tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ResolveMonikerParams {
+    /// Moniker returned by an earlier query, e.g. "cratographer@0.1.0::analyzer::Analyzer::new#fn"
+    moniker: String,
+    /// Fields to include per symbol, e.g. ["name", "file_path", "start_line"]; one of
+    /// "name", "kind", "file_path", "start_line", "end_line", "documentation", "crate",
+    /// "crate_version", "moniker", "rustdoc_path", "rustdoc_url", "docs_url", "attributes", "owners", "size"
+    /// (default: all)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
+
+/// Parameters for the resolve_path tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct ResolvePathParams {
+    /// Path starting with a crate name, e.g. "tokio::sync::mpsc::Sender" or "std::vec::Vec::new"
+    path: String,
+    /// Fields to include for the symbol, as for resolve_moniker (default: all)
+    #[serde(default)]
+    fields: Option<Vec<String>>,
+}
+
+/// Parameters for the begin_snapshot tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct BeginSnapshotParams {
+    /// Seconds after which the snapshot ends by itself if end_snapshot isn't
+    /// called (default: 30, at most 300)
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+}
+
+/// Parameters for the end_snapshot tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct EndSnapshotParams {
+    /// Id returned by begin_snapshot
+    snapshot_id: u64,
+}
+
+/// Parameters for the index_events tool
+#[derive(Serialize, Deserialize, JsonSchema)]
+struct IndexEventsParams {
+    /// Return only events after this sequence number, as returned in
+    /// `latest_sequence` by a previous call (default: all retained events)
+    #[serde(default)]
+    since: Option<u64>,
+}
+
+/// Fields of a symbol result, in output order
+const SYMBOL_FIELDS: &[&str] = &[
+    "name",
+    "kind",
+    "file_path",
+    "start_line",
+    "end_line",
+    "documentation",
+    "crate",
+    "crate_version",
+    "moniker",
+    "rustdoc_path",
+    "rustdoc_url",
+    "docs_url",
+    "attributes",
+    "owners",
+    "size",
+];
+
+/// Fields `enumerate_file` and `enumerate_files` report unless asked otherwise;
+/// the file path, crate and owners are the same for every symbol of a file
+const FILE_SYMBOL_FIELDS: &[&str] =
+    &["name", "kind", "start_line", "end_line", "moniker", "rustdoc_path", "rustdoc_url", "attributes", "size"];
+
+/// Files `enumerate_directory` enumerates unless asked otherwise
+const ENUMERATE_DIRECTORY_MAX_FILES: usize = 100;
+
+/// Items per page of `enumerate_crate` unless asked otherwise
+const ENUMERATE_CRATE_PAGE_SIZE: usize = 200;
+
+/// Resolve a `fields` parameter against a tool's default fields
+fn symbol_fields(requested: Option<&[String]>, defaults: &[&'static str]) -> Result<Vec<&'static str>, McpError> {
+    let Some(requested) = requested else {
+        return Ok(defaults.to_vec());
+    };
+    requested
+        .iter()
+        .map(|field| {
+            SYMBOL_FIELDS.iter().copied().find(|f| f == field).ok_or_else(|| McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown field '{}', expected one of: {}", field, SYMBOL_FIELDS.join(", ")).into(),
+                data: None,
+            })
+        })
+        .collect()
+}
+
+/// Render a symbol with only the given fields
+///
+/// Symbols from a syntax-only backend also get `"approximate": true`.
+fn symbol_output(sym: &SymbolInfo, fields: &[&str]) -> SymbolOutput {
+    let mut output = SymbolOutput::default();
+    for field in fields {
+        match *field {
+            "name" => output.name = Some(sym.name.clone()),
+            "kind" => output.kind = Some(sym.kind),
+            "file_path" => output.file_path = Some(sym.file_path.clone()),
+            "start_line" => output.start_line = Some(sym.start_line),
+            "end_line" => output.end_line = Some(sym.end_line),
+            "documentation" => output.documentation = sym.documentation.clone(),
+            "crate" => output.crate_name = sym.crate_name.clone(),
+            "crate_version" => output.crate_version = sym.crate_version.clone(),
+            "moniker" => output.moniker = sym.moniker.clone(),
+            "rustdoc_path" => output.rustdoc_path = sym.rustdoc.as_ref().map(|d| d.path.clone()),
+            "rustdoc_url" => output.rustdoc_url = sym.rustdoc.as_ref().map(|d| d.url.clone()),
+            "docs_url" => output.docs_url = sym.rustdoc.as_ref().and_then(|d| d.docs_url.clone()),
+            "attributes" => output.attributes = sym.attributes.clone(),
+            "owners" => output.owners = sym.owners.clone(),
+            "size" => {
+                output.size = sym.size.map(|size| outputs::SymbolSizeOutput { lines: size.lines, bytes: size.bytes, tokens: size.tokens })
+            }
+            _ => {}
+        }
+    }
+    // Always flagged, whatever the selection, so approximate results can't pass for exact ones
+    output.approximate = sym.approximate;
+    output
+}
+
+/// Render a module and, recursively, its submodules
+fn module_node_output(node: &ModuleNode) -> outputs::ModuleNodeOutput {
+    outputs::ModuleNodeOutput {
+        name: node.name.clone(),
+        path: node.path.clone(),
+        file_path: node.file_path.clone(),
+        start_line: node.start_line,
+        end_line: node.end_line,
+        inline: node.inline,
+        public: node.public,
+        children: node.children.iter().map(module_node_output).collect(),
+    }
+}
+
+/// Render mounted scratch files
+fn scratch_root_output(files: &[ScratchFile]) -> outputs::ScratchRootOutput {
+    outputs::ScratchRootOutput {
+        files: files.iter().map(|file| outputs::ScratchFileOutput {
+            file_path: file.file_path.clone(),
+            module: file.module.clone(),
+            crate_name: file.crate_name.clone(),
+        }).collect(),
+    }
+}
+
+/// Render how much of a time-boxed scan was done
+fn coverage_output(coverage: Coverage) -> outputs::CoverageOutput {
+    outputs::CoverageOutput {
+        complete: coverage.complete(),
+        files_scanned: coverage.files_scanned,
+        files_total: coverage.files_total,
+    }
+}
+
+/// Summary suffix for a scan the budget cut short
+fn coverage_note(coverage: Coverage) -> String {
+    if coverage.complete() {
+        String::new()
+    } else {
+        format!(" (partial: budget ran out after {} of {} file(s))", coverage.files_scanned, coverage.files_total)
+    }
+}
+
+/// Render an impl block
+fn impl_block_output(block: &ImplBlock) -> outputs::ImplBlockOutput {
+    outputs::ImplBlockOutput {
+        trait_path: block.trait_path.clone(),
+        self_type: block.self_type.clone(),
+        header: block.header.clone(),
+        blanket: block.blanket,
+        items: block.items.clone(),
+        crate_name: block.crate_name.clone(),
+        file_path: block.file_path.clone(),
+        start_line: block.start_line,
+        end_line: block.end_line,
+    }
+}
+
+/// JSON Schema of a tool's output type, advertised as its output schema
+fn output_schema<T: JsonSchema + 'static>() -> Arc<JsonObject> {
+    cached_schema_for_type::<T>()
+}
+
+/// Build a successful tool result: the output as structured content matching
+/// the tool's output schema, with the summary as its only text
+fn tool_result(summary: String, output: &impl Serialize) -> CallToolResult {
+    let mut result = CallToolResult::success(vec![Content::text(summary)]);
+    result.structured_content = Some(serde_json::to_value(output).unwrap());
+    result
+}
+
+/// Build the result of a name-based tool whose name matched several symbols
+///
+/// The candidates go into the tool's own output type through `output`, with
+/// its other fields left empty, so the result still matches its schema.
+fn ambiguous_result<O: Serialize>(
+    name: &str,
+    candidates: &[Candidate],
+    output: impl FnOnce(Vec<outputs::CandidateOutput>) -> O,
+) -> CallToolResult {
+    let mut summary = format!("'{}' matches {} symbols; repeat the query with one's symbol_id:", name, candidates.len());
+    for c in candidates {
+        summary.push_str(&format!("\n  {} ({}:{}) {}", c.symbol_id, c.file_path, c.line, c.context));
+    }
+    let candidates = candidates.iter().map(|c| outputs::CandidateOutput {
+        symbol_id: c.symbol_id.clone(),
+        path: c.path.clone(),
+        kind: c.kind.to_string(),
+        crate_name: c.crate_name.clone(),
+        local: c.local,
+        file_path: c.file_path.clone(),
+        line: c.line,
+        context: c.context.clone(),
+    }).collect();
+    tool_result(summary, &output(candidates))
+}
+
+/// A file change reported by the watcher: the path and its new contents, if any
+type FileChange = (ra_ap_paths::AbsPathBuf, Option<Vec<u8>>);
+
+/// Files scanned into the reference index per lock of the analyzer
+const REFERENCE_INDEX_BATCH: usize = 16;
+
+/// How often the watcher checks whether deferred changes can be applied,
+/// when no new changes arrive
+const SNAPSHOT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Apply the changes deferred by snapshots, if none is held any more
+///
+/// The snapshots lock is held while applying, so deferred changes can't be
+/// overtaken by newer ones to the same file.
+fn apply_released_changes(analyzer: &Mutex<Analyzer>, snapshots: &Mutex<Snapshots<FileChange>>) -> usize {
+    let mut snapshots = snapshots.lock().unwrap();
+    let Some(files) = snapshots.release(Instant::now()) else {
+        return 0;
+    };
+    let count = files.len();
+    if let Err(e) = analyzer.lock().unwrap().apply_file_changes(files) {
+        eprintln!("Error applying file changes: {}", e);
+    }
+    count
+}
+
+/// Scan one batch of a queued reference index rescan, returning whether
+/// more is left
+fn continue_reference_rescan(analyzer: &Mutex<Analyzer>) -> bool {
+    match analyzer.lock().unwrap().continue_reference_rescan(REFERENCE_INDEX_BATCH) {
+        Ok(done) => !done,
+        Err(e) => {
+            eprintln!("Warning: Reference index rescan failed: {}", e);
+            false
+        }
+    }
+}
+
+/// Spawn background task to watch for file changes and update the index
+///
+/// While a snapshot is open, changes are deferred rather than applied. A
+/// reference index rescan queued by a change is run a batch per iteration,
+/// so the analyzer is never locked for the whole of it.
+fn spawn_file_watcher(
+    analyzer: Arc<Mutex<Analyzer>>,
+    snapshots: Arc<Mutex<Snapshots<FileChange>>>,
+    receiver: crossbeam_channel::Receiver<ra_ap_vfs::loader::Message>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    // Check if we're in a tokio runtime context
+    let _handle = tokio::runtime::Handle::try_current()
+        .map_err(|_| "No tokio runtime available")?;
+
+    tokio::spawn(async move {
+        use ra_ap_vfs::loader::Message;
+
+        let mut rescanning = false;
+        loop {
+            // Block on channel receive (runs in tokio threadpool), waking up
+            // now and then to apply changes deferred by an expired snapshot,
+            // or right away while a reference index rescan is under way
+            let timeout = if rescanning { Duration::ZERO } else { SNAPSHOT_POLL_INTERVAL };
+            let msg = match receiver.recv_timeout(timeout) {
+                Ok(msg) => msg,
+                Err(crossbeam_channel::RecvTimeoutError::Timeout) => {
+                    apply_released_changes(&analyzer, &snapshots);
+                    rescanning = continue_reference_rescan(&analyzer);
+                    continue;
+                }
+                Err(crossbeam_channel::RecvTimeoutError::Disconnected) => {
+                    eprintln!("File watcher channel closed");
+                    break;
+                }
+            };
+
+            match msg {
+                Message::Changed { files } => {
+                    // Apply incremental changes, after any deferred ones
+                    snapshots.lock().unwrap().defer(files);
+                    apply_released_changes(&analyzer, &snapshots);
+                }
+                Message::Progress { .. } => {
+                    // Ignore progress messages during incremental updates
+                }
+                Message::Loaded { .. } => {
+                    // Initial load messages, ignore (already processed during init)
+                }
+            }
+            rescanning = continue_reference_rescan(&analyzer);
+        }
+    });
+
+    Ok(())
+}
+
+/// Cratographer MCP Server
+/// Provides tools for indexing and querying Rust code symbols
+#[derive(Clone)]
+struct CratographerServer {
+    tool_router: ToolRouter<Self>,
+    analyzer: Arc<Mutex<Analyzer>>,
+    init_state: Arc<Mutex<InitState>>,
+    cache: Arc<Mutex<QueryCache>>,
+    /// Open snapshots, and the file changes deferred while one is held
+    snapshots: Arc<Mutex<Snapshots<FileChange>>>,
+    /// Whether the task applying file changes was started
+    watcher_started: Arc<Mutex<bool>>,
+    /// Backend answering symbol queries while the analyzer is still loading
+    fallback: Arc<Mutex<Option<Box<dyn SymbolProvider>>>>,
+    /// Whether view_mir is enabled; off unless `CRATOGRAPHER_ENABLE_MIR` is
+    /// set, as MIR dumps can be very large
+    enable_mir: bool,
+}
+
+#[tool_router]
+impl CratographerServer {
+    fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Self::with_fallback(None)
+    }
+
+    /// Create a server whose symbol queries are answered by `fallback` until
+    /// rust-analyzer has loaded the project
+    ///
+    /// Without one, a `QuickIndex` of the project is built and used instead.
+    fn with_fallback(fallback: Option<Box<dyn SymbolProvider>>) -> Result<Self, Box<dyn std::error::Error>> {
+        // Create empty analyzer - will be populated by background task
+        let analyzer = Arc::new(Mutex::new(Analyzer::new()));
+        let init_state = Arc::new(Mutex::new(InitState::InProgress));
+        let snapshots = Arc::new(Mutex::new(Snapshots::default()));
+
+        // Spawn background task to perform the slow initialization
+        let analyzer_clone = analyzer.clone();
+        let state_clone = init_state.clone();
+        let snapshots_clone = snapshots.clone();
+        let watcher_started = Arc::new(Mutex::new(false));
+        let watcher_started_clone = watcher_started.clone();
+        let fallback = Arc::new(Mutex::new(fallback));
+        let fallback_clone = fallback.clone();
+        tokio::spawn(async move {
+            eprintln!("Starting background initialization...");
+
+            // Build the syntactic quick index first, so symbol queries get
+            // approximate answers while rust-analyzer loads
+            if fallback_clone.lock().unwrap().is_none() {
+                match QuickIndex::build(".") {
+                    Ok(index) => {
+                        *fallback_clone.lock().unwrap() = Some(Box::new(index));
+                        eprintln!("Quick index ready");
+                    }
+                    Err(e) => eprintln!("Warning: Could not build quick index: {}", e),
+                }
+            }
+
+            // Load the project in the background
+            let receiver = {
+                let mut analyzer = analyzer_clone.lock().unwrap();
+                match analyzer.load_project(".") {
+                    Ok(receiver) => receiver,
+                    Err(e) => {
+                        eprintln!("Failed to load project: {}", e);
+                        *state_clone.lock().unwrap() = InitState::Failed(format!("{}", e));
+                        return;
+                    }
+                }
+            };
+
+            // Load library symbols from the cache shared with sibling projects,
+            // falling back to a warm-up query to force rust-analyzer's library
+            // index to load
+            {
+                let mut analyzer = analyzer_clone.lock().unwrap();
+                match analyzer.load_library_cache() {
+                    Ok(stats) => eprintln!(
+                        "Library cache loaded ({} crate(s) cached, {} indexed)",
+                        stats.hits, stats.misses
+                    ),
+                    Err(e) => {
+                        eprintln!("Warning: Could not load library cache: {}", e);
+                        let warmup_options = SearchOptions {
+                            mode: SearchMode::Exact,
+                            include_library: true,
+                            filter: SymbolFilter::Types,
+                            crate_version: None,
+                            signature: SignatureFilter::default(),
+                            crates_include: Vec::new(),
+                            crates_exclude: Vec::new(),
+                            prefer_workspace: false,
+                        };
+                        if let Err(e) = analyzer.find_symbol("HashMap", &warmup_options) {
+                            eprintln!("Warning: Warm-up query failed: {}", e);
+                        }
+                    }
+                }
+            }
+
+            // Spawn file watcher task with the receiver
+            match spawn_file_watcher(analyzer_clone.clone(), snapshots_clone, receiver) {
+                Ok(_) => {
+                    *watcher_started_clone.lock().unwrap() = true;
+                    eprintln!("File watcher initialized");
+                }
+                Err(e) => eprintln!("Warning: Could not start file watcher: {}", e),
+            }
+
+            // Mark as ready
+            *state_clone.lock().unwrap() = InitState::Ready;
+            *fallback_clone.lock().unwrap() = None;
+            eprintln!("Background initialization complete - server ready");
+
+            // Load or build the cross-reference index; only files changed since
+            // the last run are rescanned. The analyzer is locked for one batch
+            // of files at a time, so queries are answered while it builds.
+            let built = tokio::task::spawn_blocking(move || -> Result<(), AnalyzerError> {
+                let mut build = analyzer_clone.lock().unwrap().begin_reference_index()?;
+                while !analyzer_clone.lock().unwrap().continue_reference_index(&mut build, REFERENCE_INDEX_BATCH)? {
+                    std::thread::yield_now();
+                }
+                Ok(())
+            })
+            .await;
+            match built {
+                Ok(Ok(())) => eprintln!("Reference index ready"),
+                Ok(Err(e)) => eprintln!("Warning: Could not build reference index: {}", e),
+                Err(e) => eprintln!("Warning: Reference index build failed: {}", e),
+            }
+        });
+
+        Ok(Self {
+            tool_router: Self::tool_router(),
+            analyzer,
+            init_state,
+            cache: Arc::new(Mutex::new(QueryCache::default())),
+            snapshots,
+            watcher_started,
+            fallback,
+            enable_mir: std::env::var("CRATOGRAPHER_ENABLE_MIR").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true")),
+        })
+    }
+
+    /// Wait for initialization to complete (useful for tests)
+    #[allow(dead_code)]
+    async fn wait_for_ready(&self) -> Result<(), String> {
+        use tokio::time::{sleep, Duration};
+
+        for _ in 0..100 {  // Wait up to 10 seconds
+            match &*self.init_state.lock().unwrap() {
+                InitState::Ready => return Ok(()),
+                InitState::Failed(err) => return Err(err.clone()),
+                InitState::InProgress => {
+                    sleep(Duration::from_millis(100)).await;
+                }
+            }
+        }
+        Err("Initialization timeout".to_string())
+    }
+
+    /// Check if initialization is complete and return appropriate error if not
+    fn check_init_state(&self) -> Result<(), McpError> {
+        match &*self.init_state.lock().unwrap() {
+            InitState::InProgress => {
+                Err(McpError {
+                    code: ErrorCode(-32001),
+                    message: "Server is still initializing. Please try again in a moment.".into(),
+                    data: None,
+                })
+            }
+            InitState::Failed(err) => {
+                Err(McpError {
+                    code: ErrorCode(-32002),
+                    message: format!("Server initialization failed: {}", err).into(),
+                    data: None,
+                })
+            }
+            InitState::Ready => Ok(())
+        }
+    }
+
+    /// Run a query against the analyzer, reusing the result of an identical
+    /// earlier query if the index hasn't changed since
+    ///
+    /// Errors are never cached, so a failed query is retried on the next call.
+    fn cached<P: Serialize>(
+        &self,
+        tool: &str,
+        params: &P,
+        run: impl FnOnce(&Analyzer) -> Result<CallToolResult, McpError>,
+    ) -> Result<CallToolResult, McpError> {
+        let analyzer = self.analyzer.lock().unwrap();
+        self.cached_with(&analyzer, tool, params, run)
+    }
+
+    /// `cached` for queries answered from the reference index
+    ///
+    /// Until the index is built, and while a rescan is under way, its contents
+    /// move on without the revision changing, so results aren't cached then.
+    fn index_cached<P: Serialize>(
+        &self,
+        tool: &str,
+        params: &P,
+        run: impl FnOnce(&Analyzer) -> Result<CallToolResult, McpError>,
+    ) -> Result<CallToolResult, McpError> {
+        let analyzer = self.analyzer.lock().unwrap();
+        if !analyzer.reference_index_settled() {
+            return run(&analyzer);
+        }
+        self.cached_with(&analyzer, tool, params, run)
+    }
+
+    /// `cached` against an analyzer the caller has already locked
+    fn cached_with<P: Serialize>(
+        &self,
+        analyzer: &Analyzer,
+        tool: &str,
+        params: &P,
+        run: impl FnOnce(&Analyzer) -> Result<CallToolResult, McpError>,
+    ) -> Result<CallToolResult, McpError> {
+        let key = serde_json::to_string(params).unwrap_or_default();
+        let stamp = (analyzer.revision(), analyzer.reference_generation());
+
+        if let Some(result) = self.cache.lock().unwrap().get(tool, &key, stamp) {
+            return Ok(result);
+        }
+
+        let result = run(analyzer)?;
+        // Partial results of a time-boxed scan aren't kept, so a retry can get further
+        let partial = result.structured_content.as_ref().and_then(|content| content.pointer("/coverage/complete"))
+            == Some(&serde_json::Value::Bool(false));
+        if !partial {
+            self.cache.lock().unwrap().insert(tool, key, stamp, result.clone());
+        }
+        Ok(result)
+    }
+
+    /// Run a symbol query against whichever backend can answer it
+    ///
+    /// Once the analyzer is ready this is `cached`. While it is still loading,
+    /// the fallback backend answers if there is one; its results are not
+    /// cached, so the analyzer's replace them as soon as it is ready.
+    fn symbol_query<P: Serialize>(
+        &self,
+        tool: &str,
+        params: &P,
+        run: impl FnOnce(&dyn SymbolProvider) -> Result<CallToolResult, McpError>,
+    ) -> Result<CallToolResult, McpError> {
+        if matches!(*self.init_state.lock().unwrap(), InitState::InProgress) {
+            if let Some(fallback) = &*self.fallback.lock().unwrap() {
+                return run(fallback.as_ref());
+            }
+        }
+
+        self.check_init_state()?;
+        self.cached(tool, params, |analyzer| run(analyzer))
+    }
+
+    /// Find all occurrences of a symbol by name across the indexed codebase
+    #[tool(description = "Find all occurrences of a Rust symbol (struct, enum, trait, function, method, impl) by name. \
+            Searches both project and library files. Can apply symbol filter: all, types, functions, or implementations.",
+        output_schema = output_schema::<outputs::FindSymbolOutput>())]
+    async fn find_symbol(&self, params: Parameters<FindSymbolParams>) -> Result<CallToolResult, McpError> {
+        let params = params.0;
+
+        // Parse search mode from string
+        let mode = match params.mode.as_deref() {
+            Some("exact") => SearchMode::Exact,
+            Some("prefix") => SearchMode::Prefix,
+            Some("fuzzy") | None => SearchMode::Fuzzy,
+            Some(other) => {
+                return Err(McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Invalid search mode: '{}'. Valid values: 'exact', 'fuzzy', 'prefix'", other).into(),
+                    data: None,
+                });
+            }
+        };
+
+        // Parse symbol filter from string
+        let filter = match params.filter.as_deref() {
+            Some("types") => SymbolFilter::Types,
+            Some("implementations") => SymbolFilter::Implementations,
+            Some("functions") => SymbolFilter::Functions,
+            Some("all") | None => SymbolFilter::All,
+            Some(other) => {
+                return Err(McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Invalid filter: '{}'. Valid values: 'types', 'implementations', 'functions', 'all'", other).into(),
+                    data: None,
+                });
+            }
+        };
+
+        // Build search options from parameters
+        let options = SearchOptions {
+            mode,
+            include_library: params.include_library.unwrap_or(false),
+            filter,
+            crate_version: params.crate_version.clone(),
+            signature: SignatureFilter {
+                params_contains: params.params_contains.clone(),
+                num_params: params.num_params,
+                is_async: params.is_async,
+                has_attribute: params.has_attribute.clone(),
+                missing_attribute: params.missing_attribute.clone(),
+            },
+            crates_include: params.crates_include.clone().unwrap_or_default(),
+            crates_exclude: params.crates_exclude.clone().unwrap_or_default(),
+            prefer_workspace: params.prefer_workspace.unwrap_or(true),
+        };
+
+        let fields = symbol_fields(params.fields.as_deref(), SYMBOL_FIELDS)?;
+
+        // Perform the search, serving repeated queries from the cache
+        self.symbol_query("find_symbol", &params, |analyzer| {
+            let results = analyzer.find_symbol(&params.name, &options)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Search failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            // An empty result is easily taken as proof of absence, so point at near misses
+            let suggestions = if results.is_empty() && !params.name.is_empty() {
+                analyzer.suggest_names(&params.name, &options)
+            } else {
+                Vec::new()
+            };
+            let output = outputs::FindSymbolOutput {
+                symbols: results.iter().map(|sym| symbol_output(sym, &fields)).collect(),
+                suggestions: suggestions.clone(),
+            };
+
+            let mut summary = format!(
+                "Found {} symbol(s) matching '{}' (mode: {:?}, library: {}, filter: {:?}, backend: {})",
+                results.len(),
+                params.name,
+                mode,
+                options.include_library,
+                options.filter,
+                analyzer.backend()
+            );
+            if !suggestions.is_empty() {
+                summary.push_str(&format!("; did you mean: {}?", suggestions.join(", ")));
+            }
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// List all symbols defined in a specific file
+    #[tool(description = "Enumerate all Rust symbols defined in a specific file",
+        output_schema = output_schema::<outputs::EnumerateFileOutput>())]
+    async fn enumerate_file(&self, params: Parameters<EnumerateFileParams>) -> Result<CallToolResult, McpError> {
+        let params = params.0;
+
+        let fields = symbol_fields(params.fields.as_deref(), FILE_SYMBOL_FIELDS)?;
+
+        // Enumerate symbols in the file
+        self.symbol_query("enumerate_file", &params, |analyzer| {
+            let results = analyzer.enumerate_file(&params.file_path)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to enumerate file: {}", e).into(),
+                    data: None,
+                })?;
+
+            // Keep only the requested fields
+            let output = outputs::EnumerateFileOutput {
+                symbols: results.iter().map(|sym| symbol_output(sym, &fields)).collect(),
+            };
+
+            let summary = format!(
+                "Found {} symbol(s) in '{}' (backend: {})",
+                results.len(),
+                params.file_path,
+                analyzer.backend()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// List all symbols defined in several files at once
+    #[tool(description = "Enumerate the Rust symbols of several files in one call, e.g. every file of a diff. \
+            Paths may be relative to the project root. A file that fails reports its own error without failing the others.",
+        output_schema = output_schema::<outputs::EnumerateFilesOutput>())]
+    async fn enumerate_files(&self, params: Parameters<EnumerateFilesParams>) -> Result<CallToolResult, McpError> {
+        let params = params.0;
+
+        let fields = symbol_fields(params.fields.as_deref(), FILE_SYMBOL_FIELDS)?;
+
+        self.symbol_query("enumerate_files", &params, |analyzer| {
+            let results = analyzer.enumerate_files(&params.file_paths);
+
+            let output = outputs::EnumerateFilesOutput {
+                files: results.iter().map(|(file_path, result)| outputs::FileSymbolsOutput {
+                    file_path: file_path.clone(),
+                    symbols: result.as_ref().ok().map(|symbols| symbols.iter().map(|sym| symbol_output(sym, &fields)).collect()),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                }).collect(),
+            };
+
+            let failed = results.iter().filter(|(_, result)| result.is_err()).count();
+            let summary = format!(
+                "Enumerated {} file(s) with {} symbol(s) in total; {} file(s) failed (backend: {})",
+                results.len() - failed,
+                results.iter().filter_map(|(_, result)| result.as_ref().ok()).map(Vec::len).sum::<usize>(),
+                failed,
+                analyzer.backend()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// List all symbols of the Rust files below a directory
+    #[tool(description = "Enumerate the Rust symbols of every .rs file below a directory, recursively, in one call \
+            instead of one enumerate_file call per file. The directory may be relative to the project root; target/ \
+            and .git/ are skipped. At most `max_files` files (default 100) are enumerated, by path; `truncated` tells \
+            whether there were more.",
+        output_schema = output_schema::<outputs::EnumerateDirectoryOutput>())]
+    async fn enumerate_directory(&self, params: Parameters<EnumerateDirectoryParams>) -> Result<CallToolResult, McpError> {
+        let params = params.0;
+
+        let fields = symbol_fields(params.fields.as_deref(), FILE_SYMBOL_FIELDS)?;
+
+        self.symbol_query("enumerate_directory", &params, |analyzer| {
+            let max_files = params.max_files.unwrap_or(ENUMERATE_DIRECTORY_MAX_FILES);
+            let listing = analyzer.enumerate_directory(&params.directory, max_files)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to enumerate directory: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::EnumerateDirectoryOutput {
+                files: listing.files.iter().map(|(file_path, result)| outputs::FileSymbolsOutput {
+                    file_path: file_path.clone(),
+                    symbols: result.as_ref().ok().map(|symbols| symbols.iter().map(|sym| symbol_output(sym, &fields)).collect()),
+                    error: result.as_ref().err().map(|e| e.to_string()),
+                }).collect(),
+                total_files: listing.total_files,
+                truncated: listing.files.len() < listing.total_files,
+            };
+
+            let summary = format!(
+                "Enumerated {} of {} file(s) below '{}' with {} symbol(s) in total (backend: {})",
+                listing.files.len(),
+                listing.total_files,
+                params.directory,
+                listing.files.iter().filter_map(|(_, result)| result.as_ref().ok()).map(Vec::len).sum::<usize>(),
+                analyzer.backend()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// List the symbols of the files a team owns
+    #[tool(description = "List the workspace files a team or CODEOWNERS handle owns, with their symbols. Ownership \
+            comes from the project's CODEOWNERS (last matching rule wins); an optional .cratographer/teams.json maps \
+            team names to handles. Symbol results of other tools carry an `owners` field from the same rules.",
+        output_schema = output_schema::<outputs::OwnedSymbolsOutput>())]
+    async fn owned_symbols(&self, params: Parameters<OwnedSymbolsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let fields = symbol_fields(params.fields.as_deref(), FILE_SYMBOL_FIELDS)?;
+
+        self.cached("owned_symbols", &params, |analyzer| {
+            let files = analyzer.owned_symbols(&params.owner)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list owned symbols: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::OwnedSymbolsOutput {
+                owner: params.owner.clone(),
+                files: files.iter().map(|(file_path, symbols)| outputs::FileSymbolsOutput {
+                    file_path: file_path.clone(),
+                    symbols: Some(symbols.iter().map(|sym| symbol_output(sym, &fields)).collect()),
+                    error: None,
+                }).collect(),
+            };
+
+            let summary = format!(
+                "'{}' owns {} file(s) with {} symbol(s) in total",
+                params.owner,
+                files.len(),
+                files.iter().map(|(_, symbols)| symbols.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// List closures in a function with their captures and inferred Fn trait
+    #[tool(description = "List the closures defined inside a function, with each closure's captured variables, \
+            capture mode (by shared ref, mutable ref, or move), and inferred kind (Fn, FnMut, or FnOnce).",
+        output_schema = output_schema::<outputs::AnalyzeClosuresOutput>())]
+    async fn analyze_closures(&self, params: Parameters<AnalyzeClosuresParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("analyze_closures", &params, |analyzer| {
+            let results = analyzer.closures_in_function(&params.function, params.file_path.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Closure analysis failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::AnalyzeClosuresOutput {
+                closures: results.iter().map(|closure| outputs::ClosureOutput {
+                    function: closure.function.clone(),
+                    file_path: closure.file_path.clone(),
+                    start_line: closure.start_line,
+                    end_line: closure.end_line,
+                    params: closure.params.clone(),
+                    is_move: closure.is_move,
+                    fn_trait: closure.fn_trait.clone(),
+                    captures: closure.captures.iter().map(|capture| outputs::CaptureOutput {
+                        place: capture.place.clone(),
+                        kind: capture.kind.clone(),
+                    }).collect(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} closure(s) in function '{}'",
+                results.len(),
+                params.function
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Find workspace functions that accept closures of a given shape
+    #[tool(description = "Find workspace functions with a parameter that accepts a closure (impl Fn, dyn Fn, or a generic \
+            bounded by Fn/FnMut/FnOnce). Can filter by closure trait and argument count.",
+        output_schema = output_schema::<outputs::FindClosureApisOutput>())]
+    async fn find_closure_apis(&self, params: Parameters<FindClosureApisParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let fn_trait = match params.fn_trait.as_deref() {
+            Some(t @ ("Fn" | "FnMut" | "FnOnce")) => Some(t.to_string()),
+            None => None,
+            Some(other) => {
+                return Err(McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Invalid fn_trait: '{}'. Valid values: 'Fn', 'FnMut', 'FnOnce'", other).into(),
+                    data: None,
+                });
+            }
+        };
+
+        let query = ClosureShapeQuery {
+            fn_trait,
+            arity: params.arity,
+        };
+
+        self.cached("find_closure_apis", &params, |analyzer| {
+            let results = analyzer.find_closure_apis(&query)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Search failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::FindClosureApisOutput {
+                apis: results.iter().map(|api| outputs::ClosureApiOutput {
+                    function: api.function.clone(),
+                    file_path: api.file_path.clone(),
+                    start_line: api.start_line,
+                    end_line: api.end_line,
+                    parameter: api.parameter.clone(),
+                    shape: api.shape.clone(),
+                    fn_trait: api.fn_trait.clone(),
+                    arity: api.arity,
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} closure-accepting parameter(s) (fn_trait: {}, arity: {})",
+                results.len(),
+                query.fn_trait.as_deref().unwrap_or("any"),
+                query.arity.map(|a| a.to_string()).unwrap_or_else(|| "any".to_string())
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Show a function signature with all elided lifetimes made explicit
+    #[tool(description = "Show a function signature with every elided lifetime made explicit (the desugared form), \
+            following Rust's lifetime elision rules. Reports when an output lifetime cannot be elided.",
+        output_schema = output_schema::<outputs::ExpandLifetimesOutput>())]
+    async fn expand_lifetimes(&self, params: Parameters<ExpandLifetimesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("expand_lifetimes", &params, |analyzer| {
+            let results = analyzer.expand_lifetimes(&params.function, params.file_path.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Lifetime expansion failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::ExpandLifetimesOutput {
+                expansions: results.iter().map(|expansion| outputs::LifetimeExpansionOutput {
+                    function: expansion.function.clone(),
+                    file_path: expansion.file_path.clone(),
+                    start_line: expansion.start_line,
+                    end_line: expansion.end_line,
+                    original: expansion.original.clone(),
+                    expanded: expansion.expanded.clone(),
+                    introduced: expansion.introduced.clone(),
+                    notes: expansion.notes.clone(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Expanded lifetimes for {} signature(s) of '{}'",
+                results.len(),
+                params.function
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Report bounds and concrete types of `impl Trait` return types
+    #[tool(description = "For functions returning `impl Trait`, report the opaque type's declared bounds, \
+            supertraits they imply, and the concrete type(s) the body actually returns where inference can determine them.",
+        output_schema = output_schema::<outputs::InspectImplTraitOutput>())]
+    async fn inspect_impl_trait(&self, params: Parameters<InspectImplTraitParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("inspect_impl_trait", &params, |analyzer| {
+            let results = analyzer.impl_trait_returns(params.function.as_deref(), params.file_path.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("impl Trait inspection failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::InspectImplTraitOutput {
+                functions: results.iter().map(|ret| outputs::ImplTraitOutput {
+                    function: ret.function.clone(),
+                    file_path: ret.file_path.clone(),
+                    start_line: ret.start_line,
+                    end_line: ret.end_line,
+                    return_type: ret.return_type.clone(),
+                    bounds: ret.bounds.clone(),
+                    implied_bounds: ret.implied_bounds.clone(),
+                    concrete_types: ret.concrete_types.clone(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} function(s) returning impl Trait",
+                results.len()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Audit which items only exist under some feature combinations
+    #[tool(description = "Evaluate #[cfg(feature = ...)] attributes across feature combinations and report workspace \
+            symbols and modules that only exist in some of them. A lightweight feature-matrix audit without cargo builds.",
+        output_schema = output_schema::<outputs::FeatureMatrixOutput>())]
+    async fn feature_matrix(&self, params: Parameters<FeatureMatrixParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("feature_matrix", &params, |analyzer| {
+            let matrix = analyzer.feature_matrix(params.combinations.clone().unwrap_or_default())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Feature matrix audit failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let labels: Vec<String> = matrix.combinations.iter().map(|features| {
+                if features.is_empty() {
+                    "(no features)".to_string()
+                } else {
+                    features.join(",")
+                }
+            }).collect();
+
+            let items: Vec<_> = matrix.items.iter().map(|item| {
+                let presence = labels.iter().zip(&item.presence).map(|(label, present)| {
+                    let value = match present {
+                        Some(true) => "present",
+                        Some(false) => "absent",
+                        None => "unknown",
+                    };
+                    (label.clone(), value.to_string())
+                }).collect();
+
+                outputs::FeatureGatedItemOutput {
+                    name: item.name.clone(),
+                    kind: item.kind.clone(),
+                    file_path: item.file_path.clone(),
+                    start_line: item.start_line,
+                    end_line: item.end_line,
+                    cfg: item.cfg.clone(),
+                    presence,
+                }
+            }).collect();
+
+            let summary = format!(
+                "Found {} item(s) that differ across {} feature combination(s)",
+                matrix.items.len(),
+                matrix.combinations.len()
+            );
+
+            Ok(tool_result(summary, &outputs::FeatureMatrixOutput { combinations: labels, items }))
+        })
+    }
+
+    /// Look up references to a definition in the persisted reference index
+    #[tool(description = "Look up all references to a workspace definition from the precomputed cross-reference index. \
+            Accepts a bare name (matches every definition with that name) or a qualified path. Much faster than a full search.",
+        output_schema = output_schema::<outputs::IndexedReferencesOutput>())]
+    async fn indexed_references(&self, params: Parameters<IndexedReferencesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.index_cached("indexed_references", &params, |analyzer| {
+            let results = analyzer.indexed_references(&params.name)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Reference lookup failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::IndexedReferencesOutput {
+                definitions: results.iter().map(|def| outputs::IndexedDefinitionOutput {
+                    definition: def.definition.clone(),
+                    references: def.references.iter().map(|site| outputs::ReferenceSiteOutput {
+                        file_path: site.file_path.clone(),
+                        line: site.line,
+                        column: site.column,
+                        is_import: site.is_import,
+                    }).collect(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} definition(s) named '{}' with {} reference(s) in total",
+                results.len(),
+                params.name,
+                results.iter().map(|def| def.references.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+    /// Find the public signatures a type appears in
+    #[tool(description = "Report every public function, field, enum variant, trait, type alias, constant and impl header \
+            whose signature mentions a type. Shows the type's exposure in the crate's API before changing or removing it; \
+            function bodies and private items are ignored.",
+        output_schema = output_schema::<outputs::PublicApiUsesOutput>())]
+    async fn public_api_uses(&self, params: Parameters<PublicApiUsesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("public_api_uses", &params, |analyzer| {
+            let uses = analyzer.public_signature_uses(&params.type_name)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find public signature uses: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::PublicApiUsesOutput {
+                uses: uses.iter().map(|u| outputs::SignatureUseOutput {
+                    item: u.item.clone(),
+                    kind: u.kind.to_string(),
+                    file_path: u.file_path.clone(),
+                    start_line: u.start_line,
+                    end_line: u.end_line,
+                    signature: u.signature.clone(),
+                    reachable: u.reachable,
+                }).collect(),
+            };
+
+            let summary = format!(
+                "'{}' appears in {} public signature(s), {} of them reachable from outside the crate",
+                params.type_name,
+                uses.len(),
+                uses.iter().filter(|u| u.reachable).count()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Check whether a trait impl would pass the orphan rule and overlap checks
+    #[tool(description = "Pre-check a proposed trait impl (e.g. 'impl Display for Config') against Rust's coherence rules: \
+            whether the orphan rule allows it in this crate, and which existing impls of the trait it would overlap with. \
+            Blanket impls are reported as possible conflicts since their bounds are not evaluated.",
+        output_schema = output_schema::<outputs::CheckImplCoherenceOutput>())]
+    async fn check_impl_coherence(&self, params: Parameters<CheckImplCoherenceParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("check_impl_coherence", &params, |analyzer| {
+            let check = analyzer.check_coherence(&params.impl_header, params.file_path.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Coherence check failed: {}", e).into(),
+                    data: None,
+                })?;
+
+            let conflicts: Vec<_> = check.conflicts.iter().map(|c| outputs::ConflictingImplOutput {
+                header: c.header.clone(),
+                file_path: c.file_path.clone(),
+                start_line: c.start_line,
+                end_line: c.end_line,
+                certain: c.certain,
+            }).collect();
+
+            let certain = check.conflicts.iter().filter(|c| c.certain).count();
+            let verdict = match (check.orphan_allowed, certain) {
+                (Some(false), _) => "rejected by the orphan rule",
+                (_, n) if n > 0 => "conflicts with an existing impl",
+                (None, _) => "could not be fully checked",
+                (Some(true), _) if !check.conflicts.is_empty() => "allowed unless a blanket impl applies",
+                (Some(true), _) => "allowed",
+            };
+            let summary = format!(
+                "'{}' is {}: {}; {} overlapping impl(s), {} certain",
+                params.impl_header,
+                verdict,
+                check.orphan_reason,
+                check.conflicts.len(),
+                certain
+            );
+
+            Ok(tool_result(summary, &outputs::CheckImplCoherenceOutput {
+                trait_path: check.trait_path,
+                self_type: check.self_type,
+                orphan_allowed: check.orphan_allowed,
+                orphan_reason: check.orphan_reason,
+                conflicts,
+            }))
+        })
+    }
+
+    /// Find items defined several times under different cfgs
+    #[tool(description = "List items defined more than once in the same module or impl block under different #[cfg]s. \
+            Each set is classified as 'exclusive' (the cfgs never overlap, the legitimate platform/feature pattern) or \
+            'conflict' (some configuration enables two definitions, with an example configuration).",
+        output_schema = output_schema::<outputs::CfgDuplicatesOutput>())]
+    async fn cfg_duplicates(&self, params: Parameters<CfgDuplicatesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let only_conflicts = params.only_conflicts.unwrap_or(false);
+
+        self.cached("cfg_duplicates", &params, |analyzer| {
+            let duplicates = analyzer.cfg_duplicates()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find duplicate definitions: {}", e).into(),
+                    data: None,
+                })?;
+
+            let conflicts = duplicates.iter().filter(|d| d.status == DuplicateStatus::Conflict).count();
+            let output = outputs::CfgDuplicatesOutput {
+                duplicates: duplicates.iter()
+                    .filter(|d| !only_conflicts || d.status == DuplicateStatus::Conflict)
+                    .map(|d| {
+                        let status = match d.status {
+                            DuplicateStatus::Exclusive => "exclusive",
+                            DuplicateStatus::Conflict => "conflict",
+                            DuplicateStatus::Unknown => "unknown",
+                        };
+                        outputs::DuplicateOutput {
+                            name: d.name.clone(),
+                            namespace: d.namespace.to_string(),
+                            scope: d.scope.clone(),
+                            status: status.to_string(),
+                            witness: d.witness.clone(),
+                            definitions: d.definitions.iter().map(|site| outputs::DuplicateSiteOutput {
+                                file_path: site.file_path.clone(),
+                                start_line: site.start_line,
+                                end_line: site.end_line,
+                                cfg: site.cfg.clone(),
+                            }).collect(),
+                        }
+                    })
+                    .collect(),
+            };
+
+            let summary = format!(
+                "Found {} item(s) defined more than once, {} of them conflicting in some configuration",
+                duplicates.len(),
+                conflicts
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Find names whose meaning glob imports make unclear
+    #[tool(description = "Flag names used in a scope with glob imports whose resolution a reader could get wrong: \
+            'shadowed_glob' when an item declared or explicitly imported in the scope silently wins over a \
+            glob-imported item of the same name, 'ambiguous_glob' when several globs provide different items of the \
+            name. Lists the competing sources. Checks one file or the whole workspace, optionally within budget_ms.",
+        output_schema = output_schema::<outputs::GlobConflictsOutput>())]
+    async fn glob_conflicts(&self, params: Parameters<GlobConflictsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("glob_conflicts", &params, |analyzer| {
+            let (conflicts, coverage) = match &params.file_path {
+                Some(file_path) => (analyzer.glob_conflicts(file_path), None),
+                None => match analyzer.workspace_glob_conflicts(Budget::from_millis(params.budget_ms)) {
+                    Ok(report) => (Ok(report.conflicts), Some(report.coverage)),
+                    Err(e) => (Err(e), None),
+                },
+            };
+            let conflicts = conflicts.map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to check glob imports: {}", e).into(),
+                data: None,
+            })?;
+
+            let output = outputs::GlobConflictsOutput {
+                conflicts: conflicts.iter().map(|c| outputs::GlobConflictOutput {
+                    kind: c.kind.to_string(),
+                    name: c.name.clone(),
+                    file_path: c.file_path.clone(),
+                    line: c.line,
+                    resolved: c.resolved.clone(),
+                    sources: c.sources.iter().map(|source| outputs::NameSourceOutput {
+                        via: source.via.clone(),
+                        item: source.item.clone(),
+                    }).collect(),
+                }).collect(),
+                coverage: coverage.map(coverage_output),
+            };
+
+            let counts: Vec<_> = GLOB_CONFLICT_KINDS
+                .iter()
+                .map(|kind| (kind, conflicts.iter().filter(|c| c.kind == *kind).count()))
+                .filter(|(_, count)| *count > 0)
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            let mut summary = if conflicts.is_empty() {
+                "No glob import conflicts found".to_string()
+            } else {
+                format!("Found {} glob import conflict(s): {}", conflicts.len(), counts.join(", "))
+            };
+            if let Some(coverage) = coverage {
+                summary.push_str(&coverage_note(coverage));
+            }
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Rank generic functions by how many copies monomorphization makes of them
+    #[tool(description = "List generic workspace functions called with many distinct type arguments, estimated from \
+            call sites (turbofish, or the inferred types of arguments for generic parameters and of generic results), \
+            ranked by body lines times instantiations. Guides code-size and compile-time work: candidates for taking \
+            `dyn Trait` or for moving the body into a non-generic inner function. Estimates are lower bounds.",
+        output_schema = output_schema::<outputs::MonomorphizationCandidatesOutput>())]
+    async fn monomorphization_candidates(
+        &self,
+        params: Parameters<MonomorphizationCandidatesParams>,
+    ) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("monomorphization_candidates", &params, |analyzer| {
+            let report = analyzer
+                .monomorphization_candidates(params.min_instantiations.unwrap_or(2), Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to estimate instantiations: {}", e).into(),
+                    data: None,
+                })?;
+
+            let mut summary = format!("Found {} generic function(s) with several instantiations", report.functions.len());
+            for function in report.functions.iter().take(10) {
+                summary.push_str(&format!(
+                    "\n  {}: {} instantiation(s) from {} call(s), {} body line(s)",
+                    function.path, function.instantiations, function.call_sites, function.body_lines
+                ));
+            }
+            summary.push_str(&coverage_note(report.coverage));
+
+            Ok(tool_result(summary, &outputs::MonomorphizationCandidatesOutput {
+                functions: report.functions.into_iter().map(|f| outputs::GenericInstantiationsOutput {
+                    path: f.path,
+                    file_path: f.file_path,
+                    start_line: f.start_line,
+                    end_line: f.end_line,
+                    type_params: f.type_params,
+                    body_lines: f.body_lines,
+                    call_sites: f.call_sites,
+                    instantiations: f.instantiations,
+                    estimated_lines: f.estimated_lines,
+                    examples: f.examples.into_iter().map(|(type_args, calls)| outputs::InstantiationOutput { type_args, calls }).collect(),
+                }).collect(),
+                coverage: coverage_output(report.coverage),
+            }))
+        })
+    }
+
+    /// Find impls whose methods only forward to a field of self
+    #[tool(description = "Find trait and inherent impls whose methods merely forward to a field of `self`, as newtype \
+            wrappers do (e.g. `fn fmt(&self, f) { self.0.fmt(f) }` or `fn len(&self) { self.inner.len() }`), with the \
+            forwarding calls and the field and type forwarded to. Helps decide between a Deref impl, a derive or a \
+            delegation macro and hand-written forwarding.",
+        output_schema = output_schema::<outputs::DelegatingImplsOutput>())]
+    async fn delegating_impls(&self, params: Parameters<DelegatingImplsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("delegating_impls", &params, |analyzer| {
+            let report = analyzer
+                .delegating_impls(params.include_partial, Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find delegating impls: {}", e).into(),
+                    data: None,
+                })?;
+
+            let fully = report.impls.iter().filter(|i| i.fully_delegating()).count();
+            let mut summary = format!("Found {} delegating impl(s), {} forwarding every method", report.impls.len(), fully);
+            for impl_ in &report.impls {
+                summary.push_str(&format!(
+                    "\n  {} ({}:{}): {}/{} method(s) forward{}",
+                    impl_.header,
+                    impl_.file_path,
+                    impl_.start_line,
+                    impl_.forwarding.len(),
+                    impl_.methods,
+                    impl_.target_field.as_ref().map(|field| format!(" to self.{}", field)).unwrap_or_default()
+                ));
+            }
+            summary.push_str(&coverage_note(report.coverage));
+
+            Ok(tool_result(summary, &outputs::DelegatingImplsOutput {
+                impls: report.impls.into_iter().map(|i| outputs::DelegatingImplOutput {
+                    fully_delegating: i.fully_delegating(),
+                    header: i.header,
+                    trait_path: i.trait_path,
+                    self_type: i.self_type,
+                    file_path: i.file_path,
+                    start_line: i.start_line,
+                    end_line: i.end_line,
+                    methods: i.methods,
+                    forwarding: i.forwarding.into_iter().map(|m| outputs::ForwardingMethodOutput {
+                        name: m.name,
+                        field: m.field,
+                        call: m.call,
+                    }).collect(),
+                    target_field: i.target_field,
+                    target_type: i.target_type,
+                }).collect(),
+                coverage: coverage_output(report.coverage),
+            }))
+        })
+    }
+
+    /// Inventory how each workspace crate handles errors
+    #[tool(description = "Summarize how errors are handled in each workspace crate: counts and locations of `.unwrap()`, \
+            `.expect(..)`, the `?` operator, anyhow's `anyhow!`/`bail!`/`ensure!`, the crate's own error types \
+            (implementing std::error::Error) and `Box<dyn Error>` in public function signatures. Test code is left \
+            out unless `include_tests`. The current-state map for standardizing on an error strategy.",
+        output_schema = output_schema::<outputs::ErrorHandlingInventoryOutput>())]
+    async fn error_handling_inventory(&self, params: Parameters<ErrorHandlingInventoryParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if let Some(unknown) = params.kinds.iter().flatten().find(|k| !ERROR_HANDLING_KINDS.contains(&k.as_str())) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown error-handling kind '{}', expected one of: {}", unknown, ERROR_HANDLING_KINDS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        self.cached("error_handling_inventory", &params, |analyzer| {
+            let inventory = analyzer
+                .error_handling_inventory(params.include_tests, Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to inventory error handling: {}", e).into(),
+                    data: None,
+                })?;
+
+            let mut summary = inventory
+                .crates
+                .iter()
+                .map(|c| {
+                    let counts: Vec<_> = c.counts.iter().map(|(kind, count)| format!("{} {}", count, kind)).collect();
+                    format!("{}: {}", c.crate_name, counts.join(", "))
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            summary.push_str(&coverage_note(inventory.coverage));
+
+            Ok(tool_result(summary, &outputs::ErrorHandlingInventoryOutput {
+                crates: inventory.crates.into_iter().map(|c| outputs::CrateErrorHandlingOutput {
+                    crate_name: c.crate_name,
+                    counts: c.counts.into_iter().map(|(kind, count)| (kind.to_string(), count)).collect(),
+                    sites: c.sites
+                        .into_iter()
+                        .filter(|s| params.kinds.as_ref().is_none_or(|kinds| kinds.iter().any(|k| k == s.kind)))
+                        .map(|s| outputs::ErrorSiteOutput {
+                            kind: s.kind.to_string(),
+                            file_path: s.file_path,
+                            line: s.line,
+                            item: s.item,
+                            text: s.text,
+                        })
+                        .collect(),
+                }).collect(),
+                coverage: coverage_output(inventory.coverage),
+            }))
+        })
+    }
+
+    /// Find code matching a structural search pattern
+    #[tool(description = "Structural search (rust-analyzer SSR): find code matching a pattern by syntax and name \
+            resolution rather than text, e.g. `$a.unwrap()`, `Option<$t>` or `std::mem::replace($a, $b)`. \
+            Placeholders `$name` match any expression, type or pattern; paths match what they resolve to, however \
+            they are imported. Returns each match with its range and text, in one file or the whole workspace.",
+        output_schema = output_schema::<outputs::StructuralSearchOutput>())]
+    async fn structural_search(&self, params: Parameters<StructuralSearchParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("structural_search", &params, |analyzer| {
+            let found = analyzer
+                .structural_search(&params.pattern, params.file_path.as_deref(), Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to search: {}", e).into(),
+                    data: None,
+                })?;
+
+            // Matches come grouped by file
+            let mut files: Vec<_> = found.matches.iter().map(|m| &m.file_path).collect();
+            files.dedup();
+            let mut summary = format!("Found {} match(es) of `{}` in {} file(s)", found.matches.len(), params.pattern, files.len());
+            if params.file_path.is_none() {
+                summary.push_str(&coverage_note(found.coverage));
+            }
+
+            Ok(tool_result(summary, &outputs::StructuralSearchOutput {
+                matches: found.matches.into_iter().map(|m| outputs::StructuralMatchOutput {
+                    file_path: m.file_path,
+                    start_line: m.start_line,
+                    start_column: m.start_column,
+                    end_line: m.end_line,
+                    end_column: m.end_column,
+                    text: m.text,
+                }).collect(),
+                coverage: params.file_path.is_none().then(|| coverage_output(found.coverage)),
+            }))
+        })
+    }
+
+    /// Preview a structural search and replace
+    #[tool(description = "Preview a structural search and replace (rust-analyzer SSR): given a rule such as \
+            `$a.unwrap() ==>> $a.expect(\"checked\")`, returns every edit it would make as file, range and new \
+            text, in one file or the whole workspace. Paths in the replacement are shortened to what is in scope. \
+            Nothing is written to disk.",
+        output_schema = output_schema::<outputs::StructuralReplaceOutput>())]
+    async fn structural_replace(&self, params: Parameters<StructuralReplaceParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("structural_replace", &params, |analyzer| {
+            let replacement = analyzer
+                .structural_replace(&params.rule, params.file_path.as_deref(), Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to plan replacement: {}", e).into(),
+                    data: None,
+                })?;
+
+            let mut files: Vec<_> = replacement.edits.iter().map(|e| &e.file_path).collect();
+            files.dedup();
+            let mut summary = format!(
+                "Replacing {} match(es) takes {} edit(s) in {} file(s)",
+                replacement.matches,
+                replacement.edits.len(),
+                files.len()
+            );
+            if params.file_path.is_none() {
+                summary.push_str(&coverage_note(replacement.coverage));
+            }
+
+            Ok(tool_result(summary, &outputs::StructuralReplaceOutput {
+                matches: replacement.matches,
+                edits: replacement.edits.into_iter().map(|edit| outputs::TextEditOutput {
+                    file_path: edit.file_path,
+                    start_line: edit.start_line,
+                    start_column: edit.start_column,
+                    end_line: edit.end_line,
+                    end_column: edit.end_column,
+                    new_text: edit.new_text,
+                }).collect(),
+                coverage: params.file_path.is_none().then(|| coverage_output(replacement.coverage)),
+            }))
+        })
+    }
+
+    /// List the logging and tracing call sites of the workspace
+    #[tool(description = "List the `log` and `tracing` call sites of the workspace: `info!`, `warn!`, `log!`, `event!`, \
+            span macros such as `info_span!` and `#[instrument]`ed functions, each with its level, explicit `target`, \
+            message template and enclosing function. Macros are matched by what they resolve to, not by name. With \
+            `silent_functions`, also lists the functions without any logging. Test code is left out.",
+        output_schema = output_schema::<outputs::LoggingSitesOutput>())]
+    async fn logging_sites(&self, params: Parameters<LoggingSitesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let min_level = match params.min_level.as_deref() {
+            Some(level) => match LOG_LEVELS.iter().position(|l| *l == level) {
+                Some(index) => Some(index),
+                None => {
+                    return Err(McpError {
+                        code: ErrorCode(-1),
+                        message: format!("Unknown log level '{}', expected one of: {}", level, LOG_LEVELS.join(", ")).into(),
+                        data: None,
+                    });
+                }
+            },
+            None => None,
+        };
+
+        self.cached("logging_sites", &params, |analyzer| {
+            let report = analyzer
+                .logging_sites(params.silent_functions, Budget::from_millis(params.budget_ms))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find logging sites: {}", e).into(),
+                    data: None,
+                })?;
+
+            let sites: Vec<_> = report
+                .sites
+                .into_iter()
+                .filter(|site| {
+                    min_level.is_none_or(|min| {
+                        site.level.and_then(|level| LOG_LEVELS.iter().position(|l| *l == level)).is_some_and(|index| index >= min)
+                    })
+                })
+                .collect();
+
+            let mut summary = format!("{} logging site(s)", sites.len());
+            let per_level: Vec<_> = LOG_LEVELS
+                .iter()
+                .map(|level| (level, sites.iter().filter(|site| site.level == Some(*level)).count()))
+                .filter(|(_, count)| *count > 0)
+                .map(|(level, count)| format!("{} {}", count, level))
+                .collect();
+            if !per_level.is_empty() {
+                summary.push_str(&format!(" ({})", per_level.join(", ")));
+            }
+            if params.silent_functions {
+                summary.push_str(&format!("\n{} function(s) without logging", report.silent_functions.len()));
+            }
+            summary.push_str(&coverage_note(report.coverage));
+
+            Ok(tool_result(summary, &outputs::LoggingSitesOutput {
+                sites: sites.into_iter().map(|site| outputs::LogSiteOutput {
+                    kind: site.kind.to_string(),
+                    macro_name: site.macro_name,
+                    level: site.level.map(str::to_string),
+                    target: site.target,
+                    message: site.message,
+                    file_path: site.file_path,
+                    line: site.line,
+                    function: site.function,
+                }).collect(),
+                silent_functions: params.silent_functions.then(|| {
+                    report.silent_functions.into_iter().map(|f| outputs::SilentFunctionOutput {
+                        name: f.name,
+                        file_path: f.file_path,
+                        start_line: f.start_line,
+                        end_line: f.end_line,
+                    }).collect()
+                }),
+                coverage: coverage_output(report.coverage),
+            }))
+        })
+    }
+
+    /// Summarize what a file imports and which of its symbols are used elsewhere
+    #[tool(description = "Summarize a file's dependencies: the crates and modules it imports from via use statements \
+            (resolved), and which of its definitions are referenced from other files. \
+            A quick check of how entangled a file is before moving or splitting it.",
+        output_schema = output_schema::<outputs::FileDependenciesOutput>())]
+    async fn file_dependencies(&self, params: Parameters<FileDependenciesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.index_cached("file_dependencies", &params, |analyzer| {
+            let deps = analyzer.file_dependencies(&params.file_path)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to summarize file dependencies: {}", e).into(),
+                    data: None,
+                })?;
+
+            let imports: Vec<_> = deps.imports.iter().map(|import| outputs::ImportOutput {
+                path: import.path.clone(),
+                resolved: import.resolved.clone(),
+                origin: import.origin.clone(),
+                external: import.external,
+            }).collect();
+            let exported: Vec<_> = deps.exported.iter().map(|sym| outputs::ExportedSymbolOutput {
+                name: sym.name.clone(),
+                definition: sym.definition.clone(),
+                reference_count: sym.reference_count,
+                referencing_files: sym.referencing_files.clone(),
+            }).collect();
+
+            let used_elsewhere = deps.exported.iter().filter(|sym| sym.reference_count > 0).count();
+            let summary = format!(
+                "'{}' imports from {} crate(s) and {} internal module(s); {} of its {} definition(s) are used from other files",
+                deps.file_path,
+                deps.external_crates.len(),
+                deps.internal_modules.len(),
+                used_elsewhere,
+                deps.exported.len()
+            );
+
+            Ok(tool_result(summary, &outputs::FileDependenciesOutput {
+                external_crates: deps.external_crates,
+                internal_modules: deps.internal_modules,
+                imports,
+                exported,
+            }))
+        })
+    }
+
+    #[tool(description = "List a file's use declarations with, for each imported name, what it resolves to, whether \
+            anything in its scope still uses it (trait imports count as used when a method of the trait is called), \
+            and whether the declaration re-exports it. The data for cleaning up imports programmatically.",
+        output_schema = output_schema::<outputs::UseDeclarationsOutput>())]
+    async fn use_declarations(&self, params: Parameters<UseDeclarationsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("use_declarations", &params, |analyzer| {
+            let declarations = analyzer.use_declarations(&params.file_path)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list use declarations: {}", e).into(),
+                    data: None,
+                })?;
+
+            let unused = declarations.iter().flat_map(|d| &d.imports).filter(|import| !import.used).count();
+            let output = outputs::UseDeclarationsOutput {
+                declarations: declarations
+                    .iter()
+                    .filter(|d| !params.only_unused || d.imports.iter().any(|import| !import.used))
+                    .map(|d| outputs::UseDeclarationOutput {
+                        text: d.text.clone(),
+                        start_line: d.start_line,
+                        end_line: d.end_line,
+                        visibility: d.visibility.clone(),
+                        re_exported: d.re_exported,
+                        imports: d.imports.iter().map(|import| outputs::UseImportOutput {
+                            path: import.path.clone(),
+                            alias: import.alias.clone(),
+                            resolved: import.resolved.clone(),
+                            external: import.external,
+                            used: import.used,
+                        }).collect(),
+                    })
+                    .collect(),
+            };
+
+            let summary = format!(
+                "'{}' has {} use declaration(s) importing {} name(s), {} of them unused",
+                params.file_path,
+                declarations.len(),
+                declarations.iter().map(|d| d.imports.len()).sum::<usize>(),
+                unused
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Plan moving an item (fn, struct, enum, trait, mod, ...) into another module of the same crate. \
+            Returns the text edits: removal from the source file, insertion into the target file, \
+            and all use-statement and path adjustments across the workspace. Nothing is written to disk.",
+        output_schema = output_schema::<outputs::MoveItemOutput>())]
+    async fn move_item(&self, params: Parameters<MoveItemParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("move_item", &params, |analyzer| {
+            let plan = analyzer.plan_move_item(&params.name, params.file_path.as_deref(), &params.target_file)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to plan move: {}", e).into(),
+                    data: None,
+                })?;
+
+            let edits: Vec<_> = plan.edits.iter().map(|edit| outputs::TextEditOutput {
+                file_path: edit.file_path.clone(),
+                start_line: edit.start_line,
+                start_column: edit.start_column,
+                end_line: edit.end_line,
+                end_column: edit.end_column,
+                new_text: edit.new_text.clone(),
+            }).collect();
+
+            let mut files: Vec<&str> = plan.edits.iter().map(|e| e.file_path.as_str()).collect();
+            files.dedup();
+            let summary = format!(
+                "Moving '{}' from {} to {} takes {} edit(s) in {} file(s) with {} warning(s)",
+                plan.item,
+                plan.from_module,
+                plan.to_module,
+                plan.edits.len(),
+                files.len(),
+                plan.warnings.len()
+            );
+
+            Ok(tool_result(summary, &outputs::MoveItemOutput {
+                from_module: plan.from_module,
+                to_module: plan.to_module,
+                edits,
+                warnings: plan.warnings,
+            }))
+        })
+    }
+
+    #[tool(description = "Plan splitting a large file by moving a list of its top-level items into a new submodule. \
+            Returns the new file's path and contents plus the edits to existing files: removing the items, \
+            adding the `mod` declaration, and fixing visibility and imports across the workspace. Nothing is written to disk.",
+        output_schema = output_schema::<outputs::SplitModuleOutput>())]
+    async fn split_module(&self, params: Parameters<SplitModuleParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("split_module", &params, |analyzer| {
+            let plan = analyzer.plan_split_module(&params.file_path, &params.symbols, &params.module_name)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to plan split: {}", e).into(),
+                    data: None,
+                })?;
+
+            let edits: Vec<_> = plan.edits.iter().map(|edit| outputs::TextEditOutput {
+                file_path: edit.file_path.clone(),
+                start_line: edit.start_line,
+                start_column: edit.start_column,
+                end_line: edit.end_line,
+                end_column: edit.end_column,
+                new_text: edit.new_text.clone(),
+            }).collect();
+
+            let summary = format!(
+                "Splitting {} item(s) into {} creates '{}' and takes {} edit(s) with {} warning(s)",
+                params.symbols.len(),
+                plan.module,
+                plan.new_file,
+                plan.edits.len(),
+                plan.warnings.len()
+            );
+
+            Ok(tool_result(summary, &outputs::SplitModuleOutput {
+                module: plan.module,
+                new_file: plan.new_file,
+                new_file_text: plan.new_file_text,
+                edits,
+                warnings: plan.warnings,
+            }))
+        })
+    }
+
+    #[tool(description = "Find constants and statics by evaluated value or integer range, optionally filtered by name. \
+            Use it to track down the named constant behind a magic number (e.g. value 4096) \
+            or to list all timeout-like constants in a range.",
+        output_schema = output_schema::<outputs::FindConstantsOutput>())]
+    async fn find_constants(&self, params: Parameters<FindConstantsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if params.value.is_none() && params.min.is_none() && params.max.is_none() && params.name_contains.is_none() {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: "Give at least one of 'value', 'min', 'max' or 'name_contains'".into(),
+                data: None,
+            });
+        }
+
+        self.cached("find_constants", &params, |analyzer| {
+            let query = ConstantQuery {
+                value: params.value.clone(),
+                min: params.min.map(i128::from),
+                max: params.max.map(i128::from),
+                name_contains: params.name_contains.clone(),
+            };
+            let constants = analyzer.find_constants(&query)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to search constants: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::FindConstantsOutput {
+                constants: constants.iter().map(|c| outputs::ConstantOutput {
+                    name: c.name.clone(),
+                    kind: c.kind.to_string(),
+                    ty: c.ty.clone(),
+                    value: c.value.clone(),
+                    file_path: c.file_path.clone(),
+                    start_line: c.start_line,
+                    end_line: c.end_line,
+                }).collect(),
+            };
+
+            let summary = format!("Found {} matching constant(s) and static(s)", constants.len());
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Build an override matrix for a trait: rows are implementors, columns are the trait's methods, \
+            constants and associated types, and each cell says whether the impl overrides the member, uses the default, \
+            or is missing it. Useful for auditing consistency across many implementors.",
+        output_schema = output_schema::<outputs::TraitOverrideMatrixOutput>())]
+    async fn trait_override_matrix(&self, params: Parameters<TraitOverrideMatrixParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("trait_override_matrix", &params, |analyzer| {
+            let matrix = match analyzer.trait_override_matrix(&params.trait_name, params.include_library.unwrap_or(false)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::TraitOverrideMatrixOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to build override matrix: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let members: Vec<_> = matrix.members.iter().map(|m| outputs::TraitMemberOutput {
+                name: m.name.clone(),
+                kind: m.kind.to_string(),
+                has_default: m.has_default,
+            }).collect();
+            let implementors: Vec<_> = matrix.implementors.iter().map(|row| {
+                let cells = row.cells.iter().map(|cell| match cell {
+                    OverrideStatus::Overridden => "overridden",
+                    OverrideStatus::Defaulted => "defaulted",
+                    OverrideStatus::Missing => "missing",
+                }.to_string()).collect();
+                outputs::ImplementorOutput {
+                    self_type: row.self_type.clone(),
+                    file_path: row.file_path.clone(),
+                    start_line: row.start_line,
+                    end_line: row.end_line,
+                    cells,
+                }
+            }).collect();
+
+            let incomplete = matrix.implementors.iter().filter(|row| row.cells.contains(&OverrideStatus::Missing)).count();
+            let summary = format!(
+                "'{}' has {} member(s) and {} implementor(s), {} of them missing required members",
+                matrix.trait_path,
+                matrix.members.len(),
+                matrix.implementors.len(),
+                incomplete
+            );
+
+            Ok(tool_result(summary, &outputs::TraitOverrideMatrixOutput {
+                trait_path: matrix.trait_path,
+                members,
+                implementors,
+                candidates: Vec::new(),
+            }))
+        })
+    }
+
+    #[tool(description = "Summarize a module or crate's architecture: its visible types, traits, functions and constants, \
+            its submodules, how many imports come from each external crate and internal module, \
+            and its most referenced items. A structured skeleton to turn into a prose overview.",
+        output_schema = output_schema::<outputs::SummarizeModuleOutput>())]
+    async fn summarize_module(&self, params: Parameters<SummarizeModuleParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let run = |analyzer: &Analyzer| -> Result<CallToolResult, McpError> {
+            let summary = analyzer.summarize_module(&params.file_path, params.recursive.unwrap_or(true))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to summarize module: {}", e).into(),
+                    data: None,
+                })?;
+
+            let items_output = |items: &[ModuleItem]| -> Vec<outputs::ModuleItemOutput> {
+                items.iter().map(|item| outputs::ModuleItemOutput {
+                    name: item.name.clone(),
+                    kind: item.kind.to_string(),
+                    module: item.module.clone(),
+                    file_path: item.file_path.clone(),
+                    start_line: item.start_line,
+                    end_line: item.end_line,
+                }).collect()
+            };
+            let counts_output = |counts: &[(String, usize)]| -> Vec<outputs::ImportCountOutput> {
+                counts.iter().map(|(name, count)| outputs::ImportCountOutput { name: name.clone(), imports: *count }).collect()
+            };
+            let top_referenced = summary.top_referenced.as_ref().map(|top| {
+                top.iter().map(|(path, count)| outputs::ReferenceCountOutput { path: path.clone(), references: *count }).collect()
+            });
+
+            let text = format!(
+                "{}: {} type(s), {} trait(s), {} function(s), {} constant(s) across {} file(s); imports from {} crate(s) and {} other module(s)",
+                summary.module,
+                summary.types.len(),
+                summary.traits.len(),
+                summary.functions.len(),
+                summary.constants.len(),
+                summary.files.len(),
+                summary.external_dependencies.len(),
+                summary.internal_dependencies.len()
+            );
+
+            Ok(tool_result(text, &outputs::SummarizeModuleOutput {
+                types: items_output(&summary.types),
+                traits: items_output(&summary.traits),
+                functions: items_output(&summary.functions),
+                constants: items_output(&summary.constants),
+                external_dependencies: counts_output(&summary.external_dependencies),
+                internal_dependencies: counts_output(&summary.internal_dependencies),
+                top_referenced,
+                module: summary.module,
+                files: summary.files,
+                submodules: summary.submodules,
+            }))
+        };
+
+        // Until the reference index is in place summaries lack top_referenced
+        self.index_cached("summarize_module", &params, run)
+    }
+
+    #[tool(description = "List the conversions to and from a type: From, Into, TryFrom, TryInto and AsRef impls \
+            with the type on either side, plus Deref impls it implements or targets. \
+            Answers \"how do I get a Foo from a Bar\", including impls from dependencies and std.",
+        output_schema = output_schema::<outputs::FindConversionsOutput>())]
+    async fn find_conversions(&self, params: Parameters<FindConversionsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("find_conversions", &params, |analyzer| {
+            let conversions = match analyzer.find_conversions(&params.type_name) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::FindConversionsOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find conversions: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let output = outputs::FindConversionsOutput {
+                conversions: conversions.iter().map(|c| outputs::ConversionOutput {
+                    trait_name: c.trait_name.clone(),
+                    from: c.from.clone(),
+                    to: c.to.clone(),
+                    fallible: c.fallible,
+                    direction: c.direction.to_string(),
+                    file_path: c.file_path.clone(),
+                    start_line: c.start_line,
+                    end_line: c.end_line,
+                }).collect(),
+                candidates: Vec::new(),
+            };
+
+            let into = conversions.iter().filter(|c| c.direction == "into").count();
+            let summary = format!(
+                "Found {} conversion(s) involving '{}': {} from it, {} into it",
+                conversions.len(),
+                params.type_name,
+                into,
+                conversions.len() - into
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Report the operator-trait impls of a type (arithmetic and bit operators, Index, Deref, \
+            PartialEq/Eq, PartialOrd/Ord, Display/Debug) with their right-hand side and Output types, \
+            plus the chain of types auto-deref reaches. Tells whether `a + b` or `a[i]` is valid and what it yields.",
+        output_schema = output_schema::<outputs::TypeOperatorsOutput>())]
+    async fn type_operators(&self, params: Parameters<TypeOperatorsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("type_operators", &params, |analyzer| {
+            let report = match analyzer.type_operators(&params.type_name) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::TypeOperatorsOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find operator impls: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let operators: Vec<_> = report.operators.iter().map(|op| outputs::OperatorOutput {
+                trait_name: op.trait_name.clone(),
+                operator: op.operator.to_string(),
+                rhs: op.rhs.clone(),
+                output: op.output.clone(),
+                derived: op.derived,
+                file_path: op.file_path.clone(),
+                start_line: op.start_line,
+                end_line: op.end_line,
+            }).collect();
+
+            let summary = format!(
+                "'{}' implements {} operator trait(s) and derefs through {} type(s)",
+                report.type_path,
+                report.operators.len(),
+                report.deref_chain.len()
+            );
+
+            Ok(tool_result(summary, &outputs::TypeOperatorsOutput {
+                type_path: report.type_path,
+                operators,
+                deref_chain: report.deref_chain,
+                candidates: Vec::new(),
+            }))
+        })
+    }
+
+    #[tool(description = "Explain the types along an iterator or method chain such as `.iter().filter(..).map(..).collect()`. \
+            Given a position on any call in the chain, reports each step's type and its iterator Item type, \
+            and flags where inference fails. Makes type errors at the end of a chain easy to trace back.",
+        output_schema = output_schema::<outputs::ExplainIteratorChainOutput>())]
+    async fn explain_iterator_chain(&self, params: Parameters<ExplainIteratorChainParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("explain_iterator_chain", &params, |analyzer| {
+            let steps = analyzer.explain_iterator_chain(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to explain chain: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::ExplainIteratorChainOutput {
+                steps: steps.iter().map(|step| outputs::ChainStepOutput {
+                    expression: step.expression.clone(),
+                    line: step.line,
+                    ty: step.ty.clone(),
+                    item: step.item.clone(),
+                    unknown: step.unknown,
+                }).collect(),
+            };
+
+            let summary = match steps.iter().position(|step| step.unknown) {
+                Some(i) => format!("Chain of {} step(s); inference fails at step {} ({})", steps.len(), i, steps[i].expression),
+                None => format!("Chain of {} step(s), all types inferred", steps.len()),
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Find all references to a symbol across the workspace, given by name or qualified path \
+            or by file position (e.g. from a compiler error). Uses rust-analyzer's live find-all-references, \
+            and classifies each site as a read, write, import or other reference.",
+        output_schema = output_schema::<outputs::FindReferencesOutput>())]
+    async fn find_references(&self, params: Parameters<FindReferencesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let by_position = match (&params.file_path, params.line, params.column) {
+            (Some(file_path), Some(line), Some(column)) => Some((file_path.clone(), line, column)),
+            (None, None, None) => None,
+            _ => {
+                return Err(McpError {
+                    code: ErrorCode(-1),
+                    message: "A position needs all of 'file_path', 'line' and 'column'".into(),
+                    data: None,
+                });
+            }
+        };
+        if by_position.is_some() == params.name.is_some() {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: "Give either 'name' or a position ('file_path', 'line', 'column')".into(),
+                data: None,
+            });
+        }
+
+        self.cached("find_references", &params, |analyzer| {
+            let results = match (&params.name, &by_position) {
+                (Some(name), _) => analyzer.find_references(name),
+                (None, Some((file_path, line, column))) => analyzer.find_references_at(file_path, *line, *column),
+                (None, None) => unreachable!("validated above"),
+            };
+            let results = match results {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::FindReferencesOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find references: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let reference_output = |r: &Reference| outputs::ReferenceOutput {
+                file_path: r.file_path.clone(),
+                line: r.line,
+                column: r.column,
+                kind: r.kind.to_string(),
+            };
+            let output = outputs::FindReferencesOutput {
+                definitions: results.iter().map(|result| outputs::DefinitionReferencesOutput {
+                    definition: result.definition.clone(),
+                    declaration: result.declaration.as_ref().map(reference_output),
+                    references: result.references.iter().map(reference_output).collect(),
+                }).collect(),
+                candidates: Vec::new(),
+            };
+
+            let total: usize = results.iter().map(|r| r.references.len()).sum();
+            let summary = format!("Found {} reference(s) to {} definition(s)", total, results.len());
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List every externally reachable entry point of the workspace: main functions of binaries, \
+            build scripts, the public API of library targets, #[no_mangle]/#[export_name] items and test functions. \
+            A starting point for threat modeling and for finding your way around an unfamiliar workspace.",
+        output_schema = output_schema::<outputs::EntryPointsOutput>())]
+    async fn entry_points(&self, params: Parameters<EntryPointsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if let Some(unknown) = params.kinds.iter().flatten().find(|k| !ENTRY_POINT_KINDS.contains(&k.as_str())) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown entry point kind '{}', expected one of: {}", unknown, ENTRY_POINT_KINDS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        self.cached("entry_points", &params, |analyzer| {
+            let entries = analyzer.entry_points()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list entry points: {}", e).into(),
+                    data: None,
+                })?;
+
+            let entries: Vec<_> = entries
+                .into_iter()
+                .filter(|e| params.kinds.as_ref().is_none_or(|kinds| kinds.iter().any(|k| k == e.kind)))
+                .filter(|e| params.crate_name.as_ref().is_none_or(|name| *name == e.crate_name))
+                .collect();
+
+            let output = outputs::EntryPointsOutput {
+                entry_points: entries.iter().map(|e| outputs::EntryPointOutput {
+                    kind: e.kind.to_string(),
+                    name: e.name.clone(),
+                    item_kind: e.item_kind.to_string(),
+                    crate_name: e.crate_name.clone(),
+                    file_path: e.file_path.clone(),
+                    start_line: e.start_line,
+                    end_line: e.end_line,
+                }).collect(),
+            };
+
+            let counts: Vec<_> = ENTRY_POINT_KINDS
+                .iter()
+                .map(|kind| (kind, entries.iter().filter(|e| e.kind == *kind).count()))
+                .filter(|(_, count)| *count > 0)
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            let summary = if entries.is_empty() {
+                "No entry points found".to_string()
+            } else {
+                format!("Found {} entry point(s): {}", entries.len(), counts.join(", "))
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List the tests, test modules, benchmarks, doctests and binaries of a file or workspace \
+            crate, each with the exact cargo command line running just it, e.g. `cargo test --package app --lib -- \
+            parser::tests::test_empty --exact`. Use it to run precisely the tests affected by an edit.",
+        output_schema = output_schema::<outputs::RunnablesOutput>())]
+    async fn runnables(&self, params: Parameters<RunnablesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if let Some(unknown) = params.kinds.iter().flatten().find(|k| !RUNNABLE_KINDS.contains(&k.as_str())) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown runnable kind '{}', expected one of: {}", unknown, RUNNABLE_KINDS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        self.cached("runnables", &params, |analyzer| {
+            let runnables = match (&params.file_path, &params.crate_name) {
+                (Some(file_path), None) => analyzer.file_runnables(file_path),
+                (None, Some(crate_name)) => analyzer.crate_runnables(crate_name),
+                _ => {
+                    return Err(McpError {
+                        code: ErrorCode(-1),
+                        message: "Give exactly one of file_path and crate_name".into(),
+                        data: None,
+                    })
+                }
+            }
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to list runnables: {}", e).into(),
+                data: None,
+            })?;
+
+            let runnables: Vec<_> = runnables
+                .into_iter()
+                .filter(|r| params.kinds.as_ref().is_none_or(|kinds| kinds.iter().any(|k| k == r.kind)))
+                .collect();
+
+            let output = outputs::RunnablesOutput {
+                runnables: runnables.iter().map(|r| outputs::RunnableOutput {
+                    kind: r.kind.to_string(),
+                    name: r.name.clone(),
+                    crate_name: r.crate_name.clone(),
+                    file_path: r.file_path.clone(),
+                    start_line: r.start_line,
+                    end_line: r.end_line,
+                    cfg: r.cfg.clone(),
+                    command_line: r.command_line(),
+                    command: r.command.clone(),
+                }).collect(),
+            };
+
+            let counts: Vec<_> = RUNNABLE_KINDS
+                .iter()
+                .map(|kind| (kind, runnables.iter().filter(|r| r.kind == *kind).count()))
+                .filter(|(_, count)| *count > 0)
+                .map(|(kind, count)| format!("{} {}", count, kind))
+                .collect();
+            let summary = if runnables.is_empty() {
+                "No runnables found".to_string()
+            } else {
+                format!("Found {} runnable(s): {}", runnables.len(), counts.join(", "))
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Go to the definition of the symbol at a file position (0-based line and column), \
+            e.g. one taken from a compiler error. Resolves any kind of symbol, including fields, variants, \
+            locals and library items, and returns its location and documentation.",
+        output_schema = output_schema::<outputs::NavigationOutput>())]
+    async fn goto_definition(&self, params: Parameters<GotoDefinitionParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("goto_definition", &params, |analyzer| {
+            let targets = analyzer.goto_definition(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to go to definition: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::NavigationOutput {
+                targets: targets.iter().map(|t| outputs::NavigationTargetOutput {
+                    name: t.name.clone(),
+                    kind: t.kind.clone(),
+                    container: t.container.clone(),
+                    file_path: t.file_path.clone(),
+                    start_line: t.start_line,
+                    end_line: t.end_line,
+                    line: t.line,
+                    column: t.column,
+                    documentation: t.documentation.clone(),
+                    crate_name: t.crate_name.clone(),
+                    crate_version: t.crate_version.clone(),
+                    moniker: t.moniker.clone(),
+                }).collect(),
+            };
+
+            let summary = match targets.as_slice() {
+                [] => format!("No definition found at {}:{}", params.line, params.column),
+                [target] => format!("{} {} defined in {}:{}", target.kind, target.name, target.file_path, target.line),
+                _ => format!("Found {} definitions", targets.len()),
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Go to the definition of the type of the expression or variable at a file position \
+            (0-based line and column). For types built from several definitions, such as Option<Foo>, \
+            each of them is returned.",
+        output_schema = output_schema::<outputs::NavigationOutput>())]
+    async fn goto_type_definition(&self, params: Parameters<GotoTypeDefinitionParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("goto_type_definition", &params, |analyzer| {
+            let targets = analyzer.goto_type_definition(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to go to type definition: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::NavigationOutput {
+                targets: targets.iter().map(|t| outputs::NavigationTargetOutput {
+                    name: t.name.clone(),
+                    kind: t.kind.clone(),
+                    container: t.container.clone(),
+                    file_path: t.file_path.clone(),
+                    start_line: t.start_line,
+                    end_line: t.end_line,
+                    line: t.line,
+                    column: t.column,
+                    documentation: t.documentation.clone(),
+                    crate_name: t.crate_name.clone(),
+                    crate_version: t.crate_version.clone(),
+                    moniker: t.moniker.clone(),
+                }).collect(),
+            };
+
+            let summary = if targets.is_empty() {
+                format!("No type definition found at {}:{}", params.line, params.column)
+            } else {
+                let names: Vec<_> = targets.iter().map(|t| t.name.as_str()).collect();
+                format!("Type defined by {}", names.join(", "))
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List everything related to the identifier or keyword at a file position (0-based line and \
+            column) within that file, as an editor highlights it: every usage of the symbol, marked read or write; \
+            for a loop keyword its breaks and continues; for `fn`, `return` or `?` the function's exit points; for \
+            `async` or `.await` the await points. A cheap, file-local alternative to find_references.",
+        output_schema = output_schema::<outputs::HighlightRelatedOutput>())]
+    async fn highlight_related(&self, params: Parameters<HighlightRelatedParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("highlight_related", &params, |analyzer| {
+            let highlights = analyzer.highlight_related(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to highlight related ranges: {}", e).into(),
+                    data: None,
+                })?;
+
+            let summary = if highlights.is_empty() {
+                format!("Nothing related to highlight at {}:{}", params.line, params.column)
+            } else {
+                let lines: Vec<_> = highlights.iter().map(|h| format!("{}:{} {}", h.line, h.column, h.kind)).collect();
+                format!("{} related range(s): {}", highlights.len(), lines.join(", "))
+            };
+
+            Ok(tool_result(summary, &outputs::HighlightRelatedOutput {
+                highlights: highlights.into_iter().map(|h| outputs::RelatedHighlightOutput {
+                    line: h.line,
+                    column: h.column,
+                    end_line: h.end_line,
+                    end_column: h.end_column,
+                    kind: h.kind.to_string(),
+                    text: h.text,
+                }).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "List the code lenses of a file's items, as an editor shows them: per item, its number of \
+            references and how many of those are from test code, its number of impls for types and traits, and \
+            whether it is a test, test module, benchmark or binary. Tells which items are load-bearing and which \
+            no test touches, at a glance.",
+        output_schema = output_schema::<outputs::AnnotationsOutput>())]
+    async fn annotations(&self, params: Parameters<AnnotationsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("annotations", &params, |analyzer| {
+            let annotations = analyzer.annotations(&params.file_path)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to annotate file: {}", e).into(),
+                    data: None,
+                })?;
+
+            let unreferenced = annotations.iter().filter(|a| a.references == Some(0) && a.runnables.is_empty()).count();
+            let untested = annotations.iter().filter(|a| a.test_references == Some(0) && a.runnables.is_empty()).count();
+            let summary = format!(
+                "{} annotated item(s), {} without references, {} without references from tests",
+                annotations.len(),
+                unreferenced,
+                untested
+            );
+
+            Ok(tool_result(summary, &outputs::AnnotationsOutput {
+                items: annotations.into_iter().map(|a| outputs::ItemAnnotationOutput {
+                    name: a.name,
+                    kind: a.kind.to_string(),
+                    line: a.line,
+                    references: a.references,
+                    test_references: a.test_references,
+                    implementations: a.implementations,
+                    runnables: a.runnables.into_iter().map(str::to_string).collect(),
+                }).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "Show the hover information at a file position (0-based line and column), as an editor would: \
+            the resolved signature or type of the symbol or expression, its trait bounds and generic substitutions, \
+            and its rendered documentation, as Markdown.",
+        output_schema = output_schema::<outputs::HoverOutput>())]
+    async fn hover(&self, params: Parameters<HoverParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("hover", &params, |analyzer| {
+            let hover = analyzer.hover(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to hover: {}", e).into(),
+                    data: None,
+                })?;
+
+            let Some(hover) = hover else {
+                let summary = format!("Nothing to show at {}:{}", params.line, params.column);
+                return Ok(tool_result(summary, &outputs::HoverOutput { hover: None }));
+            };
+
+            Ok(tool_result(hover.markdown.clone(), &outputs::HoverOutput {
+                hover: Some(outputs::HoverRangeOutput {
+                    markdown: hover.markdown,
+                    start_line: hover.start_line,
+                    start_column: hover.start_column,
+                    end_line: hover.end_line,
+                    end_column: hover.end_column,
+                }),
+            }))
+        })
+    }
+
+    #[tool(description = "List the functions that call a given function, with the file and line of each call site, \
+            using rust-analyzer's call hierarchy. Use it for impact analysis before changing a function. \
+            Accepts a bare name or a qualified path; calls from tests can be excluded.",
+        output_schema = output_schema::<outputs::CallersOfOutput>())]
+    async fn callers_of(&self, params: Parameters<CallersOfParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("callers_of", &params, |analyzer| {
+            let results = match analyzer.callers_of(&params.name, params.exclude_tests.unwrap_or(false)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::CallersOfOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find callers: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let output = outputs::CallersOfOutput {
+                functions: results.iter().map(|result| outputs::IncomingCallsOutput {
+                    function: result.function.clone(),
+                    callers: result.callers.iter().map(|caller| outputs::CallerOutput {
+                        name: caller.name.clone(),
+                        container: caller.container.clone(),
+                        file_path: caller.file_path.clone(),
+                        start_line: caller.start_line,
+                        end_line: caller.end_line,
+                        call_sites: caller.call_sites.iter().map(|&(line, column)| outputs::CallSiteOutput {
+                            line,
+                            column,
+                        }).collect(),
+                        moniker: caller.moniker.clone(),
+                    }).collect(),
+                }).collect(),
+                candidates: Vec::new(),
+            };
+
+            let callers: usize = results.iter().map(|r| r.callers.len()).sum();
+            let call_sites: usize = results.iter().flat_map(|r| &r.callers).map(|c| c.call_sites.len()).sum();
+            let summary = format!(
+                "Found {} caller(s) with {} call site(s) of {} function(s) named '{}'",
+                callers,
+                call_sites,
+                results.len(),
+                params.name
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "For a bare identifier such as \"Error\", list every crate and module in the graph that exports \
+            an item with that name: its full path, kind, and the use path that imports it from a given file \
+            (preferring public re-exports like std::io::Error). Resolves ambiguous names.",
+        output_schema = output_schema::<outputs::WhoExportsOutput>())]
+    async fn who_exports(&self, params: Parameters<WhoExportsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if params.name.contains("::") {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("'{}' is a path; give the bare name, e.g. '{}'", params.name, params.name.rsplit("::").next().unwrap_or_default()).into(),
+                data: None,
+            });
+        }
+
+        self.cached("who_exports", &params, |analyzer| {
+            let exporters = analyzer.who_exports(&params.name, params.file_path.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find exporters: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::WhoExportsOutput {
+                exporters: exporters.iter().map(|e| outputs::ExporterOutput {
+                    path: e.path.clone(),
+                    kind: e.kind.to_string(),
+                    crate_name: e.crate_name.clone(),
+                    local: e.local,
+                    import: e.import.as_ref().map(|path| format!("use {};", path)),
+                    file_path: e.file_path.clone(),
+                    line: e.line,
+                }).collect(),
+            };
+
+            let importable = exporters.iter().filter(|e| e.import.is_some()).count();
+            let summary = format!(
+                "Found {} item(s) named '{}', {} importable from here",
+                exporters.len(),
+                params.name,
+                importable
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Show the hierarchy around a trait or type. For a trait: its direct supertraits, the traits \
+            that have it as a supertrait, and the types implementing it. For a type: the traits it implements. \
+            Each entry has its definition or impl location.",
+        output_schema = output_schema::<outputs::TypeHierarchyOutput>())]
+    async fn type_hierarchy(&self, params: Parameters<TypeHierarchyParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("type_hierarchy", &params, |analyzer| {
+            let hierarchies = match analyzer.type_hierarchy(&params.name, params.include_library.unwrap_or(false)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::TypeHierarchyOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to build hierarchy: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let related_output = |list: &[Related]| -> Vec<outputs::RelatedOutput> {
+                list.iter().map(|r| outputs::RelatedOutput {
+                    name: r.name.clone(),
+                    file_path: r.file_path.clone(),
+                    start_line: r.start_line,
+                    end_line: r.end_line,
+                }).collect()
+            };
+            let output = outputs::TypeHierarchyOutput {
+                hierarchies: hierarchies.iter().map(|h| {
+                    let is_trait = h.kind == "trait";
+                    outputs::HierarchyOutput {
+                        path: h.path.clone(),
+                        kind: h.kind.to_string(),
+                        supertraits: is_trait.then(|| related_output(&h.supertraits)),
+                        subtraits: is_trait.then(|| related_output(&h.subtraits)),
+                        implementors: is_trait.then(|| related_output(&h.implementors)),
+                        traits: (!is_trait).then(|| related_output(&h.traits)),
+                    }
+                }).collect(),
+                candidates: Vec::new(),
+            };
+
+            let descriptions: Vec<_> = hierarchies.iter().map(|h| {
+                if h.kind == "trait" {
+                    format!(
+                        "trait '{}': {} supertrait(s), {} subtrait(s), {} implementor(s)",
+                        h.path,
+                        h.supertraits.len(),
+                        h.subtraits.len(),
+                        h.implementors.len()
+                    )
+                } else {
+                    format!("type '{}': implements {} trait(s)", h.path, h.traits.len())
+                }
+            }).collect();
+            let summary = descriptions.join("\n");
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Find every `impl Trait for X` block of a trait, resolved semantically rather than by name: \
+            the implementing type, the impl header, whether it is a blanket impl, and its file and line range. \
+            Workspace impls by default; impls in dependencies can be included.",
+        output_schema = output_schema::<outputs::FindImplementationsOutput>())]
+    async fn find_implementations(&self, params: Parameters<FindImplementationsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("find_implementations", &params, |analyzer| {
+            let blocks = match analyzer.find_implementations(&params.trait_name, params.include_library.unwrap_or(false)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::FindImplementationsOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find implementations: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let output = outputs::FindImplementationsOutput {
+                impls: blocks.iter().map(impl_block_output).collect(),
+                candidates: Vec::new(),
+            };
+
+            let summary = format!(
+                "Found {} impl(s) of '{}', {} of them blanket impls",
+                blocks.len(),
+                params.trait_name,
+                blocks.iter().filter(|b| b.blanket).count()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List every impl block targeting a type, inherent impls first, then trait impls, \
+            each with its members (fn, const, type) and location. Shows a struct's or enum's full method surface \
+            without grepping. Blanket impls that happen to cover the type are not included.",
+        output_schema = output_schema::<outputs::ImplsOfTypeOutput>())]
+    async fn impls_of_type(&self, params: Parameters<ImplsOfTypeParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("impls_of_type", &params, |analyzer| {
+            let blocks = match analyzer.impls_of_type(&params.type_name, params.include_library.unwrap_or(false)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::ImplsOfTypeOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find impls: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let output = outputs::ImplsOfTypeOutput {
+                impls: blocks.iter().map(impl_block_output).collect(),
+                candidates: Vec::new(),
+            };
+
+            let inherent = blocks.iter().filter(|b| b.trait_path.is_none()).count();
+            let summary = format!(
+                "'{}' has {} inherent impl(s) and {} trait impl(s) with {} member(s) in total",
+                params.type_name,
+                inherent,
+                blocks.len() - inherent,
+                blocks.iter().map(|b| b.items.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Expand the macro call at a file position (0-based line and column) and return the generated \
+            source: a `macro_rules!` or function-like macro call, an attribute macro, or a `#[derive(...)]` attribute \
+            (place the position on `derive` to see every derived impl). Nested macro calls are expanded too.",
+        output_schema = output_schema::<outputs::ExpandMacroOutput>())]
+    async fn expand_macro(&self, params: Parameters<ExpandMacroParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("expand_macro", &params, |analyzer| {
+            let expanded = analyzer.expand_macro(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to expand macro: {}", e).into(),
+                    data: None,
+                })?;
+
+            let Some(expanded) = expanded else {
+                let summary = format!("No macro call at {}:{}", params.line, params.column);
+                return Ok(tool_result(summary, &outputs::ExpandMacroOutput { expansion: None }));
+            };
+
+            Ok(tool_result(expanded.expansion.clone(), &outputs::ExpandMacroOutput {
+                expansion: Some(outputs::MacroExpansionOutput {
+                    name: expanded.name,
+                    expansion: expanded.expansion,
+                }),
+            }))
+        })
+    }
+
+    #[tool(description = "Expand one derive of a struct, enum or union, e.g. what `#[derive(Serialize)]` generates \
+            for a type, without the other derives of the attribute. The derive is matched as written or by its last \
+            path segment; an unknown one fails with the type's derives listed. Proc-macro derives are expanded by the \
+            toolchain's proc-macro server.",
+        output_schema = output_schema::<outputs::ExpandDeriveOutput>())]
+    async fn expand_derive(&self, params: Parameters<ExpandDeriveParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("expand_derive", &params, |analyzer| {
+            let expanded = match analyzer.expand_derive(&params.type_name, &params.derive) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::ExpandDeriveOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to expand derive: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            Ok(tool_result(expanded.expansion.clone(), &outputs::ExpandDeriveOutput {
+                type_path: expanded.type_path,
+                derive: expanded.derive,
+                expansion: expanded.expansion,
+                derives: expanded.derives,
+                candidates: Vec::new(),
+            }))
+        })
+    }
+
+    #[tool(description = "List every `match` over an enum in the workspace and flag those with a catch-all arm \
+            (`_` or a plain binding) that would silently absorb a newly added variant. For each site: its location, \
+            enclosing function, scrutinee, the catch-all pattern and the variants no arm names. Run it after adding a variant. \
+            Pass `budget_ms` to bound the scan; `coverage` tells whether every file was scanned.",
+        output_schema = output_schema::<outputs::EnumMatchSitesOutput>())]
+    async fn enum_match_sites(&self, params: Parameters<EnumMatchSitesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("enum_match_sites", &params, |analyzer| {
+            let matches = match analyzer.enum_match_sites(&params.enum_name, Budget::from_millis(params.budget_ms)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::EnumMatchSitesOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find match sites: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let with_wildcard = matches.sites.iter().filter(|s| s.wildcard.is_some()).count();
+            let summary = format!(
+                "Found {} match(es) over '{}', {} with a catch-all arm{}",
+                matches.sites.len(),
+                matches.enum_path,
+                with_wildcard,
+                coverage_note(matches.coverage)
+            );
+
+            Ok(tool_result(summary, &outputs::EnumMatchSitesOutput {
+                enum_path: matches.enum_path,
+                variants: matches.variants,
+                sites: matches.sites.into_iter().map(|site| outputs::MatchSiteOutput {
+                    function: site.function,
+                    file_path: site.file_path,
+                    start_line: site.start_line,
+                    end_line: site.end_line,
+                    scrutinee: site.scrutinee,
+                    arms: site.arms,
+                    wildcard: site.wildcard,
+                    wildcard_line: site.wildcard_line,
+                    unnamed_variants: site.unnamed_variants,
+                }).collect(),
+                coverage: Some(coverage_output(matches.coverage)),
+                candidates: Vec::new(),
+            }))
+        })
+    }
+
+    #[tool(description = "Debugging aid: show rust-analyzer's HIR for the function body containing a file position \
+            (0-based line and column), with macros expanded and `for`, `?` and other sugar lowered, as type inference \
+            sees it. Use it to diagnose surprising inference results. The format is rust-analyzer's and may change.",
+        output_schema = output_schema::<outputs::ViewHirOutput>())]
+    async fn view_hir(&self, params: Parameters<ViewHirParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("view_hir", &params, |analyzer| {
+            let hir = analyzer.view_hir(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to view HIR: {}", e).into(),
+                    data: None,
+                })?;
+
+            Ok(tool_result(hir.clone(), &outputs::ViewHirOutput { hir }))
+        })
+    }
+
+    #[tool(description = "Compare each workspace crate's public API with default features against `--all-features` and \
+            list the public items only available (or only missing) behind features, with the cfg gating each. \
+            For documenting feature requirements and for semver reasoning. Works from #[cfg] attributes, without builds.",
+        output_schema = output_schema::<outputs::FeatureGatedApiOutput>())]
+    async fn feature_gated_api(&self, params: Parameters<FeatureGatedApiParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("feature_gated_api", &params, |analyzer| {
+            let mut apis = analyzer.feature_gated_api()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to compare feature APIs: {}", e).into(),
+                    data: None,
+                })?;
+            if let Some(crate_name) = &params.crate_name {
+                apis.retain(|api| &api.crate_name == crate_name);
+            }
+
+            let presence = |present: Option<bool>| match present {
+                Some(true) => "present",
+                Some(false) => "absent",
+                None => "unknown",
+            }.to_string();
+            let output = outputs::FeatureGatedApiOutput {
+                crates: apis.iter().map(|api| outputs::FeatureApiOutput {
+                    crate_name: api.crate_name.clone(),
+                    default_features: api.default_features.clone(),
+                    all_features: api.all_features.clone(),
+                    items: api.items.iter().map(|item| outputs::GatedApiItemOutput {
+                        name: item.name.clone(),
+                        kind: item.kind.clone(),
+                        file_path: item.file_path.clone(),
+                        start_line: item.start_line,
+                        end_line: item.end_line,
+                        cfg: item.cfg.clone(),
+                        with_default: presence(item.with_default),
+                        with_all_features: presence(item.with_all),
+                        reachable: item.reachable,
+                    }).collect(),
+                }).collect(),
+            };
+
+            let total: usize = apis.iter().map(|api| api.items.len()).sum();
+            let summary = format!("Found {} feature-gated public item(s) in {} crate(s)", total, apis.len());
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Debugging aid: show rust-analyzer's MIR for the function containing a file position \
+            (0-based line and column): basic blocks, locals, moves and borrows. Output can be very large, so the tool \
+            is off unless the server runs with CRATOGRAPHER_ENABLE_MIR=1. The format is rust-analyzer's and may change.",
+        output_schema = output_schema::<outputs::ViewMirOutput>())]
+    async fn view_mir(&self, params: Parameters<ViewMirParams>) -> Result<CallToolResult, McpError> {
+        if !self.enable_mir {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: "view_mir is disabled; start the server with CRATOGRAPHER_ENABLE_MIR=1 to enable it".into(),
+                data: None,
+            });
+        }
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("view_mir", &params, |analyzer| {
+            let mir = analyzer.view_mir(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to view MIR: {}", e).into(),
+                    data: None,
+                })?;
+
+            Ok(tool_result(mir.clone(), &outputs::ViewMirOutput { mir }))
+        })
+    }
+
+    #[tool(description = "Report workspace code that uses `#[doc(hidden)]` items of dependencies (or items in hidden \
+            modules, or members of hidden types and traits). Hidden items are exempt from semver, so these uses can break \
+            on any dependency update. Answered from the cross-reference index.",
+        output_schema = output_schema::<outputs::HiddenApiUsesOutput>())]
+    async fn hidden_api_uses(&self, params: Parameters<HiddenApiUsesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.index_cached("hidden_api_uses", &params, |analyzer| {
+            let results = analyzer.hidden_api_uses()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find hidden API uses: {}", e).into(),
+                    data: None,
+                })?;
+
+            let items: Vec<_> = results.iter().filter_map(|def| {
+                let crate_name = def.definition.split("::").next().unwrap_or_default().to_string();
+                if params.crate_name.as_ref().is_some_and(|name| *name != crate_name) {
+                    return None;
+                }
+                Some(outputs::HiddenItemOutput {
+                    definition: def.definition.clone(),
+                    crate_name,
+                    references: def.references.iter().map(|site| outputs::ReferenceSiteOutput {
+                        file_path: site.file_path.clone(),
+                        line: site.line,
+                        column: site.column,
+                        is_import: site.is_import,
+                    }).collect(),
+                })
+            }).collect();
+
+            let summary = format!(
+                "Found {} hidden item(s) used, with {} reference(s) in total",
+                items.len(),
+                items.iter().map(|item| item.references.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &outputs::HiddenApiUsesOutput { items }))
+        })
+    }
+
+    #[tool(description = "Dump the parsed syntax tree of a file, or of the smallest node covering a line range: \
+            every node and token with its kind (e.g. FN, PARAM_LIST, IDENT) and 0-based line:column extent, tokens \
+            with their text. For writing proc macros or lints that need the node structure of some code.",
+        output_schema = output_schema::<outputs::ViewSyntaxTreeOutput>())]
+    async fn view_syntax_tree(&self, params: Parameters<ViewSyntaxTreeParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        if params.end_line.is_some() && params.start_line.is_none() {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: "'end_line' needs 'start_line'".into(),
+                data: None,
+            });
+        }
+
+        self.cached("view_syntax_tree", &params, |analyzer| {
+            let lines = params.start_line.map(|start| (start, params.end_line.unwrap_or(start)));
+            let tree = analyzer.view_syntax_tree(&params.file_path, lines)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to view syntax tree: {}", e).into(),
+                    data: None,
+                })?;
+
+            Ok(tool_result(tree.clone(), &outputs::ViewSyntaxTreeOutput { tree }))
+        })
+    }
+
+    #[tool(description = "List the workspace build scripts (build.rs) with their symbols and the `cargo:` directives \
+            they print (rustc-cfg, rustc-env, rerun-if-changed, ...). Each cfg a build script emits is linked to the \
+            package items gated on it, which feature_matrix can only report as unknown. Read from the source, without \
+            running the scripts; directives built at runtime are marked dynamic.",
+        output_schema = output_schema::<outputs::BuildScriptsOutput>())]
+    async fn build_scripts(&self, params: Parameters<BuildScriptsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("build_scripts", &params, |analyzer| {
+            let mut scripts = analyzer.build_scripts()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to analyze build scripts: {}", e).into(),
+                    data: None,
+                })?;
+            if let Some(crate_name) = &params.crate_name {
+                scripts.retain(|script| &script.crate_name == crate_name);
+            }
+
+            let output = outputs::BuildScriptsOutput {
+                scripts: scripts.iter().map(|script| outputs::BuildScriptOutput {
+                    crate_name: script.crate_name.clone(),
+                    file_path: script.file_path.clone(),
+                    symbols: script.symbols.iter().map(|sym| symbol_output(sym, FILE_SYMBOL_FIELDS)).collect(),
+                    directives: script.directives.iter().map(|directive| outputs::BuildDirectiveOutput {
+                        key: directive.key.clone(),
+                        value: directive.value.clone(),
+                        line: directive.line,
+                        dynamic: directive.dynamic,
+                    }).collect(),
+                    cfgs: script.cfgs.iter().map(|cfg| outputs::BuildCfgOutput {
+                        cfg: cfg.cfg.clone(),
+                        items: cfg.items.iter().map(|item| outputs::BuildCfgItemOutput {
+                            name: item.name.clone(),
+                            kind: item.kind.clone(),
+                            file_path: item.file_path.clone(),
+                            start_line: item.start_line,
+                            end_line: item.end_line,
+                            cfg: item.cfg.clone(),
+                        }).collect(),
+                    }).collect(),
+                    env_vars: script.env_vars.clone(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} build script(s) emitting {} cfg(s)",
+                scripts.len(),
+                scripts.iter().map(|script| script.cfgs.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List every env!, option_env!, include_str!, include_bytes! and include! in the workspace \
+            with its arguments, location and enclosing item, including uses nested in other macros' arguments. For \
+            auditing what a build depends on from the build machine's environment and files.",
+        output_schema = output_schema::<outputs::BuildEnvUsagesOutput>())]
+    async fn build_env_usages(&self, params: Parameters<BuildEnvUsagesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if let Some(unknown) = params.macros.iter().flatten().find(|m| !BUILD_ENV_MACROS.contains(&m.as_str())) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown macro '{}', expected one of: {}", unknown, BUILD_ENV_MACROS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        self.cached("build_env_usages", &params, |analyzer| {
+            let usages = analyzer.build_env_usages()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list build environment usages: {}", e).into(),
+                    data: None,
+                })?;
+
+            let usages: Vec<_> = usages
+                .into_iter()
+                .filter(|u| params.macros.as_ref().is_none_or(|macros| macros.iter().any(|m| m == u.macro_name)))
+                .collect();
+
+            // Distinct variables read, for the deployment checklist
+            let mut variables: Vec<String> = usages
+                .iter()
+                .filter(|u| matches!(u.macro_name, "env" | "option_env"))
+                .filter_map(|u| u.argument.clone())
+                .collect();
+            variables.sort();
+            variables.dedup();
+
+            let output = outputs::BuildEnvUsagesOutput {
+                usages: usages.iter().map(|u| outputs::BuildEnvUsageOutput {
+                    macro_name: u.macro_name.to_string(),
+                    argument: u.argument.clone(),
+                    arguments: u.arguments.clone(),
+                    file_path: u.file_path.clone(),
+                    line: u.line,
+                    item: u.item.clone(),
+                    item_kind: u.item_kind.map(str::to_string),
+                }).collect(),
+                variables: variables.clone(),
+            };
+
+            let summary = format!(
+                "Found {} compile-time environment/file macro use(s) reading {} distinct environment variable(s){}",
+                usages.len(),
+                variables.len(),
+                if variables.is_empty() { String::new() } else { format!(": {}", variables.join(", ")) }
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List the non-Rust files the workspace embeds at compile time: include_str!, \
+            include_bytes! and include!, and sqlx's query_file! family and migrate!, each with the path it resolves \
+            to, whether that exists, and the Rust item embedding it. With `path`, answers which Rust code depends on \
+            a given .sql, .html or .json file.",
+        output_schema = output_schema::<outputs::EmbeddedFilesOutput>())]
+    async fn embedded_files(&self, params: Parameters<EmbeddedFilesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        if let Some(unknown) = params.macros.iter().flatten().find(|m| !EMBEDDING_MACROS.contains(&m.as_str())) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown macro '{}', expected one of: {}", unknown, EMBEDDING_MACROS.join(", ")).into(),
+                data: None,
+            });
+        }
+
+        // Whether each file exists is read from disk, which the revision doesn't
+        // track, so results aren't cached
+        let analyzer = self.analyzer.lock().unwrap();
+        let embeddings = analyzer.embedded_files(params.path.as_deref())
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to list embedded files: {}", e).into(),
+                data: None,
+            })?;
+
+        let embeddings: Vec<_> = embeddings
+            .into_iter()
+            .filter(|e| params.macros.as_ref().is_none_or(|macros| macros.iter().any(|m| m == e.macro_name)))
+            .collect();
+
+        let mut files: Vec<&str> = embeddings.iter().filter_map(|e| e.resolved_path.as_deref()).collect();
+        files.sort();
+        files.dedup();
+        let missing = embeddings.iter().filter(|e| e.resolved_path.is_some() && !e.exists).count();
+        let unresolved = embeddings.iter().filter(|e| e.resolved_path.is_none()).count();
+        let summary = format!(
+            "Found {} embedding(s) of {} distinct file(s); {} missing on disk, {} with a path only known after a build",
+            embeddings.len(),
+            files.len(),
+            missing,
+            unresolved
+        );
+
+        Ok(tool_result(summary, &outputs::EmbeddedFilesOutput {
+            embeddings: embeddings.into_iter().map(|e| outputs::EmbeddedFileOutput {
+                macro_name: e.macro_name.to_string(),
+                argument: e.argument,
+                resolved_path: e.resolved_path,
+                exists: e.exists,
+                file_path: e.file_path,
+                line: e.line,
+                item: e.item,
+                item_kind: e.item_kind.map(str::to_string),
+            }).collect(),
+        }))
+    }
+
+    #[tool(description = "Run rust-analyzer's native diagnostics on a file and list its errors, warnings and hints, \
+            each with its code, message, 0-based range and the labels of any quick fixes on offer. Much faster than \
+            cargo check, but rust-analyzer's own checks only: borrow checking errors, for example, are not reported. \
+            Without `file_path`, checks the whole workspace; pass `budget_ms` to get the files checked so far rather \
+            than wait, with `coverage` telling how many were.",
+        output_schema = output_schema::<outputs::DiagnosticsOutput>())]
+    async fn diagnostics(&self, params: Parameters<DiagnosticsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("diagnostics", &params, |analyzer| {
+            let experimental = params.experimental.unwrap_or(false);
+            let failed = |e: AnalyzerError| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to compute diagnostics: {}", e).into(),
+                data: None,
+            };
+            let (results, coverage) = match &params.file_path {
+                Some(file_path) => {
+                    let results = analyzer.diagnostics(file_path, experimental).map_err(failed)?;
+                    (results.into_iter().map(|d| (None, d)).collect::<Vec<_>>(), None)
+                }
+                None => {
+                    let found = analyzer.workspace_diagnostics(experimental, Budget::from_millis(params.budget_ms))
+                        .map_err(failed)?;
+                    let results = found.files.into_iter()
+                        .flat_map(|(path, diagnostics)| diagnostics.into_iter().map(move |d| (Some(path.clone()), d)))
+                        .collect();
+                    (results, Some(found.coverage))
+                }
+            };
+
+            let errors = results.iter().filter(|(_, d)| d.severity == "error").count();
+            let warnings = results.iter().filter(|(_, d)| d.severity == "warning").count();
+            let output = outputs::DiagnosticsOutput {
+                diagnostics: results.into_iter().map(|(file_path, d)| outputs::DiagnosticOutput {
+                    file_path,
+                    code: d.code,
+                    message: d.message,
+                    severity: d.severity.to_string(),
+                    start_line: d.start_line,
+                    start_column: d.start_column,
+                    end_line: d.end_line,
+                    end_column: d.end_column,
+                    fixes: d.fixes,
+                }).collect(),
+                coverage: coverage.map(coverage_output),
+            };
+
+            let summary = format!(
+                "Found {} error(s), {} warning(s) and {} hint(s) in {}{}",
+                errors,
+                warnings,
+                output.diagnostics.len() - errors - warnings,
+                params.file_path.as_deref().map(|path| format!("'{}'", path)).unwrap_or_else(|| "the workspace".to_string()),
+                coverage.map(coverage_note).unwrap_or_default()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Type-check a code snippet in the context of a module without writing it to disk: the \
+            snippet is placed in memory inside the module of `file_path`, seeing the same names (private items \
+            included) as code written there, and rust-analyzer's diagnostics on it are returned with lines relative \
+            to the snippet. Items are checked as they are; statements are checked as the body of a function.",
+        output_schema = output_schema::<outputs::CheckSnippetOutput>())]
+    async fn check_snippet(&self, params: Parameters<CheckSnippetParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let mut analyzer = self.analyzer.lock().unwrap();
+        let check = analyzer.check_snippet(&params.snippet, &params.file_path, params.experimental.unwrap_or(false))
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to check snippet: {}", e).into(),
+                data: None,
+            })?;
+
+        let errors = check.diagnostics.iter().filter(|d| d.severity == "error").count();
+        let output = outputs::CheckSnippetOutput {
+            diagnostics: check.diagnostics.into_iter().map(|d| outputs::DiagnosticOutput {
+                file_path: None,
+                code: d.code,
+                message: d.message,
+                severity: d.severity.to_string(),
+                start_line: d.start_line,
+                start_column: d.start_column,
+                end_line: d.end_line,
+                end_column: d.end_column,
+                fixes: d.fixes,
+            }).collect(),
+            wrapped_in_function: check.wrapped_in_function,
+        };
+
+        let summary = format!(
+            "Snippet has {} error(s) and {} other diagnostic(s){}",
+            errors,
+            output.diagnostics.len() - errors,
+            if output.wrapped_in_function { " (checked as a function body)" } else { "" }
+        );
+
+        Ok(tool_result(summary, &output))
+    }
+
+    #[tool(description = "Report each workspace crate's edition and declared rust-version, and the syntax and standard \
+            library items it uses that raise its minimum supported Rust version (let-else, async fn in traits, OnceLock, \
+            Option::is_some_and, ...), with the version each needs and every location using it. Pass `since` to see \
+            only what breaks an MSRV. Checks a fixed table of notable stabilizations, so the result is a lower bound.",
+        output_schema = output_schema::<outputs::MsrvReportOutput>())]
+    async fn msrv_report(&self, params: Parameters<MsrvReportParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("msrv_report", &params, |analyzer| {
+            let mut reports = analyzer.msrv_report(params.since.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to check MSRV: {}", e).into(),
+                    data: None,
+                })?;
+            if let Some(crate_name) = &params.crate_name {
+                reports.retain(|report| &report.crate_name == crate_name);
+            }
+
+            let output = outputs::MsrvReportOutput {
+                crates: reports.iter().map(|report| outputs::CrateMsrvOutput {
+                    crate_name: report.crate_name.clone(),
+                    edition: report.edition.clone(),
+                    rust_version: report.rust_version.clone(),
+                    required: report.required.clone(),
+                    features: report.features.iter().map(|feature| outputs::MsrvFeatureOutput {
+                        feature: feature.feature.clone(),
+                        version: feature.version.clone(),
+                        uses: feature.uses.iter().map(|(file_path, line)| outputs::MsrvUseOutput {
+                            file_path: file_path.clone(),
+                            line: *line,
+                        }).collect(),
+                    }).collect(),
+                }).collect(),
+            };
+
+            let summary = reports
+                .iter()
+                .map(|report| format!(
+                    "{} (edition {}): needs at least Rust {}",
+                    report.crate_name,
+                    report.edition,
+                    report.required.as_deref().unwrap_or("1.56")
+                ))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Find traits used as `dyn Trait` in the workspace that are not object-safe (dyn-compatible), \
+            pointing at each offending method or item: generic methods, methods returning Self or lacking a self \
+            receiver, associated consts, GATs, `Self: Sized` bounds. Pass `trait_name` to check one trait before boxing \
+            it, or after adding a method to a boxed trait.",
+        output_schema = output_schema::<outputs::ObjectSafetyOutput>())]
+    async fn object_safety(&self, params: Parameters<ObjectSafetyParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("object_safety", &params, |analyzer| {
+            let traits = match analyzer.object_safety(params.trait_name.as_deref()) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::ObjectSafetyOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to check object safety: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let output = outputs::ObjectSafetyOutput {
+                traits: traits.iter().map(|t| outputs::UnsafeTraitOutput {
+                    trait_path: t.trait_path.clone(),
+                    file_path: t.file_path.clone(),
+                    line: t.line,
+                    violations: t.violations.iter().map(|v| outputs::SafetyViolationOutput {
+                        item: v.item.clone(),
+                        reason: v.reason.clone(),
+                        file_path: v.file_path.clone(),
+                        line: v.line,
+                    }).collect(),
+                    dyn_uses: t.dyn_uses.iter().map(|(file_path, line)| outputs::DynUseOutput {
+                        file_path: file_path.clone(),
+                        line: *line,
+                    }).collect(),
+                }).collect(),
+                candidates: Vec::new(),
+            };
+
+            let summary = match &params.trait_name {
+                Some(name) if traits.is_empty() => format!("'{}' is object-safe", name),
+                _ => format!("Found {} trait(s) that are not object-safe", traits.len()),
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Show the signature of the call around a file position (0-based line and column), as an editor \
+            does while typing arguments: the callee's signature and documentation, each parameter's name and type, and \
+            which parameter the position falls in. Works for function, method and constructor calls.",
+        output_schema = output_schema::<outputs::SignatureHelpOutput>())]
+    async fn signature_help(&self, params: Parameters<SignatureHelpParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("signature_help", &params, |analyzer| {
+            let help = analyzer.signature_help(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to get signature help: {}", e).into(),
+                    data: None,
+                })?;
+
+            let Some(help) = help else {
+                let summary = format!("No call around {}:{}", params.line, params.column);
+                return Ok(tool_result(summary, &outputs::SignatureHelpOutput { signature: None }));
+            };
+
+            let summary = match help.active_parameter.and_then(|i| help.parameters.get(i)) {
+                Some(param) => format!("{}\nActive parameter: {}", help.signature, param.label),
+                None => help.signature.clone(),
+            };
+            Ok(tool_result(summary, &outputs::SignatureHelpOutput {
+                signature: Some(outputs::SignatureOutput {
+                    signature: help.signature,
+                    documentation: help.documentation,
+                    parameters: help.parameters.into_iter().map(|param| outputs::SignatureParameterOutput {
+                        label: param.label,
+                        name: param.name,
+                        ty: param.ty,
+                    }).collect(),
+                    active_parameter: help.active_parameter,
+                }),
+            }))
+        })
+    }
+
+    #[tool(description = "List the workspace types with custom destruction code: those implementing Drop, and those \
+            containing such a type in a field, directly or transitively through other types, Box, Option, tuples and \
+            arrays. Fields are listed in declaration (drop) order with the Drop types they reach. For reasoning about \
+            resource cleanup, lock guards and drop-order bugs.",
+        output_schema = output_schema::<outputs::DropTypesOutput>())]
+    async fn drop_types(&self, params: Parameters<DropTypesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("drop_types", &params, |analyzer| {
+            let types = analyzer.drop_types(params.include_library.unwrap_or(false))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find Drop types: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::DropTypesOutput {
+                types: types.iter().map(|ty| outputs::DropTypeOutput {
+                    path: ty.path.clone(),
+                    kind: ty.kind.to_string(),
+                    file_path: ty.file_path.clone(),
+                    start_line: ty.start_line,
+                    end_line: ty.end_line,
+                    drop_impl: ty.drop_impl.as_ref().map(|(file_path, line)| outputs::DropImplOutput {
+                        file_path: file_path.clone(),
+                        line: *line,
+                    }),
+                    fields: ty.fields.iter().map(|field| outputs::DropFieldOutput {
+                        name: field.name.clone(),
+                        ty: field.ty.clone(),
+                        drops: field.drops.clone(),
+                    }).collect(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} type(s) with destruction code, {} implementing Drop themselves",
+                types.len(),
+                types.iter().filter(|ty| ty.drop_impl.is_some()).count()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List rust-analyzer's inlay hints for a file or line range, as an editor draws them inline: \
+            inferred types of bindings, closure return types, parameter names at call sites, types along method chains \
+            and elided lifetimes. Each hint has its 0-based position, kind and text. Spells out what type inference \
+            decided where the source leaves it implicit.",
+        output_schema = output_schema::<outputs::InlayHintsOutput>())]
+    async fn inlay_hints(&self, params: Parameters<InlayHintsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        if params.end_line.is_some() && params.start_line.is_none() {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: "'end_line' needs 'start_line'".into(),
+                data: None,
+            });
+        }
+
+        self.cached("inlay_hints", &params, |analyzer| {
+            let lines = params.start_line.map(|start| (start, params.end_line.unwrap_or(start)));
+            let hints = analyzer.inlay_hints(&params.file_path, lines)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to get inlay hints: {}", e).into(),
+                    data: None,
+                })?;
+
+            let summary = format!("Found {} inlay hint(s) in '{}'", hints.len(), params.file_path);
+            let output = outputs::InlayHintsOutput {
+                hints: hints.into_iter().map(|hint| outputs::InlayHintOutput {
+                    line: hint.line,
+                    column: hint.column,
+                    kind: hint.kind,
+                    label: hint.label,
+                }).collect(),
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Report, per derive macro, how many workspace types derive it and where, most used first, \
+            with each type's size in bytes when its layout is known. Derives are grouped by the macro they resolve to, \
+            and those under cfg_attr carry their condition. Filter by derive and by minimum size, e.g. all Clone types \
+            of 256 bytes or more. For migration planning such as changing serde derive strategy.",
+        output_schema = output_schema::<outputs::DeriveUsageOutput>())]
+    async fn derive_usage(&self, params: Parameters<DeriveUsageParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("derive_usage", &params, |analyzer| {
+            let usages = analyzer.derive_usage(params.derive.as_deref(), params.min_size)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to count derives: {}", e).into(),
+                    data: None,
+                })?;
+
+            let summary = usages
+                .iter()
+                .map(|usage| format!("{}: {} type(s)", usage.derive, usage.types.len()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let output = outputs::DeriveUsageOutput {
+                derives: usages.into_iter().map(|usage| outputs::DeriveOutput {
+                    derive: usage.derive,
+                    name: usage.name,
+                    count: usage.types.len(),
+                    types: usage.types.into_iter().map(|ty| outputs::DerivingTypeOutput {
+                        path: ty.path,
+                        file_path: ty.file_path,
+                        line: ty.line,
+                        size: ty.size,
+                        cfg: ty.cfg,
+                    }).collect(),
+                }).collect(),
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    /// Report the public types lacking Debug or Display impls
+    #[tool(description = "Report, per workspace crate, the public types lacking a Debug and/or Display impl (derived or \
+            hand-written), with counts of how many have each. Such gaps block logging with {:?} and error reporting. \
+            Filter by `crate_name` and by `module`, and with `missing` to one of the two traits.",
+        output_schema = output_schema::<outputs::FmtCoverageOutput>())]
+    async fn fmt_coverage(&self, params: Parameters<FmtCoverageParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let missing = params.missing.as_deref().map(str::to_ascii_lowercase);
+        if let Some(unknown) = missing.as_deref().filter(|m| !matches!(*m, "debug" | "display")) {
+            return Err(McpError {
+                code: ErrorCode(-1),
+                message: format!("Unknown trait '{}', expected debug or display", unknown).into(),
+                data: None,
+            });
+        }
+
+        self.cached("fmt_coverage", &params, |analyzer| {
+            let crates = analyzer.fmt_coverage(params.crate_name.as_deref(), params.module.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to check Debug and Display coverage: {}", e).into(),
+                    data: None,
+                })?;
+
+            let summary = crates
+                .iter()
+                .map(|c| format!("{}: {} public type(s), {} with Debug, {} with Display", c.crate_name, c.types, c.with_debug, c.with_display))
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Ok(tool_result(summary, &outputs::FmtCoverageOutput {
+                crates: crates.into_iter().map(|c| outputs::CrateFmtCoverageOutput {
+                    crate_name: c.crate_name,
+                    types: c.types,
+                    with_debug: c.with_debug,
+                    with_display: c.with_display,
+                    gaps: c.gaps
+                        .into_iter()
+                        .filter(|gap| match missing.as_deref() {
+                            Some("debug") => !gap.has_debug,
+                            Some("display") => !gap.has_display,
+                            _ => true,
+                        })
+                        .map(|gap| outputs::FmtGapOutput {
+                            path: gap.path,
+                            kind: gap.kind.to_string(),
+                            file_path: gap.file_path,
+                            line: gap.line,
+                            has_debug: gap.has_debug,
+                            has_display: gap.has_display,
+                        })
+                        .collect(),
+                }).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "Classify the identifier or token at a file position (0-based line and column) the way semantic \
+            highlighting does: whether it is a local variable, parameter, field, const generic, macro, method, type \
+            parameter, etc., with modifiers such as mutable, declaration, unsafe, library or trait.",
+        output_schema = output_schema::<outputs::ClassifyTokenOutput>())]
+    async fn classify_token(&self, params: Parameters<ClassifyTokenParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("classify_token", &params, |analyzer| {
+            let class = analyzer.classify_token(&params.file_path, params.line, params.column)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to classify token: {}", e).into(),
+                    data: None,
+                })?;
+
+            let Some(class) = class else {
+                let summary = format!("No classified token at {}:{}", params.line, params.column);
+                return Ok(tool_result(summary, &outputs::ClassifyTokenOutput { token: None }));
+            };
+
+            let summary = if class.modifiers.is_empty() {
+                format!("'{}' is a {}", class.text, class.tag)
+            } else {
+                format!("'{}' is a {} ({})", class.text, class.tag, class.modifiers.join(", "))
+            };
+            Ok(tool_result(summary, &outputs::ClassifyTokenOutput {
+                token: Some(outputs::TokenClassOutput {
+                    text: class.text,
+                    tag: class.tag,
+                    modifiers: class.modifiers,
+                    line: class.line,
+                    start_column: class.start_column,
+                    end_column: class.end_column,
+                }),
+            }))
+        })
+    }
+
+    #[tool(description = "Preview renaming a symbol (local, field, function, type, module, ...) at a position: \
+            returns every edit rust-analyzer's rename would make across the workspace as file, range and new text, \
+            plus file moves for renamed modules. Nothing is written to disk.",
+        output_schema = output_schema::<outputs::PreviewRenameOutput>())]
+    async fn preview_rename(&self, params: Parameters<PreviewRenameParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("preview_rename", &params, |analyzer| {
+            let plan = analyzer.preview_rename(&params.file_path, params.line, params.column, &params.new_name)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to plan rename: {}", e).into(),
+                    data: None,
+                })?;
+
+            let edits: Vec<_> = plan.edits.iter().map(|edit| outputs::TextEditOutput {
+                file_path: edit.file_path.clone(),
+                start_line: edit.start_line,
+                start_column: edit.start_column,
+                end_line: edit.end_line,
+                end_column: edit.end_column,
+                new_text: edit.new_text.clone(),
+            }).collect();
+
+            let mut files: Vec<&str> = plan.edits.iter().map(|e| e.file_path.as_str()).collect();
+            files.dedup();
+            let summary = format!(
+                "Renaming '{}' to '{}' takes {} edit(s) in {} file(s) and {} file move(s)",
+                plan.old_name,
+                params.new_name,
+                plan.edits.len(),
+                files.len(),
+                plan.file_moves.len()
+            );
+
+            Ok(tool_result(summary, &outputs::PreviewRenameOutput {
+                old_name: plan.old_name,
+                edits,
+                file_moves: plan.file_moves.into_iter().map(|m| outputs::FileMoveOutput { from: m.from, to: m.to }).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "After moving a symbol or module by hand, rewrite its old path to the new one in every \
+            `use` statement and qualified path across the workspace. Returns the text edits, splitting nested \
+            `use` leaves into their own declarations, and warnings for relative paths to check by hand. \
+            Nothing is written to disk.",
+        output_schema = output_schema::<outputs::RewritePathsOutput>())]
+    async fn rewrite_paths(&self, params: Parameters<RewritePathsParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("rewrite_paths", &params, |analyzer| {
+            let plan = analyzer.rewrite_paths(&params.old_path, &params.new_path)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to plan path rewrite: {}", e).into(),
+                    data: None,
+                })?;
+
+            let edits: Vec<_> = plan.edits.iter().map(|edit| outputs::TextEditOutput {
+                file_path: edit.file_path.clone(),
+                start_line: edit.start_line,
+                start_column: edit.start_column,
+                end_line: edit.end_line,
+                end_column: edit.end_column,
+                new_text: edit.new_text.clone(),
+            }).collect();
+
+            let mut files: Vec<&str> = plan.edits.iter().map(|e| e.file_path.as_str()).collect();
+            files.dedup();
+            let summary = format!(
+                "Rewriting '{}' to '{}' takes {} edit(s) in {} file(s) with {} warning(s)",
+                params.old_path,
+                params.new_path,
+                plan.edits.len(),
+                files.len(),
+                plan.warnings.len()
+            );
+
+            Ok(tool_result(summary, &outputs::RewritePathsOutput { edits, warnings: plan.warnings }))
+        })
+    }
+
+    #[tool(description = "Return the module hierarchy of the workspace crates (or one crate): every module's name, \
+            path, the file it lives in with its line span, whether it is inline or public, and its submodules, \
+            nested. Use it to orient yourself in a project without reading every mod.rs.",
+        output_schema = output_schema::<outputs::ModuleTreeOutput>())]
+    async fn module_tree(&self, params: Parameters<ModuleTreeParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("module_tree", &params, |analyzer| {
+            let trees = analyzer.module_tree(params.crate_name.as_deref())
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to build module tree: {}", e).into(),
+                    data: None,
+                })?;
+
+            fn count(node: &ModuleNode) -> usize {
+                1 + node.children.iter().map(count).sum::<usize>()
+            }
+            let summary = format!(
+                "Found {} module(s) in {} crate(s)",
+                trees.iter().map(count).sum::<usize>(),
+                trees.len()
+            );
+
+            Ok(tool_result(summary, &outputs::ModuleTreeOutput {
+                crates: trees.iter().map(module_node_output).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "Return the loaded crate graph: the workspace crates with their version, edition, origin, \
+            root file and dependency edges (under the names they are imported as). Optionally includes the library \
+            crates, down to the standard library. Gives an architectural overview of the project.",
+        output_schema = output_schema::<outputs::CrateGraphOutput>())]
+    async fn crate_graph(&self, params: Parameters<CrateGraphParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("crate_graph", &params, |analyzer| {
+            let crates = analyzer.crate_graph(params.include_library.unwrap_or(false))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to read crate graph: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::CrateGraphOutput {
+                crates: crates.iter().map(|node| outputs::CrateNodeOutput {
+                    name: node.name.clone(),
+                    version: node.version.clone(),
+                    edition: node.edition.clone(),
+                    origin: node.origin.to_string(),
+                    root_file: node.root_file.clone(),
+                    dependencies: node.dependencies.iter().map(|dep| outputs::CrateDependencyOutput {
+                        name: dep.name.clone(),
+                        crate_name: dep.crate_name.clone(),
+                        version: dep.version.clone(),
+                    }).collect(),
+                }).collect(),
+            };
+
+            let workspace = crates.iter().filter(|node| node.origin == "workspace").count();
+            let summary = format!(
+                "Found {} crate(s), {} of them in the workspace, with {} dependency edge(s)",
+                crates.len(),
+                workspace,
+                crates.iter().map(|node| node.dependencies.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Report the cfg options each crate was loaded with: its enabled Cargo features, the \
+            declared features left disabled, cfg flags such as `test` or `unix`, and key-value options such as \
+            `target_os`. Explains symbols that are missing because they sit behind a disabled `#[cfg(...)]`.",
+        output_schema = output_schema::<outputs::CrateCfgOutput>())]
+    async fn crate_cfg(&self, params: Parameters<CrateCfgParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("crate_cfg", &params, |analyzer| {
+            let crates = analyzer.crate_cfg(params.crate_name.as_deref(), params.include_library.unwrap_or(false))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to read crate cfg: {}", e).into(),
+                    data: None,
+                })?;
+
+            let summary = crates
+                .iter()
+                .map(|c| {
+                    let features = if c.enabled_features.is_empty() { "none".to_string() } else { c.enabled_features.join(", ") };
+                    format!("{}: features {}; {} disabled", c.crate_name, features, c.disabled_features.len())
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            Ok(tool_result(summary, &outputs::CrateCfgOutput {
+                crates: crates.into_iter().map(|c| outputs::CrateCfgEntryOutput {
+                    crate_name: c.crate_name,
+                    version: c.version,
+                    workspace: c.workspace,
+                    enabled_features: c.enabled_features,
+                    disabled_features: c.disabled_features,
+                    flags: c.flags,
+                    key_values: c.key_values,
+                }).collect(),
+            }))
+        })
+    }
+
+    #[tool(description = "List the workspace crates that depend on a crate (workspace or library), directly or \
+            through other crates, each with the chain of crates leading to it. Check it before changing a shared \
+            crate to see what else may break.",
+        output_schema = output_schema::<outputs::ReverseDependenciesOutput>())]
+    async fn reverse_dependencies(&self, params: Parameters<ReverseDependenciesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("reverse_dependencies", &params, |analyzer| {
+            let dependents = analyzer.reverse_dependencies(&params.crate_name, params.transitive.unwrap_or(true))
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to look up reverse dependencies: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::ReverseDependenciesOutput {
+                dependents: dependents.iter().map(|dep| outputs::DependentOutput {
+                    name: dep.name.clone(),
+                    root_file: dep.root_file.clone(),
+                    direct: dep.direct,
+                    path: dep.path.clone(),
+                }).collect(),
+            };
+
+            let direct = dependents.iter().filter(|dep| dep.direct).count();
+            let summary = format!(
+                "{} workspace crate(s) depend on {} ({} directly)",
+                dependents.len(),
+                params.crate_name,
+                direct
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List the public items of a whole crate (workspace crate or dependency), grouped by the \
+            public module declaring them, for getting oriented before drilling into files. Paginated: pass \
+            `next_offset` back as `offset` to get the next page.",
+        output_schema = output_schema::<outputs::EnumerateCrateOutput>())]
+    async fn enumerate_crate(&self, params: Parameters<EnumerateCrateParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("enumerate_crate", &params, |analyzer| {
+            let offset = params.offset.unwrap_or(0);
+            let limit = params.limit.unwrap_or(ENUMERATE_CRATE_PAGE_SIZE);
+            let page = analyzer.enumerate_crate(&params.crate_name, params.version.as_deref(), offset, limit)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to enumerate crate: {}", e).into(),
+                    data: None,
+                })?;
+
+            let mut modules: Vec<outputs::CrateModuleOutput> = Vec::new();
+            for item in &page.items {
+                let entry = outputs::CrateItemOutput { name: item.name.clone(), kind: item.kind.to_string() };
+                match modules.last_mut() {
+                    Some(module) if module.module == item.module => module.items.push(entry),
+                    _ => modules.push(outputs::CrateModuleOutput { module: item.module.clone(), items: vec![entry] }),
+                }
+            }
+            let end = offset + page.items.len();
+            let output = outputs::EnumerateCrateOutput {
+                crate_name: page.crate_name.clone(),
+                version: page.version.clone(),
+                total: page.total,
+                next_offset: (end < page.total).then_some(end),
+                modules,
+            };
+
+            let summary = format!(
+                "Items {}-{} of {} public item(s) in {}{}",
+                offset.min(end),
+                end,
+                page.total,
+                page.crate_name,
+                page.version.as_deref().map(|version| format!(" {}", version)).unwrap_or_default()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List the Cargo workspace members: each package's name, version, edition, manifest path and \
+            targets (lib, bin, test, bench, example, ...) with their root files.",
+        output_schema = output_schema::<outputs::WorkspaceMembersOutput>())]
+    async fn workspace_members(&self) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        self.cached("workspace_members", &(), |analyzer| {
+            let members = analyzer.workspace_members()
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list workspace members: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::WorkspaceMembersOutput {
+                members: members.iter().map(|member| outputs::WorkspaceMemberOutput {
+                    name: member.name.clone(),
+                    version: member.version.clone(),
+                    edition: member.edition.clone(),
+                    manifest_path: member.manifest_path.clone(),
+                    targets: member.targets.iter().map(|target| outputs::MemberTargetOutput {
+                        name: target.name.clone(),
+                        kind: target.kind.to_string(),
+                        root: target.root.clone(),
+                    }).collect(),
+                }).collect(),
+            };
+
+            let summary = format!(
+                "Found {} workspace member(s) with {} target(s)",
+                members.len(),
+                members.iter().map(|member| member.targets.len()).sum::<usize>()
+            );
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "List the direct dependencies of a workspace crate (package): each with the name it is \
+            imported under, the package behind it, its resolved version, whether it is a normal, dev or build \
+            dependency, and its source: workspace, crates.io, another registry, git or path. \
+            Check it before suggesting edits to Cargo.toml.",
+        output_schema = output_schema::<outputs::CrateDependenciesOutput>())]
+    async fn crate_dependencies(&self, params: Parameters<CrateDependenciesParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("crate_dependencies", &params, |analyzer| {
+            let dependencies = analyzer.package_dependencies(&params.crate_name)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to list dependencies: {}", e).into(),
+                    data: None,
+                })?;
+
+            let output = outputs::CrateDependenciesOutput {
+                dependencies: dependencies.iter().map(|dep| outputs::PackageDependencyOutput {
+                    name: dep.name.clone(),
+                    package: dep.package.clone(),
+                    version: dep.version.clone(),
+                    kind: dep.kind.to_string(),
+                    source: dep.source.to_string(),
+                }).collect(),
+            };
+
+            let summary = format!("'{}' has {} direct dependency(ies)", params.crate_name, dependencies.len());
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Show the project fingerprint stamped on persisted caches: the toolchain version, the \
+            rust-analyzer crate version, the Cargo.lock hash and the enabled workspace features. Persisted state built \
+            under a different fingerprint is discarded and rebuilt; the status of the persisted reference index says \
+            whether it matches.",
+        output_schema = output_schema::<outputs::ProjectFingerprintOutput>())]
+    async fn project_fingerprint(&self) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        // Not cached: the persisted index can be written without the revision changing
+        let analyzer = self.analyzer.lock().unwrap();
+        let fingerprint = analyzer.project_fingerprint()
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to compute project fingerprint: {}", e).into(),
+                data: None,
+            })?;
+
+        let output = outputs::ProjectFingerprintOutput {
+            digest: format!("{:016x}", fingerprint.digest()),
+            toolchain: fingerprint.toolchain.clone(),
+            analyzer_version: fingerprint.analyzer_version.clone(),
+            lockfile_hash: fingerprint.lockfile_hash.clone(),
+            features: fingerprint.features.clone(),
+            reference_index: analyzer.reference_index_status().as_str().to_string(),
+        };
+
+        let summary = format!(
+            "Fingerprint {} (toolchain {}, rust-analyzer {}, {} feature(s)); reference index is {}",
+            output.digest,
+            output.toolchain.as_deref().unwrap_or("unknown"),
+            output.analyzer_version,
+            output.features.len(),
+            output.reference_index
+        );
+
+        Ok(tool_result(summary, &output))
+    }
+
+    #[tool(description = "Report what this server supports, to feature-detect instead of failing at call time: the \
+            rust-analyzer (ra_ap) crate versions compiled in, whether the optional subsystems are on (proc-macro \
+            expansion, the file watcher, the library cache and the persisted reference index), and which tools are \
+            available. Answers while the server is still initializing; subsystems depending on the project are then unknown.",
+        output_schema = output_schema::<outputs::CapabilitiesOutput>())]
+    async fn capabilities(&self) -> Result<CallToolResult, McpError> {
+        // Project-dependent subsystems are unknown until the project is loaded
+        let (state, unknown) = match &*self.init_state.lock().unwrap() {
+            InitState::InProgress => ("initializing", Some("initializing".to_string())),
+            InitState::Failed(err) => ("failed", Some(format!("initialization failed: {}", err))),
+            InitState::Ready => ("ready", None),
+        };
+
+        let subsystem = |name: &str, enabled: Option<bool>, detail: Option<String>| outputs::SubsystemOutput {
+            name: name.to_string(),
+            enabled,
+            detail,
+        };
+        let mut subsystems = Vec::new();
+        match unknown {
+            None => {
+                let analyzer = self.analyzer.lock().unwrap();
+                let status = analyzer.subsystems();
+                let watching = status.watching && *self.watcher_started.lock().unwrap();
+                subsystems.push(subsystem("proc_macros", Some(status.proc_macros_unavailable.is_none()), status.proc_macros_unavailable));
+                subsystems.push(subsystem("file_watcher", Some(watching), None));
+                subsystems.push(subsystem("library_cache", Some(status.library_cache), status.library_cache_dir));
+                subsystems.push(subsystem(
+                    "reference_index_persistence",
+                    Some(status.reference_index.exists()),
+                    Some(status.reference_index.as_str().to_string()),
+                ));
+            }
+            Some(reason) => {
+                for name in ["proc_macros", "file_watcher", "library_cache", "reference_index_persistence"] {
+                    subsystems.push(subsystem(name, None, Some(reason.clone())));
+                }
+            }
+        }
+        subsystems.push(subsystem(
+            "view_mir",
+            Some(self.enable_mir),
+            (!self.enable_mir).then(|| "start the server with CRATOGRAPHER_ENABLE_MIR=1".to_string()),
+        ));
+
+        let disabled_tools: Vec<String> =
+            if self.enable_mir { Vec::new() } else { vec!["view_mir".to_string()] };
+        let mut tools: Vec<String> = self.tool_router.list_all().into_iter()
+            .map(|tool| tool.name.to_string())
+            .filter(|name| !disabled_tools.contains(name))
+            .collect();
+        tools.sort();
+
+        let output = outputs::CapabilitiesOutput {
+            ra_ap_version: RA_AP_VERSION.to_string(),
+            ra_ap_crates: analyzer::ra_ap_crates().into_iter().map(|(name, version)| outputs::CrateVersionOutput {
+                name: name.to_string(),
+                version: version.to_string(),
+            }).collect(),
+            state: state.to_string(),
+            subsystems,
+            tools,
+            disabled_tools,
+        };
+        let enabled: Vec<&str> = output.subsystems.iter()
+            .filter(|s| s.enabled == Some(true))
+            .map(|s| s.name.as_str())
+            .collect();
+        let summary = format!(
+            "rust-analyzer {} ({} ra_ap crate(s)), server {}; {} tool(s) available; enabled: {}",
+            output.ra_ap_version,
+            output.ra_ap_crates.len(),
+            output.state,
+            output.tools.len(),
+            if enabled.is_empty() { "none".to_string() } else { enabled.join(", ") }
+        );
+
+        Ok(tool_result(summary, &output))
+    }
+
+    #[tool(description = "Register a scratch directory of candidate .rs files to analyze against a workspace crate \
+            without writing them into the tree. Each file is mounted in memory as a module of the crate \
+            (crate::__scratch_<file name>), so it resolves the crate's items and dependencies; every file-based \
+            tool (diagnostics, hover, goto_definition, ...) then accepts the scratch file's own path. \
+            Scratch directories aren't watched: register again after editing the files.",
+        output_schema = output_schema::<outputs::ScratchRootOutput>())]
+    async fn add_scratch_root(&self, params: Parameters<AddScratchRootParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let mut analyzer = self.analyzer.lock().unwrap();
+        let files = analyzer.add_scratch_root(&params.directory, &params.crate_name)
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to add scratch root: {}", e).into(),
+                data: None,
+            })?;
+
+        let summary = format!("Mounted {} scratch file(s) into {}", files.len(), params.crate_name);
+        Ok(tool_result(summary, &scratch_root_output(&files)))
+    }
+
+    #[tool(description = "Unregister a scratch directory added with add_scratch_root, unmounting its files.",
+        output_schema = output_schema::<outputs::ScratchRootOutput>())]
+    async fn remove_scratch_root(&self, params: Parameters<RemoveScratchRootParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let mut analyzer = self.analyzer.lock().unwrap();
+        let files = analyzer.remove_scratch_root(&params.directory)
+            .map_err(|e| McpError {
+                code: ErrorCode(-1),
+                message: format!("Failed to remove scratch root: {}", e).into(),
+                data: None,
+            })?;
+
+        let summary = format!("Unmounted {} scratch file(s)", files.len());
+        Ok(tool_result(summary, &scratch_root_output(&files)))
+    }
+
+    /// Find the tests that call a function, directly or through other functions
+    #[tool(description = "Find the test functions (#[test], #[tokio::test] and the like) that call a function, directly \
+            or transitively through other functions up to `depth` calls away (default 3), by walking the call hierarchy \
+            upwards. Each test comes with the chain of calls reaching the function and the cargo command running just \
+            that test, so the tests affected by a change can be run first. Calls through trait objects or function \
+            pointers aren't followed. Pass `budget_ms` to bound the walk.",
+        output_schema = output_schema::<outputs::TestsForFunctionOutput>())]
+    async fn tests_for_function(&self, params: Parameters<TestsForFunctionParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let run = |analyzer: &Analyzer| -> Result<CallToolResult, McpError> {
+            let budget = Budget::from_millis(params.budget_ms);
+            let found = match analyzer.covering_tests(&params.name, params.depth.unwrap_or(3), budget) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::TestsForFunctionOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
+                }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to find tests: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let direct = found.tests.iter().filter(|t| t.depth == 1).count();
+            let mut summary = format!(
+                "{} test(s) call {} ({} directly){}",
+                found.tests.len(),
+                found.function,
+                direct,
+                if found.truncated { "; search truncated" } else { "" }
+            );
+            for test in &found.tests {
+                summary.push_str(&format!("\n  {} ({}:{}) via {}", test.name, test.file_path, test.start_line, test.chain.join(" -> ")));
+            }
+
+            Ok(tool_result(summary, &outputs::TestsForFunctionOutput {
+                function: found.function,
+                tests: found.tests.into_iter().map(|t| outputs::CoveringTestOutput {
+                    command_line: t.runnable.as_ref().and_then(|r| r.command_line()),
+                    command: t.runnable.and_then(|r| r.command),
+                    name: t.name,
+                    file_path: t.file_path,
+                    start_line: t.start_line,
+                    end_line: t.end_line,
+                    depth: t.depth,
+                    chain: t.chain,
+                }).collect(),
+                truncated: found.truncated,
+                candidates: Vec::new(),
+            }))
+        };
+
+        // A walk cut short by the budget isn't kept, so a retry can get further
+        if params.budget_ms.is_some() {
+            return run(&self.analyzer.lock().unwrap());
+        }
+        self.cached("tests_for_function", &params, run)
+    }
 
-            // Perform a warm-up query to force everything to load
-            {
-                let analyzer = analyzer_clone.lock().unwrap();
-                let warmup_options = SearchOptions {
-                    mode: SearchMode::Exact,
-                    include_library: true,
-                    filter: SymbolFilter::Types,
-                };
-                if let Err(e) = analyzer.find_symbol("HashMap", &warmup_options) {
-                    eprintln!("Warning: Warm-up query failed: {}", e);
+    /// Return the source text of a symbol's definition
+    #[tool(description = "Return the exact source text of a symbol's definition, doc comments and attributes \
+            included, with its file and lines; optionally with `context_lines` lines on either side. Takes a name, \
+            qualified path or symbol_id (e.g. the moniker of a find_symbol result), so no separate file read is needed.",
+        output_schema = output_schema::<outputs::GetSourceOutput>())]
+    async fn get_source(&self, params: Parameters<GetSourceParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        self.cached("get_source", &params, |analyzer| {
+            let source = match analyzer.symbol_source(&params.name, params.context_lines.unwrap_or(0)) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::GetSourceOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
                 }
-            }
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to get source: {}", e).into(),
+                    data: None,
+                })?,
+            };
 
-            // Spawn file watcher task with the receiver
-            match spawn_file_watcher(analyzer_clone.clone(), receiver) {
-                Ok(_) => eprintln!("File watcher initialized"),
-                Err(e) => eprintln!("Warning: Could not start file watcher: {}", e),
+            let mut summary = format!("{} ({}:{}-{})", source.path, source.file_path, source.start_line, source.end_line);
+            for text in source.context_before.iter().chain([&source.source]).chain(&source.context_after) {
+                summary.push('\n');
+                summary.push_str(text);
             }
 
-            // Mark as ready
-            *state_clone.lock().unwrap() = InitState::Ready;
-            eprintln!("Background initialization complete - server ready");
-        });
-
-        Ok(Self {
-            tool_router: Self::tool_router(),
-            analyzer,
-            init_state,
+            Ok(tool_result(summary, &outputs::GetSourceOutput {
+                path: source.path,
+                file_path: source.file_path,
+                start_line: source.start_line,
+                end_line: source.end_line,
+                source: source.source,
+                context_before: source.context_before,
+                context_after: source.context_after,
+                candidates: Vec::new(),
+            }))
         })
     }
 
-    /// Wait for initialization to complete (useful for tests)
-    #[allow(dead_code)]
-    async fn wait_for_ready(&self) -> Result<(), String> {
-        use tokio::time::{sleep, Duration};
+    /// Return the signature of a symbol without its body
+    #[tool(description = "Return only the signature of an item, as written: a function's signature with generics and \
+            where clause but no body, a struct's, enum's or trait's header without fields, variants or items, an \
+            impl's header, a const's type without its value. Takes a name, qualified path or symbol_id.",
+        output_schema = output_schema::<outputs::GetSignatureOutput>())]
+    async fn get_signature(&self, params: Parameters<GetSignatureParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
 
-        for _ in 0..100 {  // Wait up to 10 seconds
-            match &*self.init_state.lock().unwrap() {
-                InitState::Ready => return Ok(()),
-                InitState::Failed(err) => return Err(err.clone()),
-                InitState::InProgress => {
-                    sleep(Duration::from_millis(100)).await;
+        let params = params.0;
+
+        self.cached("get_signature", &params, |analyzer| {
+            let signature = match analyzer.symbol_signature(&params.name) {
+                Err(AnalyzerError::Ambiguous { name, candidates }) => {
+                    return Ok(ambiguous_result(&name, &candidates, |candidates| outputs::GetSignatureOutput {
+                        candidates,
+                        ..Default::default()
+                    }));
                 }
-            }
-        }
-        Err("Initialization timeout".to_string())
+                result => result.map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to get signature: {}", e).into(),
+                    data: None,
+                })?,
+            };
+
+            let summary = format!("{} ({}:{})\n{}", signature.path, signature.file_path, signature.line, signature.signature);
+
+            Ok(tool_result(summary, &outputs::GetSignatureOutput {
+                path: signature.path,
+                file_path: signature.file_path,
+                line: signature.line,
+                signature: signature.signature,
+                candidates: Vec::new(),
+            }))
+        })
     }
 
-    /// Check if initialization is complete and return appropriate error if not
-    fn check_init_state(&self) -> Result<(), McpError> {
-        match &*self.init_state.lock().unwrap() {
-            InitState::InProgress => {
-                Err(McpError {
-                    code: ErrorCode(-32001),
-                    message: "Server is still initializing. Please try again in a moment.".into(),
-                    data: None,
-                })
-            }
-            InitState::Failed(err) => {
-                Err(McpError {
-                    code: ErrorCode(-32002),
-                    message: format!("Server initialization failed: {}", err).into(),
+    /// Resolve a stored moniker back to its symbol
+    #[tool(description = "Resolve a symbol moniker (crate@version::path::to::Item#kind, as returned by find_symbol \
+            and enumerate_file) back to the symbol's current location. Monikers stay valid across server restarts.",
+        output_schema = output_schema::<outputs::ResolveMonikerOutput>())]
+    async fn resolve_moniker(&self, params: Parameters<ResolveMonikerParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+
+        let fields = symbol_fields(params.fields.as_deref(), SYMBOL_FIELDS)?;
+
+        self.cached("resolve_moniker", &params, |analyzer| {
+            let sym = analyzer.resolve_moniker(&params.moniker)
+                .map_err(|e| McpError {
+                    code: ErrorCode(-1),
+                    message: format!("Failed to resolve moniker: {}", e).into(),
                     data: None,
-                })
-            }
-            InitState::Ready => Ok(())
-        }
+                })?;
+
+            let output = outputs::ResolveMonikerOutput { symbol: symbol_output(&sym, &fields) };
+
+            let summary = format!("Resolved '{}' to {:?} '{}' in {}", params.moniker, sym.kind, sym.name, sym.file_path);
+
+            Ok(tool_result(summary, &output))
+        })
     }
 
-    /// Find all occurrences of a symbol by name across the indexed codebase
-    #[tool(description = "Find all occurrences of a Rust symbol (struct, enum, trait, function, method, impl) by name. \
-            Searches both project and library files. Can apply symbol filter: all, types, functions, or implementations.")]
-    async fn find_symbol(&self, params: Parameters<FindSymbolParams>) -> Result<CallToolResult, McpError> {
+    /// Resolve a fully qualified path to the item it names
+    #[tool(description = "Resolve a fully qualified path such as tokio::sync::mpsc::Sender or std::vec::Vec::new to \
+            the item it names, with its defining file and lines. Follows re-exports (pub use) the way the compiler \
+            does, and reports the item's canonical path. Unlike find_symbol, a common name can't match several items.",
+        output_schema = output_schema::<outputs::ResolvePathOutput>())]
+    async fn resolve_path(&self, params: Parameters<ResolvePathParams>) -> Result<CallToolResult, McpError> {
         self.check_init_state()?;
 
         let params = params.0;
 
-        // Parse search mode from string
-        let mode = match params.mode.as_deref() {
-            Some("exact") => SearchMode::Exact,
-            Some("prefix") => SearchMode::Prefix,
-            Some("fuzzy") | None => SearchMode::Fuzzy,
-            Some(other) => {
-                return Err(McpError {
+        let fields = symbol_fields(params.fields.as_deref(), SYMBOL_FIELDS)?;
+
+        self.cached("resolve_path", &params, |analyzer| {
+            let resolved = analyzer.resolve_path(&params.path)
+                .map_err(|e| McpError {
                     code: ErrorCode(-1),
-                    message: format!("Invalid search mode: '{}'. Valid values: 'exact', 'fuzzy', 'prefix'", other).into(),
+                    message: format!("Failed to resolve path: {}", e).into(),
                     data: None,
-                });
+                })?;
+
+            let sym = &resolved.symbol;
+            let mut summary = format!("Resolved '{}' to {:?} '{}' in {}", params.path, sym.kind, sym.name, sym.file_path);
+            if resolved.reexported {
+                summary.push_str(&format!(" (re-exported; defined as {})", resolved.canonical_path));
             }
+
+            let output = outputs::ResolvePathOutput {
+                symbol: symbol_output(sym, &fields),
+                canonical_path: resolved.canonical_path,
+                reexported: resolved.reexported,
+            };
+
+            Ok(tool_result(summary, &output))
+        })
+    }
+
+    #[tool(description = "Begin a snapshot: until end_snapshot is called or the timeout passes, file changes \
+            picked up by the watcher are deferred, so a sequence of tool calls all see the same index revision. \
+            Changes made through tools, such as add_scratch_root, still apply. Returns the snapshot_id to pass \
+            to end_snapshot and the revision being held.",
+        output_schema = output_schema::<outputs::BeginSnapshotOutput>())]
+    async fn begin_snapshot(&self, params: Parameters<BeginSnapshotParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let timeout = params.timeout_secs.map_or(snapshots::DEFAULT_TIMEOUT, Duration::from_secs)
+            .min(snapshots::MAX_TIMEOUT);
+
+        // Locked before the analyzer, as the watcher does, so no change is half applied
+        let mut snapshots = self.snapshots.lock().unwrap();
+        let revision = self.analyzer.lock().unwrap().revision();
+        let snapshot_id = snapshots.begin(revision, timeout, Instant::now());
+
+        let output = outputs::BeginSnapshotOutput {
+            snapshot_id,
+            revision,
+            timeout_secs: timeout.as_secs(),
+            open_snapshots: snapshots.open_count(),
         };
+        let summary = format!(
+            "Snapshot {} holds revision {} for up to {}s ({} snapshot(s) open)",
+            snapshot_id, revision, output.timeout_secs, output.open_snapshots
+        );
 
-        // Parse symbol filter from string
-        let filter = match params.filter.as_deref() {
-            Some("types") => SymbolFilter::Types,
-            Some("implementations") => SymbolFilter::Implementations,
-            Some("functions") => SymbolFilter::Functions,
-            Some("all") | None => SymbolFilter::All,
-            Some(other) => {
-                return Err(McpError {
+        Ok(tool_result(summary, &output))
+    }
+
+    #[tool(description = "End a snapshot begun with begin_snapshot. Once no snapshot is open, the file changes \
+            deferred meanwhile are applied. 'consistent' tells whether the index was still at the snapshot's \
+            revision, i.e. every call made under it saw the same index. Fails if the snapshot had already \
+            expired, in which case calls made under it may have seen different revisions.",
+        output_schema = output_schema::<outputs::EndSnapshotOutput>())]
+    async fn end_snapshot(&self, params: Parameters<EndSnapshotParams>) -> Result<CallToolResult, McpError> {
+        self.check_init_state()?;
+
+        let params = params.0;
+        let (snapshot, revision) = {
+            let mut snapshots = self.snapshots.lock().unwrap();
+            let snapshot = snapshots.end(params.snapshot_id, Instant::now())
+                .ok_or_else(|| McpError {
                     code: ErrorCode(-1),
-                    message: format!("Invalid filter: '{}'. Valid values: 'types', 'implementations', 'functions', 'all'", other).into(),
+                    message: format!(
+                        "Unknown or expired snapshot '{}'; calls made under it may have seen different revisions",
+                        params.snapshot_id
+                    ).into(),
                     data: None,
-                });
-            }
+                })?;
+            (snapshot, self.analyzer.lock().unwrap().revision())
         };
+        let applied_changes = apply_released_changes(&self.analyzer, &self.snapshots);
+        let pending_changes = self.snapshots.lock().unwrap().pending_count();
 
-        // Build search options from parameters
-        let options = SearchOptions {
-            mode,
-            include_library: params.include_library.unwrap_or(false),
-            filter,
+        let output = outputs::EndSnapshotOutput {
+            snapshot_id: params.snapshot_id,
+            revision: snapshot.revision,
+            consistent: revision == snapshot.revision,
+            applied_changes,
+            pending_changes,
+            current_revision: self.analyzer.lock().unwrap().revision(),
         };
-
-        // Perform the search (lock the analyzer)
-        let analyzer = self.analyzer.lock().unwrap();
-        let results = analyzer.find_symbol(&params.name, &options)
-            .map_err(|e| McpError {
-                code: ErrorCode(-1),
-                message: format!("Search failed: {}", e).into(),
-                data: None,
-            })?;
-
-        // Format results as JSON
-        let results_json: Vec<_> = results.iter().map(|sym| {
-            json!({
-                "name": sym.name,
-                "kind": format!("{:?}", sym.kind),
-                "file_path": sym.file_path,
-                "start_line": sym.start_line,
-                "end_line": sym.end_line,
-                "documentation": sym.documentation,
-            })
-        }).collect();
-
         let summary = format!(
-            "Found {} symbol(s) matching '{}' (mode: {:?}, library: {}, filter: {:?})",
-            results.len(),
-            params.name,
-            mode,
-            options.include_library,
-            options.filter
+            "Snapshot {} ended at revision {}{}; {} deferred change(s) applied, {} still deferred (now at revision {})",
+            output.snapshot_id,
+            output.revision,
+            if output.consistent { "" } else { ", though the index changed through a tool meanwhile" },
+            output.applied_changes,
+            output.pending_changes,
+            output.current_revision
         );
 
-        Ok(CallToolResult::success(vec![
-            Content::text(summary),
-            Content::text(serde_json::to_string_pretty(&results_json).unwrap()),
-        ]))
+        Ok(tool_result(summary, &output))
     }
 
-    /// List all symbols defined in a specific file
-    #[tool(description = "Enumerate all Rust symbols defined in a specific file")]
-    async fn enumerate_file(&self, params: Parameters<EnumerateFileParams>) -> Result<CallToolResult, McpError> {
+    /// Poll for changes to the index since a previous call
+    #[tool(description = "Poll for index changes: files re-indexed or removed, symbols added or removed, and crate graph reloads. \
+            Pass the latest_sequence from the previous call as 'since' to get only newer events. \
+            If 'truncated' is true, events were missed and any cached knowledge should be discarded.",
+        output_schema = output_schema::<outputs::IndexEventsOutput>())]
+    async fn index_events(&self, params: Parameters<IndexEventsParams>) -> Result<CallToolResult, McpError> {
         self.check_init_state()?;
 
         let params = params.0;
+        let since = params.since.unwrap_or(0);
 
-        // Enumerate symbols in the file
-        let analyzer = self.analyzer.lock().unwrap();
-        let results = analyzer.enumerate_file(&params.file_path)
-            .map_err(|e| McpError {
-                code: ErrorCode(-1),
-                message: format!("Failed to enumerate file: {}", e).into(),
-                data: None,
-            })?;
+        // Every event coincides with a revision bump, so caching by revision is safe
+        self.cached("index_events", &params, |analyzer| {
+            let events = analyzer.index_events(since);
 
-        // Format results as JSON with only requested fields
-        let results_json: Vec<_> = results.iter().map(|sym| {
-            json!({
-                "name": sym.name,
-                "kind": format!("{:?}", sym.kind),
-                "start_line": sym.start_line,
-                "end_line": sym.end_line,
-            })
-        }).collect();
+            let events_output: Vec<_> = events.events.iter().map(|event| {
+                let (kind, file_path, name, symbol_kind) = match &event.kind {
+                    IndexEventKind::FileReindexed { file_path } => ("file_reindexed", Some(file_path), None, None),
+                    IndexEventKind::FileRemoved { file_path } => ("file_removed", Some(file_path), None, None),
+                    IndexEventKind::SymbolAdded { file_path, name, kind } => ("symbol_added", Some(file_path), Some(name), Some(*kind)),
+                    IndexEventKind::SymbolRemoved { file_path, name, kind } => ("symbol_removed", Some(file_path), Some(name), Some(*kind)),
+                    IndexEventKind::CrateGraphReloaded => ("crate_graph_reloaded", None, None, None),
+                };
 
-        let summary = format!(
-            "Found {} symbol(s) in '{}'",
-            results.len(),
-            params.file_path
-        );
+                outputs::IndexEventOutput {
+                    sequence: event.sequence,
+                    revision: event.revision,
+                    kind: kind.to_string(),
+                    file_path: file_path.cloned(),
+                    name: name.cloned(),
+                    symbol_kind,
+                }
+            }).collect();
 
-        Ok(CallToolResult::success(vec![
-            Content::text(summary),
-            Content::text(serde_json::to_string_pretty(&results_json).unwrap()),
-        ]))
+            let summary = format!(
+                "{} event(s) since sequence {} (latest sequence {}, revision {}){}",
+                events.events.len(),
+                since,
+                events.latest_sequence,
+                analyzer.revision(),
+                if events.truncated { "; older events were dropped, cached knowledge may be stale" } else { "" }
+            );
+
+            Ok(tool_result(summary, &outputs::IndexEventsOutput {
+                latest_sequence: events.latest_sequence,
+                revision: analyzer.revision(),
+                truncated: events.truncated,
+                events: events_output,
+            }))
+        })
     }
 }
 
@@ -351,6 +5585,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::json;
 
     #[tokio::test]
     async fn test_find_symbol_returns_ok() {
@@ -363,6 +5598,7 @@ mod tests {
             mode: Some("fuzzy".to_string()),
             include_library: Some(false),
             filter: Some("all".to_string()),
+            ..Default::default()
         });
 
         let result = server.find_symbol(params).await;
@@ -391,6 +5627,7 @@ mod tests {
             mode: Some("exact".to_string()),
             include_library: Some(false),
             filter: Some("all".to_string()),
+            ..Default::default()
         });
 
         let result = server.find_symbol(params).await;
@@ -415,14 +5652,46 @@ mod tests {
             mode: Some("exact".to_string()),
             include_library: Some(true),
             filter: Some("all".to_string()),
+            ..Default::default()
         });
 
         let result = server.find_symbol(params).await;
         assert!(result.is_ok(), "find_symbol should return Ok");
 
         // Should find HashMap from the standard library
-        let content_str = format!("{:?}", result.unwrap().content);
-        assert!(content_str.contains("HashMap"), "Should find HashMap");
+        let structured = result.unwrap().structured_content.expect("Result should have structured content");
+        assert!(structured["symbols"].as_array().unwrap().iter().any(|s| s["name"] == "HashMap"), "Should find HashMap");
+    }
+
+    #[test]
+    fn test_symbol_field_selection() {
+        let sym = SymbolInfo {
+            name: "Analyzer".to_string(),
+            kind: analyzer::SymbolKind::Struct,
+            file_path: "src/analyzer.rs".to_string(),
+            start_line: 10,
+            end_line: 20,
+            documentation: Some("Main analyzer interface".to_string()),
+            crate_name: Some("cratographer".to_string()),
+            crate_version: None,
+            moniker: None,
+            rustdoc: None,
+            approximate: false,
+            attributes: Vec::new(),
+            owners: Vec::new(),
+            size: None,
+        };
+
+        let requested = vec!["name".to_string(), "start_line".to_string()];
+        let fields = symbol_fields(Some(&requested), SYMBOL_FIELDS).unwrap();
+        let selected = serde_json::to_value(symbol_output(&sym, &fields)).unwrap();
+        assert_eq!(selected, json!({ "name": "Analyzer", "start_line": 10 }));
+
+        let defaults = symbol_fields(None, FILE_SYMBOL_FIELDS).unwrap();
+        assert!(symbol_output(&sym, &defaults).file_path.is_none());
+
+        let unknown = vec!["path".to_string()];
+        assert!(symbol_fields(Some(&unknown), SYMBOL_FIELDS).is_err());
     }
 
     #[tokio::test]
@@ -440,6 +5709,7 @@ mod tests {
         // Create parameters for enumerate_file
         let params = Parameters(EnumerateFileParams {
             file_path: analyzer_path.to_str().unwrap().to_string(),
+            fields: None,
         });
 
         let result = server.enumerate_file(params).await;
@@ -457,6 +5727,130 @@ mod tests {
         println!("Result: {:?}", tool_result.content);
     }
 
+    #[tokio::test]
+    async fn test_analyze_closures_returns_ok() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let params = Parameters(AnalyzeClosuresParams {
+            function: "find_symbol".to_string(),
+            file_path: None,
+        });
+
+        let result = server.analyze_closures(params).await;
+        assert!(result.is_ok(), "analyze_closures should return Ok: {:?}", result.err());
+
+        let structured = result.unwrap().structured_content.expect("Result should have structured content");
+        assert!(structured["closures"].as_array().unwrap().iter().all(|c| c["fn_trait"].is_string()), "Should report the closure kind");
+    }
+
+    #[tokio::test]
+    async fn test_find_closure_apis_rejects_invalid_trait() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let params = Parameters(FindClosureApisParams {
+            fn_trait: Some("Closure".to_string()),
+            arity: None,
+        });
+
+        let result = server.find_closure_apis(params).await;
+        assert!(result.is_err(), "Invalid fn_trait should be rejected");
+    }
+
+    #[tokio::test]
+    async fn test_repeated_query_is_served_from_cache() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let params = || Parameters(FindSymbolParams {
+            name: "Analyzer".to_string(),
+            mode: Some("exact".to_string()),
+            include_library: Some(false),
+            filter: Some("all".to_string()),
+            ..Default::default()
+        });
+
+        let first = server.find_symbol(params()).await.expect("find_symbol should return Ok");
+        assert_eq!(server.cache.lock().unwrap().len(), 1, "First query should populate the cache");
+
+        let second = server.find_symbol(params()).await.expect("find_symbol should return Ok");
+        assert_eq!(server.cache.lock().unwrap().len(), 1, "Repeated query should not add an entry");
+        assert_eq!(format!("{:?}", first.content), format!("{:?}", second.content));
+        assert_eq!(first.structured_content, second.structured_content);
+    }
+
+    #[tokio::test]
+    async fn test_index_events_reports_project_load() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let result = server.index_events(Parameters(IndexEventsParams { since: None })).await;
+        assert!(result.is_ok(), "index_events should return Ok");
+        let events = server.analyzer.lock().unwrap().index_events(0);
+        assert!(
+            events.events.iter().any(|e| e.kind == IndexEventKind::CrateGraphReloaded),
+            "Loading the project should be reported"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_defers_file_changes() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let result = server.begin_snapshot(Parameters(BeginSnapshotParams { timeout_secs: Some(10) })).await;
+        assert!(result.is_ok(), "begin_snapshot should return Ok");
+        let structured = result.unwrap().structured_content.expect("Result should have structured content");
+        let snapshot_id = structured["snapshot_id"].as_u64().unwrap();
+        let revision = structured["revision"].as_u64().unwrap();
+
+        // A change arriving from the watcher while the snapshot is held waits
+        let path = std::fs::canonicalize("src/snapshots.rs").unwrap();
+        let text = std::fs::read_to_string(&path).unwrap() + "\npub fn snapshot_probe() {}\n";
+        let change = (ra_ap_paths::AbsPathBuf::assert_utf8(path), Some(text.into_bytes()));
+        server.snapshots.lock().unwrap().defer([change]);
+        assert_eq!(apply_released_changes(&server.analyzer, &server.snapshots), 0);
+        assert_eq!(server.analyzer.lock().unwrap().revision(), revision);
+        assert_eq!(server.snapshots.lock().unwrap().pending_count(), 1);
+
+        let result = server.end_snapshot(Parameters(EndSnapshotParams { snapshot_id })).await;
+        assert!(result.is_ok(), "end_snapshot should return Ok");
+        let structured = result.unwrap().structured_content.expect("Result should have structured content");
+        assert_eq!(structured["consistent"], json!(true));
+
+        // Ending the last snapshot applies it
+        assert_eq!(structured["pending_changes"], json!(0));
+        assert!(server.analyzer.lock().unwrap().revision() > revision);
+        let options = SearchOptions { mode: SearchMode::Exact, ..Default::default() };
+        let symbols = server.analyzer.lock().unwrap().find_symbol("snapshot_probe", &options).unwrap();
+        assert_eq!(symbols.len(), 1);
+
+        // A snapshot can only be ended once
+        assert!(server.end_snapshot(Parameters(EndSnapshotParams { snapshot_id })).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_list_tools() {
+        let server = CratographerServer::new().expect("Failed to create server");
+
+        // Available before initialization completes
+        let result = server.capabilities().await;
+        assert!(result.is_ok(), "capabilities should return Ok");
+
+        server.wait_for_ready().await.expect("Server initialization failed");
+        let result = server.capabilities().await.unwrap();
+        let structured = result.structured_content.expect("Result should have structured content");
+        assert_eq!(structured["state"], json!("ready"));
+        let tools = structured["tools"].as_array().unwrap();
+        assert!(tools.contains(&json!("capabilities")));
+        assert!(tools.contains(&json!("find_symbol")));
+        let subsystem = |name: &str| structured["subsystems"].as_array().unwrap().iter().find(|s| s["name"] == name).unwrap().clone();
+        assert_eq!(subsystem("file_watcher")["enabled"], json!(true));
+        let persistence = subsystem("reference_index_persistence");
+        assert_eq!(persistence["enabled"], json!(persistence["detail"] != json!("missing")));
+    }
+
     #[tokio::test]
     async fn test_server_info() {
         let server = CratographerServer::new().expect("Failed to create server");
@@ -502,6 +5896,126 @@ mod tests {
         // If we get here, the server was created successfully
     }
 
+    #[test]
+    fn test_every_tool_has_output_schema() {
+        for tool in CratographerServer::tool_router().list_all() {
+            let schema = tool.output_schema.unwrap_or_else(|| panic!("{} has no output schema", tool.name));
+            assert_eq!(schema.get("type"), Some(&json!("object")), "{} output must be an object", tool.name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_results_are_structured() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let params = Parameters(FindSymbolParams {
+            name: "Analyzer".to_string(),
+            mode: Some("exact".to_string()),
+            include_library: Some(false),
+            filter: Some("types".to_string()),
+            fields: Some(vec!["name".to_string(), "kind".to_string()]),
+            ..Default::default()
+        });
+
+        let result = server.find_symbol(params).await.expect("find_symbol should return Ok");
+        let structured = result.structured_content.expect("Result should have structured content");
+        assert!(structured["symbols"].as_array().unwrap().iter().any(|s| s == &json!({ "name": "Analyzer", "kind": "Struct" })));
+
+        // The text is only the summary, not the JSON again
+        assert_eq!(result.content.len(), 1);
+        assert!(result.content[0].as_text().is_some_and(|text| text.text.starts_with("Found")));
+    }
+
+    #[tokio::test]
+    async fn test_ambiguous_name_returns_candidates() {
+        let server = CratographerServer::new().expect("Failed to create server");
+        server.wait_for_ready().await.expect("Server initialization failed");
+
+        let params = Parameters(ImplsOfTypeParams { type_name: "SymbolKind".to_string(), include_library: None });
+        let result = server.impls_of_type(params).await.expect("An ambiguous name is not an error");
+        let structured = result.structured_content.expect("Result should have structured content");
+        assert_eq!(structured["impls"], json!([]));
+        let candidates = structured["candidates"].as_array().expect("Candidates should be listed");
+        assert!(candidates.len() > 1);
+
+        // Each candidate's symbol_id picks it out
+        let symbol_id = candidates[0]["symbol_id"].as_str().unwrap().to_string();
+        let params = Parameters(ImplsOfTypeParams { type_name: symbol_id, include_library: None });
+        let result = server.impls_of_type(params).await.expect("impls_of_type should return Ok");
+        assert!(result.structured_content.unwrap().get("candidates").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_view_mir_is_opt_in() {
+        let mut server = CratographerServer::new().expect("Failed to create server");
+        server.enable_mir = false;
+
+        let params = Parameters(ViewMirParams { file_path: "src/main.rs".to_string(), line: 0, column: 0 });
+        let error = server.view_mir(params).await.expect_err("view_mir should be disabled by default");
+        assert!(error.message.contains("CRATOGRAPHER_ENABLE_MIR"));
+    }
+
+    /// Backend answering every query with one fixed symbol
+    struct StubProvider;
+
+    impl SymbolProvider for StubProvider {
+        fn backend(&self) -> &'static str {
+            "stub"
+        }
+
+        fn project_root(&self) -> Option<&std::path::Path> {
+            None
+        }
+
+        fn revision(&self) -> u64 {
+            0
+        }
+
+        fn find_symbol(&self, name: &str, _options: &SearchOptions) -> Result<Vec<SymbolInfo>, analyzer::AnalyzerError> {
+            Ok(vec![SymbolInfo {
+                name: name.to_string(),
+                kind: analyzer::SymbolKind::Struct,
+                file_path: "src/lib.rs".to_string(),
+                start_line: 0,
+                end_line: 0,
+                documentation: None,
+                crate_name: None,
+                crate_version: None,
+                moniker: None,
+                rustdoc: None,
+                approximate: false,
+                attributes: Vec::new(),
+                owners: Vec::new(),
+                size: None,
+            }])
+        }
+
+        fn enumerate_file(&self, _file_path: &str) -> Result<Vec<SymbolInfo>, analyzer::AnalyzerError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fallback_serves_queries_until_ready() {
+        let server = CratographerServer::with_fallback(Some(Box::new(StubProvider))).expect("Failed to create server");
+        let params = || Parameters(FindSymbolParams {
+            name: "Analyzer".to_string(),
+            mode: Some("exact".to_string()),
+            include_library: Some(false),
+            filter: Some("all".to_string()),
+            ..Default::default()
+        });
+
+        // Answered by the stub while loading, unless loading was very fast
+        let early = format!("{:?}", server.find_symbol(params()).await.expect("find_symbol should return Ok").content);
+        assert!(early.contains("backend: stub") || early.contains("backend: rust-analyzer"));
+
+        server.wait_for_ready().await.expect("Server initialization failed");
+        let ready = format!("{:?}", server.find_symbol(params()).await.expect("find_symbol should return Ok").content);
+        assert!(ready.contains("backend: rust-analyzer"), "Ready server should use the analyzer: {}", ready);
+    }
+
     #[tokio::test]
     async fn test_initialization_states() {
         use tokio::time::{sleep, Duration};
@@ -528,6 +6042,7 @@ mod tests {
             mode: Some("exact".to_string()),
             include_library: Some(false),
             filter: Some("all".to_string()),
+            ..Default::default()
         });
 
         let result = server.find_symbol(params).await;
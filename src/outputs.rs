@@ -0,0 +1,1986 @@
+//! Tool output types
+//!
+//! Every tool's result is one of these types. It is returned as structured
+//! content, and its JSON Schema is advertised as the tool's output schema, so
+//! clients have a contract to validate and post-process results against.
+//! Field names are part of that contract: rename with care.
+//!
+//! Lines and columns are 0-based throughout.
+
+use crate::analyzer::SymbolKind;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// A symbol with only the requested fields; unrequested and empty fields are left out
+#[derive(Serialize, JsonSchema, Default)]
+pub struct SymbolOutput {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<SymbolKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
+    #[serde(rename = "crate", skip_serializing_if = "Option::is_none")]
+    pub crate_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crate_version: Option<String>,
+    /// Stable identifier that `resolve_moniker` resolves back to the symbol
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moniker: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rustdoc_path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rustdoc_url: Option<String>,
+    /// Published docs of a library symbol, on docs.rs or doc.rust-lang.org
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docs_url: Option<String>,
+    /// A function's attributes as written, e.g. `inline(always)`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<String>,
+    /// Owners of the symbol's file according to CODEOWNERS
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub owners: Vec<String>,
+    /// Size of the symbol's source, body included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<SymbolSizeOutput>,
+    /// Present and true when the symbol comes from the syntax-only quick
+    /// index used during startup
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub approximate: bool,
+}
+
+/// How much source a symbol spans
+#[derive(Serialize, JsonSchema)]
+pub struct SymbolSizeOutput {
+    pub lines: u32,
+    pub bytes: u32,
+    /// Approximate number of LLM tokens, counting four bytes per token
+    pub tokens: u32,
+}
+
+/// One of the symbols an ambiguous name matched
+///
+/// Name-based tools list these instead of results when the name matches
+/// several symbols; repeating the query with a symbol_id picks one.
+#[derive(Serialize, JsonSchema)]
+pub struct CandidateOutput {
+    /// Pass this in place of the name to query just this symbol
+    pub symbol_id: String,
+    pub path: String,
+    pub kind: String,
+    #[serde(rename = "crate")]
+    pub crate_name: Option<String>,
+    pub local: bool,
+    pub file_path: String,
+    pub line: u32,
+    /// The source line declaring the symbol
+    pub context: String,
+}
+
+/// Output of find_symbol
+#[derive(Serialize, JsonSchema)]
+pub struct FindSymbolOutput {
+    pub symbols: Vec<SymbolOutput>,
+    /// Indexed names close to the one searched for, when nothing matched it
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suggestions: Vec<String>,
+}
+
+/// Output of enumerate_file
+#[derive(Serialize, JsonSchema)]
+pub struct EnumerateFileOutput {
+    pub symbols: Vec<SymbolOutput>,
+}
+
+/// The symbols of one file, or why they couldn't be listed
+#[derive(Serialize, JsonSchema)]
+pub struct FileSymbolsOutput {
+    pub file_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<SymbolOutput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Output of enumerate_files
+#[derive(Serialize, JsonSchema)]
+pub struct EnumerateFilesOutput {
+    pub files: Vec<FileSymbolsOutput>,
+}
+
+/// Output of enumerate_directory
+#[derive(Serialize, JsonSchema)]
+pub struct EnumerateDirectoryOutput {
+    /// The enumerated files, by path
+    pub files: Vec<FileSymbolsOutput>,
+    /// Number of Rust files below the directory
+    pub total_files: usize,
+    /// Whether files were left out because of `max_files`
+    pub truncated: bool,
+}
+
+/// Output of owned_symbols
+#[derive(Serialize, JsonSchema)]
+pub struct OwnedSymbolsOutput {
+    pub owner: String,
+    /// The files the owner owns, by path
+    pub files: Vec<FileSymbolsOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CaptureOutput {
+    /// Captured place, e.g. `self.items`
+    pub place: String,
+    /// rust-analyzer's capture kind, e.g. "SharedRef", "MutableRef" or "Move"
+    pub kind: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ClosureOutput {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub params: String,
+    pub is_move: bool,
+    /// "Fn", "FnMut" or "FnOnce"
+    pub fn_trait: String,
+    pub captures: Vec<CaptureOutput>,
+}
+
+/// Output of analyze_closures
+#[derive(Serialize, JsonSchema)]
+pub struct AnalyzeClosuresOutput {
+    pub closures: Vec<ClosureOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ClosureApiOutput {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub parameter: String,
+    pub shape: String,
+    pub fn_trait: String,
+    pub arity: usize,
+}
+
+/// Output of find_closure_apis
+#[derive(Serialize, JsonSchema)]
+pub struct FindClosureApisOutput {
+    pub apis: Vec<ClosureApiOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct LifetimeExpansionOutput {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub original: String,
+    pub expanded: String,
+    /// Lifetimes the expansion introduced
+    pub introduced: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+/// Output of expand_lifetimes
+#[derive(Serialize, JsonSchema)]
+pub struct ExpandLifetimesOutput {
+    pub expansions: Vec<LifetimeExpansionOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImplTraitOutput {
+    pub function: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub return_type: String,
+    pub bounds: Vec<String>,
+    pub implied_bounds: Vec<String>,
+    pub concrete_types: Vec<String>,
+}
+
+/// Output of inspect_impl_trait
+#[derive(Serialize, JsonSchema)]
+pub struct InspectImplTraitOutput {
+    pub functions: Vec<ImplTraitOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeatureGatedItemOutput {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub cfg: String,
+    /// "present", "absent" or "unknown" per feature combination label
+    pub presence: BTreeMap<String, String>,
+}
+
+/// Output of feature_matrix
+#[derive(Serialize, JsonSchema)]
+pub struct FeatureMatrixOutput {
+    /// Labels of the evaluated combinations, e.g. "serde,std" or "(no features)"
+    pub combinations: Vec<String>,
+    pub items: Vec<FeatureGatedItemOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReferenceSiteOutput {
+    pub file_path: String,
+    pub line: u32,
+    pub column: u32,
+    pub is_import: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct IndexedDefinitionOutput {
+    pub definition: String,
+    pub references: Vec<ReferenceSiteOutput>,
+}
+
+/// Output of indexed_references
+#[derive(Serialize, JsonSchema)]
+pub struct IndexedReferencesOutput {
+    pub definitions: Vec<IndexedDefinitionOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SignatureUseOutput {
+    pub item: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub signature: String,
+    /// Whether the item is reachable from outside the crate
+    pub reachable: bool,
+}
+
+/// Output of public_api_uses
+#[derive(Serialize, JsonSchema)]
+pub struct PublicApiUsesOutput {
+    pub uses: Vec<SignatureUseOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ConflictingImplOutput {
+    pub header: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// False for possible conflicts with blanket impls
+    pub certain: bool,
+}
+
+/// Output of check_impl_coherence
+#[derive(Serialize, JsonSchema)]
+pub struct CheckImplCoherenceOutput {
+    #[serde(rename = "trait")]
+    pub trait_path: Option<String>,
+    pub self_type: String,
+    /// Null when it could not be determined
+    pub orphan_allowed: Option<bool>,
+    pub orphan_reason: String,
+    pub conflicts: Vec<ConflictingImplOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DuplicateSiteOutput {
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub cfg: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DuplicateOutput {
+    pub name: String,
+    pub namespace: String,
+    pub scope: String,
+    /// "exclusive", "conflict" or "unknown"
+    pub status: String,
+    /// A configuration enabling two definitions, for conflicts
+    pub witness: Option<String>,
+    pub definitions: Vec<DuplicateSiteOutput>,
+}
+
+/// Output of cfg_duplicates
+#[derive(Serialize, JsonSchema)]
+pub struct CfgDuplicatesOutput {
+    pub duplicates: Vec<DuplicateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct NameSourceOutput {
+    /// The `use` declaration bringing the name in, or `declared` for an item of the scope itself
+    pub via: String,
+    /// Qualified path of the item the name refers to through it
+    pub item: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct GlobConflictOutput {
+    /// "shadowed_glob" or "ambiguous_glob"
+    pub kind: String,
+    pub name: String,
+    pub file_path: String,
+    /// First use of the name in the scope
+    pub line: u32,
+    /// What the name resolves to there; absent if it doesn't resolve
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    /// The competing sources, the winning one first for shadowed_glob
+    pub sources: Vec<NameSourceOutput>,
+}
+
+/// Output of glob_conflicts
+#[derive(Serialize, JsonSchema)]
+pub struct GlobConflictsOutput {
+    pub conflicts: Vec<GlobConflictOutput>,
+    /// Set when checking the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageOutput>,
+}
+
+/// Type arguments a generic function is called with
+#[derive(Serialize, JsonSchema)]
+pub struct InstantiationOutput {
+    /// The turbofish, e.g. `::<String>`, or the types of the generic arguments and result, e.g. `(&str, -> Vec<u8>)`
+    pub type_args: String,
+    pub calls: usize,
+}
+
+/// A generic function with its estimated instantiations
+#[derive(Serialize, JsonSchema)]
+pub struct GenericInstantiationsOutput {
+    /// Qualified path of the function
+    pub path: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Type parameters, `impl Trait` arguments included
+    pub type_params: Vec<String>,
+    /// Lines of the body, copied per instantiation
+    pub body_lines: u32,
+    pub call_sites: usize,
+    /// Distinct type arguments seen at the call sites
+    pub instantiations: usize,
+    /// Body lines times instantiations, the ranking key
+    pub estimated_lines: u32,
+    /// The most frequent instantiations
+    pub examples: Vec<InstantiationOutput>,
+}
+
+/// Output of monomorphization_candidates
+#[derive(Serialize, JsonSchema)]
+pub struct MonomorphizationCandidatesOutput {
+    /// Largest estimated code size first
+    pub functions: Vec<GenericInstantiationsOutput>,
+    pub coverage: CoverageOutput,
+}
+
+/// A method forwarding to a field
+#[derive(Serialize, JsonSchema)]
+pub struct ForwardingMethodOutput {
+    pub name: String,
+    /// The field forwarded to, e.g. `inner` or `0`
+    pub field: String,
+    /// The forwarding call as written, e.g. `self.0.fmt(f)`
+    pub call: String,
+}
+
+/// An impl whose methods forward to fields of self
+#[derive(Serialize, JsonSchema)]
+pub struct DelegatingImplOutput {
+    /// The impl header, e.g. `impl Display for UserId`
+    pub header: String,
+    /// Qualified path of the implemented trait; absent for an inherent impl
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trait_path: Option<String>,
+    pub self_type: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Number of methods in the impl
+    pub methods: usize,
+    /// Whether every method forwards
+    pub fully_delegating: bool,
+    pub forwarding: Vec<ForwardingMethodOutput>,
+    /// The one field every forwarding method targets, if they agree
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_field: Option<String>,
+    /// Type of that field
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target_type: Option<String>,
+}
+
+/// Output of delegating_impls
+#[derive(Serialize, JsonSchema)]
+pub struct DelegatingImplsOutput {
+    pub impls: Vec<DelegatingImplOutput>,
+    pub coverage: CoverageOutput,
+}
+
+/// An error-handling site
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorSiteOutput {
+    /// One of the kinds accepted by the `kinds` parameter
+    pub kind: String,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing function or type
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    /// The site as written, shortened to one line
+    pub text: String,
+}
+
+/// The error handling of one crate
+#[derive(Serialize, JsonSchema)]
+pub struct CrateErrorHandlingOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Count per kind, e.g. {"unwrap": 12, "try_operator": 140}
+    pub counts: BTreeMap<String, usize>,
+    /// By file and line
+    pub sites: Vec<ErrorSiteOutput>,
+}
+
+/// Output of error_handling_inventory
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorHandlingInventoryOutput {
+    pub crates: Vec<CrateErrorHandlingOutput>,
+    pub coverage: CoverageOutput,
+}
+
+/// Code matching a structural search pattern
+#[derive(Serialize, JsonSchema)]
+pub struct StructuralMatchOutput {
+    pub file_path: String,
+    /// 0-based start and end of the match
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// The matched code as written
+    pub text: String,
+}
+
+/// Output of structural_search
+#[derive(Serialize, JsonSchema)]
+pub struct StructuralSearchOutput {
+    /// By file, then in source order
+    pub matches: Vec<StructuralMatchOutput>,
+    /// Set when searching the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageOutput>,
+}
+
+/// Output of structural_replace
+#[derive(Serialize, JsonSchema)]
+pub struct StructuralReplaceOutput {
+    /// Number of matches replaced
+    pub matches: usize,
+    /// Edits in positions of the unmodified files, sorted by file and position
+    pub edits: Vec<TextEditOutput>,
+    /// Set when rewriting the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageOutput>,
+}
+
+/// A logging or tracing call site
+#[derive(Serialize, JsonSchema)]
+pub struct LogSiteOutput {
+    /// "event" for a log line, "span" for a span macro, "instrument" for an #[instrument] attribute
+    pub kind: String,
+    /// The macro or attribute name, e.g. "info", "log" or "info_span"
+    #[serde(rename = "macro")]
+    pub macro_name: String,
+    /// "trace", "debug", "info", "warn" or "error", when known
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    /// The explicit `target:`; the module path is used otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// The message template, or the span name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing function
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub function: Option<String>,
+}
+
+/// A function without logging
+#[derive(Serialize, JsonSchema)]
+pub struct SilentFunctionOutput {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of logging_sites
+#[derive(Serialize, JsonSchema)]
+pub struct LoggingSitesOutput {
+    /// By file and line
+    pub sites: Vec<LogSiteOutput>,
+    /// Set when `silent_functions` was asked for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub silent_functions: Option<Vec<SilentFunctionOutput>>,
+    pub coverage: CoverageOutput,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportOutput {
+    pub path: String,
+    pub resolved: Option<String>,
+    pub origin: Option<String>,
+    pub external: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ExportedSymbolOutput {
+    pub name: String,
+    pub definition: String,
+    pub reference_count: usize,
+    pub referencing_files: Vec<String>,
+}
+
+/// Output of file_dependencies
+#[derive(Serialize, JsonSchema)]
+pub struct FileDependenciesOutput {
+    pub external_crates: Vec<String>,
+    pub internal_modules: Vec<String>,
+    pub imports: Vec<ImportOutput>,
+    pub exported: Vec<ExportedSymbolOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UseImportOutput {
+    /// The imported path as written, e.g. `std::io::*`
+    pub path: String,
+    /// The name it is imported as with `as`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub alias: Option<String>,
+    /// Qualified path of what it resolves to; absent if it doesn't resolve
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved: Option<String>,
+    pub external: bool,
+    /// Whether anything in the declaration's scope refers to it
+    pub used: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UseDeclarationOutput {
+    pub text: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub visibility: Option<String>,
+    /// Whether the declaration re-exports its imports, which keeps them needed even if unused here
+    pub re_exported: bool,
+    pub imports: Vec<UseImportOutput>,
+}
+
+/// Output of use_declarations
+#[derive(Serialize, JsonSchema)]
+pub struct UseDeclarationsOutput {
+    pub declarations: Vec<UseDeclarationOutput>,
+}
+
+/// A text edit replacing a range of a file
+#[derive(Serialize, JsonSchema)]
+pub struct TextEditOutput {
+    pub file_path: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    pub new_text: String,
+}
+
+/// Output of move_item
+#[derive(Serialize, JsonSchema)]
+pub struct MoveItemOutput {
+    pub from_module: String,
+    pub to_module: String,
+    pub edits: Vec<TextEditOutput>,
+    pub warnings: Vec<String>,
+}
+
+/// Output of split_module
+#[derive(Serialize, JsonSchema)]
+pub struct SplitModuleOutput {
+    pub module: String,
+    pub new_file: String,
+    pub new_file_text: String,
+    pub edits: Vec<TextEditOutput>,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ConstantOutput {
+    pub name: String,
+    /// "const" or "static"
+    pub kind: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    pub value: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of find_constants
+#[derive(Serialize, JsonSchema)]
+pub struct FindConstantsOutput {
+    pub constants: Vec<ConstantOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TraitMemberOutput {
+    pub name: String,
+    /// "fn", "const" or "type"
+    pub kind: String,
+    pub has_default: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImplementorOutput {
+    pub self_type: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// "overridden", "defaulted" or "missing" per member, in member order
+    pub cells: Vec<String>,
+}
+
+/// Output of trait_override_matrix
+#[derive(Serialize, JsonSchema, Default)]
+pub struct TraitOverrideMatrixOutput {
+    #[serde(rename = "trait")]
+    pub trait_path: String,
+    pub members: Vec<TraitMemberOutput>,
+    pub implementors: Vec<ImplementorOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ModuleItemOutput {
+    pub name: String,
+    pub kind: String,
+    pub module: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImportCountOutput {
+    pub name: String,
+    pub imports: usize,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReferenceCountOutput {
+    pub path: String,
+    pub references: usize,
+}
+
+/// Output of summarize_module
+#[derive(Serialize, JsonSchema)]
+pub struct SummarizeModuleOutput {
+    pub module: String,
+    pub files: Vec<String>,
+    pub submodules: Vec<String>,
+    pub types: Vec<ModuleItemOutput>,
+    pub traits: Vec<ModuleItemOutput>,
+    pub functions: Vec<ModuleItemOutput>,
+    pub constants: Vec<ModuleItemOutput>,
+    pub external_dependencies: Vec<ImportCountOutput>,
+    pub internal_dependencies: Vec<ImportCountOutput>,
+    /// Null until the reference index is ready
+    pub top_referenced: Option<Vec<ReferenceCountOutput>>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ConversionOutput {
+    #[serde(rename = "trait")]
+    pub trait_name: String,
+    pub from: String,
+    pub to: String,
+    pub fallible: bool,
+    /// "into" if the queried type is the source, "from" if it is the target
+    pub direction: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of find_conversions
+#[derive(Serialize, JsonSchema, Default)]
+pub struct FindConversionsOutput {
+    pub conversions: Vec<ConversionOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct OperatorOutput {
+    #[serde(rename = "trait")]
+    pub trait_name: String,
+    pub operator: String,
+    pub rhs: Option<String>,
+    pub output: Option<String>,
+    pub derived: bool,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of type_operators
+#[derive(Serialize, JsonSchema, Default)]
+pub struct TypeOperatorsOutput {
+    #[serde(rename = "type")]
+    pub type_path: String,
+    pub operators: Vec<OperatorOutput>,
+    pub deref_chain: Vec<String>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ChainStepOutput {
+    pub expression: String,
+    pub line: u32,
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// Iterator item type, if the step is an iterator
+    pub item: Option<String>,
+    /// Whether inference failed at this step
+    pub unknown: bool,
+}
+
+/// Output of explain_iterator_chain
+#[derive(Serialize, JsonSchema)]
+pub struct ExplainIteratorChainOutput {
+    pub steps: Vec<ChainStepOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ReferenceOutput {
+    pub file_path: String,
+    pub line: u32,
+    pub column: u32,
+    /// "import", "write", "read", or "reference" for other uses such as calls
+    pub kind: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DefinitionReferencesOutput {
+    pub definition: Option<String>,
+    pub declaration: Option<ReferenceOutput>,
+    pub references: Vec<ReferenceOutput>,
+}
+
+/// Output of find_references
+#[derive(Serialize, JsonSchema, Default)]
+pub struct FindReferencesOutput {
+    pub definitions: Vec<DefinitionReferencesOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct EntryPointOutput {
+    /// One of the entry point kinds accepted by the `kinds` parameter
+    pub kind: String,
+    pub name: String,
+    pub item_kind: String,
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of entry_points
+#[derive(Serialize, JsonSchema)]
+pub struct EntryPointsOutput {
+    pub entry_points: Vec<EntryPointOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RunnableOutput {
+    /// One of the runnable kinds accepted by the `kinds` parameter
+    pub kind: String,
+    /// Test path within its target, or the binary's name
+    pub name: String,
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The `cfg` the runnable is gated on, e.g. `feature = "serde"`; required features are enabled in the command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>,
+    /// The command running just this, quoted for a POSIX shell; absent outside a Cargo workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_line: Option<String>,
+    /// The same command as separate arguments, for running without a shell
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+}
+
+/// Output of runnables
+#[derive(Serialize, JsonSchema)]
+pub struct RunnablesOutput {
+    pub runnables: Vec<RunnableOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct NavigationTargetOutput {
+    pub name: String,
+    pub kind: String,
+    pub container: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Position of the name
+    pub line: u32,
+    pub column: u32,
+    pub documentation: Option<String>,
+    #[serde(rename = "crate")]
+    pub crate_name: Option<String>,
+    pub crate_version: Option<String>,
+    /// Stable identifier for resolve_moniker; absent for locals and parameters
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moniker: Option<String>,
+}
+
+/// A range related to the queried position
+#[derive(Serialize, JsonSchema)]
+pub struct RelatedHighlightOutput {
+    /// 0-based start and end of the range
+    pub line: u32,
+    pub column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// `read`, `write` or `import` for usages of a symbol, `related` for control-flow highlights such as exit points
+    pub kind: String,
+    /// The highlighted text
+    pub text: String,
+}
+
+/// The code lenses of one item
+#[derive(Serialize, JsonSchema)]
+pub struct ItemAnnotationOutput {
+    pub name: String,
+    /// Item kind, e.g. "Function" or "Struct"
+    pub kind: String,
+    /// 0-based line of the item's name
+    pub line: u32,
+    /// Number of references, for items rust-analyzer counts them for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub references: Option<usize>,
+    /// How many of the references are in #[test] functions or #[cfg(test)] modules
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub test_references: Option<usize>,
+    /// Number of impls, for types and traits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implementations: Option<usize>,
+    /// What the item runs as: "test", "test_module", "bench", "doctest" or "bin"
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub runnables: Vec<String>,
+}
+
+/// Output of annotations
+#[derive(Serialize, JsonSchema)]
+pub struct AnnotationsOutput {
+    /// In file order
+    pub items: Vec<ItemAnnotationOutput>,
+}
+
+/// Output of highlight_related
+#[derive(Serialize, JsonSchema)]
+pub struct HighlightRelatedOutput {
+    /// In source order
+    pub highlights: Vec<RelatedHighlightOutput>,
+}
+
+/// Output of goto_definition and goto_type_definition
+#[derive(Serialize, JsonSchema)]
+pub struct NavigationOutput {
+    pub targets: Vec<NavigationTargetOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HoverRangeOutput {
+    pub markdown: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+/// Output of hover
+#[derive(Serialize, JsonSchema)]
+pub struct HoverOutput {
+    /// Null when there is nothing to show at the position
+    pub hover: Option<HoverRangeOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CallSiteOutput {
+    pub line: u32,
+    pub column: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CallerOutput {
+    pub name: String,
+    pub container: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub call_sites: Vec<CallSiteOutput>,
+    /// Stable identifier of the caller for resolve_moniker
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moniker: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct IncomingCallsOutput {
+    pub function: String,
+    pub callers: Vec<CallerOutput>,
+}
+
+/// Output of callers_of
+#[derive(Serialize, JsonSchema, Default)]
+pub struct CallersOfOutput {
+    pub functions: Vec<IncomingCallsOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ExporterOutput {
+    pub path: String,
+    pub kind: String,
+    #[serde(rename = "crate")]
+    pub crate_name: Option<String>,
+    pub local: bool,
+    /// `use` line importing the item from the given file, if it can be
+    pub import: Option<String>,
+    pub file_path: String,
+    pub line: u32,
+}
+
+/// Output of who_exports
+#[derive(Serialize, JsonSchema)]
+pub struct WhoExportsOutput {
+    pub exporters: Vec<ExporterOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct RelatedOutput {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Hierarchy of one trait or type; trait-only and type-only lists are left out for the other kind
+#[derive(Serialize, JsonSchema)]
+pub struct HierarchyOutput {
+    pub path: String,
+    /// "trait" or "type"
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub supertraits: Option<Vec<RelatedOutput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subtraits: Option<Vec<RelatedOutput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub implementors: Option<Vec<RelatedOutput>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub traits: Option<Vec<RelatedOutput>>,
+}
+
+/// Output of type_hierarchy
+#[derive(Serialize, JsonSchema, Default)]
+pub struct TypeHierarchyOutput {
+    pub hierarchies: Vec<HierarchyOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct ImplBlockOutput {
+    /// Qualified path of the implemented trait
+    #[serde(rename = "trait")]
+    pub trait_path: Option<String>,
+    pub self_type: String,
+    /// The impl header as written, e.g. "impl<T: Display> ToString for T"
+    pub header: String,
+    /// Whether the impl is for a bare type parameter, covering many types
+    pub blanket: bool,
+    /// The impl's members, e.g. "fn len", "const MAX" or "type Item"
+    pub items: Vec<String>,
+    #[serde(rename = "crate")]
+    pub crate_name: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Output of find_implementations
+#[derive(Serialize, JsonSchema, Default)]
+pub struct FindImplementationsOutput {
+    pub impls: Vec<ImplBlockOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of impls_of_type
+#[derive(Serialize, JsonSchema, Default)]
+pub struct ImplsOfTypeOutput {
+    /// Inherent impls first, then trait impls by trait
+    pub impls: Vec<ImplBlockOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of expand_derive
+#[derive(Serialize, JsonSchema, Default)]
+pub struct ExpandDeriveOutput {
+    #[serde(rename = "type")]
+    pub type_path: String,
+    /// The derive as written on the type
+    pub derive: String,
+    pub expansion: String,
+    /// Every derive of the type, as written
+    pub derives: Vec<String>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MacroExpansionOutput {
+    /// Name of the expanded macro, e.g. "vec" or "derive"
+    pub name: String,
+    pub expansion: String,
+}
+
+/// Output of expand_macro
+#[derive(Serialize, JsonSchema)]
+pub struct ExpandMacroOutput {
+    /// Null when there is no macro call at the position
+    pub expansion: Option<MacroExpansionOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MatchSiteOutput {
+    /// Enclosing function, if any
+    pub function: Option<String>,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub scrutinee: String,
+    pub arms: usize,
+    /// Pattern of the unguarded catch-all arm, e.g. "_" or "other"; null if
+    /// the match names every variant it handles
+    pub wildcard: Option<String>,
+    pub wildcard_line: Option<u32>,
+    /// Variants no arm names
+    pub unnamed_variants: Vec<String>,
+}
+
+/// Output of enum_match_sites
+#[derive(Serialize, JsonSchema, Default)]
+pub struct EnumMatchSitesOutput {
+    #[serde(rename = "enum")]
+    pub enum_path: String,
+    pub variants: Vec<String>,
+    /// Sites with a catch-all arm first
+    pub sites: Vec<MatchSiteOutput>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of view_hir
+#[derive(Serialize, JsonSchema)]
+pub struct ViewHirOutput {
+    /// rust-analyzer's HIR dump, in its own unstable format
+    pub hir: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct GatedApiItemOutput {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub cfg: String,
+    /// "present", "absent" or "unknown" with the default features
+    pub with_default: String,
+    /// "present", "absent" or "unknown" with all features
+    pub with_all_features: String,
+    /// Whether the item is nameable from outside the crate without a re-export
+    pub reachable: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct FeatureApiOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub default_features: Vec<String>,
+    pub all_features: Vec<String>,
+    pub items: Vec<GatedApiItemOutput>,
+}
+
+/// Output of feature_gated_api
+#[derive(Serialize, JsonSchema)]
+pub struct FeatureGatedApiOutput {
+    pub crates: Vec<FeatureApiOutput>,
+}
+
+/// Output of view_mir
+#[derive(Serialize, JsonSchema)]
+pub struct ViewMirOutput {
+    /// rust-analyzer's MIR dump, in its own unstable format
+    pub mir: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct HiddenItemOutput {
+    /// Qualified path of the hidden item
+    pub definition: String,
+    /// The dependency defining it
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub references: Vec<ReferenceSiteOutput>,
+}
+
+/// Output of hidden_api_uses
+#[derive(Serialize, JsonSchema)]
+pub struct HiddenApiUsesOutput {
+    pub items: Vec<HiddenItemOutput>,
+}
+
+/// Output of view_syntax_tree
+#[derive(Serialize, JsonSchema)]
+pub struct ViewSyntaxTreeOutput {
+    /// One element per line, indented by depth: kind, "line:column" extent
+    /// and, for tokens, the quoted text
+    pub tree: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BuildDirectiveOutput {
+    /// The instruction, e.g. "rustc-cfg" or "rerun-if-changed"
+    pub key: String,
+    pub value: String,
+    pub line: u32,
+    /// Whether the value has format placeholders, so it is only known at build time
+    pub dynamic: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BuildCfgItemOutput {
+    pub name: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The item's combined cfg predicate
+    pub cfg: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BuildCfgOutput {
+    /// The emitted cfg as written in #[cfg], e.g. "has_simd"
+    pub cfg: String,
+    pub items: Vec<BuildCfgItemOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BuildScriptOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub file_path: String,
+    pub symbols: Vec<SymbolOutput>,
+    pub directives: Vec<BuildDirectiveOutput>,
+    pub cfgs: Vec<BuildCfgOutput>,
+    /// Environment variables set with rustc-env
+    pub env_vars: Vec<String>,
+}
+
+/// Output of build_scripts
+#[derive(Serialize, JsonSchema)]
+pub struct BuildScriptsOutput {
+    pub scripts: Vec<BuildScriptOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DiagnosticOutput {
+    /// The file, when checking the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// The lint or error code, e.g. "E0308" or "unused_variables"
+    pub code: String,
+    pub message: String,
+    /// "error", "warning" or "hint"
+    pub severity: String,
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+    /// Labels of the available quick fixes
+    pub fixes: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct BuildEnvUsageOutput {
+    /// "env", "option_env", "include_str", "include_bytes" or "include"
+    #[serde(rename = "macro")]
+    pub macro_name: String,
+    /// The variable name or file path, when given as a string literal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argument: Option<String>,
+    /// The arguments as written
+    pub arguments: String,
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing item, e.g. `main` or `impl Config`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_kind: Option<String>,
+}
+
+/// Output of build_env_usages
+#[derive(Serialize, JsonSchema)]
+pub struct BuildEnvUsagesOutput {
+    pub usages: Vec<BuildEnvUsageOutput>,
+    /// Distinct environment variables read by env! and option_env! with a literal name
+    pub variables: Vec<String>,
+}
+
+/// A file embedded by a macro
+#[derive(Serialize, JsonSchema)]
+pub struct EmbeddedFileOutput {
+    /// "include_str", "include_bytes", "include", one of sqlx's "query_file" macros or "migrate"
+    #[serde(rename = "macro")]
+    pub macro_name: String,
+    /// The path as written, when given as a string literal
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub argument: Option<String>,
+    /// The embedded file, or directory for migrate!, as an absolute path; missing when built with concat! or env!
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolved_path: Option<String>,
+    /// Whether resolved_path exists on disk
+    pub exists: bool,
+    /// The Rust file embedding it
+    pub file_path: String,
+    pub line: u32,
+    /// Name of the enclosing item, e.g. `load_user` or `impl UserStore`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub item_kind: Option<String>,
+}
+
+/// Output of embedded_files
+#[derive(Serialize, JsonSchema)]
+pub struct EmbeddedFilesOutput {
+    /// By Rust file, then in source order
+    pub embeddings: Vec<EmbeddedFileOutput>,
+}
+
+/// Output of diagnostics
+#[derive(Serialize, JsonSchema)]
+pub struct DiagnosticsOutput {
+    pub diagnostics: Vec<DiagnosticOutput>,
+    /// Set when checking the whole workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub coverage: Option<CoverageOutput>,
+}
+
+/// How much of a time-boxed scan was done
+#[derive(Serialize, JsonSchema)]
+pub struct CoverageOutput {
+    /// Whether every file was scanned; if not, the result is partial
+    pub complete: bool,
+    pub files_scanned: usize,
+    pub files_total: usize,
+}
+
+/// Output of check_snippet
+#[derive(Serialize, JsonSchema)]
+pub struct CheckSnippetOutput {
+    /// Diagnostics within the snippet, with lines relative to its first line
+    pub diagnostics: Vec<DiagnosticOutput>,
+    /// Whether the snippet was checked as the body of a function, not being items
+    pub wrapped_in_function: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MsrvUseOutput {
+    pub file_path: String,
+    pub line: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct MsrvFeatureOutput {
+    /// e.g. "let-else" or "Option::is_some_and"
+    pub feature: String,
+    /// The Rust release that stabilized it, e.g. "1.65"
+    pub version: String,
+    pub uses: Vec<MsrvUseOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CrateMsrvOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub edition: String,
+    /// The rust-version declared in Cargo.toml
+    pub rust_version: Option<String>,
+    /// The newest version among the features used, a lower bound for the MSRV
+    pub required: Option<String>,
+    /// Newest first
+    pub features: Vec<MsrvFeatureOutput>,
+}
+
+/// Output of msrv_report
+#[derive(Serialize, JsonSchema)]
+pub struct MsrvReportOutput {
+    pub crates: Vec<CrateMsrvOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SafetyViolationOutput {
+    /// The offending method, associated item or supertrait, or the trait itself
+    pub item: String,
+    pub reason: String,
+    pub file_path: String,
+    pub line: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DynUseOutput {
+    pub file_path: String,
+    pub line: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct UnsafeTraitOutput {
+    #[serde(rename = "trait")]
+    pub trait_path: String,
+    pub file_path: String,
+    pub line: u32,
+    pub violations: Vec<SafetyViolationOutput>,
+    /// The `dyn Trait` types naming it in the workspace
+    pub dyn_uses: Vec<DynUseOutput>,
+}
+
+/// Output of object_safety
+#[derive(Serialize, JsonSchema, Default)]
+pub struct ObjectSafetyOutput {
+    pub traits: Vec<UnsafeTraitOutput>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SignatureParameterOutput {
+    /// e.g. "line: u32"
+    pub label: String,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SignatureOutput {
+    pub signature: String,
+    pub documentation: Option<String>,
+    pub parameters: Vec<SignatureParameterOutput>,
+    /// Index into parameters of the argument at the position
+    pub active_parameter: Option<usize>,
+}
+
+/// Output of signature_help
+#[derive(Serialize, JsonSchema)]
+pub struct SignatureHelpOutput {
+    /// Null when the position is not inside a call
+    pub signature: Option<SignatureOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DropImplOutput {
+    pub file_path: String,
+    pub line: u32,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DropFieldOutput {
+    /// Field name; "Variant.field" for enum variants
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// The types implementing Drop the field reaches
+    pub drops: Vec<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DropTypeOutput {
+    pub path: String,
+    pub kind: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The type's own impl Drop, if any
+    pub drop_impl: Option<DropImplOutput>,
+    /// Fields with destruction code, in declaration (drop) order
+    pub fields: Vec<DropFieldOutput>,
+}
+
+/// Output of drop_types
+#[derive(Serialize, JsonSchema)]
+pub struct DropTypesOutput {
+    pub types: Vec<DropTypeOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct InlayHintOutput {
+    /// Position the hint is drawn at (0-based)
+    pub line: u32,
+    pub column: u32,
+    /// e.g. "type", "parameter", "chaining" or "lifetime"
+    pub kind: String,
+    /// e.g. ": Vec<String>" or "file_path:"
+    pub label: String,
+}
+
+/// Output of inlay_hints
+#[derive(Serialize, JsonSchema)]
+pub struct InlayHintsOutput {
+    pub hints: Vec<InlayHintOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DerivingTypeOutput {
+    pub path: String,
+    pub file_path: String,
+    pub line: u32,
+    /// Size in bytes, when the layout is known
+    pub size: Option<u64>,
+    /// The cfg_attr condition the derive is under
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cfg: Option<String>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct DeriveOutput {
+    /// Qualified path of the derive macro, or the path as written if it doesn't resolve
+    pub derive: String,
+    /// e.g. "Serialize"
+    pub name: String,
+    pub count: usize,
+    pub types: Vec<DerivingTypeOutput>,
+}
+
+/// A public type lacking a Debug or Display impl
+#[derive(Serialize, JsonSchema)]
+pub struct FmtGapOutput {
+    /// Qualified path of the type
+    pub path: String,
+    /// `struct`, `enum` or `union`
+    pub kind: String,
+    pub file_path: String,
+    pub line: u32,
+    pub has_debug: bool,
+    pub has_display: bool,
+}
+
+/// Debug and Display coverage of one crate
+#[derive(Serialize, JsonSchema)]
+pub struct CrateFmtCoverageOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    /// Public types considered
+    pub types: usize,
+    pub with_debug: usize,
+    pub with_display: usize,
+    /// The types lacking the impls asked about, by path
+    pub gaps: Vec<FmtGapOutput>,
+}
+
+/// Output of fmt_coverage
+#[derive(Serialize, JsonSchema)]
+pub struct FmtCoverageOutput {
+    pub crates: Vec<CrateFmtCoverageOutput>,
+}
+
+/// Output of derive_usage
+#[derive(Serialize, JsonSchema)]
+pub struct DeriveUsageOutput {
+    /// Most used first
+    pub derives: Vec<DeriveOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct TokenClassOutput {
+    pub text: String,
+    /// e.g. "variable", "parameter", "field", "const_param", "macro" or "method"
+    pub tag: String,
+    /// e.g. "mutable", "declaration", "library" or "trait"
+    pub modifiers: Vec<String>,
+    pub line: u32,
+    pub start_column: u32,
+    pub end_column: u32,
+}
+
+/// Output of classify_token
+#[derive(Serialize, JsonSchema)]
+pub struct ClassifyTokenOutput {
+    /// Null when there is no classified token at the position
+    pub token: Option<TokenClassOutput>,
+}
+
+/// A file to move, as part of renaming a module
+#[derive(Serialize, JsonSchema)]
+pub struct FileMoveOutput {
+    pub from: String,
+    pub to: String,
+}
+
+/// Output of preview_rename
+#[derive(Serialize, JsonSchema)]
+pub struct PreviewRenameOutput {
+    pub old_name: String,
+    /// Edits in positions of the unmodified files, sorted by file and position
+    pub edits: Vec<TextEditOutput>,
+    pub file_moves: Vec<FileMoveOutput>,
+}
+
+/// Output of rewrite_paths
+#[derive(Serialize, JsonSchema)]
+pub struct RewritePathsOutput {
+    /// Edits in positions of the unmodified files, sorted by file and position
+    pub edits: Vec<TextEditOutput>,
+    pub warnings: Vec<String>,
+}
+
+/// A module and its submodules
+#[derive(Serialize, JsonSchema)]
+pub struct ModuleNodeOutput {
+    pub name: String,
+    /// e.g. `crate::analyzer`
+    pub path: String,
+    pub file_path: String,
+    /// The whole file, or the `mod` block if inline
+    pub start_line: u32,
+    pub end_line: u32,
+    pub inline: bool,
+    pub public: bool,
+    pub children: Vec<ModuleNodeOutput>,
+}
+
+/// Output of module_tree
+#[derive(Serialize, JsonSchema)]
+pub struct ModuleTreeOutput {
+    /// The root module of each crate, named after the crate
+    pub crates: Vec<ModuleNodeOutput>,
+}
+
+/// A dependency edge of a crate
+#[derive(Serialize, JsonSchema)]
+pub struct CrateDependencyOutput {
+    /// The name the dependency is imported under
+    pub name: String,
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    pub version: Option<String>,
+}
+
+/// A crate of the crate graph
+#[derive(Serialize, JsonSchema)]
+pub struct CrateNodeOutput {
+    pub name: String,
+    pub version: Option<String>,
+    pub edition: String,
+    /// "workspace", "library", "lang" (the standard library) or "rustc"
+    pub origin: String,
+    pub root_file: String,
+    pub dependencies: Vec<CrateDependencyOutput>,
+}
+
+/// The cfg options one crate was loaded with
+#[derive(Serialize, JsonSchema)]
+pub struct CrateCfgEntryOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Whether the crate is a workspace member
+    pub workspace: bool,
+    /// Enabled Cargo features, implied ones included
+    pub enabled_features: Vec<String>,
+    /// Declared features that are not enabled
+    pub disabled_features: Vec<String>,
+    /// Enabled cfg names, e.g. "test", "unix" or "debug_assertions"
+    pub flags: Vec<String>,
+    /// Enabled key-value options other than features, e.g. `target_os = "linux"`
+    pub key_values: Vec<String>,
+}
+
+/// Output of crate_cfg
+#[derive(Serialize, JsonSchema)]
+pub struct CrateCfgOutput {
+    /// Workspace crates first, then by name and version
+    pub crates: Vec<CrateCfgEntryOutput>,
+}
+
+/// Output of crate_graph
+#[derive(Serialize, JsonSchema)]
+pub struct CrateGraphOutput {
+    /// Workspace crates first
+    pub crates: Vec<CrateNodeOutput>,
+}
+
+/// A workspace crate depending on the looked-up crate
+#[derive(Serialize, JsonSchema)]
+pub struct DependentOutput {
+    pub name: String,
+    pub root_file: String,
+    /// Whether the dependency is direct rather than through other crates
+    pub direct: bool,
+    /// Chain of crates from this one down to the looked-up crate
+    pub path: Vec<String>,
+}
+
+/// Output of reverse_dependencies
+#[derive(Serialize, JsonSchema)]
+pub struct ReverseDependenciesOutput {
+    /// Direct dependents first
+    pub dependents: Vec<DependentOutput>,
+}
+
+/// A public item listed by enumerate_crate
+#[derive(Serialize, JsonSchema)]
+pub struct CrateItemOutput {
+    pub name: String,
+    /// e.g. "Struct", "Function" or "Module"
+    pub kind: String,
+}
+
+/// The public items a module declares
+#[derive(Serialize, JsonSchema)]
+pub struct CrateModuleOutput {
+    /// Path of the module, starting with the crate name, e.g. "serde_json::value"
+    pub module: String,
+    pub items: Vec<CrateItemOutput>,
+}
+
+/// Output of enumerate_crate
+#[derive(Serialize, JsonSchema)]
+pub struct EnumerateCrateOutput {
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Number of public items in the whole crate
+    pub total: usize,
+    /// Offset of the next page, if there is one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<usize>,
+    pub modules: Vec<CrateModuleOutput>,
+}
+
+/// A Cargo target of a workspace member
+#[derive(Serialize, JsonSchema)]
+pub struct MemberTargetOutput {
+    pub name: String,
+    /// "lib", "proc-macro", "bin", "test", "bench", "example", "build-script" or "other"
+    pub kind: String,
+    pub root: String,
+}
+
+/// A package of the Cargo workspace
+#[derive(Serialize, JsonSchema)]
+pub struct WorkspaceMemberOutput {
+    pub name: String,
+    pub version: String,
+    pub edition: String,
+    pub manifest_path: String,
+    pub targets: Vec<MemberTargetOutput>,
+}
+
+/// Output of workspace_members
+#[derive(Serialize, JsonSchema)]
+pub struct WorkspaceMembersOutput {
+    pub members: Vec<WorkspaceMemberOutput>,
+}
+
+/// A direct dependency of a package
+#[derive(Serialize, JsonSchema)]
+pub struct PackageDependencyOutput {
+    /// The name it is imported under
+    pub name: String,
+    pub package: String,
+    pub version: String,
+    /// "normal", "dev" or "build"
+    pub kind: String,
+    /// "workspace", "crates.io", "registry", "git" or "path"
+    pub source: String,
+}
+
+/// Output of crate_dependencies
+#[derive(Serialize, JsonSchema)]
+pub struct CrateDependenciesOutput {
+    pub dependencies: Vec<PackageDependencyOutput>,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct CrateVersionOutput {
+    pub name: String,
+    pub version: String,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct SubsystemOutput {
+    /// "proc_macros", "file_watcher", "library_cache", "reference_index_persistence" or "view_mir"
+    pub name: String,
+    /// Null while the server is initializing
+    pub enabled: Option<bool>,
+    /// Why it is off, or its location or status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Output of capabilities
+#[derive(Serialize, JsonSchema)]
+pub struct CapabilitiesOutput {
+    /// Version of ra_ap_ide, which the other ra_ap crates normally match
+    pub ra_ap_version: String,
+    /// Every ra_ap crate compiled in; a crate may appear with several versions
+    pub ra_ap_crates: Vec<CrateVersionOutput>,
+    /// "initializing", "ready" or "failed"
+    pub state: String,
+    pub subsystems: Vec<SubsystemOutput>,
+    /// Names of the tools that can be called, sorted
+    pub tools: Vec<String>,
+    /// Tools registered but switched off in this server
+    pub disabled_tools: Vec<String>,
+}
+
+/// Output of project_fingerprint
+#[derive(Serialize, JsonSchema)]
+pub struct ProjectFingerprintOutput {
+    /// Hash of all components, as stamped on persisted state
+    pub digest: String,
+    /// Version of the project's Rust toolchain
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub toolchain: Option<String>,
+    /// Version of the rust-analyzer crates cratographer was built with
+    pub analyzer_version: String,
+    /// Hash of the workspace's Cargo.lock
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lockfile_hash: Option<String>,
+    /// Enabled features of the workspace packages, as `package/feature`
+    pub features: Vec<String>,
+    /// Persisted reference index: "current", "stale" (rebuilt on next use) or "missing"
+    pub reference_index: String,
+}
+
+/// A scratch file mounted into a workspace crate
+#[derive(Serialize, JsonSchema)]
+pub struct ScratchFileOutput {
+    /// Path of the file in the scratch directory; pass it to file-based tools
+    pub file_path: String,
+    /// The module it is mounted as, e.g. "crate::__scratch_candidate"
+    pub module: String,
+    #[serde(rename = "crate")]
+    pub crate_name: String,
+}
+
+/// Output of add_scratch_root and remove_scratch_root
+#[derive(Serialize, JsonSchema)]
+pub struct ScratchRootOutput {
+    pub files: Vec<ScratchFileOutput>,
+}
+
+/// A test calling a function
+#[derive(Serialize, JsonSchema)]
+pub struct CoveringTestOutput {
+    pub name: String,
+    pub file_path: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// Number of calls between the test and the function; 1 for a direct call
+    pub depth: usize,
+    /// Functions along the call chain, from the test down to the function
+    pub chain: Vec<String>,
+    /// The command running just this test, quoted for a POSIX shell; absent outside a Cargo workspace
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command_line: Option<String>,
+    /// The same command as separate arguments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<Vec<String>>,
+}
+
+/// Output of tests_for_function
+#[derive(Serialize, JsonSchema, Default)]
+pub struct TestsForFunctionOutput {
+    /// Qualified path of the function
+    pub function: String,
+    /// Tests by depth, then by file and line
+    pub tests: Vec<CoveringTestOutput>,
+    /// Whether the search stopped early on a very widely called function or
+    /// when `budget_ms` ran out, so tests may be missing
+    pub truncated: bool,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of get_source
+#[derive(Serialize, JsonSchema, Default)]
+pub struct GetSourceOutput {
+    /// Qualified path of the symbol
+    pub path: String,
+    pub file_path: String,
+    /// Lines of the definition, doc comments and attributes included
+    pub start_line: u32,
+    pub end_line: u32,
+    /// The definition exactly as written
+    pub source: String,
+    /// Lines before the definition, up to `context_lines`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context_before: Vec<String>,
+    /// Lines after the definition, up to `context_lines`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub context_after: Vec<String>,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of get_signature
+#[derive(Serialize, JsonSchema, Default)]
+pub struct GetSignatureOutput {
+    /// Qualified path of the symbol
+    pub path: String,
+    pub file_path: String,
+    /// Line the signature starts on, after doc comments and attributes
+    pub line: u32,
+    /// The signature as written, without body, fields, items or value
+    pub signature: String,
+    /// Set, with the other fields empty, when the name was ambiguous
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub candidates: Vec<CandidateOutput>,
+}
+
+/// Output of resolve_moniker
+#[derive(Serialize, JsonSchema)]
+pub struct ResolveMonikerOutput {
+    pub symbol: SymbolOutput,
+}
+
+/// Output of resolve_path
+#[derive(Serialize, JsonSchema)]
+pub struct ResolvePathOutput {
+    pub symbol: SymbolOutput,
+    /// Path of the item where it is defined
+    pub canonical_path: String,
+    /// Whether the path reaches the item through a re-export
+    pub reexported: bool,
+}
+
+#[derive(Serialize, JsonSchema)]
+pub struct IndexEventOutput {
+    pub sequence: u64,
+    pub revision: u64,
+    /// "file_reindexed", "file_removed", "symbol_added", "symbol_removed"
+    /// or "crate_graph_reloaded"
+    pub kind: String,
+    /// Set for file and symbol events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    /// Set for symbol events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Set for symbol events
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbol_kind: Option<SymbolKind>,
+}
+
+/// Output of begin_snapshot
+#[derive(Serialize, JsonSchema)]
+pub struct BeginSnapshotOutput {
+    /// Id to pass to end_snapshot
+    pub snapshot_id: u64,
+    /// Index revision held until the snapshot ends
+    pub revision: u64,
+    /// Seconds after which the snapshot ends by itself
+    pub timeout_secs: u64,
+    /// Number of snapshots open, this one included
+    pub open_snapshots: usize,
+}
+
+/// Output of end_snapshot
+#[derive(Serialize, JsonSchema)]
+pub struct EndSnapshotOutput {
+    pub snapshot_id: u64,
+    /// Index revision when the snapshot was begun
+    pub revision: u64,
+    /// Whether the index was still at `revision` when the snapshot ended
+    pub consistent: bool,
+    /// File changes deferred during the snapshot and applied now
+    pub applied_changes: usize,
+    /// File changes still deferred because other snapshots are open
+    pub pending_changes: usize,
+    /// Index revision after applying the deferred changes
+    pub current_revision: u64,
+}
+
+/// Output of index_events
+#[derive(Serialize, JsonSchema)]
+pub struct IndexEventsOutput {
+    pub latest_sequence: u64,
+    pub revision: u64,
+    /// Whether events after `since` were dropped; discard cached knowledge if so
+    pub truncated: bool,
+    pub events: Vec<IndexEventOutput>,
+}
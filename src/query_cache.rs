@@ -0,0 +1,130 @@
+//! Query result cache
+//!
+//! Agents frequently repeat the exact same query. Results are cached per
+//! (tool, parameters) and tagged with the analyzer revision and reference
+//! index generation they were computed at; the whole cache is dropped as soon
+//! as either moves on, so stale results are never served.
+
+use rmcp::model::CallToolResult;
+use std::collections::{HashMap, VecDeque};
+
+/// Maximum number of cached results before the oldest are evicted
+const DEFAULT_CAPACITY: usize = 256;
+
+/// Analyzer revision and reference index generation a result was computed at
+pub type CacheStamp = (u64, u64);
+
+/// Cache of tool results keyed by tool name and serialized parameters
+pub struct QueryCache {
+    stamp: CacheStamp,
+    entries: HashMap<(String, String), CallToolResult>,
+    /// Insertion order, oldest first, for eviction
+    order: VecDeque<(String, String)>,
+    capacity: usize,
+}
+
+impl QueryCache {
+    /// Create a cache holding at most `capacity` results
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            stamp: (0, 0),
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            capacity,
+        }
+    }
+
+    /// Look up a cached result computed at `stamp`
+    pub fn get(&mut self, tool: &str, params: &str, stamp: CacheStamp) -> Option<CallToolResult> {
+        self.sync_stamp(stamp);
+        self.entries.get(&(tool.to_string(), params.to_string())).cloned()
+    }
+
+    /// Store a result computed at `stamp`
+    pub fn insert(&mut self, tool: &str, params: String, stamp: CacheStamp, result: CallToolResult) {
+        self.sync_stamp(stamp);
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = (tool.to_string(), params);
+        if self.entries.insert(key.clone(), result).is_none() {
+            self.order.push_back(key);
+        }
+        while self.entries.len() > self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    /// Number of cached results
+    #[allow(dead_code)]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Drop everything cached at an older revision or index generation
+    fn sync_stamp(&mut self, stamp: CacheStamp) {
+        if stamp != self.stamp {
+            self.entries.clear();
+            self.order.clear();
+            self.stamp = stamp;
+        }
+    }
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rmcp::model::Content;
+
+    fn result(text: &str) -> CallToolResult {
+        CallToolResult::success(vec![Content::text(text.to_string())])
+    }
+
+    #[test]
+    fn test_hit_and_revision_invalidation() {
+        let mut cache = QueryCache::default();
+        cache.insert("find_symbol", "{\"name\":\"A\"}".to_string(), (1, 0), result("a"));
+
+        assert!(cache.get("find_symbol", "{\"name\":\"A\"}", (1, 0)).is_some());
+        assert!(cache.get("find_symbol", "{\"name\":\"B\"}", (1, 0)).is_none());
+        assert!(cache.get("enumerate_file", "{\"name\":\"A\"}", (1, 0)).is_none());
+
+        // A new revision invalidates everything
+        assert!(cache.get("find_symbol", "{\"name\":\"A\"}", (2, 0)).is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_index_generation_invalidation() {
+        let mut cache = QueryCache::default();
+        cache.insert("indexed_references", "{\"name\":\"A\"}".to_string(), (1, 0), result("a"));
+
+        // Putting a reference index in place invalidates everything, even at the same revision
+        assert!(cache.get("indexed_references", "{\"name\":\"A\"}", (1, 1)).is_none());
+        assert_eq!(cache.len(), 0);
+    }
+
+    #[test]
+    fn test_oldest_entries_are_evicted() {
+        let mut cache = QueryCache::with_capacity(2);
+        cache.insert("t", "1".to_string(), (1, 0), result("1"));
+        cache.insert("t", "2".to_string(), (1, 0), result("2"));
+        cache.insert("t", "3".to_string(), (1, 0), result("3"));
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get("t", "1", (1, 0)).is_none());
+        assert!(cache.get("t", "3", (1, 0)).is_some());
+    }
+}
@@ -0,0 +1,136 @@
+//! Snapshots holding the index at one revision across several tool calls
+//!
+//! The file watcher applies changes as soon as they arrive, so two calls a
+//! client makes one after the other can see different revisions: a symbol
+//! found by the first may be gone by the second. While any snapshot is open,
+//! changes from the watcher are deferred instead and applied, in arrival
+//! order, once the last one is ended or has expired. Snapshots expire so a
+//! client that never ends one can't freeze the index for good.
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a snapshot stays open unless a timeout is given
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Upper bound on a snapshot's timeout
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// An open snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Index revision when the snapshot was begun
+    pub revision: u64,
+    pub expires: Instant,
+}
+
+/// Open snapshots and the changes deferred while they are held
+///
+/// Generic over the change type so it doesn't depend on the VFS.
+pub struct Snapshots<C> {
+    next_id: u64,
+    open: HashMap<u64, Snapshot>,
+    pending: Vec<C>,
+}
+
+impl<C> Snapshots<C> {
+    /// Open a snapshot of `revision`, returning its id
+    ///
+    /// The timeout is clamped to `MAX_TIMEOUT`.
+    pub fn begin(&mut self, revision: u64, timeout: Duration, now: Instant) -> u64 {
+        self.next_id += 1;
+        let expires = now + timeout.min(MAX_TIMEOUT);
+        self.open.insert(self.next_id, Snapshot { revision, expires });
+        self.next_id
+    }
+
+    /// Close a snapshot, returning it unless it was unknown or had expired
+    pub fn end(&mut self, id: u64, now: Instant) -> Option<Snapshot> {
+        self.expire(now);
+        self.open.remove(&id)
+    }
+
+    /// Whether any snapshot is still open, dropping expired ones
+    pub fn is_held(&mut self, now: Instant) -> bool {
+        self.expire(now);
+        !self.open.is_empty()
+    }
+
+    /// Number of snapshots open, expired ones included until next checked
+    pub fn open_count(&self) -> usize {
+        self.open.len()
+    }
+
+    /// Queue changes until no snapshot is held
+    pub fn defer(&mut self, changes: impl IntoIterator<Item = C>) {
+        self.pending.extend(changes);
+    }
+
+    /// Number of changes waiting to be applied
+    pub fn pending_count(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// The deferred changes, oldest first, once no snapshot is held any more
+    pub fn release(&mut self, now: Instant) -> Option<Vec<C>> {
+        if self.is_held(now) || self.pending.is_empty() {
+            return None;
+        }
+        Some(std::mem::take(&mut self.pending))
+    }
+
+    fn expire(&mut self, now: Instant) {
+        self.open.retain(|_, snapshot| snapshot.expires > now);
+    }
+}
+
+impl<C> Default for Snapshots<C> {
+    fn default() -> Self {
+        Self {
+            next_id: 0,
+            open: HashMap::new(),
+            pending: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_changes_are_deferred_until_the_last_snapshot_ends() {
+        let now = Instant::now();
+        let mut snapshots = Snapshots::default();
+        let first = snapshots.begin(3, DEFAULT_TIMEOUT, now);
+        let second = snapshots.begin(3, DEFAULT_TIMEOUT, now);
+        assert_ne!(first, second);
+
+        snapshots.defer(["a.rs", "b.rs"]);
+        assert_eq!(snapshots.release(now), None);
+
+        assert_eq!(snapshots.end(first, now).map(|s| s.revision), Some(3));
+        assert_eq!(snapshots.release(now), None);
+        assert!(snapshots.end(first, now).is_none());
+
+        assert!(snapshots.end(second, now).is_some());
+        assert_eq!(snapshots.release(now), Some(vec!["a.rs", "b.rs"]));
+        assert_eq!(snapshots.pending_count(), 0);
+    }
+
+    #[test]
+    fn test_snapshots_expire() {
+        let now = Instant::now();
+        let mut snapshots = Snapshots::default();
+        let id = snapshots.begin(1, Duration::from_secs(10), now);
+        snapshots.defer(["a.rs"]);
+
+        assert!(snapshots.is_held(now + Duration::from_secs(5)));
+        assert_eq!(snapshots.release(now + Duration::from_secs(10)), Some(vec!["a.rs"]));
+        assert!(snapshots.end(id, now + Duration::from_secs(10)).is_none());
+
+        // Timeouts are clamped
+        snapshots.begin(1, Duration::from_secs(3600), now);
+        assert!(!snapshots.is_held(now + MAX_TIMEOUT));
+    }
+}